@@ -0,0 +1,71 @@
+// Copyright (c) Microsoft. All rights reserved.
+namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet
+{
+    using System;
+    using System.Collections.Generic;
+    using System.Linq;
+    using System.Threading;
+    using System.Threading.Tasks;
+    using Microsoft.Azure.Devices.Edge.Agent.Edgelet.Models;
+    using Microsoft.Azure.Devices.Edge.Util;
+    using Microsoft.Extensions.Logging;
+
+    /// <summary>
+    /// Reports the alerts that edged evaluates on the device and whose rules have the
+    /// twin_report action. They are reported again only when one fires or resolves.
+    /// </summary>
+    public class AlertReporter : IDisposable
+    {
+        public static readonly TimeSpan DefaultFrequency = TimeSpan.FromMinutes(1);
+
+        readonly IModuleManager moduleManager;
+        readonly Func<IEnumerable<AlertStatus>, Task> report;
+        readonly TimeSpan frequency;
+        IList<AlertStatus> reported = new List<AlertStatus>();
+        PeriodicTask checkAlerts;
+
+        public AlertReporter(IModuleManager moduleManager, Func<IEnumerable<AlertStatus>, Task> report, TimeSpan frequency)
+        {
+            this.moduleManager = Preconditions.CheckNotNull(moduleManager, nameof(moduleManager));
+            this.report = Preconditions.CheckNotNull(report, nameof(report));
+            this.frequency = frequency;
+        }
+
+        public void Start(ILogger logger)
+        {
+            logger.LogInformation($"Reporting alerts every {this.frequency.Humanize()}");
+            this.checkAlerts = new PeriodicTask(this.Check, this.frequency, TimeSpan.FromMinutes(1), logger, "Report alerts", false);
+        }
+
+        public void Dispose()
+        {
+            this.checkAlerts?.Dispose();
+        }
+
+        internal async Task Check(CancellationToken token)
+        {
+            IList<AlertStatus> alerts = (await this.moduleManager.GetAlertsAsync(token))
+                .Where(a => a.Report)
+                .ToList();
+            if (alerts.Count == 0 || alerts.SequenceEqual(this.reported, AlertComparer.Instance))
+            {
+                return;
+            }
+
+            await this.report(alerts);
+
+            // A failed report throws before this, so the alerts are reported again next time.
+            this.reported = alerts;
+        }
+
+        class AlertComparer : IEqualityComparer<AlertStatus>
+        {
+            public static readonly AlertComparer Instance = new AlertComparer();
+
+            public bool Equals(AlertStatus x, AlertStatus y) =>
+                x.Name == y.Name && x.Firing == y.Firing && x.Since == y.Since;
+
+            public int GetHashCode(AlertStatus obj) => obj.Name.GetHashCode();
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright (c) Microsoft. All rights reserved.
+namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet
+{
+    using System;
+    using System.Collections.Generic;
+    using System.Threading;
+    using System.Threading.Tasks;
+    using Microsoft.Azure.Devices.Edge.Agent.Edgelet.Models;
+    using Microsoft.Azure.Devices.Edge.Util;
+    using Microsoft.Azure.Devices.Edge.Util.Metrics;
+    using Microsoft.Extensions.Logging;
+
+    /// <summary>
+    /// Exposes the free space that edged checks on the file systems of its storage
+    /// directories and the container engine's data root, since edged has no metrics
+    /// endpoint of its own.
+    /// </summary>
+    public class DiskSpaceMetrics : IDisposable
+    {
+        public static readonly TimeSpan DefaultFrequency = TimeSpan.FromMinutes(1);
+
+        readonly IModuleManager moduleManager;
+        readonly TimeSpan frequency;
+        readonly IMetricsGauge available;
+        readonly IMetricsGauge total;
+        readonly IMetricsGauge level;
+        PeriodicTask checkDiskSpace;
+
+        public DiskSpaceMetrics(IMetricsProvider metricsProvider, IModuleManager moduleManager, TimeSpan frequency)
+        {
+            Preconditions.CheckNotNull(metricsProvider, nameof(metricsProvider));
+            this.moduleManager = Preconditions.CheckNotNull(moduleManager, nameof(moduleManager));
+            this.frequency = frequency;
+
+            this.available = Preconditions.CheckNotNull(metricsProvider.CreateGauge(
+                "edged_disk_available_bytes",
+                "Space left on the file system of a path that edged stores data on",
+                new List<string> { "path", MetricsConstants.MsTelemetry }));
+
+            this.total = Preconditions.CheckNotNull(metricsProvider.CreateGauge(
+                "edged_disk_total_bytes",
+                "Size of the file system of a path that edged stores data on",
+                new List<string> { "path", MetricsConstants.MsTelemetry }));
+
+            this.level = Preconditions.CheckNotNull(metricsProvider.CreateGauge(
+                "edged_disk_space_level",
+                "Disk space level of a path that edged stores data on: 0 ok, 1 warning, 2 critical",
+                new List<string> { "path", MetricsConstants.MsTelemetry }));
+        }
+
+        public void Start(ILogger logger)
+        {
+            logger.LogInformation($"Updating disk space metrics every {this.frequency.Humanize()}");
+            this.checkDiskSpace = new PeriodicTask(this.Check, this.frequency, TimeSpan.FromMinutes(1), logger, "Get disk space", false);
+        }
+
+        public void Dispose()
+        {
+            this.checkDiskSpace?.Dispose();
+        }
+
+        internal async Task Check(CancellationToken token)
+        {
+            foreach (DiskSpaceStatus disk in await this.moduleManager.GetDiskSpaceAsync(token))
+            {
+                var tags = new string[] { disk.Path, true.ToString() };
+                this.available.Set(disk.AvailableBytes, tags);
+                this.total.Set(disk.TotalBytes, tags);
+                this.level.Set(Level(disk.Level), tags);
+            }
+        }
+
+        static double Level(string level)
+        {
+            switch (level)
+            {
+                case "critical":
+                    return 2;
+                case "warning":
+                    return 1;
+                default:
+                    return 0;
+            }
+        }
+    }
+}
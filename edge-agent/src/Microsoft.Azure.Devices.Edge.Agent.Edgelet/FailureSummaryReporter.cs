@@ -0,0 +1,59 @@
+// Copyright (c) Microsoft. All rights reserved.
+namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet
+{
+    using System;
+    using System.Collections.Generic;
+    using System.Linq;
+    using System.Threading;
+    using System.Threading.Tasks;
+    using Microsoft.Azure.Devices.Edge.Agent.Edgelet.Models;
+    using Microsoft.Azure.Devices.Edge.Util;
+    using Microsoft.Extensions.Logging;
+
+    /// <summary>
+    /// Reports the summaries of repeated failures that edged keeps while it can't report
+    /// them itself, and acknowledges them once they are sent so that edged clears them.
+    /// </summary>
+    public class FailureSummaryReporter : IDisposable
+    {
+        public static readonly TimeSpan DefaultFrequency = TimeSpan.FromMinutes(5);
+
+        readonly IModuleManager moduleManager;
+        readonly Func<IEnumerable<FailureSummary>, Task> report;
+        readonly TimeSpan frequency;
+        PeriodicTask checkFailures;
+
+        public FailureSummaryReporter(IModuleManager moduleManager, Func<IEnumerable<FailureSummary>, Task> report, TimeSpan frequency)
+        {
+            this.moduleManager = Preconditions.CheckNotNull(moduleManager, nameof(moduleManager));
+            this.report = Preconditions.CheckNotNull(report, nameof(report));
+            this.frequency = frequency;
+        }
+
+        public void Start(ILogger logger)
+        {
+            logger.LogInformation($"Reporting failure summaries every {this.frequency.Humanize()}");
+            this.checkFailures = new PeriodicTask(this.Check, this.frequency, TimeSpan.FromMinutes(1), logger, "Report failure summaries", false);
+        }
+
+        public void Dispose()
+        {
+            this.checkFailures?.Dispose();
+        }
+
+        internal async Task Check(CancellationToken token)
+        {
+            IList<FailureSummary> summaries = (await this.moduleManager.GetFailureSummariesAsync(token)).ToList();
+            if (summaries.Count == 0)
+            {
+                return;
+            }
+
+            await this.report(summaries);
+
+            // Only the summaries that were sent are acknowledged. edged keeps any that were
+            // recorded or updated since they were read, and they are sent next time.
+            await this.moduleManager.AcknowledgeFailureSummariesAsync(summaries.Select(s => s.Id), token);
+        }
+    }
+}
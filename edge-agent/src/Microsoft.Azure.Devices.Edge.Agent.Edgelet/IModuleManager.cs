@@ -29,6 +29,16 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet
 
         Task<SystemResources> GetSystemResourcesAsync();
 
+        Task<IEnumerable<FailureSummary>> GetFailureSummariesAsync(CancellationToken token);
+
+        Task AcknowledgeFailureSummariesAsync(IEnumerable<long> ids, CancellationToken token);
+
+        Task<IEnumerable<AlertStatus>> GetAlertsAsync(CancellationToken token);
+
+        Task<Option<OperationsStatus>> GetOperationsAsync(CancellationToken token);
+
+        Task<IEnumerable<DiskSpaceStatus>> GetDiskSpaceAsync(CancellationToken token);
+
         Task<IEnumerable<ModuleRuntimeInfo>> GetModules<T>(CancellationToken token);
 
         Task PrepareUpdateAsync(ModuleSpec moduleSpec);
@@ -56,6 +56,16 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet
 
         public Task<SystemResources> GetSystemResourcesAsync() => this.Throttle(() => this.inner.GetSystemResourcesAsync());
 
+        public Task<IEnumerable<FailureSummary>> GetFailureSummariesAsync(CancellationToken token) => this.Throttle(() => this.inner.GetFailureSummariesAsync(token));
+
+        public Task AcknowledgeFailureSummariesAsync(IEnumerable<long> ids, CancellationToken token) => this.Throttle(() => this.inner.AcknowledgeFailureSummariesAsync(ids, token));
+
+        public Task<IEnumerable<AlertStatus>> GetAlertsAsync(CancellationToken token) => this.Throttle(() => this.inner.GetAlertsAsync(token));
+
+        public Task<Option<OperationsStatus>> GetOperationsAsync(CancellationToken token) => this.Throttle(() => this.inner.GetOperationsAsync(token));
+
+        public Task<IEnumerable<DiskSpaceStatus>> GetDiskSpaceAsync(CancellationToken token) => this.Throttle(() => this.inner.GetDiskSpaceAsync(token));
+
         public Task<IEnumerable<ModuleRuntimeInfo>> GetModules<T>(CancellationToken token) => this.Throttle(() => this.inner.GetModules<T>(token));
 
         public Task PrepareUpdateAsync(ModuleSpec moduleSpec) => this.Throttle(() => this.inner.PrepareUpdateAsync(moduleSpec));
@@ -0,0 +1,63 @@
+// Copyright (c) Microsoft. All rights reserved.
+namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet
+{
+    using System;
+    using System.Threading;
+    using System.Threading.Tasks;
+    using Microsoft.Azure.Devices.Edge.Agent.Edgelet.Models;
+    using Microsoft.Azure.Devices.Edge.Util;
+    using Microsoft.Extensions.Logging;
+    using Newtonsoft.Json;
+
+    /// <summary>
+    /// Reports the results of edged's own operations, such as image garbage collection
+    /// and the watchdog's restarts of Edge Agent, when they change.
+    /// </summary>
+    public class OperationsReporter : IDisposable
+    {
+        public static readonly TimeSpan DefaultFrequency = TimeSpan.FromMinutes(5);
+
+        readonly IModuleManager moduleManager;
+        readonly Func<OperationsStatus, Task> report;
+        readonly TimeSpan frequency;
+        string reported;
+        PeriodicTask checkOperations;
+
+        public OperationsReporter(IModuleManager moduleManager, Func<OperationsStatus, Task> report, TimeSpan frequency)
+        {
+            this.moduleManager = Preconditions.CheckNotNull(moduleManager, nameof(moduleManager));
+            this.report = Preconditions.CheckNotNull(report, nameof(report));
+            this.frequency = frequency;
+        }
+
+        public void Start(ILogger logger)
+        {
+            logger.LogInformation($"Reporting edged operations every {this.frequency.Humanize()}");
+            this.checkOperations = new PeriodicTask(this.Check, this.frequency, TimeSpan.FromMinutes(1), logger, "Report edged operations", false);
+        }
+
+        public void Dispose()
+        {
+            this.checkOperations?.Dispose();
+        }
+
+        internal async Task Check(CancellationToken token)
+        {
+            Option<OperationsStatus> operations = await this.moduleManager.GetOperationsAsync(token);
+            await operations.ForEachAsync(
+                async status =>
+                {
+                    string json = JsonConvert.SerializeObject(status);
+                    if (json == this.reported)
+                    {
+                        return;
+                    }
+
+                    await this.report(status);
+
+                    // A failed report throws before this, so the status is reported again next time.
+                    this.reported = json;
+                });
+        }
+    }
+}
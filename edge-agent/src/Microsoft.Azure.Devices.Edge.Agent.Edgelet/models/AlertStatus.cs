@@ -0,0 +1,48 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Models
+{
+    using System;
+    using Microsoft.Azure.Devices.Edge.Util;
+    using Newtonsoft.Json;
+
+    public class AlertStatus
+    {
+        [JsonConstructor]
+        public AlertStatus(string name, bool firing, DateTime? since, string message, bool report)
+        {
+            this.Name = Preconditions.CheckNonWhiteSpace(name, nameof(name));
+            this.Firing = firing;
+            this.Since = since;
+            this.Message = message;
+            this.Report = report;
+        }
+
+        [JsonProperty("name")]
+        public string Name { get; }
+
+        [JsonProperty("firing")]
+        public bool Firing { get; }
+
+        [JsonProperty("since", NullValueHandling = NullValueHandling.Ignore)]
+        public DateTime? Since { get; }
+
+        [JsonProperty("message", NullValueHandling = NullValueHandling.Ignore)]
+        public string Message { get; }
+
+        [JsonProperty("report")]
+        public bool Report { get; }
+    }
+
+    class AlertsResponse
+    {
+        [JsonConstructor]
+        public AlertsResponse(AlertStatus[] alerts)
+        {
+            this.Alerts = alerts ?? new AlertStatus[0];
+        }
+
+        [JsonProperty("alerts")]
+        public AlertStatus[] Alerts { get; }
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Models
+{
+    using System;
+    using Microsoft.Azure.Devices.Edge.Util;
+    using Newtonsoft.Json;
+
+    /// <summary>
+    /// Free space on a file system that edged or the container engine stores data on.
+    /// </summary>
+    public class DiskSpaceStatus
+    {
+        [JsonConstructor]
+        public DiskSpaceStatus(string path, long totalBytes, long availableBytes, string level, DateTime @checked)
+        {
+            this.Path = Preconditions.CheckNonWhiteSpace(path, nameof(path));
+            this.TotalBytes = totalBytes;
+            this.AvailableBytes = availableBytes;
+            this.Level = level;
+            this.Checked = @checked;
+        }
+
+        [JsonProperty("path")]
+        public string Path { get; }
+
+        [JsonProperty("totalBytes")]
+        public long TotalBytes { get; }
+
+        [JsonProperty("availableBytes")]
+        public long AvailableBytes { get; }
+
+        /// <summary>
+        /// "ok", "warning" or "critical". Modules aren't created while any path is critical.
+        /// </summary>
+        [JsonProperty("level")]
+        public string Level { get; }
+
+        [JsonProperty("checked")]
+        public DateTime Checked { get; }
+    }
+
+    class DiskSpaceResponse
+    {
+        [JsonConstructor]
+        public DiskSpaceResponse(DiskSpaceStatus[] disks)
+        {
+            this.Disks = disks ?? new DiskSpaceStatus[0];
+        }
+
+        [JsonProperty("disks")]
+        public DiskSpaceStatus[] Disks { get; }
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Models
+{
+    using System;
+    using Microsoft.Azure.Devices.Edge.Util;
+    using Newtonsoft.Json;
+
+    public class FailureSummary
+    {
+        [JsonConstructor]
+        public FailureSummary(long id, string kind, long count, DateTime firstTime, DateTime lastTime, string lastError)
+        {
+            this.Id = id;
+            this.Kind = Preconditions.CheckNonWhiteSpace(kind, nameof(kind));
+            this.Count = count;
+            this.FirstTime = firstTime;
+            this.LastTime = lastTime;
+            this.LastError = Preconditions.CheckNotNull(lastError, nameof(lastError));
+        }
+
+        [JsonProperty("id")]
+        public long Id { get; }
+
+        [JsonProperty("kind")]
+        public string Kind { get; }
+
+        [JsonProperty("count")]
+        public long Count { get; }
+
+        [JsonProperty("firstTime")]
+        public DateTime FirstTime { get; }
+
+        [JsonProperty("lastTime")]
+        public DateTime LastTime { get; }
+
+        [JsonProperty("lastError")]
+        public string LastError { get; }
+    }
+
+    class FailuresResponse
+    {
+        [JsonConstructor]
+        public FailuresResponse(FailureSummary[] failures)
+        {
+            this.Failures = failures ?? new FailureSummary[0];
+        }
+
+        [JsonProperty("failures")]
+        public FailureSummary[] Failures { get; }
+    }
+}
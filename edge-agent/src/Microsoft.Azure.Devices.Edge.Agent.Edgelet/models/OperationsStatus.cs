@@ -0,0 +1,69 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Models
+{
+    using System;
+    using Newtonsoft.Json;
+
+    public class ImageGcRun
+    {
+        [JsonConstructor]
+        public ImageGcRun(DateTime time, long imagesRemoved, string error)
+        {
+            this.Time = time;
+            this.ImagesRemoved = imagesRemoved;
+            this.Error = error;
+        }
+
+        [JsonProperty("time")]
+        public DateTime Time { get; }
+
+        [JsonProperty("imagesRemoved")]
+        public long ImagesRemoved { get; }
+
+        [JsonProperty("error", NullValueHandling = NullValueHandling.Ignore)]
+        public string Error { get; }
+    }
+
+    /// <summary>
+    /// Results of edged's own operations, such as image garbage collection and the
+    /// watchdog's restarts of Edge Agent.
+    /// </summary>
+    public class OperationsStatus
+    {
+        [JsonConstructor]
+        public OperationsStatus(
+            ImageGcRun lastImageGc,
+            long imagesReclaimed,
+            long watchdogRestarts,
+            long unhealthyRestarts,
+            DateTime? lastReprovision,
+            string provisioning)
+        {
+            this.LastImageGc = lastImageGc;
+            this.ImagesReclaimed = imagesReclaimed;
+            this.WatchdogRestarts = watchdogRestarts;
+            this.UnhealthyRestarts = unhealthyRestarts;
+            this.LastReprovision = lastReprovision;
+            this.Provisioning = provisioning;
+        }
+
+        [JsonProperty("lastImageGc", NullValueHandling = NullValueHandling.Ignore)]
+        public ImageGcRun LastImageGc { get; }
+
+        [JsonProperty("imagesReclaimed")]
+        public long ImagesReclaimed { get; }
+
+        [JsonProperty("watchdogRestarts")]
+        public long WatchdogRestarts { get; }
+
+        [JsonProperty("unhealthyRestarts")]
+        public long UnhealthyRestarts { get; }
+
+        [JsonProperty("lastReprovision", NullValueHandling = NullValueHandling.Ignore)]
+        public DateTime? LastReprovision { get; }
+
+        [JsonProperty("provisioning", NullValueHandling = NullValueHandling.Ignore)]
+        public string Provisioning { get; }
+    }
+}
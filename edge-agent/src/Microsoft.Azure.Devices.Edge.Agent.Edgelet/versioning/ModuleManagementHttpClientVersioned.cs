@@ -5,6 +5,7 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Versioning
     using System.Collections.Generic;
     using System.Globalization;
     using System.IO;
+    using System.Linq;
     using System.Net;
     using System.Net.Http;
     using System.Net.Sockets;
@@ -19,6 +20,7 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Versioning
     using Microsoft.Azure.Devices.Edge.Util.Edged;
     using Microsoft.Azure.Devices.Edge.Util.TransientFaultHandling;
     using Microsoft.Extensions.Logging;
+    using Newtonsoft.Json;
 
     abstract class ModuleManagementHttpClientVersioned
     {
@@ -27,6 +29,10 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Versioning
         const string LogsUrlSinceParameter = "since";
         const string LogsUrlUntilParameter = "until";
         const string LogsIncludeTimestampParameter = "timestamps";
+        const string FailuresUrlTemplate = "{0}/systeminfo/failures?api-version={1}";
+        const string AlertsUrlTemplate = "{0}/systeminfo/alerts?api-version={1}";
+        const string OperationsUrlTemplate = "{0}/systeminfo/operations?api-version={1}";
+        const string DiskSpaceUrlTemplate = "{0}/systeminfo/diskspace?api-version={1}";
 
         static readonly TimeSpan DefaultOperationTimeout = TimeSpan.FromMinutes(5);
 
@@ -133,6 +139,149 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Versioning
             }
         }
 
+        public virtual async Task<IEnumerable<FailureSummary>> GetFailureSummariesAsync(CancellationToken cancellationToken)
+        {
+            // Failure summaries were added in 2022-08-03.
+            if (this.Version.Value < ApiVersion.Version20220803.Value)
+            {
+                return Enumerable.Empty<FailureSummary>();
+            }
+
+            using (HttpClient httpClient = this.GetHttpClient())
+            {
+                FailuresResponse response = await this.Execute(
+                    async () =>
+                    {
+                        var httpRequest = new HttpRequestMessage(HttpMethod.Get, this.SystemInfoUri(FailuresUrlTemplate));
+                        HttpResponseMessage httpResponseMessage = await httpClient.SendAsync(httpRequest, cancellationToken);
+                        string content = await httpResponseMessage.Content.ReadAsStringAsync();
+                        if (!httpResponseMessage.IsSuccessStatusCode)
+                        {
+                            throw new EdgeletCommunicationException(content, (int)httpResponseMessage.StatusCode);
+                        }
+
+                        return JsonConvert.DeserializeObject<FailuresResponse>(content);
+                    },
+                    "Get failure summaries");
+
+                return response?.Failures ?? Enumerable.Empty<FailureSummary>();
+            }
+        }
+
+        public virtual async Task AcknowledgeFailureSummariesAsync(IEnumerable<long> ids, CancellationToken cancellationToken)
+        {
+            if (this.Version.Value < ApiVersion.Version20220803.Value)
+            {
+                return;
+            }
+
+            using (HttpClient httpClient = this.GetHttpClient())
+            {
+                string body = JsonConvert.SerializeObject(new { ids });
+                await this.Execute(
+                    async () =>
+                    {
+                        var httpRequest = new HttpRequestMessage(HttpMethod.Delete, this.SystemInfoUri(FailuresUrlTemplate))
+                        {
+                            Content = new StringContent(body, Encoding.UTF8, "application/json")
+                        };
+                        HttpResponseMessage httpResponseMessage = await httpClient.SendAsync(httpRequest, cancellationToken);
+                        if (!httpResponseMessage.IsSuccessStatusCode)
+                        {
+                            throw new EdgeletCommunicationException(await httpResponseMessage.Content.ReadAsStringAsync(), (int)httpResponseMessage.StatusCode);
+                        }
+                    },
+                    "Acknowledge failure summaries");
+            }
+        }
+
+        public virtual async Task<IEnumerable<AlertStatus>> GetAlertsAsync(CancellationToken cancellationToken)
+        {
+            // Alerts were added in 2022-08-03.
+            if (this.Version.Value < ApiVersion.Version20220803.Value)
+            {
+                return Enumerable.Empty<AlertStatus>();
+            }
+
+            using (HttpClient httpClient = this.GetHttpClient())
+            {
+                AlertsResponse response = await this.Execute(
+                    async () =>
+                    {
+                        var httpRequest = new HttpRequestMessage(HttpMethod.Get, this.SystemInfoUri(AlertsUrlTemplate));
+                        HttpResponseMessage httpResponseMessage = await httpClient.SendAsync(httpRequest, cancellationToken);
+                        string content = await httpResponseMessage.Content.ReadAsStringAsync();
+                        if (!httpResponseMessage.IsSuccessStatusCode)
+                        {
+                            throw new EdgeletCommunicationException(content, (int)httpResponseMessage.StatusCode);
+                        }
+
+                        return JsonConvert.DeserializeObject<AlertsResponse>(content);
+                    },
+                    "Get alerts");
+
+                return response?.Alerts ?? Enumerable.Empty<AlertStatus>();
+            }
+        }
+
+        public virtual async Task<Option<OperationsStatus>> GetOperationsAsync(CancellationToken cancellationToken)
+        {
+            // Operations were added in 2022-08-03.
+            if (this.Version.Value < ApiVersion.Version20220803.Value)
+            {
+                return Option.None<OperationsStatus>();
+            }
+
+            using (HttpClient httpClient = this.GetHttpClient())
+            {
+                OperationsStatus response = await this.Execute(
+                    async () =>
+                    {
+                        var httpRequest = new HttpRequestMessage(HttpMethod.Get, this.SystemInfoUri(OperationsUrlTemplate));
+                        HttpResponseMessage httpResponseMessage = await httpClient.SendAsync(httpRequest, cancellationToken);
+                        string content = await httpResponseMessage.Content.ReadAsStringAsync();
+                        if (!httpResponseMessage.IsSuccessStatusCode)
+                        {
+                            throw new EdgeletCommunicationException(content, (int)httpResponseMessage.StatusCode);
+                        }
+
+                        return JsonConvert.DeserializeObject<OperationsStatus>(content);
+                    },
+                    "Get operations");
+
+                return Option.Maybe(response);
+            }
+        }
+
+        public virtual async Task<IEnumerable<DiskSpaceStatus>> GetDiskSpaceAsync(CancellationToken cancellationToken)
+        {
+            // Disk space was added in 2022-08-03.
+            if (this.Version.Value < ApiVersion.Version20220803.Value)
+            {
+                return Enumerable.Empty<DiskSpaceStatus>();
+            }
+
+            using (HttpClient httpClient = this.GetHttpClient())
+            {
+                DiskSpaceResponse response = await this.Execute(
+                    async () =>
+                    {
+                        var httpRequest = new HttpRequestMessage(HttpMethod.Get, this.SystemInfoUri(DiskSpaceUrlTemplate));
+                        HttpResponseMessage httpResponseMessage = await httpClient.SendAsync(httpRequest, cancellationToken);
+                        string content = await httpResponseMessage.Content.ReadAsStringAsync();
+                        if (!httpResponseMessage.IsSuccessStatusCode)
+                        {
+                            throw new EdgeletCommunicationException(content, (int)httpResponseMessage.StatusCode);
+                        }
+
+                        return JsonConvert.DeserializeObject<DiskSpaceResponse>(content);
+                    },
+                    "Get disk space");
+
+                return response?.Disks ?? Enumerable.Empty<DiskSpaceStatus>();
+            }
+        }
+
         protected abstract void HandleException(Exception ex, string operation);
 
         protected Task Execute(Func<Task> func, string operation) =>
@@ -177,6 +326,12 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Versioning
             return transientRetryPolicy.ExecuteAsync(func);
         }
 
+        Uri SystemInfoUri(string template)
+        {
+            string baseUrl = HttpClientHelper.GetBaseUrl(this.ManagementUri).TrimEnd('/');
+            return new Uri(string.Format(CultureInfo.InvariantCulture, template, baseUrl, this.Version.Name));
+        }
+
         static class Events
         {
             const int IdStart = AgentEventIds.ModuleManagementHttpClient;
@@ -15,6 +15,7 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Service
     using Microsoft.Azure.Devices.Edge.Agent.Core.Metrics;
     using Microsoft.Azure.Devices.Edge.Agent.Core.Requests;
     using Microsoft.Azure.Devices.Edge.Agent.Diagnostics;
+    using Microsoft.Azure.Devices.Edge.Agent.Edgelet;
     using Microsoft.Azure.Devices.Edge.Agent.Edgelet.Docker;
     using Microsoft.Azure.Devices.Edge.Agent.IoTHub.Stream;
     using Microsoft.Azure.Devices.Edge.Agent.Service.Modules;
@@ -245,6 +246,11 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Service
             {
                 container.Resolve<IMetricsListener>().Start(logger);
                 container.Resolve<ISystemResourcesMetrics>().Start(logger);
+                if (container.TryResolve(out DiskSpaceMetrics diskSpaceMetrics))
+                {
+                    diskSpaceMetrics.Start(logger);
+                }
+
                 await container.Resolve<MetadataMetrics>().Start(logger, versionInfo.ToString(true), Newtonsoft.Json.JsonConvert.SerializeObject(experimentalFeatures));
             }
 
@@ -256,6 +262,23 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Service
                 Console.WriteLine($"Scraping frequency: {diagnosticConfig.ScrapeInterval}\nUpload Frequency: {diagnosticConfig.UploadInterval}");
             }
 
+            // Failure summaries, alerts and edged's operations are only reported upstream with a
+            // twin config source.
+            if (container.TryResolve(out FailureSummaryReporter failureSummaryReporter))
+            {
+                failureSummaryReporter.Start(logger);
+            }
+
+            if (container.TryResolve(out AlertReporter alertReporter))
+            {
+                alertReporter.Start(logger);
+            }
+
+            if (container.TryResolve(out OperationsReporter operationsReporter))
+            {
+                operationsReporter.Start(logger);
+            }
+
             (CancellationTokenSource cts, ManualResetEventSlim completed, Option<object> handler)
                 = ShutdownHandler.Init(ShutdownWaitPeriod, logger);
 
@@ -197,6 +197,11 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Service.Modules
             builder.Register(c => new SystemResourcesMetrics(c.Resolve<IMetricsProvider>(), c.Resolve<IModuleManager>().GetSystemResourcesAsync, this.apiVersion, this.performanceMetricsUpdateFrequency))
                 .As<ISystemResourcesMetrics>()
                 .SingleInstance();
+
+            // DiskSpaceMetrics
+            builder.Register(c => new DiskSpaceMetrics(c.Resolve<IMetricsProvider>(), c.Resolve<IModuleManager>(), DiskSpaceMetrics.DefaultFrequency))
+                .As<DiskSpaceMetrics>()
+                .SingleInstance();
         }
     }
 }
@@ -5,8 +5,10 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Service.Modules
     using System.Collections.Generic;
     using System.Linq;
     using System.Security.Cryptography.X509Certificates;
+    using System.Text;
     using System.Threading.Tasks;
     using Autofac;
+    using Microsoft.Azure.Devices.Client;
     using Microsoft.Azure.Devices.Edge.Agent.Core;
     using Microsoft.Azure.Devices.Edge.Agent.Core.ConfigSources;
     using Microsoft.Azure.Devices.Edge.Agent.Core.DeviceManager;
@@ -23,6 +25,7 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Service.Modules
     using Microsoft.Azure.Devices.Edge.Agent.IoTHub.SdkClient;
     using Microsoft.Azure.Devices.Edge.Agent.IoTHub.Stream;
     using Microsoft.Azure.Devices.Edge.Util;
+    using Microsoft.Azure.Devices.Shared;
     using Microsoft.Extensions.Configuration;
 
     public class TwinConfigSourceModule : Module
@@ -244,6 +247,71 @@ namespace Microsoft.Azure.Devices.Edge.Agent.Service.Modules
                 .As<IReporter>()
                 .SingleInstance();
 
+            // FailureSummaryReporter
+            builder.Register(
+                c =>
+                {
+                    var moduleManager = c.Resolve<IModuleManager>();
+                    var edgeAgentConnection = c.Resolve<IEdgeAgentConnection>();
+                    return new FailureSummaryReporter(
+                        moduleManager,
+                        summaries =>
+                        {
+                            string json = Newtonsoft.Json.JsonConvert.SerializeObject(new { failures = summaries });
+                            var message = new Message(Encoding.UTF8.GetBytes(json))
+                            {
+                                ContentType = "application/json",
+                                ContentEncoding = "utf-8"
+                            };
+                            message.Properties["messageType"] = "failureSummaries";
+                            return edgeAgentConnection.SendEventAsync(message);
+                        },
+                        FailureSummaryReporter.DefaultFrequency);
+                })
+                .As<FailureSummaryReporter>()
+                .SingleInstance();
+
+            // AlertReporter
+            builder.Register(
+                c =>
+                {
+                    var moduleManager = c.Resolve<IModuleManager>();
+                    var edgeAgentConnection = c.Resolve<IEdgeAgentConnection>();
+                    return new AlertReporter(
+                        moduleManager,
+                        alerts =>
+                        {
+                            var patch = new
+                            {
+                                alerts = alerts.ToDictionary(
+                                    a => a.Name,
+                                    a => new { firing = a.Firing, since = a.Since, message = a.Message })
+                            };
+                            return edgeAgentConnection.UpdateReportedPropertiesAsync(new TwinCollection(Newtonsoft.Json.JsonConvert.SerializeObject(patch)));
+                        },
+                        AlertReporter.DefaultFrequency);
+                })
+                .As<AlertReporter>()
+                .SingleInstance();
+
+            // OperationsReporter
+            builder.Register(
+                c =>
+                {
+                    var moduleManager = c.Resolve<IModuleManager>();
+                    var edgeAgentConnection = c.Resolve<IEdgeAgentConnection>();
+                    return new OperationsReporter(
+                        moduleManager,
+                        status =>
+                        {
+                            string json = Newtonsoft.Json.JsonConvert.SerializeObject(new { edgedOperations = status });
+                            return edgeAgentConnection.UpdateReportedPropertiesAsync(new TwinCollection(json));
+                        },
+                        OperationsReporter.DefaultFrequency);
+                })
+                .As<OperationsReporter>()
+                .SingleInstance();
+
             base.Load(builder);
         }
     }
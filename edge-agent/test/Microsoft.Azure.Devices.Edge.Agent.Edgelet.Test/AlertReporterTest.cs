@@ -0,0 +1,108 @@
+// Copyright (c) Microsoft. All rights reserved.
+namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Test
+{
+    using System;
+    using System.Collections.Generic;
+    using System.Linq;
+    using System.Threading;
+    using System.Threading.Tasks;
+    using Microsoft.Azure.Devices.Edge.Agent.Edgelet.Models;
+    using Microsoft.Azure.Devices.Edge.Util.Test.Common;
+    using Moq;
+    using Xunit;
+
+    [Unit]
+    public class AlertReporterTest
+    {
+        [Fact]
+        public async Task ReportsOnlyAlertsMarkedForReporting()
+        {
+            // Arrange
+            var since = DateTime.UtcNow;
+            var moduleManager = new Mock<IModuleManager>();
+            moduleManager.Setup(m => m.GetAlertsAsync(It.IsAny<CancellationToken>()))
+                .ReturnsAsync(new[]
+                {
+                    new AlertStatus("disk", true, since, "/ is 95% full", true),
+                    new AlertStatus("edgehub-down", false, null, null, false)
+                });
+
+            IEnumerable<AlertStatus> reported = null;
+            var reporter = new AlertReporter(
+                moduleManager.Object,
+                a =>
+                {
+                    reported = a;
+                    return Task.CompletedTask;
+                },
+                TimeSpan.FromMinutes(1));
+
+            // Act
+            await reporter.Check(CancellationToken.None);
+
+            // Assert
+            Assert.Equal(new[] { "disk" }, reported.Select(a => a.Name));
+        }
+
+        [Fact]
+        public async Task ReportsAgainOnlyWhenAlertsChange()
+        {
+            // Arrange
+            var firing = new AlertStatus("disk", true, DateTime.UtcNow, "/ is 95% full", true);
+            var resolved = new AlertStatus("disk", false, DateTime.UtcNow.AddMinutes(1), "/ is 95% full", true);
+            var moduleManager = new Mock<IModuleManager>();
+            moduleManager.SetupSequence(m => m.GetAlertsAsync(It.IsAny<CancellationToken>()))
+                .ReturnsAsync(new[] { firing })
+                .ReturnsAsync(new[] { firing })
+                .ReturnsAsync(new[] { resolved });
+
+            int reports = 0;
+            var reporter = new AlertReporter(
+                moduleManager.Object,
+                _ =>
+                {
+                    reports++;
+                    return Task.CompletedTask;
+                },
+                TimeSpan.FromMinutes(1));
+
+            // Act
+            await reporter.Check(CancellationToken.None);
+            await reporter.Check(CancellationToken.None);
+            await reporter.Check(CancellationToken.None);
+
+            // Assert
+            Assert.Equal(2, reports);
+        }
+
+        [Fact]
+        public async Task ReportsAgainAfterFailure()
+        {
+            // Arrange
+            var moduleManager = new Mock<IModuleManager>();
+            moduleManager.Setup(m => m.GetAlertsAsync(It.IsAny<CancellationToken>()))
+                .ReturnsAsync(new[] { new AlertStatus("disk", true, DateTime.UtcNow, "/ is 95% full", true) });
+
+            int attempts = 0;
+            var reporter = new AlertReporter(
+                moduleManager.Object,
+                _ =>
+                {
+                    if (attempts++ == 0)
+                    {
+                        throw new InvalidOperationException("not connected");
+                    }
+
+                    return Task.CompletedTask;
+                },
+                TimeSpan.FromMinutes(1));
+
+            // Act
+            await Assert.ThrowsAsync<InvalidOperationException>(() => reporter.Check(CancellationToken.None));
+            await reporter.Check(CancellationToken.None);
+
+            // Assert
+            Assert.Equal(2, attempts);
+        }
+    }
+}
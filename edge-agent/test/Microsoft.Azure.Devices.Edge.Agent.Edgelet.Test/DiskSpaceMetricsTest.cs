@@ -0,0 +1,55 @@
+// Copyright (c) Microsoft. All rights reserved.
+namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Test
+{
+    using System;
+    using System.Collections.Generic;
+    using System.Linq;
+    using System.Threading;
+    using System.Threading.Tasks;
+    using Microsoft.Azure.Devices.Edge.Agent.Edgelet.Models;
+    using Microsoft.Azure.Devices.Edge.Util.Metrics;
+    using Microsoft.Azure.Devices.Edge.Util.Test.Common;
+    using Moq;
+    using Xunit;
+
+    [Unit]
+    public class DiskSpaceMetricsTest
+    {
+        [Fact]
+        public async Task SetsGaugesPerPath()
+        {
+            // Arrange
+            var disks = new[]
+            {
+                new DiskSpaceStatus("/var/lib/aziot/edged", 1000, 400, "ok", DateTime.UtcNow),
+                new DiskSpaceStatus("/var/lib/docker", 1000, 50, "critical", DateTime.UtcNow),
+            };
+            var moduleManager = new Mock<IModuleManager>();
+            moduleManager.Setup(m => m.GetDiskSpaceAsync(It.IsAny<CancellationToken>()))
+                .ReturnsAsync(disks);
+
+            var available = new Mock<IMetricsGauge>();
+            var total = new Mock<IMetricsGauge>();
+            var level = new Mock<IMetricsGauge>();
+            var metricsProvider = new Mock<IMetricsProvider>();
+            metricsProvider.Setup(m => m.CreateGauge("edged_disk_available_bytes", It.IsAny<string>(), It.IsAny<List<string>>()))
+                .Returns(available.Object);
+            metricsProvider.Setup(m => m.CreateGauge("edged_disk_total_bytes", It.IsAny<string>(), It.IsAny<List<string>>()))
+                .Returns(total.Object);
+            metricsProvider.Setup(m => m.CreateGauge("edged_disk_space_level", It.IsAny<string>(), It.IsAny<List<string>>()))
+                .Returns(level.Object);
+
+            var metrics = new DiskSpaceMetrics(metricsProvider.Object, moduleManager.Object, TimeSpan.FromMinutes(1));
+
+            // Act
+            await metrics.Check(CancellationToken.None);
+
+            // Assert
+            available.Verify(g => g.Set(400, It.Is<string[]>(t => t.SequenceEqual(new[] { "/var/lib/aziot/edged", "True" }))), Times.Once);
+            available.Verify(g => g.Set(50, It.Is<string[]>(t => t.SequenceEqual(new[] { "/var/lib/docker", "True" }))), Times.Once);
+            total.Verify(g => g.Set(1000, It.IsAny<string[]>()), Times.Exactly(2));
+            level.Verify(g => g.Set(0, It.Is<string[]>(t => t.SequenceEqual(new[] { "/var/lib/aziot/edged", "True" }))), Times.Once);
+            level.Verify(g => g.Set(2, It.Is<string[]>(t => t.SequenceEqual(new[] { "/var/lib/docker", "True" }))), Times.Once);
+        }
+    }
+}
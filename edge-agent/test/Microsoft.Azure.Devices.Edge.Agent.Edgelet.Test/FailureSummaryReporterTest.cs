@@ -0,0 +1,99 @@
+// Copyright (c) Microsoft. All rights reserved.
+namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Test
+{
+    using System;
+    using System.Collections.Generic;
+    using System.Linq;
+    using System.Threading;
+    using System.Threading.Tasks;
+    using Microsoft.Azure.Devices.Edge.Agent.Edgelet.Models;
+    using Microsoft.Azure.Devices.Edge.Util.Test.Common;
+    using Moq;
+    using Xunit;
+
+    [Unit]
+    public class FailureSummaryReporterTest
+    {
+        [Fact]
+        public async Task ReportsThenAcknowledgesSummaries()
+        {
+            // Arrange
+            var summaries = new[]
+            {
+                new FailureSummary(3, "provisioning", 2, DateTime.UtcNow, DateTime.UtcNow, "timed out"),
+                new FailureSummary(5, "moduleRuntime", 1, DateTime.UtcNow, DateTime.UtcNow, "not found")
+            };
+            var calls = new List<string>();
+            var moduleManager = new Mock<IModuleManager>();
+            moduleManager.Setup(m => m.GetFailureSummariesAsync(It.IsAny<CancellationToken>()))
+                .ReturnsAsync(summaries);
+            moduleManager.Setup(m => m.AcknowledgeFailureSummariesAsync(It.IsAny<IEnumerable<long>>(), It.IsAny<CancellationToken>()))
+                .Callback<IEnumerable<long>, CancellationToken>((ids, _) => calls.Add($"ack {string.Join(",", ids)}"))
+                .Returns(Task.CompletedTask);
+
+            IEnumerable<FailureSummary> reported = null;
+            var reporter = new FailureSummaryReporter(
+                moduleManager.Object,
+                s =>
+                {
+                    reported = s;
+                    calls.Add("report");
+                    return Task.CompletedTask;
+                },
+                TimeSpan.FromMinutes(5));
+
+            // Act
+            await reporter.Check(CancellationToken.None);
+
+            // Assert
+            Assert.Equal(summaries, reported);
+            Assert.Equal(new[] { "report", "ack 3,5" }, calls);
+        }
+
+        [Fact]
+        public async Task DoesNotAcknowledgeWhenReportFails()
+        {
+            // Arrange
+            var moduleManager = new Mock<IModuleManager>();
+            moduleManager.Setup(m => m.GetFailureSummariesAsync(It.IsAny<CancellationToken>()))
+                .ReturnsAsync(new[] { new FailureSummary(1, "provisioning", 1, DateTime.UtcNow, DateTime.UtcNow, "timed out") });
+
+            var reporter = new FailureSummaryReporter(
+                moduleManager.Object,
+                _ => throw new InvalidOperationException("not connected"),
+                TimeSpan.FromMinutes(5));
+
+            // Act
+            await Assert.ThrowsAsync<InvalidOperationException>(() => reporter.Check(CancellationToken.None));
+
+            // Assert
+            moduleManager.Verify(m => m.AcknowledgeFailureSummariesAsync(It.IsAny<IEnumerable<long>>(), It.IsAny<CancellationToken>()), Times.Never);
+        }
+
+        [Fact]
+        public async Task SkipsEmptyReports()
+        {
+            // Arrange
+            var moduleManager = new Mock<IModuleManager>();
+            moduleManager.Setup(m => m.GetFailureSummariesAsync(It.IsAny<CancellationToken>()))
+                .ReturnsAsync(Enumerable.Empty<FailureSummary>());
+            bool reported = false;
+
+            var reporter = new FailureSummaryReporter(
+                moduleManager.Object,
+                _ =>
+                {
+                    reported = true;
+                    return Task.CompletedTask;
+                },
+                TimeSpan.FromMinutes(5));
+
+            // Act
+            await reporter.Check(CancellationToken.None);
+
+            // Assert
+            Assert.False(reported);
+            moduleManager.Verify(m => m.AcknowledgeFailureSummariesAsync(It.IsAny<IEnumerable<long>>(), It.IsAny<CancellationToken>()), Times.Never);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright (c) Microsoft. All rights reserved.
+namespace Microsoft.Azure.Devices.Edge.Agent.Edgelet.Test
+{
+    using System;
+    using System.Threading;
+    using System.Threading.Tasks;
+    using Microsoft.Azure.Devices.Edge.Agent.Edgelet.Models;
+    using Microsoft.Azure.Devices.Edge.Util;
+    using Microsoft.Azure.Devices.Edge.Util.Test.Common;
+    using Moq;
+    using Xunit;
+
+    [Unit]
+    public class OperationsReporterTest
+    {
+        [Fact]
+        public async Task ReportsOnlyWhenOperationsChange()
+        {
+            // Arrange
+            var first = new OperationsStatus(new ImageGcRun(DateTime.UtcNow, 2, null), 2, 1, 0, null, "connected");
+            var second = new OperationsStatus(new ImageGcRun(DateTime.UtcNow, 2, null), 2, 2, 0, null, "connected");
+            var moduleManager = new Mock<IModuleManager>();
+            moduleManager.SetupSequence(m => m.GetOperationsAsync(It.IsAny<CancellationToken>()))
+                .ReturnsAsync(Option.Some(first))
+                .ReturnsAsync(Option.Some(first))
+                .ReturnsAsync(Option.Some(second));
+
+            int reports = 0;
+            var reporter = new OperationsReporter(
+                moduleManager.Object,
+                _ =>
+                {
+                    reports++;
+                    return Task.CompletedTask;
+                },
+                TimeSpan.FromMinutes(5));
+
+            // Act
+            await reporter.Check(CancellationToken.None);
+            await reporter.Check(CancellationToken.None);
+            await reporter.Check(CancellationToken.None);
+
+            // Assert
+            Assert.Equal(2, reports);
+        }
+
+        [Fact]
+        public async Task SkipsOlderEdged()
+        {
+            // Arrange
+            var moduleManager = new Mock<IModuleManager>();
+            moduleManager.Setup(m => m.GetOperationsAsync(It.IsAny<CancellationToken>()))
+                .ReturnsAsync(Option.None<OperationsStatus>());
+            bool reported = false;
+
+            var reporter = new OperationsReporter(
+                moduleManager.Object,
+                _ =>
+                {
+                    reported = true;
+                    return Task.CompletedTask;
+                },
+                TimeSpan.FromMinutes(5));
+
+            // Act
+            await reporter.Check(CancellationToken.None);
+
+            // Assert
+            Assert.False(reported);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::{Module, ModuleRuntime, ModuleRuntimeState, ModuleStatus};
+use edgelet_settings::alerts::{Action, Condition, Rule};
+
+use crate::error::Error as EdgedError;
+
+/// How often alert conditions are evaluated.
+const ALERT_CHECK_PERIOD: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Webhooks that don't respond within this are abandoned, so that a stuck endpoint
+/// doesn't hold up the evaluation of other rules.
+const WEBHOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+struct RuleState {
+    rule: Rule,
+
+    /// When the condition started holding, if it holds.
+    holding_since: Option<tokio::time::Instant>,
+}
+
+/// Evaluate the configured alert rules against local module, disk and certificate state,
+/// and take their actions when they fire and resolve.
+pub(crate) fn start<M>(
+    settings: &impl edgelet_settings::RuntimeSettings,
+    runtime: M,
+    alerts: edgelet_http::Alerts,
+    changes: edgelet_http::ChangeFeed,
+) -> Result<(), EdgedError>
+where
+    M: ModuleRuntime + Send + Sync + 'static,
+{
+    if settings.alerts().is_empty() {
+        return Ok(());
+    }
+
+    for rule in settings.alerts() {
+        for action in rule.actions() {
+            if let Action::Webhook { url } = action {
+                if url.scheme() != "http" {
+                    return Err(EdgedError::new(format!(
+                        "Webhook {} of alert {} must be an http URL",
+                        url,
+                        rule.name()
+                    )));
+                }
+            }
+        }
+    }
+
+    let cert_connector = http_common::Connector::new(settings.endpoints().aziot_certd_url())
+        .map_err(|err| EdgedError::from_err("Invalid Certificates Service URL", err))?;
+    let cert_client = aziot_cert_client_async::Client::new(
+        aziot_cert_common_http::ApiVersion::V2020_09_01,
+        cert_connector,
+        1,
+    );
+
+    let edge_ca_cert = settings
+        .edge_ca_cert()
+        .unwrap_or(edgelet_settings::AZIOT_EDGED_CA_ALIAS)
+        .to_string();
+
+    let mut rules: Vec<RuleState> = settings
+        .alerts()
+        .iter()
+        .map(|rule| RuleState {
+            rule: rule.clone(),
+            holding_since: None,
+        })
+        .collect();
+
+    edgelet_core::tasks::spawn("alerts", async move {
+        let mut timer = tokio::time::interval(ALERT_CHECK_PERIOD);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            timer.tick().await;
+
+            let modules = match runtime.list_with_details().await {
+                Ok(modules) => modules,
+                Err(err) => {
+                    log::warn!("Failed to list modules for alerts: {}", err);
+
+                    continue;
+                }
+            };
+
+            for state in &mut rules {
+                let holds = match state.rule.condition() {
+                    Condition::ModuleDown { module } => Ok(module_down(&modules, module)),
+                    Condition::DiskUsage {
+                        path,
+                        above_percent,
+                    } => disk_usage(path, *above_percent).await,
+                    Condition::CertExpiry { cert, within } => {
+                        let cert = cert.as_deref().unwrap_or(&edge_ca_cert);
+
+                        cert_expiry(&cert_client, cert, *within).await
+                    }
+                };
+
+                // A condition that can't be evaluated leaves the alert as it was, so that
+                // a transient failure doesn't resolve it.
+                let message = match holds {
+                    Ok(message) => message,
+                    Err(err) => {
+                        log::warn!("Failed to evaluate alert {}: {}", state.rule.name(), err);
+
+                        continue;
+                    }
+                };
+
+                let alert = if let Some(message) = message {
+                    let holding_since = *state
+                        .holding_since
+                        .get_or_insert_with(tokio::time::Instant::now);
+
+                    if holding_since.elapsed() < state.rule.for_duration() {
+                        continue;
+                    }
+
+                    alerts.fire(state.rule.name(), &message)
+                } else {
+                    state.holding_since = None;
+
+                    alerts.resolve(state.rule.name())
+                };
+
+                if let Some(alert) = alert {
+                    act(&state.rule, &alert, &changes).await;
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn module_down(modules: &[(impl Module, ModuleRuntimeState)], name: &str) -> Option<String> {
+    match modules.iter().find(|(module, _)| module.name() == name) {
+        Some((_, state)) if *state.status() == ModuleStatus::Running => None,
+        Some((_, state)) => Some(format!("module {name} is {}", state.status())),
+        None => Some(format!("module {name} does not exist")),
+    }
+}
+
+async fn disk_usage(path: &std::path::Path, above_percent: u8) -> Result<Option<String>, String> {
+    // statvfs blocks on unresponsive file systems, such as a network mount that went away.
+    let owned = path.to_owned();
+    let stat = tokio::task::spawn_blocking(move || nix::sys::statvfs::statvfs(&owned))
+        .await
+        .map_err(|err| err.to_string())?
+        .map_err(|err| err.to_string())?;
+
+    // Blocks reserved for root count as neither used nor available, as with df.
+    #[allow(clippy::useless_conversion)]
+    let used = u64::from(stat.blocks()) - u64::from(stat.blocks_free());
+    #[allow(clippy::useless_conversion)]
+    let total = used + u64::from(stat.blocks_available());
+    if total == 0 {
+        return Ok(None);
+    }
+
+    let percent = used * 100 / total;
+
+    Ok((percent > u64::from(above_percent))
+        .then(|| format!("{} is {}% full", path.display(), percent)))
+}
+
+async fn cert_expiry(
+    cert_client: &aziot_cert_client_async::Client,
+    cert: &str,
+    within: std::time::Duration,
+) -> Result<Option<String>, String> {
+    let pem = cert_client
+        .get_cert(cert)
+        .await
+        .map_err(|err| format!("could not get certificate {cert}: {err}"))?;
+    let x509 = openssl::x509::X509::from_pem(&pem).map_err(|err| err.to_string())?;
+
+    let within = i64::try_from(within.as_secs()).unwrap_or(i64::MAX);
+    let deadline =
+        openssl::asn1::Asn1Time::from_unix(chrono::Utc::now().timestamp().saturating_add(within))
+            .map_err(|err| err.to_string())?;
+
+    Ok((x509.not_after() <= deadline)
+        .then(|| format!("certificate {cert} expires at {}", x509.not_after())))
+}
+
+async fn act(rule: &Rule, alert: &edgelet_http::AlertStatus, changes: &edgelet_http::ChangeFeed) {
+    let status = if alert.firing { "firing" } else { "resolved" };
+
+    if alert.firing {
+        log::warn!(
+            "Alert {} is firing: {}",
+            alert.name,
+            alert.message.as_deref().unwrap_or_default()
+        );
+    } else {
+        log::info!("Alert {} resolved", alert.name);
+    }
+
+    let body = serde_json::to_value(alert).expect("alert status is serializable");
+
+    for action in rule.actions() {
+        match action {
+            Action::Event => changes.record(
+                edgelet_http::Change::new(
+                    edgelet_http::ChangeKind::Alert,
+                    &alert.name,
+                    edgelet_http::ChangeEvent::StatusChanged,
+                )
+                .with_status(status)
+                .with_config(body.clone()),
+            ),
+
+            // The alert is marked for reporting when the rules are loaded, and Edge Agent's
+            // AlertReporter copies its status from the management API into the twin.
+            Action::TwinReport => log::debug!(
+                "Alert {} is {} and will be reported by Edge Agent",
+                alert.name,
+                status
+            ),
+
+            Action::Webhook { url } => {
+                match tokio::time::timeout(WEBHOOK_TIMEOUT, post(url, &body)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => {
+                        log::warn!("Failed to send alert {} to {}: {}", alert.name, url, err);
+                    }
+                    Err(_) => log::warn!("Sending alert {} to {} timed out", alert.name, url),
+                }
+            }
+        }
+    }
+}
+
+async fn post(url: &url::Url, body: &serde_json::Value) -> Result<(), String> {
+    let host = url.host_str().ok_or("URL has no host")?;
+    let port = url.port_or_known_default().ok_or("URL has no port")?;
+
+    // IPv6 hosts keep their brackets in the URL.
+    let stream =
+        tokio::net::TcpStream::connect((host.trim_start_matches('[').trim_end_matches(']'), port))
+            .await
+            .map_err(|err| err.to_string())?;
+
+    let (mut sender, connection) = hyper::client::conn::handshake(stream)
+        .await
+        .map_err(|err| err.to_string())?;
+    edgelet_core::tasks::spawn("alerts::webhook_connection", async move {
+        if let Err(err) = connection.await {
+            log::debug!("Alert webhook connection closed: {}", err);
+        }
+    });
+
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+
+    let request = hyper::Request::post(path)
+        .header(hyper::header::HOST, host)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(hyper::Body::from(body.to_string()))
+        .map_err(|err| err.to_string())?;
+    let response = sender
+        .send_request(request)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("endpoint responded with {}", response.status()))
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::ModuleRuntime;
+
+/// How often modules are compared with their last observed state.
+const CHANGE_FEED_CHECK_PERIOD: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Record module state transitions in the change feed served by the management API.
+///
+/// Modules are polled rather than followed through container engine events, so that
+/// transitions made while the daemon wasn't running are recorded on the first check.
+pub(crate) fn start(
+    runtime: edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    changes: edgelet_http::ChangeFeed,
+) {
+    edgelet_core::tasks::spawn("change_feed", async move {
+        let mut timer = tokio::time::interval(CHANGE_FEED_CHECK_PERIOD);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            timer.tick().await;
+
+            match runtime.list_with_details().await {
+                Ok(modules) => {
+                    let modules: edgelet_http::ListModulesResponse = modules.into();
+
+                    changes.observe_modules(&modules.modules);
+                }
+                Err(err) => log::warn!("Failed to list modules for change feed: {}", err),
+            }
+        }
+    });
+}
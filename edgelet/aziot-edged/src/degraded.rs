@@ -0,0 +1,287 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::{module::ModuleAction, ModuleRegistry, ModuleRuntime};
+use edgelet_settings::RuntimeSettings;
+
+use crate::error::Error as EdgedError;
+
+/// Set in the environment of degraded mode modules so they can tell that the device
+/// isn't provisioned.
+const DEGRADED_MODE_ENV: &str = "IOTEDGE_DEGRADEDMODE";
+
+/// Backoff of provisioning retries while in degraded mode.
+const PROVISION_RETRY_MIN: std::time::Duration = std::time::Duration::from_secs(5);
+const PROVISION_RETRY_MAX: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// How often Edge Agent's health is checked once the device is provisioned.
+const AGENT_CHECK_PERIOD: std::time::Duration = std::time::Duration::from_secs(30);
+
+type Runtime = edgelet_docker::DockerModuleRuntime<http_common::Connector>;
+
+/// Wait for provisioning to finish, starting the configured degraded mode modules if
+/// it takes too long.
+///
+/// Provisioning retries for as long as Identity Service or the cloud is unreachable,
+/// which leaves a device without any modules. The degraded mode modules give field
+/// staff something to troubleshoot the device with in the meantime, such as a local UI.
+/// They are removed once provisioning finishes so that they don't clash with the
+/// deployment.
+///
+/// With degraded mode configured, failed provisioning attempts are retried with
+/// backoff rather than stopping the daemon, since that would also stop the modules.
+pub(crate) async fn until_provisioned<T, F>(
+    settings: &edgelet_settings::docker::Settings,
+    runtime: &Runtime,
+    create_socket_channel_rcv: &mut tokio::sync::mpsc::UnboundedReceiver<ModuleAction>,
+    failures: &edgelet_http::FailureReport,
+    mut provision: impl FnMut() -> F,
+) -> Result<T, EdgedError>
+where
+    F: std::future::Future<Output = Result<T, EdgedError>>,
+{
+    let degraded_mode = settings.degraded_mode();
+
+    if degraded_mode.is_empty() {
+        return provision().await;
+    }
+
+    let provisioning = async {
+        let mut backoff = PROVISION_RETRY_MIN;
+
+        loop {
+            match provision().await {
+                Ok(result) => return result,
+                Err(err) => {
+                    log::warn!(
+                        "Failed to provision device, retrying in {} seconds: {}",
+                        backoff.as_secs(),
+                        err
+                    );
+                    failures.record(edgelet_http::FailureKind::Provisioning, &err);
+
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(PROVISION_RETRY_MAX);
+                }
+            }
+        }
+    };
+    tokio::pin!(provisioning);
+
+    tokio::select! {
+        result = &mut provisioning => return Ok(result),
+        () = tokio::time::sleep(degraded_mode.after()) => {},
+    }
+
+    log::warn!(
+        "Device is not provisioned after {} seconds; entering degraded mode",
+        degraded_mode.after().as_secs()
+    );
+
+    let degraded = async {
+        for module in degraded_mode.modules() {
+            if let Err(err) = start_module(runtime, module.clone(), true).await {
+                log::warn!(
+                    "Failed to start degraded mode module {}: {}",
+                    module.name(),
+                    err
+                );
+            }
+        }
+
+        let result = provisioning.await;
+
+        log::info!("Device provisioned; leaving degraded mode");
+
+        for module in degraded_mode.modules() {
+            remove_module(runtime, module.name()).await;
+        }
+
+        result
+    };
+
+    let result = answer_module_actions(degraded, create_socket_channel_rcv).await;
+
+    // Socket actions for degraded mode modules that were queued after the last poll are
+    // of no use to the workload manager.
+    while create_socket_channel_rcv.try_recv().is_ok() {}
+
+    Ok(result)
+}
+
+/// Start the degraded mode modules while Edge Agent is unhealthy, and remove them once
+/// it recovers.
+///
+/// A provisioned device can still be left without a working deployment, for example if
+/// Edge Agent can't reach IoT Hub and fails its Docker healthcheck, or keeps failing to
+/// start. Edge Agent is unhealthy if it isn't running, or if its healthcheck reports
+/// "unhealthy"; an image without a healthcheck is healthy while it runs.
+pub(crate) fn watch_agent(settings: &edgelet_settings::docker::Settings, runtime: Runtime) {
+    let degraded_mode = settings.degraded_mode().clone();
+
+    if degraded_mode.is_empty() {
+        return;
+    }
+
+    let agent_name = settings.agent().name().to_string();
+
+    edgelet_core::tasks::spawn("degraded_mode", async move {
+        let mut timer = tokio::time::interval(AGENT_CHECK_PERIOD);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        let mut unhealthy_since = None;
+
+        // Modules that were started by this task. Modules that already exist, such as
+        // deployment modules with the same name, are left alone.
+        let mut started: Vec<String> = Vec::new();
+
+        loop {
+            timer.tick().await;
+
+            match agent_health(&runtime, &agent_name).await {
+                Ok(()) => {
+                    unhealthy_since = None;
+
+                    if !started.is_empty() {
+                        log::info!("Edge runtime is healthy; leaving degraded mode");
+
+                        for name in started.drain(..) {
+                            remove_module(&runtime, &name).await;
+                        }
+                    }
+                }
+
+                Err(reason) => {
+                    let since = *unhealthy_since.get_or_insert_with(std::time::Instant::now);
+
+                    if !started.is_empty() || since.elapsed() < degraded_mode.after() {
+                        continue;
+                    }
+
+                    log::warn!(
+                        "Edge runtime unhealthy for {} seconds ({}); entering degraded mode",
+                        since.elapsed().as_secs(),
+                        reason
+                    );
+
+                    for module in degraded_mode.modules() {
+                        if runtime.get(module.name()).await.is_ok() {
+                            log::warn!(
+                                "Not starting degraded mode module {}: the module exists",
+                                module.name()
+                            );
+                            continue;
+                        }
+
+                        match start_module(&runtime, module.clone(), false).await {
+                            Ok(()) => started.push(module.name().to_string()),
+                            Err(err) => log::warn!(
+                                "Failed to start degraded mode module {}: {}",
+                                module.name(),
+                                err
+                            ),
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Whether Edge Agent is running and not reported unhealthy by its Docker healthcheck.
+async fn agent_health(runtime: &Runtime, agent_name: &str) -> Result<(), String> {
+    let (_, state) = runtime
+        .get(agent_name)
+        .await
+        .map_err(|err| format!("could not get its status: {err}"))?;
+
+    if *state.status() != edgelet_core::ModuleStatus::Running {
+        return Err(format!("status {}", state.status()));
+    }
+
+    match runtime.health(agent_name).await {
+        Ok(Some((status, output))) if status == "unhealthy" => Err(format!(
+            "healthcheck failed: {}",
+            output.unwrap_or_default()
+        )),
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("could not get its health: {err:#}")),
+    }
+}
+
+async fn remove_module(runtime: &Runtime, name: &str) {
+    if let Err(err) = runtime.remove(name).await {
+        log::warn!("Failed to remove degraded mode module {}: {}", name, err);
+    }
+}
+
+/// Start a degraded mode module. `replace_stale` removes an existing container of the
+/// same name first, which is only safe before the device is provisioned.
+async fn start_module(
+    runtime: &Runtime,
+    mut module: edgelet_settings::module::Settings<edgelet_settings::DockerConfig>,
+    replace_stale: bool,
+) -> Result<(), EdgedError> {
+    let name = module.name().to_string();
+
+    // A container from an earlier run of degraded mode may still exist if edged was
+    // stopped before it could remove it.
+    if replace_stale && runtime.get(&name).await.is_ok() {
+        runtime
+            .remove(&name)
+            .await
+            .map_err(|err| EdgedError::from_err("Failed to remove stale container", err))?;
+    }
+
+    module
+        .env_mut()
+        .insert(DEGRADED_MODE_ENV.to_string(), "true".to_string());
+
+    // The device may have no connectivity at all, so an image that is already on the
+    // device is used if the pull fails.
+    if let edgelet_settings::module::ImagePullPolicy::OnCreate = module.image_pull_policy() {
+        if let Err(err) = runtime.registry().pull_module(&name, module.config()).await {
+            log::warn!(
+                "Failed to pull image of degraded mode module {}: {}",
+                name,
+                err
+            );
+        }
+    }
+
+    runtime
+        .create(module)
+        .await
+        .map_err(|err| EdgedError::from_err("Failed to create module", err))?;
+    runtime
+        .start(&name)
+        .await
+        .map_err(|err| EdgedError::from_err("Failed to start module", err))?;
+
+    log::info!("Started degraded mode module {}", name);
+
+    Ok(())
+}
+
+/// Drive `future` while answering the module actions that the runtime sends to the
+/// workload manager, which isn't running yet.
+///
+/// Degraded mode modules get no workload socket, since the device has no identity to
+/// serve through it.
+async fn answer_module_actions<T>(
+    future: impl std::future::Future<Output = T>,
+    create_socket_channel_rcv: &mut tokio::sync::mpsc::UnboundedReceiver<ModuleAction>,
+) -> T {
+    tokio::pin!(future);
+
+    loop {
+        tokio::select! {
+            result = &mut future => return result,
+            Some(action) = create_socket_channel_rcv.recv() => {
+                if let ModuleAction::Start(_, sender) = action {
+                    // The runtime only waits on this to know the socket is ready.
+                    let _ = sender.send(());
+                }
+            },
+        }
+    }
+}
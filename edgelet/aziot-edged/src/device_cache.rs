@@ -0,0 +1,141 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use crate::error::Error as EdgedError;
+
+/// Key in Key Service that the device cache is encrypted with.
+const DEVICE_CACHE_KEY_ID: &str = "iotedge_device_cache";
+
+/// An encrypted cache file.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Envelope {
+    /// Base64-encoded.
+    iv: String,
+
+    /// Base64-encoded.
+    ciphertext: String,
+}
+
+/// Files in the cache directory that describe the device's identity.
+///
+/// They are encrypted with a key owned by Key Service, so that the hub, device ID and
+/// gateway of a device can't be read from its storage alone.
+pub(crate) struct DeviceCache {
+    dir: std::path::PathBuf,
+    key_client: aziot_key_client_async::Client,
+}
+
+impl DeviceCache {
+    pub fn new(
+        settings: &impl edgelet_settings::RuntimeSettings,
+        dir: std::path::PathBuf,
+    ) -> Result<Self, EdgedError> {
+        let key_connector = http_common::Connector::new(settings.endpoints().aziot_keyd_url())
+            .map_err(|err| EdgedError::from_err("Invalid Key Service URL", err))?;
+
+        let key_client = aziot_key_client_async::Client::new(
+            aziot_key_common_http::ApiVersion::V2020_09_01,
+            key_connector,
+            1,
+        );
+
+        Ok(DeviceCache { dir, key_client })
+    }
+
+    pub fn dir(&self) -> &std::path::Path {
+        &self.dir
+    }
+
+    /// Read and decrypt a cache file. Returns `None` if it doesn't exist.
+    ///
+    /// Files that aren't encrypted by this cache are rejected, since anyone who can write
+    /// to the cache directory could have put them there.
+    pub async fn read(&self, name: &str) -> std::io::Result<Option<Vec<u8>>> {
+        let path = self.dir.join(name);
+
+        let contents = match std::fs::read(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        let invalid = |err: &dyn std::fmt::Display| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} is not an encrypted cache file: {}", path.display(), err),
+            )
+        };
+
+        let envelope: Envelope = serde_json::from_slice(&contents).map_err(|err| invalid(&err))?;
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let iv = base64::Engine::decode(&engine, envelope.iv).map_err(|err| invalid(&err))?;
+        let ciphertext =
+            base64::Engine::decode(&engine, envelope.ciphertext).map_err(|err| invalid(&err))?;
+
+        let key = self.key().await?;
+        let parameters = aziot_key_common::EncryptMechanism::Aead { iv, aad: aad(name) };
+
+        self.key_client
+            .decrypt(&key, parameters, &ciphertext)
+            .await
+            .map(Some)
+    }
+
+    /// Encrypt a file that an earlier version cached in plaintext.
+    ///
+    /// The file is only carried over if its contents are exactly `expected`, so a plaintext
+    /// file can confirm what the daemon already knows but can't tell it anything new.
+    /// Any other plaintext file is left for `read` to reject.
+    pub async fn migrate(&self, name: &str, expected: &[u8]) -> std::io::Result<()> {
+        match std::fs::read(self.dir.join(name)) {
+            Ok(contents) if contents == expected => {
+                log::info!("Encrypting cached {}", name);
+
+                self.write(name, expected).await
+            }
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Encrypt and write a cache file.
+    pub async fn write(&self, name: &str, plaintext: &[u8]) -> std::io::Result<()> {
+        let mut iv = vec![0; 16];
+        openssl::rand::rand_bytes(&mut iv)?;
+
+        let key = self.key().await?;
+        let parameters = aziot_key_common::EncryptMechanism::Aead {
+            iv: iv.clone(),
+            aad: aad(name),
+        };
+
+        let ciphertext = self.key_client.encrypt(&key, parameters, plaintext).await?;
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let envelope = Envelope {
+            iv: base64::Engine::encode(&engine, iv),
+            ciphertext: base64::Engine::encode(&engine, ciphertext),
+        };
+        let envelope = serde_json::to_vec(&envelope)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+
+        std::fs::create_dir_all(&self.dir)?;
+        edgelet_http::persist::write(&self.dir.join(name), &envelope)
+    }
+
+    async fn key(&self) -> std::io::Result<aziot_key_common::KeyHandle> {
+        self.key_client
+            .create_key_if_not_exists(
+                DEVICE_CACHE_KEY_ID,
+                aziot_key_common::CreateKeyValue::Generate,
+                &[aziot_key_common::KeyUsage::Encrypt],
+            )
+            .await
+    }
+}
+
+/// Binds the ciphertext to its file, so that cache files can't be swapped.
+fn aad(name: &str) -> Vec<u8> {
+    format!("device_cache:{name}").into_bytes()
+}
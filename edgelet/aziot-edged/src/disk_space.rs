@@ -0,0 +1,27 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Periodically check the free space on the daemon's directories and the container
+/// engine's data root, so that the runtime can refuse to fill the disk further.
+pub(crate) fn start(
+    settings: &impl edgelet_settings::RuntimeSettings,
+    runtime: edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+) {
+    let settings = settings.watchdog().disk_space();
+
+    if !settings.enabled {
+        return;
+    }
+
+    let check_interval = settings.check_interval;
+
+    edgelet_core::tasks::spawn("disk_space", async move {
+        let mut timer = tokio::time::interval(check_interval);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            timer.tick().await;
+
+            runtime.check_disk_space().await;
+        }
+    });
+}
@@ -0,0 +1,78 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Add the daemon's sections to the state dump.
+pub(crate) fn register(
+    state_dump: &edgelet_http::StateDump,
+    server_tasks: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    watchdog: crate::watchdog::Status,
+    listeners: crate::workload_manager::Listeners,
+    runtime: edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    image_use_data: edgelet_docker::ImagePruneData,
+    operations: edgelet_http::Operations,
+) {
+    state_dump.add_section("tasks", move || {
+        serde_json::json!({
+            "servers": server_tasks.load(std::sync::atomic::Ordering::Acquire),
+            "spawned": edgelet_core::tasks::list(),
+        })
+    });
+
+    state_dump.add_section("watchdog", move || to_value(&watchdog.get()));
+
+    state_dump.add_section("workloadListeners", move || to_value(&listeners.list()));
+
+    state_dump.add_section("dockerOperations", move || to_value(&runtime.in_flight()));
+
+    state_dump.add_section("imageGc", move || {
+        // Image garbage collection holds its data while it runs, so a busy lock is
+        // reported rather than waited on.
+        let tracked_images = match image_use_data.tracked_images() {
+            Ok(images) => images
+                .into_iter()
+                .map(|(image, last_used)| {
+                    let last_used: chrono::DateTime<chrono::Utc> =
+                        (std::time::UNIX_EPOCH + last_used).into();
+
+                    (image, serde_json::json!(last_used))
+                })
+                .collect::<std::collections::BTreeMap<_, _>>(),
+            Err(err) => return serde_json::json!({ "error": err.to_string() }),
+        };
+
+        serde_json::json!({
+            "trackedImages": tracked_images,
+            "lastRun": operations.get().last_image_gc,
+        })
+    });
+}
+
+/// Write the state dump to `dump.json` in the state directory on SIGUSR2, for when the
+/// management API can't be reached.
+pub(crate) fn set_signal_handler(state_dump: edgelet_http::StateDump, path: std::path::PathBuf) {
+    let mut sigusr2_stream =
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined2())
+            .expect("cannot fail to set signal handler");
+
+    edgelet_core::tasks::spawn("state_dump_signal", async move {
+        while sigusr2_stream.recv().await.is_some() {
+            log::info!("Received SIGUSR2; writing state dump");
+
+            let report = state_dump.collect();
+            let result = serde_json::to_vec_pretty(&report)
+                .map_err(|err| err.to_string())
+                .and_then(|report| {
+                    edgelet_http::persist::write(&path, &report).map_err(|err| err.to_string())
+                });
+
+            match result {
+                Ok(()) => log::info!("Wrote state dump to {}", path.display()),
+                Err(err) => log::warn!("Failed to write state dump: {}", err),
+            }
+        }
+    });
+}
+
+fn to_value(value: &impl serde::Serialize) -> serde_json::Value {
+    serde_json::to_value(value)
+        .unwrap_or_else(|err| serde_json::json!({ "error": err.to_string() }))
+}
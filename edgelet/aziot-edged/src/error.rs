@@ -46,6 +46,16 @@ impl Error {
             exit_code: 154,
         }
     }
+
+    pub fn site_overlay_changed() -> Self {
+        Error {
+            message: "Site overlay from parent has changed. Restarting Edge daemon to apply it."
+                .to_string(),
+
+            // Same as reprovisioning, so that systemd restarts this process.
+            exit_code: 154,
+        }
+    }
 }
 
 // Clippy wants an implementation of From<i32> over Into<i32>. However, we don't want to convert
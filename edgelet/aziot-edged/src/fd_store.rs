@@ -0,0 +1,244 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Listening sockets kept open by systemd across restarts of the daemon.
+//!
+//! Each module's workload socket is passed to systemd's file descriptor store under a
+//! name of its own. systemd passes stored sockets back on the next start, the same way
+//! as socket-activated sockets, so that they are picked up by name when listeners are
+//! created. The service needs `FileDescriptorStoreMax` for systemd to keep them.
+
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+const NAME_PREFIX: &str = "aziot-edged.workload.";
+
+/// Name of a module's workload socket in the store.
+pub(crate) fn name(module_id: &str) -> String {
+    format!("{NAME_PREFIX}{module_id}")
+}
+
+/// Names of the stored workload sockets that systemd passed to this run of the daemon.
+///
+/// The names are read once, since creating a listener consumes the passed sockets.
+fn passed() -> &'static Passed {
+    static PASSED: std::sync::OnceLock<Passed> = std::sync::OnceLock::new();
+
+    PASSED.get_or_init(|| Passed::new(std::env::var("LISTEN_FDNAMES").ok().as_deref()))
+}
+
+struct Passed(std::sync::Mutex<std::collections::BTreeSet<String>>);
+
+impl Passed {
+    fn new(listen_fdnames: Option<&str>) -> Self {
+        let names = listen_fdnames
+            .unwrap_or_default()
+            .split(':')
+            .filter(|name| name.starts_with(NAME_PREFIX))
+            .map(str::to_string)
+            .collect();
+
+        Passed(std::sync::Mutex::new(names))
+    }
+
+    fn any(&self) -> bool {
+        !self.lock().is_empty()
+    }
+
+    fn take(&self, name: &str) -> Option<String> {
+        self.lock().take(name)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, std::collections::BTreeSet<String>> {
+        self.0.lock().expect("passed sockets lock poisoned")
+    }
+}
+
+/// Whether systemd passed stored workload sockets to this run of the daemon, which
+/// means that the modules using them are still connected to them.
+///
+/// This must be checked before any listener is created, since that consumes the
+/// passed sockets.
+pub(crate) fn restored() -> bool {
+    passed().any()
+}
+
+/// The name to look a module's workload socket up by, if systemd passed it to this run
+/// of the daemon and it hasn't been used yet.
+///
+/// A passed socket is only used by the first listener of the module. Once that listener
+/// is stopped its socket is closed, so a listener started later must not pick up the
+/// socket's descriptor, which may have been reused by then.
+pub(crate) fn take(module_id: &str) -> Option<String> {
+    passed().take(&name(module_id))
+}
+
+/// Whether systemd is restarting the service, rather than stopping it.
+///
+/// systemd drops the stored sockets when the service is stopped, so modules are only
+/// left running for a restart. After `systemctl stop`, they would otherwise keep
+/// running with sockets that nothing listens on.
+pub(crate) async fn restarting() -> bool {
+    let cgroup = match tokio::fs::read_to_string("/proc/self/cgroup").await {
+        Ok(cgroup) => cgroup,
+        Err(err) => {
+            log::warn!("Failed to read the service's cgroup: {}", err);
+            return false;
+        }
+    };
+    let Some(unit) = unit(&cgroup) else {
+        return false;
+    };
+
+    let output = tokio::process::Command::new("systemctl")
+        .args(["list-jobs", "--no-legend", "--plain", unit])
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => {
+            has_restart_job(&String::from_utf8_lossy(&output.stdout), unit)
+        }
+        Ok(output) => {
+            log::warn!(
+                "Failed to list systemd jobs: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            false
+        }
+        Err(err) => {
+            log::warn!("Failed to list systemd jobs: {}", err);
+            false
+        }
+    }
+}
+
+/// The service unit of the process, out of the contents of `/proc/self/cgroup`.
+fn unit(cgroup: &str) -> Option<&str> {
+    cgroup
+        .lines()
+        .filter_map(|line| line.rsplit_once(':'))
+        .flat_map(|(_, path)| path.rsplit('/'))
+        .find(|component| component.ends_with(".service"))
+}
+
+/// Whether `systemctl list-jobs` output has a restart job of the unit. systemd runs a
+/// restart as a stop under the restart job, and only turns it into a start job once
+/// the unit has stopped.
+fn has_restart_job(jobs: &str, unit: &str) -> bool {
+    jobs.lines().any(|line| {
+        let mut fields = line.split_whitespace().skip(1);
+
+        fields.next() == Some(unit) && fields.next().map_or(false, |job| job.ends_with("restart"))
+    })
+}
+
+/// Keep a listening socket open in systemd.
+pub(crate) fn store(name: &str, fd: RawFd) {
+    if let Err(err) = notify(&format!("FDSTORE=1\nFDNAME={name}"), &[fd]) {
+        log::warn!("Failed to store socket {} with systemd: {}", name, err);
+    }
+}
+
+/// Close a socket that was kept open in systemd, e.g. because its module was removed.
+pub(crate) fn remove(name: &str) {
+    if let Err(err) = notify(&format!("FDSTOREREMOVE=1\nFDNAME={name}"), &[]) {
+        log::warn!("Failed to remove socket {} from systemd: {}", name, err);
+    }
+}
+
+fn notify(message: &str, fds: &[RawFd]) -> std::io::Result<()> {
+    // Not running under systemd, so there is nowhere to store sockets.
+    let Some(notify_socket) = std::env::var_os("NOTIFY_SOCKET") else {
+        return Ok(());
+    };
+    let notify_socket = notify_socket.as_bytes();
+
+    // Sockets in the abstract namespace are given with a leading '@'.
+    let addr = match notify_socket.strip_prefix(b"@") {
+        Some(name) => nix::sys::socket::UnixAddr::new_abstract(name),
+        None => nix::sys::socket::UnixAddr::new(notify_socket),
+    }?;
+
+    let socket = std::os::unix::net::UnixDatagram::unbound()?;
+
+    let iov = [std::io::IoSlice::new(message.as_bytes())];
+    let cmsgs: Vec<_> = if fds.is_empty() {
+        Vec::new()
+    } else {
+        vec![nix::sys::socket::ControlMessage::ScmRights(fds)]
+    };
+
+    nix::sys::socket::sendmsg(
+        socket.as_raw_fd(),
+        &iov,
+        &cmsgs,
+        nix::sys::socket::MsgFlags::empty(),
+        Some(&addr),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_restart_job, name, unit, Passed};
+
+    #[test]
+    fn passed_sockets_are_taken_once() {
+        let passed = Passed::new(Some(&format!(
+            "aziot-edged.mgmt.socket:{}:{}",
+            name("sensor"),
+            name("filter")
+        )));
+        assert!(passed.any());
+
+        assert_eq!(Some(name("sensor")), passed.take(&name("sensor")));
+        assert_eq!(None, passed.take(&name("sensor")));
+        assert_eq!(None, passed.take("aziot-edged.mgmt.socket"));
+
+        assert_eq!(Some(name("filter")), passed.take(&name("filter")));
+        assert!(!passed.any());
+
+        assert!(!Passed::new(None).any());
+        assert!(!Passed::new(Some("aziot-edged.mgmt.socket")).any());
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(
+            Some("aziot-edged.service"),
+            unit("0::/system.slice/aziot-edged.service\n")
+        );
+        assert_eq!(
+            Some("snap.azure-iot-edge.aziot-edged.service"),
+            unit("12:pids:/system.slice/snap.azure-iot-edge.aziot-edged.service\n0::/\n")
+        );
+        assert_eq!(
+            None,
+            unit("0::/user.slice/user-1000.slice/session-1.scope\n")
+        );
+    }
+
+    #[test]
+    fn restart_jobs() {
+        let unit = "aziot-edged.service";
+
+        assert!(has_restart_job(
+            "1234 aziot-edged.service restart running\n",
+            unit
+        ));
+        assert!(has_restart_job(
+            "1234 aziot-edged.service try-restart running\n",
+            unit
+        ));
+        assert!(!has_restart_job(
+            "1234 aziot-edged.service stop running\n",
+            unit
+        ));
+        assert!(!has_restart_job(
+            "1234 docker.service restart running\n",
+            unit
+        ));
+        assert!(!has_restart_job("", unit));
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Listeners for the management and workload APIs.
+//!
+//! The APIs identify callers by the PID of the process on the other end of the socket,
+//! so they are only served on Unix sockets: `unix://` paths, or `fd://` sockets passed
+//! by systemd. Callers that can't use Unix sockets use the token-authenticated workload
+//! TCP listener (`workload_tcp_uri`) or the mutual TLS management listener
+//! (`[listen.management_tcp]`) instead.
+
+use crate::error::Error as EdgedError;
+
+pub(crate) async fn bind(
+    uri: &url::Url,
+    permission: u32,
+    max_requests: usize,
+    socket_name: Option<String>,
+) -> Result<http_common::Incoming, EdgedError> {
+    if !matches!(uri.scheme(), "unix" | "fd") {
+        return Err(EdgedError::new(format!(
+            "Cannot listen on {uri}: only unix:// and fd:// URIs are supported"
+        )));
+    }
+
+    let connector = http_common::Connector::new(uri)
+        .map_err(|err| EdgedError::from_err(format!("Invalid URI {uri}"), err))?;
+
+    connector
+        .incoming(permission, max_requests, socket_name)
+        .await
+        .map_err(|err| EdgedError::from_err(format!("Failed to listen on {uri}"), err))
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn bind_rejects_other_schemes() {
+        for uri in ["http://127.0.0.1:8080", "npipe://./pipe/aziot-edged.mgmt"] {
+            let uri = url::Url::parse(uri).unwrap();
+
+            let err = super::bind(&uri, 0o660, 10, None).await.unwrap_err();
+            assert!(
+                err.to_string().contains("only unix:// and fd:// URIs"),
+                "{err}"
+            );
+        }
+    }
+}
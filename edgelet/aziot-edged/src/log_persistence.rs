@@ -0,0 +1,183 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::HashMap;
+
+use edgelet_core::ModuleRuntime;
+use futures_util::TryStreamExt;
+
+use crate::log_sink::Demux;
+
+/// Parse a frame of a container's logs. Timestamps are requested, so each frame starts
+/// with the time it was written.
+fn parse_frame(stream: &str, payload: &[u8]) -> Option<edgelet_http::LogEntry> {
+    let payload = String::from_utf8_lossy(payload);
+    let (time, log) = payload.split_once(' ')?;
+
+    let time = chrono::DateTime::parse_from_rfc3339(time)
+        .ok()?
+        .with_timezone(&chrono::Utc);
+
+    Some(edgelet_http::LogEntry {
+        time,
+        stream: log_stream(stream),
+        log: log.to_string(),
+    })
+}
+
+fn log_stream(stream: &str) -> edgelet_http::LogStream {
+    if stream == "stderr" {
+        edgelet_http::LogStream::Stderr
+    } else {
+        edgelet_http::LogStream::Stdout
+    }
+}
+
+/// Keep a module's output until its container stops.
+async fn follow(
+    runtime: edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    persisted_logs: edgelet_http::PersistedLogs,
+    module: String,
+) {
+    let last = match persisted_logs.last_time(&module) {
+        Ok(last) => last,
+        Err(err) => {
+            log::warn!("Failed to read kept output of module {}: {}", module, err);
+
+            return;
+        }
+    };
+
+    // The container engine only filters by the second, so output at or before the last
+    // kept entry is skipped below rather than kept twice.
+    let since = last.map_or(0, |last| {
+        i32::try_from(last.timestamp()).unwrap_or(i32::MAX)
+    });
+
+    let options = edgelet_core::LogOptions::new()
+        .with_follow(true)
+        .with_since(since)
+        .with_timestamps(true);
+
+    let mut logs = match runtime.logs(&module, &options).await {
+        Ok(logs) => logs,
+        Err(err) => {
+            log::warn!("Failed to keep output of module {}: {}", module, err);
+
+            return;
+        }
+    };
+
+    let mut demux = Demux::default();
+
+    // The time of the entry whose frame was last read, if it is kept. The rest of a
+    // frame that is passed on in pieces has no timestamp of its own.
+    let mut current = None;
+
+    loop {
+        match logs.try_next().await {
+            Ok(Some(bytes)) => demux.push(&bytes),
+            Ok(None) => break,
+            Err(err) => {
+                log::warn!("Failed to read output of module {}: {}", module, err);
+
+                break;
+            }
+        }
+
+        let mut entries = Vec::new();
+        while let Some((stream, payload)) = demux.next_frame() {
+            if demux.continues() {
+                if let Some(time) = current {
+                    entries.push(edgelet_http::LogEntry {
+                        time,
+                        stream: log_stream(stream),
+                        log: String::from_utf8_lossy(&payload).into_owned(),
+                    });
+                }
+
+                continue;
+            }
+
+            current = None;
+
+            if let Some(entry) = parse_frame(stream, &payload) {
+                if last.map_or(true, |last| entry.time > last) {
+                    current = Some(entry.time);
+                    entries.push(entry);
+                }
+            }
+        }
+
+        if entries.is_empty() {
+            continue;
+        }
+
+        // Rotating a file compresses it, so this isn't done on the runtime's threads.
+        let persisted_logs = persisted_logs.clone();
+        let name = module.clone();
+        let result =
+            tokio::task::spawn_blocking(move || persisted_logs.append(&name, &entries)).await;
+
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => log::warn!("Failed to keep output of module {}: {}", module, err),
+            Err(err) => log::warn!("Failed to keep output of module {}: {}", module, err),
+        }
+    }
+}
+
+/// Keep the stdout and stderr of modules in rotated files in the home directory, so that
+/// they survive the recreation of the modules' containers.
+pub(crate) fn start(
+    runtime: edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    persisted_logs: edgelet_http::PersistedLogs,
+) {
+    if !persisted_logs.is_enabled() {
+        return;
+    }
+
+    log::info!("Keeping module output in the home directory");
+
+    edgelet_core::tasks::spawn("log_persistence", async move {
+        let mut followers: HashMap<String, tokio::task::JoinHandle<()>> = HashMap::new();
+
+        crate::log_sink::watch_modules(runtime.clone(), |event| match event {
+            edgelet_docker::ContainerEvent::Started(name) => {
+                if !persisted_logs.persists(&name)
+                    || followers
+                        .get(&name)
+                        .map_or(false, |follower| !follower.is_finished())
+                {
+                    return;
+                }
+
+                let follower = edgelet_core::tasks::spawn(
+                    "log_persistence::follow",
+                    follow(runtime.clone(), persisted_logs.clone(), name.clone()),
+                );
+                followers.insert(name, follower);
+            }
+
+            // The kept output of a removed module stays, so that it can be read after the
+            // module is created again.
+            edgelet_docker::ContainerEvent::Removed(name) => {
+                followers.remove(&name);
+            }
+        })
+        .await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_frame() {
+        let entry =
+            super::parse_frame("stderr", b"2023-01-01T00:00:00.123456789Z failed\n").unwrap();
+        assert_eq!(edgelet_http::LogStream::Stderr, entry.stream);
+        assert_eq!("failed\n", entry.log);
+        assert_eq!(123_456_789, chrono::Timelike::nanosecond(&entry.time));
+
+        assert!(super::parse_frame("stdout", b"no timestamp").is_none());
+    }
+}
@@ -0,0 +1,471 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use edgelet_core::{Module, ModuleRuntime, ModuleStatus};
+use futures_util::{FutureExt, TryStreamExt};
+
+use crate::error::Error as EdgedError;
+
+enum Sink {
+    Unix(tokio::net::UnixDatagram, std::path::PathBuf),
+    Udp(tokio::net::UdpSocket),
+}
+
+impl Sink {
+    async fn new(url: &url::Url) -> Result<Self, EdgedError> {
+        match url.scheme() {
+            "unix" => {
+                let socket = tokio::net::UnixDatagram::unbound()
+                    .map_err(|err| EdgedError::from_err("Failed to create log sink socket", err))?;
+
+                Ok(Sink::Unix(socket, url.path().into()))
+            }
+
+            "udp" => {
+                let (host, port) = match (url.host_str(), url.port()) {
+                    (Some(host), Some(port)) => (host, port),
+                    _ => {
+                        return Err(EdgedError::new(format!(
+                            "Log sink {url} must specify a host and port"
+                        )));
+                    }
+                };
+
+                // IPv6 hosts keep their brackets in the URL.
+                let host = host.trim_start_matches('[').trim_end_matches(']');
+
+                let addr = tokio::net::lookup_host((host, port))
+                    .await
+                    .map_err(|err| EdgedError::from_err(format!("Failed to resolve {url}"), err))?
+                    .next()
+                    .ok_or_else(|| EdgedError::new(format!("Failed to resolve {url}")))?;
+
+                let local: std::net::SocketAddr = if addr.is_ipv4() {
+                    (std::net::Ipv4Addr::UNSPECIFIED, 0).into()
+                } else {
+                    (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+                };
+
+                let socket = tokio::net::UdpSocket::bind(local)
+                    .await
+                    .map_err(|err| EdgedError::from_err("Failed to create log sink socket", err))?;
+                socket.connect(addr).await.map_err(|err| {
+                    EdgedError::from_err(format!("Failed to connect to {url}"), err)
+                })?;
+
+                Ok(Sink::Udp(socket))
+            }
+
+            scheme => Err(EdgedError::new(format!(
+                "Log sink scheme {scheme:?} is not supported; use \"unix\" or \"udp\""
+            ))),
+        }
+    }
+
+    async fn send(&self, frame: &[u8]) -> std::io::Result<()> {
+        match self {
+            Sink::Unix(socket, path) => socket.send_to(frame, path).await.map(|_| ()),
+            Sink::Udp(socket) => socket.send(frame).await.map(|_| ()),
+        }
+    }
+}
+
+/// Longest payload that is buffered. Longer frames and lines are passed on in pieces of
+/// this size, so that output that never ends a line can't grow the buffer unbounded.
+const MAX_FRAME_LEN: usize = 64 * 1024;
+
+/// Splits the logs of a container into frames of its stdout and stderr.
+///
+/// The container engine multiplexes the streams of a container without a TTY: each frame
+/// has an 8 byte header with the stream type (1 for stdout, 2 for stderr), 3 bytes of
+/// padding and the big-endian length of the payload that follows. The output of a
+/// container with a TTY isn't multiplexed, so when the stream doesn't start with such a
+/// header it is split into lines instead, all of them stdout. Frames and lines can span
+/// the chunks of the response body.
+#[derive(Default)]
+pub(crate) struct Demux {
+    buf: Vec<u8>,
+
+    /// Whether the stream was found not to be multiplexed.
+    raw: bool,
+
+    /// The stream and remaining length of a frame whose header has been read, and
+    /// whether part of its payload has been passed on.
+    partial: Option<(&'static str, usize, bool)>,
+
+    /// Whether the last line passed on was cut short.
+    open_line: bool,
+
+    /// Whether the last frame returned continues the one before it.
+    continues: bool,
+}
+
+impl Demux {
+    pub(crate) fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub(crate) fn next_frame(&mut self) -> Option<(&'static str, Vec<u8>)> {
+        if self.raw {
+            return self.next_line();
+        }
+
+        let (stream, len, continues) = match self.partial.take() {
+            Some(partial) => partial,
+            None => {
+                if self.buf.len() < 8 {
+                    return None;
+                }
+
+                if self.buf[0] > 2 || self.buf[1..4] != [0, 0, 0] {
+                    self.raw = true;
+
+                    return self.next_line();
+                }
+
+                let stream = if self.buf[0] == 2 { "stderr" } else { "stdout" };
+                let len = u32::from_be_bytes([self.buf[4], self.buf[5], self.buf[6], self.buf[7]])
+                    as usize;
+                self.buf.drain(..8);
+
+                (stream, len, false)
+            }
+        };
+
+        if self.buf.len() >= len {
+            self.continues = continues;
+
+            return Some((stream, self.buf.drain(..len).collect()));
+        }
+
+        if self.buf.len() >= MAX_FRAME_LEN {
+            self.continues = continues;
+            self.partial = Some((stream, len - MAX_FRAME_LEN, true));
+
+            return Some((stream, self.buf.drain(..MAX_FRAME_LEN).collect()));
+        }
+
+        self.partial = Some((stream, len, continues));
+
+        None
+    }
+
+    fn next_line(&mut self) -> Option<(&'static str, Vec<u8>)> {
+        let len = match self.buf.iter().position(|b| *b == b'\n') {
+            Some(end) => end + 1,
+            None if self.buf.len() >= MAX_FRAME_LEN => MAX_FRAME_LEN,
+            None => return None,
+        };
+
+        let line: Vec<u8> = self.buf.drain(..len).collect();
+        self.continues = self.open_line;
+        self.open_line = line.last() != Some(&b'\n');
+
+        Some(("stdout", line))
+    }
+
+    /// Whether the frame last returned by `next_frame` is a piece of a longer frame or
+    /// line other than its first.
+    pub(crate) fn continues(&self) -> bool {
+        self.continues
+    }
+}
+
+/// How long to wait before listening for the container engine's events again after the
+/// connection to it is lost.
+const EVENTS_RETRY_PERIOD: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Call `handle` with each module whose container is running now or starts later, and
+/// each module whose container is removed, until the daemon stops.
+///
+/// Modules are found through the container engine's events rather than by polling. Each
+/// time the events are subscribed to, running modules are listed again, and modules that
+/// were removed meanwhile are reported as such.
+pub(crate) async fn watch_modules<F>(
+    runtime: edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    mut handle: F,
+) where
+    F: FnMut(edgelet_docker::ContainerEvent),
+{
+    use edgelet_docker::ContainerEvent;
+
+    let mut known = std::collections::HashSet::new();
+
+    loop {
+        // Events are subscribed to before modules are listed, so that a module that
+        // starts in between isn't missed.
+        let events = match runtime.container_events().await {
+            Ok(events) => events,
+            Err(err) => {
+                log::warn!("Failed to listen for module events: {}", err);
+                tokio::time::sleep(EVENTS_RETRY_PERIOD).await;
+
+                continue;
+            }
+        };
+        futures_util::pin_mut!(events);
+
+        match runtime.list_with_details().await {
+            Ok(modules) => {
+                let listed: std::collections::HashSet<_> = modules
+                    .iter()
+                    .map(|(module, _)| module.name().to_string())
+                    .collect();
+
+                for name in known.difference(&listed) {
+                    handle(ContainerEvent::Removed(name.clone()));
+                }
+
+                for (module, state) in modules {
+                    if *state.status() == ModuleStatus::Running {
+                        handle(ContainerEvent::Started(module.name().to_string()));
+                    }
+                }
+
+                known = listed;
+            }
+            Err(err) => log::warn!("Failed to list modules: {}", err),
+        }
+
+        loop {
+            match events.try_next().await {
+                Ok(Some(event)) => {
+                    match &event {
+                        ContainerEvent::Started(name) => known.insert(name.clone()),
+                        ContainerEvent::Removed(name) => known.remove(name),
+                    };
+
+                    handle(event);
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    log::warn!("Failed to read module events: {}", err);
+
+                    break;
+                }
+            }
+        }
+
+        tokio::time::sleep(EVENTS_RETRY_PERIOD).await;
+    }
+}
+
+fn now() -> i32 {
+    i32::try_from(chrono::Utc::now().timestamp()).unwrap_or(i32::MAX)
+}
+
+/// Copy a module's output to the sink until its container stops. Returns the time the
+/// copy stopped, so that the next copy picks up where this one left off.
+async fn follow(
+    runtime: edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    sink: Arc<Sink>,
+    module: String,
+    since: i32,
+) -> i32 {
+    let options = edgelet_core::LogOptions::new()
+        .with_follow(true)
+        .with_since(since);
+
+    let mut logs = match runtime.logs(&module, &options).await {
+        Ok(logs) => logs,
+        Err(err) => {
+            log::warn!(
+                "Failed to copy output of module {} to log sink: {}",
+                module,
+                err
+            );
+
+            return since;
+        }
+    };
+
+    let mut demux = Demux::default();
+
+    loop {
+        match logs.try_next().await {
+            Ok(Some(bytes)) => demux.push(&bytes),
+            Ok(None) => break,
+            Err(err) => {
+                log::warn!("Failed to read output of module {}: {}", module, err);
+
+                break;
+            }
+        }
+
+        while let Some((stream, payload)) = demux.next_frame() {
+            let frame = serde_json::json!({
+                "module": module,
+                "stream": stream,
+                "log": String::from_utf8_lossy(&payload),
+            });
+
+            // The sink is best-effort. Its reader may not have started yet, and output
+            // is not held back waiting for it.
+            if let Err(err) = sink.send(frame.to_string().as_bytes()).await {
+                log::debug!("Failed to write to log sink: {}", err);
+            }
+        }
+    }
+
+    now()
+}
+
+/// Copy the stdout and stderr of modules to the configured log sink as they are written.
+///
+/// Each datagram is a JSON object with the module name, the stream ("stdout" or
+/// "stderr") and the output itself, so that consumers on the device don't need access
+/// to the container engine.
+pub(crate) async fn start(
+    settings: &impl edgelet_settings::RuntimeSettings,
+    runtime: edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+) -> Result<(), EdgedError> {
+    let Some(settings) = settings.log_sink() else {
+        return Ok(());
+    };
+
+    let sink = Arc::new(Sink::new(settings.url()).await?);
+    let modules = settings.modules().to_vec();
+
+    log::info!("Copying module output to log sink {}", settings.url());
+
+    // Modules are stopped at startup, so no output written before now is missed.
+    let started = now();
+
+    edgelet_core::tasks::spawn("log_sink", async move {
+        // The output of a module that stops is copied again from when its last copy
+        // stopped, if the module starts again.
+        let mut followers: HashMap<String, tokio::task::JoinHandle<i32>> = HashMap::new();
+
+        watch_modules(runtime.clone(), |event| match event {
+            edgelet_docker::ContainerEvent::Started(name) => {
+                if !modules.is_empty() && !modules.contains(&name) {
+                    return;
+                }
+
+                let since = match followers.remove(&name) {
+                    Some(follower) if !follower.is_finished() => {
+                        followers.insert(name, follower);
+
+                        return;
+                    }
+                    Some(follower) => follower
+                        .now_or_never()
+                        .and_then(Result::ok)
+                        .unwrap_or(started),
+                    None => started,
+                };
+
+                let follower = edgelet_core::tasks::spawn(
+                    "log_sink::follow",
+                    follow(runtime.clone(), sink.clone(), name.clone(), since),
+                );
+                followers.insert(name, follower);
+            }
+
+            // A removed module's output has been copied, since its container stopped
+            // first. If it is created again, it has new output only.
+            edgelet_docker::ContainerEvent::Removed(name) => {
+                followers.remove(&name);
+            }
+        })
+        .await;
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Demux;
+
+    fn frame(stream: u8, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![stream, 0, 0, 0];
+        frame.extend_from_slice(&u32::try_from(payload.len()).unwrap().to_be_bytes());
+        frame.extend_from_slice(payload);
+
+        frame
+    }
+
+    fn frames(demux: &mut Demux) -> Vec<(&'static str, Vec<u8>)> {
+        std::iter::from_fn(|| demux.next_frame()).collect()
+    }
+
+    #[test]
+    fn multiplexed() {
+        let mut bytes = frame(1, b"out\n");
+        bytes.extend(frame(2, b"err\n"));
+
+        // Frames can span chunks.
+        let mut demux = Demux::default();
+        demux.push(&bytes[..6]);
+        assert!(frames(&mut demux).is_empty());
+        demux.push(&bytes[6..]);
+
+        assert_eq!(
+            vec![("stdout", b"out\n".to_vec()), ("stderr", b"err\n".to_vec())],
+            frames(&mut demux)
+        );
+    }
+
+    #[test]
+    fn tty() {
+        let mut demux = Demux::default();
+        demux.push(b"first line\nsecond");
+        assert_eq!(
+            vec![("stdout", b"first line\n".to_vec())],
+            frames(&mut demux)
+        );
+
+        demux.push(b" line\n");
+        assert_eq!(
+            vec![("stdout", b"second line\n".to_vec())],
+            frames(&mut demux)
+        );
+    }
+
+    #[test]
+    fn bounded() {
+        let len = super::MAX_FRAME_LEN + 10;
+
+        // A frame longer than the limit is passed on in pieces.
+        let mut demux = Demux::default();
+        demux.push(&frame(2, &vec![b'a'; len]));
+        demux.push(&frame(1, b"next"));
+
+        assert!(demux.next_frame().is_some());
+        assert!(!demux.continues());
+        assert!(demux.next_frame().is_some());
+        assert!(demux.continues());
+
+        let mut demux = Demux::default();
+        demux.push(&frame(2, &vec![b'a'; len]));
+        demux.push(&frame(1, b"next"));
+
+        let frames = frames(&mut demux);
+        assert!(!demux.continues());
+        assert_eq!(
+            vec![
+                ("stderr", super::MAX_FRAME_LEN),
+                ("stderr", 10),
+                ("stdout", 4)
+            ],
+            frames
+                .iter()
+                .map(|(stream, payload)| (*stream, payload.len()))
+                .collect::<Vec<_>>()
+        );
+
+        // So is output that never ends a line.
+        let mut demux = Demux::default();
+        demux.push(&vec![b'a'; len]);
+        assert_eq!(super::MAX_FRAME_LEN, demux.next_frame().unwrap().1.len());
+        assert!(demux.next_frame().is_none());
+        assert_eq!(10, demux.buf.len());
+
+        demux.push(b"\n");
+        assert_eq!(11, demux.next_frame().unwrap().1.len());
+        assert!(demux.continues());
+    }
+}
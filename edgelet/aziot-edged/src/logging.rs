@@ -0,0 +1,178 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::Write;
+
+use edgelet_settings::logging::LogFormat;
+
+/// Environment variable that sets the log filter. This matches the other Azure IoT
+/// services, which use the `logger` crate.
+const LOG_LEVEL_ENV_VAR: &str = "AZIOT_LOG";
+
+/// Writes the records that pass the daemon's log filter, which can be changed while it
+/// runs. env_logger's own filter is fixed when the logger is built, so the inner logger
+/// writes every record it is given.
+struct Logger {
+    inner: env_logger::Logger,
+    filter: edgelet_http::LogFilter,
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        self.filter.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if self.filter.matches(record) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// The log filter from `AZIOT_LOG`. An invalid filter is reported once the logger is
+/// initialized, and the default is used instead.
+pub(crate) fn filter() -> (edgelet_http::LogFilter, Option<String>) {
+    match std::env::var(LOG_LEVEL_ENV_VAR) {
+        Ok(spec) => match edgelet_http::LogFilter::new(&spec) {
+            Ok(filter) => (filter, None),
+            Err(err) => (
+                edgelet_http::LogFilter::default(),
+                Some(format!("Ignoring {LOG_LEVEL_ENV_VAR}: {err}")),
+            ),
+        },
+        Err(_) => (edgelet_http::LogFilter::default(), None),
+    }
+}
+
+pub(crate) fn init(format: LogFormat, filter: edgelet_http::LogFilter) {
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(log::LevelFilter::Trace);
+
+    match format {
+        // Matches the `logger` crate: syslog priority prefixes, and targets only for
+        // debug messages.
+        LogFormat::Text => builder.format(|buf, record| {
+            let priority = match record.level() {
+                log::Level::Error => 3,
+                log::Level::Warn => 4,
+                log::Level::Info => 6,
+                log::Level::Debug | log::Level::Trace => 7,
+            };
+            let timestamp = buf.timestamp();
+
+            if record.level() >= log::Level::Debug {
+                writeln!(
+                    buf,
+                    "<{}>{} [{}] - [{}] {}",
+                    priority,
+                    timestamp,
+                    record.level(),
+                    record.target(),
+                    record.args()
+                )
+            } else {
+                writeln!(
+                    buf,
+                    "<{}>{} [{}] - {}",
+                    priority,
+                    timestamp,
+                    record.level(),
+                    record.args()
+                )
+            }
+        }),
+
+        LogFormat::Json => builder.format(|buf, record| writeln!(buf, "{}", json_record(record))),
+    };
+
+    let max_level = filter.max_level();
+    let logger = Logger {
+        inner: builder.build(),
+        filter,
+    };
+
+    log::set_boxed_logger(Box::new(logger))
+        .expect("cannot fail to initialize global logger from the process entrypoint");
+    log::set_max_level(max_level);
+}
+
+/// A record as a JSON object. Fields that the record carries, such as the `module` and
+/// `event` of module operations, are added to it, as is the correlation ID of the API
+/// request that the record was logged for.
+fn json_record(record: &log::Record<'_>) -> serde_json::Value {
+    struct Fields<'a>(&'a mut serde_json::Map<String, serde_json::Value>);
+
+    impl<'kvs> log::kv::Visitor<'kvs> for Fields<'_> {
+        fn visit_pair(
+            &mut self,
+            key: log::kv::Key<'kvs>,
+            value: log::kv::Value<'kvs>,
+        ) -> Result<(), log::kv::Error> {
+            self.0.insert(key.to_string(), value.to_string().into());
+
+            Ok(())
+        }
+    }
+
+    let mut line = serde_json::Map::new();
+    line.insert(
+        "time".to_string(),
+        chrono::Utc::now()
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+            .into(),
+    );
+    line.insert("level".to_string(), record.level().as_str().into());
+    line.insert("target".to_string(), record.target().into());
+    line.insert("message".to_string(), record.args().to_string().into());
+
+    // Fields can't fail to be visited.
+    let _ = record.key_values().visit(&mut Fields(&mut line));
+
+    if let Some(correlation_id) = edgelet_http::correlation_id() {
+        line.insert("correlation_id".to_string(), correlation_id.into());
+    }
+
+    serde_json::Value::Object(line)
+}
+
+/// Switch between debug logging and the configured filter on SIGUSR1, for when the
+/// management API can't be reached.
+pub(crate) fn set_signal_handler(filter: edgelet_http::LogFilter) {
+    let mut sigusr1_stream =
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined1())
+            .expect("cannot fail to set signal handler");
+
+    edgelet_core::tasks::spawn("log_level_signal", async move {
+        while sigusr1_stream.recv().await.is_some() {
+            log::info!("Received SIGUSR1; toggling debug logging");
+
+            filter.toggle_debug();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn json_record() {
+        let fields: &[(&str, &str)] = &[("module", "tempSensor"), ("event", "module.stop")];
+        let line = super::json_record(
+            &log::Record::builder()
+                .args(format_args!("Stopping module {}...", "tempSensor"))
+                .level(log::Level::Info)
+                .target("edgelet_docker::runtime")
+                .key_values(&fields)
+                .build(),
+        );
+
+        assert_eq!("INFO", line["level"]);
+        assert_eq!("edgelet_docker::runtime", line["target"]);
+        assert_eq!("Stopping module tempSensor...", line["message"]);
+        assert_eq!("tempSensor", line["module"]);
+        assert_eq!("module.stop", line["event"]);
+        assert!(line.get("correlation_id").is_none());
+    }
+}
@@ -3,9 +3,30 @@
 #![deny(rust_2018_idioms)]
 #![warn(clippy::all, clippy::pedantic)]
 
+mod alerts;
+mod change_feed;
+mod degraded;
+mod device_cache;
+mod disk_space;
+mod dump;
 mod error;
+mod fd_store;
+mod listener;
+mod log_persistence;
+mod log_sink;
+mod logging;
 mod management;
+mod mirrors;
+mod module_health;
+mod parent_monitor;
 mod provision;
+mod shim;
+mod site_overlay;
+mod standby;
+mod state;
+mod storage;
+mod stores;
+mod time_sync;
 mod watchdog;
 mod workload_manager;
 
@@ -22,45 +43,121 @@ use crate::{error::Error as EdgedError, workload_manager::WorkloadManager};
 async fn main() {
     let version = edgelet_core::version_with_source_version();
 
-    clap::Command::new(clap::crate_name!())
+    let archive = clap::Arg::new("archive")
+        .required(true)
+        .value_parser(clap::value_parser!(std::path::PathBuf));
+
+    let matches = clap::Command::new(clap::crate_name!())
         .version(&version)
         .author(clap::crate_authors!("\n"))
         .about(clap::crate_description!())
+        .subcommand(
+            clap::Command::new("export-state")
+                .about("Export the daemon's local state to an archive, to import on a replacement device")
+                .arg(archive.clone()),
+        )
+        .subcommand(
+            clap::Command::new("import-state")
+                .about("Import local state exported from another device. aziot-edged must be stopped.")
+                .arg(archive)
+                .arg(
+                    clap::Arg::new("force")
+                        .long("force")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Replace the state this device already has"),
+                ),
+        )
         .get_matches();
 
-    logger::try_init()
-        .expect("cannot fail to initialize global logger from the process entrypoint");
+    // Settings are read before initializing the logger so that the configured log format
+    // applies to all messages. Errors loading settings are reported by run().
+    let settings = edgelet_settings::docker::Settings::new();
+    let (log_filter, log_filter_err) = logging::filter();
+    logging::init(
+        settings
+            .as_ref()
+            .map_or_else(|_| Default::default(), RuntimeSettings::log_format),
+        log_filter.clone(),
+    );
+    if let Some(err) = log_filter_err {
+        log::warn!("{err}");
+    }
+
+    if let Some((command, args)) = matches.subcommand() {
+        if let Err(err) = run_command(command, args, settings).await {
+            log::error!("{err}");
+
+            std::process::exit(err.into());
+        }
+
+        return;
+    }
 
     log::info!("Starting Azure IoT Edge Daemon");
     log::info!("Version - {version}");
 
-    if let Err(err) = run().await {
+    logging::set_signal_handler(log_filter.clone());
+
+    let restarts = settings
+        .as_ref()
+        .map_or_else(|_| Default::default(), restart_history);
+    let diagnostics = edgelet_http::Diagnostics::default();
+    set_panic_hook(restarts.clone(), diagnostics.clone());
+
+    if let Err(err) = run(settings, restarts.clone(), diagnostics, log_filter).await {
         if err.exit_code() == EdgedError::reprovisioned().exit_code() {
             log::info!("{err}");
         } else {
             log::error!("{err}");
+
+            restarts.stop(edgelet_http::ShutdownReason::Error);
         }
 
         std::process::exit(err.into());
     }
 }
 
+async fn run_command(
+    command: &str,
+    args: &clap::ArgMatches,
+    settings: Result<edgelet_settings::docker::Settings, Box<dyn std::error::Error>>,
+) -> Result<(), EdgedError> {
+    let settings = settings.map_err(EdgedError::settings_err)?;
+    let archive = args
+        .get_one::<std::path::PathBuf>("archive")
+        .expect("archive is required");
+
+    match command {
+        "export-state" => state::export(&settings, archive).await,
+        "import-state" => state::import(&settings, archive, args.get_flag("force")).await,
+        _ => unreachable!("unknown subcommand {command}"),
+    }
+}
+
 #[allow(clippy::too_many_lines)]
-async fn run() -> Result<(), EdgedError> {
-    let settings = edgelet_settings::docker::Settings::new().map_err(EdgedError::settings_err)?;
+async fn run(
+    settings: Result<edgelet_settings::docker::Settings, Box<dyn std::error::Error>>,
+    restarts: edgelet_http::RestartHistory,
+    diagnostics: edgelet_http::Diagnostics,
+    log_filter: edgelet_http::LogFilter,
+) -> Result<(), EdgedError> {
+    let settings = settings.map_err(EdgedError::settings_err)?;
 
-    let cache_dir = std::path::Path::new(&settings.homedir()).join("cache");
-    std::fs::create_dir_all(cache_dir.clone()).map_err(|err| {
-        EdgedError::from_err(
-            format!(
-                "Failed to create cache directory {}",
-                cache_dir.as_path().display()
-            ),
-            err,
-        )
-    })?;
+    // The state files were usually already moved for the restart history, but failing
+    // to do so only stops the daemon here.
+    let state_dir = settings.storage().state_dir(settings.homedir());
+    storage::prepare_state_dir(settings.homedir(), &state_dir)?;
+
+    let settings = site_overlay::apply_cached(settings);
 
-    let mnt_dir = std::path::Path::new(&settings.homedir()).join("mnt");
+    // Checked before any listener consumes the sockets passed by systemd.
+    let warm_restarted = settings.warm_restart() && fd_store::restored();
+
+    let cache_dir = settings.storage().cache_dir(settings.homedir());
+    storage::prepare_dir("cache", &settings.homedir().join("cache"), &cache_dir)?;
+
+    // Workload sockets are recreated at every start, so they aren't moved.
+    let mnt_dir = settings.storage().mnt_dir(settings.homedir());
     std::fs::create_dir_all(&mnt_dir).map_err(|err| {
         EdgedError::from_err(
             format!(
@@ -70,28 +167,57 @@ async fn run() -> Result<(), EdgedError> {
             err,
         )
     })?;
+    diagnostics.set_mnt_dir(mnt_dir);
+
+    // Modules mount the time status directory when they are created, so it must be
+    // written before any module is.
+    if settings.inject_host_time() {
+        let time_dir = edgelet_core::host_time_dir(settings.homedir());
+
+        edgelet_core::TimeStatus::current()
+            .write_to(&time_dir)
+            .map_err(|err| {
+                EdgedError::from_err(
+                    format!(
+                        "Failed to write host time status to {}",
+                        time_dir.as_path().display()
+                    ),
+                    err,
+                )
+            })?;
+    }
 
-    let gc_dir = std::path::Path::new(&settings.homedir()).join("gc");
-    std::fs::create_dir_all(&gc_dir).map_err(|err| {
-        EdgedError::from_err(
-            format!(
-                "Failed to create gc directory {}",
-                gc_dir.as_path().display()
-            ),
-            err,
-        )
-    })?;
+    let gc_dir = settings.storage().gc_dir(settings.homedir());
+    storage::prepare_dir("gc", &settings.homedir().join("gc"), &gc_dir)?;
 
-    let identity_client = provision::identity_client(&settings)?;
+    let logs_dir = settings.storage().logs_dir(settings.homedir());
+    storage::prepare_dir("logs", &settings.homedir().join("logs"), &logs_dir)?;
 
-    let device_info = provision::get_device_info(
-        &identity_client,
-        settings.auto_reprovisioning_mode(),
-        &cache_dir,
-    )
-    .await?;
+    let stores = stores::load(
+        &settings,
+        &state_dir,
+        restarts.clone(),
+        diagnostics,
+        log_filter,
+    )?;
+
+    // Modules are run by an out-of-process runtime shim instead of the container engine.
+    if settings.moby_runtime().engine() == edgelet_settings::ContainerEngine::Shim {
+        return shim::run(&settings, stores, cache_dir, warm_restarted).await;
+    }
 
-    let (create_socket_channel_snd, create_socket_channel_rcv) =
+    let failures = stores.failures.clone();
+    let identity_health = stores.identity_health.clone();
+    let changes = stores.changes.clone();
+    let alerts = stores.alerts.clone();
+    let audit_log = stores.audit_log.clone();
+    let module_health = stores.module_health.clone();
+    let persisted_logs = stores.persisted_logs.clone();
+    let operations = stores.operations.clone();
+    let parent_health = stores.parent_health.clone();
+    let state_dump = stores.state_dump.clone();
+
+    let (create_socket_channel_snd, mut create_socket_channel_rcv) =
         tokio::sync::mpsc::unbounded_channel::<ModuleAction>();
 
     let gc_settings = settings.image_garbage_collection().clone();
@@ -105,10 +231,64 @@ async fn run() -> Result<(), EdgedError> {
         image_use_data.clone(),
     )
     .await
-    .map_err(|err| EdgedError::from_err("Failed to initialize module runtime", err))?;
+    .map_err(|err| {
+        let err = EdgedError::from_err("Failed to initialize module runtime", err);
+        failures.record(edgelet_http::FailureKind::ModuleRuntime, &err);
+
+        err
+    })?;
+
+    // The runtime is initialized before provisioning so that degraded mode modules can
+    // run while the device can't be provisioned.
+    let identity_client = provision::identity_client(&settings)?;
+    let device_cache = device_cache::DeviceCache::new(&settings, cache_dir.clone())?;
+
+    let (device_info, offline) = degraded::until_provisioned(
+        &settings,
+        &runtime,
+        &mut create_socket_channel_rcv,
+        &failures,
+        || {
+            provision::get_device_info(
+                &identity_client,
+                &identity_health,
+                settings.auto_reprovisioning_mode(),
+                settings.offline_start(),
+                &device_cache,
+            )
+        },
+    )
+    .await
+    .map_err(|err| {
+        failures.record(edgelet_http::FailureKind::Provisioning, &err);
+
+        err
+    })?;
+
+    site_overlay::refresh(&settings, &device_info).await?;
+
+    diagnostics.set_upstream(device_info.gateway_host.clone());
 
-    let (watchdog_tx, watchdog_rx) =
-        tokio::sync::mpsc::unbounded_channel::<edgelet_core::WatchdogAction>();
+    parent_monitor::start(&settings, &device_info, parent_health.clone())?;
+
+    runtime.set_device_variables(
+        &device_info.device_id.0,
+        &device_info.hub_name,
+        &device_info.gateway_host,
+    );
+
+    let (watchdog_tx, watchdog_rx) = edgelet_core::watchdog_channel();
+
+    if offline {
+        provision::reconcile(
+            &settings,
+            identity_health.clone(),
+            &device_info,
+            watchdog_tx.clone(),
+        )?;
+    }
+
+    provision::check_reprovision(&settings, &device_info, watchdog_tx.clone())?;
 
     // Keep track of running tasks to determine when all server tasks have shut down.
     // Workload and management API each have one task, so start with 2 tasks total.
@@ -123,7 +303,7 @@ async fn run() -> Result<(), EdgedError> {
         tasks.clone(),
         create_socket_channel_snd,
         watchdog_tx.clone(),
-        settings.iotedge_max_requests().workload,
+        &stores,
     )
     .await?;
 
@@ -134,34 +314,85 @@ async fn run() -> Result<(), EdgedError> {
     // descriptors for the workload and management APIs and calls on these APIs will
     // begin to fail. Resilient modules should be able to deal with this, but we'll
     // restart all modules to ensure a clean start.
-    log::info!("Stopping all modules...");
-    if let Err(err) = runtime
-        .stop_all(Some(std::time::Duration::from_secs(30)))
-        .await
-    {
-        log::warn!("Failed to stop modules on startup: {}", err);
+    //
+    // After a warm restart, modules are still listened to on the sockets they hold, so
+    // they are left running.
+    if warm_restarted {
+        log::info!("Warm restart; leaving modules running");
     } else {
-        log::info!("All modules stopped");
+        log::info!("Stopping all modules...");
+        if let Err(err) = runtime
+            .stop_all(Some(std::time::Duration::from_secs(30)))
+            .await
+        {
+            log::warn!("Failed to stop modules on startup: {}", err);
+        } else {
+            log::info!("All modules stopped");
+        }
     }
 
-    provision::update_device_cache(&cache_dir, &device_info, &runtime).await?;
+    provision::update_device_cache(&device_cache, &device_info, &runtime).await?;
 
     // Resolve the parent hostname used to pull Edge Agent. This translates '$upstream' into the
     // appropriate hostname.
     let settings = settings.agent_upstream_resolve(&device_info.gateway_host);
 
+    // Taken by the management API and the daemon's own tasks while they change modules.
+    let runtime_lock = std::sync::Arc::new(tokio::sync::Mutex::new(runtime.clone()));
+
     // Start management and workload sockets.
     let management_shutdown = management::start(
         &settings,
-        runtime.clone(),
+        runtime_lock.clone(),
         watchdog_tx.clone(),
+        stores,
+        workload_manager.service().clone(),
         tasks.clone(),
-        settings.iotedge_max_requests().management,
     )
     .await?;
 
+    let watchdog_status = watchdog::Status::new(watchdog_tx.clone());
+
+    dump::register(
+        &state_dump,
+        tasks.clone(),
+        watchdog_status.clone(),
+        workload_manager.listeners(),
+        runtime.clone(),
+        image_use_data.clone(),
+        operations.clone(),
+    );
+    dump::set_signal_handler(state_dump, state_dir.join("dump.json"));
+
     workload_manager::server(workload_manager, runtime.clone(), create_socket_channel_rcv).await?;
 
+    standby::start(&settings, runtime.clone());
+
+    degraded::watch_agent(&settings, runtime.clone());
+
+    disk_space::start(&settings, runtime.clone());
+
+    mirrors::start(&settings, runtime.clone());
+
+    module_health::start(
+        &settings,
+        runtime_lock,
+        module_health,
+        audit_log.clone(),
+        operations.clone(),
+    )
+    .await?;
+
+    log_sink::start(&settings, runtime.clone()).await?;
+
+    log_persistence::start(runtime.clone(), persisted_logs);
+
+    alerts::start(&settings, runtime.clone(), alerts, changes.clone())?;
+
+    change_feed::start(runtime.clone(), changes);
+
+    time_sync::start(&settings);
+
     // Set signal handlers for SIGTERM and SIGINT.
     set_signal_handlers(watchdog_tx);
 
@@ -172,7 +403,13 @@ async fn run() -> Result<(), EdgedError> {
         &device_info,
         runtime.clone(),
         &identity_client,
+        identity_health,
         watchdog_rx,
+        failures.clone(),
+        audit_log.clone(),
+        operations.clone(),
+        parent_health,
+        watchdog_status,
     );
 
     let edge_agent_bootstrap: String = settings.agent().config().image().to_string();
@@ -181,20 +418,46 @@ async fn run() -> Result<(), EdgedError> {
         gc_settings.clone(),
         &runtime,
         image_use_data,
+        audit_log.clone(),
+        operations.clone(),
     );
 
     tokio::select! {
         watchdog_finished = watchdog => {
             log::info!("watchdog finished");
             shutdown_reason = watchdog_finished?;
+            restarts.stop((&shutdown_reason).into());
         },
         image_gc_finished = image_gc => {
             let err_msg = "image garbage collection stopped unexpectedly";
-            image_gc_finished.map_err(|e| EdgedError::from_err(err_msg, e))?;
-            return Err(EdgedError::new(err_msg));
+            let err = match image_gc_finished {
+                Ok(()) => EdgedError::new(err_msg),
+                Err(e) => EdgedError::from_err(err_msg, e),
+            };
+            failures.record(edgelet_http::FailureKind::ImageGarbageCollection, &err);
+
+            return Err(err);
         }
     };
 
+    stop_servers(management_shutdown, workload_shutdown, &tasks).await;
+
+    finish_shutdown(
+        &shutdown_reason,
+        &identity_client,
+        &cache_dir,
+        &audit_log,
+        &operations,
+    )
+    .await
+}
+
+/// Stop the management and workload APIs and wait for their tasks to exit.
+async fn stop_servers(
+    management_shutdown: tokio::sync::oneshot::Sender<()>,
+    workload_shutdown: tokio::sync::oneshot::Sender<()>,
+    tasks: &atomic::AtomicUsize,
+) {
     log::info!("Stopping management API...");
     management_shutdown
         .send(())
@@ -225,27 +488,66 @@ async fn run() -> Result<(), EdgedError> {
         tokio::time::sleep(poll_period).await;
         wait_time += poll_period;
     }
+}
 
+/// Reprovision the device if that is why the daemon is shutting down.
+async fn finish_shutdown(
+    shutdown_reason: &WatchdogAction,
+    identity_client: &aziot_identity_client_async::Client,
+    cache_dir: &std::path::Path,
+    audit_log: &edgelet_http::AuditLog,
+    operations: &edgelet_http::Operations,
+) -> Result<(), EdgedError> {
     if let edgelet_core::WatchdogAction::Reprovision = shutdown_reason {
-        provision::reprovision(&identity_client, &cache_dir)
+        provision::reprovision(identity_client, cache_dir)
             .await
             .map_err(|err| EdgedError::from_err("Failed to reprovision", err))?;
 
         log::info!("Successfully reprovisioned");
 
+        audit_log.record(edgelet_http::AuditEvent::new(
+            edgelet_http::AuditEventType::Reprovision,
+        ));
+        operations.reprovisioned();
+
         Err(EdgedError::reprovisioned())
     } else {
         Ok(())
     }
 }
 
-fn set_signal_handlers(
-    shutdown_tx: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
-) {
+fn restart_history(settings: &edgelet_settings::docker::Settings) -> edgelet_http::RestartHistory {
+    let state_dir = settings.storage().state_dir(settings.homedir());
+
+    if let Err(err) = storage::prepare_state_dir(settings.homedir(), &state_dir) {
+        log::warn!("Failed to load restart history: {}", err);
+
+        return Default::default();
+    }
+
+    edgelet_http::RestartHistory::start(state_dir.join("restarts.json")).unwrap_or_else(|err| {
+        log::warn!("Failed to load restart history: {}", err);
+
+        Default::default()
+    })
+}
+
+fn set_panic_hook(restarts: edgelet_http::RestartHistory, diagnostics: edgelet_http::Diagnostics) {
+    let default_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        restarts.stop(edgelet_http::ShutdownReason::Panic);
+        diagnostics.panicked(info.to_string());
+
+        default_hook(info);
+    }));
+}
+
+fn set_signal_handlers(shutdown_tx: edgelet_core::WatchdogSender) {
     // Set the signal handler to listen for CTRL+C (SIGINT).
     let sigint_sender = shutdown_tx.clone();
 
-    tokio::spawn(async move {
+    edgelet_core::tasks::spawn("sigint", async move {
         tokio::signal::ctrl_c()
             .await
             .expect("cannot fail to set signal handler");
@@ -261,7 +563,7 @@ fn set_signal_handlers(
             .expect("cannot fail to set signal handler");
     let sigterm_sender = shutdown_tx;
 
-    tokio::spawn(async move {
+    edgelet_core::tasks::spawn("sigterm", async move {
         sigterm_stream.recv().await;
 
         // Failure to send the shutdown signal means that the mpsc queue is closed.
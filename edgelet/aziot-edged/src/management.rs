@@ -1,54 +1,336 @@
 // Copyright (c) Microsoft. All rights reserved.
 
-use edgelet_settings::uri::Listen;
+use edgelet_settings::uri::{Listen, ManagementTcp};
 
 use crate::error::Error as EdgedError;
+use crate::stores::Stores;
+
+const MANAGEMENT_TCP_CERT_ID: &str = "aziot-edged/management/server";
+
+/// The TCP listener's server certificate is reissued when it is this close to expiry.
+const CERT_RENEWAL_MARGIN_SECS: i64 = 24 * 60 * 60;
 
 pub(crate) async fn start<M>(
     settings: &impl edgelet_settings::RuntimeSettings,
-    runtime: M,
-    sender: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    sender: edgelet_core::WatchdogSender,
+    stores: Stores,
+    server_certs: edgelet_http_workload::Service<M>,
     tasks: std::sync::Arc<std::sync::atomic::AtomicUsize>,
-    max_requests: usize,
 ) -> Result<tokio::sync::oneshot::Sender<()>, EdgedError>
 where
     M: edgelet_core::ModuleRuntime + Clone + Send + Sync + 'static,
     <M as edgelet_core::ModuleRuntime>::Config: serde::de::DeserializeOwned + Sync,
 {
-    let socket = settings.listen().management_uri();
+    let max_requests = settings.iotedge_max_requests().management;
+
+    let socket_name = Listen::get_management_systemd_socket_name();
+    let mut incoming = crate::listener::bind(
+        settings.listen().management_uri(),
+        http_common::SOCKET_DEFAULT_PERMISSION,
+        max_requests,
+        Some(socket_name),
+    )
+    .await
+    .map_err(|err| EdgedError::from_err("Failed to listen on management socket", err))?;
 
-    let connector = http_common::Connector::new(socket)
-        .map_err(|err| EdgedError::from_err("Invalid management API URL", err))?;
+    let access_log = stores.access_log.clone();
+    let audit_log = stores.audit_log.clone();
 
     let service = edgelet_http_mgmt::Service::new(
         settings.endpoints().aziot_identityd_url(),
+        settings.endpoints().aziot_keyd_url(),
         runtime,
         sender,
+        stores,
     )
-    .map_err(|err| EdgedError::from_err("Invalid Identity Service URL", err))?;
+    .await
+    .map_err(|err| EdgedError::from_err("Invalid service endpoint", err))?;
 
-    let socket_name = Listen::get_management_systemd_socket_name();
-    let mut incoming = connector
-        .incoming(
-            http_common::SOCKET_DEFAULT_PERMISSION,
-            max_requests,
-            Some(socket_name),
+    let service = edgelet_http::CompressionService::new(edgelet_http::ApiVersionService::new(
+        edgelet_http::ETagService::new(service),
+    ));
+    let service = access_log.wrap("management", service);
+
+    let tcp_shutdown_tx = if let Some(management_tcp) = settings.listen().management_tcp() {
+        let listener = format!("tcp://{}", management_tcp.address);
+
+        Some(
+            start_tcp(
+                management_tcp.clone(),
+                settings.hostname().to_string(),
+                settings.tls_performance_mode(),
+                audit_log.wrap("management", listener, service.clone()),
+                server_certs,
+                max_requests,
+            )
+            .await?,
         )
-        .await
-        .map_err(|err| EdgedError::from_err("Failed to listen on management socket", err))?;
+    } else {
+        None
+    };
+
+    let listener = settings.listen().management_uri().to_string();
+    let service = audit_log.wrap("management", listener, service);
 
     let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
 
-    tokio::spawn(async move {
+    edgelet_core::tasks::spawn("management_api", async move {
         log::info!("Starting management API...");
 
         if let Err(err) = incoming.serve(service, shutdown_rx).await {
             log::error!("Failed to serve management socket: {}", err);
         }
 
+        if let Some(tcp_shutdown_tx) = tcp_shutdown_tx {
+            let _ = tcp_shutdown_tx.send(());
+        }
+
         tasks.fetch_sub(1, std::sync::atomic::Ordering::AcqRel);
         log::info!("Management API stopped");
     });
 
     Ok(shutdown_tx)
 }
+
+/// Serve the management API over TCP with mutual TLS.
+///
+/// Requests on this listener carry no caller PID, so routes that are restricted to
+/// Edge Agent aren't available on it.
+async fn start_tcp<M, S>(
+    settings: ManagementTcp,
+    hostname: String,
+    tls_performance_mode: edgelet_settings::TlsPerformanceMode,
+    service: S,
+    server_certs: edgelet_http_workload::Service<M>,
+    max_requests: usize,
+) -> Result<tokio::sync::oneshot::Sender<()>, EdgedError>
+where
+    M: edgelet_core::ModuleRuntime + Clone + Send + Sync + 'static,
+    S: hyper::service::Service<
+            hyper::Request<hyper::Body>,
+            Response = hyper::Response<hyper::Body>,
+            Error = std::convert::Infallible,
+        > + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    if settings.allowed_client_thumbprints.is_empty() {
+        log::warn!("No client thumbprints are allowed on the management TCP listener");
+    }
+
+    // Without AES instructions, ChaCha20-Poly1305 is several times cheaper than AES-GCM.
+    let prefer_chacha20 = edgelet_http_workload::crypto::performance_mode(tls_performance_mode)
+        && !edgelet_http_workload::crypto::CpuFeatures::detect().fast_aes_gcm();
+
+    let mut tls = ServerTls::new(&settings, &hostname, &server_certs, prefer_chacha20)
+        .await
+        .map_err(|err| EdgedError::from_err("Failed to set up management TCP listener", err))?;
+
+    let listener = tokio::net::TcpListener::bind(settings.address)
+        .await
+        .map_err(|err| EdgedError::from_err("Failed to listen on management TCP port", err))?;
+
+    let settings = std::sync::Arc::new(settings);
+    let connections = std::sync::Arc::new(tokio::sync::Semaphore::new(max_requests));
+
+    let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+
+    edgelet_core::tasks::spawn("management_api_remote", async move {
+        log::info!(
+            "Starting management API on TCP listener {}...",
+            settings.address
+        );
+
+        loop {
+            let (stream, peer) = tokio::select! {
+                _ = &mut shutdown_rx => break,
+
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        log::warn!("Failed to accept management TCP connection: {}", err);
+
+                        continue;
+                    }
+                },
+            };
+
+            if tls.expires_soon() {
+                log::info!("Renewing management TCP listener certificate...");
+
+                match ServerTls::new(&settings, &hostname, &server_certs, prefer_chacha20).await {
+                    Ok(renewed) => tls = renewed,
+                    Err(err) => log::warn!("Failed to renew management TCP certificate: {}", err),
+                }
+            }
+
+            let permit = if let Ok(permit) = connections.clone().try_acquire_owned() {
+                permit
+            } else {
+                log::warn!(
+                    "Rejecting management TCP connection from {}: too many connections",
+                    peer
+                );
+
+                continue;
+            };
+
+            let ssl = match openssl::ssl::Ssl::new(&tls.context) {
+                Ok(ssl) => ssl,
+                Err(err) => {
+                    log::warn!("Failed to create TLS session: {}", err);
+
+                    continue;
+                }
+            };
+
+            let settings = settings.clone();
+            let service = service.clone();
+
+            edgelet_core::tasks::spawn("management_api_remote::connection", async move {
+                let _permit = permit;
+
+                let mut stream = match tokio_openssl::SslStream::new(ssl, stream) {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        log::warn!("Failed to create TLS stream: {}", err);
+
+                        return;
+                    }
+                };
+
+                if let Err(err) = std::pin::Pin::new(&mut stream).accept().await {
+                    log::info!("TLS handshake with {} failed: {}", peer, err);
+
+                    return;
+                }
+
+                let allowed = stream
+                    .ssl()
+                    .peer_certificate()
+                    .and_then(|cert| cert.to_der().ok())
+                    .map_or(false, |cert| is_allowed_client(&settings, &cert));
+
+                if !allowed {
+                    log::warn!(
+                        "Rejecting management TCP connection from {}: client certificate is not allowed",
+                        peer
+                    );
+
+                    return;
+                }
+
+                if let Err(err) = hyper::server::conn::Http::new()
+                    .serve_connection(stream, service)
+                    .await
+                {
+                    log::info!("Management TCP connection from {} failed: {}", peer, err);
+                }
+            });
+        }
+
+        log::info!("Management API on TCP listener stopped");
+    });
+
+    Ok(shutdown_tx)
+}
+
+struct ServerTls {
+    context: openssl::ssl::SslContext,
+    not_after: openssl::asn1::Asn1Time,
+}
+
+impl ServerTls {
+    async fn new<M>(
+        settings: &ManagementTcp,
+        hostname: &str,
+        server_certs: &edgelet_http_workload::Service<M>,
+        prefer_chacha20: bool,
+    ) -> Result<Self, String>
+    where
+        M: edgelet_core::ModuleRuntime,
+    {
+        let mut names = vec![hostname.to_string()];
+        if !settings.address.ip().is_unspecified() {
+            names.push(settings.address.ip().to_string());
+        }
+
+        let (key, cert) = server_certs
+            .issue_server_cert(MANAGEMENT_TCP_CERT_ID, hostname, &names)
+            .await?;
+
+        Self::from_pem(&key, &cert, prefer_chacha20).map_err(|err| err.to_string())
+    }
+
+    fn from_pem(
+        key: &str,
+        cert: &str,
+        prefer_chacha20: bool,
+    ) -> Result<Self, openssl::error::ErrorStack> {
+        let key = openssl::pkey::PKey::private_key_from_pem(key.as_bytes())?;
+        let mut chain = openssl::x509::X509::stack_from_pem(cert.as_bytes())?.into_iter();
+
+        let cert = chain.next().ok_or_else(openssl::error::ErrorStack::get)?;
+        let not_after = cert.not_after().to_owned();
+
+        let mut acceptor = openssl::ssl::SslAcceptor::mozilla_intermediate_v5(
+            openssl::ssl::SslMethod::tls_server(),
+        )?;
+        acceptor.set_private_key(&key)?;
+        acceptor.set_certificate(&cert)?;
+        for cert in chain {
+            acceptor.add_extra_chain_cert(cert)?;
+        }
+        acceptor.check_private_key()?;
+
+        if prefer_chacha20 {
+            acceptor.set_ciphersuites(
+                "TLS_CHACHA20_POLY1305_SHA256:TLS_AES_128_GCM_SHA256:TLS_AES_256_GCM_SHA384",
+            )?;
+            acceptor.set_cipher_list(
+                "ECDHE-ECDSA-CHACHA20-POLY1305:ECDHE-RSA-CHACHA20-POLY1305:\
+                 ECDHE-ECDSA-AES128-GCM-SHA256:ECDHE-RSA-AES128-GCM-SHA256:\
+                 ECDHE-ECDSA-AES256-GCM-SHA384:ECDHE-RSA-AES256-GCM-SHA384",
+            )?;
+            acceptor.set_options(openssl::ssl::SslOptions::CIPHER_SERVER_PREFERENCE);
+        }
+
+        // Client certificates are pinned by thumbprint after the handshake rather than
+        // validated against a CA, so any certificate is accepted here.
+        acceptor.set_verify_callback(
+            openssl::ssl::SslVerifyMode::PEER | openssl::ssl::SslVerifyMode::FAIL_IF_NO_PEER_CERT,
+            |_, _| true,
+        );
+
+        Ok(ServerTls {
+            context: acceptor.build().into_context(),
+            not_after,
+        })
+    }
+
+    fn expires_soon(&self) -> bool {
+        openssl::asn1::Asn1Time::from_unix(
+            chrono::Utc::now().timestamp() + CERT_RENEWAL_MARGIN_SECS,
+        )
+        .map_or(true, |renew_at| self.not_after <= renew_at)
+    }
+}
+
+fn is_allowed_client(settings: &ManagementTcp, cert_der: &[u8]) -> bool {
+    let thumbprint: String = openssl::sha::sha256(cert_der)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    settings.allowed_client_thumbprints.iter().any(|allowed| {
+        let allowed: String = allowed
+            .chars()
+            .filter(|c| *c != ':')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+
+        allowed == thumbprint
+    })
+}
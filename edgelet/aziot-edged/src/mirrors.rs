@@ -0,0 +1,27 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Periodically check the configured registry mirrors, so that pulls try the mirrors
+/// that are up first.
+pub(crate) fn start(
+    settings: &edgelet_settings::docker::Settings,
+    runtime: edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+) {
+    let image_pull = settings.moby_runtime().image_pull();
+
+    if image_pull.mirrors().is_empty() || image_pull.mirror_check_interval().is_zero() {
+        return;
+    }
+
+    let check_interval = image_pull.mirror_check_interval();
+
+    edgelet_core::tasks::spawn("mirrors", async move {
+        let mut timer = tokio::time::interval(check_interval);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            timer.tick().await;
+
+            runtime.check_mirrors().await;
+        }
+    });
+}
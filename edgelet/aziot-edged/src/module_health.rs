@@ -0,0 +1,244 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::{Module, ModuleRuntime, ModuleStatus};
+use edgelet_settings::watchdog::Probe;
+
+use crate::error::Error as EdgedError;
+
+/// How often running modules are probed.
+const PROBE_PERIOD: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Restart running modules that stay unhealthy for longer than the configured grace period.
+///
+/// Restarts take the runtime lock that the management API holds while it changes
+/// modules, so that a restart doesn't interleave with an update from Edge Agent.
+pub(crate) async fn start(
+    settings: &impl edgelet_settings::RuntimeSettings,
+    runtime_lock: std::sync::Arc<
+        tokio::sync::Mutex<edgelet_docker::DockerModuleRuntime<http_common::Connector>>,
+    >,
+    module_health: edgelet_http::ModuleHealth,
+    audit_log: edgelet_http::AuditLog,
+    operations: edgelet_http::Operations,
+) -> Result<(), EdgedError> {
+    let settings = settings.watchdog().module_health().clone();
+
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    for (module, probe) in &settings.probes {
+        if let Probe::Http { url, .. } = probe {
+            if url.scheme() != "http" {
+                return Err(EdgedError::new(format!(
+                    "Health probe {} of module {} must be an http URL",
+                    url, module
+                )));
+            }
+        }
+    }
+
+    log::info!(
+        "Restarting modules that are unhealthy for {:?}",
+        settings.grace_period
+    );
+
+    // Modules are listed and probed without the lock.
+    let runtime = runtime_lock.lock().await.clone();
+
+    edgelet_core::tasks::spawn("module_health", async move {
+        let mut timer = tokio::time::interval(PROBE_PERIOD);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            timer.tick().await;
+
+            let modules = match runtime.list_with_details().await {
+                Ok(modules) => modules,
+                Err(err) => {
+                    log::warn!("Failed to list modules for health probes: {}", err);
+                    continue;
+                }
+            };
+
+            module_health.retain(
+                &modules
+                    .iter()
+                    .map(|(module, _)| module.name().to_string())
+                    .collect(),
+            );
+
+            for (module, state) in &modules {
+                if *state.status() != ModuleStatus::Running {
+                    continue;
+                }
+
+                let name = module.name();
+
+                let result = match probe(&runtime, name, settings.probes.get(name)).await {
+                    Ok(Some(result)) => result,
+
+                    // The module has no healthcheck, or it hasn't finished starting.
+                    Ok(None) => continue,
+
+                    Err(err) => {
+                        log::debug!("Failed to probe module {}: {}", name, err);
+                        continue;
+                    }
+                };
+
+                let message = result.message.clone();
+
+                if !module_health.record(name, result, settings.grace_period) {
+                    continue;
+                }
+
+                log::warn!(
+                    "Module {} has been unhealthy for {:?}, restarting it",
+                    name,
+                    settings.grace_period
+                );
+
+                if let Err(err) = runtime_lock.lock().await.restart(name).await {
+                    log::warn!("Failed to restart unhealthy module {}: {}", name, err);
+                    continue;
+                }
+
+                module_health.restarted(name);
+
+                let mut event =
+                    edgelet_http::AuditEvent::new(edgelet_http::AuditEventType::WatchdogRestart)
+                        .with_module(name);
+                if let Some(message) = message {
+                    event = event.with_details(format!("unhealthy: {}", message));
+                }
+                audit_log.record(event);
+                operations.unhealthy_restart();
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Probe a module with its HTTP probe if it has one, or else with its Docker healthcheck.
+/// Returns `None` if there's no result yet.
+async fn probe(
+    runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    module: &str,
+    probe: Option<&Probe>,
+) -> Result<Option<edgelet_http::ProbeResult>, String> {
+    if let Some(Probe::Http { url, timeout }) = probe {
+        let url = module_url(runtime, module, url).await?;
+
+        let result = match tokio::time::timeout(*timeout, get(&url)).await {
+            Ok(Ok(())) => {
+                edgelet_http::ProbeResult::new(edgelet_http::ProbeSource::Http, true, None)
+            }
+            Ok(Err(err)) => {
+                edgelet_http::ProbeResult::new(edgelet_http::ProbeSource::Http, false, Some(err))
+            }
+            Err(_) => edgelet_http::ProbeResult::new(
+                edgelet_http::ProbeSource::Http,
+                false,
+                Some("probe timed out".to_string()),
+            ),
+        };
+
+        return Ok(Some(result));
+    }
+
+    let health = runtime
+        .health(module)
+        .await
+        .map_err(|err| format!("{:#}", err))?;
+
+    let result =
+        match health {
+            Some((status, output)) if status == "healthy" => Some(edgelet_http::ProbeResult::new(
+                edgelet_http::ProbeSource::Docker,
+                true,
+                output,
+            )),
+            Some((status, output)) if status == "unhealthy" => Some(
+                edgelet_http::ProbeResult::new(edgelet_http::ProbeSource::Docker, false, output),
+            ),
+            _ => None,
+        };
+
+    Ok(result)
+}
+
+/// Probes are sent from the host, which can't resolve module names and whose loopback
+/// interface isn't the module's. A probe of the module by either name is sent to the
+/// module's address on its network instead.
+async fn module_url(
+    runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    module: &str,
+    url: &url::Url,
+) -> Result<url::Url, String> {
+    let refers_to_module = match url.host() {
+        Some(url::Host::Domain(host)) => host == "localhost" || host == module,
+        Some(url::Host::Ipv4(address)) => address.is_loopback(),
+        Some(url::Host::Ipv6(address)) => address.is_loopback(),
+        None => false,
+    };
+    if !refers_to_module {
+        return Ok(url.clone());
+    }
+
+    // Modules on the host network share the host's loopback interface.
+    let Some(address) = runtime
+        .module_address(module)
+        .await
+        .map_err(|err| format!("{:#}", err))?
+    else {
+        return Ok(url.clone());
+    };
+
+    let mut url = url.clone();
+    url.set_ip_host(address)
+        .map_err(|()| format!("could not set address of {url}"))?;
+
+    Ok(url)
+}
+
+async fn get(url: &url::Url) -> Result<(), String> {
+    let host = url.host_str().ok_or("URL has no host")?;
+    let port = url.port_or_known_default().ok_or("URL has no port")?;
+
+    // IPv6 hosts keep their brackets in the URL.
+    let stream =
+        tokio::net::TcpStream::connect((host.trim_start_matches('[').trim_end_matches(']'), port))
+            .await
+            .map_err(|err| err.to_string())?;
+
+    let (mut sender, connection) = hyper::client::conn::handshake(stream)
+        .await
+        .map_err(|err| err.to_string())?;
+    edgelet_core::tasks::spawn("module_health::probe_connection", async move {
+        if let Err(err) = connection.await {
+            log::debug!("Health probe connection closed: {}", err);
+        }
+    });
+
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+
+    let request = hyper::Request::get(path)
+        .header(hyper::header::HOST, host)
+        .body(hyper::Body::empty())
+        .map_err(|err| err.to_string())?;
+    let response = sender
+        .send_request(request)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if response.status().is_success() || response.status().is_redirection() {
+        Ok(())
+    } else {
+        Err(format!("endpoint responded with {}", response.status()))
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_settings::RuntimeSettings;
+
+use crate::error::Error as EdgedError;
+
+enum Failure {
+    Unreachable(String),
+    Untrusted(String),
+}
+
+/// Periodically connect to the parent of a nested device over TLS, trusting the device's
+/// trust bundle as modules do, and record whether it could be reached.
+///
+/// Devices that connect to IoT Hub directly have no parent, so nothing is checked.
+pub(crate) fn start(
+    settings: &edgelet_settings::docker::Settings,
+    device_info: &aziot_identity_common::AzureIoTSpec,
+    parent_health: edgelet_http::ParentHealth,
+) -> Result<(), EdgedError> {
+    let monitor = settings.watchdog().parent().clone();
+
+    let parent = device_info.gateway_host.clone();
+    if !monitor.enabled || parent.eq_ignore_ascii_case(&device_info.hub_name) {
+        return Ok(());
+    }
+
+    let cert_connector = http_common::Connector::new(settings.endpoints().aziot_certd_url())
+        .map_err(|err| EdgedError::from_err("Invalid Certificates Service URL", err))?;
+    let cert_client = aziot_cert_client_async::Client::new(
+        aziot_cert_common_http::ApiVersion::V2020_09_01,
+        cert_connector,
+        1,
+    );
+
+    let trust_bundle = settings
+        .trust_bundle_cert()
+        .unwrap_or(edgelet_settings::TRUST_BUNDLE_ALIAS)
+        .to_string();
+
+    parent_health.set_parent(&parent);
+
+    edgelet_core::tasks::spawn("parent_monitor", async move {
+        log::info!("Monitoring connectivity to parent {}", parent);
+
+        let mut timer = tokio::time::interval(monitor.check_interval);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            timer.tick().await;
+
+            // The trust bundle is fetched for every check, so that a parent whose CA
+            // was added to it becomes trusted without a restart.
+            let trust_bundle = match cert_client.get_cert(&trust_bundle).await {
+                Ok(trust_bundle) => trust_bundle,
+                Err(err) => {
+                    log::warn!("Could not get trust bundle to check parent: {}", err);
+
+                    continue;
+                }
+            };
+
+            match check(&trust_bundle, &parent, monitor.timeout).await {
+                Ok(()) => parent_health.reachable(),
+                Err(Failure::Unreachable(err)) => parent_health.unreachable(&err),
+                Err(Failure::Untrusted(err)) => parent_health.untrusted(&err),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+async fn check(
+    trust_bundle: &[u8],
+    parent: &str,
+    timeout: std::time::Duration,
+) -> Result<(), Failure> {
+    let ssl = crate::site_overlay::client_tls(trust_bundle, parent)
+        .map_err(|err| Failure::Untrusted(format!("invalid trust bundle: {err}")))?;
+
+    let stream =
+        match tokio::time::timeout(timeout, tokio::net::TcpStream::connect((parent, 443))).await {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(err)) => return Err(Failure::Unreachable(err.to_string())),
+            Err(_) => return Err(Failure::Unreachable("connection timed out".to_string())),
+        };
+
+    let mut stream = tokio_openssl::SslStream::new(ssl, stream)
+        .map_err(|err| Failure::Unreachable(err.to_string()))?;
+
+    match tokio::time::timeout(timeout, std::pin::Pin::new(&mut stream).connect()).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(err)) => {
+            let verify_result = stream.ssl().verify_result();
+
+            if verify_result == openssl::x509::X509VerifyResult::OK {
+                Err(Failure::Unreachable(format!("TLS handshake failed: {err}")))
+            } else {
+                Err(Failure::Untrusted(format!(
+                    "certificate verification failed: {}",
+                    verify_result.error_string()
+                )))
+            }
+        }
+        Err(_) => Err(Failure::Unreachable("TLS handshake timed out".to_string())),
+    }
+}
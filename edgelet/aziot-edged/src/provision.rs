@@ -2,6 +2,7 @@
 
 use sha2::Digest;
 
+use crate::device_cache::DeviceCache;
 use crate::error::Error as EdgedError;
 
 pub(crate) fn identity_client(
@@ -20,37 +21,81 @@ pub(crate) fn identity_client(
     Ok(identity_client)
 }
 
+/// First delay between attempts to get the device identity. It doubles after each
+/// failed attempt, up to `MAX_RETRY_DELAY`.
+const MIN_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+const MAX_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Number of times Identity Service may answer that it has no device identity, despite
+/// reprovisioning in between, before provisioning is considered to have failed.
+const MAX_PROVISIONING_ATTEMPTS: u32 = 5;
+
+/// Shortest allowed interval between reprovision checks, so that a fleet configured
+/// with a short interval doesn't overwhelm DPS.
+const MIN_REPROVISION_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Cached provisioning state: the digest of the device's identity.
+const PROVISIONING_STATE: &str = "provisioning_state";
+
+/// Cached device identity, used to start offline.
+const DEVICE_INFO: &str = "device_info.json";
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct CachedDevice {
+    device_id: String,
+    gateway_host: String,
+    hub_name: String,
+}
+
+/// Get the device identity from Identity Service, and whether it is the identity cached
+/// from the last successful provisioning.
+///
+/// While Identity Service is unreachable, e.g. because it is still starting, this
+/// retries indefinitely rather than failing, since restarting the daemon wouldn't help.
+/// If Identity Service is reachable but can't provide the device identity, the cached
+/// identity is used with `offline_start`. Otherwise the device is reprovisioned and this
+/// fails after `MAX_PROVISIONING_ATTEMPTS`.
 pub(crate) async fn get_device_info(
     identity_client: &aziot_identity_client_async::Client,
+    identity_health: &edgelet_http::IdentityHealth,
     auto_reprovisioning_mode: edgelet_settings::aziot::AutoReprovisioningMode,
-    cache_dir: &std::path::Path,
-) -> Result<aziot_identity_common::AzureIoTSpec, EdgedError> {
+    offline_start: bool,
+    cache: &DeviceCache,
+) -> Result<(aziot_identity_common::AzureIoTSpec, bool), EdgedError> {
     if let edgelet_settings::aziot::AutoReprovisioningMode::AlwaysOnStartup =
         auto_reprovisioning_mode
     {
-        reprovision(identity_client, cache_dir)
+        reprovision(identity_client, cache.dir())
             .await
             .map_err(|err| EdgedError::from_err("Reprovision on startup failed: {}", err))?;
     }
 
+    let mut retry_delay = MIN_RETRY_DELAY;
+    let mut provisioning_attempts = 0;
+
     loop {
         log::info!("Obtaining Edge device provisioning data...");
 
         match identity_client.get_device_identity().await {
             Ok(device_info) => match device_info {
                 aziot_identity_common::Identity::Aziot(device_info) => {
+                    identity_health.available();
+
                     log::info!(
                         "Device is {} on {}",
                         device_info.device_id.0,
                         device_info.hub_name
                     );
 
-                    return Ok(device_info);
+                    return Ok((device_info, false));
                 }
                 aziot_identity_common::Identity::Local(..) => {
                     // Identity Service should never return an invalid device identity.
                     // Treat this as a fatal error.
-                    return Err(EdgedError::new("Invalid device identity"));
+                    let err = EdgedError::new("Invalid device identity");
+                    identity_health.provisioning_failed(&err);
+
+                    return Err(err);
                 }
             },
             Err(err) => {
@@ -61,48 +106,243 @@ pub(crate) async fn get_device_info(
                 // permission errors should not trigger a reprovision, as these generally mean
                 // that Identity Service has not yet fully started.
                 if err.kind() == std::io::ErrorKind::Other {
+                    // Reprovisioning clears the cache, so it is checked first.
+                    let cached = if offline_start {
+                        cached_device_info(cache).await
+                    } else {
+                        None
+                    };
+
+                    if let Some(device_info) = cached {
+                        identity_health.cached(&err);
+
+                        log::warn!(
+                            "Starting offline as {} on {} from the last successful provisioning",
+                            device_info.device_id.0,
+                            device_info.hub_name
+                        );
+
+                        return Ok((device_info, true));
+                    }
+
+                    identity_health.provisioning_failed(&err);
+
+                    provisioning_attempts += 1;
+                    if provisioning_attempts >= MAX_PROVISIONING_ATTEMPTS {
+                        return Err(EdgedError::from_err("Device provisioning failed", err));
+                    }
+
                     log::info!("Requesting device reprovision");
 
-                    if let Err(err) = reprovision(identity_client, cache_dir).await {
+                    if let Err(err) = reprovision(identity_client, cache.dir()).await {
                         log::warn!("Failed to reprovision: {}", err);
                     }
                 } else {
+                    identity_health.unavailable(&err);
+
                     log::warn!(
-                        "Identity Service was unreachable. Waiting for Identity Service startup."
+                        "Identity Service was unreachable. Waiting {} seconds for Identity Service startup.",
+                        retry_delay.as_secs()
                     );
                 }
 
-                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                tokio::time::sleep(retry_delay).await;
+                retry_delay = std::cmp::min(retry_delay * 2, MAX_RETRY_DELAY);
             }
         }
     }
 }
 
-pub(crate) async fn update_device_cache(
-    cache_dir: &std::path::Path,
+/// Wait for provisioning to succeed after starting offline with the cached identity.
+///
+/// Identity Service is asked to reprovision whenever it answers that the device isn't
+/// provisioned, without clearing the cache so that the next start can still be offline.
+/// If the device identity turns out to have changed, the daemon reprovisions so that
+/// modules are recreated for the new identity.
+pub(crate) fn reconcile(
+    settings: &impl edgelet_settings::RuntimeSettings,
+    identity_health: edgelet_http::IdentityHealth,
     device_info: &aziot_identity_common::AzureIoTSpec,
-    runtime: &impl edgelet_core::ModuleRuntime,
+    watchdog_tx: edgelet_core::WatchdogSender,
 ) -> Result<(), EdgedError> {
-    log::info!("Detecting if device information has changed...");
+    let identity_client = identity_client(settings)?;
+    let cached_device = device_digest(device_info);
+
+    edgelet_core::tasks::spawn("provision::reconcile", async move {
+        let mut retry_delay = MIN_RETRY_DELAY;
+
+        loop {
+            tokio::time::sleep(retry_delay).await;
+            retry_delay = std::cmp::min(retry_delay * 2, MAX_RETRY_DELAY);
+
+            match identity_client.get_device_identity().await {
+                Ok(aziot_identity_common::Identity::Aziot(device_info)) => {
+                    if device_digest(&device_info) == cached_device {
+                        identity_health.available();
+
+                        log::info!("Device provisioned with the identity it started offline with");
+                    } else {
+                        log::warn!(
+                            "Device provisioned as {} on {}, which differs from the identity it started offline with; reprovisioning",
+                            device_info.device_id.0,
+                            device_info.hub_name
+                        );
+
+                        let _ = watchdog_tx.send(edgelet_core::WatchdogAction::Reprovision);
+                    }
+
+                    return;
+                }
+                Ok(aziot_identity_common::Identity::Local(..)) => {
+                    log::warn!("Identity Service returned an invalid device identity");
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::Other => {
+                    identity_health.cached(&err);
+
+                    if let Err(err) = identity_client.reprovision().await {
+                        log::debug!("Failed to reprovision while offline: {}", err);
+                    }
+                }
+                Err(err) => identity_health.unavailable(&err),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Periodically ask Identity Service to reprovision, so that the device picks up a
+/// changed DPS assignment, e.g. after its IoT hub was migrated.
+///
+/// Identity Service keeps the device identity if DPS assigns it to the same hub, so the
+/// daemon reprovisions, recreating modules, only if the identity changed.
+pub(crate) fn check_reprovision(
+    settings: &impl edgelet_settings::RuntimeSettings,
+    device_info: &aziot_identity_common::AzureIoTSpec,
+    watchdog_tx: edgelet_core::WatchdogSender,
+) -> Result<(), EdgedError> {
+    let Some(mut interval) = settings.reprovision_check_interval() else {
+        return Ok(());
+    };
+
+    if interval < MIN_REPROVISION_CHECK_INTERVAL {
+        log::warn!(
+            "Reprovision check interval is shorter than {} seconds; using {} seconds",
+            MIN_REPROVISION_CHECK_INTERVAL.as_secs(),
+            MIN_REPROVISION_CHECK_INTERVAL.as_secs()
+        );
+
+        interval = MIN_REPROVISION_CHECK_INTERVAL;
+    }
 
-    let cache_path = cache_dir.join("provisioning_state");
-
-    let cached_device = match std::fs::read_to_string(cache_path.clone()) {
-        Ok(cache) => cache,
-        Err(err) => match err.kind() {
-            std::io::ErrorKind::NotFound => String::default(),
-            _ => {
-                return Err(EdgedError::from_err(
-                    "Failed to read cached provisioning state",
-                    err,
-                ));
+    let identity_client = identity_client(settings)?;
+    let current_device = device_digest(device_info);
+
+    edgelet_core::tasks::spawn("provision::reprovision_check", async move {
+        loop {
+            tokio::time::sleep(interval).await;
+
+            log::info!("Checking device provisioning...");
+
+            if let Err(err) = identity_client.reprovision().await {
+                log::warn!("Failed to check device provisioning: {}", err);
+
+                continue;
             }
-        },
+
+            match identity_client.get_device_identity().await {
+                Ok(aziot_identity_common::Identity::Aziot(device_info)) => {
+                    if device_digest(&device_info) == current_device {
+                        log::info!("Device provisioning has not changed");
+                    } else {
+                        log::info!(
+                            "Device was provisioned as {} on {}; reprovisioning",
+                            device_info.device_id.0,
+                            device_info.hub_name
+                        );
+
+                        let _ = watchdog_tx.send(edgelet_core::WatchdogAction::Reprovision);
+
+                        return;
+                    }
+                }
+                Ok(aziot_identity_common::Identity::Local(..)) => {
+                    log::warn!("Identity Service returned an invalid device identity");
+                }
+                Err(err) => log::warn!("Failed to obtain device identity: {}", err),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// The device identity from the last successful provisioning, if it is still valid.
+async fn cached_device_info(cache: &DeviceCache) -> Option<aziot_identity_common::AzureIoTSpec> {
+    let (provisioning_state, device) = match (
+        cache.read(PROVISIONING_STATE).await,
+        cache.read(DEVICE_INFO).await,
+    ) {
+        (Ok(Some(provisioning_state)), Ok(Some(device))) => (provisioning_state, device),
+        (Err(err), _) | (_, Err(err)) => {
+            log::warn!("Failed to read cached device identity: {}", err);
+
+            return None;
+        }
+        _ => return None,
+    };
+
+    let device: CachedDevice = match serde_json::from_slice(&device) {
+        Ok(device) => device,
+        Err(err) => {
+            log::warn!("Ignoring invalid cached device identity: {}", err);
+
+            return None;
+        }
     };
 
+    let device_info = aziot_identity_common::AzureIoTSpec {
+        hub_name: device.hub_name,
+        gateway_host: device.gateway_host,
+        device_id: aziot_identity_common::DeviceId(device.device_id),
+        module_id: None,
+        gen_id: None,
+        auth: None,
+    };
+
+    // The identity must be the one that the modules on the device were created for.
+    if device_digest(&device_info).as_bytes() == provisioning_state {
+        Some(device_info)
+    } else {
+        None
+    }
+}
+
+pub(crate) async fn update_device_cache(
+    cache: &DeviceCache,
+    device_info: &aziot_identity_common::AzureIoTSpec,
+    runtime: &impl edgelet_core::ModuleRuntime,
+) -> Result<(), EdgedError> {
+    log::info!("Detecting if device information has changed...");
+
     let current_device = device_digest(device_info);
 
-    if current_device == cached_device {
+    // Earlier versions cached the digest in plaintext.
+    cache
+        .migrate(PROVISIONING_STATE, current_device.as_bytes())
+        .await
+        .map_err(|err| EdgedError::from_err("Failed to encrypt provisioning cache", err))?;
+
+    // A missing cache means that no modules were created for this device yet. Any cache
+    // that can't be read is an error rather than a device change, since treating it as one
+    // would remove every module.
+    let cached_device = cache
+        .read(PROVISIONING_STATE)
+        .await
+        .map_err(|err| EdgedError::from_err("Failed to read cached provisioning state", err))?
+        .unwrap_or_default();
+
+    if current_device.as_bytes() == cached_device {
         log::info!("Device information has not changed");
     } else {
         log::info!("Change to device information detected");
@@ -115,12 +355,24 @@ pub(crate) async fn update_device_cache(
         log::info!("Removed all modules");
 
         log::info!("Updating cached device information");
-        std::fs::create_dir_all(cache_dir)
-            .map_err(|err| EdgedError::from_err("Failed to create cache directory", err))?;
-        std::fs::write(cache_path, current_device)
+        cache
+            .write(PROVISIONING_STATE, current_device.as_bytes())
+            .await
             .map_err(|err| EdgedError::from_err("Failed to save provisioning cache", err))?;
     }
 
+    let device = CachedDevice {
+        device_id: device_info.device_id.0.clone(),
+        gateway_host: device_info.gateway_host.clone(),
+        hub_name: device_info.hub_name.clone(),
+    };
+    let device = serde_json::to_vec(&device)
+        .map_err(|err| EdgedError::from_err("Failed to save device identity cache", err))?;
+    cache
+        .write(DEVICE_INFO, &device)
+        .await
+        .map_err(|err| EdgedError::from_err("Failed to save device identity cache", err))?;
+
     Ok(())
 }
 
@@ -0,0 +1,306 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Running modules through an out-of-process runtime shim, when `moby_runtime.engine`
+//! is "shim".
+//!
+//! The management and workload APIs work as they do with a container engine, and Edge
+//! Agent deploys and supervises the other modules through them. The daemon's own
+//! background tasks that inspect containers directly, such as image garbage collection,
+//! module health checks and log persistence, don't run.
+
+use std::sync::atomic;
+
+use edgelet_core::{module::ModuleAction, ModuleRegistry, ModuleRuntime, WatchdogAction};
+use edgelet_settings::RuntimeSettings;
+
+use crate::error::Error as EdgedError;
+use crate::stores::Stores;
+use crate::workload_manager::WorkloadManager;
+
+/// How often Edge Agent is checked, and created or started if it isn't running.
+const AGENT_CHECK_PERIOD: std::time::Duration = std::time::Duration::from_secs(60);
+
+pub(crate) async fn run(
+    settings: &edgelet_settings::docker::Settings,
+    stores: Stores,
+    cache_dir: std::path::PathBuf,
+    warm_restarted: bool,
+) -> Result<(), EdgedError> {
+    log::info!(
+        "Running modules through the runtime shim at {}",
+        settings.moby_runtime().uri()
+    );
+
+    let runtime = edgelet_shim::ShimModuleRuntime::new(settings.moby_runtime().uri())
+        .map_err(|err| EdgedError::from_err("Failed to initialize module runtime", err))?;
+
+    let failures = stores.failures.clone();
+    let identity_health = stores.identity_health.clone();
+    let audit_log = stores.audit_log.clone();
+    let operations = stores.operations.clone();
+    let restarts = stores.restarts.clone();
+    let alerts = stores.alerts.clone();
+    let changes = stores.changes.clone();
+
+    let identity_client = crate::provision::identity_client(settings)?;
+    let device_cache = crate::device_cache::DeviceCache::new(settings, cache_dir.clone())?;
+
+    let (device_info, _) = crate::provision::get_device_info(
+        &identity_client,
+        &identity_health,
+        settings.auto_reprovisioning_mode(),
+        settings.offline_start(),
+        &device_cache,
+    )
+    .await
+    .map_err(|err| {
+        failures.record(edgelet_http::FailureKind::Provisioning, &err);
+
+        err
+    })?;
+
+    let (create_socket_channel_snd, create_socket_channel_rcv) =
+        tokio::sync::mpsc::unbounded_channel::<ModuleAction>();
+    let (watchdog_tx, watchdog_rx) = edgelet_core::watchdog_channel();
+
+    crate::provision::check_reprovision(settings, &device_info, watchdog_tx.clone())?;
+
+    // Workload and management API each have one task.
+    let tasks = std::sync::Arc::new(atomic::AtomicUsize::new(2));
+
+    let (workload_manager, workload_shutdown) = WorkloadManager::start(
+        settings,
+        runtime.clone(),
+        &device_info,
+        tasks.clone(),
+        create_socket_channel_snd,
+        watchdog_tx.clone(),
+        &stores,
+    )
+    .await?;
+
+    // Modules left running by a crash hold stale workload sockets, as with a container
+    // engine.
+    if warm_restarted {
+        log::info!("Warm restart; leaving modules running");
+    } else {
+        log::info!("Stopping all modules...");
+        if let Err(err) = runtime
+            .stop_all(Some(std::time::Duration::from_secs(30)))
+            .await
+        {
+            log::warn!("Failed to stop modules on startup: {}", err);
+        } else {
+            log::info!("All modules stopped");
+        }
+    }
+
+    crate::provision::update_device_cache(&device_cache, &device_info, &runtime).await?;
+
+    let settings = settings.agent_upstream_resolve(&device_info.gateway_host);
+
+    let management_shutdown = crate::management::start(
+        &settings,
+        std::sync::Arc::new(tokio::sync::Mutex::new(runtime.clone())),
+        watchdog_tx.clone(),
+        stores,
+        workload_manager.service().clone(),
+        tasks.clone(),
+    )
+    .await?;
+
+    crate::workload_manager::server(workload_manager, runtime.clone(), create_socket_channel_rcv)
+        .await?;
+
+    crate::alerts::start(&settings, runtime.clone(), alerts, changes)?;
+
+    crate::set_signal_handlers(watchdog_tx);
+
+    let shutdown_reason = run_until_shutdown(
+        &settings,
+        &device_info,
+        &runtime,
+        &identity_client,
+        &identity_health,
+        watchdog_rx,
+        &failures,
+    )
+    .await?;
+    restarts.stop((&shutdown_reason).into());
+
+    crate::stop_servers(management_shutdown, workload_shutdown, &tasks).await;
+
+    crate::finish_shutdown(
+        &shutdown_reason,
+        &identity_client,
+        &cache_dir,
+        &audit_log,
+        &operations,
+    )
+    .await
+}
+
+/// Keep Edge Agent running until a watchdog action stops the daemon.
+async fn run_until_shutdown(
+    settings: &edgelet_settings::docker::Settings,
+    device_info: &aziot_identity_common::AzureIoTSpec,
+    runtime: &edgelet_shim::ShimModuleRuntime,
+    identity_client: &aziot_identity_client_async::Client,
+    identity_health: &edgelet_http::IdentityHealth,
+    mut action_rx: edgelet_core::WatchdogReceiver,
+    failures: &edgelet_http::FailureReport,
+) -> Result<WatchdogAction, EdgedError> {
+    let mut timer = tokio::time::interval(AGENT_CHECK_PERIOD);
+    timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = timer.tick() => {
+                if let Err(err) =
+                    check_agent(settings, device_info, runtime, identity_client, identity_health)
+                        .await
+                {
+                    log::warn!("Error in watchdog: {}", err);
+                    failures.record(edgelet_http::FailureKind::ModuleRuntime, &err);
+                }
+            }
+
+            action = action_rx.recv() => {
+                let action = action.expect("shutdown channel closed");
+                log::info!("{}", action);
+
+                if let WatchdogAction::EdgeCaRenewal = action {
+                    crate::watchdog::restart_modules(settings, runtime).await;
+                } else {
+                    log::info!("Watchdog stopped");
+
+                    // Modules must be recreated after a reprovision, so they are only
+                    // left running when systemd restarts the daemon.
+                    if settings.warm_restart()
+                        && matches!(action, WatchdogAction::Signal)
+                        && crate::fd_store::restarting().await
+                    {
+                        log::info!("Leaving modules running for warm restart");
+                    } else {
+                        log::info!("Stopping all modules...");
+
+                        if let Err(err) = runtime
+                            .stop_all(Some(std::time::Duration::from_secs(30)))
+                            .await
+                        {
+                            log::warn!("Failed to stop modules on shutdown: {}", err);
+                        } else {
+                            log::info!("All modules stopped");
+                        }
+                    }
+
+                    return Ok(action);
+                }
+            }
+        }
+    }
+}
+
+/// Create Edge Agent if it doesn't exist, and start it if it isn't running.
+async fn check_agent(
+    settings: &edgelet_settings::docker::Settings,
+    device_info: &aziot_identity_common::AzureIoTSpec,
+    runtime: &edgelet_shim::ShimModuleRuntime,
+    identity_client: &aziot_identity_client_async::Client,
+    identity_health: &edgelet_http::IdentityHealth,
+) -> Result<(), EdgedError> {
+    let agent_name = settings.agent().name();
+
+    match runtime.get(agent_name).await {
+        Ok((_, state)) => {
+            if state.status() != &edgelet_core::ModuleStatus::Running {
+                log::info!("Starting Edge runtime module {}...", agent_name);
+
+                runtime
+                    .start(agent_name)
+                    .await
+                    .map_err(|err| EdgedError::from_err("Failed to start Edge runtime", err))?;
+            }
+
+            Ok(())
+        }
+
+        Err(err)
+            if edgelet_shim::ShimModuleRuntime::error_code(&err)
+                == hyper::StatusCode::NOT_FOUND =>
+        {
+            create_and_start_agent(
+                settings,
+                device_info,
+                runtime,
+                identity_client,
+                identity_health,
+            )
+            .await
+        }
+
+        Err(err) => Err(EdgedError::from_err(
+            "Failed to get Edge runtime module",
+            err,
+        )),
+    }
+}
+
+async fn create_and_start_agent(
+    settings: &edgelet_settings::docker::Settings,
+    device_info: &aziot_identity_common::AzureIoTSpec,
+    runtime: &edgelet_shim::ShimModuleRuntime,
+    identity_client: &aziot_identity_client_async::Client,
+    identity_health: &edgelet_http::IdentityHealth,
+) -> Result<(), EdgedError> {
+    let agent = settings.agent();
+    let agent_name = agent.name();
+
+    let gen_id = crate::watchdog::agent_gen_id(identity_client, identity_health).await?;
+    let mut env = agent.env().clone();
+    env.append(&mut crate::watchdog::agent_env(
+        gen_id,
+        settings,
+        device_info,
+    ));
+
+    // The shim interprets the agent's settings itself, so they are passed on as they are
+    // configured.
+    let config = serde_json::to_value(agent.config())
+        .map_err(|err| EdgedError::from_err("Invalid Edge runtime module settings", err))?;
+    let agent_spec = edgelet_settings::ModuleSpec::new(
+        agent_name.to_string(),
+        agent.r#type().to_string(),
+        config,
+        env,
+        agent.image_pull_policy(),
+    )
+    .map_err(EdgedError::new)?;
+
+    log::info!(
+        "Creating and starting Edge runtime module {}...",
+        agent_name
+    );
+
+    if let edgelet_settings::module::ImagePullPolicy::OnCreate = agent_spec.image_pull_policy() {
+        runtime
+            .registry()
+            .pull_module(agent_name, agent_spec.config())
+            .await
+            .map_err(|err| EdgedError::from_err("Failed to pull Edge runtime module", err))?;
+    }
+
+    runtime
+        .create(agent_spec)
+        .await
+        .map_err(|err| EdgedError::from_err("Failed to create Edge runtime module", err))?;
+
+    runtime
+        .start(agent_name)
+        .await
+        .map_err(|err| EdgedError::from_err("Failed to start Edge runtime", err))?;
+
+    log::info!("Started Edge runtime module {}", agent_name);
+
+    Ok(())
+}
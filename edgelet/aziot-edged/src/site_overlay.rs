@@ -0,0 +1,179 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_settings::{RuntimeSettings, SiteOverlay};
+
+use crate::error::Error as EdgedError;
+
+/// Parents that take longer than this to serve the overlay are treated as unreachable.
+const FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+fn cache_path(settings: &edgelet_settings::docker::Settings) -> std::path::PathBuf {
+    settings
+        .storage()
+        .state_dir(settings.homedir())
+        .join("site_overlay.json")
+}
+
+fn read_cache(settings: &edgelet_settings::docker::Settings) -> Option<SiteOverlay> {
+    edgelet_http::persist::read_json(&cache_path(settings), "cached site overlay").unwrap_or_else(
+        |err| {
+            log::warn!("Failed to read cached site overlay: {}", err);
+
+            None
+        },
+    )
+}
+
+/// Apply the site overlay fetched from the parent on a previous start.
+///
+/// The parent's hostname is only known after provisioning, which needs settings, so
+/// the overlay that applies is always the one from the previous start.
+pub(crate) fn apply_cached(
+    settings: edgelet_settings::docker::Settings,
+) -> edgelet_settings::docker::Settings {
+    if settings.site_overlay().is_none() {
+        return settings;
+    }
+
+    match read_cache(&settings) {
+        Some(overlay) => {
+            log::info!("Applying site overlay from parent");
+
+            settings.with_site_overlay(&overlay)
+        }
+        None => settings,
+    }
+}
+
+/// Fetch the site overlay from the parent, and cache it for the next start.
+///
+/// Returns an error that restarts the daemon if the overlay changed, so that it is
+/// applied. Failures to fetch the overlay are only logged, since the cached overlay
+/// still applies.
+pub(crate) async fn refresh(
+    settings: &edgelet_settings::docker::Settings,
+    device_info: &aziot_identity_common::AzureIoTSpec,
+) -> Result<(), EdgedError> {
+    let source = match settings.site_overlay() {
+        Some(source) => source,
+        None => return Ok(()),
+    };
+
+    // Only devices with a parent have a site overlay.
+    let parent = &device_info.gateway_host;
+    if parent.eq_ignore_ascii_case(&device_info.hub_name) {
+        return Ok(());
+    }
+
+    let overlay = match tokio::time::timeout(FETCH_TIMEOUT, fetch(settings, parent, source)).await {
+        Ok(Ok(overlay)) => overlay,
+        Ok(Err(err)) => {
+            log::warn!("Failed to fetch site overlay from {}: {}", parent, err);
+
+            return Ok(());
+        }
+        Err(_) => {
+            log::warn!("Timed out fetching site overlay from {}", parent);
+
+            return Ok(());
+        }
+    };
+
+    if read_cache(settings).unwrap_or_default() == overlay {
+        return Ok(());
+    }
+
+    let contents = serde_json::to_vec_pretty(&overlay)
+        .map_err(|err| EdgedError::from_err("Failed to serialize site overlay", err))?;
+    edgelet_http::persist::write(&cache_path(settings), &contents)
+        .map_err(|err| EdgedError::from_err("Failed to save site overlay", err))?;
+
+    Err(EdgedError::site_overlay_changed())
+}
+
+/// GET the overlay from the parent over HTTPS, trusting the device's trust bundle. The
+/// connection goes through the configured proxy unless the parent is in its `no_proxy`.
+///
+/// A parent that has no overlay responds with 404, which is the same as an empty one.
+async fn fetch(
+    settings: &edgelet_settings::docker::Settings,
+    parent: &str,
+    source: &edgelet_settings::SiteOverlaySource,
+) -> Result<SiteOverlay, String> {
+    let cert_connector = http_common::Connector::new(settings.endpoints().aziot_certd_url())
+        .map_err(|err| err.to_string())?;
+    let cert_client = aziot_cert_client_async::Client::new(
+        aziot_cert_common_http::ApiVersion::V2020_09_01,
+        cert_connector,
+        1,
+    );
+
+    let trust_bundle = settings
+        .trust_bundle_cert()
+        .unwrap_or(edgelet_settings::TRUST_BUNDLE_ALIAS);
+    let trust_bundle = cert_client
+        .get_cert(trust_bundle)
+        .await
+        .map_err(|err| format!("could not get trust bundle: {err}"))?;
+
+    let ssl = client_tls(&trust_bundle, parent).map_err(|err| err.to_string())?;
+
+    let stream = edgelet_docker::connect(settings.proxy(), parent, source.port())
+        .await
+        .map_err(|err| err.to_string())?;
+    let mut stream = tokio_openssl::SslStream::new(ssl, stream).map_err(|err| err.to_string())?;
+    std::pin::Pin::new(&mut stream)
+        .connect()
+        .await
+        .map_err(|err| format!("TLS handshake failed: {err}"))?;
+
+    let (mut sender, connection) = hyper::client::conn::handshake(stream)
+        .await
+        .map_err(|err| err.to_string())?;
+    edgelet_core::tasks::spawn("site_overlay::connection", async move {
+        if let Err(err) = connection.await {
+            log::debug!("Site overlay connection closed: {}", err);
+        }
+    });
+
+    let host = if source.port() == 443 {
+        parent.to_string()
+    } else {
+        format!("{parent}:{}", source.port())
+    };
+    let request = hyper::Request::get(source.path())
+        .header(hyper::header::HOST, host)
+        .body(hyper::Body::empty())
+        .map_err(|err| err.to_string())?;
+    let response = sender
+        .send_request(request)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let status = response.status();
+    if status == hyper::StatusCode::NOT_FOUND {
+        return Ok(SiteOverlay::default());
+    }
+    if !status.is_success() {
+        return Err(format!("parent responded with {status}"));
+    }
+
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(|err| err.to_string())?;
+
+    serde_json::from_slice(&body).map_err(|err| format!("invalid site overlay: {err}"))
+}
+
+pub(crate) fn client_tls(
+    trust_bundle: &[u8],
+    hostname: &str,
+) -> Result<openssl::ssl::Ssl, openssl::error::ErrorStack> {
+    let mut connector = openssl::ssl::SslConnector::builder(openssl::ssl::SslMethod::tls_client())?;
+
+    for cert in openssl::x509::X509::stack_from_pem(trust_bundle)? {
+        connector.cert_store_mut().add_cert(cert)?;
+    }
+
+    connector.build().configure()?.into_ssl(hostname)
+}
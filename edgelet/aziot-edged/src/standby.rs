@@ -0,0 +1,39 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Modules with warm standbys are checked much more often than the watchdog checks
+/// Edge Agent, since the point of a standby is to minimize downtime.
+const STANDBY_CHECK_PERIOD: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Start the standby containers of designated modules when those modules fail.
+pub(crate) fn start(
+    settings: &impl edgelet_settings::RuntimeSettings,
+    runtime: edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+) {
+    let modules = settings.warm_standby_modules().to_vec();
+
+    if modules.is_empty() {
+        return;
+    }
+
+    log::info!(
+        "Monitoring modules with warm standbys: {}",
+        modules.join(", ")
+    );
+
+    edgelet_core::tasks::spawn("standby", async move {
+        let mut timer = tokio::time::interval(STANDBY_CHECK_PERIOD);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            timer.tick().await;
+
+            for module in &modules {
+                // Modules that don't exist yet are expected while Edge Agent is starting,
+                // so errors are only logged at debug level.
+                if let Err(err) = runtime.fail_over_if_failed(module).await {
+                    log::debug!("Failed to check standby for module {}: {}", module, err);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,571 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::{Module, ModuleRuntime};
+use edgelet_docker::MakeModuleRuntime;
+use edgelet_settings::RuntimeSettings;
+
+use crate::device_cache::DeviceCache;
+use crate::error::Error as EdgedError;
+
+/// Version of the archive format. Archives of other versions are refused.
+const ARCHIVE_VERSION: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+
+/// State files that are exported. They are under `home/` in the archive, since earlier
+/// versions kept them in the home directory.
+///
+/// Secrets and module certificates are bound to the device's keys and Edge CA, so they
+/// can't be used on another device and are left out.
+const STATE_FILES: &[&str] = &[
+    "audit.log",
+    "changes.json",
+    "data_epochs.json",
+    "failures.json",
+    "feature_flags.json",
+    "operations.json",
+    "restarts.json",
+    "site_overlay.json",
+];
+
+/// Files of the device cache, which are decrypted into the archive and encrypted again
+/// with the importing device's key.
+const CACHE_FILES: &[&str] = &["device_info.json", "provisioning_state"];
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    version: u32,
+    created: chrono::DateTime<chrono::Utc>,
+    daemon_version: String,
+
+    /// Modules that existed on the exporting device. Their images are pulled on import.
+    modules: Vec<ExportedModule>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedModule {
+    name: String,
+    image: String,
+}
+
+/// Write the daemon's local state to a gzipped tar archive, for `import` on a
+/// replacement device.
+pub(crate) async fn export(
+    settings: &edgelet_settings::docker::Settings,
+    path: &std::path::Path,
+) -> Result<(), EdgedError> {
+    let homedir = settings.homedir();
+    let state_dir = settings.storage().state_dir(homedir);
+    crate::storage::prepare_state_dir(homedir, &state_dir)?;
+    let cache = DeviceCache::new(settings, settings.storage().cache_dir(homedir))?;
+
+    let manifest = Manifest {
+        version: ARCHIVE_VERSION,
+        created: chrono::Utc::now(),
+        daemon_version: edgelet_core::version_with_source_version(),
+        modules: list_modules(settings).await,
+    };
+
+    // The archive holds the device identity, so only its owner may read it.
+    let file = std::os::unix::fs::OpenOptionsExt::mode(
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true),
+        0o600,
+    )
+    .open(path)
+    .and_then(|file| {
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        Ok(file)
+    })
+    .map_err(|err| EdgedError::from_err(format!("Failed to create {}", path.display()), err))?;
+    let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(
+        file,
+        flate2::Compression::default(),
+    ));
+
+    let archive_err = |err| EdgedError::from_err("Failed to write state archive", err);
+
+    let manifest = serde_json::to_vec_pretty(&manifest)
+        .map_err(|err| EdgedError::from_err("Failed to write state archive", err))?;
+    append_data(&mut archive, MANIFEST, &manifest).map_err(archive_err)?;
+
+    for name in STATE_FILES {
+        let file = state_dir.join(name);
+
+        if file.exists() {
+            archive
+                .append_path_with_name(&file, format!("home/{name}"))
+                .map_err(archive_err)?;
+        }
+    }
+
+    for name in CACHE_FILES {
+        let contents = cache
+            .read(name)
+            .await
+            .map_err(|err| EdgedError::from_err("Failed to read device cache", err))?;
+
+        if let Some(contents) = contents {
+            append_data(&mut archive, &format!("cache/{name}"), &contents).map_err(archive_err)?;
+        }
+    }
+
+    for (prefix, dir) in [
+        ("gc", settings.storage().gc_dir(homedir)),
+        ("logs", settings.storage().logs_dir(homedir)),
+    ] {
+        if dir.is_dir() {
+            archive.append_dir_all(prefix, &dir).map_err(archive_err)?;
+        }
+    }
+
+    archive
+        .into_inner()
+        .and_then(flate2::write::GzEncoder::finish)
+        .map_err(archive_err)?;
+
+    log::info!(
+        "Exported state to {}. The archive holds the device identity in plaintext, so keep it safe.",
+        path.display()
+    );
+
+    Ok(())
+}
+
+/// Restore the state exported from another device. The daemon must be stopped.
+///
+/// Existing state is only replaced with `force`, so that a device's own history isn't
+/// overwritten by mistake. The whole archive is unpacked before any state is replaced,
+/// so that an archive that can't be imported leaves the device as it was.
+pub(crate) async fn import(
+    settings: &edgelet_settings::docker::Settings,
+    path: &std::path::Path,
+    force: bool,
+) -> Result<(), EdgedError> {
+    let homedir = settings.homedir();
+    let cache_dir = settings.storage().cache_dir(homedir);
+    let gc_dir = settings.storage().gc_dir(homedir);
+    let logs_dir = settings.storage().logs_dir(homedir);
+    let state_dir = settings.storage().state_dir(homedir);
+    crate::storage::prepare_state_dir(homedir, &state_dir)?;
+    let cache = DeviceCache::new(settings, cache_dir.clone())?;
+
+    if !force && cache_dir.join("provisioning_state").exists() {
+        return Err(EdgedError::new(
+            "This device already has state; stop aziot-edged and use --force to replace it",
+        ));
+    }
+
+    let file = std::fs::File::open(path)
+        .map_err(|err| EdgedError::from_err(format!("Failed to open {}", path.display()), err))?;
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+
+    let archive_err = |err| EdgedError::from_err("Failed to read state archive", err);
+
+    // Files are unpacked here first, where `unpack_in` keeps them from escaping through
+    // links, and only moved into place once the whole archive has been read.
+    let staging = homedir.join(".import");
+    remove_if_exists(&staging).map_err(archive_err)?;
+    std::fs::create_dir_all(&staging).map_err(archive_err)?;
+
+    let mut manifest: Option<Manifest> = None;
+    let mut staged = Vec::new();
+    let mut cache_files = Vec::new();
+
+    for entry in archive.entries().map_err(archive_err)? {
+        let mut entry = entry.map_err(archive_err)?;
+        let entry_path = entry.path().map_err(archive_err)?.into_owned();
+
+        // The manifest comes first, so that nothing is written from an archive that
+        // can't be imported.
+        if manifest.is_none() {
+            if entry_path != std::path::Path::new(MANIFEST) {
+                return Err(EdgedError::new("State archive has no manifest"));
+            }
+
+            let imported: Manifest = serde_json::from_reader(&mut entry)
+                .map_err(|err| EdgedError::from_err("Invalid state archive manifest", err))?;
+            if imported.version != ARCHIVE_VERSION {
+                return Err(EdgedError::new(format!(
+                    "State archive version {} is not supported",
+                    imported.version
+                )));
+            }
+
+            log::info!(
+                "Importing state exported at {} by aziot-edged {}",
+                imported.created,
+                imported.daemon_version
+            );
+
+            manifest = Some(imported);
+
+            continue;
+        }
+
+        // Directories are created for the files in them.
+        if entry.header().entry_type().is_dir() {
+            continue;
+        }
+
+        // Exports only hold regular files, so links and devices mean the archive was
+        // tampered with.
+        if !entry.header().entry_type().is_file() {
+            return Err(EdgedError::new(format!(
+                "State archive entry {} is not a regular file",
+                entry_path.display()
+            )));
+        }
+
+        let Some((prefix, name)) = split_entry(&entry_path) else {
+            log::warn!(
+                "Skipping unexpected {} in state archive",
+                entry_path.display()
+            );
+
+            continue;
+        };
+
+        let target = match prefix {
+            "home"
+                if STATE_FILES
+                    .iter()
+                    .any(|file| name == std::path::Path::new(file)) =>
+            {
+                state_dir.join(name)
+            }
+            "cache"
+                if CACHE_FILES
+                    .iter()
+                    .any(|file| name == std::path::Path::new(file)) =>
+            {
+                let mut contents = Vec::new();
+                std::io::Read::read_to_end(&mut entry, &mut contents).map_err(archive_err)?;
+                cache_files.push((name.to_string_lossy().into_owned(), contents));
+
+                continue;
+            }
+            "gc" => gc_dir.join(name),
+            "logs" => logs_dir.join(name),
+            _ => {
+                log::warn!(
+                    "Skipping unexpected {} in state archive",
+                    entry_path.display()
+                );
+
+                continue;
+            }
+        };
+
+        if !entry.unpack_in(&staging).map_err(archive_err)? {
+            return Err(EdgedError::new(format!(
+                "State archive entry {} is outside the archive",
+                entry_path.display()
+            )));
+        }
+        staged.push((staging.join(&entry_path), target));
+    }
+
+    let Some(manifest) = manifest else {
+        return Err(EdgedError::new("State archive has no manifest"));
+    };
+
+    let write_err = |err| EdgedError::from_err("Failed to write imported state", err);
+
+    if force {
+        clear_state(&state_dir, &cache_dir, &gc_dir, &logs_dir).map_err(write_err)?;
+    }
+
+    for (from, to) in &staged {
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent).map_err(write_err)?;
+        }
+        crate::storage::move_entry(from, to).map_err(write_err)?;
+    }
+
+    for (name, contents) in &cache_files {
+        cache
+            .write(name, contents)
+            .await
+            .map_err(|err| EdgedError::from_err("Failed to write device cache", err))?;
+    }
+
+    remove_if_exists(&staging).map_err(write_err)?;
+
+    // The import is run as root, but the daemon runs as the owner of its home directory.
+    if nix::unistd::Uid::effective().is_root() {
+        let owner = std::fs::metadata(homedir).map_err(write_err)?;
+        let owner = (
+            std::os::unix::fs::MetadataExt::uid(&owner),
+            std::os::unix::fs::MetadataExt::gid(&owner),
+        );
+
+        for path in [&state_dir, &cache_dir, &gc_dir, &logs_dir] {
+            chown_all(path, owner).map_err(write_err)?;
+        }
+    }
+
+    pull_images(settings, &manifest.modules).await;
+
+    log::info!("Imported state from {}", path.display());
+
+    Ok(())
+}
+
+/// Remove the state that an import replaces, so that none of the device's own state is
+/// mixed with the imported state.
+fn clear_state(
+    state_dir: &std::path::Path,
+    cache_dir: &std::path::Path,
+    gc_dir: &std::path::Path,
+    logs_dir: &std::path::Path,
+) -> std::io::Result<()> {
+    for name in STATE_FILES {
+        remove_if_exists(&state_dir.join(name))?;
+    }
+
+    for name in CACHE_FILES {
+        remove_if_exists(&cache_dir.join(name))?;
+    }
+
+    remove_if_exists(gc_dir)?;
+    remove_if_exists(logs_dir)?;
+
+    Ok(())
+}
+
+fn remove_if_exists(path: &std::path::Path) -> std::io::Result<()> {
+    let result = if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+
+    match result {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Give `path`, and everything under it, to `owner`.
+fn chown_all(path: &std::path::Path, owner: (u32, u32)) -> std::io::Result<()> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    nix::unistd::fchownat(
+        None,
+        path,
+        Some(nix::unistd::Uid::from_raw(owner.0)),
+        Some(nix::unistd::Gid::from_raw(owner.1)),
+        nix::unistd::FchownatFlags::NoFollowSymlink,
+    )?;
+
+    if metadata.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            chown_all(&entry?.path(), owner)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn append_data(
+    archive: &mut tar::Builder<impl std::io::Write>,
+    path: &str,
+    data: &[u8],
+) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs()),
+    );
+    header.set_cksum();
+
+    archive.append_data(&mut header, path, data)
+}
+
+/// Split an archive path into its top-level directory and the path below it. Paths that
+/// could escape the directory they are unpacked to are refused.
+fn split_entry(path: &std::path::Path) -> Option<(&str, &std::path::Path)> {
+    if !path
+        .components()
+        .all(|component| matches!(component, std::path::Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let mut components = path.components();
+    let prefix = components.next()?.as_os_str().to_str()?;
+    let name = components.as_path();
+
+    if name.as_os_str().is_empty() {
+        return None;
+    }
+
+    Some((prefix, name))
+}
+
+async fn make_runtime(
+    settings: &edgelet_settings::docker::Settings,
+) -> Result<edgelet_docker::DockerModuleRuntime<http_common::Connector>, EdgedError> {
+    let (create_socket_channel, _) = tokio::sync::mpsc::unbounded_channel();
+    let image_use_data = edgelet_docker::ImagePruneData::new(
+        &settings.storage().gc_dir(settings.homedir()),
+        settings.image_garbage_collection().clone(),
+    )
+    .map_err(|err| EdgedError::from_err("Failed to set up image garbage collection", err))?;
+
+    edgelet_docker::DockerModuleRuntime::make_runtime(
+        settings,
+        create_socket_channel,
+        image_use_data,
+    )
+    .await
+    .map_err(|err| EdgedError::from_err("Failed to initialize module runtime", err))
+}
+
+/// Modules of this device. Their full definitions belong to Edge Agent's deployment, so
+/// only their images are kept, to be pulled ahead of the deployment on import.
+async fn list_modules(settings: &edgelet_settings::docker::Settings) -> Vec<ExportedModule> {
+    let modules = match make_runtime(settings).await {
+        Ok(runtime) => runtime
+            .list()
+            .await
+            .map_err(|err| EdgedError::from_err("Failed to list modules", err)),
+        Err(err) => Err(err),
+    };
+
+    match modules {
+        Ok(modules) => modules
+            .iter()
+            .map(|module| ExportedModule {
+                name: module.name().to_string(),
+                image: module.config().image().to_string(),
+            })
+            .collect(),
+        Err(err) => {
+            log::warn!("Exporting without modules: {}", err);
+
+            Vec::new()
+        }
+    }
+}
+
+/// Pull the images of the exported modules. Failures are only logged, since Edge Agent
+/// pulls them again when it deploys the modules, e.g. with registry credentials that
+/// the archive doesn't hold.
+async fn pull_images(settings: &edgelet_settings::docker::Settings, modules: &[ExportedModule]) {
+    if modules.is_empty() {
+        return;
+    }
+
+    let runtime = match make_runtime(settings).await {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            log::warn!("Failed to pull module images: {}", err);
+
+            return;
+        }
+    };
+
+    for module in modules {
+        let config = match edgelet_settings::DockerConfig::new(
+            module.image.clone(),
+            Default::default(),
+            None,
+            None,
+            settings.allow_elevated_docker_permissions(),
+        ) {
+            Ok(config) => config,
+            Err(err) => {
+                log::warn!("Skipping image of module {}: {}", module.name, err);
+
+                continue;
+            }
+        };
+
+        if let Err(err) = edgelet_core::ModuleRegistry::pull(runtime.registry(), &config).await {
+            log::warn!(
+                "Failed to pull image {} of module {}: {}",
+                module.image,
+                module.name,
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn split_entry() {
+        let split = |path: &str| {
+            super::split_entry(std::path::Path::new(path))
+                .map(|(prefix, name)| (prefix.to_string(), name.to_string_lossy().into_owned()))
+        };
+
+        assert_eq!(
+            Some(("home".to_string(), "restarts.json".to_string())),
+            split("home/restarts.json")
+        );
+        assert_eq!(
+            Some(("logs".to_string(), "tempSensor/current.log".to_string())),
+            split("logs/tempSensor/current.log")
+        );
+
+        assert_eq!(None, split("manifest.json"));
+        assert_eq!(None, split("gc/"));
+        assert_eq!(None, split("/etc/passwd"));
+        assert_eq!(None, split("logs/../../etc/passwd"));
+        assert_eq!(None, split("./home/restarts.json"));
+    }
+
+    #[test]
+    fn clear_state() {
+        let dir = std::env::temp_dir().join(format!("state-clear-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let state_dir = dir.join("state");
+        let cache_dir = dir.join("cache");
+        let gc_dir = dir.join("gc");
+        let logs_dir = dir.join("logs");
+        for dir in [
+            &state_dir,
+            &cache_dir,
+            &gc_dir,
+            &logs_dir.join("tempSensor"),
+        ] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(state_dir.join("restarts.json"), "{}").unwrap();
+        std::fs::write(state_dir.join("secrets.json"), "{}").unwrap();
+        std::fs::write(cache_dir.join("provisioning_state"), "old").unwrap();
+        std::fs::write(gc_dir.join("image_use"), "{}").unwrap();
+        std::fs::write(logs_dir.join("tempSensor").join("current.log"), "old").unwrap();
+
+        super::clear_state(&state_dir, &cache_dir, &gc_dir, &logs_dir).unwrap();
+
+        assert!(!state_dir.join("restarts.json").exists());
+        assert!(!cache_dir.join("provisioning_state").exists());
+        assert!(!gc_dir.exists());
+        assert!(!logs_dir.exists());
+
+        // State that isn't exported, and so isn't imported, is kept.
+        assert!(state_dir.join("secrets.json").exists());
+
+        // Clearing state that doesn't exist is not an error.
+        super::clear_state(&state_dir, &cache_dir, &gc_dir, &logs_dir).unwrap();
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
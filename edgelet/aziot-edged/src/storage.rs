@@ -0,0 +1,229 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use crate::error::Error as EdgedError;
+
+/// State files that earlier versions kept in the home directory itself.
+const STATE_FILES: &[&str] = &[
+    "audit.log",
+    "changes.json",
+    "data_epochs.json",
+    "data_epochs.json.bak",
+    "dump.json",
+    "failures.json",
+    "feature_flags.json",
+    "module_certs.json",
+    "operations.json",
+    "restarts.json",
+    "secrets.json",
+    "site_overlay.json",
+    "workload_token.key",
+];
+
+/// Create a working directory of the daemon. If it was moved out of the home directory,
+/// the state left at its old location is moved to it first.
+pub(crate) fn prepare_dir(
+    name: &str,
+    default: &std::path::Path,
+    dir: &std::path::Path,
+) -> Result<(), EdgedError> {
+    if dir != default {
+        migrate(default, dir).map_err(|err| {
+            EdgedError::from_err(
+                format!(
+                    "Failed to move {} directory from {} to {}",
+                    name,
+                    default.display(),
+                    dir.display()
+                ),
+                err,
+            )
+        })?;
+    }
+
+    std::fs::create_dir_all(dir).map_err(|err| {
+        EdgedError::from_err(
+            format!("Failed to create {} directory {}", name, dir.display()),
+            err,
+        )
+    })
+}
+
+/// Create the directory of the daemon's state files. State files left in the home
+/// directory are moved to it first.
+pub(crate) fn prepare_state_dir(
+    homedir: &std::path::Path,
+    dir: &std::path::Path,
+) -> Result<(), EdgedError> {
+    std::fs::create_dir_all(dir).map_err(|err| {
+        EdgedError::from_err(
+            format!("Failed to create state directory {}", dir.display()),
+            err,
+        )
+    })?;
+
+    for name in STATE_FILES {
+        let from = homedir.join(name);
+        let to = dir.join(name);
+
+        // State already at the new location wins, as for the other directories.
+        if !from.exists() || to.exists() {
+            continue;
+        }
+
+        log::info!("Moving {} to {}", from.display(), to.display());
+
+        move_entry(&from, &to).map_err(|err| {
+            EdgedError::from_err(
+                format!("Failed to move {} to {}", from.display(), to.display()),
+                err,
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Move the entries of `from` into `to`. Entries already in `to` are kept, so that state
+/// written since an earlier migration isn't replaced.
+fn migrate(from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+    let entries = match std::fs::read_dir(from) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    std::fs::create_dir_all(to)?;
+
+    for entry in entries {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+
+        if target.exists() {
+            continue;
+        }
+
+        log::info!("Moving {} to {}", entry.path().display(), target.display());
+
+        move_entry(&entry.path(), &target)?;
+    }
+
+    // The directory may still hold entries that were already at the new location.
+    if let Err(err) = std::fs::remove_dir(from) {
+        log::debug!("Keeping {}: {}", from.display(), err);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn move_entry(from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+    // Renaming fails when the directories are on different file systems, which is
+    // usually why they were split.
+    if std::fs::rename(from, to).is_err() {
+        copy(from, to)?;
+        remove(from)?;
+    }
+
+    Ok(())
+}
+
+fn copy(from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(from, to)?;
+    }
+
+    Ok(())
+}
+
+fn remove(path: &std::path::Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn migrate() {
+        let dir = std::env::temp_dir().join(format!("storage-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let from = dir.join("home").join("cache");
+        let to = dir.join("flash").join("cache");
+        std::fs::create_dir_all(from.join("nested")).unwrap();
+        std::fs::write(from.join("device_info"), "old").unwrap();
+        std::fs::write(from.join("provisioning_state"), "old").unwrap();
+        std::fs::write(from.join("nested").join("file"), "nested").unwrap();
+
+        // State already at the new location wins.
+        std::fs::create_dir_all(&to).unwrap();
+        std::fs::write(to.join("provisioning_state"), "new").unwrap();
+
+        super::prepare_dir("cache", &from, &to).unwrap();
+
+        assert_eq!(
+            "old",
+            std::fs::read_to_string(to.join("device_info")).unwrap()
+        );
+        assert_eq!(
+            "new",
+            std::fs::read_to_string(to.join("provisioning_state")).unwrap()
+        );
+        assert_eq!(
+            "nested",
+            std::fs::read_to_string(to.join("nested").join("file")).unwrap()
+        );
+        assert!(!from.join("device_info").exists());
+
+        // Nothing is left to move on the next start.
+        super::prepare_dir("cache", &from, &to).unwrap();
+        assert!(to.join("device_info").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn state_files() {
+        let dir = std::env::temp_dir().join(format!("storage-state-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let homedir = dir.join("home");
+        let state_dir = homedir.join("state");
+        std::fs::create_dir_all(&homedir).unwrap();
+        std::fs::write(homedir.join("secrets.json"), "old").unwrap();
+        std::fs::write(homedir.join("restarts.json"), "old").unwrap();
+        std::fs::write(homedir.join("unrelated"), "kept").unwrap();
+
+        // State already at the new location wins.
+        std::fs::create_dir_all(&state_dir).unwrap();
+        std::fs::write(state_dir.join("restarts.json"), "new").unwrap();
+
+        super::prepare_state_dir(&homedir, &state_dir).unwrap();
+
+        assert_eq!(
+            "old",
+            std::fs::read_to_string(state_dir.join("secrets.json")).unwrap()
+        );
+        assert_eq!(
+            "new",
+            std::fs::read_to_string(state_dir.join("restarts.json")).unwrap()
+        );
+        assert!(!homedir.join("secrets.json").exists());
+        assert!(homedir.join("unrelated").exists());
+        assert!(!state_dir.join("unrelated").exists());
+
+        // A state directory that is the home directory itself leaves the files in place.
+        super::prepare_state_dir(&state_dir, &state_dir).unwrap();
+        assert!(state_dir.join("secrets.json").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
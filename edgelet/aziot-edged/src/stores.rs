@@ -0,0 +1,95 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_settings::RuntimeSettings;
+
+use crate::error::Error as EdgedError;
+
+pub(crate) use edgelet_http::Stores;
+
+/// Load the stores from the state directory.
+pub(crate) fn load(
+    settings: &edgelet_settings::docker::Settings,
+    state_dir: &std::path::Path,
+    restarts: edgelet_http::RestartHistory,
+    diagnostics: edgelet_http::Diagnostics,
+    log_filter: edgelet_http::LogFilter,
+) -> Result<Stores, EdgedError> {
+    let workload_tcp = edgelet_http::WorkloadTcp::new(settings)
+        .map_err(|err| EdgedError::from_err("Failed to set up workload TCP listener", err))?;
+
+    let access_log = edgelet_http::AccessLog::new(settings.api_access_log().clone());
+
+    let rate_limit = edgelet_http::RateLimit::new(settings.workload_rate_limit().clone());
+
+    let feature_flags = edgelet_http::FeatureFlags::new(
+        settings.feature_flags().clone(),
+        state_dir.join("feature_flags.json"),
+    );
+
+    let data_epochs = edgelet_http::DataEpochs::new(state_dir.join("data_epochs.json"))
+        .map_err(|err| EdgedError::from_err("Failed to load module data epochs", err))?;
+
+    let module_certs = edgelet_http::ModuleCerts::new(
+        state_dir.join("module_certs.json"),
+        settings.module_cert_renewal().renew_before,
+    )
+    .map_err(|err| EdgedError::from_err("Failed to load module certificate list", err))?;
+
+    let secrets = edgelet_http::Secrets::new(state_dir.join("secrets.json"))
+        .map_err(|err| EdgedError::from_err("Failed to load module secrets", err))?;
+
+    let changes = edgelet_http::ChangeFeed::new(state_dir.join("changes.json"))
+        .map_err(|err| EdgedError::from_err("Failed to load change feed", err))?;
+
+    let alerts = edgelet_http::Alerts::new(settings.alerts());
+
+    let audit_log = edgelet_http::AuditLog::new(state_dir.join("audit.log"))
+        .map_err(|err| EdgedError::from_err("Failed to load audit log", err))?;
+
+    let module_health = edgelet_http::ModuleHealth::default();
+
+    let persisted_logs =
+        settings
+            .log_persistence()
+            .map_or_else(Default::default, |log_persistence| {
+                edgelet_http::PersistedLogs::new(
+                    settings.storage().logs_dir(settings.homedir()),
+                    log_persistence,
+                )
+            });
+
+    let operations = edgelet_http::Operations::start(state_dir.join("operations.json"))
+        .unwrap_or_else(|err| {
+            log::warn!("Failed to load operations report: {}", err);
+
+            Default::default()
+        });
+
+    let failures = edgelet_http::FailureReport::new(
+        settings.cloud_notify().clone(),
+        state_dir.join("failures.json"),
+    );
+
+    Ok(Stores {
+        workload_tcp,
+        access_log,
+        rate_limit,
+        feature_flags,
+        restarts,
+        failures,
+        data_epochs,
+        identity_health: edgelet_http::IdentityHealth::default(),
+        changes,
+        alerts,
+        secrets,
+        module_certs,
+        audit_log,
+        module_health,
+        persisted_logs,
+        operations,
+        diagnostics,
+        parent_health: edgelet_http::ParentHealth::default(),
+        log_filter,
+        state_dump: edgelet_http::StateDump::default(),
+    })
+}
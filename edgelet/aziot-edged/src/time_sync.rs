@@ -0,0 +1,46 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Timezone changes and NTP synchronization are rare, so there is no need to track
+/// them closely.
+const TIME_STATUS_PERIOD: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Keep the host's time status up to date in the directory that modules mount.
+pub(crate) fn start(settings: &impl edgelet_settings::RuntimeSettings) {
+    if !settings.inject_host_time() {
+        return;
+    }
+
+    let time_dir = edgelet_core::host_time_dir(settings.homedir());
+
+    edgelet_core::tasks::spawn("time_sync", async move {
+        let mut timer = tokio::time::interval(TIME_STATUS_PERIOD);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        let mut previous: Option<edgelet_core::TimeStatus> = None;
+
+        loop {
+            timer.tick().await;
+
+            let status = edgelet_core::TimeStatus::current();
+
+            if let Some(previous) = &previous {
+                if previous.synchronized != status.synchronized {
+                    log::info!("Host clock synchronized: {}", status.synchronized);
+                }
+
+                if previous.timezone != status.timezone {
+                    log::info!(
+                        "Host timezone changed to {}; modules pick it up when they are recreated",
+                        status.timezone
+                    );
+                }
+            }
+
+            if let Err(err) = status.write_to(&time_dir) {
+                log::warn!("Failed to write host time status: {}", err);
+            }
+
+            previous = Some(status);
+        }
+    });
+}
@@ -5,18 +5,68 @@ use edgelet_settings::RuntimeSettings;
 
 use crate::error::Error as EdgedError;
 
+/// What the watchdog loop is doing, for the state dump. A check that started long ago
+/// and hasn't finished means the loop is stuck, most likely on the container engine.
+#[derive(Clone)]
+pub(crate) struct Status {
+    inner: std::sync::Arc<std::sync::Mutex<StatusInner>>,
+    actions: edgelet_core::WatchdogSender,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StatusInner {
+    pub checking_since: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_check: Option<chrono::DateTime<chrono::Utc>>,
+    pub consecutive_errors: u32,
+    pub actions_handled: u64,
+    pub last_action: Option<String>,
+
+    /// Actions sent to the watchdog that it hasn't received yet.
+    pub queued_actions: usize,
+}
+
+impl Status {
+    pub fn new(actions: edgelet_core::WatchdogSender) -> Self {
+        Status {
+            inner: std::sync::Arc::default(),
+            actions,
+        }
+    }
+
+    pub fn get(&self) -> StatusInner {
+        let mut status = self.lock().clone();
+        status.queued_actions = self.actions.queued();
+
+        status
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, StatusInner> {
+        self.inner.lock().expect("watchdog status lock poisoned")
+    }
+}
+
 pub(crate) async fn run_until_shutdown(
     settings: edgelet_settings::docker::Settings,
     device_info: &aziot_identity_common::AzureIoTSpec,
     runtime: edgelet_docker::DockerModuleRuntime<http_common::Connector>,
     identity_client: &aziot_identity_client_async::Client,
-    mut action_rx: tokio::sync::mpsc::UnboundedReceiver<edgelet_core::WatchdogAction>,
+    identity_health: edgelet_http::IdentityHealth,
+    mut action_rx: edgelet_core::WatchdogReceiver,
+    failures: edgelet_http::FailureReport,
+    audit_log: edgelet_http::AuditLog,
+    operations: edgelet_http::Operations,
+    parent_health: edgelet_http::ParentHealth,
+    status: Status,
 ) -> Result<edgelet_core::WatchdogAction, EdgedError> {
     // Run the watchdog every 60 seconds while waiting for any running task to send a
-    // watchdog action.
+    // watchdog action. The period backs off after consecutive errors.
     let watchdog_period = std::time::Duration::from_secs(60);
+    let watchdog_max_backoff = settings.watchdog().max_backoff().max(watchdog_period);
     let watchdog_retries = settings.watchdog().max_retries();
     let mut watchdog_errors = 0;
+    let mut consecutive_errors = 0;
+    let mut backoff = None;
 
     let mut watchdog_timer = tokio::time::interval(watchdog_period);
     watchdog_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
@@ -24,6 +74,12 @@ pub(crate) async fn run_until_shutdown(
     log::info!("Starting watchdog with 60 second period...");
 
     loop {
+        if let Some(backoff) = backoff.take() {
+            watchdog_timer =
+                tokio::time::interval_at(tokio::time::Instant::now() + backoff, watchdog_period);
+            watchdog_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        }
+
         let watchdog_next = watchdog_timer.tick();
         tokio::pin!(watchdog_next);
 
@@ -32,9 +88,29 @@ pub(crate) async fn run_until_shutdown(
 
         match futures_util::future::select(watchdog_next, action_next).await {
             futures_util::future::Either::Left((_, _)) => {
-                if let Err(err) = watchdog(&settings, device_info, &runtime, identity_client).await
+                status.lock().checking_since = Some(chrono::Utc::now());
+
+                let result = watchdog(
+                    &settings,
+                    device_info,
+                    &runtime,
+                    identity_client,
+                    &identity_health,
+                    &audit_log,
+                    &operations,
+                    &parent_health,
+                )
+                .await;
+
                 {
+                    let mut status = status.lock();
+                    status.checking_since = None;
+                    status.last_check = Some(chrono::Utc::now());
+                }
+
+                if let Err(err) = result {
                     log::warn!("Error in watchdog: {}", err);
+                    failures.record(edgelet_http::FailureKind::ModuleRuntime, &err);
 
                     watchdog_errors += 1;
 
@@ -43,6 +119,21 @@ pub(crate) async fn run_until_shutdown(
                             "Watchdog error count has exceeded allowed retries",
                         ));
                     }
+
+                    consecutive_errors += 1;
+                    status.lock().consecutive_errors = consecutive_errors;
+
+                    let next = watchdog_period
+                        .checked_mul(2_u32.saturating_pow(consecutive_errors))
+                        .map_or(watchdog_max_backoff, |next| next.min(watchdog_max_backoff));
+
+                    if next > watchdog_period {
+                        log::info!("Next watchdog check in {} seconds", next.as_secs());
+                        backoff = Some(next);
+                    }
+                } else {
+                    consecutive_errors = 0;
+                    status.lock().consecutive_errors = 0;
                 }
             }
 
@@ -50,20 +141,35 @@ pub(crate) async fn run_until_shutdown(
                 let action = action.expect("shutdown channel closed");
                 log::info!("{}", action);
 
+                {
+                    let mut status = status.lock();
+                    status.actions_handled += 1;
+                    status.last_action = Some(action.to_string());
+                }
+
                 if let edgelet_core::WatchdogAction::EdgeCaRenewal = action {
                     restart_modules(&settings, &runtime).await;
                 } else {
                     log::info!("Watchdog stopped");
 
-                    log::info!("Stopping all modules...");
-
-                    if let Err(err) = runtime
-                        .stop_all(Some(std::time::Duration::from_secs(30)))
-                        .await
+                    // Modules must be recreated after a reprovision, so they are only
+                    // left running when systemd restarts the daemon.
+                    if settings.warm_restart()
+                        && matches!(action, edgelet_core::WatchdogAction::Signal)
+                        && crate::fd_store::restarting().await
                     {
-                        log::warn!("Failed to stop modules on shutdown: {}", err);
+                        log::info!("Leaving modules running for warm restart");
                     } else {
-                        log::info!("All modules stopped");
+                        log::info!("Stopping all modules...");
+
+                        if let Err(err) = runtime
+                            .stop_all(Some(std::time::Duration::from_secs(30)))
+                            .await
+                        {
+                            log::warn!("Failed to stop modules on shutdown: {}", err);
+                        } else {
+                            log::info!("All modules stopped");
+                        }
                     }
 
                     return Ok(action);
@@ -78,6 +184,10 @@ async fn watchdog(
     device_info: &aziot_identity_common::AzureIoTSpec,
     runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
     identity_client: &aziot_identity_client_async::Client,
+    identity_health: &edgelet_http::IdentityHealth,
+    audit_log: &edgelet_http::AuditLog,
+    operations: &edgelet_http::Operations,
+    parent_health: &edgelet_http::ParentHealth,
 ) -> Result<(), EdgedError> {
     log::info!("Watchdog checking Edge runtime status");
     let agent_name = settings.agent().name();
@@ -102,9 +212,20 @@ async fn watchdog(
                     .map_err(|err| EdgedError::from_err("Failed to start Edge runtime", err))?;
 
                 log::info!("Started Edge runtime module {}", agent_name);
+
+                audit_log.record(
+                    edgelet_http::AuditEvent::new(edgelet_http::AuditEventType::WatchdogRestart)
+                        .with_module(agent_name)
+                        .with_details(format!("started after status {agent_status}")),
+                );
+                operations.watchdog_restart();
             }
 
             edgelet_core::ModuleStatus::Dead | edgelet_core::ModuleStatus::Unknown => {
+                if defer_for_parent(settings, parent_health) {
+                    return Ok(());
+                }
+
                 log::info!(
                     "Edge runtime status is {}, removing and recreating module...",
                     agent_status
@@ -115,19 +236,65 @@ async fn watchdog(
                     .await
                     .map_err(|err| EdgedError::from_err("Failed to remove Edge runtime", err))?;
 
-                create_and_start_agent(settings, device_info, runtime, identity_client).await?;
+                create_and_start_agent(
+                    settings,
+                    device_info,
+                    runtime,
+                    identity_client,
+                    identity_health,
+                )
+                .await?;
+
+                audit_log.record(
+                    edgelet_http::AuditEvent::new(edgelet_http::AuditEventType::WatchdogRestart)
+                        .with_module(agent_name)
+                        .with_details(format!("recreated after status {agent_status}")),
+                );
+                operations.watchdog_restart();
             }
         }
     } else {
-        create_and_start_agent(settings, device_info, runtime, identity_client).await?;
+        if defer_for_parent(settings, parent_health) {
+            return Ok(());
+        }
+
+        create_and_start_agent(
+            settings,
+            device_info,
+            runtime,
+            identity_client,
+            identity_health,
+        )
+        .await?;
+
+        audit_log.record(
+            edgelet_http::AuditEvent::new(edgelet_http::AuditEventType::ModuleCreated)
+                .with_module(agent_name)
+                .with_details("created by the watchdog"),
+        );
     }
 
     Ok(())
 }
 
-async fn restart_modules(
+/// Whether creating Edge Agent should wait for the parent of a nested device, since its
+/// image is pulled through the parent and would fail to pull while the parent is down.
+fn defer_for_parent(
     settings: &edgelet_settings::docker::Settings,
-    runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    parent_health: &edgelet_http::ParentHealth,
+) -> bool {
+    if settings.watchdog().parent().defer_agent_restarts && parent_health.is_down() {
+        log::info!("Parent is down; deferring creation of Edge runtime until it is reachable");
+
+        true
+    } else {
+        false
+    }
+}
+
+pub(crate) async fn restart_modules(
+    settings: &edgelet_settings::docker::Settings,
+    runtime: &impl ModuleRuntime,
 ) {
     let agent_name = settings.agent().name();
 
@@ -186,11 +353,12 @@ async fn create_and_start_agent(
     device_info: &aziot_identity_common::AzureIoTSpec,
     runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
     identity_client: &aziot_identity_client_async::Client,
+    identity_health: &edgelet_http::IdentityHealth,
 ) -> Result<(), EdgedError> {
     let agent_name = settings.agent().name();
     let mut agent_spec = settings.agent().clone();
 
-    let gen_id = agent_gen_id(identity_client).await?;
+    let gen_id = agent_gen_id(identity_client, identity_health).await?;
     let mut env = agent_env(gen_id, settings, device_info);
     agent_spec.env_mut().append(&mut env);
 
@@ -200,9 +368,22 @@ async fn create_and_start_agent(
     );
 
     if let edgelet_settings::module::ImagePullPolicy::OnCreate = agent_spec.image_pull_policy() {
-        edgelet_core::ModuleRegistry::pull(runtime.registry(), agent_spec.config())
-            .await
-            .map_err(|err| EdgedError::from_err("Failed to pull Edge runtime module", err))?;
+        let (config, cached) = pull_agent_image(
+            runtime,
+            agent_name,
+            agent_spec.config(),
+            settings.agent_fallback_images(),
+            settings.agent_cached_image_fallback(),
+        )
+        .await?;
+        agent_spec.set_config(config);
+
+        // Lets Edge Agent report that it may not be running the configured version.
+        if cached {
+            agent_spec
+                .env_mut()
+                .insert("IOTEDGE_CACHEDIMAGE".to_string(), "true".to_string());
+        }
     }
 
     runtime
@@ -222,13 +403,105 @@ async fn create_and_start_agent(
     Ok(())
 }
 
-async fn agent_gen_id(
+/// Pull the Edge Agent image. If it can't be pulled, the fallback images are tried in
+/// order, and then, if `cached_fallback` is set, a previously pulled image of the same
+/// repository as any of them.
+///
+/// Returns the config for the image to use, and whether that image is a cached one that
+/// couldn't be pulled.
+async fn pull_agent_image(
+    runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    agent_name: &str,
+    config: &edgelet_settings::DockerConfig,
+    fallback_images: &[String],
+    cached_fallback: bool,
+) -> Result<(edgelet_settings::DockerConfig, bool), EdgedError> {
+    let images: Vec<_> = std::iter::once(config.image())
+        .chain(fallback_images.iter().map(String::as_str))
+        .collect();
+
+    let mut last_err = None;
+
+    for &image in &images {
+        let config = config.clone().with_image(image.to_string());
+
+        // Pulled as Edge Agent's, so that the credential its deployment last had
+        // accepted is used.
+        match edgelet_core::ModuleRegistry::pull_module(runtime.registry(), agent_name, &config)
+            .await
+        {
+            Ok(()) => return Ok((config, false)),
+            Err(err) => {
+                log::warn!("Failed to pull Edge runtime image {}: {}", image, err);
+
+                last_err = Some(err);
+            }
+        }
+    }
+
+    let err = last_err.expect("agent config always has at least one image");
+
+    if !cached_fallback {
+        return Err(EdgedError::from_err(
+            "Failed to pull Edge runtime module",
+            err,
+        ));
+    }
+
+    // Devices behind unreliable links would otherwise have no Edge Agent until the
+    // registry is reachable again.
+    match runtime.cached_image(&images).await {
+        Ok(Some(image)) => {
+            log::warn!(
+                "Starting Edge runtime from cached image {} since {} could not be pulled",
+                image,
+                config.image()
+            );
+
+            Ok((config.clone().with_image(image), true))
+        }
+        Ok(None) => Err(EdgedError::from_err(
+            "Failed to pull Edge runtime module",
+            err,
+        )),
+        Err(cache_err) => {
+            log::warn!(
+                "Failed to look for cached Edge runtime image: {}",
+                cache_err
+            );
+
+            Err(EdgedError::from_err(
+                "Failed to pull Edge runtime module",
+                err,
+            ))
+        }
+    }
+}
+
+pub(crate) async fn agent_gen_id(
     identity_client: &aziot_identity_client_async::Client,
+    identity_health: &edgelet_http::IdentityHealth,
 ) -> Result<String, EdgedError> {
-    let identity = identity_client
-        .update_module_identity("$edgeAgent")
-        .await
-        .map_err(|err| EdgedError::from_err("Failed to update $edgeAgent identity", err))?;
+    let identity = match identity_client.update_module_identity("$edgeAgent").await {
+        Ok(identity) => {
+            identity_health.available();
+
+            identity
+        }
+        Err(err) => {
+            // Errors returned by Identity Service itself mean that it is reachable.
+            if err.kind() == std::io::ErrorKind::Other {
+                identity_health.available();
+            } else {
+                identity_health.unavailable(&err);
+            }
+
+            return Err(EdgedError::from_err(
+                "Failed to update $edgeAgent identity",
+                err,
+            ));
+        }
+    };
 
     if let aziot_identity_common::Identity::Aziot(identity) = identity {
         identity.gen_id.map_or_else(
@@ -240,7 +513,7 @@ async fn agent_gen_id(
     }
 }
 
-fn agent_env(
+pub(crate) fn agent_env(
     gen_id: String,
     settings: &edgelet_settings::docker::Settings,
     device_info: &aziot_identity_common::AzureIoTSpec,
@@ -285,12 +558,11 @@ fn agent_env(
     );
     let workload_mnt_uri = {
         // Home directory was used before this function was called, so it should be valid.
-        let mut path = settings
+        let homedir = settings
             .homedir()
             .canonicalize()
             .expect("Invalid homedir path");
-
-        path.push("mnt");
+        let path = settings.storage().mnt_dir(&homedir);
 
         let path = path.to_str().expect("invalid path");
 
@@ -304,6 +576,24 @@ fn agent_env(
         workload_mnt_uri,
     );
 
+    // The token key was created at startup, so this only needs to read it.
+    match edgelet_http::WorkloadTcp::load(settings) {
+        Ok(Some(workload_tcp)) => {
+            let host_network = settings
+                .agent()
+                .config()
+                .create_options()
+                .host_config()
+                .map_or(false, |host_config| {
+                    host_config.network_mode() == Some("host")
+                });
+
+            workload_tcp.set_module_env("$edgeAgent", host_network, &mut env);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn!("Failed to set workload token for Edge Agent: {}", err),
+    }
+
     env.insert("Mode".to_string(), "iotedged".to_string());
 
     env
@@ -4,9 +4,105 @@ use edgelet_core::{module::ModuleAction, Error, UrlExt};
 use edgelet_settings::uri::Listen;
 
 use crate::error::Error as EdgedError;
+use crate::stores::Stores;
 
 const WORKLOAD_SOCKET_PERMISSION: u32 = 0o666;
 
+/// How often module certificates are checked for upcoming expiry.
+const MODULE_CERT_CHECK_PERIOD: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// The key of the legacy shared socket in [`Listeners`]. Module names can't contain '$'.
+const LEGACY_LISTENER: &str = "$legacy";
+
+/// The key of the TCP listener in [`Listeners`] and the shutdown senders.
+const TCP_LISTENER: &str = "$tcp";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum ListenerState {
+    Listening,
+    Stopping,
+    Stopped,
+    Failed,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListenerStatus {
+    pub uri: String,
+    pub state: ListenerState,
+    pub since: chrono::DateTime<chrono::Utc>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Distinguishes a listener from the one it replaced, so that a stopped listener's
+    /// task doesn't overwrite the status of its module's new listener.
+    #[serde(skip)]
+    generation: u64,
+}
+
+/// The status of each workload listener, by module, for the state dump. These are
+/// kept separately from the manager since the manager is owned by its server task.
+#[derive(Clone, Default)]
+pub(crate) struct Listeners {
+    listeners: std::sync::Arc<std::sync::Mutex<std::collections::BTreeMap<String, ListenerStatus>>>,
+    next_generation: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+impl Listeners {
+    pub fn list(&self) -> std::collections::BTreeMap<String, ListenerStatus> {
+        self.lock().clone()
+    }
+
+    fn set(&self, key: &str, uri: &url::Url, state: ListenerState, error: Option<String>) -> u64 {
+        let generation = self
+            .next_generation
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        self.lock().insert(
+            key.to_string(),
+            ListenerStatus {
+                uri: uri.to_string(),
+                state,
+                since: chrono::Utc::now(),
+                error,
+                generation,
+            },
+        );
+
+        generation
+    }
+
+    fn update(
+        &self,
+        key: &str,
+        generation: Option<u64>,
+        state: ListenerState,
+        error: Option<String>,
+    ) {
+        if let Some(status) = self.lock().get_mut(key) {
+            if generation.map_or(true, |generation| generation == status.generation) {
+                status.state = state;
+                status.since = chrono::Utc::now();
+                status.error = error;
+            }
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        self.lock().remove(key);
+    }
+
+    fn lock(
+        &self,
+    ) -> std::sync::MutexGuard<'_, std::collections::BTreeMap<String, ListenerStatus>> {
+        self.listeners
+            .lock()
+            .expect("workload listeners lock poisoned")
+    }
+}
+
 pub(crate) struct WorkloadManager<M>
 where
     M: edgelet_core::ModuleRuntime + Clone + Send + Sync + 'static,
@@ -16,8 +112,15 @@ where
     shutdown_senders: HashMap<String, tokio::sync::oneshot::Sender<()>>,
     legacy_workload_uri: url::Url,
     legacy_workload_systemd_socket_name: String,
-    home_dir: std::path::PathBuf,
+    mnt_dir: std::path::PathBuf,
     service: edgelet_http_workload::Service<M>,
+    workload_tcp: Option<edgelet_http::WorkloadTcp>,
+    access_log: edgelet_http::AccessLog,
+    rate_limit: edgelet_http::RateLimit,
+    module_certs: edgelet_http::ModuleCerts,
+    warm_restart: bool,
+    sockets: std::collections::BTreeMap<String, edgelet_settings::WorkloadSocket>,
+    listeners: Listeners,
 }
 
 impl<M> WorkloadManager<M>
@@ -31,8 +134,8 @@ where
         device_info: &aziot_identity_common::AzureIoTSpec,
         tasks: std::sync::Arc<std::sync::atomic::AtomicUsize>,
         create_socket_channel_snd: tokio::sync::mpsc::UnboundedSender<ModuleAction>,
-        renewal_tx: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
-        max_requests: usize,
+        renewal_tx: edgelet_core::WatchdogSender,
+        stores: &Stores,
     ) -> Result<(WorkloadManager<M>, tokio::sync::oneshot::Sender<()>), EdgedError> {
         let shutdown_senders: HashMap<String, tokio::sync::oneshot::Sender<()>> = HashMap::new();
         let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
@@ -41,33 +144,71 @@ where
         let legacy_workload_uri = settings.listen().legacy_workload_uri().clone();
         let legacy_workload_systemd_socket_name = Listen::get_workload_systemd_socket_name();
 
-        let service =
-            edgelet_http_workload::Service::new(settings, runtime, renewal_tx, device_info)
-                .map_err(|err| EdgedError::from_err("Invalid service endpoint", err))?;
+        let service = edgelet_http_workload::Service::new(
+            settings,
+            runtime,
+            renewal_tx,
+            device_info,
+            stores.feature_flags.clone(),
+            stores.data_epochs.clone(),
+            stores.module_certs.clone(),
+            stores.secrets.clone(),
+        )
+        .map_err(|err| EdgedError::from_err("Invalid service endpoint", err))?;
 
         service.check_edge_ca().await.map_err(EdgedError::new)?;
 
-        let home_dir = settings.homedir().to_path_buf();
+        let mnt_dir = settings.storage().mnt_dir(settings.homedir());
+        let warm_restart = settings.warm_restart();
+        let sockets = settings.workload_sockets().clone();
+
+        edgelet_core::tasks::spawn(
+            "module_certs",
+            check_module_certs(
+                module_runtime.clone(),
+                stores.module_certs.clone(),
+                settings.module_cert_renewal().restart_modules,
+            ),
+        );
 
         let workload_manager = WorkloadManager {
-            max_requests,
+            max_requests: settings.iotedge_max_requests().workload,
             shutdown_senders,
             legacy_workload_uri,
             legacy_workload_systemd_socket_name,
-            home_dir,
+            mnt_dir,
             service,
+            workload_tcp: stores.workload_tcp.clone(),
+            access_log: stores.access_log.clone(),
+            rate_limit: stores.rate_limit.clone(),
+            module_certs: stores.module_certs.clone(),
+            warm_restart,
+            sockets,
+            listeners: Listeners::default(),
         };
 
-        tokio::spawn(stop(
-            create_socket_channel_snd,
-            module_runtime,
-            shutdown_rx,
-            tasks,
-        ));
+        edgelet_core::tasks::spawn(
+            "workload_manager::stop",
+            stop(
+                create_socket_channel_snd,
+                module_runtime,
+                shutdown_rx,
+                tasks,
+            ),
+        );
 
         Ok((workload_manager, shutdown_tx))
     }
 
+    /// The workload service also issues certificates for aziot-edged's own listeners.
+    pub(crate) fn service(&self) -> &edgelet_http_workload::Service<M> {
+        &self.service
+    }
+
+    pub(crate) fn listeners(&self) -> Listeners {
+        self.listeners.clone()
+    }
+
     async fn spawn_listener(
         &mut self,
         workload_uri: url::Url,
@@ -80,13 +221,79 @@ where
         self.shutdown_senders
             .insert(module_id.to_string(), shutdown_sender);
 
-        let connector = http_common::Connector::new(&workload_uri)
-            .map_err(|err| EdgedError::from_err("Invalid workload API URL", err))?;
+        // The legacy shared socket is created by systemd, so it can't be configured.
+        let socket_settings = if module_id.is_empty() {
+            None
+        } else {
+            edgelet_settings::WorkloadSocket::for_module(&self.sockets, module_id)
+        };
+        let permission = socket_settings
+            .and_then(|socket| socket.mode)
+            .unwrap_or(WORKLOAD_SOCKET_PERMISSION);
+
+        let key = if module_id.is_empty() {
+            LEGACY_LISTENER
+        } else {
+            module_id
+        };
 
-        let mut incoming = connector
-            .incoming(WORKLOAD_SOCKET_PERMISSION, self.max_requests, socket_name)
-            .await
-            .map_err(|err| EdgedError::from_err("Failed to listen on workload socket", err))?;
+        let mut incoming = match crate::listener::bind(
+            &workload_uri,
+            permission,
+            self.max_requests,
+            socket_name.clone(),
+        )
+        .await
+        {
+            Ok(incoming) => incoming,
+            Err(err) => {
+                self.listeners.set(
+                    key,
+                    &workload_uri,
+                    ListenerState::Failed,
+                    Some(err.to_string()),
+                );
+
+                return Err(EdgedError::from_err(
+                    "Failed to listen on workload socket",
+                    err,
+                ));
+            }
+        };
+
+        if let Some(socket_settings) = socket_settings {
+            if socket_settings.uid.is_some() || socket_settings.gid.is_some() {
+                let path = workload_uri
+                    .to_uds_file_path()
+                    .map_err(|err| EdgedError::from_err("Could not convert uri to path", err))?;
+
+                nix::unistd::chown(
+                    &path,
+                    socket_settings.uid.map(nix::unistd::Uid::from_raw),
+                    socket_settings.gid.map(nix::unistd::Gid::from_raw),
+                )
+                .map_err(|err| {
+                    EdgedError::from_err("Failed to set owner of workload socket", err)
+                })?;
+            }
+        }
+
+        // The legacy shared socket is socket-activated, so systemd already keeps it.
+        if self.warm_restart && !module_id.is_empty() {
+            if let http_common::Incoming::Unix { listener, .. } = &incoming {
+                let stored_name = crate::fd_store::name(module_id);
+
+                // A new socket replaces any that is still stored under the name.
+                if socket_name.is_none() {
+                    crate::fd_store::remove(&stored_name);
+                }
+
+                crate::fd_store::store(
+                    &stored_name,
+                    std::os::unix::io::AsRawFd::as_raw_fd(listener),
+                );
+            }
+        }
 
         // Send signal back to module runtime that socket and folder are created.
         if let Some(signal_socket_created) = signal_socket_created {
@@ -98,12 +305,38 @@ where
             })?;
         }
 
-        let service = self.service.clone();
-        tokio::spawn(async move {
+        // Callers on the legacy shared socket can't be told apart, so it isn't rate limited.
+        let rate_limit = if module_id.is_empty() {
+            edgelet_http::RateLimit::default()
+        } else {
+            self.rate_limit.clone()
+        };
+
+        let service = self.access_log.wrap(
+            "workload",
+            rate_limit.wrap(
+                module_id,
+                edgelet_http::CompressionService::new(edgelet_http::ApiVersionService::new(
+                    self.service.clone(),
+                )),
+            ),
+        );
+        let listeners = self.listeners.clone();
+        let key = key.to_string();
+        let generation = listeners.set(&key, &workload_uri, ListenerState::Listening, None);
+        edgelet_core::tasks::spawn("workload_api", async move {
             log::info!("Starting workload API...");
 
             if let Err(err) = incoming.serve(service, shutdown_receiver).await {
                 log::error!("Failed to start workload API: {}", err);
+                listeners.update(
+                    &key,
+                    Some(generation),
+                    ListenerState::Failed,
+                    Some(err.to_string()),
+                );
+            } else {
+                listeners.update(&key, Some(generation), ListenerState::Stopped, None);
             }
 
             log::info!("Workload API stopped");
@@ -112,6 +345,76 @@ where
         Ok(())
     }
 
+    async fn spawn_tcp_listener(&mut self, runtime: M) -> Result<(), EdgedError> {
+        let workload_tcp = match &self.workload_tcp {
+            Some(workload_tcp) => workload_tcp.clone(),
+            None => return Ok(()),
+        };
+
+        let (shutdown_sender, shutdown_receiver) = tokio::sync::oneshot::channel();
+
+        // Module names can't contain '$', so this key can't collide with a module's listener.
+        self.shutdown_senders
+            .insert(TCP_LISTENER.to_string(), shutdown_sender);
+
+        let connector = http_common::Connector::new(workload_tcp.listen_uri())
+            .map_err(|err| EdgedError::from_err("Invalid workload TCP URL", err))?;
+
+        let listen_uri = workload_tcp.listen_uri().clone();
+        let mut incoming = match connector
+            .incoming(WORKLOAD_SOCKET_PERMISSION, self.max_requests, None)
+            .await
+        {
+            Ok(incoming) => incoming,
+            Err(err) => {
+                self.listeners.set(
+                    TCP_LISTENER,
+                    &listen_uri,
+                    ListenerState::Failed,
+                    Some(err.to_string()),
+                );
+
+                return Err(EdgedError::from_err(
+                    "Failed to listen on workload TCP port",
+                    err,
+                ));
+            }
+        };
+
+        let service = self.access_log.wrap(
+            "workload",
+            edgelet_http::TokenAuth::new(
+                edgelet_http::CompressionService::new(edgelet_http::ApiVersionService::new(
+                    self.service.clone(),
+                )),
+                workload_tcp,
+                self.rate_limit.clone(),
+                runtime,
+            ),
+        );
+        let listeners = self.listeners.clone();
+        let generation = listeners.set(TCP_LISTENER, &listen_uri, ListenerState::Listening, None);
+        edgelet_core::tasks::spawn("workload_api_tcp", async move {
+            log::info!("Starting workload API on TCP listener...");
+
+            if let Err(err) = incoming.serve(service, shutdown_receiver).await {
+                log::error!("Failed to start workload API on TCP listener: {}", err);
+                listeners.update(
+                    TCP_LISTENER,
+                    Some(generation),
+                    ListenerState::Failed,
+                    Some(err.to_string()),
+                );
+            } else {
+                listeners.update(TCP_LISTENER, Some(generation), ListenerState::Stopped, None);
+            }
+
+            log::info!("Workload API on TCP listener stopped");
+        });
+
+        Ok(())
+    }
+
     async fn start_listener(
         &mut self,
         module_id: &str,
@@ -120,7 +423,14 @@ where
         log::info!("Starting new listener for module {}", module_id);
         let workload_uri = self.get_listener_uri(module_id)?;
 
-        self.spawn_listener(workload_uri, signal_socket_created, module_id, None)
+        // A socket kept by systemd from the previous run is used if there is one.
+        let socket_name = if self.warm_restart {
+            crate::fd_store::take(module_id)
+        } else {
+            None
+        };
+
+        self.spawn_listener(workload_uri, signal_socket_created, module_id, socket_name)
             .await?;
 
         Ok(())
@@ -132,9 +442,17 @@ where
         let shutdown_sender = self.shutdown_senders.remove(module_id);
 
         if let Some(shutdown_sender) = shutdown_sender {
+            self.listeners
+                .update(module_id, None, ListenerState::Stopping, None);
+
             // When edged boots up, it cleans all modules. At this moment, no socket could listening so it could legitimately return an error.
             let _ = shutdown_sender.send(());
         }
+
+        // The stopped listener's socket must not be passed back after a restart.
+        if self.warm_restart && !module_id.is_empty() {
+            crate::fd_store::remove(&crate::fd_store::name(module_id));
+        }
     }
 
     fn remove_listener(&mut self, module_id: &str) -> Result<(), EdgedError> {
@@ -143,6 +461,9 @@ where
         // Try to stop the listener, just in case it was not stopped before
         self.stop_listener(module_id);
 
+        self.module_certs.remove(module_id);
+        self.listeners.remove(module_id);
+
         // If the container is removed, also remove the socket file to limit the leaking of socket file
         let workload_uri = self.get_listener_uri(module_id)?;
 
@@ -157,15 +478,15 @@ where
     }
 
     fn get_listener_uri(&self, module_id: &str) -> Result<url::Url, EdgedError> {
-        let uri = self.home_dir.to_str().map_or_else(
+        let uri = self.mnt_dir.to_str().map_or_else(
             || {
                 Err(EdgedError::from_err(
-                    "No home dir found",
+                    "No mnt dir found",
                     Error::WorkloadManager,
                 ))
             },
-            |home_dir| {
-                Listen::workload_uri(home_dir, module_id)
+            |mnt_dir| {
+                Listen::workload_uri(mnt_dir, module_id)
                     .map_err(|err| EdgedError::from_err("Could not get workload uri", err))
             },
         )?;
@@ -199,6 +520,9 @@ where
         )
         .await?;
 
+    // Spawn the TCP listener for modules that can't use Unix sockets, if it's enabled.
+    workload_manager.spawn_tcp_listener(runtime.clone()).await?;
+
     for module in module_list {
         if let Err(err) = workload_manager
             .start_listener(edgelet_core::Module::name(&module), None)
@@ -213,7 +537,7 @@ where
     }
 
     // Ignore error, we don't want the server to close on error.
-    tokio::spawn(async move {
+    edgelet_core::tasks::spawn("workload_manager", async move {
         loop {
             if let Some(module_id) = create_socket_channel_rcv.recv().await {
                 match module_id {
@@ -239,6 +563,54 @@ where
     Ok(())
 }
 
+/// Wake modules waiting on the workload API for their certificates to become due, and
+/// restart modules that don't renew them if so configured.
+async fn check_module_certs<M>(
+    runtime: M,
+    module_certs: edgelet_http::ModuleCerts,
+    restart_modules: bool,
+) where
+    M: edgelet_core::ModuleRuntime + Clone + Send + Sync + 'static,
+{
+    let mut timer = tokio::time::interval(MODULE_CERT_CHECK_PERIOD);
+    timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        timer.tick().await;
+
+        let due = module_certs.due();
+        if due.is_empty() {
+            continue;
+        }
+
+        module_certs.notify();
+
+        if !restart_modules {
+            continue;
+        }
+
+        // Each module is restarted once per certificate. A module that doesn't request a
+        // new certificate after restarting is left running.
+        let mut modules: Vec<&str> = due
+            .iter()
+            .filter(|cert| !cert.restarted)
+            .map(|cert| cert.module.as_str())
+            .collect();
+        modules.sort_unstable();
+        modules.dedup();
+
+        for module in modules {
+            log::info!("Restarting module {} to renew its certificates", module);
+
+            if let Err(err) = runtime.restart(module).await {
+                log::warn!("Failed to restart module {}: {}", module, err);
+            }
+
+            module_certs.mark_restarted(module);
+        }
+    }
+}
+
 async fn stop<M>(
     create_socket_channel_snd: tokio::sync::mpsc::UnboundedSender<ModuleAction>,
     runtime: M,
@@ -6,6 +6,10 @@ use crate::models;
 type BoxFutureResult<'a, T> =
     std::pin::Pin<Box<dyn std::future::Future<Output = anyhow::Result<T>> + Send + 'a>>;
 
+/// Receives the progress messages of an operation as they arrive.
+pub type ProgressHandler<'a> =
+    &'a (dyn Fn(&serde_json::Map<String, serde_json::Value>) + Send + Sync);
+
 #[derive(Debug, serde_derive::Deserialize)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct ApiError {
@@ -103,6 +107,25 @@ pub trait DockerApi {
         no_prune: bool,
     ) -> BoxFutureResult<'a, Vec<models::ImageDeleteResponseItem>>;
 
+    fn image_export<'a>(&'a self, name: &'a str) -> BoxFutureResult<'a, hyper::Body>;
+
+    fn image_load(&self, tarball: hyper::Body) -> BoxFutureResult<'_, ()>;
+
+    /// Pull an image, passing the engine's progress messages to `on_progress`.
+    fn image_pull<'a>(
+        &'a self,
+        from_image: &'a str,
+        x_registry_auth: &'a str,
+        on_progress: ProgressHandler<'a>,
+    ) -> BoxFutureResult<'a, ()>;
+
+    fn image_tag<'a>(
+        &'a self,
+        name: &'a str,
+        repo: &'a str,
+        tag: &'a str,
+    ) -> BoxFutureResult<'a, ()>;
+
     fn container_create<'a>(
         &'a self,
         name: &'a str,
@@ -123,6 +146,8 @@ pub trait DockerApi {
         size: bool,
     ) -> BoxFutureResult<'a, models::InlineResponse200>;
 
+    fn container_kill<'a>(&'a self, id: &'a str, signal: &'a str) -> BoxFutureResult<'a, ()>;
+
     fn container_list<'a>(
         &'a self,
         all: bool,
@@ -136,6 +161,7 @@ pub trait DockerApi {
         id: &'a str,
         timeout: Option<i32>,
     ) -> BoxFutureResult<'a, ()>;
+    fn container_rename<'a>(&'a self, id: &'a str, name: &'a str) -> BoxFutureResult<'a, ()>;
     fn container_start<'a>(&'a self, id: &'a str, detach_keys: &'a str) -> BoxFutureResult<'a, ()>;
     fn container_stats<'a>(
         &'a self,
@@ -167,6 +193,16 @@ pub trait DockerApi {
     ) -> BoxFutureResult<'_, models::InlineResponse2011>;
 
     fn network_list<'a>(&'a self, filters: &'a str) -> BoxFutureResult<'a, Vec<models::Network>>;
+
+    fn network_connect<'a>(
+        &'a self,
+        id: &'a str,
+        body: models::NetworkConnectConfig,
+    ) -> BoxFutureResult<'a, ()>;
+
+    fn volume_delete<'a>(&'a self, name: &'a str, force: bool) -> BoxFutureResult<'a, ()>;
+
+    fn system_events<'a>(&'a self, filters: &'a str) -> BoxFutureResult<'a, hyper::Body>;
 }
 
 macro_rules! api_call {
@@ -264,6 +300,76 @@ macro_rules! api_call {
     }
 }
 
+/// Result of an operation that reports its progress as a stream of JSON messages, the
+/// last of which holds the error if the operation failed.
+async fn progress_result(response: hyper::Response<hyper::Body>) -> anyhow::Result<()> {
+    progress_stream(response, &|_| ()).await
+}
+
+/// Like `progress_result`, passing each message to `on_progress` as it arrives.
+async fn progress_stream(
+    response: hyper::Response<hyper::Body>,
+    on_progress: ProgressHandler<'_>,
+) -> anyhow::Result<()> {
+    use hyper::body::HttpBody;
+
+    let (parts, mut body) = response.into_parts();
+
+    anyhow::ensure!(
+        parts
+            .headers
+            .get(hyper::header::CONTENT_TYPE)
+            .ok_or_else(|| anyhow::anyhow!("expected Content-Type"))?
+            .to_str()?
+            .contains("application/json"),
+        "expected JSON Content-Type"
+    );
+
+    // Messages can be split across chunks, so the rest of a partial message is kept
+    // until the next chunk.
+    let mut buffer = Vec::new();
+    let mut last = None;
+    while let Some(chunk) = body.data().await {
+        buffer.extend_from_slice(&chunk?);
+
+        let mut messages = serde_json::Deserializer::from_slice(&buffer)
+            .into_iter::<serde_json::Map<String, serde_json::Value>>();
+        let mut consumed = 0;
+        loop {
+            match messages.next() {
+                Some(Ok(message)) => {
+                    consumed = messages.byte_offset();
+                    on_progress(&message);
+                    last = Some(message);
+                }
+                Some(Err(err)) if err.is_eof() => break,
+                Some(Err(err)) => return Err(err.into()),
+                None => break,
+            }
+        }
+        buffer.drain(..consumed);
+    }
+
+    anyhow::ensure!(
+        buffer.iter().all(u8::is_ascii_whitespace),
+        "received incomplete response from container runtime"
+    );
+    let mut last =
+        last.ok_or_else(|| anyhow::anyhow!("received empty response from container runtime"))?;
+
+    if let Some(detail) = last.remove("errorDetail") {
+        let fallback_msg = serde_json::to_string(&detail)?;
+        Err(anyhow::anyhow!(serde_json::from_value(detail).unwrap_or(
+            ApiError {
+                code: hyper::StatusCode::INTERNAL_SERVER_ERROR,
+                message: fallback_msg
+            }
+        )))
+    } else {
+        Ok(())
+    }
+}
+
 impl<C> DockerApi for DockerApiClient<C>
 where
     C: Clone + hyper::client::connect::Connect + Send + Sync + 'static,
@@ -280,6 +386,20 @@ where
         ok : [OK]
     }
 
+    api_call! {
+        image_tag : post "/images/{name}/tag" ;
+        path : [ name: &'a str ] ;
+        query : [ "repo" = (repo: &'a str), "tag" = (tag: &'a str) ] ;
+        ok : [CREATED]
+    }
+
+    api_call! {
+        image_export : get "/images/{name}/get" -> hyper::Body ;
+        path : [ name: &'a str ] ;
+        ok : [OK] ;
+        and_then(response) : { Ok(response.into_body()) }
+    }
+
     api_call! {
         images_list : get "/images/json" -> Vec<models::ImageSummary> ;
         query : [ "all" = (all: bool), "filters" = (filters: &'a str), "digests" = (digests: bool)] ;
@@ -307,6 +427,13 @@ where
         ok : [NO_CONTENT]
     }
 
+    api_call! {
+        container_rename : post "/containers/{id}/rename" ;
+        path : [ id: &'a str ] ;
+        query : [ "name" = (name: &'a str) ] ;
+        ok : [NO_CONTENT]
+    }
+
     api_call! {
         container_inspect : get "/containers/{id}/json" -> models::InlineResponse200 ;
         path : [ id: &'a str ] ;
@@ -314,6 +441,13 @@ where
         ok : [OK]
     }
 
+    api_call! {
+        container_kill : post "/containers/{id}/kill" ;
+        path : [ id: &'a str ] ;
+        query : [ "signal" = (signal: &'a str) ] ;
+        ok : [NO_CONTENT]
+    }
+
     api_call! {
         container_list : get "/containers/json" -> Vec<models::ContainerSummary> ;
         query : [
@@ -365,6 +499,20 @@ where
         ok : [OK]
     }
 
+    api_call! {
+        network_connect : post "/networks/{id}/connect" ;
+        path : [ id: &'a str ] ;
+        body : models::NetworkConnectConfig ;
+        ok : [OK]
+    }
+
+    api_call! {
+        volume_delete : delete "/volumes/{name}" ;
+        path : [ name: &'a str ] ;
+        query : [ "force" = (force: bool) ] ;
+        ok : [NO_CONTENT]
+    }
+
     api_call! {
         image_create : post "/images/create" ;
         query : [
@@ -379,38 +527,77 @@ where
         ] ;
         body : &'a str ;
         ok : [OK] ;
-        and_then(response) : {
-            let (parts, body) = response.into_parts();
-
-            anyhow::ensure!(
-                parts.headers.get(hyper::header::CONTENT_TYPE)
-                    .ok_or_else(|| anyhow::anyhow!("expected Content-Type"))?
-                    .to_str()?
-                    .contains("application/json"),
-                "expected JSON Content-Type"
-            );
+        and_then(response) : { progress_result(response).await }
+    }
+
+    fn image_load(&self, tarball: hyper::Body) -> BoxFutureResult<'_, ()> {
+        Box::pin(async move {
+            let uri = (self.configuration.uri_composer)(
+                &self.configuration.base_path,
+                "/images/load?quiet=1",
+            )?;
+
+            let mut builder =
+                hyper::Request::post(&uri).header(hyper::header::CONTENT_TYPE, "application/x-tar");
+            if let Some(agent) = &self.configuration.user_agent {
+                builder = builder.header(hyper::header::USER_AGENT, agent);
+            }
+            let request = builder.body(tarball)?;
 
-            let response_bytes = hyper::body::to_bytes(body).await?;
-            let mut last = serde_json::Deserializer::from_slice(&response_bytes)
-                .into_iter::<serde_json::Map<String, serde_json::Value>>()
-                .last()
-                .ok_or_else(|| {
-                    anyhow::anyhow!("received empty response from container runtime")
-                })??;
+            // Unlike other calls, this has no timeout, since the engine only responds
+            // once it has read the whole tarball.
+            let response = self.client.request(request).await?;
 
-            if let Some(detail) = last.remove("errorDetail") {
-                let fallback_msg = serde_json::to_string(&detail)?;
+            if response.status() == hyper::StatusCode::OK {
+                progress_result(response).await
+            } else {
                 Err(anyhow::anyhow!(
-                    serde_json::from_value(detail)
-                        .unwrap_or(ApiError {
-                            code: hyper::StatusCode::INTERNAL_SERVER_ERROR,
-                            message: fallback_msg
-                        })
+                    ApiError::try_from_response(response).await?
                 ))
+            }
+        })
+    }
+
+    fn image_pull<'a>(
+        &'a self,
+        from_image: &'a str,
+        x_registry_auth: &'a str,
+        on_progress: ProgressHandler<'a>,
+    ) -> BoxFutureResult<'a, ()> {
+        Box::pin(async move {
+            let query = url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("fromImage", from_image)
+                .finish();
+            let uri = (self.configuration.uri_composer)(
+                &self.configuration.base_path,
+                &format!("/images/create?{query}"),
+            )?;
+
+            let mut builder = hyper::Request::post(&uri).header(
+                hyper::header::HeaderName::from_static("x-registry-auth"),
+                x_registry_auth,
+            );
+            if let Some(agent) = &self.configuration.user_agent {
+                builder = builder.header(hyper::header::USER_AGENT, agent);
+            }
+            let request = builder.body(hyper::Body::empty())?;
+
+            // As with other calls, the timeout only covers the engine's response, not the
+            // pull that it streams the progress of.
+            let response = tokio::time::timeout(
+                std::time::Duration::from_secs(30),
+                self.client.request(request),
+            )
+            .await??;
+
+            if response.status() == hyper::StatusCode::OK {
+                progress_stream(response, on_progress).await
             } else {
-                Ok(())
+                Err(anyhow::anyhow!(
+                    ApiError::try_from_response(response).await?
+                ))
             }
-        }
+        })
     }
 
     api_call! {
@@ -428,6 +615,13 @@ where
         ok : [OK] ;
         and_then(response) : { Ok(response.into_body()) }
     }
+
+    api_call! {
+        system_events : get "/events" -> hyper::Body ;
+        query : [ "filters" = (filters: &'a str) ] ;
+        ok : [OK] ;
+        and_then(response) : { Ok(response.into_body()) }
+    }
 }
 
 #[cfg(test)]
@@ -500,6 +694,33 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn image_pull_progress() {
+        let payload = format!(
+            "{}\r\n{}\r\n",
+            serde_json::to_string(&serde_json::json!({"status":"Downloading","id":"a1"})).unwrap(),
+            serde_json::to_string(&serde_json::json!({"status":"Pull complete","id":"a1"}))
+                .unwrap(),
+        );
+        let client = DockerApiClient::new(JsonConnector::ok(&payload));
+
+        let statuses = std::sync::Mutex::new(Vec::new());
+        client
+            .image_pull("image", "", &|message| {
+                statuses
+                    .lock()
+                    .unwrap()
+                    .push(message["status"].as_str().unwrap().to_owned());
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec!["Downloading".to_owned(), "Pull complete".to_owned()],
+            statuses.into_inner().unwrap()
+        );
+    }
+
     #[tokio::test]
     async fn images_list_null_repo_tags() {
         let payload = format!(
@@ -1,4 +1,4 @@
 mod client;
 mod configuration;
-pub use self::client::{ApiError, DockerApi, DockerApiClient};
+pub use self::client::{ApiError, DockerApi, DockerApiClient, ProgressHandler};
 pub use self::configuration::Configuration;
@@ -74,8 +74,8 @@ pub struct ContainerCreateBody {
     /// Command to run specified as a string or an array of strings.
     #[serde(rename = "Cmd", skip_serializing_if = "Option::is_none")]
     cmd: Option<Vec<String>>,
-    // #[serde(rename = "Healthcheck", skip_serializing_if = "Option::is_none")]
-    // healthcheck: Option<crate::models::HealthConfig>,
+    #[serde(rename = "Healthcheck", skip_serializing_if = "Option::is_none")]
+    healthcheck: Option<crate::models::HealthConfig>,
     // /// Command is already escaped (Windows only)
     // #[serde(rename = "ArgsEscaped", skip_serializing_if = "Option::is_none")]
     // args_escaped: Option<bool>,
@@ -137,7 +137,7 @@ impl ContainerCreateBody {
             // stdin_once: None,
             env: None,
             cmd: None,
-            // healthcheck: None,
+            healthcheck: None,
             // args_escaped: None,
             image: None,
             volumes: None,
@@ -363,22 +363,22 @@ impl ContainerCreateBody {
         self.cmd = None;
     }
 
-    // pub fn set_healthcheck(&mut self, healthcheck: crate::models::HealthConfig) {
-    //     self.healthcheck = Some(healthcheck);
-    // }
+    pub fn set_healthcheck(&mut self, healthcheck: crate::models::HealthConfig) {
+        self.healthcheck = Some(healthcheck);
+    }
 
-    // pub fn with_healthcheck(mut self, healthcheck: crate::models::HealthConfig) -> Self {
-    //     self.healthcheck = Some(healthcheck);
-    //     self
-    // }
+    pub fn with_healthcheck(mut self, healthcheck: crate::models::HealthConfig) -> Self {
+        self.healthcheck = Some(healthcheck);
+        self
+    }
 
-    // pub fn healthcheck(&self) -> Option<&crate::models::HealthConfig> {
-    //     self.healthcheck.as_ref()
-    // }
+    pub fn healthcheck(&self) -> Option<&crate::models::HealthConfig> {
+        self.healthcheck.as_ref()
+    }
 
-    // pub fn reset_healthcheck(&mut self) {
-    //     self.healthcheck = None;
-    // }
+    pub fn reset_healthcheck(&mut self) {
+        self.healthcheck = None;
+    }
 
     // pub fn set_args_escaped(&mut self, args_escaped: bool) {
     //     self.args_escaped = Some(args_escaped);
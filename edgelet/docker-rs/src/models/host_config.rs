@@ -76,12 +76,12 @@ pub struct HostConfig {
     //     skip_serializing_if = "Option::is_none"
     // )]
     // blkio_device_write_i_ops: Option<Vec<crate::models::ThrottleDevice>>,
-    // /// The length of a CPU period in microseconds.
-    // #[serde(rename = "CpuPeriod", skip_serializing_if = "Option::is_none")]
-    // cpu_period: Option<i64>,
-    // /// Microseconds of CPU time that the container can get in a CPU period.
-    // #[serde(rename = "CpuQuota", skip_serializing_if = "Option::is_none")]
-    // cpu_quota: Option<i64>,
+    /// The length of a CPU period in microseconds.
+    #[serde(rename = "CpuPeriod", skip_serializing_if = "Option::is_none")]
+    cpu_period: Option<i64>,
+    /// Microseconds of CPU time that the container can get in a CPU period.
+    #[serde(rename = "CpuQuota", skip_serializing_if = "Option::is_none")]
+    cpu_quota: Option<i64>,
     // /// The length of a CPU real-time period in microseconds. Set to 0 to allocate no time allocated to real-time tasks.
     // #[serde(rename = "CpuRealtimePeriod", skip_serializing_if = "Option::is_none")]
     // cpu_realtime_period: Option<i64>,
@@ -94,36 +94,36 @@ pub struct HostConfig {
     // /// Memory nodes (MEMs) in which to allow execution (0-3, 0,1). Only effective on NUMA systems.
     // #[serde(rename = "CpusetMems", skip_serializing_if = "Option::is_none")]
     // cpuset_mems: Option<String>,
-    // /// A list of devices to add to the container.
-    // #[serde(rename = "Devices", skip_serializing_if = "Option::is_none")]
-    // devices: Option<Vec<crate::models::DeviceMapping>>,
-    // /// a list of cgroup rules to apply to the container
-    // #[serde(rename = "DeviceCgroupRules", skip_serializing_if = "Option::is_none")]
-    // device_cgroup_rules: Option<Vec<String>>,
+    /// A list of devices to add to the container.
+    #[serde(rename = "Devices", skip_serializing_if = "Option::is_none")]
+    devices: Option<Vec<crate::models::DeviceMapping>>,
+    /// a list of cgroup rules to apply to the container
+    #[serde(rename = "DeviceCgroupRules", skip_serializing_if = "Option::is_none")]
+    device_cgroup_rules: Option<Vec<String>>,
     // /// Disk limit (in bytes).
     // #[serde(rename = "DiskQuota", skip_serializing_if = "Option::is_none")]
     // disk_quota: Option<i64>,
     // /// Kernel memory limit in bytes.
     // #[serde(rename = "KernelMemory", skip_serializing_if = "Option::is_none")]
     // kernel_memory: Option<i64>,
-    // /// Memory soft limit in bytes.
-    // #[serde(rename = "MemoryReservation", skip_serializing_if = "Option::is_none")]
-    // memory_reservation: Option<i64>,
+    /// Memory soft limit in bytes.
+    #[serde(rename = "MemoryReservation", skip_serializing_if = "Option::is_none")]
+    memory_reservation: Option<i64>,
     // /// Total memory limit (memory + swap). Set as `-1` to enable unlimited swap.
     // #[serde(rename = "MemorySwap", skip_serializing_if = "Option::is_none")]
     // memory_swap: Option<i64>,
     // /// Tune a container's memory swappiness behavior. Accepts an integer between 0 and 100.
     // #[serde(rename = "MemorySwappiness", skip_serializing_if = "Option::is_none")]
     // memory_swappiness: Option<i64>,
-    // /// CPU quota in units of 10<sup>-9</sup> CPUs.
-    // #[serde(rename = "NanoCPUs", skip_serializing_if = "Option::is_none")]
-    // nano_cp_us: Option<i64>,
+    /// CPU quota in units of 10<sup>-9</sup> CPUs.
+    #[serde(rename = "NanoCPUs", skip_serializing_if = "Option::is_none")]
+    nano_cp_us: Option<i64>,
     // /// Disable OOM Killer for the container.
     // #[serde(rename = "OomKillDisable", skip_serializing_if = "Option::is_none")]
     // oom_kill_disable: Option<bool>,
-    // /// Tune a container's pids limit. Set -1 for unlimited.
-    // #[serde(rename = "PidsLimit", skip_serializing_if = "Option::is_none")]
-    // pids_limit: Option<i64>,
+    /// Tune a container's pids limit. Set -1 for unlimited.
+    #[serde(rename = "PidsLimit", skip_serializing_if = "Option::is_none")]
+    pids_limit: Option<i64>,
     // /// A list of resource limits to set in the container. For example: `{\"Name\": \"nofile\", \"Soft\": 1024, \"Hard\": 2048}`\"
     // #[serde(rename = "Ulimits", skip_serializing_if = "Option::is_none")]
     // ulimits: Option<Vec<crate::models::ResourcesUlimits>>,
@@ -145,11 +145,11 @@ pub struct HostConfig {
     // /// Path to a file where the container ID is written
     // #[serde(rename = "ContainerIDFile", skip_serializing_if = "Option::is_none")]
     // container_id_file: Option<String>,
-    // #[serde(rename = "LogConfig", skip_serializing_if = "Option::is_none")]
-    // log_config: Option<crate::models::HostConfigLogConfig>,
-    // /// Network mode to use for this container. Supported standard values are: `bridge`, `host`, `none`, and `container:<name|id>`. Any other value is taken as a custom network's name to which this container should connect to.
-    // #[serde(rename = "NetworkMode", skip_serializing_if = "Option::is_none")]
-    // network_mode: Option<String>,
+    #[serde(rename = "LogConfig", skip_serializing_if = "Option::is_none")]
+    log_config: Option<crate::models::HostConfigLogConfig>,
+    /// Network mode to use for this container. Supported standard values are: `bridge`, `host`, `none`, and `container:<name|id>`. Any other value is taken as a custom network's name to which this container should connect to.
+    #[serde(rename = "NetworkMode", skip_serializing_if = "Option::is_none")]
+    network_mode: Option<String>,
     /// A map of exposed container ports and the host port they should map to.
     #[serde(rename = "PortBindings", skip_serializing_if = "Option::is_none")]
     port_bindings:
@@ -174,9 +174,9 @@ pub struct HostConfig {
     /// A list of kernel capabilities to drop from the container.
     #[serde(rename = "CapDrop", skip_serializing_if = "Option::is_none")]
     cap_drop: Option<Vec<String>>,
-    // /// A list of DNS servers for the container to use.
-    // #[serde(rename = "Dns", skip_serializing_if = "Option::is_none")]
-    // dns: Option<Vec<String>>,
+    /// A list of DNS servers for the container to use.
+    #[serde(rename = "Dns", skip_serializing_if = "Option::is_none")]
+    dns: Option<Vec<String>>,
     // /// A list of DNS options.
     // #[serde(rename = "DnsOptions", skip_serializing_if = "Option::is_none")]
     // dns_options: Option<Vec<String>>,
@@ -234,9 +234,9 @@ pub struct HostConfig {
     // /// A list of kernel parameters (sysctls) to set in the container. For example: `{\"net.ipv4.ip_forward\": \"1\"}`
     // #[serde(rename = "Sysctls", skip_serializing_if = "Option::is_none")]
     // sysctls: Option<::std::collections::BTreeMap<String, String>>,
-    // /// Runtime to use with this container.
-    // #[serde(rename = "Runtime", skip_serializing_if = "Option::is_none")]
-    // runtime: Option<String>,
+    /// Runtime to use with this container.
+    #[serde(rename = "Runtime", skip_serializing_if = "Option::is_none")]
+    runtime: Option<String>,
     // /// Initial console size, as an `[height, width]` array. (Windows only)
     // #[serde(rename = "ConsoleSize", skip_serializing_if = "Option::is_none")]
     // console_size: Option<Vec<i32>>,
@@ -260,22 +260,22 @@ impl HostConfig {
             // blkio_device_write_bps: None,
             // blkio_device_read_i_ops: None,
             // blkio_device_write_i_ops: None,
-            // cpu_period: None,
-            // cpu_quota: None,
+            cpu_period: None,
+            cpu_quota: None,
             // cpu_realtime_period: None,
             // cpu_realtime_runtime: None,
             // cpuset_cpus: None,
             // cpuset_mems: None,
-            // devices: None,
-            // device_cgroup_rules: None,
+            devices: None,
+            device_cgroup_rules: None,
             // disk_quota: None,
             // kernel_memory: None,
-            // memory_reservation: None,
+            memory_reservation: None,
             // memory_swap: None,
             // memory_swappiness: None,
-            // nano_cp_us: None,
+            nano_cp_us: None,
             // oom_kill_disable: None,
-            // pids_limit: None,
+            pids_limit: None,
             // ulimits: None,
             // cpu_count: None,
             // cpu_percent: None,
@@ -283,8 +283,8 @@ impl HostConfig {
             // io_maximum_bandwidth: None,
             binds: None,
             // container_id_file: None,
-            // log_config: None,
-            // network_mode: None,
+            log_config: None,
+            network_mode: None,
             port_bindings: None,
             // restart_policy: None,
             // auto_remove: None,
@@ -293,7 +293,7 @@ impl HostConfig {
             mounts: None,
             cap_add: None,
             cap_drop: None,
-            // dns: None,
+            dns: None,
             // dns_options: None,
             // dns_search: None,
             extra_hosts: None,
@@ -313,7 +313,7 @@ impl HostConfig {
             // userns_mode: None,
             // shm_size: None,
             // sysctls: None,
-            // runtime: None,
+            runtime: None,
             // console_size: None,
             // isolation: None,
             other_properties: Default::default(),
@@ -503,39 +503,39 @@ impl HostConfig {
     //     self.blkio_device_write_i_ops = None;
     // }
 
-    // pub fn set_cpu_period(&mut self, cpu_period: i64) {
-    //     self.cpu_period = Some(cpu_period);
-    // }
+    pub fn set_cpu_period(&mut self, cpu_period: i64) {
+        self.cpu_period = Some(cpu_period);
+    }
 
-    // pub fn with_cpu_period(mut self, cpu_period: i64) -> Self {
-    //     self.cpu_period = Some(cpu_period);
-    //     self
-    // }
+    pub fn with_cpu_period(mut self, cpu_period: i64) -> Self {
+        self.cpu_period = Some(cpu_period);
+        self
+    }
 
-    // pub fn cpu_period(&self) -> Option<i64> {
-    //     self.cpu_period
-    // }
+    pub fn cpu_period(&self) -> Option<i64> {
+        self.cpu_period
+    }
 
-    // pub fn reset_cpu_period(&mut self) {
-    //     self.cpu_period = None;
-    // }
+    pub fn reset_cpu_period(&mut self) {
+        self.cpu_period = None;
+    }
 
-    // pub fn set_cpu_quota(&mut self, cpu_quota: i64) {
-    //     self.cpu_quota = Some(cpu_quota);
-    // }
+    pub fn set_cpu_quota(&mut self, cpu_quota: i64) {
+        self.cpu_quota = Some(cpu_quota);
+    }
 
-    // pub fn with_cpu_quota(mut self, cpu_quota: i64) -> Self {
-    //     self.cpu_quota = Some(cpu_quota);
-    //     self
-    // }
+    pub fn with_cpu_quota(mut self, cpu_quota: i64) -> Self {
+        self.cpu_quota = Some(cpu_quota);
+        self
+    }
 
-    // pub fn cpu_quota(&self) -> Option<i64> {
-    //     self.cpu_quota
-    // }
+    pub fn cpu_quota(&self) -> Option<i64> {
+        self.cpu_quota
+    }
 
-    // pub fn reset_cpu_quota(&mut self) {
-    //     self.cpu_quota = None;
-    // }
+    pub fn reset_cpu_quota(&mut self) {
+        self.cpu_quota = None;
+    }
 
     // pub fn set_cpu_realtime_period(&mut self, cpu_realtime_period: i64) {
     //     self.cpu_realtime_period = Some(cpu_realtime_period);
@@ -605,39 +605,39 @@ impl HostConfig {
     //     self.cpuset_mems = None;
     // }
 
-    // pub fn set_devices(&mut self, devices: Vec<crate::models::DeviceMapping>) {
-    //     self.devices = Some(devices);
-    // }
+    pub fn set_devices(&mut self, devices: Vec<crate::models::DeviceMapping>) {
+        self.devices = Some(devices);
+    }
 
-    // pub fn with_devices(mut self, devices: Vec<crate::models::DeviceMapping>) -> Self {
-    //     self.devices = Some(devices);
-    //     self
-    // }
+    pub fn with_devices(mut self, devices: Vec<crate::models::DeviceMapping>) -> Self {
+        self.devices = Some(devices);
+        self
+    }
 
-    // pub fn devices(&self) -> Option<&[crate::models::DeviceMapping]> {
-    //     self.devices.as_ref().map(AsRef::as_ref)
-    // }
+    pub fn devices(&self) -> Option<&[crate::models::DeviceMapping]> {
+        self.devices.as_ref().map(AsRef::as_ref)
+    }
 
-    // pub fn reset_devices(&mut self) {
-    //     self.devices = None;
-    // }
+    pub fn reset_devices(&mut self) {
+        self.devices = None;
+    }
 
-    // pub fn set_device_cgroup_rules(&mut self, device_cgroup_rules: Vec<String>) {
-    //     self.device_cgroup_rules = Some(device_cgroup_rules);
-    // }
+    pub fn set_device_cgroup_rules(&mut self, device_cgroup_rules: Vec<String>) {
+        self.device_cgroup_rules = Some(device_cgroup_rules);
+    }
 
-    // pub fn with_device_cgroup_rules(mut self, device_cgroup_rules: Vec<String>) -> Self {
-    //     self.device_cgroup_rules = Some(device_cgroup_rules);
-    //     self
-    // }
+    pub fn with_device_cgroup_rules(mut self, device_cgroup_rules: Vec<String>) -> Self {
+        self.device_cgroup_rules = Some(device_cgroup_rules);
+        self
+    }
 
-    // pub fn device_cgroup_rules(&self) -> Option<&[String]> {
-    //     self.device_cgroup_rules.as_ref().map(AsRef::as_ref)
-    // }
+    pub fn device_cgroup_rules(&self) -> Option<&[String]> {
+        self.device_cgroup_rules.as_ref().map(AsRef::as_ref)
+    }
 
-    // pub fn reset_device_cgroup_rules(&mut self) {
-    //     self.device_cgroup_rules = None;
-    // }
+    pub fn reset_device_cgroup_rules(&mut self) {
+        self.device_cgroup_rules = None;
+    }
 
     // pub fn set_disk_quota(&mut self, disk_quota: i64) {
     //     self.disk_quota = Some(disk_quota);
@@ -673,22 +673,22 @@ impl HostConfig {
     //     self.kernel_memory = None;
     // }
 
-    // pub fn set_memory_reservation(&mut self, memory_reservation: i64) {
-    //     self.memory_reservation = Some(memory_reservation);
-    // }
+    pub fn set_memory_reservation(&mut self, memory_reservation: i64) {
+        self.memory_reservation = Some(memory_reservation);
+    }
 
-    // pub fn with_memory_reservation(mut self, memory_reservation: i64) -> Self {
-    //     self.memory_reservation = Some(memory_reservation);
-    //     self
-    // }
+    pub fn with_memory_reservation(mut self, memory_reservation: i64) -> Self {
+        self.memory_reservation = Some(memory_reservation);
+        self
+    }
 
-    // pub fn memory_reservation(&self) -> Option<i64> {
-    //     self.memory_reservation
-    // }
+    pub fn memory_reservation(&self) -> Option<i64> {
+        self.memory_reservation
+    }
 
-    // pub fn reset_memory_reservation(&mut self) {
-    //     self.memory_reservation = None;
-    // }
+    pub fn reset_memory_reservation(&mut self) {
+        self.memory_reservation = None;
+    }
 
     // pub fn set_memory_swap(&mut self, memory_swap: i64) {
     //     self.memory_swap = Some(memory_swap);
@@ -724,22 +724,22 @@ impl HostConfig {
     //     self.memory_swappiness = None;
     // }
 
-    // pub fn set_nano_cp_us(&mut self, nano_cp_us: i64) {
-    //     self.nano_cp_us = Some(nano_cp_us);
-    // }
+    pub fn set_nano_cp_us(&mut self, nano_cp_us: i64) {
+        self.nano_cp_us = Some(nano_cp_us);
+    }
 
-    // pub fn with_nano_cp_us(mut self, nano_cp_us: i64) -> Self {
-    //     self.nano_cp_us = Some(nano_cp_us);
-    //     self
-    // }
+    pub fn with_nano_cp_us(mut self, nano_cp_us: i64) -> Self {
+        self.nano_cp_us = Some(nano_cp_us);
+        self
+    }
 
-    // pub fn nano_cp_us(&self) -> Option<i64> {
-    //     self.nano_cp_us
-    // }
+    pub fn nano_cp_us(&self) -> Option<i64> {
+        self.nano_cp_us
+    }
 
-    // pub fn reset_nano_cp_us(&mut self) {
-    //     self.nano_cp_us = None;
-    // }
+    pub fn reset_nano_cp_us(&mut self) {
+        self.nano_cp_us = None;
+    }
 
     // pub fn set_oom_kill_disable(&mut self, oom_kill_disable: bool) {
     //     self.oom_kill_disable = Some(oom_kill_disable);
@@ -758,22 +758,22 @@ impl HostConfig {
     //     self.oom_kill_disable = None;
     // }
 
-    // pub fn set_pids_limit(&mut self, pids_limit: i64) {
-    //     self.pids_limit = Some(pids_limit);
-    // }
+    pub fn set_pids_limit(&mut self, pids_limit: i64) {
+        self.pids_limit = Some(pids_limit);
+    }
 
-    // pub fn with_pids_limit(mut self, pids_limit: i64) -> Self {
-    //     self.pids_limit = Some(pids_limit);
-    //     self
-    // }
+    pub fn with_pids_limit(mut self, pids_limit: i64) -> Self {
+        self.pids_limit = Some(pids_limit);
+        self
+    }
 
-    // pub fn pids_limit(&self) -> Option<i64> {
-    //     self.pids_limit
-    // }
+    pub fn pids_limit(&self) -> Option<i64> {
+        self.pids_limit
+    }
 
-    // pub fn reset_pids_limit(&mut self) {
-    //     self.pids_limit = None;
-    // }
+    pub fn reset_pids_limit(&mut self) {
+        self.pids_limit = None;
+    }
 
     // pub fn set_ulimits(&mut self, ulimits: Vec<crate::models::ResourcesUlimits>) {
     //     self.ulimits = Some(ulimits);
@@ -894,39 +894,39 @@ impl HostConfig {
     //     self.container_id_file = None;
     // }
 
-    // pub fn set_log_config(&mut self, log_config: crate::models::HostConfigLogConfig) {
-    //     self.log_config = Some(log_config);
-    // }
+    pub fn set_log_config(&mut self, log_config: crate::models::HostConfigLogConfig) {
+        self.log_config = Some(log_config);
+    }
 
-    // pub fn with_log_config(mut self, log_config: crate::models::HostConfigLogConfig) -> Self {
-    //     self.log_config = Some(log_config);
-    //     self
-    // }
+    pub fn with_log_config(mut self, log_config: crate::models::HostConfigLogConfig) -> Self {
+        self.log_config = Some(log_config);
+        self
+    }
 
-    // pub fn log_config(&self) -> Option<&crate::models::HostConfigLogConfig> {
-    //     self.log_config.as_ref()
-    // }
+    pub fn log_config(&self) -> Option<&crate::models::HostConfigLogConfig> {
+        self.log_config.as_ref()
+    }
 
-    // pub fn reset_log_config(&mut self) {
-    //     self.log_config = None;
-    // }
+    pub fn reset_log_config(&mut self) {
+        self.log_config = None;
+    }
 
-    // pub fn set_network_mode(&mut self, network_mode: String) {
-    //     self.network_mode = Some(network_mode);
-    // }
+    pub fn set_network_mode(&mut self, network_mode: String) {
+        self.network_mode = Some(network_mode);
+    }
 
-    // pub fn with_network_mode(mut self, network_mode: String) -> Self {
-    //     self.network_mode = Some(network_mode);
-    //     self
-    // }
+    pub fn with_network_mode(mut self, network_mode: String) -> Self {
+        self.network_mode = Some(network_mode);
+        self
+    }
 
-    // pub fn network_mode(&self) -> Option<&str> {
-    //     self.network_mode.as_ref().map(AsRef::as_ref)
-    // }
+    pub fn network_mode(&self) -> Option<&str> {
+        self.network_mode.as_ref().map(AsRef::as_ref)
+    }
 
-    // pub fn reset_network_mode(&mut self) {
-    //     self.network_mode = None;
-    // }
+    pub fn reset_network_mode(&mut self) {
+        self.network_mode = None;
+    }
 
     pub fn set_port_bindings(
         &mut self,
@@ -1079,22 +1079,22 @@ impl HostConfig {
         self.cap_drop = None;
     }
 
-    // pub fn set_dns(&mut self, dns: Vec<String>) {
-    //     self.dns = Some(dns);
-    // }
+    pub fn set_dns(&mut self, dns: Vec<String>) {
+        self.dns = Some(dns);
+    }
 
-    // pub fn with_dns(mut self, dns: Vec<String>) -> Self {
-    //     self.dns = Some(dns);
-    //     self
-    // }
+    pub fn with_dns(mut self, dns: Vec<String>) -> Self {
+        self.dns = Some(dns);
+        self
+    }
 
-    // pub fn dns(&self) -> Option<&[String]> {
-    //     self.dns.as_ref().map(AsRef::as_ref)
-    // }
+    pub fn dns(&self) -> Option<&[String]> {
+        self.dns.as_ref().map(AsRef::as_ref)
+    }
 
-    // pub fn reset_dns(&mut self) {
-    //     self.dns = None;
-    // }
+    pub fn reset_dns(&mut self) {
+        self.dns = None;
+    }
 
     // pub fn set_dns_options(&mut self, dns_options: Vec<String>) {
     //     self.dns_options = Some(dns_options);
@@ -1422,22 +1422,22 @@ impl HostConfig {
     //     self.sysctls = None;
     // }
 
-    // pub fn set_runtime(&mut self, runtime: String) {
-    //     self.runtime = Some(runtime);
-    // }
+    pub fn set_runtime(&mut self, runtime: String) {
+        self.runtime = Some(runtime);
+    }
 
-    // pub fn with_runtime(mut self, runtime: String) -> Self {
-    //     self.runtime = Some(runtime);
-    //     self
-    // }
+    pub fn with_runtime(mut self, runtime: String) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
 
-    // pub fn runtime(&self) -> Option<&str> {
-    //     self.runtime.as_ref().map(AsRef::as_ref)
-    // }
+    pub fn runtime(&self) -> Option<&str> {
+        self.runtime.as_ref().map(AsRef::as_ref)
+    }
 
-    // pub fn reset_runtime(&mut self) {
-    //     self.runtime = None;
-    // }
+    pub fn reset_runtime(&mut self) {
+        self.runtime = None;
+    }
 
     // pub fn set_console_size(&mut self, console_size: Vec<i32>) {
     //     self.console_size = Some(console_size);
@@ -13,12 +13,39 @@
 #[allow(unused_imports)]
 use serde_json::Value;
 
+// DEVNOTE: Why is most of this type commented out?
+//
+// We do not want to restrict the properties that the user can set in their create options, because future versions of Docker can add new properties
+// that we don't define here.
+//
+// So this type has a `#[serde(flatten)] BTreeMap` field to collect all the extra properties that we don't have a struct field for.
+//
+// But if an existing field references another type under `crate::models::`, then that would still be parsed lossily, so we would have to also add
+// a `#[serde(flatten)] BTreeMap` field there. And if that type has fields that reference types under `crate::models::` ...
+//
+// To avoid having to do this for effectively the whole crate, instead we've just commented out the fields we don't use in our code.
+//
+// Note: We're using BTreeMap instead of HashMap because aziot-edged stores a hash of its local config (whose object representation uses this struct)
+// to detect changes. Since different HashMaps with the same keys aren't guaranteed to serialize in the same order (and thus won't compare equal),
+// we need to use another map type that can provide that guarantee.
+//
+// ---
+//
+// If you need to access a commented out field, uncomment it.
+//
+// - If it's a simple built-in type, then that is all you need to do.
+//
+// - Otherwise if it references another type under `crate::models::`, then ensure that that type also has a `#[serde(flatten)] BTreeMap` property
+//   and is commented out as much as possible. Also copy this devnote there for future readers.
+
 #[derive(Debug, serde_derive::Serialize, serde_derive::Deserialize, Clone)]
 pub struct HostConfigLogConfig {
     #[serde(rename = "Type", skip_serializing_if = "Option::is_none")]
     _type: Option<String>,
     #[serde(rename = "Config", skip_serializing_if = "Option::is_none")]
-    config: Option<::std::collections::HashMap<String, String>>,
+    config: Option<::std::collections::BTreeMap<String, String>>,
+    #[serde(flatten)]
+    other_properties: std::collections::BTreeMap<String, serde_json::Value>,
 }
 
 impl HostConfigLogConfig {
@@ -27,6 +54,7 @@ impl HostConfigLogConfig {
         HostConfigLogConfig {
             _type: None,
             config: None,
+            other_properties: Default::default(),
         }
     }
 
@@ -47,16 +75,16 @@ impl HostConfigLogConfig {
         self._type = None;
     }
 
-    pub fn set_config(&mut self, config: ::std::collections::HashMap<String, String>) {
+    pub fn set_config(&mut self, config: ::std::collections::BTreeMap<String, String>) {
         self.config = Some(config);
     }
 
-    pub fn with_config(mut self, config: ::std::collections::HashMap<String, String>) -> Self {
+    pub fn with_config(mut self, config: ::std::collections::BTreeMap<String, String>) -> Self {
         self.config = Some(config);
         self
     }
 
-    pub fn config(&self) -> Option<&::std::collections::HashMap<String, String>> {
+    pub fn config(&self) -> Option<&::std::collections::BTreeMap<String, String>> {
         self.config.as_ref()
     }
 
@@ -18,6 +18,8 @@ pub struct ImageSummary {
     id: String,
     #[serde(deserialize_with = "parse_sequence", rename = "RepoTags")]
     repo_tags: Vec<String>,
+    #[serde(default, deserialize_with = "parse_sequence", rename = "RepoDigests")]
+    repo_digests: Vec<String>,
 }
 
 fn parse_sequence<'de, D>(d: D) -> Result<Vec<String>, D::Error>
@@ -29,7 +31,11 @@ where
 
 impl ImageSummary {
     pub fn new(id: String, repo_tags: Vec<String>) -> Self {
-        ImageSummary { id, repo_tags }
+        ImageSummary {
+            id,
+            repo_tags,
+            repo_digests: Vec::new(),
+        }
     }
 
     pub fn set_id(&mut self, id: String) {
@@ -57,4 +63,17 @@ impl ImageSummary {
     pub fn repo_tags(&self) -> &[String] {
         &self.repo_tags
     }
+
+    pub fn set_repo_digests(&mut self, repo_digests: Vec<String>) {
+        self.repo_digests = repo_digests;
+    }
+
+    pub fn with_repo_digests(mut self, repo_digests: Vec<String>) -> Self {
+        self.repo_digests = repo_digests;
+        self
+    }
+
+    pub fn repo_digests(&self) -> &[String] {
+        &self.repo_digests
+    }
 }
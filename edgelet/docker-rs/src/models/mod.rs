@@ -74,8 +74,12 @@ mod generic_resources_inner_named_resource_spec;
 pub use self::generic_resources_inner_named_resource_spec::GenericResourcesInnerNamedResourceSpec;
 mod graph_driver_data;
 pub use self::graph_driver_data::GraphDriverData;
+mod health;
+pub use self::health::Health;
 mod health_config;
 pub use self::health_config::HealthConfig;
+mod healthcheck_result;
+pub use self::healthcheck_result::HealthcheckResult;
 mod host_config_log_config;
 pub use self::host_config_log_config::HostConfigLogConfig;
 mod host_config_port_bindings;
@@ -178,6 +182,8 @@ mod network;
 pub use self::network::Network;
 mod network_config;
 pub use self::network_config::NetworkConfig;
+mod network_connect_config;
+pub use self::network_connect_config::NetworkConnectConfig;
 mod network_container;
 pub use self::network_container::NetworkContainer;
 mod network_settings;
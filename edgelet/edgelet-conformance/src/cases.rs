@@ -0,0 +1,505 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use serde_json::Value;
+
+use crate::schema::{self, PROBE};
+use crate::spec::{Location, Operation, Spec};
+
+const PATH_SEGMENT: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.');
+
+/// An api-version that no daemon serves.
+const BAD_API_VERSION: &str = "1900-01-01";
+
+/// Path segments that handlers must cope with without failing.
+fn hostile_segments() -> [(&'static str, String); 3] {
+    [
+        ("long", "a".repeat(4096)),
+        ("traversal", "../../../etc/passwd".to_string()),
+        ("control", "\0\r\n".to_string()),
+    ]
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Expect {
+    /// A response whose status and body are documented for the operation.
+    Documented,
+
+    /// A 4xx response.
+    ClientError,
+
+    /// Any response other than a server error. Used for requests that the daemon may
+    /// reasonably accept, such as bodies with unexpected property types where the
+    /// property is free-form.
+    NoServerError,
+}
+
+#[derive(Clone, Debug)]
+pub struct Case {
+    /// Index of the operation in `Spec::operations`.
+    pub operation: usize,
+    pub name: String,
+    pub method: hyper::Method,
+
+    /// Path and query of the request.
+    pub uri: String,
+    pub body: Option<Vec<u8>>,
+    pub expect: Expect,
+}
+
+/// The parts of a request that cases vary.
+#[derive(Clone)]
+struct Request {
+    path: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    body: Option<Value>,
+}
+
+impl Request {
+    /// A request with valid values for all required parameters.
+    fn valid(spec: &Spec, operation: &Operation) -> Self {
+        let mut request = Request {
+            path: Vec::new(),
+            query: Vec::new(),
+            body: None,
+        };
+
+        for parameter in &operation.parameters {
+            if !parameter.required {
+                continue;
+            }
+
+            match parameter.location {
+                Location::Path => request.path.push((
+                    parameter.name.clone(),
+                    schema::example(spec, &parameter.schema)
+                        .as_str()
+                        .map_or_else(|| "1".to_string(), str::to_string),
+                )),
+
+                Location::Query => {
+                    let value = if parameter.name == "api-version" {
+                        // The parameter's default is often left at the first version.
+                        spec.version.clone()
+                    } else {
+                        match schema::example(spec, &parameter.schema) {
+                            Value::String(value) => value,
+                            value => value.to_string(),
+                        }
+                    };
+
+                    request.query.push((parameter.name.clone(), value));
+                }
+
+                Location::Body => request.body = Some(schema::example(spec, &parameter.schema)),
+
+                Location::Header => {}
+            }
+        }
+
+        request
+    }
+
+    fn set_query(&mut self, name: &str, value: Option<&str>) {
+        self.query.retain(|(query, _)| query != name);
+
+        if let Some(value) = value {
+            self.query.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    fn uri(&self, operation: &Operation) -> String {
+        let mut uri = operation.path.clone();
+
+        for (name, value) in &self.path {
+            let value = percent_encoding::utf8_percent_encode(value, PATH_SEGMENT).to_string();
+
+            uri = uri.replace(&format!("{{{name}}}"), &value);
+        }
+
+        if !self.query.is_empty() {
+            let query = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(&self.query)
+                .finish();
+
+            uri.push('?');
+            uri.push_str(&query);
+        }
+
+        uri
+    }
+}
+
+struct Generator<'a> {
+    spec: &'a Spec,
+    cases: Vec<Case>,
+}
+
+impl Generator<'_> {
+    fn push(
+        &mut self,
+        operation: usize,
+        name: impl Into<String>,
+        request: &Request,
+        expect: Expect,
+    ) {
+        let body = request
+            .body
+            .as_ref()
+            .map(|body| body.to_string().into_bytes());
+
+        self.push_raw(operation, name, request, body, expect);
+    }
+
+    fn push_raw(
+        &mut self,
+        operation: usize,
+        name: impl Into<String>,
+        request: &Request,
+        body: Option<Vec<u8>>,
+        expect: Expect,
+    ) {
+        let op = &self.spec.operations[operation];
+
+        self.cases.push(Case {
+            operation,
+            name: name.into(),
+            method: op.method.clone(),
+            uri: request.uri(op),
+            body,
+            expect,
+        });
+    }
+
+    fn operation(&mut self, index: usize) {
+        let spec = self.spec;
+        let operation = &spec.operations[index];
+        let valid = Request::valid(spec, operation);
+
+        // Requests the daemon accepts are only sent for operations that don't change
+        // anything, so that the checks can run against a device that is in use.
+        if operation.is_safe() {
+            self.push(index, "valid", &valid, Expect::Documented);
+        }
+
+        let mut request = valid.clone();
+        request.set_query("api-version", None);
+        self.push(index, "no-api-version", &request, Expect::ClientError);
+
+        let mut request = valid.clone();
+        request.set_query("api-version", Some(BAD_API_VERSION));
+        self.push(index, "bad-api-version", &request, Expect::ClientError);
+
+        for parameter in &operation.parameters {
+            match parameter.location {
+                Location::Query if parameter.name != "api-version" => {
+                    if parameter.required {
+                        let mut request = valid.clone();
+                        request.set_query(&parameter.name, None);
+                        self.push(
+                            index,
+                            format!("no-{}", parameter.name),
+                            &request,
+                            Expect::ClientError,
+                        );
+                    }
+
+                    // Free-form strings accept the probe, so only typed parameters are
+                    // expected to reject it.
+                    let typed = parameter.schema.get("enum").is_some()
+                        || matches!(
+                            parameter.schema["type"].as_str(),
+                            Some("integer" | "number" | "boolean")
+                        );
+
+                    if typed {
+                        let mut request = valid.clone();
+                        request.set_query(&parameter.name, Some(PROBE));
+                        self.push(
+                            index,
+                            format!("invalid-{}", parameter.name),
+                            &request,
+                            Expect::ClientError,
+                        );
+                    }
+                }
+
+                Location::Path => {
+                    for (kind, segment) in hostile_segments() {
+                        let mut request = valid.clone();
+                        for (name, value) in &mut request.path {
+                            if *name == parameter.name {
+                                *value = segment.clone();
+                            }
+                        }
+
+                        self.push(
+                            index,
+                            format!("{kind}-{}", parameter.name),
+                            &request,
+                            Expect::NoServerError,
+                        );
+                    }
+                }
+
+                Location::Body => self.body(index, &valid, &parameter.schema),
+
+                Location::Query | Location::Header => {}
+            }
+        }
+    }
+
+    fn body(&mut self, index: usize, valid: &Request, body_schema: &Value) {
+        let spec = self.spec;
+
+        self.push_raw(index, "no-body", valid, None, Expect::ClientError);
+        self.push_raw(
+            index,
+            "malformed-body",
+            valid,
+            Some(b"{".to_vec()),
+            Expect::ClientError,
+        );
+
+        let mut request = valid.clone();
+        request.body = Some(schema::wrong_type(spec, body_schema));
+        self.push(index, "wrong-type-body", &request, Expect::ClientError);
+
+        let body_schema = spec.resolve(body_schema);
+        let Some(valid_body) = valid.body.as_ref().and_then(Value::as_object) else {
+            return;
+        };
+
+        for name in valid_body.keys() {
+            let mut request = valid.clone();
+            if let Some(Value::Object(body)) = &mut request.body {
+                body.remove(name);
+            }
+
+            self.push(index, format!("no-{name}"), &request, Expect::NoServerError);
+        }
+
+        for (name, property) in body_schema["properties"].as_object().into_iter().flatten() {
+            let mut request = valid.clone();
+            if let Some(Value::Object(body)) = &mut request.body {
+                body.insert(name.clone(), schema::wrong_type(spec, property));
+            }
+
+            self.push(
+                index,
+                format!("wrong-type-{name}"),
+                &request,
+                Expect::NoServerError,
+            );
+        }
+    }
+
+    /// A method that the path doesn't serve.
+    fn unsupported_method(&mut self, index: usize) {
+        let operation = &self.spec.operations[index];
+
+        let served = self
+            .spec
+            .operations
+            .iter()
+            .any(|other| other.path == operation.path && other.method == hyper::Method::PATCH);
+        if served {
+            return;
+        }
+
+        let valid = Request::valid(self.spec, operation);
+
+        self.cases.push(Case {
+            operation: index,
+            name: "unsupported-method".to_string(),
+            method: hyper::Method::PATCH,
+            uri: valid.uri(operation),
+            body: None,
+            expect: Expect::ClientError,
+        });
+    }
+}
+
+/// Generate requests for every operation in `spec`.
+///
+/// Apart from requests for read-only operations, every request is invalid in some
+/// way, and any names in them are [`PROBE`], so that they don't change the device.
+pub fn generate(spec: &Spec) -> Vec<Case> {
+    let mut generator = Generator {
+        spec,
+        cases: Vec::new(),
+    };
+
+    let mut paths = std::collections::BTreeSet::new();
+
+    for index in 0..spec.operations.len() {
+        generator.operation(index);
+
+        if paths.insert(&spec.operations[index].path) {
+            generator.unsupported_method(index);
+        }
+    }
+
+    generator.cases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate, Expect};
+    use crate::Spec;
+
+    const SPEC: &str = r"
+swagger: '2.0'
+info:
+  title: Test API
+  version: '2022-08-03'
+paths:
+  /modules/{name}/logs:
+    get:
+      operationId: ModuleLogs
+      parameters:
+        - $ref: '#/parameters/api-version'
+        - in: path
+          name: name
+          type: string
+        - in: query
+          name: follow
+          type: boolean
+        - in: query
+          name: tail
+          type: string
+      responses:
+        '200':
+          description: Ok
+  /modules/{name}:
+    put:
+      operationId: UpdateModule
+      parameters:
+        - $ref: '#/parameters/api-version'
+        - in: path
+          name: name
+          type: string
+        - in: body
+          name: module
+          required: true
+          schema:
+            $ref: '#/definitions/ModuleSpec'
+      responses:
+        '200':
+          description: Ok
+definitions:
+  ModuleSpec:
+    type: object
+    required:
+      - name
+    properties:
+      name:
+        type: string
+      config:
+        type: object
+parameters:
+  api-version:
+    name: api-version
+    in: query
+    required: true
+    type: string
+    default: '2018-06-28'
+";
+
+    fn case<'a>(cases: &'a [super::Case], operation: usize, name: &str) -> &'a super::Case {
+        cases
+            .iter()
+            .find(|case| case.operation == operation && case.name == name)
+            .unwrap_or_else(|| panic!("no case {name} for operation {operation}"))
+    }
+
+    #[test]
+    fn safe_operation() {
+        let spec = Spec::parse(SPEC).unwrap();
+        let cases = generate(&spec);
+
+        let valid = case(&cases, 0, "valid");
+        assert_eq!(hyper::Method::GET, valid.method);
+        assert_eq!(
+            "/modules/conformance-probe/logs?api-version=2022-08-03",
+            valid.uri
+        );
+        assert_eq!(Expect::Documented, valid.expect);
+
+        assert_eq!(
+            "/modules/conformance-probe/logs",
+            case(&cases, 0, "no-api-version").uri
+        );
+        assert_eq!(
+            "/modules/conformance-probe/logs?api-version=1900-01-01",
+            case(&cases, 0, "bad-api-version").uri
+        );
+        assert_eq!(
+            "/modules/conformance-probe/logs?api-version=2022-08-03&follow=conformance-probe",
+            case(&cases, 0, "invalid-follow").uri
+        );
+        assert_eq!(
+            "/modules/..%2F..%2F..%2Fetc%2Fpasswd/logs?api-version=2022-08-03",
+            case(&cases, 0, "traversal-name").uri
+        );
+        assert_eq!(
+            Expect::NoServerError,
+            case(&cases, 0, "control-name").expect
+        );
+
+        // Free-form strings aren't expected to be rejected.
+        assert!(cases.iter().all(|case| case.name != "invalid-tail"));
+
+        let unsupported = case(&cases, 0, "unsupported-method");
+        assert_eq!(hyper::Method::PATCH, unsupported.method);
+        assert_eq!(Expect::ClientError, unsupported.expect);
+    }
+
+    #[test]
+    fn unsafe_operation() {
+        let spec = Spec::parse(SPEC).unwrap();
+        let cases = generate(&spec);
+
+        // Nothing that the daemon should accept is sent for operations that change state.
+        assert!(cases
+            .iter()
+            .all(|case| case.operation != 1 || case.name != "valid"));
+
+        assert_eq!(None, case(&cases, 1, "no-body").body);
+        assert_eq!(Some(b"{".to_vec()), case(&cases, 1, "malformed-body").body);
+        assert_eq!(
+            Some(b"[]".to_vec()),
+            case(&cases, 1, "wrong-type-body").body
+        );
+        assert_eq!(Some(b"{}".to_vec()), case(&cases, 1, "no-name").body);
+        assert_eq!(
+            Some(br#"{"config":[],"name":"conformance-probe"}"#.to_vec()),
+            case(&cases, 1, "wrong-type-config").body
+        );
+        assert_eq!(
+            Some(br#"{"name":1}"#.to_vec()),
+            case(&cases, 1, "wrong-type-name").body
+        );
+        assert_eq!(
+            Expect::NoServerError,
+            case(&cases, 1, "wrong-type-name").expect
+        );
+    }
+
+    #[test]
+    fn api_definitions() {
+        let spec =
+            Spec::parse(include_str!("../../api/managementVersion_2022_08_03.yaml")).unwrap();
+        let cases = generate(&spec);
+
+        for case in &cases {
+            assert!(!case.uri.contains('{'), "unfilled path in {}", case.uri);
+
+            if case.expect == Expect::Documented {
+                assert_eq!(hyper::Method::GET, case.method);
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("invalid API definition: {0}")]
+    Spec(String),
+
+    #[error("invalid daemon URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("daemon did not respond within {0} seconds")]
+    NotReady(u64),
+
+    #[error("daemon stopped responding after {0}")]
+    Unresponsive(String),
+}
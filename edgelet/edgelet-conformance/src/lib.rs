@@ -0,0 +1,38 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Conformance checks of a running daemon against the management and workload API
+//! definitions in the `api` directory.
+//!
+//! Requests are generated from a definition, most of them invalid in some way, and sent
+//! to the daemon. Server errors, requests that go unanswered (such as when a handler
+//! panics), and responses that don't match the definition are reported as failures.
+//!
+//! ```sh
+//! edgelet-conformance \
+//!     --spec api/managementVersion_2022_08_03.yaml \
+//!     --uri unix:///var/run/iotedge/mgmt.sock \
+//!     --harness
+//! ```
+//!
+//! In harness mode, the daemon is given time to start, each result is printed as a JSON
+//! line, and the exit code is 1 if any case failed or 2 if the daemon stopped responding.
+
+#![deny(rust_2018_idioms, warnings)]
+#![deny(clippy::all, clippy::pedantic)]
+#![allow(
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::module_name_repetitions,
+    clippy::must_use_candidate
+)]
+
+mod cases;
+mod error;
+mod runner;
+pub mod schema;
+mod spec;
+
+pub use cases::{generate, Case, Expect};
+pub use error::Error;
+pub use runner::{check, Client};
+pub use spec::{Location, Operation, Parameter, Spec};
@@ -0,0 +1,147 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#![deny(rust_2018_idioms, warnings)]
+#![deny(clippy::all, clippy::pedantic)]
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::{crate_description, crate_name, Arg, ArgAction, Command};
+
+use edgelet_conformance::{check, generate, Client, Error, Spec};
+
+#[tokio::main]
+async fn main() {
+    match run().await {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            eprintln!("{err:#}");
+
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Returns whether every case passed.
+#[allow(clippy::too_many_lines)]
+async fn run() -> anyhow::Result<bool> {
+    let matches = Command::new(crate_name!())
+        .about(crate_description!())
+        .arg(
+            Arg::new("spec")
+                .long("spec")
+                .help("API definition to check the daemon against, from the api directory")
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(true),
+        )
+        .arg(
+            Arg::new("uri")
+                .long("uri")
+                .help("URI of the daemon's API, such as unix:///var/run/iotedge/mgmt.sock")
+                .value_parser(clap::value_parser!(url::Url))
+                .required(true),
+        )
+        .arg(
+            Arg::new("skip")
+                .long("skip")
+                .help("Operation ID to skip. May be repeated")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("harness")
+                .long("harness")
+                .help("Wait for the daemon to start and print results as JSON lines, for CI")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("wait")
+                .long("wait")
+                .help("Seconds to wait for the daemon to start in harness mode")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("30"),
+        )
+        .get_matches();
+
+    let path = matches.get_one::<PathBuf>("spec").expect("arg is required");
+    let uri = matches.get_one::<url::Url>("uri").expect("arg is required");
+    let skip: Vec<&String> = matches.get_many("skip").into_iter().flatten().collect();
+    let harness = matches.get_flag("harness");
+    let wait = *matches.get_one::<u64>("wait").expect("arg has a default");
+
+    let spec = std::fs::read_to_string(path)
+        .with_context(|| format!("could not read {}", path.display()))?;
+    let spec = Spec::parse(&spec)?;
+
+    let client = Client::new(uri)?;
+
+    if harness {
+        client
+            .wait_until_alive(std::time::Duration::from_secs(wait))
+            .await?;
+    }
+
+    let cases = generate(&spec);
+    let mut sent = 0;
+    let mut failed = 0;
+
+    for case in &cases {
+        let operation = &spec.operations[case.operation];
+        if skip.contains(&&operation.id) {
+            continue;
+        }
+
+        sent += 1;
+
+        let result = match client
+            .send(case.method.clone(), &case.uri, case.body.clone())
+            .await
+        {
+            Ok((status, body)) => check(&spec, case, status, &body),
+            Err(err) => Err(err),
+        };
+
+        if harness {
+            let (outcome, reason) = match &result {
+                Ok(()) => ("pass", None),
+                Err(reason) => ("fail", Some(reason)),
+            };
+
+            println!(
+                "{}",
+                serde_json::json!({
+                    "operation": operation.id,
+                    "case": case.name,
+                    "method": case.method.as_str(),
+                    "uri": case.uri,
+                    "result": outcome,
+                    "reason": reason,
+                })
+            );
+        } else if let Err(reason) = &result {
+            println!(
+                "FAIL {} {} ({} {}): {}",
+                operation.id, case.name, case.method, case.uri, reason
+            );
+        }
+
+        if result.is_err() {
+            failed += 1;
+
+            // A handler that panics takes the daemon down with it, so nothing that
+            // follows would get a response.
+            if !client.is_alive().await {
+                return Err(Error::Unresponsive(format!("{} {}", operation.id, case.name)).into());
+            }
+        }
+    }
+
+    if !harness {
+        println!(
+            "{} API {}: {} cases, {} failed",
+            spec.title, spec.version, sent, failed
+        );
+    }
+
+    Ok(failed == 0)
+}
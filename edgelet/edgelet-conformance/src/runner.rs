@@ -0,0 +1,263 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::UrlExt;
+
+use crate::{schema, Case, Error, Expect, Spec};
+
+/// Handlers that take longer than this to respond are treated as hung.
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+pub struct Client {
+    connector: http_common::Connector,
+    host: String,
+}
+
+impl Client {
+    pub fn new(url: &url::Url) -> Result<Self, Error> {
+        let connector =
+            http_common::Connector::new(url).map_err(|err| Error::InvalidUrl(err.to_string()))?;
+
+        let base_path = url
+            .to_base_path()
+            .map_err(|err| Error::InvalidUrl(err.to_string()))?;
+        let base_path = base_path
+            .to_str()
+            .ok_or_else(|| Error::InvalidUrl(url.to_string()))?;
+        let host = hex::encode(base_path.as_bytes());
+
+        Ok(Client { connector, host })
+    }
+
+    /// Send a request. Returns an error if the daemon didn't respond.
+    pub async fn send(
+        &self,
+        method: hyper::Method,
+        uri: &str,
+        body: Option<Vec<u8>>,
+    ) -> Result<(hyper::StatusCode, Vec<u8>), String> {
+        let request = hyper::Request::builder()
+            .method(method)
+            .uri(format!("unix://{}:0{}", self.host, uri));
+
+        let request = match body {
+            Some(body) => request
+                .header(hyper::header::CONTENT_TYPE, "application/json")
+                .body(hyper::Body::from(body)),
+            None => request.body(hyper::Body::empty()),
+        }
+        .map_err(|err| format!("invalid request: {err}"))?;
+
+        let client = self.connector.clone().into_client();
+
+        let response = async {
+            let response = client.request(request).await?;
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await?;
+
+            Ok::<_, hyper::Error>((status, body.to_vec()))
+        };
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, response).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(err)) => Err(format!("no response: {err}")),
+            Err(_) => Err(format!(
+                "no response within {} seconds",
+                REQUEST_TIMEOUT.as_secs()
+            )),
+        }
+    }
+
+    /// Whether the daemon answers requests at all. Any response will do.
+    pub async fn is_alive(&self) -> bool {
+        self.send(hyper::Method::GET, "/", None).await.is_ok()
+    }
+
+    /// Wait for the daemon to answer requests, such as when it has just been started.
+    pub async fn wait_until_alive(&self, timeout: std::time::Duration) -> Result<(), Error> {
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        while !self.is_alive().await {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(Error::NotReady(timeout.as_secs()));
+            }
+
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+
+        Ok(())
+    }
+}
+
+/// Check the daemon's response to `case` against the definition.
+pub fn check(
+    spec: &Spec,
+    case: &Case,
+    status: hyper::StatusCode,
+    body: &[u8],
+) -> Result<(), String> {
+    let operation = &spec.operations[case.operation];
+
+    if status.is_server_error() {
+        return Err(format!(
+            "server error {status}: {}",
+            String::from_utf8_lossy(body)
+        ));
+    }
+
+    if case.expect == Expect::ClientError && !status.is_client_error() {
+        return Err(format!("expected a client error, got {status}"));
+    }
+
+    let schema = if status.is_success() {
+        operation
+            .response(status)
+            .ok_or_else(|| format!("{status} is not documented for {}", operation.id))?
+    } else {
+        match operation
+            .response(status)
+            .or_else(|| operation.default_response())
+        {
+            Some(schema) => schema,
+            None if case.expect == Expect::Documented => {
+                return Err(format!("{status} is not documented for {}", operation.id));
+            }
+            None => return Ok(()),
+        }
+    };
+
+    // Some errors, such as for requests that match no route, have no body.
+    if schema.is_null() || (body.is_empty() && !status.is_success()) {
+        return Ok(());
+    }
+
+    let body: serde_json::Value = serde_json::from_slice(body)
+        .map_err(|err| format!("{status} response is not valid JSON: {err}"))?;
+
+    let errors = schema::validate(spec, schema, &body);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{status} response doesn't match the definition: {}",
+            errors.join("; ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::StatusCode;
+
+    use super::check;
+    use crate::{Case, Expect, Spec};
+
+    const SPEC: &str = r"
+swagger: '2.0'
+info:
+  title: Test API
+  version: '2022-08-03'
+paths:
+  /modules:
+    get:
+      operationId: ListModules
+      responses:
+        '200':
+          description: Ok
+          schema:
+            type: object
+            required:
+              - modules
+            properties:
+              modules:
+                type: array
+        default:
+          description: Error
+          schema:
+            $ref: '#/definitions/ErrorResponse'
+definitions:
+  ErrorResponse:
+    type: object
+    required:
+      - message
+    properties:
+      message:
+        type: string
+";
+
+    fn case(expect: Expect) -> Case {
+        Case {
+            operation: 0,
+            name: "test".to_string(),
+            method: hyper::Method::GET,
+            uri: "/modules".to_string(),
+            body: None,
+            expect,
+        }
+    }
+
+    #[test]
+    fn documented() {
+        let spec = Spec::parse(SPEC).unwrap();
+        let case = case(Expect::Documented);
+
+        check(&spec, &case, StatusCode::OK, br#"{"modules":[]}"#).unwrap();
+        check(
+            &spec,
+            &case,
+            StatusCode::NOT_FOUND,
+            br#"{"message":"not found"}"#,
+        )
+        .unwrap();
+
+        // Schema drift.
+        let err = check(&spec, &case, StatusCode::OK, br#"{"items":[]}"#).unwrap_err();
+        assert!(err.contains("missing required property"), "{}", err);
+
+        let err = check(&spec, &case, StatusCode::NO_CONTENT, b"").unwrap_err();
+        assert!(err.contains("not documented"), "{}", err);
+
+        let err = check(&spec, &case, StatusCode::OK, b"modules").unwrap_err();
+        assert!(err.contains("not valid JSON"), "{}", err);
+    }
+
+    #[test]
+    fn client_error() {
+        let spec = Spec::parse(SPEC).unwrap();
+        let case = case(Expect::ClientError);
+
+        check(
+            &spec,
+            &case,
+            StatusCode::BAD_REQUEST,
+            br#"{"message":"bad"}"#,
+        )
+        .unwrap();
+        check(&spec, &case, StatusCode::METHOD_NOT_ALLOWED, b"").unwrap();
+
+        let err = check(&spec, &case, StatusCode::OK, br#"{"modules":[]}"#).unwrap_err();
+        assert!(err.contains("expected a client error"), "{}", err);
+
+        let err = check(&spec, &case, StatusCode::BAD_REQUEST, br#"{"error":"bad"}"#).unwrap_err();
+        assert!(err.contains("doesn't match"), "{}", err);
+    }
+
+    #[test]
+    fn server_error() {
+        let spec = Spec::parse(SPEC).unwrap();
+
+        for expect in [
+            Expect::Documented,
+            Expect::ClientError,
+            Expect::NoServerError,
+        ] {
+            let err = check(
+                &spec,
+                &case(expect),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                br#"{"message":"failed"}"#,
+            )
+            .unwrap_err();
+            assert!(err.contains("server error"), "{}", err);
+        }
+    }
+}
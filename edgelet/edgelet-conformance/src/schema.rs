@@ -0,0 +1,278 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! The subset of JSON schema that the API definitions use.
+
+use serde_json::Value;
+
+use crate::Spec;
+
+/// Placeholder for strings in generated requests. It doesn't name anything that
+/// exists, so that requests can't affect real modules.
+pub const PROBE: &str = "conformance-probe";
+
+/// Check `value` against `schema`. Returns a description of each mismatch, prefixed with
+/// the JSON pointer to the mismatched value.
+pub fn validate(spec: &Spec, schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(spec, schema, value, "", &mut errors);
+
+    errors
+}
+
+fn validate_at(spec: &Spec, schema: &Value, value: &Value, at: &str, errors: &mut Vec<String>) {
+    let schema = spec.resolve(schema);
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            errors.push(format!("{at}: {value} is not one of {allowed:?}"));
+
+            return;
+        }
+    }
+
+    let Some(expected) = schema.get("type").and_then(Value::as_str) else {
+        return;
+    };
+
+    let matches = match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+
+        // Not a type this checker knows, which is itself drift worth reporting.
+        _ => {
+            errors.push(format!("{at}: schema has unknown type {expected:?}"));
+
+            return;
+        }
+    };
+
+    if !matches {
+        errors.push(format!("{at}: expected {expected}, found {value}"));
+
+        return;
+    }
+
+    if let Some(items) = value.as_array() {
+        if let Some(item_schema) = schema.get("items") {
+            for (i, item) in items.iter().enumerate() {
+                validate_at(spec, item_schema, item, &format!("{at}/{i}"), errors);
+            }
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for required in schema["required"].as_array().into_iter().flatten() {
+            if let Some(required) = required.as_str() {
+                if !object.contains_key(required) {
+                    errors.push(format!("{at}: missing required property {required:?}"));
+                }
+            }
+        }
+
+        for (name, property) in object {
+            let at = format!("{at}/{name}");
+
+            // Responses may carry properties that older definitions don't list, so
+            // unknown properties are only checked against additionalProperties.
+            if let Some(property_schema) = schema["properties"].get(name) {
+                validate_at(spec, property_schema, property, &at, errors);
+            } else if let Some(additional) = schema.get("additionalProperties") {
+                if additional.is_object() {
+                    validate_at(spec, additional, property, &at, errors);
+                }
+            }
+        }
+    }
+}
+
+/// Generate the smallest value that satisfies `schema`: objects only have their
+/// required properties.
+pub fn example(spec: &Spec, schema: &Value) -> Value {
+    let schema = spec.resolve(schema);
+
+    if let Some(example) = schema.get("example") {
+        return example.clone();
+    }
+
+    if let Some(first) = schema
+        .get("enum")
+        .and_then(Value::as_array)
+        .and_then(|allowed| allowed.first())
+    {
+        return first.clone();
+    }
+
+    match schema.get("type").and_then(Value::as_str) {
+        Some("object") => schema["required"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(|name| (name.to_string(), example(spec, &schema["properties"][name])))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        Some("array") => Value::Array(Vec::new()),
+        Some("integer" | "number") => Value::from(1),
+        Some("boolean") => Value::from(false),
+        _ => Value::from(PROBE),
+    }
+}
+
+/// A value that doesn't satisfy `schema`'s type.
+pub fn wrong_type(spec: &Spec, schema: &Value) -> Value {
+    match spec.resolve(schema).get("type").and_then(Value::as_str) {
+        Some("string") => Value::from(1),
+        Some("object") => Value::Array(Vec::new()),
+        _ => Value::from(PROBE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{example, validate, wrong_type, PROBE};
+    use crate::Spec;
+
+    fn spec() -> Spec {
+        Spec::parse(
+            r"
+swagger: '2.0'
+info:
+  title: Test API
+  version: '2022-08-03'
+paths: {}
+definitions:
+  Module:
+    type: object
+    required:
+      - name
+      - status
+      - ports
+    properties:
+      name:
+        type: string
+      status:
+        type: string
+        enum:
+          - running
+          - stopped
+      ports:
+        type: array
+        items:
+          type: integer
+      restartCount:
+        type: integer
+        example: 3
+      labels:
+        type: object
+        additionalProperties:
+          type: string
+",
+        )
+        .unwrap()
+    }
+
+    fn module() -> serde_json::Value {
+        serde_json::json!({ "$ref": "#/definitions/Module" })
+    }
+
+    #[test]
+    fn valid() {
+        let spec = spec();
+
+        let value = serde_json::json!({
+            "name": "edgeAgent",
+            "status": "running",
+            "ports": [80, 443],
+            "labels": { "net.azure-devices.edge.owner": "Microsoft.Azure.Devices.Edge.Agent" },
+            "unlisted": true,
+        });
+
+        assert!(validate(&spec, &module(), &value).is_empty());
+    }
+
+    #[test]
+    fn invalid() {
+        let spec = spec();
+
+        let value = serde_json::json!({
+            "name": 1,
+            "status": "paused",
+            "ports": [80, "443"],
+            "labels": { "owner": false },
+        });
+
+        assert_eq!(
+            vec![
+                "/labels/owner: expected string, found false".to_string(),
+                "/name: expected string, found 1".to_string(),
+                r#"/ports/1: expected integer, found "443""#.to_string(),
+                r#"/status: "paused" is not one of [String("running"), String("stopped")]"#
+                    .to_string(),
+            ],
+            validate(&spec, &module(), &value)
+        );
+
+        assert_eq!(
+            vec![
+                r#": missing required property "status""#.to_string(),
+                r#": missing required property "ports""#.to_string(),
+            ],
+            validate(
+                &spec,
+                &module(),
+                &serde_json::json!({ "name": "edgeAgent" })
+            )
+        );
+    }
+
+    #[test]
+    fn unknown_type() {
+        let spec = spec();
+
+        let schema = serde_json::json!({ "type": "#/definitions/Module" });
+
+        assert_eq!(
+            vec![r##": schema has unknown type "#/definitions/Module""##.to_string()],
+            validate(&spec, &schema, &serde_json::json!({}))
+        );
+    }
+
+    #[test]
+    fn examples() {
+        let spec = spec();
+
+        let value = example(&spec, &module());
+
+        assert_eq!(
+            serde_json::json!({ "name": PROBE, "status": "running", "ports": [] }),
+            value
+        );
+        assert!(validate(&spec, &module(), &value).is_empty());
+
+        assert_eq!(
+            serde_json::json!(3),
+            example(
+                &spec,
+                &serde_json::json!({ "type": "integer", "example": 3 })
+            )
+        );
+    }
+
+    #[test]
+    fn wrong_types() {
+        let spec = spec();
+
+        for schema in [
+            module(),
+            serde_json::json!({ "type": "string" }),
+            serde_json::json!({ "type": "integer" }),
+            serde_json::json!({ "type": "array" }),
+        ] {
+            assert!(!validate(&spec, &schema, &wrong_type(&spec, &schema)).is_empty());
+        }
+    }
+}
@@ -0,0 +1,369 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use serde_json::Value;
+
+use crate::Error;
+
+const METHODS: [&str; 5] = ["get", "put", "post", "delete", "patch"];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Location {
+    Path,
+    Query,
+    Header,
+    Body,
+}
+
+#[derive(Clone, Debug)]
+pub struct Parameter {
+    pub name: String,
+    pub location: Location,
+    pub required: bool,
+
+    /// The body schema for body parameters, and the parameter itself otherwise, since
+    /// Swagger 2.0 puts `type` and `enum` directly on non-body parameters.
+    pub schema: Value,
+}
+
+#[derive(Clone, Debug)]
+pub struct Operation {
+    pub id: String,
+    pub method: hyper::Method,
+    pub path: String,
+    pub parameters: Vec<Parameter>,
+
+    /// Response schemas keyed by status code, or "default". Responses without a body
+    /// have a `null` schema.
+    pub responses: Vec<(String, Value)>,
+}
+
+impl Operation {
+    pub fn body(&self) -> Option<&Parameter> {
+        self.parameters
+            .iter()
+            .find(|parameter| parameter.location == Location::Body)
+    }
+
+    /// Whether the operation only reads state, so that requests that the daemon accepts
+    /// are safe to send.
+    pub fn is_safe(&self) -> bool {
+        self.method == hyper::Method::GET
+    }
+
+    pub fn response(&self, status: hyper::StatusCode) -> Option<&Value> {
+        let status = status.as_str();
+
+        self.responses
+            .iter()
+            .find(|(code, _)| code == status)
+            .map(|(_, schema)| schema)
+    }
+
+    pub fn default_response(&self) -> Option<&Value> {
+        self.responses
+            .iter()
+            .find(|(code, _)| code == "default")
+            .map(|(_, schema)| schema)
+    }
+}
+
+/// A Swagger 2.0 API definition, as found in the `api` directory.
+#[derive(Debug)]
+pub struct Spec {
+    pub title: String,
+    pub version: String,
+    pub operations: Vec<Operation>,
+    document: Value,
+}
+
+impl Spec {
+    pub fn parse(yaml: &str) -> Result<Self, Error> {
+        let documents = yaml_rust::YamlLoader::load_from_str(yaml)
+            .map_err(|err| Error::Spec(err.to_string()))?;
+        let document = documents
+            .first()
+            .map(yaml_to_json)
+            .ok_or_else(|| Error::Spec("empty document".to_string()))?;
+
+        let info_field = |name: &str| {
+            document["info"][name]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| Error::Spec(format!("missing info.{name}")))
+        };
+        let title = info_field("title")?;
+        let version = info_field("version")?;
+
+        let mut spec = Spec {
+            title,
+            version,
+            operations: Vec::new(),
+            document,
+        };
+
+        let paths = spec.document["paths"]
+            .as_object()
+            .ok_or_else(|| Error::Spec("missing paths".to_string()))?;
+
+        let mut operations = Vec::new();
+
+        for (path, item) in paths {
+            for method in METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+
+                operations.push(spec.operation(path, method, operation)?);
+            }
+        }
+
+        spec.operations = operations;
+
+        Ok(spec)
+    }
+
+    /// Follow `$ref`s to the schema they point to.
+    pub fn resolve<'a>(&'a self, mut schema: &'a Value) -> &'a Value {
+        // Bounded so that circular references can't loop forever.
+        for _ in 0..32 {
+            let Some(reference) = schema.get("$ref").and_then(Value::as_str) else {
+                break;
+            };
+
+            match self.lookup(reference) {
+                Some(target) => schema = target,
+                None => break,
+            }
+        }
+
+        schema
+    }
+
+    fn lookup(&self, reference: &str) -> Option<&Value> {
+        let pointer = reference.strip_prefix('#')?;
+
+        self.document.pointer(pointer)
+    }
+
+    fn operation(&self, path: &str, method: &str, operation: &Value) -> Result<Operation, Error> {
+        let id = operation["operationId"].as_str().map_or_else(
+            || format!("{} {path}", method.to_uppercase()),
+            str::to_string,
+        );
+
+        let mut parameters = Vec::new();
+
+        for parameter in operation["parameters"].as_array().into_iter().flatten() {
+            if let Some(reference) = parameter.get("$ref").and_then(Value::as_str) {
+                if self.lookup(reference).is_none() {
+                    return Err(Error::Spec(format!(
+                        "{id}: unresolved parameter reference {reference}"
+                    )));
+                }
+            }
+
+            let parameter = self.resolve(parameter);
+
+            let name = parameter["name"]
+                .as_str()
+                .ok_or_else(|| Error::Spec(format!("{id}: parameter without a name")))?
+                .to_string();
+
+            let location = match parameter["in"].as_str() {
+                Some("path") => Location::Path,
+                Some("query") => Location::Query,
+                Some("header") => Location::Header,
+                Some("body") => Location::Body,
+                other => {
+                    return Err(Error::Spec(format!(
+                        "{id}: parameter {name} has unsupported location {other:?}"
+                    )));
+                }
+            };
+
+            let schema = if location == Location::Body {
+                parameter["schema"].clone()
+            } else {
+                parameter.clone()
+            };
+
+            parameters.push(Parameter {
+                name,
+                // Path parameters are always required, whether or not the definition says so.
+                required: location == Location::Path
+                    || parameter["required"].as_bool().unwrap_or_default(),
+                location,
+                schema,
+            });
+        }
+
+        let responses = operation["responses"]
+            .as_object()
+            .ok_or_else(|| Error::Spec(format!("{id}: missing responses")))?
+            .iter()
+            .map(|(code, response)| {
+                (
+                    code.clone(),
+                    self.resolve(response)
+                        .get("schema")
+                        .cloned()
+                        .unwrap_or_default(),
+                )
+            })
+            .collect();
+
+        Ok(Operation {
+            id,
+            method: method
+                .to_uppercase()
+                .parse()
+                .expect("METHODS are valid HTTP methods"),
+            path: path.to_string(),
+            parameters,
+            responses,
+        })
+    }
+}
+
+fn yaml_to_json(yaml: &yaml_rust::Yaml) -> Value {
+    use yaml_rust::Yaml;
+
+    match yaml {
+        Yaml::Real(real) => real
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map_or(Value::Null, Value::Number),
+        Yaml::Integer(integer) => Value::from(*integer),
+        Yaml::String(string) => Value::from(string.as_str()),
+        Yaml::Boolean(boolean) => Value::from(*boolean),
+        Yaml::Array(array) => array.iter().map(yaml_to_json).collect(),
+        Yaml::Hash(hash) => hash
+            .iter()
+            .map(|(key, value)| {
+                // Status codes are often written as unquoted integers.
+                let key = match key {
+                    Yaml::String(key) => key.clone(),
+                    Yaml::Integer(key) => key.to_string(),
+                    Yaml::Boolean(key) => key.to_string(),
+                    _ => String::new(),
+                };
+
+                (key, yaml_to_json(value))
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        Yaml::Alias(_) | Yaml::Null | Yaml::BadValue => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Location, Spec};
+
+    const SPEC: &str = r"
+swagger: '2.0'
+info:
+  title: Test API
+  version: '2022-08-03'
+paths:
+  /modules/{name}:
+    get:
+      operationId: GetModule
+      parameters:
+        - $ref: '#/parameters/api-version'
+        - in: path
+          name: name
+          type: string
+      responses:
+        200:
+          description: Ok
+          schema:
+            $ref: '#/definitions/Module'
+        default:
+          description: Error
+          schema:
+            $ref: '#/definitions/ErrorResponse'
+    delete:
+      parameters:
+        - $ref: '#/parameters/api-version'
+      responses:
+        '204':
+          description: No Content
+definitions:
+  Module:
+    type: object
+    required:
+      - name
+    properties:
+      name:
+        type: string
+  ErrorResponse:
+    type: object
+    properties:
+      message:
+        type: string
+parameters:
+  api-version:
+    name: api-version
+    in: query
+    required: true
+    type: string
+";
+
+    #[test]
+    fn parse() {
+        let spec = Spec::parse(SPEC).unwrap();
+
+        assert_eq!("Test API", spec.title);
+        assert_eq!("2022-08-03", spec.version);
+        assert_eq!(2, spec.operations.len());
+
+        let get = &spec.operations[0];
+        assert_eq!("GetModule", get.id);
+        assert_eq!(hyper::Method::GET, get.method);
+        assert!(get.is_safe());
+
+        assert_eq!("api-version", get.parameters[0].name);
+        assert_eq!(Location::Query, get.parameters[0].location);
+        assert!(get.parameters[0].required);
+
+        // Path parameters are required even when the definition doesn't say so.
+        assert_eq!(Location::Path, get.parameters[1].location);
+        assert!(get.parameters[1].required);
+
+        let schema = spec.resolve(get.response(hyper::StatusCode::OK).unwrap());
+        assert_eq!("object", schema["type"]);
+        assert!(get.default_response().is_some());
+
+        let delete = &spec.operations[1];
+        assert_eq!("DELETE /modules/{name}", delete.id);
+        assert!(!delete.is_safe());
+        assert!(delete
+            .response(hyper::StatusCode::NO_CONTENT)
+            .unwrap()
+            .is_null());
+    }
+
+    #[test]
+    fn unresolved_parameter() {
+        let spec = SPEC.replace("'#/parameters/api-version'", "'#/parameters/missing'");
+
+        Spec::parse(&spec).unwrap_err();
+    }
+
+    #[test]
+    fn api_definitions() {
+        // Every served API definition must parse, since they are what the daemon is
+        // checked against.
+        for spec in [
+            include_str!("../../api/managementVersion_2022_08_03.yaml"),
+            include_str!("../../api/workloadVersion_2020_07_07.yaml"),
+            include_str!("../../api/workloadVersion_2022_08_03.yaml"),
+        ] {
+            let spec = Spec::parse(spec).unwrap();
+
+            assert!(!spec.operations.is_empty());
+        }
+    }
+}
@@ -13,17 +13,26 @@
 
 pub mod error;
 pub mod module;
+pub mod tasks;
 
 mod parse_since;
+mod time_sync;
 mod virtualization;
+mod watchdog;
 
 pub use error::Error;
 pub use module::{
-    DiskInfo, LogOptions, LogTail, Module, ModuleAction, ModuleOperation, ModuleRegistry,
-    ModuleRuntime, ModuleRuntimeErrorReason, ModuleRuntimeState, ModuleStatus, ProvisioningInfo,
-    RegistryOperation, RuntimeOperation, SystemInfo, SystemResources,
+    CircuitBreaker, DiskInfo, DiskSpaceLevel, DiskSpaceStatus, ImagePull, ImageSbom, LogOptions,
+    LogTail, Module, ModuleAction, ModuleOperation, ModuleRegistry, ModuleRuntime,
+    ModuleRuntimeErrorReason, ModuleRuntimeState, ModuleStatus, ProvisioningInfo,
+    RegistryCredential, RegistryCredentialSource, RegistryMirror, RegistryOperation,
+    RuntimeOperation, SbomLookup, SystemInfo, SystemResources,
 };
 pub use parse_since::parse_since;
+pub use time_sync::{
+    host_time_dir, host_timezone, TimeStatus, MODULE_TIME_DIR, MODULE_TIME_DIR_ENV,
+};
+pub use watchdog::{watchdog_channel, WatchdogReceiver, WatchdogSender};
 
 use std::path::{Path, PathBuf};
 
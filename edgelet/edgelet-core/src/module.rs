@@ -65,6 +65,8 @@ pub struct ModuleRuntimeState {
     finished_at: Option<DateTime<Utc>>,
     image_id: Option<String>,
     pid: Option<i32>,
+    description: Option<String>,
+    circuit_breaker: Option<CircuitBreaker>,
 }
 
 impl ModuleRuntimeState {
@@ -127,6 +129,42 @@ impl ModuleRuntimeState {
         self.pid = pid;
         self
     }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    #[must_use]
+    pub fn with_description(mut self, description: Option<String>) -> Self {
+        self.description = description;
+        self
+    }
+
+    pub fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.circuit_breaker.as_ref()
+    }
+
+    #[must_use]
+    pub fn with_circuit_breaker(mut self, circuit_breaker: Option<CircuitBreaker>) -> Self {
+        self.circuit_breaker = circuit_breaker;
+        self
+    }
+}
+
+/// State of the restart circuit breaker of a module with a restart policy.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CircuitBreaker {
+    /// A tripped (open) circuit breaker refuses all starts until `next_retry`.
+    pub tripped: bool,
+
+    /// Starts within the policy's window.
+    pub restarts: u32,
+
+    /// When the module may next be started. Omitted if it may be started now, or if the
+    /// circuit breaker stays open until the module is deployed again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_retry: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -252,10 +290,32 @@ pub trait ModuleRegistry {
     type Config;
 
     async fn pull(&self, config: &Self::Config) -> anyhow::Result<()>;
+
+    /// Pull the image of a module. The pull is reported in the module's status, which
+    /// may still describe the image that the module was created with.
+    async fn pull_module(&self, module: &str, config: &Self::Config) -> anyhow::Result<()>;
+
     async fn remove(&self, name: &str) -> anyhow::Result<()>;
+
+    /// Image pulls that haven't completed.
+    fn pulls(&self) -> Vec<ImagePull> {
+        Vec::new()
+    }
 }
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+/// An image pull that hasn't completed.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImagePull {
+    pub image: String,
+
+    /// Modules that the image is pulled for.
+    pub modules: Vec<String>,
+
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct SystemInfo {
     #[serde(rename = "osType")]
     pub kernel: String,
@@ -398,7 +458,7 @@ pub struct ProvisioningInfo {
     pub always_reprovision_on_startup: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct SystemResources {
     host_uptime: u64,
     process_uptime: u64,
@@ -431,7 +491,7 @@ impl SystemResources {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct DiskInfo {
     name: String,
     available_space: u64,
@@ -458,6 +518,98 @@ impl DiskInfo {
     }
 }
 
+/// A software bill of materials attached to a module's image.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageSbom {
+    /// The SBOM format, as an in-toto predicate type or OCI artifact type.
+    pub format: String,
+
+    /// Digest of the image manifest that the SBOM describes, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+
+    pub document: serde_json::Value,
+}
+
+/// Finds a module's SBOM. Exporting and scanning an image can take minutes, so this is
+/// awaited after the module runtime's lock is released.
+pub type SbomLookup =
+    std::pin::Pin<Box<dyn std::future::Future<Output = anyhow::Result<Option<ImageSbom>>> + Send>>;
+
+/// Health and pull statistics of a registry mirror that module images are pulled from.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryMirror {
+    /// The registry that this is a mirror of.
+    pub registry: String,
+    pub mirror: String,
+
+    /// Unhealthy mirrors are tried after healthy ones until they recover.
+    pub healthy: bool,
+
+    pub pulls: u64,
+    pub failures: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_success: Option<DateTime<Utc>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_failure: Option<DateTime<Utc>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RegistryCredentialSource {
+    /// The credentials that a module was deployed with.
+    Deployment,
+
+    /// The daemon's `image_pull.credentials` settings.
+    Settings,
+}
+
+/// A registry credential that pulls from the registry use first, since the registry
+/// last accepted it. The password isn't included.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryCredential {
+    pub registry: String,
+
+    /// The module whose deployment the credential came from. Only pulls of that module
+    /// use it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub module: Option<String>,
+
+    pub username: String,
+    pub source: RegistryCredentialSource,
+    pub accepted: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiskSpaceLevel {
+    Ok,
+    Warning,
+    Critical,
+}
+
+/// Free space on a file system that the daemon or the container engine stores data on.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskSpaceStatus {
+    pub path: std::path::PathBuf,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+
+    /// Modules aren't created and images aren't pulled while any path is critical.
+    pub level: DiskSpaceLevel,
+
+    pub checked: DateTime<Utc>,
+}
+
 pub trait ProvisioningResult {
     fn device_id(&self) -> &str;
     fn hub_name(&self) -> &str;
@@ -467,7 +619,7 @@ pub trait ProvisioningResult {
 pub trait ModuleRuntime {
     type Config: Clone + Send + serde::Serialize;
     type Module: Module<Config = Self::Config> + Send;
-    type ModuleRegistry: ModuleRegistry<Config = Self::Config> + Send + Sync;
+    type ModuleRegistry: ModuleRegistry<Config = Self::Config> + Clone + Send + Sync + 'static;
 
     async fn create(&self, module: ModuleSpec<Self::Config>) -> anyhow::Result<()>;
     async fn get(&self, id: &str) -> anyhow::Result<(Self::Module, ModuleRuntimeState)>;
@@ -481,12 +633,28 @@ pub trait ModuleRuntime {
     async fn list_with_details(&self) -> anyhow::Result<Vec<(Self::Module, ModuleRuntimeState)>>;
     async fn list_images(&self) -> anyhow::Result<std::collections::HashMap<String, String>>;
     async fn logs(&self, id: &str, options: &LogOptions) -> anyhow::Result<hyper::Body>;
+    async fn module_sbom(&self, id: &str) -> anyhow::Result<SbomLookup>;
+    async fn registry_mirrors(&self) -> anyhow::Result<Vec<RegistryMirror>>;
+    async fn registry_credentials(&self) -> anyhow::Result<Vec<RegistryCredential>>;
+    async fn invalidate_registry_credentials(&self, registry: Option<&str>) -> anyhow::Result<()>;
+    async fn disk_space(&self) -> anyhow::Result<Vec<DiskSpaceStatus>>;
+    async fn purge_data(&self, id: &str) -> anyhow::Result<Vec<String>>;
     async fn remove_all(&self) -> anyhow::Result<()>;
     async fn stop_all(&self, wait_before_kill: Option<Duration>) -> anyhow::Result<()>;
     async fn module_top(&self, id: &str) -> anyhow::Result<Vec<i32>>;
 
     fn registry(&self) -> &Self::ModuleRegistry;
 
+    /// Check that a module can be created, without side effects.
+    fn check(&self, _module: &ModuleSpec<Self::Config>) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Check that a module can be created, before anything is done with it.
+    fn validate(&self, module: &ModuleSpec<Self::Config>) -> anyhow::Result<()> {
+        self.check(module)
+    }
+
     fn error_code(error: &anyhow::Error) -> hyper::StatusCode;
 }
 
@@ -532,8 +700,10 @@ pub enum RuntimeOperation {
     CreateModule(String),
     GetModule(String),
     GetModuleLogs(String),
+    GetModuleSbom(String),
     GetSupportBundle,
     Init,
+    PurgeModuleData(String),
     ListImages,
     ListModules,
     RemoveModule(String),
@@ -553,8 +723,14 @@ impl fmt::Display for RuntimeOperation {
             RuntimeOperation::GetModuleLogs(name) => {
                 write!(f, "get logs for module {name:?}")
             }
+            RuntimeOperation::GetModuleSbom(name) => {
+                write!(f, "get SBOM for module {name:?}")
+            }
             RuntimeOperation::GetSupportBundle => write!(f, "get support bundle"),
             RuntimeOperation::Init => write!(f, "initialize module runtime"),
+            RuntimeOperation::PurgeModuleData(name) => {
+                write!(f, "purge data for module {name:?}")
+            }
             RuntimeOperation::ListModules => write!(f, "list modules"),
             RuntimeOperation::ListImages => write!(f, "list images"),
             RuntimeOperation::RemoveModule(name) => write!(f, "remove module {name:?}"),
@@ -0,0 +1,94 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatus {
+    /// Tasks of this name that are running.
+    pub running: usize,
+
+    /// Tasks of this name started since the daemon started.
+    pub started: u64,
+
+    pub last_started: chrono::DateTime<chrono::Utc>,
+}
+
+/// The daemon's tasks by name. The runtime doesn't list its tasks, so the daemon's
+/// crates spawn theirs through here for the state dump.
+static TASKS: std::sync::Mutex<std::collections::BTreeMap<&'static str, TaskStatus>> =
+    std::sync::Mutex::new(std::collections::BTreeMap::new());
+
+/// Marks its task as no longer running when dropped, whether the task completed,
+/// panicked or was aborted.
+struct Running(&'static str);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        if let Some(status) = lock().get_mut(self.0) {
+            status.running -= 1;
+        }
+    }
+}
+
+/// Spawn a task that is listed in the state dump.
+pub fn spawn<F>(name: &'static str, future: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: std::future::Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    {
+        let mut tasks = lock();
+        let status = tasks.entry(name).or_insert_with(|| TaskStatus {
+            running: 0,
+            started: 0,
+            last_started: chrono::Utc::now(),
+        });
+
+        status.running += 1;
+        status.started += 1;
+        status.last_started = chrono::Utc::now();
+    }
+
+    let running = Running(name);
+
+    tokio::spawn(async move {
+        let _running = running;
+
+        future.await
+    })
+}
+
+pub fn list() -> std::collections::BTreeMap<&'static str, TaskStatus> {
+    lock().clone()
+}
+
+fn lock() -> std::sync::MutexGuard<'static, std::collections::BTreeMap<&'static str, TaskStatus>> {
+    // Tasks are marked as stopped while they unwind from a panic, so the lock may be
+    // poisoned.
+    match TASKS.lock() {
+        Ok(tasks) => tasks,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn spawn() {
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+
+        let task = super::spawn("tasks::tests::spawn", async move {
+            let _ = rx.await;
+        });
+
+        let status = super::list()["tasks::tests::spawn"].clone();
+        assert_eq!(1, status.running);
+        assert_eq!(1, status.started);
+
+        tx.send(()).unwrap();
+        task.await.unwrap();
+
+        let status = super::list()["tasks::tests::spawn"].clone();
+        assert_eq!(0, status.running);
+        assert_eq!(1, status.started);
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::path::{Path, PathBuf};
+
+/// Where the host's time status is mounted in module containers.
+pub const MODULE_TIME_DIR: &str = "/run/iotedge/time";
+
+/// Environment variable that points modules at `MODULE_TIME_DIR`.
+pub const MODULE_TIME_DIR_ENV: &str = "IOTEDGE_TIMEDIR";
+
+/// Directory of the host's time status under the edged home directory.
+pub fn host_time_dir(homedir: &Path) -> PathBuf {
+    homedir.join("time")
+}
+
+/// Timezone and clock synchronization state of the host.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeStatus {
+    /// IANA name of the host's timezone, such as "Europe/Berlin".
+    pub timezone: String,
+
+    /// Whether the kernel considers the clock synchronized, as set by NTP daemons
+    /// such as chrony and systemd-timesyncd.
+    pub synchronized: bool,
+
+    /// Upper bound of the clock's error, in microseconds.
+    pub max_error_us: i64,
+
+    /// Estimated error of the clock, in microseconds.
+    pub estimated_error_us: i64,
+}
+
+impl TimeStatus {
+    pub fn current() -> Self {
+        let (synchronized, max_error_us, estimated_error_us) = clock_state();
+
+        TimeStatus {
+            timezone: host_timezone(),
+            synchronized,
+            max_error_us,
+            estimated_error_us,
+        }
+    }
+
+    /// Write the status to `dir` for modules that mount it.
+    ///
+    /// `timezone` holds the bare timezone name and `status.json` the full status. Files
+    /// are replaced by renames, so modules never read them half-written.
+    pub fn write_to(&self, dir: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+
+        let status = serde_json::to_vec_pretty(self)?;
+
+        write_atomic(dir, "timezone", format!("{}\n", self.timezone).as_bytes())?;
+        write_atomic(dir, "status.json", &status)?;
+
+        Ok(())
+    }
+}
+
+fn write_atomic(dir: &Path, name: &str, contents: &[u8]) -> std::io::Result<()> {
+    let temp = dir.join(format!(".{name}.tmp"));
+
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, dir.join(name))
+}
+
+/// Timezone of the host from `/etc/timezone`, or from the zoneinfo file that
+/// `/etc/localtime` links to.
+pub fn host_timezone() -> String {
+    if let Ok(timezone) = std::fs::read_to_string("/etc/timezone") {
+        let timezone = timezone.trim();
+
+        if !timezone.is_empty() {
+            return timezone.to_string();
+        }
+    }
+
+    std::fs::read_link("/etc/localtime")
+        .ok()
+        .and_then(|target| timezone_from_zoneinfo(&target))
+        .unwrap_or_else(|| "UTC".to_string())
+}
+
+fn timezone_from_zoneinfo(path: &Path) -> Option<String> {
+    let path = path.to_str()?;
+    let (_, timezone) = path.split_once("zoneinfo/")?;
+
+    // Distributions with leap second support keep the usual zones under "posix".
+    let timezone = timezone.strip_prefix("posix/").unwrap_or(timezone);
+
+    (!timezone.is_empty()).then(|| timezone.to_string())
+}
+
+// c_long is only 32 bits on 32-bit targets.
+#[cfg(target_os = "linux")]
+#[allow(clippy::useless_conversion)]
+fn clock_state() -> (bool, i64, i64) {
+    // SAFETY: timex is plain data, and modes of 0 only reads the kernel's clock state.
+    let mut timex: libc::timex = unsafe { std::mem::zeroed() };
+    let state = unsafe { libc::adjtimex(&mut timex) };
+
+    let synchronized = state != -1 && state != libc::TIME_ERROR;
+
+    (
+        synchronized,
+        i64::from(timex.maxerror),
+        i64::from(timex.esterror),
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+fn clock_state() -> (bool, i64, i64) {
+    (false, 0, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{timezone_from_zoneinfo, TimeStatus};
+
+    #[test]
+    fn zoneinfo_timezone() {
+        assert_eq!(
+            Some("Europe/Berlin".to_string()),
+            timezone_from_zoneinfo(Path::new("/usr/share/zoneinfo/Europe/Berlin"))
+        );
+        assert_eq!(
+            Some("America/New_York".to_string()),
+            timezone_from_zoneinfo(Path::new("../usr/share/zoneinfo/posix/America/New_York"))
+        );
+        assert_eq!(
+            Some("UTC".to_string()),
+            timezone_from_zoneinfo(Path::new("/usr/share/zoneinfo/UTC"))
+        );
+        assert_eq!(
+            None,
+            timezone_from_zoneinfo(Path::new("/etc/localtime.bak"))
+        );
+    }
+
+    #[test]
+    fn write_to() {
+        let dir = std::env::temp_dir().join(format!("time-sync-test-{}", std::process::id()));
+
+        let status = TimeStatus {
+            timezone: "Asia/Tokyo".to_string(),
+            synchronized: true,
+            max_error_us: 16000,
+            estimated_error_us: 500,
+        };
+        status.write_to(&dir).unwrap();
+
+        let timezone = std::fs::read_to_string(dir.join("timezone")).unwrap();
+        assert_eq!("Asia/Tokyo\n", timezone);
+
+        let written = std::fs::read(dir.join("status.json")).unwrap();
+        let written: TimeStatus = serde_json::from_slice(&written).unwrap();
+        assert_eq!(status, written);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tokio::sync::mpsc::error::SendError;
+
+use crate::WatchdogAction;
+
+/// Create the channel on which the daemon's tasks send actions to the watchdog.
+///
+/// The channel counts the actions sent but not yet received, so that a watchdog that is
+/// stuck in a check while actions pile up shows in the state dump.
+pub fn watchdog_channel() -> (WatchdogSender, WatchdogReceiver) {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let queued = Arc::new(AtomicUsize::new(0));
+
+    (
+        WatchdogSender {
+            tx,
+            queued: queued.clone(),
+        },
+        WatchdogReceiver { rx, queued },
+    )
+}
+
+#[derive(Clone, Debug)]
+pub struct WatchdogSender {
+    tx: tokio::sync::mpsc::UnboundedSender<WatchdogAction>,
+    queued: Arc<AtomicUsize>,
+}
+
+impl WatchdogSender {
+    pub fn send(&self, action: WatchdogAction) -> Result<(), SendError<WatchdogAction>> {
+        // Counted before sending, so that the receiver never sees more actions than are
+        // counted.
+        self.queued.fetch_add(1, Ordering::AcqRel);
+
+        self.tx.send(action).map_err(|err| {
+            self.queued.fetch_sub(1, Ordering::AcqRel);
+
+            err
+        })
+    }
+
+    /// Actions sent but not yet received by the watchdog.
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Acquire)
+    }
+}
+
+#[derive(Debug)]
+pub struct WatchdogReceiver {
+    rx: edgelet_core::WatchdogReceiver,
+    queued: Arc<AtomicUsize>,
+}
+
+impl WatchdogReceiver {
+    pub async fn recv(&mut self) -> Option<WatchdogAction> {
+        let action = self.rx.recv().await;
+        if action.is_some() {
+            self.queued.fetch_sub(1, Ordering::AcqRel);
+        }
+
+        action
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::WatchdogAction;
+
+    #[tokio::test]
+    async fn queued() {
+        let (tx, mut rx) = super::watchdog_channel();
+        assert_eq!(0, tx.queued());
+
+        tx.send(WatchdogAction::EdgeCaRenewal).unwrap();
+        tx.clone().send(WatchdogAction::Signal).unwrap();
+        assert_eq!(2, tx.queued());
+        assert_eq!(2, rx.queued());
+
+        assert_eq!(Some(WatchdogAction::EdgeCaRenewal), rx.recv().await);
+        assert_eq!(1, tx.queued());
+
+        // Actions that can't be delivered aren't counted.
+        drop(rx);
+        tx.send(WatchdogAction::Reprovision).unwrap_err();
+        assert_eq!(1, tx.queued());
+    }
+}
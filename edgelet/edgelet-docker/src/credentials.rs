@@ -0,0 +1,392 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::RegistryCredentialSource;
+
+/// Registry credentials for pulls, shared by clones of the runtime.
+///
+/// A pull is tried with the credentials of the module's deployment, then the credential
+/// that the registry last accepted, then the configured credentials of the registry,
+/// until one isn't denied. The accepted credential is cached, so that pulls with stale
+/// credentials, such as the watchdog's pulls of Edge Agent after the deployment's
+/// credentials were rotated, use the current ones.
+///
+/// A credential from a deployment is only reused for pulls of the module it came with,
+/// since other modules' deployments may not be meant to have access to the same
+/// repositories. Configured credentials are reused for any pull from their registry.
+#[derive(Clone, Default)]
+pub(crate) struct Credentials {
+    configured: std::sync::Arc<
+        std::collections::BTreeMap<
+            String,
+            Vec<edgelet_settings::docker::runtime::RegistryCredential>,
+        >,
+    >,
+    accepted: std::sync::Arc<std::sync::Mutex<std::collections::BTreeMap<Key, Accepted>>>,
+}
+
+/// The registry that accepted a credential, and the module whose deployment it came
+/// from.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+struct Key {
+    registry: String,
+    module: Option<String>,
+}
+
+#[derive(Clone)]
+struct Accepted {
+    auth: docker::models::AuthConfig,
+    source: RegistryCredentialSource,
+    time: chrono::DateTime<chrono::Utc>,
+}
+
+/// A credential to try a pull with.
+#[derive(Clone)]
+pub(crate) struct Candidate {
+    pub auth: docker::models::AuthConfig,
+    pub source: RegistryCredentialSource,
+
+    /// The module whose deployment the credential came from.
+    module: Option<String>,
+}
+
+impl Candidate {
+    /// The credential as the `X-Registry-Auth` header of a pull.
+    pub fn encode(&self) -> anyhow::Result<String> {
+        let json = serde_json::to_string(&self.auth)?;
+        let engine = base64::engine::general_purpose::URL_SAFE;
+
+        Ok(base64::Engine::encode(&engine, json))
+    }
+
+    fn username(&self) -> Option<&str> {
+        self.auth.username()
+    }
+
+    fn same_as(&self, other: &docker::models::AuthConfig) -> bool {
+        self.auth.username() == other.username() && self.auth.password() == other.password()
+    }
+}
+
+impl Credentials {
+    pub fn new(
+        configured: &std::collections::BTreeMap<
+            String,
+            Vec<edgelet_settings::docker::runtime::RegistryCredential>,
+        >,
+    ) -> Self {
+        Credentials {
+            configured: std::sync::Arc::new(configured.clone()),
+            accepted: std::sync::Arc::default(),
+        }
+    }
+
+    /// Credentials to try a pull of a module's image from the registry with, in order.
+    /// Configured passwords are read from their files now, so that rotated passwords are
+    /// picked up.
+    pub async fn candidates(
+        &self,
+        registry: &str,
+        module: Option<&str>,
+        deployment: Option<&docker::models::AuthConfig>,
+    ) -> Vec<Candidate> {
+        let mut candidates: Vec<Candidate> = Vec::new();
+        let mut add = |candidate: Candidate| {
+            if !candidates
+                .iter()
+                .any(|existing| existing.same_as(&candidate.auth))
+            {
+                candidates.push(candidate);
+            }
+        };
+
+        if let Some(auth) = deployment {
+            add(Candidate {
+                auth: auth.clone(),
+                source: RegistryCredentialSource::Deployment,
+                module: module.map(ToString::to_string),
+            });
+        }
+
+        let accepted: Vec<Candidate> = {
+            let accepted = self.lock();
+
+            // The module's own deployment credential, then the configured one.
+            [module, None]
+                .into_iter()
+                .filter_map(|module| {
+                    let key = Key {
+                        registry: registry.to_string(),
+                        module: module.map(ToString::to_string),
+                    };
+
+                    accepted.get(&key).map(|accepted| Candidate {
+                        auth: accepted.auth.clone(),
+                        source: accepted.source,
+                        module: key.module,
+                    })
+                })
+                .collect()
+        };
+        for candidate in accepted {
+            add(candidate);
+        }
+
+        for credential in self.configured.get(registry).into_iter().flatten() {
+            match tokio::fs::read_to_string(&credential.password_file).await {
+                Ok(password) => add(Candidate {
+                    auth: docker::models::AuthConfig::new()
+                        .with_username(credential.username.clone())
+                        .with_password(password.trim_end().to_string())
+                        .with_serveraddress(registry.to_string()),
+                    source: RegistryCredentialSource::Settings,
+                    module: None,
+                }),
+                Err(err) => log::warn!(
+                    "Could not read password of registry credential {} for {} from {}: {}",
+                    credential.username,
+                    registry,
+                    credential.password_file.display(),
+                    err
+                ),
+            }
+        }
+
+        candidates
+    }
+
+    /// Record that the registry accepted a credential. A deployment credential of a pull
+    /// that isn't for a module isn't kept, since no module would reuse it.
+    pub fn accepted(&self, registry: &str, candidate: &Candidate) {
+        if candidate.source == RegistryCredentialSource::Deployment && candidate.module.is_none() {
+            return;
+        }
+
+        let key = Key {
+            registry: registry.to_string(),
+            module: candidate.module.clone(),
+        };
+        let mut accepted = self.lock();
+
+        if accepted
+            .get(&key)
+            .map_or(true, |accepted| !candidate.same_as(&accepted.auth))
+        {
+            log::info!(
+                "Registry {} accepted credential {} from {}",
+                registry,
+                candidate.username().unwrap_or_default(),
+                match &candidate.module {
+                    Some(module) => format!("the deployment of module {module}"),
+                    None => "settings".to_string(),
+                }
+            );
+        }
+
+        accepted.insert(
+            key,
+            Accepted {
+                auth: candidate.auth.clone(),
+                source: candidate.source,
+                time: chrono::Utc::now(),
+            },
+        );
+    }
+
+    /// Forget the credential that the registry accepted, or those of all registries.
+    pub fn invalidate(&self, registry: Option<&str>) {
+        let mut accepted = self.lock();
+
+        if let Some(registry) = registry {
+            accepted.retain(|key, _| key.registry != registry);
+        } else {
+            accepted.clear();
+        }
+    }
+
+    pub fn list(&self) -> Vec<edgelet_core::RegistryCredential> {
+        self.lock()
+            .iter()
+            .map(|(key, accepted)| edgelet_core::RegistryCredential {
+                registry: key.registry.clone(),
+                module: key.module.clone(),
+                username: accepted.auth.username().unwrap_or_default().to_string(),
+                source: accepted.source,
+                accepted: accepted.time,
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, std::collections::BTreeMap<Key, Accepted>> {
+        self.accepted
+            .lock()
+            .expect("registry credentials lock poisoned")
+    }
+}
+
+/// Whether a pull failed because the registry denied its credentials, in which case
+/// the next credential is tried.
+pub(crate) fn is_denied(err: &anyhow::Error) -> bool {
+    err.chain()
+        .find_map(|err| err.downcast_ref::<docker::apis::ApiError>())
+        .map_or(false, |err| {
+            let message = err.message.to_lowercase();
+
+            err.code == hyper::StatusCode::UNAUTHORIZED
+                || err.code == hyper::StatusCode::FORBIDDEN
+                || message.contains("unauthorized")
+                || message.contains("authentication required")
+                || message.contains("access denied")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_core::RegistryCredentialSource;
+    use edgelet_settings::docker::runtime::RegistryCredential;
+
+    use super::{is_denied, Credentials};
+
+    fn auth(username: &str, password: &str) -> docker::models::AuthConfig {
+        docker::models::AuthConfig::new()
+            .with_username(username.to_string())
+            .with_password(password.to_string())
+    }
+
+    #[tokio::test]
+    async fn candidates() {
+        let dir =
+            std::env::temp_dir().join(format!("registry-credentials-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let password_file = dir.join("password");
+        std::fs::write(&password_file, "secret1\n").unwrap();
+
+        let credentials = Credentials::new(
+            &[(
+                "contoso.azurecr.io".to_string(),
+                vec![
+                    RegistryCredential {
+                        username: "pull".to_string(),
+                        password_file: password_file.clone(),
+                    },
+                    RegistryCredential {
+                        username: "missing".to_string(),
+                        password_file: dir.join("missing"),
+                    },
+                ],
+            )]
+            .into_iter()
+            .collect(),
+        );
+
+        let deployment = auth("old", "stale");
+        let candidates = credentials
+            .candidates("contoso.azurecr.io", Some("module"), Some(&deployment))
+            .await;
+        assert_eq!(2, candidates.len());
+        assert_eq!(Some("old"), candidates[0].username());
+        assert_eq!(RegistryCredentialSource::Settings, candidates[1].source);
+        assert_eq!(Some("secret1"), candidates[1].auth.password());
+
+        // The accepted credential is tried before the configured ones.
+        credentials.accepted("contoso.azurecr.io", &candidates[1]);
+        let candidates = credentials
+            .candidates("contoso.azurecr.io", Some("module"), None)
+            .await;
+        assert_eq!(1, candidates.len());
+        assert_eq!(Some("pull"), candidates[0].username());
+
+        // Rotated passwords are read at the next pull.
+        std::fs::write(&password_file, "secret2").unwrap();
+        let candidates = credentials
+            .candidates("contoso.azurecr.io", Some("module"), Some(&deployment))
+            .await;
+        assert_eq!(3, candidates.len());
+        assert_eq!(Some("secret1"), candidates[1].auth.password());
+        assert_eq!(Some("secret2"), candidates[2].auth.password());
+
+        assert!(credentials
+            .candidates("mcr.microsoft.com", None, None)
+            .await
+            .is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn deployment_credentials_per_module() {
+        let credentials = Credentials::default();
+
+        let candidates = credentials
+            .candidates("contoso.azurecr.io", Some("moduleA"), Some(&auth("a", "a")))
+            .await;
+        credentials.accepted("contoso.azurecr.io", &candidates[0]);
+
+        // Module A reuses its deployment's credential after the deployment dropped it.
+        let candidates = credentials
+            .candidates("contoso.azurecr.io", Some("moduleA"), None)
+            .await;
+        assert_eq!(1, candidates.len());
+        assert_eq!(Some("a"), candidates[0].username());
+
+        // Other modules, and pulls that aren't for a module, don't.
+        assert!(credentials
+            .candidates("contoso.azurecr.io", Some("moduleB"), None)
+            .await
+            .is_empty());
+        assert!(credentials
+            .candidates("contoso.azurecr.io", None, None)
+            .await
+            .is_empty());
+
+        let list = credentials.list();
+        assert_eq!(1, list.len());
+        assert_eq!(Some("moduleA"), list[0].module.as_deref());
+
+        // A deployment credential of a pull that isn't for a module isn't kept.
+        let candidates = credentials
+            .candidates("fabrikam.azurecr.io", None, Some(&auth("b", "b")))
+            .await;
+        credentials.accepted("fabrikam.azurecr.io", &candidates[0]);
+        assert_eq!(1, credentials.list().len());
+    }
+
+    #[test]
+    fn invalidate() {
+        let credentials = Credentials::default();
+        let candidate = super::Candidate {
+            auth: auth("user", "password"),
+            source: RegistryCredentialSource::Deployment,
+            module: Some("edgeAgent".to_string()),
+        };
+
+        credentials.accepted("contoso.azurecr.io", &candidate);
+        credentials.accepted("fabrikam.azurecr.io", &candidate);
+        assert_eq!(2, credentials.list().len());
+
+        credentials.invalidate(Some("contoso.azurecr.io"));
+        let list = credentials.list();
+        assert_eq!(1, list.len());
+        assert_eq!("fabrikam.azurecr.io", list[0].registry);
+        assert_eq!("user", list[0].username);
+
+        credentials.invalidate(None);
+        assert!(credentials.list().is_empty());
+    }
+
+    #[test]
+    fn denied() {
+        let denied = docker::apis::ApiError {
+            code: hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            message: "Head \"https://contoso.azurecr.io/v2/module/manifests/1.0\": unauthorized: authentication required".to_string(),
+        };
+        assert!(is_denied(&anyhow::Error::from(denied)));
+
+        let not_found = docker::apis::ApiError {
+            code: hyper::StatusCode::NOT_FOUND,
+            message: "manifest unknown".to_string(),
+        };
+        assert!(!is_denied(&anyhow::Error::from(not_found)));
+
+        assert!(!is_denied(&anyhow::anyhow!("pull timed out")));
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::BTreeMap;
+
+/// Label in a module's create options that pins its image to a digest.
+pub(crate) const IMAGE_DIGEST_LABEL_KEY: &str = "net.azure-devices.edge.image-digest";
+
+/// Digest that an image is pinned to.
+///
+/// A digest in the image reference takes precedence over the label in the module's
+/// create options, which takes precedence over the local allowlist.
+pub(crate) fn pinned_digest<'a>(
+    image: &'a str,
+    labels: Option<&'a BTreeMap<String, String>>,
+    allowlist: &'a BTreeMap<String, String>,
+) -> Option<&'a str> {
+    image
+        .split_once('@')
+        .map(|(_, digest)| digest)
+        .or_else(|| {
+            labels
+                .and_then(|labels| labels.get(IMAGE_DIGEST_LABEL_KEY))
+                .map(String::as_str)
+        })
+        .or_else(|| allowlist.get(image).map(String::as_str))
+}
+
+/// Whether any of the repository digests of an image, such as `repo@sha256:...`, is
+/// the given digest.
+///
+/// Images that were built or loaded locally have no repository digests, so they
+/// never match.
+pub(crate) fn has_digest(repo_digests: &[String], digest: &str) -> bool {
+    repo_digests.iter().any(|repo_digest| {
+        repo_digest
+            .split_once('@')
+            .map_or(false, |(_, repo_digest)| {
+                repo_digest.eq_ignore_ascii_case(digest)
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{has_digest, pinned_digest, IMAGE_DIGEST_LABEL_KEY};
+
+    #[test]
+    fn pinned() {
+        let labels: BTreeMap<_, _> = [(
+            IMAGE_DIGEST_LABEL_KEY.to_string(),
+            "sha256:label".to_string(),
+        )]
+        .into_iter()
+        .collect();
+        let allowlist: BTreeMap<_, _> = [("module:1.0".to_string(), "sha256:allowed".to_string())]
+            .into_iter()
+            .collect();
+
+        assert_eq!(
+            Some("sha256:reference"),
+            pinned_digest("module@sha256:reference", Some(&labels), &allowlist)
+        );
+        assert_eq!(
+            Some("sha256:label"),
+            pinned_digest("module:1.0", Some(&labels), &allowlist)
+        );
+        assert_eq!(
+            Some("sha256:allowed"),
+            pinned_digest("module:1.0", None, &allowlist)
+        );
+        assert_eq!(None, pinned_digest("module:2.0", None, &allowlist));
+    }
+
+    #[test]
+    fn digests() {
+        let repo_digests = vec![
+            "mcr.microsoft.com/azureiotedge-agent@sha256:abcd".to_string(),
+            "mirror.contoso.com/azureiotedge-agent@sha256:abcd".to_string(),
+        ];
+
+        assert!(has_digest(&repo_digests, "sha256:abcd"));
+        assert!(has_digest(&repo_digests, "SHA256:ABCD"));
+        assert!(!has_digest(&repo_digests, "sha256:1234"));
+        assert!(!has_digest(&[], "sha256:abcd"));
+    }
+}
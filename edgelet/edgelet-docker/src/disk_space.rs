@@ -0,0 +1,214 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::{DiskSpaceLevel, DiskSpaceStatus};
+
+use crate::Error;
+
+/// Free space on the directories that the daemon stores data in and the container
+/// engine's data root, shared by clones of the runtime.
+///
+/// The space is checked periodically rather than on each operation, since the data
+/// root is only known to the container engine. While any path is critically low,
+/// modules aren't created and images aren't pulled, so that a full disk doesn't leave
+/// the device unable to run the modules it already has.
+#[derive(Clone, Default)]
+pub(crate) struct DiskSpace {
+    settings: edgelet_settings::watchdog::DiskSpace,
+    paths: Vec<std::path::PathBuf>,
+    status: std::sync::Arc<std::sync::Mutex<Vec<DiskSpaceStatus>>>,
+}
+
+impl DiskSpace {
+    /// `paths` are the directories that the daemon stores data in, such as the home
+    /// directory and the storage directories that may be on other file systems.
+    pub fn new(
+        settings: edgelet_settings::watchdog::DiskSpace,
+        paths: Vec<std::path::PathBuf>,
+    ) -> Self {
+        let mut unique = Vec::with_capacity(paths.len());
+        for path in paths {
+            if !unique.contains(&path) {
+                unique.push(path);
+            }
+        }
+
+        DiskSpace {
+            settings,
+            paths: unique,
+            status: std::sync::Arc::default(),
+        }
+    }
+
+    /// Check the free space on the daemon's directories and, if given, the data root.
+    pub fn check(&self, data_root: Option<&std::path::Path>) {
+        if !self.settings.enabled {
+            return;
+        }
+
+        let mut paths = self.paths.clone();
+        if let Some(data_root) = data_root {
+            if !paths.iter().any(|path| path == data_root) {
+                paths.push(data_root.to_path_buf());
+            }
+        }
+
+        let mut status = self.status.lock().expect("disk space lock poisoned");
+        let previous = std::mem::take(&mut *status);
+
+        for path in paths {
+            let (total_bytes, available_bytes) = match statvfs(&path) {
+                Ok(space) => space,
+                Err(err) => {
+                    log::warn!("Failed to check free space on {}: {}", path.display(), err);
+
+                    continue;
+                }
+            };
+
+            let level = level(
+                total_bytes,
+                available_bytes,
+                self.settings.warning_free_percent,
+                self.settings.critical_free_percent,
+            );
+
+            let previous_level = previous
+                .iter()
+                .find(|previous| previous.path == path)
+                .map_or(DiskSpaceLevel::Ok, |previous| previous.level);
+            if level != previous_level {
+                let percent = free_percent(total_bytes, available_bytes);
+
+                match level {
+                    DiskSpaceLevel::Ok => {
+                        log::info!("{} has {}% free space again", path.display(), percent);
+                    }
+                    DiskSpaceLevel::Warning => {
+                        log::warn!("{} is low on space: {}% free", path.display(), percent);
+                    }
+                    DiskSpaceLevel::Critical => log::error!(
+                        "{} is critically low on space: {}% free. Modules won't be created and images won't be pulled until space is freed.",
+                        path.display(),
+                        percent
+                    ),
+                }
+            }
+
+            status.push(DiskSpaceStatus {
+                path,
+                total_bytes,
+                available_bytes,
+                level,
+                checked: chrono::Utc::now(),
+            });
+        }
+    }
+
+    /// Fail if any path was critically low on space when last checked.
+    pub fn ensure(&self) -> Result<(), Error> {
+        let status = self.status.lock().expect("disk space lock poisoned");
+
+        match status
+            .iter()
+            .find(|status| status.level == DiskSpaceLevel::Critical)
+        {
+            Some(status) => Err(Error::LowDiskSpace {
+                path: status.path.clone(),
+                available_percent: free_percent(status.total_bytes, status.available_bytes),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn list(&self) -> Vec<DiskSpaceStatus> {
+        self.status
+            .lock()
+            .expect("disk space lock poisoned")
+            .clone()
+    }
+}
+
+/// Total and available bytes of the file system that `path` is on.
+fn statvfs(path: &std::path::Path) -> nix::Result<(u64, u64)> {
+    let stat = nix::sys::statvfs::statvfs(path)?;
+
+    // Blocks reserved for root count as neither used nor available, as with df.
+    #[allow(clippy::useless_conversion)]
+    let block_size = u64::from(stat.fragment_size());
+    #[allow(clippy::useless_conversion)]
+    let used = u64::from(stat.blocks()) - u64::from(stat.blocks_free());
+    #[allow(clippy::useless_conversion)]
+    let available = u64::from(stat.blocks_available());
+
+    Ok(((used + available) * block_size, available * block_size))
+}
+
+fn free_percent(total_bytes: u64, available_bytes: u64) -> u64 {
+    if total_bytes == 0 {
+        return 100;
+    }
+
+    available_bytes.saturating_mul(100) / total_bytes
+}
+
+fn level(total_bytes: u64, available_bytes: u64, warning: u8, critical: u8) -> DiskSpaceLevel {
+    let percent = free_percent(total_bytes, available_bytes);
+
+    if percent < u64::from(critical) {
+        DiskSpaceLevel::Critical
+    } else if percent < u64::from(warning) {
+        DiskSpaceLevel::Warning
+    } else {
+        DiskSpaceLevel::Ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_core::DiskSpaceLevel;
+
+    #[test]
+    fn level() {
+        assert_eq!(DiskSpaceLevel::Ok, super::level(1000, 500, 10, 3));
+        assert_eq!(DiskSpaceLevel::Ok, super::level(1000, 100, 10, 3));
+        assert_eq!(DiskSpaceLevel::Warning, super::level(1000, 99, 10, 3));
+        assert_eq!(DiskSpaceLevel::Warning, super::level(1000, 30, 10, 3));
+        assert_eq!(DiskSpaceLevel::Critical, super::level(1000, 29, 10, 3));
+        assert_eq!(DiskSpaceLevel::Critical, super::level(1000, 0, 10, 3));
+
+        // File systems that report no size, like some virtual ones, are never full.
+        assert_eq!(DiskSpaceLevel::Ok, super::level(0, 0, 10, 3));
+    }
+
+    #[test]
+    fn ensure() {
+        let dir = std::env::temp_dir();
+
+        let disk_space = super::DiskSpace::new(Default::default(), vec![dir.clone(), dir.clone()]);
+        disk_space.check(None);
+        assert_eq!(1, disk_space.list().len());
+        assert_eq!(dir, disk_space.list()[0].path);
+
+        // Every path is checked once, including a data root that is one of them.
+        disk_space.check(Some(&dir));
+        assert_eq!(1, disk_space.list().len());
+
+        disk_space.check(Some(&std::env::current_dir().unwrap()));
+        assert_eq!(2, disk_space.list().len());
+
+        // Every disk is critically low when all of it must be free.
+        let settings = edgelet_settings::watchdog::DiskSpace {
+            warning_free_percent: 101,
+            critical_free_percent: 101,
+            ..Default::default()
+        };
+        let disk_space = super::DiskSpace::new(settings, vec![dir]);
+        assert!(disk_space.ensure().is_ok());
+
+        disk_space.check(None);
+        assert!(matches!(
+            disk_space.ensure(),
+            Err(crate::Error::LowDiskSpace { .. })
+        ));
+    }
+}
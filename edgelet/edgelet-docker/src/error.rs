@@ -18,6 +18,36 @@ pub enum Error {
     #[error("invalid module type: {0:?}")]
     InvalidModuleType(String),
 
+    #[error("image {0} is not pinned to a digest")]
+    ImageNotPinned(String),
+
+    #[error("image {image} does not match its pinned digest {digest}")]
+    ImageDigestMismatch { image: String, digest: String },
+
+    #[error("signature of image {image} could not be verified: {reason}")]
+    ImageSignature { image: String, reason: String },
+
+    #[error("invalid createOptions for module {module}: {}", .errors.join("; "))]
+    InvalidCreateOptions { module: String, errors: Vec<String> },
+
+    #[error("module {module} maps a device that is not allowed: {reason}")]
+    DeviceNotAllowed { module: String, reason: String },
+
+    #[error("module {module} exceeds its resource limits: {reason}")]
+    ModuleLimits { module: String, reason: String },
+
+    #[error("module {module} is not allowed by the network policy: {reason}")]
+    NetworkPolicy { module: String, reason: String },
+
+    #[error("module {module} may not be started because {reason}")]
+    RestartRefused { module: String, reason: String },
+
+    #[error("not enough disk space: {path} has {available_percent}% free")]
+    LowDiskSpace {
+        path: std::path::PathBuf,
+        available_percent: u64,
+    },
+
     #[error("module operation error: {0}")]
     ModuleOperation(ModuleOperation),
 
@@ -12,11 +12,13 @@ use crate::Error;
 
 const IMAGE_USE_FILENAME: &str = "image_use";
 const TMP_FILENAME: &str = "image_use_tmp";
+const LAST_RUN_FILENAME: &str = "last_run";
 
 #[derive(Debug, Clone)]
 struct ImagePruneInner {
     image_use_filepath: String,
     tmp_filepath: String,
+    last_run_filepath: PathBuf,
     settings: ImagePruneSettings,
 }
 
@@ -45,6 +47,7 @@ impl ImagePruneData {
             inner: Arc::new(Mutex::new(ImagePruneInner {
                 image_use_filepath: image_use_filepath.to_string(),
                 tmp_filepath: tmp_filepath.to_string(),
+                last_run_filepath: homedir.join(LAST_RUN_FILENAME),
                 settings,
             })),
         })
@@ -97,6 +100,63 @@ impl ImagePruneData {
         Ok(())
     }
 
+    /// The images tracked for garbage collection, with the time each was last used.
+    ///
+    /// Fails rather than waits if garbage collection is using the data, so that it can be
+    /// called to find out why garbage collection doesn't complete.
+    pub fn tracked_images(&self) -> Result<HashMap<String, Duration>, Error> {
+        let guard = self
+            .inner
+            .try_lock()
+            .map_err(|_| Error::LockError("image garbage collection data is in use".to_string()))?;
+
+        get_images_with_timestamp(guard.image_use_filepath.clone())
+    }
+
+    /// When garbage collection last completed (in epoch), or `None` if it never has on
+    /// this device.
+    pub fn last_run(&self) -> Result<Option<Duration>, Error> {
+        let guard = self
+            .inner
+            .lock()
+            .map_err(|e| Error::LockError(e.to_string()))?;
+
+        match fs::read_to_string(&guard.last_run_filepath) {
+            Ok(contents) => Ok(Some(Duration::from_secs(
+                contents
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(Error::ParseIntError)?,
+            ))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Error::FileOperation(format!(
+                "Could not read last garbage collection time: {err}"
+            ))),
+        }
+    }
+
+    /// Record that garbage collection completed now, so that a run missed while the
+    /// device was off can be made up when the daemon next starts.
+    pub fn record_run(&self) -> Result<(), Error> {
+        let guard = self
+            .inner
+            .lock()
+            .map_err(|e| Error::LockError(e.to_string()))?;
+
+        let current_time = std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(Error::GetCurrentTimeEpoch)?;
+
+        // write to the temp file and then rename, as for the image use data
+        fs::write(&guard.tmp_filepath, current_time.as_secs().to_string())
+            .and_then(|()| fs::rename(&guard.tmp_filepath, &guard.last_run_filepath))
+            .map_err(|err| {
+                Error::FileOperation(format!(
+                    "Could not update last garbage collection time: {err}"
+                ))
+            })
+    }
+
     /// <summary>
     /// This method is called during image garbage collection. It returns a map of images that
     /// will be deleted by the image garbage collector.
@@ -462,6 +522,34 @@ mod tests {
 
     /* =============================================================== MORE TESTS ============================================================ */
 
+    #[test]
+    #[serial]
+    fn test_last_run() {
+        let test_file_dir = std::env::current_dir().unwrap().join(TEST_FILE_DIR);
+        if test_file_dir.is_dir() {
+            std::fs::remove_dir_all(test_file_dir.clone()).unwrap();
+        }
+        std::fs::create_dir(Path::new(&test_file_dir)).unwrap();
+
+        let image_use_data =
+            ImagePruneData::new(&test_file_dir, ImagePruneSettings::default()).unwrap();
+        assert_eq!(None, image_use_data.last_run().unwrap());
+
+        let before = std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap();
+        image_use_data.record_run().unwrap();
+
+        // The time is kept across restarts.
+        let image_use_data =
+            ImagePruneData::new(&test_file_dir, ImagePruneSettings::default()).unwrap();
+        let last_run = image_use_data.last_run().unwrap().unwrap();
+        assert!(last_run.as_secs() >= before.as_secs());
+        assert!(!test_file_dir.join(TMP_FILENAME).exists());
+
+        std::fs::remove_dir_all(test_file_dir).unwrap();
+    }
+
     #[test]
     #[serial]
     fn test_file_rename_succeeds() {
@@ -0,0 +1,229 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const TARBALL_EXTENSIONS: &[&str] = &["tar", "tar.gz", "tgz"];
+
+/// Find the `docker save` tarball of an image in `dir`.
+///
+/// Tarballs named after the image, with `/`, `:` and `@` replaced by `_`, are used
+/// without looking inside them, which is the only way to match compressed tarballs.
+/// Otherwise the image names and digests recorded in each uncompressed tarball are
+/// searched.
+pub(crate) fn find_tarball(dir: &Path, image: &str) -> std::io::Result<Option<PathBuf>> {
+    let file_stem = image.replace(['/', ':', '@'], "_");
+
+    for extension in TARBALL_EXTENSIONS {
+        let path = dir.join(format!("{file_stem}.{extension}"));
+
+        if path.is_file() {
+            return Ok(Some(path));
+        }
+    }
+
+    let mut tarballs = std::fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "tar")
+        })
+        .collect::<Vec<_>>();
+    tarballs.sort();
+
+    let image = normalize(image);
+
+    for tarball in tarballs {
+        match tarball_references(&tarball) {
+            Ok(references) => {
+                if references
+                    .iter()
+                    .any(|reference| normalize(reference) == image)
+                {
+                    return Ok(Some(tarball));
+                }
+            }
+            Err(err) => log::warn!(
+                "Failed to read image tarball {}: {}",
+                tarball.display(),
+                err
+            ),
+        }
+    }
+
+    Ok(None)
+}
+
+/// Image references that a tarball holds.
+///
+/// Tarballs from `docker save` list tags in `manifest.json`. Those in the OCI layout,
+/// which newer versions of Docker also write, name their images and list the digests
+/// of their manifests in `index.json`.
+fn tarball_references(path: &Path) -> std::io::Result<Vec<String>> {
+    let mut archive = tar::Archive::new(std::fs::File::open(path)?);
+
+    let mut references = Vec::new();
+
+    for entry in archive.entries_with_seek()? {
+        let mut entry = entry?;
+
+        let name = entry
+            .path()?
+            .to_string_lossy()
+            .trim_start_matches("./")
+            .to_string();
+        if name != "manifest.json" && name != "index.json" {
+            continue;
+        }
+
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents)?;
+
+        if name == "manifest.json" {
+            let manifest: Vec<DockerManifest> = serde_json::from_slice(&contents)?;
+
+            references.extend(manifest.into_iter().flat_map(|image| image.repo_tags));
+        } else {
+            let index: OciIndex = serde_json::from_slice(&contents)?;
+
+            for manifest in index.manifests {
+                if let Some(name) = manifest.annotations.get("io.containerd.image.name") {
+                    if let Some((repo, _)) = crate::pull::split_tag(name) {
+                        references.push(format!("{repo}@{}", manifest.digest));
+                    }
+
+                    references.push(name.clone());
+                }
+            }
+        }
+    }
+
+    Ok(references)
+}
+
+/// Fully-qualified form of an image reference, so that references that Docker treats
+/// as the same image compare equal.
+fn normalize(image: &str) -> String {
+    let (registry, rest) = crate::pull::split_registry(image);
+
+    let rest = if registry == crate::pull::DOCKER_HUB && !rest.contains('/') {
+        format!("library/{rest}")
+    } else {
+        rest.to_string()
+    };
+
+    let image = format!("{registry}/{rest}");
+
+    match crate::pull::split_tag(&image) {
+        Some((repo, tag)) => format!("{repo}:{tag}"),
+        None => image,
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct DockerManifest {
+    #[serde(rename = "RepoTags", default)]
+    repo_tags: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct OciIndex {
+    #[serde(default)]
+    manifests: Vec<OciDescriptor>,
+}
+
+#[derive(serde::Deserialize)]
+struct OciDescriptor {
+    digest: String,
+
+    #[serde(default)]
+    annotations: std::collections::BTreeMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_tarball, normalize};
+
+    fn append_file(builder: &mut tar::Builder<Vec<u8>>, name: &str, contents: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+
+        builder.append_data(&mut header, name, contents).unwrap();
+    }
+
+    #[test]
+    fn normalized_references() {
+        assert_eq!("docker.io/library/ubuntu:latest", normalize("ubuntu"));
+        assert_eq!(
+            "docker.io/library/ubuntu:22.04",
+            normalize("docker.io/library/ubuntu:22.04")
+        );
+        assert_eq!(
+            "docker.io/grafana/grafana:latest",
+            normalize("grafana/grafana")
+        );
+        assert_eq!(
+            "mcr.microsoft.com/azureiotedge-agent:1.4",
+            normalize("mcr.microsoft.com/azureiotedge-agent:1.4")
+        );
+        assert_eq!(
+            "localhost:5000/module@sha256:abcd",
+            normalize("localhost:5000/module@sha256:abcd")
+        );
+    }
+
+    #[test]
+    fn find() {
+        let dir = std::env::temp_dir().join(format!("image-import-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Matched by file name.
+        std::fs::write(
+            dir.join("mcr.microsoft.com_azureiotedge-agent_1.4.tar.gz"),
+            b"",
+        )
+        .unwrap();
+
+        // Matched by the tags in manifest.json.
+        let mut builder = tar::Builder::new(Vec::new());
+        append_file(&mut builder, "layer.tar", &[0; 2048]);
+        append_file(
+            &mut builder,
+            "manifest.json",
+            br#"[{"Config":"config.json","RepoTags":["ubuntu:22.04"],"Layers":["layer.tar"]}]"#,
+        );
+        std::fs::write(dir.join("a.tar"), builder.into_inner().unwrap()).unwrap();
+
+        // Matched by the names and digests in index.json.
+        let mut builder = tar::Builder::new(Vec::new());
+        append_file(
+            &mut builder,
+            "index.json",
+            br#"{"schemaVersion":2,"manifests":[{"digest":"sha256:abcd","annotations":{"io.containerd.image.name":"localhost:5000/module:1.0"}}]}"#,
+        );
+        std::fs::write(dir.join("b.tar"), builder.into_inner().unwrap()).unwrap();
+
+        assert_eq!(
+            Some(dir.join("mcr.microsoft.com_azureiotedge-agent_1.4.tar.gz")),
+            find_tarball(&dir, "mcr.microsoft.com/azureiotedge-agent:1.4").unwrap()
+        );
+        assert_eq!(
+            Some(dir.join("a.tar")),
+            find_tarball(&dir, "docker.io/library/ubuntu:22.04").unwrap()
+        );
+        assert_eq!(
+            Some(dir.join("b.tar")),
+            find_tarball(&dir, "localhost:5000/module:1.0").unwrap()
+        );
+        assert_eq!(
+            Some(dir.join("b.tar")),
+            find_tarball(&dir, "localhost:5000/module@sha256:abcd").unwrap()
+        );
+        assert_eq!(None, find_tarball(&dir, "ubuntu:20.04").unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
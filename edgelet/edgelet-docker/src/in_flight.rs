@@ -0,0 +1,94 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InFlightOperation {
+    pub operation: &'static str,
+
+    /// The module or image that the operation is on.
+    pub target: String,
+
+    pub started: chrono::DateTime<chrono::Utc>,
+}
+
+/// Container engine operations that haven't completed, shared by clones of the runtime,
+/// so that a daemon waiting on the container engine can be told apart from an idle one.
+#[derive(Clone, Default)]
+pub(crate) struct InFlight {
+    operations:
+        std::sync::Arc<std::sync::Mutex<std::collections::BTreeMap<u64, InFlightOperation>>>,
+    next_id: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+/// Removes its operation from the in-flight operations when dropped, whether the
+/// operation completed, failed or was cancelled.
+pub(crate) struct Guard {
+    in_flight: InFlight,
+    id: u64,
+}
+
+impl InFlight {
+    pub fn begin(&self, operation: &'static str, target: &str) -> Guard {
+        let id = self
+            .next_id
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        self.lock().insert(
+            id,
+            InFlightOperation {
+                operation,
+                target: target.to_string(),
+                started: chrono::Utc::now(),
+            },
+        );
+
+        Guard {
+            in_flight: self.clone(),
+            id,
+        }
+    }
+
+    /// Operations in the order they started.
+    pub fn list(&self) -> Vec<InFlightOperation> {
+        self.lock().values().cloned().collect()
+    }
+
+    fn lock(
+        &self,
+    ) -> std::sync::MutexGuard<'_, std::collections::BTreeMap<u64, InFlightOperation>> {
+        self.operations
+            .lock()
+            .expect("in-flight operations lock poisoned")
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        self.in_flight.lock().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn begin_and_drop() {
+        let in_flight = super::InFlight::default();
+
+        let pull = in_flight.begin("pull", "mcr.microsoft.com/azureiotedge-agent:1.4");
+        let start = in_flight.begin("start", "edgeAgent");
+
+        let operations = in_flight.list();
+        assert_eq!(2, operations.len());
+        assert_eq!("pull", operations[0].operation);
+        assert_eq!("edgeAgent", operations[1].target);
+
+        drop(pull);
+
+        let operations = in_flight.list();
+        assert_eq!(1, operations.len());
+        assert_eq!("start", operations[0].operation);
+
+        drop(start);
+        assert!(in_flight.list().is_empty());
+    }
+}
@@ -13,15 +13,32 @@
 )]
 
 // mod client;
+mod credentials;
+mod digest;
+mod disk_space;
 mod error;
 mod image_prune_data;
+mod import;
+mod in_flight;
 mod module;
+mod pre_stop;
+mod pull;
+mod pull_scheduler;
+mod registry;
+mod restart_policy;
 mod runtime;
+mod sbom;
+mod signature;
+mod standby;
+mod template;
+mod validate;
 
 pub use error::Error;
 pub use image_prune_data::ImagePruneData;
+pub use in_flight::InFlightOperation;
 pub use module::{DockerModule, MODULE_TYPE};
-pub use runtime::{init_client, DockerModuleRuntime};
+pub use registry::connect;
+pub use runtime::{init_client, ContainerEvent, DockerModuleRuntime};
 
 use tokio::sync::mpsc::UnboundedSender;
 
@@ -0,0 +1,197 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Interval at which a module that was signaled is checked for having exited.
+pub(crate) const EXIT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+#[derive(Debug, serde::Serialize)]
+struct Notification<'a> {
+    module: &'a str,
+    action: &'a str,
+}
+
+/// The endpoint of a module's HTTP hook. A host that is the module's name can only be
+/// resolved by other containers, so it's replaced with the module's address, preferring
+/// its address on the modules' network.
+pub(crate) fn endpoint(
+    url: &url::Url,
+    module: &str,
+    module_network: &str,
+    container: &docker::models::InlineResponse200,
+) -> anyhow::Result<hyper::Uri> {
+    anyhow::ensure!(
+        url.scheme() == "http",
+        "pre-stop hook {} is not an http URL",
+        url
+    );
+
+    let mut url = url.clone();
+
+    if url
+        .host_str()
+        .map_or(false, |host| host.eq_ignore_ascii_case(module))
+    {
+        let networks = container
+            .network_settings()
+            .and_then(docker::models::NetworkSettings::networks);
+        let address = networks
+            .and_then(|networks| networks.get(module_network))
+            .into_iter()
+            .chain(networks.into_iter().flat_map(|networks| networks.values()))
+            .filter_map(docker::models::EndpointSettings::ip_address)
+            .find(|address| !address.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("module {} has no network address", module))?;
+        let address: std::net::IpAddr = address.parse()?;
+
+        url.set_ip_host(address)
+            .map_err(|()| anyhow::anyhow!("could not set the host of {}", url))?;
+    }
+
+    Ok(url.as_str().parse()?)
+}
+
+/// POST the notification to a module's HTTP hook. The module acknowledges with a
+/// success status.
+pub(crate) async fn post(uri: &hyper::Uri, module: &str, action: &str) -> anyhow::Result<()> {
+    let host = uri
+        .host()
+        .ok_or_else(|| anyhow::anyhow!("{} has no host", uri))?;
+    let port = uri.port_u16().unwrap_or(80);
+
+    let stream =
+        tokio::net::TcpStream::connect((host.trim_start_matches('[').trim_end_matches(']'), port))
+            .await?;
+
+    let (mut sender, connection) = hyper::client::conn::handshake(stream).await?;
+    edgelet_core::tasks::spawn("pre_stop::connection", async move {
+        if let Err(err) = connection.await {
+            log::debug!("Pre-stop hook connection closed: {}", err);
+        }
+    });
+
+    let body = serde_json::to_vec(&Notification { module, action })?;
+    let path = uri.path_and_query().map_or("/", |path| path.as_str());
+    let request = hyper::Request::post(path)
+        .header(
+            hyper::header::HOST,
+            uri.authority().map_or(host, |a| a.as_str()),
+        )
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(hyper::Body::from(body))?;
+
+    let response = sender.send_request(request).await?;
+    anyhow::ensure!(
+        response.status().is_success(),
+        "{} responded with {}",
+        uri,
+        response.status()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::{endpoint, post};
+
+    fn container(networks: &[(&str, &str)]) -> docker::models::InlineResponse200 {
+        let networks: serde_json::Map<_, _> = networks
+            .iter()
+            .map(|(network, address)| {
+                (
+                    network.to_string(),
+                    serde_json::json!({ "IPAddress": address }),
+                )
+            })
+            .collect();
+
+        serde_json::from_value(serde_json::json!({
+            "NetworkSettings": { "Networks": networks },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn resolve_endpoint() {
+        let url: url::Url = "http://streamProcessor:8080/drain?wait=true"
+            .parse()
+            .unwrap();
+
+        let inspect = container(&[("bridge", "172.17.0.5"), ("azure-iot-edge", "172.18.0.7")]);
+        assert_eq!(
+            "http://172.18.0.7:8080/drain?wait=true",
+            endpoint(&url, "streamProcessor", "azure-iot-edge", &inspect)
+                .unwrap()
+                .to_string()
+        );
+
+        // Any address of the module will do if it isn't on the modules' network.
+        assert_eq!(
+            "http://172.17.0.5:8080/drain?wait=true",
+            endpoint(&url, "streamProcessor", "custom", &inspect)
+                .unwrap()
+                .to_string()
+        );
+
+        // Other hosts are kept.
+        let other: url::Url = "http://127.0.0.1:9000/drain".parse().unwrap();
+        assert_eq!(
+            "http://127.0.0.1:9000/drain",
+            endpoint(&other, "streamProcessor", "azure-iot-edge", &inspect)
+                .unwrap()
+                .to_string()
+        );
+
+        endpoint(&url, "streamProcessor", "azure-iot-edge", &container(&[])).unwrap_err();
+
+        let https: url::Url = "https://streamProcessor/drain".parse().unwrap();
+        endpoint(&https, "streamProcessor", "azure-iot-edge", &inspect).unwrap_err();
+    }
+
+    async fn serve(listener: tokio::net::TcpListener, status: &'static str) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        // The notification is the last part of the request.
+        let mut request = Vec::new();
+        let mut buf = [0; 4096];
+        while !request.ends_with(b"}") {
+            let read = stream.read(&mut buf).await.unwrap();
+            assert_ne!(
+                0, read,
+                "connection closed before the notification was read"
+            );
+            request.extend_from_slice(&buf[..read]);
+        }
+
+        stream
+            .write_all(format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\n\r\n").as_bytes())
+            .await
+            .unwrap();
+
+        String::from_utf8(request).unwrap()
+    }
+
+    #[tokio::test]
+    async fn post_notification() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let uri: hyper::Uri = format!("http://{}/drain", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+        let server = tokio::spawn(serve(listener, "200 OK"));
+
+        post(&uri, "streamProcessor", "stop").await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /drain HTTP/1.1\r\n"));
+        assert!(request.ends_with(r#"{"module":"streamProcessor","action":"stop"}"#));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let uri: hyper::Uri = format!("http://{}/drain", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+        tokio::spawn(serve(listener, "503 Service Unavailable"));
+
+        post(&uri, "streamProcessor", "restart").await.unwrap_err();
+    }
+}
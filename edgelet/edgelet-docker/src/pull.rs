@@ -0,0 +1,485 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) const DOCKER_HUB: &str = "docker.io";
+
+/// Result of the last pull of an image, reported in the status of modules that use it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PullOutcome {
+    /// Reference that the image was pulled from, which differs from the image name if it
+    /// was pulled from a fallback registry.
+    pub source: String,
+    pub attempts: u32,
+    pub error: Option<String>,
+
+    /// Whether the image was loaded from the device after the pull failed. `source` is
+    /// the tarball it was loaded from, or the image name if it was already on the device.
+    pub imported: bool,
+
+    /// Whether `source` is a mirror of the image's registry.
+    pub mirrored: bool,
+}
+
+impl PullOutcome {
+    /// Description of the outcome for module status, or `None` if the pull went as
+    /// planned.
+    pub fn description(&self, image: &str) -> Option<String> {
+        if let Some(error) = &self.error {
+            Some(format!(
+                "image pull failed after {} attempt(s): {}",
+                self.attempts, error
+            ))
+        } else if self.imported && self.source == image {
+            Some(format!(
+                "image pull failed after {} attempt(s); using the image on the device",
+                self.attempts
+            ))
+        } else if self.imported {
+            Some(format!("image loaded from {}", self.source))
+        } else if self.mirrored && self.attempts > 1 {
+            Some(format!(
+                "image pulled from mirror {} after {} attempts",
+                self.source, self.attempts
+            ))
+        } else if self.mirrored {
+            None
+        } else if self.source != image {
+            Some(format!("image pulled from fallback {}", self.source))
+        } else if self.attempts > 1 {
+            Some(format!("image pulled after {} attempts", self.attempts))
+        } else {
+            None
+        }
+    }
+}
+
+/// Health and statistics of the configured registry mirrors, shared by clones of the
+/// runtime.
+///
+/// A mirror is unhealthy for a cooldown after a pull from it fails with a registry or
+/// network error, or after a periodic check finds it down. A check that finds it up
+/// again ends the cooldown. Unhealthy mirrors are still tried, after the healthy ones,
+/// so that a pull can succeed if every mirror is down.
+#[derive(Clone, Default)]
+pub(crate) struct Mirrors {
+    mirrors: std::sync::Arc<std::sync::Mutex<Vec<MirrorState>>>,
+    cooldown: std::time::Duration,
+}
+
+struct MirrorState {
+    status: edgelet_core::RegistryMirror,
+    unhealthy_until: Option<std::time::Instant>,
+}
+
+impl Mirrors {
+    pub fn new(
+        mirrors: &std::collections::BTreeMap<String, Vec<String>>,
+        cooldown: std::time::Duration,
+    ) -> Self {
+        let mirrors = mirrors
+            .iter()
+            .flat_map(|(registry, mirrors)| {
+                mirrors.iter().map(move |mirror| MirrorState {
+                    status: edgelet_core::RegistryMirror {
+                        registry: registry.clone(),
+                        mirror: mirror.clone(),
+                        healthy: true,
+                        pulls: 0,
+                        failures: 0,
+                        last_success: None,
+                        last_failure: None,
+                        last_error: None,
+                    },
+                    unhealthy_until: None,
+                })
+            })
+            .collect();
+
+        Mirrors {
+            mirrors: std::sync::Arc::new(std::sync::Mutex::new(mirrors)),
+            cooldown,
+        }
+    }
+
+    /// Mirrors of a registry in the order they should be tried.
+    pub fn order(&self, registry: &str) -> Vec<String> {
+        let mut mirrors = self.mirrors.lock().expect("mirrors lock poisoned");
+        let now = std::time::Instant::now();
+
+        let mut order: Vec<(bool, String)> = mirrors
+            .iter_mut()
+            .filter(|state| state.status.registry == registry)
+            .map(|state| (!state.refresh(now), state.status.mirror.clone()))
+            .collect();
+
+        // The sort is stable, so mirrors keep their configured order within each group.
+        order.sort_by_key(|(unhealthy, _)| *unhealthy);
+
+        order.into_iter().map(|(_, mirror)| mirror).collect()
+    }
+
+    /// Record the result of a pull from a mirror.
+    pub fn record(&self, registry: &str, mirror: &str, result: &anyhow::Result<()>) {
+        let mut mirrors = self.mirrors.lock().expect("mirrors lock poisoned");
+
+        let state = match mirrors
+            .iter_mut()
+            .find(|state| state.status.registry == registry && state.status.mirror == mirror)
+        {
+            Some(state) => state,
+            None => return,
+        };
+
+        state.status.pulls += 1;
+
+        match result {
+            Ok(()) => {
+                state.status.last_success = Some(chrono::Utc::now());
+                state.set_healthy();
+            }
+            Err(err) => {
+                state.status.failures += 1;
+                state.status.last_failure = Some(chrono::Utc::now());
+                state.status.last_error = Some(err.to_string());
+
+                // A mirror that doesn't have an image is still healthy.
+                if is_retryable(err) {
+                    state.set_unhealthy(self.cooldown);
+                }
+            }
+        }
+    }
+
+    /// Record the result of a check of a mirror. Checks aren't counted as pulls.
+    pub fn record_check(&self, registry: &str, mirror: &str, result: &anyhow::Result<()>) {
+        let mut mirrors = self.mirrors.lock().expect("mirrors lock poisoned");
+
+        let state = match mirrors
+            .iter_mut()
+            .find(|state| state.status.registry == registry && state.status.mirror == mirror)
+        {
+            Some(state) => state,
+            None => return,
+        };
+
+        match result {
+            Ok(()) => {
+                if !state.status.healthy {
+                    log::info!("Registry mirror {} is healthy again", mirror);
+                }
+
+                state.set_healthy();
+            }
+            Err(err) => {
+                state.status.last_error = Some(err.to_string());
+
+                // A mirror that is down stays unhealthy for as long as checks fail.
+                state.set_unhealthy(self.cooldown);
+            }
+        }
+    }
+
+    pub fn list(&self) -> Vec<edgelet_core::RegistryMirror> {
+        let mut mirrors = self.mirrors.lock().expect("mirrors lock poisoned");
+        let now = std::time::Instant::now();
+
+        mirrors
+            .iter_mut()
+            .map(|state| {
+                state.refresh(now);
+
+                state.status.clone()
+            })
+            .collect()
+    }
+}
+
+impl MirrorState {
+    fn set_healthy(&mut self) {
+        self.status.healthy = true;
+        self.unhealthy_until = None;
+    }
+
+    fn set_unhealthy(&mut self, cooldown: std::time::Duration) {
+        if self.status.healthy {
+            log::warn!(
+                "Registry mirror {} is unhealthy for {:?}",
+                self.status.mirror,
+                cooldown
+            );
+        }
+
+        self.status.healthy = false;
+        self.unhealthy_until = Some(std::time::Instant::now() + cooldown);
+    }
+
+    /// Mark the mirror healthy again once its cooldown has passed. Returns whether
+    /// it's healthy.
+    fn refresh(&mut self, now: std::time::Instant) -> bool {
+        if matches!(self.unhealthy_until, Some(until) if until <= now) {
+            self.status.healthy = true;
+            self.unhealthy_until = None;
+        }
+
+        self.status.healthy
+    }
+}
+
+/// Split an image reference into its registry and the rest of the reference.
+///
+/// As in Docker, the first path component is a registry only if it looks like a host.
+pub(crate) fn split_registry(image: &str) -> (&str, &str) {
+    match image.split_once('/') {
+        Some((host, rest)) if host.contains('.') || host.contains(':') || host == "localhost" => {
+            (host, rest)
+        }
+        _ => (DOCKER_HUB, image),
+    }
+}
+
+/// Reference of an image in another registry, keeping its repository and tag.
+pub(crate) fn with_registry(image: &str, registry: &str) -> String {
+    let (host, rest) = split_registry(image);
+    let registry = registry.trim_end_matches('/');
+
+    // Official images on Docker Hub live under "library" in mirrors.
+    if host == DOCKER_HUB && !rest.contains('/') {
+        format!("{registry}/library/{rest}")
+    } else {
+        format!("{registry}/{rest}")
+    }
+}
+
+/// Split a tagged image reference into its repository and tag.
+///
+/// Returns `None` for references by digest, since those can't be tagged.
+pub(crate) fn split_tag(image: &str) -> Option<(&str, &str)> {
+    if image.contains('@') {
+        return None;
+    }
+
+    let name_start = image.rfind('/').map_or(0, |i| i + 1);
+    match image[name_start..].rfind(':') {
+        Some(i) => {
+            let (repo, tag) = image.split_at(name_start + i);
+
+            Some((repo, &tag[1..]))
+        }
+        None => Some((image, "latest")),
+    }
+}
+
+/// Whether another attempt at a failed pull could succeed.
+///
+/// Registries answer with client errors for missing images and bad credentials, which
+/// retries don't fix. Network and registry outages surface as server errors or timeouts.
+pub(crate) fn is_retryable(err: &anyhow::Error) -> bool {
+    err.chain()
+        .find_map(|err| err.downcast_ref::<docker::apis::ApiError>())
+        .map_or(true, |err| !err.code.is_client_error())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_retryable, split_tag, with_registry, Mirrors, PullOutcome};
+
+    #[test]
+    fn fallback_references() {
+        assert_eq!(
+            "mirror.contoso.com/azureiotedge-agent:1.4",
+            with_registry(
+                "mcr.microsoft.com/azureiotedge-agent:1.4",
+                "mirror.contoso.com"
+            )
+        );
+        assert_eq!(
+            "mirror:5000/library/ubuntu:22.04",
+            with_registry("ubuntu:22.04", "mirror:5000/")
+        );
+        assert_eq!(
+            "mirror:5000/grafana/grafana",
+            with_registry("grafana/grafana", "mirror:5000")
+        );
+        assert_eq!(
+            "mirror:5000/module@sha256:abcd",
+            with_registry("localhost:5000/module@sha256:abcd", "mirror:5000")
+        );
+    }
+
+    #[test]
+    fn tags() {
+        assert_eq!(
+            Some(("mcr.microsoft.com/azureiotedge-agent", "1.4")),
+            split_tag("mcr.microsoft.com/azureiotedge-agent:1.4")
+        );
+        assert_eq!(
+            Some(("localhost:5000/module", "latest")),
+            split_tag("localhost:5000/module")
+        );
+        assert_eq!(None, split_tag("module@sha256:abcd"));
+    }
+
+    #[test]
+    fn retryable() {
+        let err = anyhow::anyhow!(docker::apis::ApiError {
+            code: hyper::StatusCode::NOT_FOUND,
+            message: "manifest unknown".to_string(),
+        });
+        assert!(!is_retryable(&err));
+
+        let err = anyhow::anyhow!(docker::apis::ApiError {
+            code: hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            message: "i/o timeout".to_string(),
+        })
+        .context("failed to pull");
+        assert!(is_retryable(&err));
+
+        assert!(is_retryable(&anyhow::anyhow!("pull timed out")));
+    }
+
+    #[test]
+    fn descriptions() {
+        let outcome = PullOutcome {
+            source: "image:1.0".to_string(),
+            attempts: 1,
+            error: None,
+            imported: false,
+            mirrored: false,
+        };
+        assert_eq!(None, outcome.description("image:1.0"));
+
+        let outcome = PullOutcome {
+            source: "image:1.0".to_string(),
+            attempts: 3,
+            error: Some("i/o timeout".to_string()),
+            imported: false,
+            mirrored: false,
+        };
+        assert_eq!(
+            Some("image pull failed after 3 attempt(s): i/o timeout".to_string()),
+            outcome.description("image:1.0")
+        );
+
+        let outcome = PullOutcome {
+            source: "mirror/image:1.0".to_string(),
+            attempts: 4,
+            error: None,
+            imported: false,
+            mirrored: false,
+        };
+        assert_eq!(
+            Some("image pulled from fallback mirror/image:1.0".to_string()),
+            outcome.description("image:1.0")
+        );
+
+        let outcome = PullOutcome {
+            source: "/var/lib/images/image.tar".to_string(),
+            attempts: 3,
+            error: None,
+            imported: true,
+            mirrored: false,
+        };
+        assert_eq!(
+            Some("image loaded from /var/lib/images/image.tar".to_string()),
+            outcome.description("image:1.0")
+        );
+
+        let outcome = PullOutcome {
+            source: "mirror/image:1.0".to_string(),
+            attempts: 1,
+            error: None,
+            imported: false,
+            mirrored: true,
+        };
+        assert_eq!(None, outcome.description("image:1.0"));
+
+        let outcome = PullOutcome {
+            attempts: 2,
+            ..outcome
+        };
+        assert_eq!(
+            Some("image pulled from mirror mirror/image:1.0 after 2 attempts".to_string()),
+            outcome.description("image:1.0")
+        );
+    }
+
+    #[test]
+    fn mirrors() {
+        let config: std::collections::BTreeMap<_, _> = [(
+            "mcr.microsoft.com".to_string(),
+            vec!["mirror1".to_string(), "mirror2".to_string()],
+        )]
+        .into_iter()
+        .collect();
+        let mirrors = Mirrors::new(&config, std::time::Duration::from_secs(300));
+
+        assert_eq!(
+            ["mirror1", "mirror2"],
+            mirrors.order("mcr.microsoft.com").as_slice()
+        );
+        assert!(mirrors.order("docker.io").is_empty());
+
+        // A mirror that doesn't have the image stays healthy.
+        let not_found = Err(anyhow::anyhow!(docker::apis::ApiError {
+            code: hyper::StatusCode::NOT_FOUND,
+            message: "manifest unknown".to_string(),
+        }));
+        mirrors.record("mcr.microsoft.com", "mirror1", &not_found);
+        assert_eq!(
+            ["mirror1", "mirror2"],
+            mirrors.order("mcr.microsoft.com").as_slice()
+        );
+
+        // A mirror that is down is tried last.
+        mirrors.record(
+            "mcr.microsoft.com",
+            "mirror1",
+            &Err(anyhow::anyhow!("pull timed out")),
+        );
+        assert_eq!(
+            ["mirror2", "mirror1"],
+            mirrors.order("mcr.microsoft.com").as_slice()
+        );
+
+        mirrors.record("mcr.microsoft.com", "mirror2", &Ok(()));
+
+        let list = mirrors.list();
+        assert!(!list[0].healthy);
+        assert_eq!(2, list[0].pulls);
+        assert_eq!(2, list[0].failures);
+        assert_eq!(Some("pull timed out"), list[0].last_error.as_deref());
+        assert!(list[1].healthy);
+        assert_eq!(1, list[1].pulls);
+        assert!(list[1].last_success.is_some());
+
+        // A check that finds a mirror up ends its cooldown, and one that finds it down
+        // doesn't count as a pull.
+        mirrors.record_check("mcr.microsoft.com", "mirror1", &Ok(()));
+        mirrors.record_check(
+            "mcr.microsoft.com",
+            "mirror2",
+            &Err(anyhow::anyhow!("connection refused")),
+        );
+        assert_eq!(
+            ["mirror1", "mirror2"],
+            mirrors.order("mcr.microsoft.com").as_slice()
+        );
+
+        let list = mirrors.list();
+        assert!(list[0].healthy);
+        assert!(!list[1].healthy);
+        assert_eq!(1, list[1].pulls);
+        assert_eq!(Some("connection refused"), list[1].last_error.as_deref());
+
+        // Mirrors recover after the cooldown.
+        let mirrors = Mirrors::new(&config, std::time::Duration::ZERO);
+        mirrors.record(
+            "mcr.microsoft.com",
+            "mirror1",
+            &Err(anyhow::anyhow!("pull timed out")),
+        );
+        assert_eq!(
+            ["mirror1", "mirror2"],
+            mirrors.order("mcr.microsoft.com").as_slice()
+        );
+    }
+}
@@ -0,0 +1,417 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+type Done = tokio::sync::watch::Receiver<Option<Result<(), String>>>;
+
+/// Image pulls of the runtime, shared by its clones.
+///
+/// At most `max_concurrent` pulls run at once, so that a deployment that updates many
+/// modules doesn't saturate a slow link; the others wait for a slot in the order they
+/// were requested. A pull of an image that is already being pulled waits for that pull
+/// and gets its result, since pulling the same layers again would only compete with it.
+///
+/// Pulls are counted in batches, from the first pull after the scheduler was idle, so
+/// that module status can report how many of a deployment's images were pulled. Pulls
+/// are reported in the status of the modules that requested them rather than of the
+/// modules whose containers use the image, since a module that is being updated still
+/// runs its old image.
+#[derive(Clone, Default)]
+pub(crate) struct PullScheduler {
+    slots: Option<Arc<tokio::sync::Semaphore>>,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    pulls: HashMap<String, Pull>,
+
+    /// The image that each module is waiting for.
+    modules: HashMap<String, String>,
+
+    requested: usize,
+    completed: usize,
+}
+
+struct Pull {
+    progress: Option<Progress>,
+    done: Done,
+}
+
+/// Progress of a pull, from the engine's progress messages.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Progress {
+    /// Bytes downloaded and total bytes by layer. Layers that were already on the device
+    /// have no size.
+    layers: BTreeMap<String, (u64, u64)>,
+}
+
+/// A request to pull an image.
+pub(crate) enum Join {
+    /// The image isn't being pulled, so this request pulls it.
+    Leader(Leader),
+
+    /// The image is being pulled by an earlier request.
+    Follower(Follower),
+}
+
+/// Pulls an image on behalf of every request for it. Followers are told the result of
+/// the pull when it's dropped.
+pub(crate) struct Leader {
+    scheduler: PullScheduler,
+    image: String,
+    result: Option<Result<(), String>>,
+    done: tokio::sync::watch::Sender<Option<Result<(), String>>>,
+}
+
+pub(crate) struct Follower {
+    done: Done,
+}
+
+impl PullScheduler {
+    /// A scheduler that runs at most `max_concurrent` pulls at once, or any number of
+    /// pulls if not set.
+    pub fn new(max_concurrent: Option<usize>) -> Self {
+        PullScheduler {
+            slots: max_concurrent.map(|max| Arc::new(tokio::sync::Semaphore::new(max.max(1)))),
+            state: Arc::default(),
+        }
+    }
+
+    /// Request a pull of an image, for a module if given.
+    pub fn join(&self, image: &str, module: Option<&str>) -> Join {
+        let mut state = self.lock();
+
+        if let Some(module) = module {
+            state.modules.insert(module.to_string(), image.to_string());
+        }
+
+        if let Some(pull) = state.pulls.get(image) {
+            return Join::Follower(Follower {
+                done: pull.done.clone(),
+            });
+        }
+
+        let (done, receiver) = tokio::sync::watch::channel(None);
+        state.pulls.insert(
+            image.to_string(),
+            Pull {
+                progress: None,
+                done: receiver,
+            },
+        );
+        state.requested += 1;
+
+        Join::Leader(Leader {
+            scheduler: self.clone(),
+            image: image.to_string(),
+            result: None,
+            done,
+        })
+    }
+
+    /// Description of the pull that a module is waiting for, for its status, or `None`
+    /// if it isn't waiting for one.
+    pub fn status(&self, module: &str) -> Option<String> {
+        let state = self.lock();
+        let image = state.modules.get(module)?;
+
+        state.describe(image)
+    }
+
+    /// The pulls that haven't completed.
+    pub fn list(&self) -> Vec<edgelet_core::ImagePull> {
+        let state = self.lock();
+
+        let mut pulls: Vec<_> = state
+            .pulls
+            .keys()
+            .map(|image| {
+                let mut modules: Vec<_> = state
+                    .modules
+                    .iter()
+                    .filter(|(_, module_image)| *module_image == image)
+                    .map(|(module, _)| module.clone())
+                    .collect();
+                modules.sort();
+
+                edgelet_core::ImagePull {
+                    image: image.clone(),
+                    modules,
+                    status: state.describe(image).unwrap_or_default(),
+                }
+            })
+            .collect();
+        pulls.sort_by(|a, b| a.image.cmp(&b.image));
+
+        pulls
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("pull scheduler lock poisoned")
+    }
+}
+
+impl State {
+    fn describe(&self, image: &str) -> Option<String> {
+        let pull = self.pulls.get(image)?;
+        let pulled = format!("{}/{} images pulled", self.completed, self.requested);
+
+        Some(match &pull.progress {
+            Some(progress) => format!(
+                "pulling image, {}% ({})",
+                progress.percent().unwrap_or_default(),
+                pulled
+            ),
+            None => format!("waiting to pull image ({pulled})"),
+        })
+    }
+}
+
+impl Leader {
+    /// Wait for a slot to pull the image in. The pull holds the slot until the returned
+    /// permit is dropped.
+    pub async fn slot(&self) -> Option<tokio::sync::OwnedSemaphorePermit> {
+        let permit = match &self.scheduler.slots {
+            Some(slots) => Some(
+                slots
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("pull slots are never closed"),
+            ),
+            None => None,
+        };
+
+        if let Some(pull) = self.scheduler.lock().pulls.get_mut(&self.image) {
+            pull.progress = Some(Progress::default());
+        }
+
+        permit
+    }
+
+    /// Record a progress message of the engine.
+    pub fn progress(&self, message: &serde_json::Map<String, serde_json::Value>) {
+        if let Some(progress) = self
+            .scheduler
+            .lock()
+            .pulls
+            .get_mut(&self.image)
+            .and_then(|pull| pull.progress.as_mut())
+        {
+            progress.update(message);
+        }
+    }
+
+    /// Record the result of the pull, which is passed on to the followers.
+    pub fn finish(&mut self, result: &anyhow::Result<()>) {
+        self.result = Some(match result {
+            Ok(()) => Ok(()),
+            Err(err) => Err(format!("{err:#}")),
+        });
+    }
+}
+
+impl Drop for Leader {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.lock();
+
+        state.pulls.remove(&self.image);
+        state.modules.retain(|_, image| *image != self.image);
+        state.completed += 1;
+        if state.pulls.is_empty() {
+            state.requested = 0;
+            state.completed = 0;
+        }
+
+        let result = self
+            .result
+            .take()
+            .unwrap_or_else(|| Err("the pull was cancelled".to_string()));
+        self.done.send_replace(Some(result));
+    }
+}
+
+impl Follower {
+    /// Wait for the pull of the leader and get its result.
+    pub async fn wait(mut self) -> anyhow::Result<()> {
+        let result = self
+            .done
+            .wait_for(Option::is_some)
+            .await
+            .map_err(|_| anyhow::anyhow!("the pull was cancelled"))?
+            .clone()
+            .expect("waited for a result");
+
+        result.map_err(|err| anyhow::anyhow!(err))
+    }
+}
+
+impl Progress {
+    fn update(&mut self, message: &serde_json::Map<String, serde_json::Value>) {
+        let Some(id) = message.get("id").and_then(serde_json::Value::as_str) else {
+            return;
+        };
+        let status = message
+            .get("status")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default();
+        let detail = |key: &str| {
+            message
+                .get("progressDetail")
+                .and_then(|detail| detail.get(key))
+                .and_then(serde_json::Value::as_u64)
+        };
+
+        match status {
+            "Pulling fs layer" | "Waiting" => {
+                self.layers.entry(id.to_string()).or_default();
+            }
+            "Downloading" => {
+                if let (Some(current), Some(total)) = (detail("current"), detail("total")) {
+                    self.layers
+                        .insert(id.to_string(), (current.min(total), total));
+                }
+            }
+            "Download complete" | "Pull complete" | "Already exists" => {
+                let layer = self.layers.entry(id.to_string()).or_default();
+                layer.0 = layer.1;
+            }
+            _ => (),
+        }
+    }
+
+    /// Percentage of the bytes of the image's layers that were downloaded, once the size
+    /// of any layer is known.
+    pub fn percent(&self) -> Option<u64> {
+        let (current, total) =
+            self.layers
+                .values()
+                .fold((0, 0), |(current, total), (layer_current, layer_total)| {
+                    (current + layer_current, total + layer_total)
+                });
+
+        (total > 0).then(|| current * 100 / total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Join, Progress, PullScheduler};
+
+    fn message(value: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+        match value {
+            serde_json::Value::Object(message) => message,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn progress() {
+        let mut progress = Progress::default();
+
+        progress.update(&message(serde_json::json!({
+            "status": "Pulling from azureiotedge-hub", "id": "1.5"
+        })));
+        progress.update(&message(serde_json::json!({
+            "status": "Already exists", "id": "a1"
+        })));
+        progress.update(&message(serde_json::json!({
+            "status": "Pulling fs layer", "id": "b2"
+        })));
+        assert_eq!(None, progress.percent());
+
+        progress.update(&message(serde_json::json!({
+            "status": "Downloading", "id": "b2",
+            "progressDetail": { "current": 300, "total": 1000 }
+        })));
+        progress.update(&message(serde_json::json!({
+            "status": "Downloading", "id": "c3",
+            "progressDetail": { "current": 100, "total": 1000 }
+        })));
+        assert_eq!(Some(20), progress.percent());
+
+        progress.update(&message(serde_json::json!({
+            "status": "Download complete", "id": "b2"
+        })));
+        assert_eq!(Some(55), progress.percent());
+    }
+
+    #[tokio::test]
+    async fn deduplicate() {
+        let scheduler = PullScheduler::new(Some(1));
+
+        let Join::Leader(mut leader) = scheduler.join("hub:1.5", Some("edgeHub")) else {
+            panic!("first pull of an image should lead");
+        };
+        let Join::Follower(follower) = scheduler.join("hub:1.5", Some("otherHub")) else {
+            panic!("pull of an image being pulled should follow");
+        };
+        let Join::Leader(other) = scheduler.join("agent:1.5", None) else {
+            panic!("first pull of an image should lead");
+        };
+
+        // Pulls are reported for the modules that requested them.
+        assert_eq!(
+            Some("waiting to pull image (0/2 images pulled)".to_string()),
+            scheduler.status("edgeHub")
+        );
+        assert_eq!(scheduler.status("edgeHub"), scheduler.status("otherHub"));
+        assert_eq!(None, scheduler.status("edgeAgent"));
+
+        let pulls = scheduler.list();
+        assert_eq!(2, pulls.len());
+        assert_eq!("agent:1.5", pulls[0].image);
+        assert!(pulls[0].modules.is_empty());
+        assert_eq!("hub:1.5", pulls[1].image);
+        assert_eq!(vec!["edgeHub", "otherHub"], pulls[1].modules);
+
+        let slot = leader.slot().await;
+        assert_eq!(
+            Some("pulling image, 0% (0/2 images pulled)".to_string()),
+            scheduler.status("edgeHub")
+        );
+
+        // The other pull waits for the slot.
+        let mut other_slot = Box::pin(async move {
+            let slot = other.slot().await;
+            (other, slot)
+        });
+        assert!(futures::poll!(other_slot.as_mut()).is_pending());
+
+        leader.finish(&Err(anyhow::anyhow!("manifest unknown")));
+        drop(slot);
+        drop(leader);
+        assert_eq!(
+            "manifest unknown",
+            follower.wait().await.unwrap_err().to_string()
+        );
+        assert_eq!(None, scheduler.status("edgeHub"));
+
+        let (other, _slot) = other_slot.await;
+
+        // A module that joins a pull is reported from then on.
+        let Join::Follower(follower) = scheduler.join("agent:1.5", Some("edgeAgent")) else {
+            panic!("pull of an image being pulled should follow");
+        };
+        assert_eq!(
+            Some("pulling image, 0% (1/2 images pulled)".to_string()),
+            scheduler.status("edgeAgent")
+        );
+
+        // A cancelled pull fails its followers, and the next batch starts over.
+        drop(other);
+        follower.wait().await.unwrap_err();
+        assert_eq!(None, scheduler.status("edgeAgent"));
+        assert!(scheduler.list().is_empty());
+
+        let Join::Leader(_leader) = scheduler.join("agent:1.5", Some("edgeAgent")) else {
+            panic!("first pull of an image should lead");
+        };
+        assert_eq!(
+            Some("waiting to pull image (0/1 images pulled)".to_string()),
+            scheduler.status("edgeAgent")
+        );
+    }
+}
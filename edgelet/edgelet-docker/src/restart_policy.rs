@@ -0,0 +1,436 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_settings::watchdog::{GiveUpAction, RestartPolicy};
+
+use crate::error::Error;
+
+/// Restart circuit breakers of modules with a restart policy.
+///
+/// Every start and restart of such a module goes through its breaker. Starts are held
+/// back while the module backs off, and refused entirely once it has started more than
+/// its policy allows within the window.
+///
+/// The breakers are saved in the state directory, so that a module in a crash loop
+/// can't get a fresh budget by restarting the daemon.
+#[derive(Clone)]
+pub(crate) struct CircuitBreakers {
+    policies: std::sync::Arc<std::collections::BTreeMap<String, RestartPolicy>>,
+    breakers: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, Breaker>>>,
+    path: std::path::PathBuf,
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+struct Breaker {
+    /// Starts within the policy's window, oldest first.
+    starts: std::collections::VecDeque<chrono::DateTime<chrono::Utc>>,
+
+    /// Set while the breaker is open.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    open: Option<Open>,
+}
+
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Open {
+    Until(chrono::DateTime<chrono::Utc>),
+    UntilDeployed,
+}
+
+impl Open {
+    fn until(self) -> Option<chrono::DateTime<chrono::Utc>> {
+        match self {
+            Open::Until(until) => Some(until),
+            Open::UntilDeployed => None,
+        }
+    }
+}
+
+/// Why a start was refused.
+pub(crate) struct Refusal {
+    pub error: Error,
+
+    /// The breaker tripped on this start, so the module should be stopped.
+    pub tripped: bool,
+}
+
+impl CircuitBreakers {
+    pub fn new(
+        policies: &std::collections::BTreeMap<String, RestartPolicy>,
+        state_dir: &std::path::Path,
+    ) -> Self {
+        let path = state_dir.join("circuit_breakers.json");
+
+        let mut breakers: std::collections::HashMap<String, Breaker> = match std::fs::read(&path) {
+            Ok(breakers) => serde_json::from_slice(&breakers).unwrap_or_else(|err| {
+                log::warn!(
+                    "Ignoring invalid circuit breakers in {}: {}",
+                    path.display(),
+                    err
+                );
+
+                Default::default()
+            }),
+            Err(err) => {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Failed to read circuit breakers: {}", err);
+                }
+
+                Default::default()
+            }
+        };
+
+        // Modules whose policy was removed are no longer limited.
+        breakers.retain(|module, _| policies.contains_key(module));
+
+        CircuitBreakers {
+            policies: std::sync::Arc::new(policies.clone()),
+            breakers: std::sync::Arc::new(std::sync::Mutex::new(breakers)),
+            path,
+        }
+    }
+
+    /// Whether a module's starts are limited by a restart policy.
+    pub fn has_policy(&self, module: &str) -> bool {
+        self.policies.contains_key(module)
+    }
+
+    /// Check whether a module may be started now, and count the start if it may.
+    pub fn start(&self, module: &str) -> Result<(), Refusal> {
+        self.start_at(module, chrono::Utc::now())
+    }
+
+    fn start_at(&self, module: &str, now: chrono::DateTime<chrono::Utc>) -> Result<(), Refusal> {
+        let policy = match self.policies.get(module) {
+            Some(policy) => policy,
+            None => return Ok(()),
+        };
+
+        let mut breakers = self
+            .breakers
+            .lock()
+            .expect("circuit breakers lock poisoned");
+        let result = Self::start_breaker(
+            breakers.entry(module.to_string()).or_default(),
+            module,
+            policy,
+            now,
+        );
+
+        // Only starts that are counted or trip the breaker change it.
+        if result
+            .as_ref()
+            .err()
+            .map_or(true, |refusal| refusal.tripped)
+        {
+            self.save(&breakers);
+        }
+
+        result
+    }
+
+    fn start_breaker(
+        breaker: &mut Breaker,
+        module: &str,
+        policy: &RestartPolicy,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), Refusal> {
+        match breaker.open {
+            Some(Open::UntilDeployed) => {
+                return Err(Refusal {
+                    error: Error::RestartRefused {
+                        module: module.to_string(),
+                        reason: "its circuit breaker is open until it is deployed again"
+                            .to_string(),
+                    },
+                    tripped: false,
+                });
+            }
+
+            Some(Open::Until(until)) if now < until => {
+                let reason = format!("its circuit breaker is open until {}", until.to_rfc3339());
+
+                return Err(Refusal {
+                    error: Error::RestartRefused {
+                        module: module.to_string(),
+                        reason,
+                    },
+                    tripped: false,
+                });
+            }
+
+            // The cooldown has passed, so the module gets a fresh window.
+            Some(Open::Until(_)) => {
+                breaker.open = None;
+                breaker.starts.clear();
+            }
+
+            None => {}
+        }
+
+        breaker.prune(policy, now);
+
+        let starts = u32::try_from(breaker.starts.len()).unwrap_or(u32::MAX);
+
+        if starts >= policy.max_restarts {
+            log::warn!(
+                "Module {} started {} times within {:?}, tripping its circuit breaker",
+                module,
+                starts,
+                policy.window
+            );
+
+            // A cooldown too long to represent never ends.
+            let open = match policy.give_up {
+                GiveUpAction::Stop => chrono::Duration::from_std(policy.cooldown)
+                    .ok()
+                    .and_then(|cooldown| now.checked_add_signed(cooldown))
+                    .map_or(Open::UntilDeployed, Open::Until),
+                GiveUpAction::Disable => Open::UntilDeployed,
+            };
+            breaker.open = Some(open);
+            let until = open.until();
+
+            let reason = match until {
+                Some(until) => format!(
+                    "its circuit breaker tripped and is open until {}",
+                    until.to_rfc3339()
+                ),
+                None => {
+                    "its circuit breaker tripped and is open until it is deployed again".to_string()
+                }
+            };
+
+            return Err(Refusal {
+                error: Error::RestartRefused {
+                    module: module.to_string(),
+                    reason,
+                },
+                tripped: true,
+            });
+        }
+
+        if let Some(next_retry) = breaker.next_retry(policy) {
+            if now < next_retry {
+                return Err(Refusal {
+                    error: Error::RestartRefused {
+                        module: module.to_string(),
+                        reason: format!("it is backing off until {}", next_retry.to_rfc3339()),
+                    },
+                    tripped: false,
+                });
+            }
+        }
+
+        breaker.starts.push_back(now);
+
+        Ok(())
+    }
+
+    /// Close a module's breaker and forget its starts, e.g. after it is deployed again.
+    pub fn reset(&self, module: &str) {
+        let mut breakers = self
+            .breakers
+            .lock()
+            .expect("circuit breakers lock poisoned");
+
+        if breakers.remove(module).is_some() {
+            self.save(&breakers);
+        }
+    }
+
+    pub fn status(&self, module: &str) -> Option<edgelet_core::CircuitBreaker> {
+        self.status_at(module, chrono::Utc::now())
+    }
+
+    fn status_at(
+        &self,
+        module: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Option<edgelet_core::CircuitBreaker> {
+        let policy = self.policies.get(module)?;
+
+        let mut breakers = self
+            .breakers
+            .lock()
+            .expect("circuit breakers lock poisoned");
+        let breaker = breakers.entry(module.to_string()).or_default();
+        breaker.prune(policy, now);
+
+        let (tripped, next_retry) = match breaker.open.map(Open::until) {
+            Some(until) => (until.map_or(true, |until| now < until), until),
+            None => (false, breaker.next_retry(policy)),
+        };
+
+        Some(edgelet_core::CircuitBreaker {
+            tripped,
+            restarts: u32::try_from(breaker.starts.len()).unwrap_or(u32::MAX),
+            next_retry: next_retry.filter(|next_retry| now < *next_retry),
+        })
+    }
+
+    fn save(&self, breakers: &std::collections::HashMap<String, Breaker>) {
+        if let Err(err) = write(&self.path, breakers) {
+            log::warn!("Failed to save circuit breakers: {}", err);
+        }
+    }
+}
+
+/// Breakers are replaced by a rename, so that a crash while they're written leaves the
+/// previous ones.
+fn write(
+    path: &std::path::Path,
+    breakers: &std::collections::HashMap<String, Breaker>,
+) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, serde_json::to_vec(breakers)?)?;
+    std::fs::rename(&temp, path)
+}
+
+impl Breaker {
+    fn prune(&mut self, policy: &RestartPolicy, now: chrono::DateTime<chrono::Utc>) {
+        let window = chrono::Duration::from_std(policy.window)
+            .unwrap_or_else(|_| chrono::Duration::max_value());
+
+        while matches!(self.starts.front(), Some(start) if now - *start >= window) {
+            self.starts.pop_front();
+        }
+    }
+
+    /// When the next start is allowed, given the backoff of the starts so far.
+    fn next_retry(&self, policy: &RestartPolicy) -> Option<chrono::DateTime<chrono::Utc>> {
+        let last = self.starts.back()?;
+        let backoff = policy.backoff(u32::try_from(self.starts.len()).unwrap_or(u32::MAX));
+
+        chrono::Duration::from_std(backoff)
+            .ok()
+            .and_then(|backoff| last.checked_add_signed(backoff))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_settings::watchdog::{GiveUpAction, RestartPolicy};
+
+    use super::CircuitBreakers;
+
+    fn state_dir(test: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("circuit-breakers-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        dir
+    }
+
+    fn breakers(give_up: GiveUpAction, state_dir: &std::path::Path) -> CircuitBreakers {
+        let policy = RestartPolicy {
+            max_restarts: 3,
+            window: std::time::Duration::from_secs(60),
+            initial_backoff: std::time::Duration::from_secs(10),
+            max_backoff: std::time::Duration::from_secs(20),
+            give_up,
+            cooldown: std::time::Duration::from_secs(600),
+        };
+
+        CircuitBreakers::new(
+            &[("sensor".to_string(), policy)].into_iter().collect(),
+            state_dir,
+        )
+    }
+
+    #[test]
+    fn backoff_and_trip() {
+        let state_dir = state_dir("backoff-and-trip");
+        let breakers = breakers(GiveUpAction::Stop, &state_dir);
+        let t0 = chrono::Utc::now();
+        let at = |secs| t0 + chrono::Duration::seconds(secs);
+
+        // Modules without a policy aren't limited.
+        for _ in 0..10 {
+            assert!(breakers.start_at("camera", t0).is_ok());
+        }
+        assert!(breakers.status("camera").is_none());
+
+        assert!(breakers.start_at("sensor", at(0)).is_ok());
+        assert!(breakers.start_at("sensor", at(5)).is_ok());
+
+        // The third start must wait 10 seconds after the second.
+        let refusal = breakers.start_at("sensor", at(10)).unwrap_err();
+        assert!(!refusal.tripped);
+        assert_eq!(
+            Some(at(15)),
+            breakers.status_at("sensor", at(10)).unwrap().next_retry
+        );
+        assert!(breakers.start_at("sensor", at(15)).is_ok());
+
+        // The fourth start within the window trips the breaker.
+        let refusal = breakers.start_at("sensor", at(40)).unwrap_err();
+        assert!(refusal.tripped);
+
+        let status = breakers.status_at("sensor", at(41)).unwrap();
+        assert!(status.tripped);
+        assert_eq!(Some(at(640)), status.next_retry);
+
+        let refusal = breakers.start_at("sensor", at(100)).unwrap_err();
+        assert!(!refusal.tripped);
+
+        // After the cooldown, the module gets a fresh window.
+        assert!(breakers.start_at("sensor", at(640)).is_ok());
+        let status = breakers.status_at("sensor", at(641)).unwrap();
+        assert!(!status.tripped);
+        assert_eq!(1, status.restarts);
+
+        // Starts are counted across restarts of the daemon.
+        let breakers = super::CircuitBreakers::new(&breakers.policies, &state_dir);
+        assert_eq!(1, breakers.status_at("sensor", at(641)).unwrap().restarts);
+
+        std::fs::remove_dir_all(state_dir).unwrap();
+    }
+
+    #[test]
+    fn window() {
+        let state_dir = state_dir("window");
+        let breakers = breakers(GiveUpAction::Stop, &state_dir);
+        let t0 = chrono::Utc::now();
+        let at = |secs| t0 + chrono::Duration::seconds(secs);
+
+        // Starts spread out beyond the window never trip the breaker.
+        for i in 0..10 {
+            assert!(breakers.start_at("sensor", at(i * 61)).is_ok());
+        }
+
+        std::fs::remove_dir_all(state_dir).unwrap();
+    }
+
+    #[test]
+    fn disable() {
+        let state_dir = state_dir("disable");
+        let breakers = breakers(GiveUpAction::Disable, &state_dir);
+        let t0 = chrono::Utc::now();
+        let at = |secs| t0 + chrono::Duration::seconds(secs);
+
+        assert!(breakers.start_at("sensor", at(0)).is_ok());
+        assert!(breakers.start_at("sensor", at(1)).is_ok());
+        assert!(breakers.start_at("sensor", at(20)).is_ok());
+        assert!(breakers.start_at("sensor", at(50)).unwrap_err().tripped);
+
+        // The breaker stays open, even long after the window.
+        assert!(breakers.start_at("sensor", at(100_000)).is_err());
+        let status = breakers.status_at("sensor", at(100_000)).unwrap();
+        assert!(status.tripped);
+        assert!(status.next_retry.is_none());
+
+        // It also stays open after the daemon restarts.
+        let breakers = super::CircuitBreakers::new(&breakers.policies, &state_dir);
+        assert!(breakers.start_at("sensor", at(100_000)).is_err());
+
+        // Deploying the module again closes it.
+        breakers.reset("sensor");
+        assert!(breakers.start_at("sensor", at(100_001)).is_ok());
+
+        std::fs::remove_dir_all(state_dir).unwrap();
+    }
+}
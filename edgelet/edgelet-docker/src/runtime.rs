@@ -12,19 +12,34 @@ use tokio::sync::Mutex;
 use url::Url;
 
 use docker::apis::{Configuration, DockerApi, DockerApiClient};
-use docker::models::{ContainerCreateBody, HostConfig, InlineResponse2001, Ipam, NetworkConfig};
+use docker::models::{
+    ContainerCreateBody, ContainerCreateBodyNetworkingConfig, EndpointSettings, HealthConfig,
+    HostConfig, InlineResponse2001, Ipam, NetworkConfig, NetworkConnectConfig,
+};
 use edgelet_core::{
     DiskInfo, LogOptions, Module, ModuleAction, ModuleRegistry, ModuleRuntime, ModuleRuntimeState,
-    RegistryOperation, RuntimeOperation, SystemInfo as CoreSystemInfo, SystemResources, UrlExt,
+    RegistryOperation, RuntimeOperation, SbomLookup, SystemInfo as CoreSystemInfo, SystemResources,
+    UrlExt,
 };
 use edgelet_settings::{
-    DockerConfig, Ipam as CoreIpam, MobyNetwork, ModuleSpec, RuntimeSettings, Settings,
+    ContainerEngine, DeviceMapping, DockerConfig, Egress, ImageDigestSettings, ImagePullSettings,
+    ImageSignatureSettings, Ipam as CoreIpam, MobyNetwork, ModuleDefaults, ModuleLimits,
+    ModuleSpec, NetworkPolicy, PreStopHook, Proxy, ResourceLimits, RuntimeSettings, Settings,
+    SignatureMode,
 };
 use edgelet_utils::ensure_not_empty;
 use http_common::Connector;
 
+use crate::credentials::Credentials;
+use crate::disk_space::DiskSpace;
 use crate::error::Error;
+use crate::in_flight::{InFlight, InFlightOperation};
 use crate::module::{runtime_state, DockerModule, MODULE_TYPE as DOCKER_MODULE_TYPE};
+use crate::pre_stop;
+use crate::pull::{Mirrors, PullOutcome};
+use crate::pull_scheduler::{Join, Leader, PullScheduler};
+use crate::restart_policy::CircuitBreakers;
+use crate::standby::StandbyOptions;
 use crate::{ImagePruneData, MakeModuleRuntime};
 
 type Deserializer = &'static mut serde_json::Deserializer<serde_json::de::IoRead<std::io::Empty>>;
@@ -34,14 +49,79 @@ const OWNER_LABEL_VALUE: &str = "Microsoft.Azure.Devices.Edge.Agent";
 const ORIGINAL_IMAGE_LABEL_KEY: &str = "net.azure-devices.edge.original-image";
 const LABELS: &[&str] = &["net.azure-devices.edge.owner=Microsoft.Azure.Devices.Edge.Agent"];
 
+// Standby containers are labeled with the module they stand in for. They are hidden
+// from the module list until they are renamed to replace a failed module.
+const STANDBY_LABEL_KEY: &str = "net.azure-devices.edge.standby-for";
+const STANDBY_SUFFIX: &str = "-standby";
+
+/// Longest line of the container engine's event stream that is buffered.
+const MAX_EVENT_LEN: usize = 64 * 1024;
+
+/// How long a check of a registry mirror may take before the mirror counts as down.
+const MIRROR_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// A change to a module's container, reported by `DockerModuleRuntime::container_events`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ContainerEvent {
+    Started(String),
+    Removed(String),
+}
+
+impl ContainerEvent {
+    fn parse(line: &[u8]) -> Option<Self> {
+        let event: serde_json::Value = serde_json::from_slice(line).ok()?;
+        let attributes = event.pointer("/Actor/Attributes")?;
+
+        // Standby containers aren't modules until they replace one.
+        if attributes.get(STANDBY_LABEL_KEY).is_some() {
+            return None;
+        }
+
+        let name = attributes.get("name")?.as_str()?.to_string();
+
+        match event.get("Action")?.as_str()? {
+            "start" => Some(ContainerEvent::Started(name)),
+            "destroy" => Some(ContainerEvent::Removed(name)),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct DockerModuleRuntime<C> {
     client: DockerApiClient<C>,
+    engine: ContainerEngine,
+    image_pull: ImagePullSettings,
+    image_import_dir: Option<std::path::PathBuf>,
+    image_digests: ImageDigestSettings,
+    image_signatures: ImageSignatureSettings,
+    proxy: Option<Proxy>,
+    module_defaults: ModuleDefaults,
+    module_limits: ModuleLimits,
+    pre_stop: BTreeMap<String, PreStopHook>,
+    module_dns: Vec<String>,
+    module_network: String,
+    network_policy: Option<NetworkPolicy>,
+    device_mapping: DeviceMapping,
+    module_health: edgelet_settings::watchdog::ModuleHealth,
+    circuit_breakers: CircuitBreakers,
+    create_errors: Arc<std::sync::Mutex<HashMap<String, String>>>,
+    pull_outcomes: Arc<std::sync::Mutex<HashMap<String, PullOutcome>>>,
+    pulls: PullScheduler,
+    mirrors: Mirrors,
+    disk_space: DiskSpace,
+    in_flight: InFlight,
+    credentials: Credentials,
+    time_dir: Option<std::path::PathBuf>,
     system_resources: Arc<Mutex<System>>,
     create_socket_channel: UnboundedSender<ModuleAction>,
     allow_elevated_docker_permissions: bool,
     additional_info: BTreeMap<String, String>,
     image_use_data: ImagePruneData,
+    warm_standby: Arc<std::collections::BTreeSet<String>>,
+    standby_options: StandbyOptions,
+    stop_requested: Arc<std::sync::Mutex<std::collections::HashSet<String>>>,
+    template_variables: Arc<std::sync::RwLock<BTreeMap<String, String>>>,
 }
 
 fn merge_env(cur_env: Option<&[String]>, new_env: &BTreeMap<String, String>) -> Vec<String> {
@@ -82,28 +162,23 @@ where
     type Config = DockerConfig;
 
     async fn pull(&self, config: &Self::Config) -> anyhow::Result<()> {
-        let image = config.image().to_owned();
-        let is_content_trust_enabled = false;
+        self.schedule_pull(None, config).await
+    }
 
-        if is_content_trust_enabled {
-            log::info!("Pulling image via digest {}...", image);
-        } else {
-            log::info!("Pulling image via tag {}...", image);
-        }
+    async fn pull_module(&self, module: &str, config: &Self::Config) -> anyhow::Result<()> {
+        self.schedule_pull(Some(module), config).await
+    }
 
-        let creds = match config.auth() {
-            Some(a) => {
-                let json = serde_json::to_string(&a).with_context(|| {
-                    Error::RegistryOperation(RegistryOperation::PullImage(image.clone()))
-                })?;
-                let engine = base64::engine::general_purpose::URL_SAFE;
-                base64::Engine::encode(&engine, json)
-            }
-            None => String::new(),
-        };
+    async fn remove(&self, name: &str) -> anyhow::Result<()> {
+        log::info!("Removing image {}...", name);
+        let _in_flight = self.in_flight.begin("removeImage", name);
+
+        ensure_not_empty(name).with_context(|| {
+            Error::RegistryOperation(RegistryOperation::RemoveImage(name.to_string()))
+        })?;
 
         self.client
-            .image_create(&image, "", "", "", "", &creds, "")
+            .image_delete(name, true, false)
             .await
             .context(Error::Docker)
             .map_err(|e| {
@@ -111,9 +186,436 @@ where
                 e
             })
             .with_context(|| {
-                Error::RegistryOperation(RegistryOperation::PullImage(image.clone()))
+                Error::RegistryOperation(RegistryOperation::RemoveImage(name.to_string()))
+            })?;
+
+        log::info!("Successfully removed image {}", name);
+        Ok(())
+    }
+
+    fn pulls(&self) -> Vec<edgelet_core::ImagePull> {
+        self.pulls.list()
+    }
+}
+
+#[async_trait::async_trait]
+impl MakeModuleRuntime for DockerModuleRuntime<Connector> {
+    type Config = DockerConfig;
+    type Settings = Settings;
+    type ModuleRuntime = Self;
+
+    async fn make_runtime(
+        settings: &Settings,
+        create_socket_channel: UnboundedSender<ModuleAction>,
+        image_use_data: ImagePruneData,
+    ) -> anyhow::Result<Self::ModuleRuntime> {
+        log::info!("Initializing module runtime...");
+
+        let client = init_client(settings.moby_runtime().uri())?;
+        create_network_if_missing(settings, &client).await?;
+        if let Some(policy) = settings.network_policy() {
+            create_local_network_if_missing(policy, &client).await?;
+        }
+
+        let runtime = Self::with_client(client, settings, create_socket_channel, image_use_data);
+        log::info!("Successfully initialized module runtime");
+
+        Ok(runtime)
+    }
+}
+
+impl<C> DockerModuleRuntime<C>
+where
+    C: Clone + hyper::client::connect::Connect + Send + Sync + 'static,
+{
+    /// A runtime for the container engine that `client` connects to, whose networks
+    /// already exist.
+    fn with_client(
+        client: DockerApiClient<C>,
+        settings: &Settings,
+        create_socket_channel: UnboundedSender<ModuleAction>,
+        image_use_data: ImagePruneData,
+    ) -> Self {
+        // to avoid excessive FD usage, we will not allow sysinfo to keep files open.
+        sysinfo::set_open_files_limit(0);
+        let system_resources = System::new_all();
+
+        Self {
+            client,
+            engine: settings.moby_runtime().engine(),
+            image_pull: settings.moby_runtime().image_pull().clone(),
+            image_import_dir: settings
+                .moby_runtime()
+                .image_import_dir()
+                .map(std::path::Path::to_path_buf),
+            image_digests: settings.moby_runtime().image_digests().clone(),
+            image_signatures: settings.moby_runtime().image_signatures().clone(),
+            proxy: settings.proxy().cloned(),
+            module_defaults: settings.moby_runtime().module_defaults().clone(),
+            module_limits: settings.moby_runtime().module_limits().clone(),
+            pre_stop: settings.moby_runtime().pre_stop().clone(),
+            module_dns: settings.moby_runtime().network().dns().to_vec(),
+            module_network: settings.moby_runtime().network().name().to_string(),
+            network_policy: settings.network_policy().cloned(),
+            device_mapping: settings.device_mapping().clone(),
+            module_health: settings.watchdog().module_health().clone(),
+            circuit_breakers: CircuitBreakers::new(
+                settings.watchdog().restart_policies(),
+                &settings.storage().state_dir(settings.homedir()),
+            ),
+            create_errors: Arc::default(),
+            pull_outcomes: Arc::default(),
+            pulls: PullScheduler::new(settings.moby_runtime().image_pull().max_concurrent_pulls()),
+            mirrors: Mirrors::new(
+                settings.moby_runtime().image_pull().mirrors(),
+                settings.moby_runtime().image_pull().mirror_cooldown(),
+            ),
+            disk_space: DiskSpace::new(
+                settings.watchdog().disk_space().clone(),
+                vec![
+                    settings.homedir().to_path_buf(),
+                    settings.storage().cache_dir(settings.homedir()),
+                    settings.storage().gc_dir(settings.homedir()),
+                    settings.storage().state_dir(settings.homedir()),
+                    settings.storage().logs_dir(settings.homedir()),
+                ],
+            ),
+            in_flight: InFlight::default(),
+            credentials: Credentials::new(settings.moby_runtime().image_pull().credentials()),
+            time_dir: settings
+                .inject_host_time()
+                .then(|| edgelet_core::host_time_dir(settings.homedir())),
+            system_resources: Arc::new(Mutex::new(system_resources)),
+            create_socket_channel,
+            allow_elevated_docker_permissions: settings.allow_elevated_docker_permissions(),
+            additional_info: settings.additional_info().clone(),
+            image_use_data,
+            warm_standby: Arc::new(settings.warm_standby_modules().iter().cloned().collect()),
+            standby_options: StandbyOptions::new(settings.homedir()),
+            stop_requested: Arc::default(),
+            template_variables: Arc::new(std::sync::RwLock::new(template_variables(settings))),
+        }
+    }
+
+    /// Set the template variables that describe the device's identity, which are only
+    /// known once it is provisioned. They take precedence over variables from settings.
+    pub fn set_device_variables(&self, device_id: &str, hub_name: &str, gateway_host: &str) {
+        let mut variables = self
+            .template_variables
+            .write()
+            .expect("template variables lock poisoned");
+
+        variables.insert("device_id".to_string(), device_id.to_string());
+        variables.insert("hub_name".to_string(), hub_name.to_string());
+        variables.insert("gateway_host".to_string(), gateway_host.to_string());
+    }
+
+    /// Check that the registry mirrors are up.
+    pub async fn check_mirrors(&self) {
+        for mirror in self.mirrors.list() {
+            let result = tokio::time::timeout(
+                MIRROR_CHECK_TIMEOUT,
+                crate::registry::ping(&mirror.mirror, self.proxy.as_ref()),
+            )
+            .await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("check timed out")));
+
+            if let Err(err) = &result {
+                log::debug!("Check of registry mirror {} failed: {}", mirror.mirror, err);
+            }
+
+            self.mirrors
+                .record_check(&mirror.registry, &mirror.mirror, &result);
+        }
+    }
+
+    /// Check the free space on the daemon's directories and the container engine's data
+    /// root.
+    pub async fn check_disk_space(&self) {
+        let data_root = match self.client.system_info().await {
+            Ok(info) => info.docker_root_dir().map(std::path::PathBuf::from),
+            Err(err) => {
+                log::warn!("Failed to query container engine data root: {}", err);
+
+                None
+            }
+        };
+
+        self.disk_space.check(data_root.as_deref());
+    }
+
+    /// Container engine operations that haven't completed, in the order they started.
+    pub fn in_flight(&self) -> Vec<InFlightOperation> {
+        self.in_flight.list()
+    }
+
+    /// Notify a running module through its pre-stop hook that it's about to be stopped,
+    /// restarted or removed, and wait up to its grace period for it to acknowledge. The
+    /// module is stopped regardless, so failures are only logged.
+    async fn pre_stop(&self, id: &str, action: &str) {
+        let Some(hook) = self.pre_stop.get(id) else {
+            return;
+        };
+
+        let container = match self.client.container_inspect(id, false).await {
+            Ok(container) => container,
+            Err(err) => {
+                log::warn!("Could not run pre-stop hook of module {}: {}", id, err);
+                return;
+            }
+        };
+        if !is_running(&container) {
+            return;
+        }
+
+        log::info!(
+            module = id,
+            event = "module.prestop";
+            "Notifying module {} before {}, waiting up to {:?}...",
+            id,
+            action,
+            hook.grace_period()
+        );
+        let _in_flight = self.in_flight.begin("pre-stop", id);
+
+        match tokio::time::timeout(
+            hook.grace_period(),
+            self.notify_pre_stop(id, action, hook, &container),
+        )
+        .await
+        {
+            Ok(Ok(())) => log::info!(
+                module = id,
+                event = "module.prestop";
+                "Module {} acknowledged its pre-stop hook",
+                id
+            ),
+            Ok(Err(err)) => log::warn!(
+                module = id,
+                event = "module.prestop";
+                "Pre-stop hook of module {} failed: {:#}",
+                id,
+                err
+            ),
+            Err(_) => log::warn!(
+                module = id,
+                event = "module.prestop";
+                "Module {} did not acknowledge its pre-stop hook within {:?}",
+                id,
+                hook.grace_period()
+            ),
+        }
+    }
+
+    async fn notify_pre_stop(
+        &self,
+        id: &str,
+        action: &str,
+        hook: &PreStopHook,
+        container: &docker::models::InlineResponse200,
+    ) -> anyhow::Result<()> {
+        if let Some(url) = hook.http() {
+            let uri = pre_stop::endpoint(url, id, &self.module_network, container)?;
+            pre_stop::post(&uri, id, action).await?;
+        }
+
+        if let Some(signal) = hook.signal() {
+            self.client
+                .container_kill(id, signal)
+                .await
+                .context(Error::Docker)?;
+
+            // The module acknowledges by exiting.
+            loop {
+                tokio::time::sleep(pre_stop::EXIT_POLL_INTERVAL).await;
+
+                let container = self
+                    .client
+                    .container_inspect(id, false)
+                    .await
+                    .context(Error::Docker)?;
+                if !is_running(&container) {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replace a module with its standby container if the module has failed.
+    ///
+    /// Returns `true` if the standby was started. Modules that were stopped through
+    /// the runtime are left alone, since they didn't fail.
+    pub async fn fail_over_if_failed(&self, id: &str) -> anyhow::Result<bool> {
+        if self
+            .stop_requested
+            .lock()
+            .expect("stop requested lock poisoned")
+            .contains(id)
+        {
+            return Ok(false);
+        }
+
+        let (_, state) = self.get(id).await?;
+        if !matches!(
+            state.status(),
+            edgelet_core::ModuleStatus::Failed | edgelet_core::ModuleStatus::Dead
+        ) {
+            return Ok(false);
+        }
+
+        let standby = format!("{id}{STANDBY_SUFFIX}");
+        if self
+            .client
+            .container_inspect(&standby, false)
+            .await
+            .is_err()
+        {
+            return Ok(false);
+        }
+
+        log::warn!(
+            module = id,
+            event = "module.failover";
+            "Module {} has failed; replacing it with its standby",
+            id
+        );
+
+        self.client
+            .container_delete(id, false, true, false)
+            .await
+            .context(Error::Docker)
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::RemoveModule(id.to_owned()))
+            })?;
+
+        self.client
+            .container_rename(&standby, id)
+            .await
+            .context(Error::Docker)
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::StartModule(id.to_owned()))
             })?;
 
+        self.start(id).await?;
+
+        log::info!(module = id, event = "module.failover"; "Started standby for module {}", id);
+
+        // Create a new standby so that the module is covered if it fails again.
+        if let Some(standby_options) = self.standby_options.get(id) {
+            if let Err(err) = self.create_standby(id, standby_options).await {
+                log::warn!(
+                    "Failed to create standby container for module {}: {:?}",
+                    id,
+                    err
+                );
+            }
+        } else {
+            log::info!(
+                "A new standby for module {} will be created when the module is next created",
+                id
+            );
+        }
+
+        Ok(true)
+    }
+
+    /// Podman reports stopping a container that isn't running as a server error rather
+    /// than Docker's 304, so map it to the latter.
+    fn normalize_stop_error(&self, err: anyhow::Error) -> anyhow::Error {
+        if self.engine != ContainerEngine::Podman {
+            return err;
+        }
+
+        match err.downcast::<docker::apis::ApiError>() {
+            Ok(api_error)
+                if api_error.code == hyper::StatusCode::INTERNAL_SERVER_ERROR
+                    && api_error.message.contains("state improper") =>
+            {
+                anyhow::anyhow!(docker::apis::ApiError {
+                    code: hyper::StatusCode::NOT_MODIFIED,
+                    ..api_error
+                })
+            }
+            Ok(api_error) => anyhow::anyhow!(api_error),
+            Err(err) => err,
+        }
+    }
+
+    /// Pull an image, or wait for the pull of it that is already running.
+    async fn schedule_pull(
+        &self,
+        module: Option<&str>,
+        config: &DockerConfig,
+    ) -> anyhow::Result<()> {
+        let mut leader = match self.pulls.join(config.image(), module) {
+            Join::Leader(leader) => leader,
+            Join::Follower(follower) => {
+                log::info!(
+                    "Image {} is already being pulled; waiting for that pull",
+                    config.image()
+                );
+
+                return follower.wait().await;
+            }
+        };
+
+        let result = self.pull_image(module, config, &leader).await;
+        leader.finish(&result);
+
+        result
+    }
+
+    /// Pull the image of a module once the scheduler has a slot for it.
+    async fn pull_image(
+        &self,
+        module: Option<&str>,
+        config: &DockerConfig,
+        leader: &Leader,
+    ) -> anyhow::Result<()> {
+        let image = config.image().to_owned();
+
+        self.disk_space.ensure().with_context(|| {
+            Error::RegistryOperation(RegistryOperation::PullImage(image.clone()))
+        })?;
+
+        let _slot = leader.slot().await;
+        let _in_flight = self.in_flight.begin("pull", &image);
+
+        let is_content_trust_enabled = false;
+
+        if is_content_trust_enabled {
+            log::info!("Pulling image via digest {}...", image);
+        } else {
+            log::info!("Pulling image via tag {}...", image);
+        }
+
+        let (registry, _) = crate::pull::split_registry(&image);
+        let candidates = self
+            .credentials
+            .candidates(registry, module, config.auth())
+            .await;
+
+        let (outcome, result) = match self
+            .pull_with_retries(&image, &candidates, &|message| leader.progress(message))
+            .await
+        {
+            (outcome, Err(err)) if self.image_import_dir.is_some() => {
+                self.import_image(&image, outcome, err).await
+            }
+            pulled => pulled,
+        };
+
+        self.pull_outcomes
+            .lock()
+            .expect("pull outcomes lock poisoned")
+            .insert(image.clone(), outcome);
+
+        result.context(Error::Docker).with_context(|| {
+            Error::RegistryOperation(RegistryOperation::PullImage(image.clone()))
+        })?;
+
         log::info!("Successfully pulled image {}", image);
 
         // Now, get the image_id of the image we just pulled for image garbage collection in future
@@ -133,88 +635,1427 @@ where
         Ok(())
     }
 
-    async fn remove(&self, name: &str) -> anyhow::Result<()> {
-        log::info!("Removing image {}...", name);
+    /// Pull an image from the mirrors of its registry, then from its registry with the
+    /// configured retries, then from each fallback registry in turn.
+    ///
+    /// The image's own registry is tried with each credential in turn while it denies
+    /// them.
+    async fn pull_with_retries(
+        &self,
+        image: &str,
+        candidates: &[crate::credentials::Candidate],
+        on_progress: docker::apis::ProgressHandler<'_>,
+    ) -> (PullOutcome, anyhow::Result<()>) {
+        let mut attempts = 0;
+        let mut last_error = anyhow::anyhow!("no pull was attempted");
+
+        // Images referenced by digest can't be tagged with their original name, so they
+        // are only pulled from their own registry.
+        let (registry, _) = crate::pull::split_registry(image);
+        let (mirrors, fallbacks) = if crate::pull::split_tag(image).is_some() {
+            (
+                self.mirrors.order(registry),
+                self.image_pull.fallback_registries(),
+            )
+        } else {
+            (Vec::new(), &[][..])
+        };
 
-        ensure_not_empty(name).with_context(|| {
-            Error::RegistryOperation(RegistryOperation::RemoveImage(name.to_string()))
-        })?;
+        // Credentials are only sent to the image's own registry, since they aren't meant
+        // for mirrors and fallbacks. Each mirror gets one attempt, since the next mirror
+        // is the retry.
+        let mirrors = mirrors.into_iter().map(|mirror| {
+            let source = crate::pull::with_registry(image, &mirror);
 
-        self.client
-            .image_delete(name, true, false)
-            .await
-            .context(Error::Docker)
-            .map_err(|e| {
-                log::warn!("{:?}", e);
-                e
-            })
-            .with_context(|| {
-                Error::RegistryOperation(RegistryOperation::RemoveImage(name.to_string()))
-            })?;
+            (source, None, Some(mirror))
+        });
+        let own: Vec<_> = if candidates.is_empty() {
+            vec![(image.to_string(), None, None)]
+        } else {
+            candidates
+                .iter()
+                .map(|candidate| (image.to_string(), Some(candidate), None))
+                .collect()
+        };
+        let fallbacks = fallbacks
+            .iter()
+            .map(|registry| (crate::pull::with_registry(image, registry), None, None));
 
-        log::info!("Successfully removed image {}", name);
-        Ok(())
+        // The next credential is only tried if the registry denied the previous one.
+        let mut try_credential = true;
+
+        for (source, candidate, mirror) in mirrors.chain(own).chain(fallbacks) {
+            if candidate.is_some() && !try_credential {
+                continue;
+            }
+
+            let creds = match candidate.map(crate::credentials::Candidate::encode) {
+                Some(Ok(creds)) => creds,
+                Some(Err(err)) => {
+                    last_error = err;
+                    continue;
+                }
+                None => String::new(),
+            };
+
+            let max_attempts = if mirror.is_some() {
+                1
+            } else {
+                self.image_pull.max_attempts()
+            };
+
+            for attempt in 1..=max_attempts {
+                if attempt > 1 {
+                    tokio::time::sleep(self.image_pull.backoff(attempt - 1)).await;
+                }
+
+                attempts += 1;
+
+                let result = match tokio::time::timeout(
+                    self.image_pull.attempt_timeout(),
+                    self.client.image_pull(&source, &creds, on_progress),
+                )
+                .await
+                {
+                    Ok(result) => result,
+                    Err(_) => Err(anyhow::anyhow!(
+                        "pull timed out after {:?}",
+                        self.image_pull.attempt_timeout()
+                    )),
+                };
+
+                let result = match result {
+                    Ok(()) if source != image => self.tag_fallback(&source, image).await,
+                    result => result,
+                };
+
+                if let Some(mirror) = &mirror {
+                    self.mirrors.record(registry, mirror, &result);
+                }
+
+                match result {
+                    Ok(()) => {
+                        if let Some(candidate) = candidate {
+                            self.credentials.accepted(registry, candidate);
+                        }
+
+                        let outcome = PullOutcome {
+                            source,
+                            attempts,
+                            error: None,
+                            imported: false,
+                            mirrored: mirror.is_some(),
+                        };
+
+                        return (outcome, Ok(()));
+                    }
+                    Err(err) => {
+                        log::warn!(
+                            "Attempt {} to pull image {} failed: {:?}",
+                            attempt,
+                            source,
+                            err
+                        );
+
+                        // Retrying with credentials that were denied won't help.
+                        let denied = candidate.is_some() && crate::credentials::is_denied(&err);
+                        if candidate.is_some() {
+                            try_credential = denied;
+                        }
+
+                        let is_retryable = crate::pull::is_retryable(&err) && !denied;
+                        last_error = err;
+
+                        if !is_retryable {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        let outcome = PullOutcome {
+            source: image.to_string(),
+            attempts,
+            error: Some(last_error.to_string()),
+            imported: false,
+            mirrored: false,
+        };
+
+        (outcome, Err(last_error))
     }
-}
 
-#[async_trait::async_trait]
-impl MakeModuleRuntime for DockerModuleRuntime<Connector> {
-    type Config = DockerConfig;
-    type Settings = Settings;
-    type ModuleRuntime = Self;
+    /// Fall back to the image on the device, or to loading it from its tarball in the
+    /// import directory, after the image couldn't be pulled.
+    ///
+    /// Returns the pull error if neither is possible.
+    async fn import_image(
+        &self,
+        image: &str,
+        outcome: PullOutcome,
+        pull_error: anyhow::Error,
+    ) -> (PullOutcome, anyhow::Result<()>) {
+        let dir = self
+            .image_import_dir
+            .clone()
+            .expect("images are only imported with an import directory");
+
+        if let Ok(true) = self.image_exists(image).await {
+            log::warn!(
+                "Failed to pull image {}; using the image already on the device",
+                image
+            );
+
+            let outcome = PullOutcome {
+                error: None,
+                imported: true,
+                ..outcome
+            };
+
+            return (outcome, Ok(()));
+        }
+
+        let tarball = {
+            let image = image.to_string();
+            let dir = dir.clone();
+
+            tokio::task::spawn_blocking(move || crate::import::find_tarball(&dir, &image)).await
+        };
+
+        let tarball = match tarball
+            .map_err(std::io::Error::from)
+            .and_then(|found| found)
+        {
+            Ok(Some(tarball)) => tarball,
+            Ok(None) => {
+                log::warn!("No tarball of image {} in {}", image, dir.display());
+
+                return (outcome, Err(pull_error));
+            }
+            Err(err) => {
+                log::warn!(
+                    "Failed to search {} for image tarballs: {}",
+                    dir.display(),
+                    err
+                );
+
+                return (outcome, Err(pull_error));
+            }
+        };
+
+        log::info!("Loading image {} from {}...", image, tarball.display());
+
+        if let Err(err) = self.load_tarball(&tarball).await {
+            log::warn!(
+                "Failed to load image tarball {}: {:?}",
+                tarball.display(),
+                err
+            );
+
+            return (outcome, Err(pull_error));
+        }
+
+        // Tarballs matched by name may hold some other image.
+        if let Ok(false) = self.image_exists(image).await {
+            log::warn!(
+                "Image tarball {} does not contain image {}",
+                tarball.display(),
+                image
+            );
+
+            return (outcome, Err(pull_error));
+        }
+
+        log::info!("Loaded image {} from {}", image, tarball.display());
+
+        let outcome = PullOutcome {
+            source: tarball.display().to_string(),
+            error: None,
+            imported: true,
+            ..outcome
+        };
+
+        (outcome, Ok(()))
+    }
+
+    /// Apply the device's policies and defaults to the create options of a module, and check
+    /// that the result can be created.
+    fn prepare(&self, module: &mut ModuleSpec<DockerConfig>) -> anyhow::Result<()> {
+        // we only want "docker" modules
+        if module.r#type() != DOCKER_MODULE_TYPE {
+            return Err(Error::InvalidModuleType(module.r#type().to_string()).into());
+        }
+
+        unset_privileged(
+            self.allow_elevated_docker_permissions,
+            module.config_mut().create_options_mut(),
+        );
+        drop_unsafe_privileges(
+            self.allow_elevated_docker_permissions,
+            module.config_mut().create_options_mut(),
+        );
+
+        if let Some(time_dir) = &self.time_dir {
+            inject_host_time(time_dir, module);
+        }
+
+        apply_module_defaults(
+            &self.module_defaults,
+            module.config_mut().create_options_mut(),
+        );
+        apply_module_dns(&self.module_dns, module.config_mut().create_options_mut());
+        if self.circuit_breakers.has_policy(module.name()) {
+            let name = module.name().to_string();
+            disable_engine_restarts(&name, module.config_mut().create_options_mut())?;
+        }
+        apply_health_probe(
+            self.module_health.exec_probe(module.name()),
+            module.config_mut().create_options_mut(),
+        );
+        apply_module_limits(
+            &self.module_limits.for_module(module.name()),
+            self.module_limits.enforce(),
+            module.config_mut().create_options_mut(),
+        )
+        .map_err(|reason| Error::ModuleLimits {
+            module: module.name().to_string(),
+            reason,
+        })?;
+        apply_device_mapping(
+            &self.device_mapping,
+            module.config_mut().create_options_mut(),
+        )
+        .map_err(|reason| Error::DeviceNotAllowed {
+            module: module.name().to_string(),
+            reason,
+        })?;
+        if let Some(policy) = &self.network_policy {
+            apply_network_policy(
+                policy,
+                &self.module_network,
+                module.name(),
+                module.config_mut().create_options_mut(),
+            )
+            .map_err(|reason| Error::NetworkPolicy {
+                module: module.name().to_string(),
+                reason,
+            })?;
+        }
+
+        let template_variables = self
+            .template_variables
+            .read()
+            .expect("template variables lock poisoned")
+            .clone();
+        crate::template::apply(module, &template_variables).with_context(|| {
+            Error::RuntimeOperation(RuntimeOperation::CreateModule(module.name().to_string()))
+        })?;
+
+        let errors = crate::validate::create_options(module.config().create_options());
+        if !errors.is_empty() {
+            return Err(Error::InvalidCreateOptions {
+                module: module.name().to_string(),
+                errors,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Count a start of a module against its restart policy. The module is stopped if
+    /// this trips its circuit breaker.
+    async fn check_restart_policy(&self, id: &str) -> anyhow::Result<()> {
+        if let Err(refusal) = self.circuit_breakers.start(id) {
+            if refusal.tripped {
+                if let Err(err) = self.stop(id, None).await {
+                    log::warn!(
+                        "Failed to stop module {} after its circuit breaker tripped: {:?}",
+                        id,
+                        err
+                    );
+                }
+            }
+
+            return Err(refusal.error.into());
+        }
+
+        Ok(())
+    }
+
+    /// Record why a module couldn't be created, or clear the record if it could, so that
+    /// the status of the module it would replace says so.
+    fn record_create_error(&self, module: &str, result: &anyhow::Result<()>) {
+        let mut create_errors = self
+            .create_errors
+            .lock()
+            .expect("create errors lock poisoned");
+
+        match result {
+            Ok(()) => {
+                create_errors.remove(module);
+            }
+            Err(err) => {
+                log::warn!("{:#}", err);
+                create_errors.insert(module.to_string(), format!("{err:#}"));
+            }
+        }
+    }
+
+    /// Make sure that an image is the one it's pinned to, if it's pinned.
+    async fn verify_image_digest(
+        &self,
+        image: &str,
+        labels: Option<&BTreeMap<String, String>>,
+    ) -> anyhow::Result<()> {
+        let digest =
+            match crate::digest::pinned_digest(image, labels, self.image_digests.allowlist()) {
+                Some(digest) => digest,
+                None if self.image_digests.require_pinned() => {
+                    return Err(Error::ImageNotPinned(image.to_string()).into());
+                }
+                None => return Ok(()),
+            };
+
+        let filters = serde_json::json!({ "reference": [image] }).to_string();
+
+        let images = self
+            .client
+            .images_list(false, &filters, true)
+            .await
+            .context(Error::Docker)?;
+
+        if images
+            .iter()
+            .any(|summary| crate::digest::has_digest(summary.repo_digests(), digest))
+        {
+            log::info!("Verified image {} against digest {}", image, digest);
+
+            Ok(())
+        } else {
+            Err(Error::ImageDigestMismatch {
+                image: image.to_string(),
+                digest: digest.to_string(),
+            }
+            .into())
+        }
+    }
+
+    /// Make sure that an image is signed as the signature policy of its registry
+    /// requires.
+    async fn verify_image_signature(
+        &self,
+        image: &str,
+        auth: Option<&docker::models::AuthConfig>,
+    ) -> anyhow::Result<()> {
+        let (registry, _) = crate::pull::split_registry(image);
+
+        let policy = match self.image_signatures.policy(registry) {
+            Some(policy) if policy.mode() != SignatureMode::Off => policy,
+            _ => return Ok(()),
+        };
+
+        let filters = serde_json::json!({ "reference": [image] }).to_string();
+        let images = self
+            .client
+            .images_list(false, &filters, false)
+            .await
+            .context(Error::Docker)?;
+
+        let mut sources: Vec<_> = images
+            .iter()
+            .flat_map(|summary| crate::signature::manifest_digests(image, summary.repo_digests()))
+            .collect();
+
+        // An image that was pulled from a mirror or a fallback registry is verified with
+        // the signatures there, and then with those in its own registry, since not every
+        // mirror copies signatures.
+        if let Some(&(_, digest)) = sources.first() {
+            if sources.iter().all(|(source, _)| *source != registry) {
+                sources.push((registry, digest));
+            }
+        }
+
+        let credentials = auth.and_then(|auth| auth.username().zip(auth.password()));
+        let proxy = self.proxy.as_ref();
+
+        let mut result = Err(anyhow::anyhow!("image has no digest from a registry"));
+        for (source, digest) in sources {
+            // Credentials are only sent to the image's own registry, as when pulling.
+            result = if source == registry {
+                crate::signature::verify(image, digest, policy, credentials, proxy).await
+            } else {
+                let reference = crate::pull::with_registry(image, source);
+                crate::signature::verify(&reference, digest, policy, None, proxy).await
+            };
+
+            match &result {
+                Ok(()) => break,
+                Err(err) => log::debug!(
+                    "Signature of image {} could not be verified in {}: {}",
+                    image,
+                    source,
+                    err
+                ),
+            }
+        }
+
+        match result {
+            Ok(()) => {
+                log::info!("Verified signature of image {}", image);
+
+                Ok(())
+            }
+            Err(err) if policy.mode() == SignatureMode::Warn => {
+                log::warn!(
+                    "Signature of image {} could not be verified: {}",
+                    image,
+                    err
+                );
+
+                Ok(())
+            }
+            Err(err) => Err(Error::ImageSignature {
+                image: image.to_string(),
+                reason: err.to_string(),
+            }
+            .into()),
+        }
+    }
+
+    async fn image_exists(&self, image: &str) -> anyhow::Result<bool> {
+        let filters = serde_json::json!({ "reference": [image] }).to_string();
+
+        let images = self.client.images_list(false, &filters, false).await?;
+
+        Ok(!images.is_empty())
+    }
+
+    /// The status of a module's Docker healthcheck and the output of its last run, or
+    /// `None` if the module has no healthcheck.
+    pub async fn health(&self, name: &str) -> anyhow::Result<Option<(String, Option<String>)>> {
+        let response = self
+            .client
+            .container_inspect(name, false)
+            .await
+            .context(Error::Docker)?;
+
+        let health = response
+            .state()
+            .and_then(docker::models::InlineResponse200State::health);
+
+        Ok(health.and_then(|health| {
+            let status = health.status()?.to_string();
+            let output = health
+                .log()
+                .and_then(<[_]>::last)
+                .and_then(|result| result.output())
+                .map(|output| output.trim().to_string());
+
+            Some((status, output))
+        }))
+    }
+
+    /// The address of a module's container on the module network, or on its only other
+    /// network. `None` for modules without an address of their own, such as those on the
+    /// host network.
+    pub async fn module_address(&self, name: &str) -> anyhow::Result<Option<std::net::IpAddr>> {
+        let response = self
+            .client
+            .container_inspect(name, false)
+            .await
+            .context(Error::Docker)?;
+
+        let Some(networks) = response
+            .network_settings()
+            .and_then(docker::models::NetworkSettings::networks)
+        else {
+            return Ok(None);
+        };
+
+        let endpoint = networks.get(&self.module_network).or_else(|| {
+            if networks.len() == 1 {
+                networks.values().next()
+            } else {
+                None
+            }
+        });
+
+        Ok(endpoint
+            .and_then(docker::models::EndpointSettings::ip_address)
+            .and_then(|address| address.parse().ok()))
+    }
+
+    /// Starts and removals of module containers as the container engine reports them,
+    /// so that callers can react to them without polling. The stream ends when the engine
+    /// closes the connection.
+    pub async fn container_events(
+        &self,
+    ) -> anyhow::Result<impl futures::Stream<Item = anyhow::Result<ContainerEvent>> + Send> {
+        let filters = serde_json::json!({
+            "type": ["container"],
+            "event": ["start", "destroy"],
+            "label": LABELS,
+        })
+        .to_string();
+
+        let body = self
+            .client
+            .system_events(&filters)
+            .await
+            .context(Error::Docker)?;
+
+        // Events are JSON objects that each end with a newline, and can span the chunks of
+        // the response body.
+        let events = futures::stream::unfold((body, Vec::new()), |(mut body, mut buf)| async {
+            loop {
+                if let Some(end) = buf.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = buf.drain(..=end).collect();
+
+                    if let Some(event) = ContainerEvent::parse(&line) {
+                        return Some((Ok(event), (body, buf)));
+                    }
+
+                    continue;
+                }
+
+                // An event is a few hundred bytes, so a longer line isn't one.
+                if buf.len() > MAX_EVENT_LEN {
+                    buf.clear();
+                }
+
+                match futures::TryStreamExt::try_next(&mut body).await {
+                    Ok(Some(chunk)) => buf.extend_from_slice(&chunk),
+                    Ok(None) => return None,
+                    Err(err) => return Some((Err(err.into()), (body, buf))),
+                }
+            }
+        });
+
+        Ok(events)
+    }
+
+    /// Find a local image to use when none of `images` can be pulled.
+    ///
+    /// The images themselves are preferred, in order. Otherwise, the newest local image
+    /// of the same repository as one of them is returned, again in order; the engine
+    /// lists images newest first.
+    pub async fn cached_image(&self, images: &[&str]) -> anyhow::Result<Option<String>> {
+        for image in images {
+            if self.image_exists(image).await? {
+                return Ok(Some((*image).to_string()));
+            }
+        }
+
+        for image in images {
+            let repo = match crate::pull::split_tag(image) {
+                Some((repo, _)) => repo,
+                None => image.split_once('@').map_or(*image, |(repo, _)| repo),
+            };
+
+            let filters = serde_json::json!({ "reference": [repo] }).to_string();
+            let local = self.client.images_list(false, &filters, false).await?;
+
+            let prefix = format!("{repo}:");
+            let cached = local
+                .iter()
+                .flat_map(|image| image.repo_tags())
+                .find(|tag| tag.starts_with(&prefix));
+
+            if let Some(cached) = cached {
+                return Ok(Some(cached.clone()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Stream a tarball to the engine, as `docker load` does.
+    async fn load_tarball(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        use tokio::io::AsyncReadExt;
+
+        let mut file = tokio::fs::File::open(path).await?;
+        let (mut sender, body) = hyper::Body::channel();
+
+        let send = async move {
+            let mut buf = vec![0; 64 * 1024];
+
+            loop {
+                let read = file.read(&mut buf).await?;
+                if read == 0 {
+                    break;
+                }
+
+                sender
+                    .send_data(hyper::body::Bytes::copy_from_slice(&buf[..read]))
+                    .await?;
+            }
+
+            Ok::<_, anyhow::Error>(())
+        };
+
+        let (load, send) = tokio::join!(self.client.image_load(body), send);
+
+        // The engine's error explains why it stopped reading the tarball, if it did.
+        load?;
+        send
+    }
+
+    /// Tag an image pulled from a mirror or fallback registry with its original name, so
+    /// that modules are created from it as usual.
+    async fn tag_fallback(&self, source: &str, image: &str) -> anyhow::Result<()> {
+        let (repo, tag) = crate::pull::split_tag(image)
+            .ok_or_else(|| anyhow::anyhow!("image {} has no tag", image))?;
+
+        self.client.image_tag(source, repo, tag).await?;
+
+        log::info!("Pulled image {} from {}", image, source);
+
+        Ok(())
+    }
+
+    async fn create_standby(
+        &self,
+        id: &str,
+        create_options: ContainerCreateBody,
+    ) -> anyhow::Result<()> {
+        let standby = format!("{id}{STANDBY_SUFFIX}");
+
+        // Remove any previous standby, which may have been created with an outdated config.
+        let _ = self
+            .client
+            .container_delete(&standby, false, true, false)
+            .await;
+
+        let mut labels = create_options.labels().cloned().unwrap_or_default();
+        labels.insert(STANDBY_LABEL_KEY.to_string(), id.to_string());
+        let create_options = create_options.with_labels(labels);
+        let local_endpoint = self.local_endpoint(id, &create_options);
+
+        log::info!(
+            module = id,
+            event = "module.standby";
+            "Creating standby container for module {}...",
+            id
+        );
+
+        self.client
+            .container_create(&standby, create_options)
+            .await
+            .context(Error::Docker)?;
+
+        if let Some((network, endpoint)) = local_endpoint {
+            self.connect_network(&standby, network, endpoint).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Create a module's container again after it was removed, with the create options it
+    /// had before.
+    async fn recreate(&self, id: &str, create_options: ContainerCreateBody) -> anyhow::Result<()> {
+        let standby_options = self
+            .warm_standby
+            .contains(id)
+            .then(|| create_options.clone());
+        let local_endpoint = self.local_endpoint(id, &create_options);
+
+        if self.engine == ContainerEngine::Podman {
+            create_bind_sources(&create_options)?;
+        }
+
+        self.client
+            .container_create(id, create_options)
+            .await
+            .context(Error::Docker)?;
+
+        if let Some((network, endpoint)) = local_endpoint {
+            self.connect_network(id, network, endpoint).await?;
+        }
+
+        if let Some(standby_options) = standby_options {
+            self.standby_options.insert(id, standby_options.clone());
+
+            if let Err(err) = self.create_standby(id, standby_options).await {
+                log::warn!(
+                    "Failed to create standby container for module {}: {:?}",
+                    id,
+                    err
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The internal network of the network policy and the endpoint on it of a module
+    /// that may reach the internet, if the module is to be connected to it.
+    fn local_endpoint(
+        &self,
+        module: &str,
+        create_options: &ContainerCreateBody,
+    ) -> Option<(&str, EndpointSettings)> {
+        let policy = self.network_policy.as_ref()?;
+        let endpoint =
+            local_network_endpoint(policy, &self.module_network, module, create_options)?;
+
+        Some((policy.local_network(), endpoint))
+    }
+
+    /// Connect a container that was just created to a network. If that fails, the
+    /// container is removed, so that it isn't left to be started without the network
+    /// its policy requires, and creating it again starts over.
+    async fn connect_network(
+        &self,
+        container: &str,
+        network: &str,
+        endpoint: EndpointSettings,
+    ) -> anyhow::Result<()> {
+        log::debug!("Connecting container {} to network {}", container, network);
+
+        let result = self
+            .client
+            .network_connect(
+                network,
+                NetworkConnectConfig::new(container.to_string()).with_endpoint_config(endpoint),
+            )
+            .await
+            .context(Error::Docker);
+
+        if result.is_err() {
+            if let Err(err) = self
+                .client
+                .container_delete(container, false, true, false)
+                .await
+            {
+                log::warn!(
+                    "Failed to remove container {} not connected to network {}: {}",
+                    container,
+                    network,
+                    err
+                );
+            }
+        }
+
+        result
+    }
+}
+
+/// A standby container keeps its label after it replaces its module, so only
+/// containers that aren't named after the module they stand in for are standbys.
+fn is_standby(container: &docker::models::ContainerSummary) -> bool {
+    container
+        .labels()
+        .get(STANDBY_LABEL_KEY)
+        .map_or(false, |module| {
+            container
+                .names()
+                .iter()
+                .all(|name| name.trim_start_matches('/') != module)
+        })
+}
+
+pub fn init_client(docker_url: &Url) -> anyhow::Result<DockerApiClient<Connector>> {
+    // build the hyper client
+    let connector = Connector::new(docker_url).context(Error::Initialization)?;
+
+    // extract base path - the bit that comes after the scheme
+    let base_path = docker_url
+        .to_base_path()
+        .context(Error::Initialization)?
+        .to_str()
+        .ok_or(Error::Initialization)?
+        .to_string();
+
+    let configuration = Configuration {
+        base_path,
+        uri_composer: Box::new(|base_path, path| {
+            // https://docs.rs/hyperlocal/0.6.0/src/hyperlocal/lib.rs.html#59
+            let host = hex::encode(base_path.as_bytes());
+            let host_str = format!("unix://{host}:0{path}");
+            Ok(host_str.parse()?)
+        }),
+        ..Default::default()
+    };
+
+    Ok(DockerApiClient::new(connector).with_configuration(configuration))
+}
+
+/// Podman lists images by their fully-qualified names, while deployments and the
+/// bootstrap edgeAgent image usually use the short names that Docker lists.
+fn short_image_name(name: &str) -> Option<&str> {
+    ["docker.io/library/", "docker.io/", "localhost/"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+}
+
+/// Give a module the host's timezone and mount the directory with the host's time status.
+///
+/// A timezone set in the module's own environment takes precedence.
+fn inject_host_time(time_dir: &std::path::Path, module: &mut ModuleSpec<DockerConfig>) {
+    let env = module.env_mut();
+    env.entry("TZ".to_string())
+        .or_insert_with(edgelet_core::host_timezone);
+    env.insert(
+        edgelet_core::MODULE_TIME_DIR_ENV.to_string(),
+        edgelet_core::MODULE_TIME_DIR.to_string(),
+    );
+
+    let create_options = module.config_mut().create_options_mut();
+    let mut host_config = create_options
+        .host_config()
+        .cloned()
+        .unwrap_or_else(HostConfig::new);
+
+    let mut binds = host_config
+        .binds()
+        .map(<[String]>::to_vec)
+        .unwrap_or_default();
+    binds.push(format!(
+        "{}:{}:ro",
+        time_dir.display(),
+        edgelet_core::MODULE_TIME_DIR
+    ));
+    host_config.set_binds(binds);
+
+    create_options.set_host_config(host_config);
+}
+
+/// Variables from settings, and the device's hostname, which modules can refer to as
+/// `{{name}}` in their env and createOptions.
+fn template_variables(settings: &Settings) -> BTreeMap<String, String> {
+    let mut variables = settings.template_variables().clone();
+    variables.insert("hostname".to_string(), settings.hostname().to_string());
+
+    variables
+}
+
+/// Give a module the configured memory reservation and log rotation, unless its create
+/// options already set them.
+fn apply_module_defaults(defaults: &ModuleDefaults, create_options: &mut ContainerCreateBody) {
+    if defaults.is_default() {
+        return;
+    }
+
+    let mut host_config = create_options
+        .host_config()
+        .cloned()
+        .unwrap_or_else(HostConfig::new);
+
+    if let Some(memory_reservation_mb) = defaults.memory_reservation_mb {
+        if host_config.memory_reservation().is_none() {
+            let memory_reservation = memory_reservation_mb.saturating_mul(1024 * 1024);
+            host_config
+                .set_memory_reservation(i64::try_from(memory_reservation).unwrap_or(i64::MAX));
+        }
+    }
+
+    if let Some(log_max_size_mb) = defaults.log_max_size_mb {
+        if host_config.log_config().is_none() {
+            let mut config = BTreeMap::new();
+            config.insert("max-size".to_string(), format!("{log_max_size_mb}m"));
+            if let Some(log_max_files) = defaults.log_max_files {
+                config.insert("max-file".to_string(), log_max_files.to_string());
+            }
+
+            host_config.set_log_config(
+                docker::models::HostConfigLogConfig::new()
+                    .with__type("json-file".to_string())
+                    .with_config(config),
+            );
+        }
+    }
+
+    create_options.set_host_config(host_config);
+}
+
+/// Give a module its resource limits, unless its create options already set them.
+///
+/// Create options that set a limit to 0 or less, i.e. unlimited, are given the configured
+/// limit. With `enforce`, create options that set a limit above the configured one are
+/// rejected.
+fn apply_module_limits(
+    limits: &ResourceLimits,
+    enforce: bool,
+    create_options: &mut ContainerCreateBody,
+) -> Result<(), String> {
+    if limits.is_default() {
+        return Ok(());
+    }
+
+    let mut host_config = create_options
+        .host_config()
+        .cloned()
+        .unwrap_or_else(HostConfig::new);
+
+    if let Some(memory_mb) = limits.memory_mb {
+        let limit = i64::try_from(memory_mb.saturating_mul(1024 * 1024)).unwrap_or(i64::MAX);
+
+        match host_config.memory().filter(|memory| *memory > 0) {
+            Some(memory) if enforce && memory > limit => {
+                return Err(format!(
+                    "memory of {memory} bytes is above the limit of {memory_mb} MB"
+                ));
+            }
+            Some(_) => (),
+            None => host_config.set_memory(limit),
+        }
+    }
+
+    if let Some(cpus) = limits.cpus {
+        #[allow(clippy::cast_possible_truncation)]
+        let limit = (cpus * 1e9) as i64;
+
+        // CPU time is limited either with NanoCpus or with CpuQuota per CpuPeriod, and
+        // Docker rejects both being set.
+        let nano_cpus = match (host_config.nano_cp_us(), host_config.cpu_quota()) {
+            (Some(nano_cpus), _) if nano_cpus > 0 => Some(nano_cpus),
+            (_, Some(quota)) if quota > 0 => {
+                let period = host_config
+                    .cpu_period()
+                    .filter(|period| *period > 0)
+                    .unwrap_or(100_000);
+
+                Some(quota.saturating_mul(1_000_000_000) / period)
+            }
+            _ => None,
+        };
+
+        match nano_cpus {
+            Some(nano_cpus) if enforce && nano_cpus > limit => {
+                return Err(format!(
+                    "{nano_cpus} nano CPUs is above the limit of {cpus} CPUs"
+                ));
+            }
+            Some(_) => (),
+            None => host_config.set_nano_cp_us(limit),
+        }
+    }
+
+    if let Some(pids) = limits.pids {
+        let limit = i64::try_from(pids).unwrap_or(i64::MAX);
+
+        match host_config
+            .pids_limit()
+            .filter(|pids_limit| *pids_limit > 0)
+        {
+            Some(pids_limit) if enforce && pids_limit > limit => {
+                return Err(format!(
+                    "pids limit of {pids_limit} is above the limit of {pids}"
+                ));
+            }
+            Some(_) => (),
+            None => host_config.set_pids_limit(limit),
+        }
+    }
+
+    create_options.set_host_config(host_config);
+
+    Ok(())
+}
+
+/// Resolve `..` and `.` in an absolute host path without touching the filesystem.
+fn normalize_host_path(path: &str) -> std::path::PathBuf {
+    let mut normalized = std::path::PathBuf::from("/");
+    for component in std::path::Path::new(path).components() {
+        match component {
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            std::path::Component::Normal(part) => normalized.push(part),
+            _ => (),
+        }
+    }
+    normalized
+}
+
+/// Whether the device at `path` is allowed, either as written or as the node it resolves to,
+/// so neither `..` nor a symlink can reach a device the policy doesn't name.
+fn device_allowed(policy: &DeviceMapping, path: &str) -> bool {
+    let normalized = normalize_host_path(path);
+    let resolved = std::fs::canonicalize(&normalized).unwrap_or_else(|_| normalized.clone());
+
+    [normalized, resolved]
+        .iter()
+        .any(|path| path.to_str().map_or(false, |path| policy.allows(path)))
+}
+
+/// Check the host side of a bind mount. Binding `/dev` or any of its ancestors would map
+/// every device, and binding a path under `/dev` maps that device.
+fn check_bind_source(policy: &DeviceMapping, source: &str) -> Result<(), String> {
+    // Sources that aren't absolute paths are named volumes.
+    if !source.starts_with('/') {
+        return Ok(());
+    }
+
+    let dev = std::path::Path::new("/dev");
+    let normalized = normalize_host_path(source);
+    let resolved = std::fs::canonicalize(&normalized).unwrap_or_else(|_| normalized.clone());
+
+    for path in [&normalized, &resolved] {
+        if dev.starts_with(path) {
+            return Err(format!(
+                "bind of {source:?} would map every device, which is not allowed"
+            ));
+        }
+
+        if path.starts_with(dev) && !device_allowed(policy, source) {
+            return Err(format!(
+                "bind of device {source:?} is not in device_mapping.allowed"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a device cgroup rule such as `c 188:0 rwm` names a single allowed device. Rules
+/// with wildcards, or for devices that aren't present, can't be checked and aren't allowed.
+fn cgroup_rule_allowed(policy: &DeviceMapping, rule: &str) -> bool {
+    let mut parts = rule.split_whitespace();
+    let kind = match parts.next() {
+        Some("c") => "char",
+        Some("b") => "block",
+        _ => return false,
+    };
+    let Some((major, minor)) = parts.next().and_then(|numbers| numbers.split_once(':')) else {
+        return false;
+    };
+    if major.parse::<u32>().is_err() || minor.parse::<u32>().is_err() {
+        return false;
+    }
+
+    // The kernel links every device node as /dev/{char,block}/<major>:<minor>.
+    std::fs::canonicalize(format!("/dev/{kind}/{major}:{minor}"))
+        .ok()
+        .and_then(|path| path.to_str().map(|path| policy.allows(path)))
+        .unwrap_or(false)
+}
+
+/// Check the devices a module maps against the allowed devices, and give modules labeled
+/// as GPU workloads the NVIDIA container runtime.
+fn apply_device_mapping(
+    policy: &DeviceMapping,
+    create_options: &mut ContainerCreateBody,
+) -> Result<(), String> {
+    let is_gpu = create_options
+        .labels()
+        .and_then(|labels| labels.get(DeviceMapping::GPU_LABEL))
+        .map_or(false, |value| value == "true");
+
+    let mut host_config = create_options
+        .host_config()
+        .cloned()
+        .unwrap_or_else(HostConfig::new);
+
+    if policy.is_restricted() {
+        // Privileged containers can access every device.
+        if host_config.privileged() == Some(&true) {
+            return Err("privileged containers are not allowed when devices are restricted".into());
+        }
+
+        for device in host_config.devices().unwrap_or_default() {
+            let path = device.path_on_host().unwrap_or_default();
+            if !device_allowed(policy, path) {
+                return Err(format!("device {path:?} is not in device_mapping.allowed"));
+            }
+        }
+
+        // Bind mounts of device nodes are checked too, as they are another way to map them.
+        for bind in host_config.binds().unwrap_or_default() {
+            let source = bind.split(':').next().unwrap_or_default();
+            check_bind_source(policy, source)?;
+        }
+
+        for mount in host_config.mounts().unwrap_or_default() {
+            if mount._type() == Some("bind") {
+                check_bind_source(policy, mount.source().unwrap_or_default())?;
+            }
+        }
+
+        // Cgroup rules grant access to device numbers, whatever path the device is at.
+        for rule in host_config.device_cgroup_rules().unwrap_or_default() {
+            if !cgroup_rule_allowed(policy, rule) {
+                return Err(format!(
+                    "device cgroup rule {rule:?} does not match a device in device_mapping.allowed"
+                ));
+            }
+        }
+    }
+
+    if policy.nvidia_runtime && is_gpu {
+        if host_config.runtime().is_none() {
+            host_config.set_runtime("nvidia".to_string());
+        }
+
+        let mut env = create_options
+            .env()
+            .map(<[String]>::to_vec)
+            .unwrap_or_default();
+        for (name, value) in [
+            ("NVIDIA_VISIBLE_DEVICES", "all"),
+            ("NVIDIA_DRIVER_CAPABILITIES", "compute,utility"),
+        ] {
+            if !env.iter().any(|env| env.split('=').next() == Some(name)) {
+                env.push(format!("{name}={value}"));
+            }
+        }
+        create_options.set_env(env);
+    }
+
+    create_options.set_host_config(host_config);
+
+    Ok(())
+}
+
+/// Attach a module that may not reach the internet only to the internal network of the
+/// network policy, in place of the module network that Edge Agent attaches it to. Its
+/// endpoint settings, such as aliases, are kept.
+fn apply_network_policy(
+    policy: &NetworkPolicy,
+    module_network: &str,
+    module: &str,
+    create_options: &mut ContainerCreateBody,
+) -> Result<(), String> {
+    if policy.egress(module) == Egress::Internet {
+        return Ok(());
+    }
+
+    let local_network = policy.local_network();
+
+    let mut host_config = create_options
+        .host_config()
+        .cloned()
+        .unwrap_or_else(HostConfig::new);
+
+    match host_config.network_mode() {
+        // A container without a network can't reach anything.
+        Some("none") => return Ok(()),
+        None => (),
+        Some(mode) if mode == module_network || mode == local_network => (),
+        Some(mode) => {
+            return Err(format!(
+                "network mode {mode:?} is not allowed for modules without internet access"
+            ))
+        }
+    }
+
+    let mut endpoints = create_options
+        .networking_config()
+        .and_then(ContainerCreateBodyNetworkingConfig::endpoints_config)
+        .cloned()
+        .unwrap_or_default();
+    let endpoint = endpoints
+        .remove(local_network)
+        .or_else(|| endpoints.remove(module_network))
+        .unwrap_or_else(EndpointSettings::new);
+    if let Some(network) = endpoints.keys().next() {
+        return Err(format!(
+            "network {network:?} is not allowed for modules without internet access"
+        ));
+    }
+
+    host_config.set_network_mode(local_network.to_string());
+    create_options.set_host_config(host_config);
+
+    let networking_config = create_options
+        .networking_config()
+        .cloned()
+        .unwrap_or_else(ContainerCreateBodyNetworkingConfig::new)
+        .with_endpoints_config(std::iter::once((local_network.to_string(), endpoint)).collect());
+    create_options.set_networking_config(networking_config);
+
+    Ok(())
+}
+
+/// The endpoint on the internal network of a module that may reach the internet, so that
+/// modules without internet access can reach it, e.g. Edge Hub. It has the endpoint settings
+/// of the module's network, so that it is reached by the same aliases.
+fn local_network_endpoint(
+    policy: &NetworkPolicy,
+    module_network: &str,
+    module: &str,
+    create_options: &ContainerCreateBody,
+) -> Option<EndpointSettings> {
+    if policy.egress(module) == Egress::Local {
+        return None;
+    }
+
+    let network = match create_options
+        .host_config()
+        .and_then(HostConfig::network_mode)
+    {
+        // These containers share a network namespace, so they can't be connected to more networks.
+        Some(mode) if mode == "host" || mode == "none" || mode.starts_with("container:") => {
+            return None
+        }
+        Some(mode) => mode,
+        None => module_network,
+    };
+
+    let endpoint = create_options
+        .networking_config()
+        .and_then(ContainerCreateBodyNetworkingConfig::endpoints_config)
+        .and_then(|endpoints| endpoints.get(network))
+        .cloned()
+        .unwrap_or_else(EndpointSettings::new);
+
+    Some(endpoint)
+}
+
+fn is_running(container: &docker::models::InlineResponse200) -> bool {
+    container
+        .state()
+        .and_then(docker::models::InlineResponse200State::running)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Create options that create a container again as it is.
+///
+/// Docker connects a new container only to the network of its network mode, so only the
+/// endpoint on that network is kept. The container's automatic alias, its short ID,
+/// would be stale and is dropped.
+fn recreate_options(
+    container: &docker::models::InlineResponse200,
+) -> anyhow::Result<ContainerCreateBody> {
+    let mut create_options = serde_json::to_value(container.config())?;
+    let create_options_map = create_options
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("container has no config"))?;
+
+    create_options_map.insert(
+        "HostConfig".to_string(),
+        serde_json::to_value(container.host_config())?,
+    );
+
+    let network_mode = container.host_config().and_then(HostConfig::network_mode);
+    let endpoint = network_mode.and_then(|network| {
+        container
+            .network_settings()?
+            .networks()?
+            .get(network)
+            .map(|endpoint| (network, endpoint))
+    });
+
+    if let Some((network, endpoint)) = endpoint {
+        let id = container.id().unwrap_or_default();
+        let mut endpoint = serde_json::to_value(endpoint)?;
+
+        let mut config = serde_json::Map::new();
+        for key in ["IPAMConfig", "Links", "Aliases"] {
+            if let Some(value) = endpoint.get_mut(key).map(serde_json::Value::take) {
+                config.insert(key.to_string(), value);
+            }
+        }
+
+        if let Some(aliases) = config
+            .get_mut("Aliases")
+            .and_then(serde_json::Value::as_array_mut)
+        {
+            aliases.retain(|alias| alias.as_str().map_or(true, |alias| !id.starts_with(alias)));
+        }
+
+        create_options_map.insert(
+            "NetworkingConfig".to_string(),
+            serde_json::json!({ "EndpointsConfig": { network: config } }),
+        );
+    }
+
+    Ok(serde_json::from_value(create_options)?)
+}
+
+/// Remove the container engine's restart policy from the create options of a module
+/// that has a restart policy, so that the engine doesn't restart it behind its circuit
+/// breaker's back. Edge Agent restarts the module through the management API instead.
+fn disable_engine_restarts(
+    module: &str,
+    create_options: &mut ContainerCreateBody,
+) -> anyhow::Result<()> {
+    let Some(host_config) = create_options.host_config() else {
+        return Ok(());
+    };
+
+    // The restart policy isn't a field of the model, so it's only in the JSON.
+    let mut host_config = serde_json::to_value(host_config)?;
+    let Some(host_config_map) = host_config.as_object_mut() else {
+        return Ok(());
+    };
+
+    let restarts = host_config_map
+        .get("RestartPolicy")
+        .and_then(|policy| policy.get("Name"))
+        .and_then(serde_json::Value::as_str)
+        .map_or(false, |name| !name.is_empty() && name != "no");
+    if !restarts {
+        return Ok(());
+    }
 
-    async fn make_runtime(
-        settings: &Settings,
-        create_socket_channel: UnboundedSender<ModuleAction>,
-        image_use_data: ImagePruneData,
-    ) -> anyhow::Result<Self::ModuleRuntime> {
-        log::info!("Initializing module runtime...");
+    log::warn!(
+        "Ignoring the RestartPolicy in the create options of module {}, whose starts are limited by its restart policy",
+        module
+    );
+    host_config_map.remove("RestartPolicy");
+    create_options.set_host_config(serde_json::from_value(host_config)?);
 
-        let client = init_client(settings.moby_runtime().uri())?;
-        create_network_if_missing(settings, &client).await?;
+    Ok(())
+}
 
-        // to avoid excessive FD usage, we will not allow sysinfo to keep files open.
-        sysinfo::set_open_files_limit(0);
-        let system_resources = System::new_all();
-        log::info!("Successfully initialized module runtime");
+/// Set a module's exec probe as its Docker healthcheck, unless its create options
+/// already have one.
+fn apply_health_probe(command: Option<&[String]>, create_options: &mut ContainerCreateBody) {
+    let Some(command) = command else {
+        return;
+    };
 
-        let runtime = Self {
-            client,
-            system_resources: Arc::new(Mutex::new(system_resources)),
-            create_socket_channel,
-            allow_elevated_docker_permissions: settings.allow_elevated_docker_permissions(),
-            additional_info: settings.additional_info().clone(),
-            image_use_data,
-        };
+    if create_options.healthcheck().is_none() {
+        let test = std::iter::once("CMD".to_string())
+            .chain(command.iter().cloned())
+            .collect();
 
-        Ok(runtime)
+        create_options.set_healthcheck(HealthConfig::new().with_test(test));
     }
 }
 
-pub fn init_client(docker_url: &Url) -> anyhow::Result<DockerApiClient<Connector>> {
-    // build the hyper client
-    let connector = Connector::new(docker_url).context(Error::Initialization)?;
+/// Give a module the DNS servers of the module network, unless its create options
+/// already set them.
+fn apply_module_dns(dns: &[String], create_options: &mut ContainerCreateBody) {
+    if dns.is_empty() {
+        return;
+    }
 
-    // extract base path - the bit that comes after the scheme
-    let base_path = docker_url
-        .to_base_path()
-        .context(Error::Initialization)?
-        .to_str()
-        .ok_or(Error::Initialization)?
-        .to_string();
+    let mut host_config = create_options
+        .host_config()
+        .cloned()
+        .unwrap_or_else(HostConfig::new);
 
-    let configuration = Configuration {
-        base_path,
-        uri_composer: Box::new(|base_path, path| {
-            // https://docs.rs/hyperlocal/0.6.0/src/hyperlocal/lib.rs.html#59
-            let host = hex::encode(base_path.as_bytes());
-            let host_str = format!("unix://{host}:0{path}");
-            Ok(host_str.parse()?)
-        }),
-        ..Default::default()
-    };
+    if host_config.dns().is_none() {
+        host_config.set_dns(dns.to_vec());
+    }
 
-    Ok(DockerApiClient::new(connector).with_configuration(configuration))
+    create_options.set_host_config(host_config);
+}
+
+/// Create the missing host directories of a module's binds.
+///
+/// Docker creates these itself, but Podman fails to create the container instead.
+fn create_bind_sources(create_options: &ContainerCreateBody) -> std::io::Result<()> {
+    let binds = create_options
+        .host_config()
+        .and_then(HostConfig::binds)
+        .unwrap_or_default();
+
+    for bind in binds {
+        let source = bind.split(':').next().unwrap_or_default();
+
+        // Sources that aren't absolute paths are named volumes.
+        if source.starts_with('/') && !std::path::Path::new(source).exists() {
+            log::info!("Creating bind mount source {}", source);
+            std::fs::create_dir_all(source)?;
+        }
+    }
+
+    Ok(())
 }
 
 async fn create_network_if_missing(
@@ -225,7 +2066,8 @@ async fn create_network_if_missing(
     let network_id = settings.moby_runtime().network().name();
     log::info!("Using runtime network id {}", network_id);
 
-    let filter = format!(r#"{{"name":{{"{network_id}":true}}}}"#);
+    // Podman only accepts filters in list form.
+    let filter = format!(r#"{{"name":["{network_id}"]}}"#);
     let existing_iotedge_networks = client
         .network_list(&filter)
         .await
@@ -236,7 +2078,20 @@ async fn create_network_if_missing(
         })
         .context(Error::RuntimeOperation(RuntimeOperation::Init))?;
 
-    if existing_iotedge_networks.is_empty() {
+    // The list filter matches names by substring.
+    if let Some(existing) = existing_iotedge_networks
+        .iter()
+        .find(|network| network.name() == Some(network_id))
+    {
+        // An existing network isn't changed, since modules may be attached to it.
+        if enable_i_pv6 && existing.enable_i_pv6() != Some(&true) {
+            log::warn!(
+                "Network {} exists without IPv6, so modules can't be reached over IPv6. \
+                 Remove the network to have it created again with IPv6.",
+                network_id
+            );
+        }
+    } else {
         let mut network_config =
             NetworkConfig::new(network_id.to_string()).with_enable_i_pv6(enable_i_pv6);
 
@@ -258,35 +2113,94 @@ async fn create_network_if_missing(
     Ok(())
 }
 
+/// Create the internal network of the network policy. The container engine drops traffic
+/// between it and outside the device. An existing network that isn't internal is refused,
+/// since modules attached to it could reach the internet.
+async fn create_local_network_if_missing(
+    policy: &NetworkPolicy,
+    client: &DockerApiClient<Connector>,
+) -> anyhow::Result<()> {
+    let network_id = policy.local_network();
+    log::info!(
+        "Using internal network id {} for the network policy",
+        network_id
+    );
+
+    let filter = format!(r#"{{"name":["{network_id}"]}}"#);
+    let existing_networks = client
+        .network_list(&filter)
+        .await
+        .context(Error::Docker)
+        .context(Error::RuntimeOperation(RuntimeOperation::Init))?;
+
+    if let Some(existing) = existing_networks
+        .iter()
+        .find(|network| network.name() == Some(network_id))
+    {
+        if existing.internal() != Some(&true) {
+            return Err(anyhow::anyhow!(
+                "network {} exists but is not internal; remove it to have it created again",
+                network_id
+            ))
+            .context(Error::RuntimeOperation(RuntimeOperation::Init));
+        }
+    } else {
+        client
+            .network_create(NetworkConfig::new(network_id.to_string()).with_internal(true))
+            .await
+            .context(Error::Docker)
+            .context(Error::RuntimeOperation(RuntimeOperation::Init))?;
+    }
+
+    Ok(())
+}
+
 fn get_ipv6_settings(network_configuration: &MobyNetwork) -> (bool, Option<Ipam>) {
     if let MobyNetwork::Network(network) = network_configuration {
         let ipv6 = network.ipv6().unwrap_or_default();
-        network.ipam().and_then(CoreIpam::config).map_or_else(
-            || (ipv6, None),
-            |ipam_config| {
-                let config = ipam_config
-                    .iter()
-                    .map(|ipam_config| {
-                        let mut config_map = HashMap::new();
-                        if let Some(gateway_config) = ipam_config.gateway() {
-                            config_map.insert("Gateway".to_string(), gateway_config.to_string());
-                        };
+        let mut config: Vec<HashMap<String, String>> = network
+            .ipam()
+            .and_then(CoreIpam::config)
+            .unwrap_or_default()
+            .iter()
+            .map(|ipam_config| {
+                let mut config_map = HashMap::new();
+                if let Some(gateway_config) = ipam_config.gateway() {
+                    config_map.insert("Gateway".to_string(), gateway_config.to_string());
+                };
 
-                        if let Some(subnet_config) = ipam_config.subnet() {
-                            config_map.insert("Subnet".to_string(), subnet_config.to_string());
-                        };
+                if let Some(subnet_config) = ipam_config.subnet() {
+                    config_map.insert("Subnet".to_string(), subnet_config.to_string());
+                };
 
-                        if let Some(ip_range_config) = ipam_config.ip_range() {
-                            config_map.insert("IPRange".to_string(), ip_range_config.to_string());
-                        };
+                if let Some(ip_range_config) = ipam_config.ip_range() {
+                    config_map.insert("IPRange".to_string(), ip_range_config.to_string());
+                };
 
-                        config_map
-                    })
-                    .collect();
+                config_map
+            })
+            .collect();
 
-                (ipv6, Some(Ipam::new().with_config(config)))
-            },
-        )
+        // The engine only enables IPv6 on a network with an IPv6 subnet.
+        if let Some(subnet) = network.ipv6_subnet() {
+            let has_ipv6_subnet = config.iter().any(|config_map| {
+                config_map
+                    .get("Subnet")
+                    .map_or(false, |subnet| subnet.contains(':'))
+            });
+
+            if !has_ipv6_subnet {
+                let mut config_map = HashMap::new();
+                config_map.insert("Subnet".to_string(), subnet.to_string());
+                config.push(config_map);
+            }
+        }
+
+        if config.is_empty() {
+            (ipv6, None)
+        } else {
+            (ipv6, Some(Ipam::new().with_config(config)))
+        }
     } else {
         (false, None)
     }
@@ -302,21 +2216,21 @@ where
     type ModuleRegistry = Self;
 
     async fn create(&self, mut module: ModuleSpec<Self::Config>) -> anyhow::Result<()> {
-        log::info!("Creating module {}...", module.name());
-
-        // we only want "docker" modules
-        if module.r#type() != DOCKER_MODULE_TYPE {
-            return Err(Error::InvalidModuleType(module.r#type().to_string()).into());
-        }
-
-        unset_privileged(
-            self.allow_elevated_docker_permissions,
-            module.config_mut().create_options_mut(),
-        );
-        drop_unsafe_privileges(
-            self.allow_elevated_docker_permissions,
-            module.config_mut().create_options_mut(),
+        log::info!(
+            module = module.name(),
+            event = "module.create";
+            "Creating module {}...",
+            module.name()
         );
+        let _in_flight = self.in_flight.begin("create", module.name());
+
+        let result = self
+            .disk_space
+            .ensure()
+            .map_err(anyhow::Error::from)
+            .and_then(|()| self.prepare(&mut module));
+        self.record_create_error(module.name(), &result);
+        result?;
 
         let image = module.config().image().to_owned();
         let is_content_trust_enabled = false;
@@ -327,6 +2241,26 @@ where
             log::info!("Creating image via tag {}...", &image);
         }
 
+        self.verify_image_digest(&image, module.config().create_options().labels())
+            .await
+            .map_err(|e| {
+                log::warn!("{:?}", e);
+                e
+            })
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::CreateModule(module.name().to_string()))
+            })?;
+
+        self.verify_image_signature(&image, module.config().auth())
+            .await
+            .map_err(|e| {
+                log::warn!("{:?}", e);
+                e
+            })
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::CreateModule(module.name().to_string()))
+            })?;
+
         let create_options = module.config().create_options().clone();
         let merged_env = merge_env(create_options.env(), module.env());
 
@@ -344,6 +2278,18 @@ where
             .with_env(merged_env)
             .with_labels(labels);
 
+        let standby_options = self
+            .warm_standby
+            .contains(module.name())
+            .then(|| create_options.clone());
+        let local_endpoint = self.local_endpoint(module.name(), &create_options);
+
+        if self.engine == ContainerEngine::Podman {
+            create_bind_sources(&create_options).with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::CreateModule(module.name().to_string()))
+            })?;
+        }
+
         // Here we don't add the container to the iot edge docker network as the edge-agent is expected to do that.
         // It contains the logic to add a container to the iot edge network only if a network is not already specified.
         self.client
@@ -358,6 +2304,19 @@ where
                 Error::RuntimeOperation(RuntimeOperation::CreateModule(module.name().to_string()))
             })?;
 
+        if let Some((network, endpoint)) = local_endpoint {
+            self.connect_network(module.name(), network, endpoint)
+                .await
+                .with_context(|| {
+                    Error::RuntimeOperation(RuntimeOperation::CreateModule(
+                        module.name().to_string(),
+                    ))
+                })?;
+        }
+
+        // A new deployment of the module gets a fresh restart budget.
+        self.circuit_breakers.reset(module.name());
+
         // Now, get the image id of the image associated with the module we started
         let module_with_details = self.get(module.name()).await?;
 
@@ -370,6 +2329,20 @@ where
                 .ok_or(Error::GetImageId())?,
         )?;
 
+        if let Some(standby_options) = standby_options {
+            self.standby_options
+                .insert(module.name(), standby_options.clone());
+
+            // Failing to create the standby shouldn't fail creation of the module itself.
+            if let Err(err) = self.create_standby(module.name(), standby_options).await {
+                log::warn!(
+                    "Failed to create standby container for module {}: {:?}",
+                    module.name(),
+                    err
+                );
+            }
+        }
+
         Ok(())
     }
 
@@ -424,21 +2397,42 @@ where
             config = config.with_image_hash(image_hash.to_string());
         }
 
+        let create_error = self
+            .create_errors
+            .lock()
+            .expect("create errors lock poisoned")
+            .get(&name)
+            .map(|err| format!("last update was rejected: {err}"));
+        let description = create_error
+            .or_else(|| self.pulls.status(&name))
+            .or_else(|| {
+                self.pull_outcomes
+                    .lock()
+                    .expect("pull outcomes lock poisoned")
+                    .get(config.image())
+                    .and_then(|outcome| outcome.description(config.image()))
+            });
+
         let module = DockerModule::new(self.client.clone(), name, config).with_context(|| {
             Error::RuntimeOperation(RuntimeOperation::GetModule(id.to_string()))
         })?;
-        let state = runtime_state(response.id(), response.state());
+        let state = runtime_state(response.id(), response.state())
+            .with_description(description)
+            .with_circuit_breaker(self.circuit_breakers.status(&name));
 
         Ok((module, state))
     }
 
     async fn start(&self, id: &str) -> anyhow::Result<()> {
-        log::info!("Starting module {}...", id);
+        log::info!(module = id, event = "module.start"; "Starting module {}...", id);
+        let _in_flight = self.in_flight.begin("start", id);
 
         ensure_not_empty(id).with_context(|| {
             Error::RuntimeOperation(RuntimeOperation::StartModule(id.to_owned()))
         })?;
 
+        self.check_restart_policy(id).await?;
+
         let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
 
         self.create_socket_channel
@@ -456,6 +2450,11 @@ where
             Error::RuntimeOperation(RuntimeOperation::StartModule(id.to_owned()))
         })?;
 
+        self.stop_requested
+            .lock()
+            .expect("stop requested lock poisoned")
+            .remove(id);
+
         self.client
             .container_start(id, "")
             .await
@@ -468,7 +2467,8 @@ where
     }
 
     async fn stop(&self, id: &str, wait_before_kill: Option<Duration>) -> anyhow::Result<()> {
-        log::info!("Stopping module {}...", id);
+        log::info!(module = id, event = "module.stop"; "Stopping module {}...", id);
+        let _in_flight = self.in_flight.begin("stop", id);
 
         ensure_not_empty(id).with_context(|| {
             Error::RuntimeOperation(RuntimeOperation::StopModule(id.to_owned()))
@@ -480,6 +2480,14 @@ where
             s => s as i32,
         });
 
+        self.stop_requested
+            .lock()
+            .expect("stop requested lock poisoned")
+            .insert(id.to_string());
+
+        // The module may still need its workload socket while it drains.
+        self.pre_stop(id, "stop").await;
+
         self.create_socket_channel
             .send(ModuleAction::Stop(id.to_string()))
             .map_err(|_| {
@@ -487,9 +2495,28 @@ where
                 Error::RuntimeOperation(RuntimeOperation::GetModule(id.to_string()))
             })?;
 
-        self.client
+        let result = self
+            .client
             .container_stop(id, wait_timeout)
             .await
+            .map_err(|e| self.normalize_stop_error(e));
+
+        // A module that is still running after a failed stop can fail, and must then be
+        // failed over. One that was already stopped stays stopped.
+        if let Err(err) = &result {
+            let already_stopped = err
+                .downcast_ref::<docker::apis::ApiError>()
+                .map_or(false, |err| err.code == hyper::StatusCode::NOT_MODIFIED);
+
+            if !already_stopped {
+                self.stop_requested
+                    .lock()
+                    .expect("stop requested lock poisoned")
+                    .remove(id);
+            }
+        }
+
+        result
             .context(Error::Docker)
             .map_err(|e| {
                 log::warn!("{:?}", e);
@@ -499,12 +2526,26 @@ where
     }
 
     async fn restart(&self, id: &str) -> anyhow::Result<()> {
-        log::info!("Restarting module {}...", id);
+        log::info!(module = id, event = "module.restart"; "Restarting module {}...", id);
+        let _in_flight = self.in_flight.begin("restart", id);
         ensure_not_empty(id).with_context(|| {
             Error::RuntimeOperation(RuntimeOperation::RestartModule(id.to_owned()))
         })?;
 
-        self.client
+        self.check_restart_policy(id).await?;
+
+        // A module that exits when it's signaled to drain hasn't failed, so it mustn't be
+        // failed over while it's restarted.
+        let stop_requested = !self
+            .stop_requested
+            .lock()
+            .expect("stop requested lock poisoned")
+            .insert(id.to_string());
+
+        self.pre_stop(id, "restart").await;
+
+        let result = self
+            .client
             .container_restart(id, None)
             .await
             .context(Error::Docker)
@@ -514,10 +2555,21 @@ where
             })
             .with_context(|| {
                 Error::RuntimeOperation(RuntimeOperation::RestartModule(id.to_owned()))
-            })
+            });
+
+        if !stop_requested {
+            self.stop_requested
+                .lock()
+                .expect("stop requested lock poisoned")
+                .remove(id);
+        }
+
+        result
     }
 
     async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        let _in_flight = self.in_flight.begin("remove", id);
+
         // get the image id of the image associated with the module we want to delete
         let module_with_details = self.get(id).await?;
         let image_id = module_with_details
@@ -526,12 +2578,20 @@ where
             .image_hash()
             .ok_or(Error::GetImageId())?;
 
-        log::info!("Removing module {}...", id);
+        log::info!(module = id, event = "module.remove"; "Removing module {}...", id);
 
         ensure_not_empty(id).with_context(|| {
             Error::RuntimeOperation(RuntimeOperation::RemoveModule(id.to_owned()))
         })?;
 
+        // A running module is force-deleted, so it's notified first as when it's stopped.
+        // Its exit in response isn't a failure to fail over from.
+        self.stop_requested
+            .lock()
+            .expect("stop requested lock poisoned")
+            .insert(id.to_string());
+        self.pre_stop(id, "remove").await;
+
         self.client
             .container_delete(
                 id, /* remove volumes */ false, /* force */ true,
@@ -550,6 +2610,25 @@ where
         // update image use timestamp for image garbage collection job later
         self.image_use_data.record_image_use_timestamp(image_id)?;
 
+        self.circuit_breakers.reset(id);
+        self.stop_requested
+            .lock()
+            .expect("stop requested lock poisoned")
+            .remove(id);
+
+        if self.warm_standby.contains(id) {
+            self.standby_options.remove(id);
+
+            let standby = format!("{id}{STANDBY_SUFFIX}");
+            if let Err(err) = self
+                .client
+                .container_delete(&standby, false, true, false)
+                .await
+            {
+                log::debug!("Could not remove standby container {}: {}", standby, err);
+            }
+        }
+
         // Remove the socket to avoid having socket files polluting the home folder.
         self.create_socket_channel
             .send(ModuleAction::Remove(id.to_string()))
@@ -676,6 +2755,7 @@ where
 
         let result = containers
             .iter()
+            .filter(|container| !is_standby(container))
             .flat_map(|container| {
                 DockerConfig::new(
                     container.image().to_string(),
@@ -751,6 +2831,10 @@ where
         for image in images {
             // an individual image id may be associated with multiple image names
             for name in image.repo_tags() {
+                if let Some(short_name) = short_image_name(name) {
+                    result.insert(short_name.to_string(), image.id().clone());
+                }
+
                 result.insert(name.clone(), image.id().clone());
             }
         }
@@ -758,26 +2842,140 @@ where
         Ok(result)
     }
 
-    async fn logs(&self, id: &str, options: &LogOptions) -> anyhow::Result<hyper::Body> {
-        log::info!("Getting logs for module {}...", id);
+    async fn logs(&self, id: &str, options: &LogOptions) -> anyhow::Result<hyper::Body> {
+        log::info!("Getting logs for module {}...", id);
+
+        self.client
+            .container_logs(
+                id,
+                options.follow(),
+                true,
+                true,
+                options.since(),
+                options.until(),
+                options.timestamps(),
+                &options.tail().to_string(),
+            )
+            .await
+            .context(Error::Docker)
+            .map_err(|e| {
+                log::warn!("{:?}", e);
+                e
+            })
+    }
+
+    async fn module_sbom(&self, id: &str) -> anyhow::Result<SbomLookup> {
+        use hyper::body::HttpBody;
+
+        log::info!("Getting SBOM for module {}...", id);
+
+        let (module, _) = self.get(id).await?;
+        let image = module.config().image();
+
+        let mut archive = self
+            .client
+            .image_export(image)
+            .await
+            .context(Error::Docker)
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::GetModuleSbom(id.to_owned()))
+            })?;
+
+        // The archive is streamed and scanned by the caller, without the runtime.
+        let id = id.to_owned();
+        Ok(Box::pin(async move {
+            let mut scanner = crate::sbom::ArchiveScanner::default();
+
+            while let Some(chunk) = archive.data().await {
+                let chunk = chunk.with_context(|| {
+                    Error::RuntimeOperation(RuntimeOperation::GetModuleSbom(id.clone()))
+                })?;
+
+                scanner.feed(&chunk).with_context(|| {
+                    Error::RuntimeOperation(RuntimeOperation::GetModuleSbom(id.clone()))
+                })?;
+            }
+
+            Ok(scanner.find_sbom())
+        }))
+    }
+
+    async fn registry_mirrors(&self) -> anyhow::Result<Vec<edgelet_core::RegistryMirror>> {
+        Ok(self.mirrors.list())
+    }
+
+    async fn registry_credentials(&self) -> anyhow::Result<Vec<edgelet_core::RegistryCredential>> {
+        Ok(self.credentials.list())
+    }
+
+    async fn invalidate_registry_credentials(&self, registry: Option<&str>) -> anyhow::Result<()> {
+        log::info!(
+            "Invalidating cached credentials of {}",
+            registry.unwrap_or("all registries")
+        );
+
+        self.credentials.invalidate(registry);
+
+        Ok(())
+    }
+
+    async fn disk_space(&self) -> anyhow::Result<Vec<edgelet_core::DiskSpaceStatus>> {
+        Ok(self.disk_space.list())
+    }
+
+    async fn purge_data(&self, id: &str) -> anyhow::Result<Vec<String>> {
+        log::info!("Purging data for module {}...", id);
+
+        ensure_not_empty(id).with_context(|| {
+            Error::RuntimeOperation(RuntimeOperation::PurgeModuleData(id.to_owned()))
+        })?;
+
+        let container = self
+            .client
+            .container_inspect(id, false)
+            .await
+            .context(Error::Docker)
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::PurgeModuleData(id.to_owned()))
+            })?;
+
+        let volumes: Vec<String> = container
+            .mounts()
+            .unwrap_or_default()
+            .iter()
+            .filter(|mount| mount._type() == Some("volume"))
+            .filter_map(|mount| mount.name().map(ToOwned::to_owned))
+            .collect();
+
+        let create_options = recreate_options(&container).with_context(|| {
+            Error::RuntimeOperation(RuntimeOperation::PurgeModuleData(id.to_owned()))
+        })?;
+
+        // Volumes can't be removed while a container uses them, so the container is
+        // removed, which also removes its workload socket, and then recreated as it was.
+        ModuleRuntime::remove(self, id).await?;
+
+        for volume in &volumes {
+            self.client
+                .volume_delete(volume, false)
+                .await
+                .context(Error::Docker)
+                .with_context(|| {
+                    Error::RuntimeOperation(RuntimeOperation::PurgeModuleData(id.to_owned()))
+                })?;
+
+            log::info!("Removed volume {} of module {}", volume, id);
+        }
+
+        self.recreate(id, create_options).await.with_context(|| {
+            Error::RuntimeOperation(RuntimeOperation::PurgeModuleData(id.to_owned()))
+        })?;
+
+        if is_running(&container) {
+            ModuleRuntime::start(self, id).await?;
+        }
 
-        self.client
-            .container_logs(
-                id,
-                options.follow(),
-                true,
-                true,
-                options.since(),
-                options.until(),
-                options.timestamps(),
-                &options.tail().to_string(),
-            )
-            .await
-            .context(Error::Docker)
-            .map_err(|e| {
-                log::warn!("{:?}", e);
-                e
-            })
+        Ok(volumes)
     }
 
     async fn remove_all(&self) -> anyhow::Result<()> {
@@ -807,7 +3005,10 @@ where
 
         for result in futures::future::join_all(stop).await {
             if let Err(err) = result {
-                log::warn!("Failed to stop module: {:?}", err);
+                // Modules that aren't running don't need to be stopped.
+                if Self::error_code(&err) != hyper::StatusCode::NOT_MODIFIED {
+                    log::warn!("Failed to stop module: {:?}", err);
+                }
             }
         }
 
@@ -836,9 +3037,37 @@ where
         self
     }
 
+    /// Check that a module can be created with the device's policies and defaults applied.
+    fn check(&self, module: &ModuleSpec<Self::Config>) -> anyhow::Result<()> {
+        self.prepare(&mut module.clone())
+    }
+
+    /// Like `check`, but also record the problems in the status of the module it would replace.
+    fn validate(&self, module: &ModuleSpec<Self::Config>) -> anyhow::Result<()> {
+        let result = self.check(module);
+        self.record_create_error(module.name(), &result);
+
+        result
+    }
+
     fn error_code(error: &anyhow::Error) -> hyper::StatusCode {
         if let Some(error) = error.root_cause().downcast_ref::<docker::apis::ApiError>() {
             error.code
+        } else if let Some(
+            Error::InvalidCreateOptions { .. }
+            | Error::ModuleLimits { .. }
+            | Error::DeviceNotAllowed { .. }
+            | Error::NetworkPolicy { .. },
+        ) = error.root_cause().downcast_ref::<Error>()
+        {
+            hyper::StatusCode::BAD_REQUEST
+        } else if let Some(Error::RestartRefused { .. }) =
+            error.root_cause().downcast_ref::<Error>()
+        {
+            hyper::StatusCode::TOO_MANY_REQUESTS
+        } else if let Some(Error::LowDiskSpace { .. }) = error.root_cause().downcast_ref::<Error>()
+        {
+            hyper::StatusCode::INSUFFICIENT_STORAGE
         } else {
             hyper::StatusCode::INTERNAL_SERVER_ERROR
         }
@@ -957,6 +3186,89 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn parse_container_event() {
+        let event = |action: &str, attributes: serde_json::Value| {
+            serde_json::json!({
+                "Type": "container",
+                "Action": action,
+                "Actor": { "ID": "id", "Attributes": attributes },
+            })
+            .to_string()
+        };
+
+        assert_eq!(
+            Some(ContainerEvent::Started("edgeHub".to_string())),
+            ContainerEvent::parse(
+                event("start", serde_json::json!({ "name": "edgeHub" })).as_bytes()
+            )
+        );
+        assert_eq!(
+            Some(ContainerEvent::Removed("edgeHub".to_string())),
+            ContainerEvent::parse(
+                event("destroy", serde_json::json!({ "name": "edgeHub" })).as_bytes()
+            )
+        );
+
+        assert_eq!(
+            None,
+            ContainerEvent::parse(
+                event(
+                    "start",
+                    serde_json::json!({
+                        "name": "edgeHub-standby",
+                        STANDBY_LABEL_KEY: "edgeHub",
+                    })
+                )
+                .as_bytes()
+            )
+        );
+        assert_eq!(
+            None,
+            ContainerEvent::parse(
+                event("die", serde_json::json!({ "name": "edgeHub" })).as_bytes()
+            )
+        );
+        assert_eq!(None, ContainerEvent::parse(b"not json"));
+    }
+
+    #[test]
+    fn standby_containers_are_hidden() {
+        let container = |name: &str, standby_for: Option<&str>| {
+            let mut labels = HashMap::new();
+            if let Some(module) = standby_for {
+                labels.insert(STANDBY_LABEL_KEY.to_string(), module.to_string());
+            }
+
+            docker::models::ContainerSummary::new(
+                String::new(),
+                vec![format!("/{name}")],
+                String::new(),
+                String::new(),
+                String::new(),
+                0,
+                Vec::new(),
+                0,
+                0,
+                labels,
+                String::new(),
+                String::new(),
+                docker::models::ContainerHostConfig::new("default"),
+                docker::models::ContainerNetworkSettings::new(HashMap::new()),
+                Vec::new(),
+            )
+        };
+
+        assert!(!is_standby(&container("testModule", None)));
+        assert!(is_standby(&container(
+            "testModule-standby",
+            Some("testModule")
+        )));
+
+        // A standby that has replaced its module is listed as the module.
+        assert!(!is_standby(&container("testModule", Some("testModule"))));
+    }
+
     #[test]
     fn parse_top_response_returns_pid_array() {
         let response = InlineResponse2001::new()
@@ -1086,6 +3398,774 @@ mod tests {
         );
     }
 
+    #[test]
+    fn apply_module_dns_works() {
+        let dns = vec!["fd00::53".to_string()];
+
+        let mut create_options = ContainerCreateBody::new();
+        apply_module_dns(&dns, &mut create_options);
+        assert_eq!(
+            Some(&dns[..]),
+            create_options.host_config().and_then(HostConfig::dns)
+        );
+
+        // DNS servers in the create options are kept.
+        let mut create_options = ContainerCreateBody::new()
+            .with_host_config(HostConfig::new().with_dns(vec!["8.8.8.8".to_string()]));
+        apply_module_dns(&dns, &mut create_options);
+        assert_eq!(
+            Some(&["8.8.8.8".to_string()][..]),
+            create_options.host_config().and_then(HostConfig::dns)
+        );
+    }
+
+    #[test]
+    fn apply_module_defaults_works() {
+        let defaults = ModuleDefaults {
+            memory_reservation_mb: Some(64),
+            log_max_size_mb: Some(10),
+            log_max_files: Some(3),
+        };
+
+        let mut create_options = ContainerCreateBody::new();
+        apply_module_defaults(&defaults, &mut create_options);
+
+        let host_config = create_options.host_config().unwrap();
+        assert_eq!(Some(64 * 1024 * 1024), host_config.memory_reservation());
+        let log_config = host_config.log_config().unwrap();
+        assert_eq!(Some("json-file"), log_config._type());
+        assert_eq!(
+            Some("10m"),
+            log_config
+                .config()
+                .unwrap()
+                .get("max-size")
+                .map(String::as_str)
+        );
+        assert_eq!(
+            Some("3"),
+            log_config
+                .config()
+                .unwrap()
+                .get("max-file")
+                .map(String::as_str)
+        );
+
+        // Create options take precedence.
+        let mut create_options = ContainerCreateBody::new().with_host_config(
+            HostConfig::new()
+                .with_memory_reservation(1024)
+                .with_log_config(
+                    docker::models::HostConfigLogConfig::new().with__type("journald".to_string()),
+                ),
+        );
+        apply_module_defaults(&defaults, &mut create_options);
+
+        let host_config = create_options.host_config().unwrap();
+        assert_eq!(Some(1024), host_config.memory_reservation());
+        assert_eq!(Some("journald"), host_config.log_config().unwrap()._type());
+    }
+
+    #[test]
+    fn apply_module_limits_works() {
+        let limits = ResourceLimits {
+            memory_mb: Some(256),
+            cpus: Some(0.5),
+            pids: Some(100),
+        };
+
+        let mut create_options = ContainerCreateBody::new();
+        apply_module_limits(&limits, false, &mut create_options).unwrap();
+
+        let host_config = create_options.host_config().unwrap();
+        assert_eq!(Some(256 * 1024 * 1024), host_config.memory());
+        assert_eq!(Some(500_000_000), host_config.nano_cp_us());
+        assert_eq!(Some(100), host_config.pids_limit());
+
+        // Create options take precedence, and unlimited values are replaced.
+        let create_options = ContainerCreateBody::new().with_host_config(
+            HostConfig::new()
+                .with_memory(1024 * 1024 * 1024)
+                .with_cpu_quota(200_000)
+                .with_pids_limit(-1),
+        );
+
+        let mut unenforced = create_options.clone();
+        apply_module_limits(&limits, false, &mut unenforced).unwrap();
+
+        let host_config = unenforced.host_config().unwrap();
+        assert_eq!(Some(1024 * 1024 * 1024), host_config.memory());
+        assert_eq!(None, host_config.nano_cp_us());
+        assert_eq!(Some(100), host_config.pids_limit());
+
+        // With enforcement, create options above the limits are rejected.
+        let mut enforced = create_options;
+        apply_module_limits(&limits, true, &mut enforced).unwrap_err();
+
+        let mut within_limits =
+            ContainerCreateBody::new().with_host_config(HostConfig::new().with_cpu_quota(25_000));
+        apply_module_limits(&limits, true, &mut within_limits).unwrap();
+        assert_eq!(None, within_limits.host_config().unwrap().nano_cp_us());
+
+        let mut above_cpus = ContainerCreateBody::new()
+            .with_host_config(HostConfig::new().with_nano_cp_us(2_000_000_000));
+        apply_module_limits(&limits, true, &mut above_cpus).unwrap_err();
+    }
+
+    #[test]
+    fn disable_engine_restarts_works() {
+        let mut create_options: ContainerCreateBody = serde_json::from_value(serde_json::json!({
+            "HostConfig": {
+                "Memory": 1024,
+                "RestartPolicy": { "Name": "always" },
+            },
+        }))
+        .unwrap();
+        disable_engine_restarts("sensor", &mut create_options).unwrap();
+
+        let create_options = serde_json::to_value(&create_options).unwrap();
+        assert_eq!(
+            serde_json::json!({ "Memory": 1024 }),
+            create_options["HostConfig"]
+        );
+
+        // Create options that don't let the engine restart the module are kept.
+        let mut create_options: ContainerCreateBody = serde_json::from_value(serde_json::json!({
+            "HostConfig": { "RestartPolicy": { "Name": "no" } },
+        }))
+        .unwrap();
+        disable_engine_restarts("sensor", &mut create_options).unwrap();
+        assert_eq!(
+            serde_json::json!({ "RestartPolicy": { "Name": "no" } }),
+            serde_json::to_value(&create_options).unwrap()["HostConfig"]
+        );
+    }
+
+    #[test]
+    fn apply_health_probe_works() {
+        let command = vec!["/bin/check".to_string(), "--quick".to_string()];
+
+        let mut create_options = ContainerCreateBody::new();
+        apply_health_probe(Some(&command), &mut create_options);
+        assert_eq!(
+            Some(
+                &[
+                    "CMD".to_string(),
+                    "/bin/check".to_string(),
+                    "--quick".to_string()
+                ][..]
+            ),
+            create_options.healthcheck().and_then(HealthConfig::test)
+        );
+
+        // A healthcheck in the create options is kept.
+        let mut create_options = ContainerCreateBody::new()
+            .with_healthcheck(HealthConfig::new().with_test(vec!["NONE".to_string()]));
+        apply_health_probe(Some(&command), &mut create_options);
+        assert_eq!(
+            Some(&["NONE".to_string()][..]),
+            create_options.healthcheck().and_then(HealthConfig::test)
+        );
+
+        let mut create_options = ContainerCreateBody::new();
+        apply_health_probe(None, &mut create_options);
+        assert!(create_options.healthcheck().is_none());
+    }
+
+    #[test]
+    fn apply_device_mapping_works() {
+        let policy = DeviceMapping {
+            nvidia_runtime: true,
+            allowed: Some(vec!["/dev/gpiomem".to_string(), "/dev/ttyUSB*".to_string()]),
+        };
+
+        let device = |path: &str| {
+            docker::models::DeviceMapping::new()
+                .with_path_on_host(path.to_string())
+                .with_path_in_container(path.to_string())
+        };
+
+        let mut create_options = ContainerCreateBody::new().with_host_config(
+            HostConfig::new()
+                .with_devices(vec![device("/dev/gpiomem"), device("/dev/ttyUSB1")])
+                .with_binds(vec!["/dev/ttyUSB0:/dev/ttyUSB0".to_string()]),
+        );
+        apply_device_mapping(&policy, &mut create_options).unwrap();
+        assert_eq!(None, create_options.host_config().unwrap().runtime());
+
+        let mut create_options = ContainerCreateBody::new()
+            .with_host_config(HostConfig::new().with_devices(vec![device("/dev/mem")]));
+        apply_device_mapping(&policy, &mut create_options).unwrap_err();
+
+        for bind in [
+            "/dev:/dev",
+            "/dev/:/dev",
+            "/:/host",
+            "/dev/../:/host",
+            "/dev/ttyUSB0/../mem:/dev/mem",
+            "/dev/gpiomem/../../dev/mem:/dev/mem",
+        ] {
+            let mut create_options = ContainerCreateBody::new()
+                .with_host_config(HostConfig::new().with_binds(vec![bind.to_string()]));
+            apply_device_mapping(&policy, &mut create_options).unwrap_err();
+        }
+
+        // Named volumes and other host paths are not devices.
+        let mut create_options =
+            ContainerCreateBody::new().with_host_config(HostConfig::new().with_binds(vec![
+                "data:/data".to_string(),
+                "/var/lib/data:/data".to_string(),
+            ]));
+        apply_device_mapping(&policy, &mut create_options).unwrap();
+
+        let mut create_options = ContainerCreateBody::new()
+            .with_host_config(HostConfig::new().with_devices(vec![device("/dev/ttyUSB0/../mem")]));
+        apply_device_mapping(&policy, &mut create_options).unwrap_err();
+
+        let mount = |source: &str| {
+            docker::models::Mount::new()
+                .with__type("bind".to_string())
+                .with_source(source.to_string())
+        };
+        let mut create_options = ContainerCreateBody::new()
+            .with_host_config(HostConfig::new().with_mounts(vec![mount("/dev/ttyUSB0")]));
+        apply_device_mapping(&policy, &mut create_options).unwrap();
+
+        let mut create_options = ContainerCreateBody::new()
+            .with_host_config(HostConfig::new().with_mounts(vec![mount("/dev")]));
+        apply_device_mapping(&policy, &mut create_options).unwrap_err();
+
+        for rule in ["a *:* rwm", "c *:* rwm", "c 1:* rwm", "c 1:1 rwm"] {
+            let mut create_options = ContainerCreateBody::new().with_host_config(
+                HostConfig::new().with_device_cgroup_rules(vec![rule.to_string()]),
+            );
+            apply_device_mapping(&policy, &mut create_options).unwrap_err();
+        }
+
+        let mut create_options =
+            ContainerCreateBody::new().with_host_config(HostConfig::new().with_privileged(true));
+        apply_device_mapping(&policy, &mut create_options).unwrap_err();
+
+        // GPU workloads are given the NVIDIA runtime, keeping their own env.
+        let mut labels = BTreeMap::new();
+        labels.insert(DeviceMapping::GPU_LABEL.to_string(), "true".to_string());
+        let mut create_options = ContainerCreateBody::new()
+            .with_labels(labels)
+            .with_env(vec!["NVIDIA_VISIBLE_DEVICES=0".to_string()]);
+        apply_device_mapping(&policy, &mut create_options).unwrap();
+
+        assert_eq!(
+            Some("nvidia"),
+            create_options.host_config().unwrap().runtime()
+        );
+        assert_eq!(
+            Some(
+                &[
+                    "NVIDIA_VISIBLE_DEVICES=0".to_string(),
+                    "NVIDIA_DRIVER_CAPABILITIES=compute,utility".to_string()
+                ][..]
+            ),
+            create_options.env()
+        );
+
+        // Without a policy, any device may be mapped.
+        let mut create_options = ContainerCreateBody::new()
+            .with_host_config(HostConfig::new().with_devices(vec![device("/dev/mem")]));
+        apply_device_mapping(&DeviceMapping::default(), &mut create_options).unwrap();
+    }
+
+    #[test]
+    fn apply_network_policy_works() {
+        let policy: NetworkPolicy = serde_json::from_value(serde_json::json!({
+            "modules": { "uploader": "internet" },
+        }))
+        .unwrap();
+
+        let create_options = |network: &str| -> ContainerCreateBody {
+            serde_json::from_value(serde_json::json!({
+                "HostConfig": { "NetworkMode": network },
+                "NetworkingConfig": {
+                    "EndpointsConfig": { network: { "Aliases": ["alias"] } },
+                },
+            }))
+            .unwrap()
+        };
+
+        // Modules without internet access are moved to the internal network with their aliases.
+        let mut sensor = create_options("azure-iot-edge");
+        apply_network_policy(&policy, "azure-iot-edge", "sensor", &mut sensor).unwrap();
+        let value = serde_json::to_value(&sensor).unwrap();
+        assert_eq!("azure-iot-edge-local", value["HostConfig"]["NetworkMode"]);
+        assert_eq!(
+            serde_json::json!({ "azure-iot-edge-local": { "Aliases": ["alias"] } }),
+            value["NetworkingConfig"]["EndpointsConfig"]
+        );
+        assert!(local_network_endpoint(&policy, "azure-iot-edge", "sensor", &sensor).is_none());
+
+        // Other networks could reach the internet.
+        for network in ["host", "bridge"] {
+            apply_network_policy(
+                &policy,
+                "azure-iot-edge",
+                "sensor",
+                &mut create_options(network),
+            )
+            .unwrap_err();
+        }
+
+        // Modules with internet access keep their network and join the internal one too.
+        let mut uploader = create_options("azure-iot-edge");
+        apply_network_policy(&policy, "azure-iot-edge", "uploader", &mut uploader).unwrap();
+        assert_eq!(
+            Some("azure-iot-edge"),
+            uploader.host_config().unwrap().network_mode()
+        );
+        let endpoint =
+            local_network_endpoint(&policy, "azure-iot-edge", "uploader", &uploader).unwrap();
+        assert_eq!(
+            serde_json::json!({ "Aliases": ["alias"] }),
+            serde_json::to_value(endpoint).unwrap()
+        );
+        assert!(local_network_endpoint(
+            &policy,
+            "azure-iot-edge",
+            "uploader",
+            &create_options("host")
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn recreate_options_works() {
+        let container: docker::models::InlineResponse200 =
+            serde_json::from_value(serde_json::json!({
+                "Id": "0123456789abcdef",
+                "Config": {
+                    "Image": "sensor:1.0",
+                    "Env": ["A=1"],
+                    "Labels": { OWNER_LABEL_KEY: OWNER_LABEL_VALUE },
+                },
+                "HostConfig": {
+                    "NetworkMode": "azure-iot-edge",
+                    "Binds": ["sensor-data:/data"],
+                },
+                "NetworkSettings": {
+                    "Networks": {
+                        "azure-iot-edge": {
+                            "Aliases": ["sensor", "0123456789ab"],
+                            "IPAddress": "172.18.0.5",
+                        },
+                        "azure-iot-edge-local": { "Aliases": ["sensor"] },
+                    },
+                },
+            }))
+            .unwrap();
+
+        let create_options = recreate_options(&container).unwrap();
+        let value = serde_json::to_value(&create_options).unwrap();
+        assert_eq!("sensor:1.0", value["Image"]);
+        assert_eq!(serde_json::json!(["A=1"]), value["Env"]);
+        assert_eq!(
+            serde_json::json!(["sensor-data:/data"]),
+            value["HostConfig"]["Binds"]
+        );
+
+        // Only the endpoint on the network of the network mode is kept, without the
+        // container's ID or address.
+        assert_eq!(
+            serde_json::json!({ "azure-iot-edge": { "Aliases": ["sensor"] } }),
+            value["NetworkingConfig"]["EndpointsConfig"]
+        );
+    }
+
+    #[test]
+    fn short_image_names() {
+        assert_eq!(
+            Some("ubuntu:22.04"),
+            short_image_name("docker.io/library/ubuntu:22.04")
+        );
+        assert_eq!(
+            Some("grafana/grafana:latest"),
+            short_image_name("docker.io/grafana/grafana:latest")
+        );
+        assert_eq!(Some("module:1.0"), short_image_name("localhost/module:1.0"));
+        assert_eq!(
+            None,
+            short_image_name("mcr.microsoft.com/azureiotedge-agent:1.4")
+        );
+    }
+
+    /// A runtime whose container engine is served by `engine`, and the receiver of its
+    /// notifications to the workload manager.
+    fn test_runtime<F, R>(
+        homedir: &std::path::Path,
+        configure: impl FnOnce(&mut Settings),
+        engine: F,
+    ) -> (
+        DockerModuleRuntime<edgelet_test_utils::HandlerConnector>,
+        tokio::sync::mpsc::UnboundedReceiver<ModuleAction>,
+    )
+    where
+        F: Fn(hyper::Request<hyper::Body>) -> R + Send + Sync + 'static,
+        R: std::future::Future<Output = hyper::Response<hyper::Body>> + Send + 'static,
+    {
+        let mut settings: Settings = serde_json::from_value(serde_json::json!({
+            "hostname": "localhost",
+            "homedir": homedir,
+            "agent": {
+                "name": "edgeAgent",
+                "type": "docker",
+                "config": { "image": "mcr.microsoft.com/azureiotedge-agent:1.4" },
+            },
+            "connect": {
+                "workload_uri": "unix:///var/run/iotedge/workload.sock",
+                "management_uri": "unix:///var/run/iotedge/mgmt.sock",
+            },
+            "listen": {
+                "workload_uri": "fd://aziot-edged.workload.socket",
+                "management_uri": "fd://aziot-edged.mgmt.socket",
+            },
+            "moby_runtime": {
+                "uri": "unix:///var/run/docker.sock",
+                "network": "azure-iot-edge",
+            },
+        }))
+        .unwrap();
+        configure(&mut settings);
+
+        let client = DockerApiClient::new(edgelet_test_utils::HandlerConnector::new(engine));
+        let (create_socket_channel, create_socket_channel_rcv) =
+            tokio::sync::mpsc::unbounded_channel();
+        let image_use_data = ImagePruneData::new(homedir, Default::default()).unwrap();
+
+        (
+            DockerModuleRuntime::with_client(
+                client,
+                &settings,
+                create_socket_channel,
+                image_use_data,
+            ),
+            create_socket_channel_rcv,
+        )
+    }
+
+    fn engine_response(
+        status: hyper::StatusCode,
+        body: &serde_json::Value,
+    ) -> hyper::Response<hyper::Body> {
+        hyper::Response::builder()
+            .status(status)
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string().into())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn concurrent_pulls() {
+        struct Engine {
+            pulls: std::sync::Mutex<Vec<String>>,
+            active: std::sync::atomic::AtomicUsize,
+            max_active: std::sync::atomic::AtomicUsize,
+            done: tokio::sync::Semaphore,
+        }
+
+        let homedir = std::env::temp_dir().join(format!("concurrent-pulls-{}", process::id()));
+        std::fs::create_dir_all(&homedir).unwrap();
+
+        let engine = Arc::new(Engine {
+            pulls: std::sync::Mutex::default(),
+            active: std::sync::atomic::AtomicUsize::default(),
+            max_active: std::sync::atomic::AtomicUsize::default(),
+            done: tokio::sync::Semaphore::new(0),
+        });
+        let (runtime, _) = test_runtime(
+            &homedir,
+            |settings| settings.moby_runtime.image_pull.max_concurrent_pulls = Some(1),
+            {
+                let engine = engine.clone();
+                move |req| {
+                    let engine = engine.clone();
+
+                    async move {
+                        if !req.uri().path().ends_with("/images/create") {
+                            // The list of images after a pull.
+                            return engine_response(hyper::StatusCode::OK, &serde_json::json!([]));
+                        }
+
+                        let image =
+                            url::form_urlencoded::parse(req.uri().query().unwrap().as_bytes())
+                                .find(|(key, _)| key == "fromImage")
+                                .unwrap()
+                                .1
+                                .into_owned();
+                        engine.pulls.lock().unwrap().push(image);
+
+                        let active = engine
+                            .active
+                            .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+                            + 1;
+                        engine
+                            .max_active
+                            .fetch_max(active, std::sync::atomic::Ordering::SeqCst);
+
+                        // Each pull runs until the test lets it complete.
+                        engine.done.acquire().await.unwrap().forget();
+                        engine
+                            .active
+                            .fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+
+                        engine_response(
+                            hyper::StatusCode::OK,
+                            &serde_json::json!({ "status": "Pull complete", "id": "a1" }),
+                        )
+                    }
+                }
+            },
+        );
+
+        let hub = DockerConfig::new(
+            "hub:1.5".to_string(),
+            ContainerCreateBody::new(),
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+        let agent = DockerConfig::new(
+            "agent:1.5".to_string(),
+            ContainerCreateBody::new(),
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+
+        let pulls = async {
+            futures::join!(
+                runtime.pull_module("edgeHub", &hub),
+                runtime.pull_module("otherHub", &hub),
+                runtime.pull_module("edgeAgent", &agent),
+            )
+        };
+        let check = async {
+            while engine.pulls.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+
+            // Only one pull runs at a time, and both are reported while it does.
+            let pulls = runtime.pulls();
+            assert_eq!(2, pulls.len());
+            assert!(runtime.pulls.status("edgeHub").is_some());
+            assert!(runtime.pulls.status("edgeAgent").is_some());
+            assert_eq!(
+                runtime.pulls.status("edgeHub"),
+                runtime.pulls.status("otherHub")
+            );
+
+            engine.done.add_permits(2);
+        };
+
+        let ((hub_result, other_result, agent_result), ()) = futures::join!(pulls, check);
+        hub_result.unwrap();
+        other_result.unwrap();
+        agent_result.unwrap();
+
+        // The two modules that use the same image share one pull.
+        let mut pulled = engine.pulls.lock().unwrap().clone();
+        pulled.sort();
+        assert_eq!(vec!["agent:1.5", "hub:1.5"], pulled);
+        assert_eq!(
+            1,
+            engine.max_active.load(std::sync::atomic::Ordering::SeqCst)
+        );
+        assert!(runtime.pulls().is_empty());
+
+        std::fs::remove_dir_all(homedir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn pre_stop_hook() {
+        #[derive(Default)]
+        struct Engine {
+            requests: std::sync::Mutex<Vec<String>>,
+            running: std::sync::atomic::AtomicBool,
+        }
+
+        let homedir = std::env::temp_dir().join(format!("pre-stop-hook-{}", process::id()));
+        std::fs::create_dir_all(&homedir).unwrap();
+
+        let engine = Arc::new(Engine::default());
+        let (runtime, _create_socket_channel_rcv) = test_runtime(
+            &homedir,
+            |settings| {
+                settings.moby_runtime.pre_stop.insert(
+                    "streamProcessor".to_string(),
+                    serde_json::from_value(serde_json::json!({ "signal": "SIGUSR1" })).unwrap(),
+                );
+            },
+            {
+                let engine = engine.clone();
+                move |req| {
+                    let engine = engine.clone();
+
+                    async move {
+                        let action = req.uri().path().rsplit('/').next().unwrap().to_string();
+                        engine.requests.lock().unwrap().push(format!(
+                            "{} {}",
+                            req.method(),
+                            action
+                        ));
+
+                        match action.as_str() {
+                            "json" => engine_response(
+                                hyper::StatusCode::OK,
+                                &serde_json::json!({
+                                    "Name": "/streamProcessor",
+                                    "Image": "sha256:1234",
+                                    "State": {
+                                        "Running": engine
+                                            .running
+                                            .load(std::sync::atomic::Ordering::SeqCst),
+                                    },
+                                }),
+                            ),
+                            "kill" => {
+                                // The module exits when it's signaled.
+                                engine
+                                    .running
+                                    .store(false, std::sync::atomic::Ordering::SeqCst);
+
+                                engine_response(
+                                    hyper::StatusCode::NO_CONTENT,
+                                    &serde_json::json!({}),
+                                )
+                            }
+                            _ => engine_response(
+                                hyper::StatusCode::NO_CONTENT,
+                                &serde_json::json!({}),
+                            ),
+                        }
+                    }
+                }
+            },
+        );
+
+        for (action, request) in [
+            ("stop", "POST stop"),
+            ("restart", "POST restart"),
+            ("remove", "DELETE streamProcessor"),
+        ] {
+            engine.requests.lock().unwrap().clear();
+            engine
+                .running
+                .store(true, std::sync::atomic::Ordering::SeqCst);
+
+            match action {
+                "stop" => runtime.stop("streamProcessor", None).await.unwrap(),
+                "restart" => runtime.restart("streamProcessor").await.unwrap(),
+                _ => ModuleRuntime::remove(&runtime, "streamProcessor")
+                    .await
+                    .unwrap(),
+            }
+
+            // The module is signaled, and stopped once it has exited.
+            let requests = engine.requests.lock().unwrap().clone();
+            let kill = requests.iter().position(|r| r == "POST kill");
+            let request = requests.iter().position(|r| r == request);
+            assert!(kill.is_some(), "{action}: {requests:?}");
+            assert!(kill < request, "{action}: {requests:?}");
+        }
+
+        // Modules without a hook are stopped right away.
+        engine.requests.lock().unwrap().clear();
+        runtime.stop("edgeHub", None).await.unwrap();
+        assert_eq!(
+            vec!["POST stop".to_string()],
+            *engine.requests.lock().unwrap()
+        );
+
+        std::fs::remove_dir_all(homedir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn verify_image_digest() {
+        let homedir = std::env::temp_dir().join(format!("verify-image-digest-{}", process::id()));
+        std::fs::create_dir_all(&homedir).unwrap();
+
+        let engine = |_| async {
+            engine_response(
+                hyper::StatusCode::OK,
+                &serde_json::json!([{
+                    "Id": "sha256:1234",
+                    "RepoTags": ["hub:1.5"],
+                    "RepoDigests": ["hub@sha256:AAAA"],
+                }]),
+            )
+        };
+        let allowlist = |settings: &mut Settings| {
+            settings
+                .moby_runtime
+                .image_digests
+                .allowlist
+                .insert("hub:1.5".to_string(), "sha256:bbbb".to_string());
+        };
+
+        let (runtime, _create_socket_channel_rcv) = test_runtime(&homedir, |_| (), engine);
+
+        // Images that aren't pinned are not checked.
+        runtime.verify_image_digest("hub:1.5", None).await.unwrap();
+
+        let labels: BTreeMap<_, _> = [(
+            crate::digest::IMAGE_DIGEST_LABEL_KEY.to_string(),
+            "sha256:aaaa".to_string(),
+        )]
+        .into_iter()
+        .collect();
+        runtime
+            .verify_image_digest("hub:1.5", Some(&labels))
+            .await
+            .unwrap();
+
+        // The label takes precedence over the allowlist.
+        let (runtime, _create_socket_channel_rcv) = test_runtime(&homedir, allowlist, engine);
+        runtime
+            .verify_image_digest("hub:1.5", Some(&labels))
+            .await
+            .unwrap();
+
+        let err = runtime
+            .verify_image_digest("hub:1.5", None)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<Error>(),
+                Some(Error::ImageDigestMismatch { digest, .. }) if digest == "sha256:bbbb"
+            ),
+            "{err:?}"
+        );
+
+        let (runtime, _create_socket_channel_rcv) = test_runtime(
+            &homedir,
+            |settings| settings.moby_runtime.image_digests.require_pinned = true,
+            engine,
+        );
+        let err = runtime
+            .verify_image_digest("hub:1.5", None)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err.downcast_ref::<Error>(), Some(Error::ImageNotPinned(_))),
+            "{err:?}"
+        );
+        runtime
+            .verify_image_digest("hub@sha256:aaaa", None)
+            .await
+            .unwrap();
+
+        std::fs::remove_dir_all(homedir).unwrap();
+    }
+
     // Compare the total memory returned by the 'total_memory_bytes()' helper method
     // to the value in /proc/meminfo
     #[test]
@@ -0,0 +1,434 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Extracts SBOMs from image archives exported by the container engine.
+//!
+//! Engines that use the containerd image store export images as an OCI image layout,
+//! which includes any attestation manifests and referrer artifacts that were pulled
+//! with the image. The archive can be as large as the image itself, so it is scanned
+//! as it streams in and only small JSON blobs are kept.
+
+use std::collections::BTreeMap;
+
+use edgelet_core::ImageSbom;
+
+const BLOCK_SIZE: usize = 512;
+
+/// Manifests, indexes and SBOM documents are all JSON. Anything larger than this is
+/// assumed to be an image layer and skipped.
+const MAX_BLOB_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Nested indexes deeper than this are ignored.
+const MAX_INDEX_DEPTH: usize = 4;
+
+const SBOM_PREDICATE_TYPES: &[&str] = &[
+    "https://spdx.dev/Document",
+    "https://cyclonedx.org/bom",
+    "https://cyclonedx.org/schema",
+];
+
+const SBOM_ARTIFACT_TYPES: &[&str] = &["application/spdx+json", "application/vnd.cyclonedx+json"];
+
+struct Entry {
+    name: Option<String>,
+    data: Vec<u8>,
+    remaining: u64,
+    padding: usize,
+}
+
+#[derive(Default)]
+pub(crate) struct ArchiveScanner {
+    buf: Vec<u8>,
+    entry: Option<Entry>,
+    files: BTreeMap<String, Vec<u8>>,
+    finished: bool,
+}
+
+impl ArchiveScanner {
+    /// Process the next chunk of the tar archive.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> anyhow::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+
+        self.buf.extend_from_slice(chunk);
+        let mut pos = 0;
+
+        loop {
+            if let Some(entry) = &mut self.entry {
+                #[allow(clippy::cast_possible_truncation)]
+                let len = std::cmp::min(entry.remaining, (self.buf.len() - pos) as u64) as usize;
+                let data = &self.buf[pos..pos + len];
+
+                if entry.name.is_some() {
+                    // Layers are compressed tarballs, so a blob that doesn't start like a
+                    // JSON document can be dropped early.
+                    if entry.data.is_empty() && !data.is_empty() && data[0] != b'{' {
+                        entry.name = None;
+                    } else {
+                        entry.data.extend_from_slice(data);
+                    }
+                }
+
+                pos += len;
+                entry.remaining -= len as u64;
+
+                if entry.remaining > 0 {
+                    break;
+                }
+
+                let padding = std::cmp::min(entry.padding, self.buf.len() - pos);
+                pos += padding;
+                entry.padding -= padding;
+
+                if entry.padding > 0 {
+                    break;
+                }
+
+                let entry = self.entry.take().expect("entry was checked above");
+                if let Some(name) = entry.name {
+                    self.files.insert(name, entry.data);
+                }
+            } else {
+                if self.buf.len() - pos < BLOCK_SIZE {
+                    break;
+                }
+
+                let header = &self.buf[pos..pos + BLOCK_SIZE];
+                pos += BLOCK_SIZE;
+
+                if header.iter().all(|b| *b == 0) {
+                    self.finished = true;
+
+                    break;
+                }
+
+                self.entry = Some(parse_header(header)?);
+            }
+        }
+
+        self.buf.drain(..pos);
+
+        Ok(())
+    }
+
+    /// Find the first SBOM in the scanned archive.
+    pub(crate) fn find_sbom(&self) -> Option<ImageSbom> {
+        let index = self.json("index.json")?;
+
+        self.find_in_index(&index, 0)
+    }
+
+    fn find_in_index(&self, index: &serde_json::Value, depth: usize) -> Option<ImageSbom> {
+        if depth > MAX_INDEX_DEPTH {
+            return None;
+        }
+
+        for descriptor in index["manifests"].as_array()? {
+            let media_type = descriptor["mediaType"].as_str().unwrap_or_default();
+            let annotations = &descriptor["annotations"];
+            let digest = descriptor["digest"].as_str().unwrap_or_default();
+
+            let sbom = if media_type == "application/vnd.oci.image.index.v1+json"
+                || media_type == "application/vnd.docker.distribution.manifest.list.v2+json"
+            {
+                self.blob(digest)
+                    .and_then(|index| self.find_in_index(&index, depth + 1))
+            } else if annotations["vnd.docker.reference.type"] == "attestation-manifest" {
+                let subject = annotations["vnd.docker.reference.digest"]
+                    .as_str()
+                    .map(ToString::to_string);
+
+                self.blob(digest)
+                    .and_then(|manifest| self.find_attestation(&manifest, subject))
+            } else if let Some(artifact_type) = descriptor["artifactType"]
+                .as_str()
+                .filter(|artifact_type| SBOM_ARTIFACT_TYPES.contains(artifact_type))
+            {
+                self.blob(digest)
+                    .and_then(|manifest| self.find_artifact(&manifest, artifact_type))
+            } else {
+                None
+            };
+
+            if sbom.is_some() {
+                return sbom;
+            }
+        }
+
+        None
+    }
+
+    /// BuildKit attestations are in-toto statements stored as layers of an attestation
+    /// manifest. The SBOM is the statement's predicate.
+    fn find_attestation(
+        &self,
+        manifest: &serde_json::Value,
+        subject: Option<String>,
+    ) -> Option<ImageSbom> {
+        manifest["layers"].as_array()?.iter().find_map(|layer| {
+            let predicate_type = layer["annotations"]["in-toto.io/predicate-type"].as_str()?;
+
+            if !SBOM_PREDICATE_TYPES.contains(&predicate_type) {
+                return None;
+            }
+
+            let mut statement = self.blob(layer["digest"].as_str()?)?;
+
+            Some(ImageSbom {
+                format: predicate_type.to_string(),
+                subject: subject.clone(),
+                document: statement["predicate"].take(),
+            })
+        })
+    }
+
+    /// OCI referrer artifacts store the SBOM document as their only layer.
+    fn find_artifact(
+        &self,
+        manifest: &serde_json::Value,
+        artifact_type: &str,
+    ) -> Option<ImageSbom> {
+        let layer = manifest["layers"].as_array()?.first()?;
+        let document = self.blob(layer["digest"].as_str()?)?;
+
+        Some(ImageSbom {
+            format: artifact_type.to_string(),
+            subject: manifest["subject"]["digest"]
+                .as_str()
+                .map(ToString::to_string),
+            document,
+        })
+    }
+
+    fn blob(&self, digest: &str) -> Option<serde_json::Value> {
+        let (algorithm, hash) = digest.split_once(':')?;
+
+        self.json(&format!("blobs/{algorithm}/{hash}"))
+    }
+
+    fn json(&self, name: &str) -> Option<serde_json::Value> {
+        serde_json::from_slice(self.files.get(name)?).ok()
+    }
+}
+
+fn parse_header(header: &[u8]) -> anyhow::Result<Entry> {
+    let name = header_str(&header[0..100]);
+    let prefix = if &header[257..262] == b"ustar" {
+        header_str(&header[345..500])
+    } else {
+        String::new()
+    };
+
+    let name = if prefix.is_empty() {
+        name
+    } else {
+        format!("{prefix}/{name}")
+    };
+    let name = name.trim_start_matches("./").to_string();
+
+    let size = parse_size(&header[124..136])?;
+    let type_flag = header[156];
+
+    let wanted = (type_flag == b'0' || type_flag == 0)
+        && size <= MAX_BLOB_SIZE
+        && (name == "index.json" || name.starts_with("blobs/"));
+
+    #[allow(clippy::cast_possible_truncation)]
+    let padding = ((BLOCK_SIZE as u64 - size % BLOCK_SIZE as u64) % BLOCK_SIZE as u64) as usize;
+
+    Ok(Entry {
+        name: if wanted { Some(name) } else { None },
+        data: Vec::new(),
+        remaining: size,
+        padding,
+    })
+}
+
+fn header_str(field: &[u8]) -> String {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn parse_size(field: &[u8]) -> anyhow::Result<u64> {
+    // Sizes that don't fit in the octal field use big-endian base-256 with the high
+    // bit of the first byte set.
+    if field[0] & 0x80 != 0 {
+        return Ok(field[1..]
+            .iter()
+            .fold(u64::from(field[0] & 0x7f), |size, b| {
+                (size << 8) | u64::from(*b)
+            }));
+    }
+
+    let size = header_str(field);
+    let size = size.trim_matches(|c: char| c == ' ' || c == '\0');
+
+    if size.is_empty() {
+        return Ok(0);
+    }
+
+    u64::from_str_radix(size, 8).map_err(|_| anyhow::anyhow!("invalid tar entry size {size:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArchiveScanner;
+
+    fn tar_entry(archive: &mut Vec<u8>, name: &str, data: &[u8]) {
+        let mut header = [0; super::BLOCK_SIZE];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = b'0';
+        header[257..262].copy_from_slice(b"ustar");
+
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(data);
+        archive.resize(
+            (archive.len() + super::BLOCK_SIZE - 1) / super::BLOCK_SIZE * super::BLOCK_SIZE,
+            0,
+        );
+    }
+
+    fn scan(archive: &[u8], chunk_size: usize) -> ArchiveScanner {
+        let mut scanner = ArchiveScanner::default();
+
+        for chunk in archive.chunks(chunk_size) {
+            scanner.feed(chunk).unwrap();
+        }
+
+        scanner
+    }
+
+    #[test]
+    fn attestation() {
+        let statement = serde_json::json!({
+            "_type": "https://in-toto.io/Statement/v0.1",
+            "predicateType": "https://spdx.dev/Document",
+            "predicate": { "spdxVersion": "SPDX-2.3" },
+        });
+        let attestation_manifest = serde_json::json!({
+            "layers": [
+                {
+                    "digest": "sha256:2222",
+                    "annotations": { "in-toto.io/predicate-type": "https://slsa.dev/provenance/v0.2" },
+                },
+                {
+                    "digest": "sha256:3333",
+                    "annotations": { "in-toto.io/predicate-type": "https://spdx.dev/Document" },
+                },
+            ],
+        });
+        let image_index = serde_json::json!({
+            "manifests": [
+                { "mediaType": "application/vnd.oci.image.manifest.v1+json", "digest": "sha256:aaaa" },
+                {
+                    "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                    "digest": "sha256:1111",
+                    "annotations": {
+                        "vnd.docker.reference.type": "attestation-manifest",
+                        "vnd.docker.reference.digest": "sha256:aaaa",
+                    },
+                },
+            ],
+        });
+        let index = serde_json::json!({
+            "manifests": [
+                { "mediaType": "application/vnd.oci.image.index.v1+json", "digest": "sha256:0000" },
+            ],
+        });
+
+        let mut archive = Vec::new();
+        tar_entry(
+            &mut archive,
+            "blobs/sha256/0000",
+            image_index.to_string().as_bytes(),
+        );
+        tar_entry(
+            &mut archive,
+            "blobs/sha256/1111",
+            attestation_manifest.to_string().as_bytes(),
+        );
+        tar_entry(&mut archive, "blobs/sha256/2222", b"{}");
+        tar_entry(
+            &mut archive,
+            "blobs/sha256/3333",
+            statement.to_string().as_bytes(),
+        );
+        tar_entry(&mut archive, "blobs/sha256/4444", &[0x1f, 0x8b, 0, 0]);
+        tar_entry(&mut archive, "index.json", index.to_string().as_bytes());
+        archive.extend_from_slice(&[0; 2 * super::BLOCK_SIZE]);
+
+        // The result must not depend on how the archive is split into chunks.
+        for chunk_size in [1, 100, 512, archive.len()] {
+            let scanner = scan(&archive, chunk_size);
+
+            // Layers aren't kept.
+            assert!(!scanner.files.contains_key("blobs/sha256/4444"));
+
+            let sbom = scanner.find_sbom().unwrap();
+            assert_eq!("https://spdx.dev/Document", sbom.format);
+            assert_eq!(Some("sha256:aaaa".to_string()), sbom.subject);
+            assert_eq!(
+                serde_json::json!({ "spdxVersion": "SPDX-2.3" }),
+                sbom.document
+            );
+        }
+    }
+
+    #[test]
+    fn referrer_artifact() {
+        let artifact_manifest = serde_json::json!({
+            "artifactType": "application/vnd.cyclonedx+json",
+            "layers": [ { "digest": "sha256:2222" } ],
+            "subject": { "digest": "sha256:aaaa" },
+        });
+        let index = serde_json::json!({
+            "manifests": [
+                {
+                    "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                    "artifactType": "application/vnd.cyclonedx+json",
+                    "digest": "sha256:1111",
+                },
+            ],
+        });
+
+        let mut archive = Vec::new();
+        tar_entry(
+            &mut archive,
+            "blobs/sha256/1111",
+            artifact_manifest.to_string().as_bytes(),
+        );
+        tar_entry(
+            &mut archive,
+            "blobs/sha256/2222",
+            br#"{"bomFormat":"CycloneDX"}"#,
+        );
+        tar_entry(&mut archive, "index.json", index.to_string().as_bytes());
+
+        let sbom = scan(&archive, 64).find_sbom().unwrap();
+        assert_eq!("application/vnd.cyclonedx+json", sbom.format);
+        assert_eq!(Some("sha256:aaaa".to_string()), sbom.subject);
+        assert_eq!(
+            serde_json::json!({ "bomFormat": "CycloneDX" }),
+            sbom.document
+        );
+    }
+
+    #[test]
+    fn no_sbom() {
+        // Archives in the legacy `docker save` format have no OCI index.
+        let mut archive = Vec::new();
+        tar_entry(&mut archive, "manifest.json", b"[]");
+
+        assert!(scan(&archive, 512).find_sbom().is_none());
+    }
+
+    #[test]
+    fn invalid_header() {
+        let mut header = [b'x'; super::BLOCK_SIZE];
+        header[124..136].copy_from_slice(b"not a size!!");
+
+        assert!(ArchiveScanner::default().feed(&header).is_err());
+    }
+}
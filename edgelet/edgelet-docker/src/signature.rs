@@ -0,0 +1,757 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Verification of cosign and Notation signatures of images.
+//!
+//! cosign stores the signatures of an image as the layers of an OCI manifest tagged
+//! `sha256-<digest>.sig` in the image's repository. Each layer is a "simple signing"
+//! payload that names the digest of the signed manifest, and carries the signature of
+//! the payload in an annotation.
+//!
+//! Notation stores each signature as an artifact that refers to the image, found with
+//! the registry's referrers API, or the `sha256-<digest>` tag of registries without
+//! one. Its layer is a JWS envelope whose payload names the signed manifest, with the
+//! certificate chain of the signing key in its unprotected header.
+//!
+//! Keyless cosign signatures and Notation signatures in COSE envelopes are not
+//! supported.
+
+use edgelet_settings::{Proxy, SignaturePolicy};
+
+use crate::registry::RegistryClient;
+
+const OCI_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+const OCI_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+const SIGNATURE_ANNOTATION: &str = "dev.cosignproject.cosign/signature";
+
+const NOTATION_ARTIFACT: &str = "application/vnd.cncf.notary.signature";
+const NOTATION_PAYLOAD: &str = "application/vnd.cncf.notary.payload.v1+json";
+const JWS_ENVELOPE: &str = "application/jose+json";
+
+/// Critical headers of Notation JWS envelopes that are understood.
+const NOTATION_SIGNING_SCHEME: &str = "io.cncf.notary.signingScheme";
+const NOTATION_EXPIRY: &str = "io.cncf.notary.expiry";
+
+/// Registry and repository of an image, with Docker Hub's official images under
+/// `library/` as the registry API names them.
+pub(crate) fn repository(image: &str) -> (&str, String) {
+    let (registry, rest) = crate::pull::split_registry(image);
+
+    let rest = rest.split_once('@').map_or(rest, |(rest, _)| rest);
+    let rest = crate::pull::split_tag(rest).map_or(rest, |(rest, _)| rest);
+
+    if registry == crate::pull::DOCKER_HUB && !rest.contains('/') {
+        (registry, format!("library/{rest}"))
+    } else {
+        (registry, rest.to_string())
+    }
+}
+
+/// Tag of the signatures of a manifest digest, such as `sha256-abcd.sig`.
+pub(crate) fn signature_tag(digest: &str) -> Option<String> {
+    let (algorithm, hex) = digest.split_once(':')?;
+
+    Some(format!("{algorithm}-{hex}.sig"))
+}
+
+/// Tag of the referrers of a manifest digest on registries without the referrers API,
+/// such as `sha256-abcd`.
+pub(crate) fn referrers_tag(digest: &str) -> Option<String> {
+    let (algorithm, hex) = digest.split_once(':')?;
+
+    Some(format!("{algorithm}-{hex}"))
+}
+
+/// Manifest digests of an image, with the registries they were recorded for, out of the
+/// repository digests that the engine recorded when it pulled the image.
+///
+/// An image that was pulled from a mirror or a fallback registry only has the repository
+/// digest of that registry. Manifests are content addressed, so it's the digest that the
+/// image's signatures name too. The image's own registry comes first.
+pub(crate) fn manifest_digests<'a>(
+    image: &str,
+    repo_digests: &'a [String],
+) -> Vec<(&'a str, &'a str)> {
+    let (registry, repo) = repository(image);
+
+    let mut digests: Vec<_> = repo_digests
+        .iter()
+        .filter_map(|repo_digest| {
+            let (name, digest) = repo_digest.split_once('@')?;
+            let (name_registry, name_repo) = repository(name);
+
+            (name_repo == repo).then_some((name_registry, digest))
+        })
+        .collect();
+    digests.sort_by_key(|(name_registry, _)| *name_registry != registry);
+    digests.dedup_by_key(|(name_registry, _)| *name_registry);
+
+    digests
+}
+
+/// Verify that an image has a cosign signature from one of the policy's keys, or a
+/// Notation signature from a certificate that chains to one of its trust roots.
+///
+/// `credentials` are the module's registry credentials, which are also used to read
+/// signatures from private registries.
+pub(crate) async fn verify(
+    image: &str,
+    digest: &str,
+    policy: &SignaturePolicy,
+    credentials: Option<(&str, &str)>,
+    proxy: Option<&Proxy>,
+) -> anyhow::Result<()> {
+    let mut keys = Vec::new();
+    for path in policy.public_keys() {
+        let pem = read(path).await?;
+        keys.push(openssl::pkey::PKey::public_key_from_pem(&pem)?);
+    }
+
+    let mut roots = Vec::new();
+    for path in policy.trust_roots() {
+        let pem = read(path).await?;
+        roots.append(&mut openssl::x509::X509::stack_from_pem(&pem)?);
+    }
+
+    anyhow::ensure!(
+        !keys.is_empty() || !roots.is_empty(),
+        "no public keys or trust roots are trusted for the registry"
+    );
+
+    let (registry, repo) = repository(image);
+    let mut client = RegistryClient::new(registry, &repo, credentials, proxy);
+
+    let mut errors = Vec::new();
+
+    if !keys.is_empty() {
+        match verify_cosign(&mut client, image, digest, &keys).await {
+            Ok(()) => return Ok(()),
+            Err(err) => errors.push(format!("cosign: {err}")),
+        }
+    }
+
+    if !roots.is_empty() {
+        match verify_notation(&mut client, image, digest, &roots).await {
+            Ok(()) => return Ok(()),
+            Err(err) => errors.push(format!("Notation: {err}")),
+        }
+    }
+
+    Err(anyhow::anyhow!("{}", errors.join("; ")))
+}
+
+async fn read(path: &std::path::Path) -> anyhow::Result<Vec<u8>> {
+    tokio::fs::read(path)
+        .await
+        .map_err(|err| anyhow::anyhow!("could not read {}: {}", path.display(), err))
+}
+
+async fn verify_cosign(
+    client: &mut RegistryClient,
+    image: &str,
+    digest: &str,
+    keys: &[openssl::pkey::PKey<openssl::pkey::Public>],
+) -> anyhow::Result<()> {
+    let tag = signature_tag(digest).ok_or_else(|| anyhow::anyhow!("invalid digest {}", digest))?;
+
+    let manifest = client
+        .get(&format!("manifests/{tag}"), OCI_MANIFEST)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("image is not signed"))?;
+    let manifest: SignatureManifest = serde_json::from_slice(&manifest)?;
+
+    for layer in manifest.layers {
+        let signature = match layer.annotations.get(SIGNATURE_ANNOTATION) {
+            Some(signature) => signature,
+            None => continue,
+        };
+
+        let payload = match client
+            .get(&format!("blobs/{}", layer.digest), &layer.media_type)
+            .await?
+        {
+            Some(payload) => payload,
+            None => continue,
+        };
+
+        match verify_layer(keys, &layer.digest, &payload, signature, digest) {
+            Ok(()) => return Ok(()),
+            Err(err) => log::debug!("Ignoring signature of {}: {}", image, err),
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "image has no valid signature from a trusted key"
+    ))
+}
+
+async fn verify_notation(
+    client: &mut RegistryClient,
+    image: &str,
+    digest: &str,
+    roots: &[openssl::x509::X509],
+) -> anyhow::Result<()> {
+    let referrers = match client
+        .get(
+            &format!("referrers/{digest}?artifactType={NOTATION_ARTIFACT}"),
+            OCI_INDEX,
+        )
+        .await?
+    {
+        Some(referrers) => referrers,
+        None => {
+            let tag = referrers_tag(digest)
+                .ok_or_else(|| anyhow::anyhow!("invalid digest {}", digest))?;
+
+            client
+                .get(&format!("manifests/{tag}"), OCI_INDEX)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("image is not signed"))?
+        }
+    };
+    let referrers: Referrers = serde_json::from_slice(&referrers)?;
+
+    // Registries may ignore the artifact type filter, and the fallback tag lists every
+    // referrer.
+    let signatures = referrers
+        .manifests
+        .iter()
+        .filter(|referrer| referrer.artifact_type.as_deref() == Some(NOTATION_ARTIFACT));
+
+    for signature in signatures {
+        let manifest = match client
+            .get(&format!("manifests/{}", signature.digest), OCI_MANIFEST)
+            .await?
+        {
+            Some(manifest) => manifest,
+            None => continue,
+        };
+        if blob_digest(&manifest) != signature.digest {
+            log::debug!(
+                "Ignoring signature manifest of {} that doesn't match its digest",
+                image
+            );
+            continue;
+        }
+        let manifest: SignatureManifest = serde_json::from_slice(&manifest)?;
+
+        for layer in manifest.layers {
+            if layer.media_type != JWS_ENVELOPE {
+                log::debug!(
+                    "Ignoring signature of {} in unsupported envelope {}",
+                    image,
+                    layer.media_type
+                );
+                continue;
+            }
+
+            let envelope = match client
+                .get(&format!("blobs/{}", layer.digest), &layer.media_type)
+                .await?
+            {
+                Some(envelope) => envelope,
+                None => continue,
+            };
+
+            let result = if blob_digest(&envelope) == layer.digest {
+                verify_jws(roots, &envelope, digest)
+            } else {
+                Err(anyhow::anyhow!("envelope does not match its digest"))
+            };
+            match result {
+                Ok(()) => return Ok(()),
+                Err(err) => log::debug!("Ignoring signature of {}: {}", image, err),
+            }
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "image has no valid signature from a trusted certificate"
+    ))
+}
+
+fn blob_digest(blob: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(openssl::sha::sha256(blob)))
+}
+
+/// Verify a Notation JWS envelope: its payload must name the image's digest, its
+/// certificate chain must lead to one of the trust roots, and it must be signed by the
+/// leaf certificate's key.
+fn verify_jws(roots: &[openssl::x509::X509], envelope: &[u8], digest: &str) -> anyhow::Result<()> {
+    let envelope: JwsEnvelope = serde_json::from_slice(envelope)?;
+
+    let url_safe = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    let protected: JwsProtected =
+        serde_json::from_slice(&base64::Engine::decode(&url_safe, &envelope.protected)?)?;
+    anyhow::ensure!(
+        protected.cty == NOTATION_PAYLOAD,
+        "unsupported payload type {}",
+        protected.cty
+    );
+    if let Some(unknown) = protected
+        .crit
+        .iter()
+        .find(|header| *header != NOTATION_SIGNING_SCHEME && *header != NOTATION_EXPIRY)
+    {
+        return Err(anyhow::anyhow!("unsupported critical header {}", unknown));
+    }
+    if let Some(expiry) = &protected.expiry {
+        let expiry = chrono::DateTime::parse_from_rfc3339(expiry)?;
+        anyhow::ensure!(
+            expiry > chrono::Utc::now(),
+            "signature expired at {}",
+            expiry
+        );
+    }
+
+    let payload: NotationPayload =
+        serde_json::from_slice(&base64::Engine::decode(&url_safe, &envelope.payload)?)?;
+    anyhow::ensure!(
+        payload.target_artifact.digest.eq_ignore_ascii_case(digest),
+        "signature is for {}",
+        payload.target_artifact.digest
+    );
+
+    let standard = base64::engine::general_purpose::STANDARD;
+    let mut certs = envelope
+        .header
+        .x5c
+        .iter()
+        .map(|cert| {
+            Ok(openssl::x509::X509::from_der(&base64::Engine::decode(
+                &standard, cert,
+            )?)?)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .into_iter();
+    let leaf = certs
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("envelope has no certificate"))?;
+
+    let mut store = openssl::x509::store::X509StoreBuilder::new()?;
+    for root in roots {
+        store.add_cert(root.clone())?;
+    }
+    let store = store.build();
+
+    let mut chain = openssl::stack::Stack::new()?;
+    for cert in certs {
+        chain.push(cert)?;
+    }
+
+    let mut context = openssl::x509::X509StoreContext::new()?;
+    let trusted = context.init(&store, &leaf, &chain, |context| {
+        Ok(if context.verify_cert()? {
+            Ok(())
+        } else {
+            Err(context.error())
+        })
+    })?;
+    trusted.map_err(|err| anyhow::anyhow!("certificate is not trusted: {}", err))?;
+
+    let signature = base64::Engine::decode(&url_safe, &envelope.signature)?;
+    let signed = format!("{}.{}", envelope.protected, envelope.payload);
+    anyhow::ensure!(
+        verify_jws_signature(
+            &protected.alg,
+            &leaf.public_key()?,
+            signed.as_bytes(),
+            &signature
+        )?,
+        "signature is not from the envelope's certificate"
+    );
+
+    Ok(())
+}
+
+/// Verify a JWS signature made with one of the algorithms that Notation signs with.
+fn verify_jws_signature(
+    alg: &str,
+    key: &openssl::pkey::PKey<openssl::pkey::Public>,
+    signed: &[u8],
+    signature: &[u8],
+) -> anyhow::Result<bool> {
+    let (pss, digest) = match alg {
+        "PS256" => (true, openssl::hash::MessageDigest::sha256()),
+        "PS384" => (true, openssl::hash::MessageDigest::sha384()),
+        "PS512" => (true, openssl::hash::MessageDigest::sha512()),
+        "ES256" => (false, openssl::hash::MessageDigest::sha256()),
+        "ES384" => (false, openssl::hash::MessageDigest::sha384()),
+        "ES512" => (false, openssl::hash::MessageDigest::sha512()),
+        _ => return Err(anyhow::anyhow!("unsupported signature algorithm {}", alg)),
+    };
+
+    let mut verifier = openssl::sign::Verifier::new(digest, key)?;
+
+    if pss {
+        verifier.set_rsa_padding(openssl::rsa::Padding::PKCS1_PSS)?;
+        verifier.set_rsa_pss_saltlen(openssl::sign::RsaPssSaltlen::DIGEST_LENGTH)?;
+        verifier.set_rsa_mgf1_md(digest)?;
+
+        Ok(verifier.verify_oneshot(signature, signed).unwrap_or(false))
+    } else {
+        // JWS ECDSA signatures are the concatenated r and s, rather than DER.
+        anyhow::ensure!(signature.len() % 2 == 0, "invalid ECDSA signature");
+        let (r, s) = signature.split_at(signature.len() / 2);
+        let signature = openssl::ecdsa::EcdsaSig::from_private_components(
+            openssl::bn::BigNum::from_slice(r)?,
+            openssl::bn::BigNum::from_slice(s)?,
+        )?
+        .to_der()?;
+
+        Ok(verifier.verify_oneshot(&signature, signed).unwrap_or(false))
+    }
+}
+
+/// Verify one signature layer: its payload must be the blob it claims to be, name the
+/// image's digest, and be signed by one of the keys.
+fn verify_layer(
+    keys: &[openssl::pkey::PKey<openssl::pkey::Public>],
+    layer_digest: &str,
+    payload: &[u8],
+    signature: &str,
+    digest: &str,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        blob_digest(payload) == layer_digest,
+        "payload does not match its digest"
+    );
+
+    let signed: SimpleSigning = serde_json::from_slice(payload)?;
+    anyhow::ensure!(
+        signed
+            .critical
+            .image
+            .docker_manifest_digest
+            .eq_ignore_ascii_case(digest),
+        "signature is for {}",
+        signed.critical.image.docker_manifest_digest
+    );
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let signature = base64::Engine::decode(&engine, signature)?;
+
+    for key in keys {
+        let mut verifier =
+            openssl::sign::Verifier::new(openssl::hash::MessageDigest::sha256(), key)?;
+
+        if verifier
+            .verify_oneshot(&signature, payload)
+            .unwrap_or(false)
+        {
+            return Ok(());
+        }
+    }
+
+    Err(anyhow::anyhow!("signature is not from a trusted key"))
+}
+
+#[derive(serde::Deserialize)]
+struct SignatureManifest {
+    #[serde(default)]
+    layers: Vec<Descriptor>,
+}
+
+#[derive(serde::Deserialize)]
+struct Referrers {
+    #[serde(default)]
+    manifests: Vec<Descriptor>,
+}
+
+#[derive(serde::Deserialize)]
+struct Descriptor {
+    #[serde(rename = "mediaType")]
+    media_type: String,
+    digest: String,
+
+    #[serde(rename = "artifactType")]
+    artifact_type: Option<String>,
+
+    #[serde(default)]
+    annotations: std::collections::BTreeMap<String, String>,
+}
+
+#[derive(serde::Deserialize)]
+struct SimpleSigning {
+    critical: Critical,
+}
+
+#[derive(serde::Deserialize)]
+struct Critical {
+    image: SignedImage,
+}
+
+#[derive(serde::Deserialize)]
+struct SignedImage {
+    #[serde(rename = "docker-manifest-digest")]
+    docker_manifest_digest: String,
+}
+
+#[derive(serde::Deserialize)]
+struct JwsEnvelope {
+    payload: String,
+    protected: String,
+    header: JwsHeader,
+    signature: String,
+}
+
+#[derive(serde::Deserialize)]
+struct JwsHeader {
+    #[serde(default)]
+    x5c: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct JwsProtected {
+    alg: String,
+    cty: String,
+
+    #[serde(default)]
+    crit: Vec<String>,
+
+    #[serde(rename = "io.cncf.notary.expiry")]
+    expiry: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct NotationPayload {
+    #[serde(rename = "targetArtifact")]
+    target_artifact: TargetArtifact,
+}
+
+#[derive(serde::Deserialize)]
+struct TargetArtifact {
+    digest: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        manifest_digests, referrers_tag, repository, signature_tag, verify_jws, verify_layer,
+    };
+
+    #[test]
+    fn repositories() {
+        assert_eq!(
+            ("docker.io", "library/ubuntu".to_string()),
+            repository("ubuntu:22.04")
+        );
+        assert_eq!(
+            ("docker.io", "grafana/grafana".to_string()),
+            repository("grafana/grafana")
+        );
+        assert_eq!(
+            ("localhost:5000", "module".to_string()),
+            repository("localhost:5000/module@sha256:abcd")
+        );
+    }
+
+    #[test]
+    fn tags() {
+        assert_eq!(
+            Some("sha256-abcd.sig".to_string()),
+            signature_tag("sha256:abcd")
+        );
+        assert_eq!(None, signature_tag("abcd"));
+
+        assert_eq!(
+            Some("sha256-abcd".to_string()),
+            referrers_tag("sha256:abcd")
+        );
+        assert_eq!(None, referrers_tag("abcd"));
+    }
+
+    #[test]
+    fn digests() {
+        let repo_digests = vec![
+            "mirror.contoso.com/library/ubuntu@sha256:abcd".to_string(),
+            "ubuntu@sha256:abcd".to_string(),
+        ];
+
+        assert_eq!(
+            vec![
+                ("docker.io", "sha256:abcd"),
+                ("mirror.contoso.com", "sha256:abcd")
+            ],
+            manifest_digests("docker.io/library/ubuntu:22.04", &repo_digests)
+        );
+        assert_eq!(
+            vec![
+                ("mirror.contoso.com", "sha256:abcd"),
+                ("docker.io", "sha256:abcd")
+            ],
+            manifest_digests("mirror.contoso.com/library/ubuntu", &repo_digests)
+        );
+        assert!(manifest_digests("grafana/grafana", &repo_digests).is_empty());
+
+        // An image pulled from a fallback registry only has that registry's digest.
+        let repo_digests = vec!["parent:443/azureiotedge-agent@sha256:abcd".to_string()];
+        assert_eq!(
+            vec![("parent:443", "sha256:abcd")],
+            manifest_digests("mcr.microsoft.com/azureiotedge-agent:1.5", &repo_digests)
+        );
+    }
+
+    #[test]
+    fn layers() {
+        let key = openssl::ec::EcKey::generate(
+            &openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1).unwrap(),
+        )
+        .unwrap();
+        let key = openssl::pkey::PKey::from_ec_key(key).unwrap();
+        let public_key =
+            openssl::pkey::PKey::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap();
+
+        let payload = br#"{"critical":{"identity":{"docker-reference":"example.azurecr.io/module"},"image":{"docker-manifest-digest":"sha256:abcd"},"type":"cosign container image signature"},"optional":null}"#;
+        let layer_digest = format!("sha256:{}", hex::encode(openssl::sha::sha256(payload)));
+
+        let mut signer =
+            openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), &key).unwrap();
+        let signature = signer.sign_oneshot_to_vec(payload).unwrap();
+        let engine = base64::engine::general_purpose::STANDARD;
+        let signature = base64::Engine::encode(&engine, signature);
+
+        let keys = [public_key];
+
+        verify_layer(&keys, &layer_digest, payload, &signature, "sha256:abcd").unwrap();
+
+        // Signature of another image.
+        verify_layer(&keys, &layer_digest, payload, &signature, "sha256:1234").unwrap_err();
+
+        // Payload that isn't the layer's blob.
+        verify_layer(&keys, "sha256:1234", payload, &signature, "sha256:abcd").unwrap_err();
+
+        // Signature from an untrusted key.
+        let other = openssl::ec::EcKey::generate(
+            &openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1).unwrap(),
+        )
+        .unwrap();
+        let other =
+            openssl::pkey::PKey::public_key_from_pem(&other.public_key_to_pem().unwrap()).unwrap();
+        verify_layer(&[other], &layer_digest, payload, &signature, "sha256:abcd").unwrap_err();
+    }
+
+    fn certificate(
+        name: &str,
+        key: &openssl::pkey::PKey<openssl::pkey::Private>,
+        issuer: Option<(
+            &openssl::x509::X509,
+            &openssl::pkey::PKey<openssl::pkey::Private>,
+        )>,
+    ) -> openssl::x509::X509 {
+        let mut subject = openssl::x509::X509NameBuilder::new().unwrap();
+        subject.append_entry_by_text("CN", name).unwrap();
+        let subject = subject.build();
+
+        let mut builder = openssl::x509::X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&subject).unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder
+            .set_not_before(&openssl::asn1::Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&openssl::asn1::Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+
+        let (issuer_name, issuer_key) = match issuer {
+            Some((issuer, issuer_key)) => (issuer.subject_name().to_owned().unwrap(), issuer_key),
+            None => {
+                let constraints = openssl::x509::extension::BasicConstraints::new()
+                    .critical()
+                    .ca()
+                    .build()
+                    .unwrap();
+                builder.append_extension(constraints).unwrap();
+
+                (subject, key)
+            }
+        };
+        builder.set_issuer_name(&issuer_name).unwrap();
+        builder
+            .sign(issuer_key, openssl::hash::MessageDigest::sha256())
+            .unwrap();
+
+        builder.build()
+    }
+
+    fn ec_key() -> openssl::pkey::PKey<openssl::pkey::Private> {
+        let group =
+            openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1).unwrap();
+
+        openssl::pkey::PKey::from_ec_key(openssl::ec::EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn jws(
+        key: &openssl::pkey::PKey<openssl::pkey::Private>,
+        chain: &[&openssl::x509::X509],
+        digest: &str,
+    ) -> Vec<u8> {
+        let url_safe = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let standard = base64::engine::general_purpose::STANDARD;
+
+        let protected = serde_json::json!({
+            "alg": "ES256",
+            "cty": "application/vnd.cncf.notary.payload.v1+json",
+            "crit": ["io.cncf.notary.signingScheme"],
+            "io.cncf.notary.signingScheme": "notary.x509",
+            "io.cncf.notary.signingTime": "2024-01-01T00:00:00Z",
+        });
+        let protected = base64::Engine::encode(&url_safe, protected.to_string());
+        let payload = serde_json::json!({
+            "targetArtifact": {
+                "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                "digest": digest,
+                "size": 1234,
+            },
+        });
+        let payload = base64::Engine::encode(&url_safe, payload.to_string());
+
+        let mut signer =
+            openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), key).unwrap();
+        let signature = signer
+            .sign_oneshot_to_vec(format!("{protected}.{payload}").as_bytes())
+            .unwrap();
+
+        // JWS ECDSA signatures are the fixed-length r and s.
+        let signature = openssl::ecdsa::EcdsaSig::from_der(&signature).unwrap();
+        let mut raw = signature.r().to_vec_padded(32).unwrap();
+        raw.append(&mut signature.s().to_vec_padded(32).unwrap());
+
+        let x5c: Vec<_> = chain
+            .iter()
+            .map(|cert| base64::Engine::encode(&standard, cert.to_der().unwrap()))
+            .collect();
+
+        serde_json::json!({
+            "payload": payload,
+            "protected": protected,
+            "header": { "x5c": x5c },
+            "signature": base64::Engine::encode(&url_safe, raw),
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[test]
+    fn notation() {
+        let root_key = ec_key();
+        let root = certificate("root", &root_key, None);
+        let leaf_key = ec_key();
+        let leaf = certificate("signer", &leaf_key, Some((&root, &root_key)));
+
+        let envelope = jws(&leaf_key, &[&leaf], "sha256:abcd");
+        verify_jws(&[root.clone()], &envelope, "sha256:abcd").unwrap();
+
+        // Signature of another image.
+        verify_jws(&[root.clone()], &envelope, "sha256:1234").unwrap_err();
+
+        // Certificate that doesn't chain to a trust root.
+        let other_key = ec_key();
+        let other = certificate("other", &other_key, None);
+        verify_jws(&[other], &envelope, "sha256:abcd").unwrap_err();
+
+        // Signature from a key other than the certificate's.
+        let envelope = jws(&ec_key(), &[&leaf], "sha256:abcd");
+        verify_jws(&[root], &envelope, "sha256:abcd").unwrap_err();
+    }
+}
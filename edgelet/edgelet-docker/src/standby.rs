@@ -0,0 +1,143 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use docker::models::ContainerCreateBody;
+
+/// The create options of modules with warm standbys, from which a new standby is created
+/// after a module fails over to its standby.
+///
+/// The options are saved under the home directory, so that a module that fails over
+/// after the daemon restarted still gets a new standby, without waiting for Edge Agent
+/// to create the module again.
+#[derive(Clone)]
+pub(crate) struct StandbyOptions {
+    dir: PathBuf,
+    options: Arc<Mutex<HashMap<String, ContainerCreateBody>>>,
+}
+
+impl StandbyOptions {
+    pub fn new(homedir: &Path) -> Self {
+        StandbyOptions {
+            dir: homedir.join("standby"),
+            options: Arc::default(),
+        }
+    }
+
+    pub fn get(&self, module: &str) -> Option<ContainerCreateBody> {
+        if let Some(options) = self.lock().get(module) {
+            return Some(options.clone());
+        }
+
+        let path = self.path(module);
+        let options: ContainerCreateBody = match std::fs::read(&path) {
+            Ok(options) => match serde_json::from_slice(&options) {
+                Ok(options) => options,
+                Err(err) => {
+                    log::warn!(
+                        "Ignoring invalid standby options in {}: {}",
+                        path.display(),
+                        err
+                    );
+
+                    return None;
+                }
+            },
+            Err(err) => {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!(
+                        "Failed to read standby options of module {}: {}",
+                        module,
+                        err
+                    );
+                }
+
+                return None;
+            }
+        };
+
+        self.lock().insert(module.to_string(), options.clone());
+
+        Some(options)
+    }
+
+    pub fn insert(&self, module: &str, options: ContainerCreateBody) {
+        if let Err(err) = self.save(module, &options) {
+            log::warn!(
+                "Failed to save standby options of module {}: {}",
+                module,
+                err
+            );
+        }
+
+        self.lock().insert(module.to_string(), options);
+    }
+
+    pub fn remove(&self, module: &str) {
+        self.lock().remove(module);
+
+        match std::fs::remove_file(self.path(module)) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => log::warn!(
+                "Failed to remove standby options of module {}: {}",
+                module,
+                err
+            ),
+        }
+    }
+
+    /// Options are replaced by a rename, so that a crash while they're written leaves
+    /// the previous options.
+    fn save(&self, module: &str, options: &ContainerCreateBody) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+
+        let temp = self.dir.join(format!(".{module}.json.tmp"));
+        std::fs::write(&temp, serde_json::to_vec(options)?)?;
+        std::fs::rename(&temp, self.path(module))
+    }
+
+    fn path(&self, module: &str) -> PathBuf {
+        self.dir.join(format!("{module}.json"))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, ContainerCreateBody>> {
+        self.options.lock().expect("standby options lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use docker::models::ContainerCreateBody;
+
+    use super::StandbyOptions;
+
+    #[test]
+    fn options_survive_restart() {
+        let homedir =
+            std::env::temp_dir().join(format!("standby-options-test-{}", std::process::id()));
+
+        let options = StandbyOptions::new(&homedir);
+        options.insert(
+            "edgeHub",
+            ContainerCreateBody::new().with_image("edgehub:1.4".to_string()),
+        );
+
+        // A new instance, as after the daemon restarted, reads the saved options.
+        let restarted = StandbyOptions::new(&homedir);
+        assert_eq!(
+            Some("edgehub:1.4"),
+            restarted
+                .get("edgeHub")
+                .as_ref()
+                .and_then(ContainerCreateBody::image)
+        );
+
+        restarted.remove("edgeHub");
+        assert!(StandbyOptions::new(&homedir).get("edgeHub").is_none());
+
+        std::fs::remove_dir_all(&homedir).unwrap();
+    }
+}
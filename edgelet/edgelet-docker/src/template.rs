@@ -0,0 +1,163 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::BTreeMap;
+
+use edgelet_settings::{DockerConfig, ModuleSpec};
+
+/// Replace the `{{name}}` placeholders in `s` with the variables they name. Placeholders
+/// for unknown variables are left as they are, since modules may use the same syntax
+/// for their own templates.
+pub(crate) fn substitute(s: &str, variables: &BTreeMap<String, String>) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+
+        result.push_str(&rest[..start]);
+
+        match variables.get(rest[start + 2..end - 2].trim()) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..end]),
+        }
+
+        rest = &rest[end..];
+    }
+
+    result.push_str(rest);
+
+    result
+}
+
+/// Substitute variables in the strings of a JSON value. Object keys are left alone.
+fn substitute_value(value: &mut serde_json::Value, variables: &BTreeMap<String, String>) {
+    match value {
+        serde_json::Value::String(s) => {
+            if s.contains("{{") {
+                *s = substitute(s, variables);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                substitute_value(item, variables);
+            }
+        }
+        serde_json::Value::Object(properties) => {
+            for property in properties.values_mut() {
+                substitute_value(property, variables);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Substitute variables in a module's env and createOptions. `module_id` is always
+/// available as the module's name.
+pub(crate) fn apply(
+    module: &mut ModuleSpec<DockerConfig>,
+    variables: &BTreeMap<String, String>,
+) -> anyhow::Result<()> {
+    let mut variables = variables.clone();
+    variables.insert("module_id".to_string(), module.name().to_string());
+
+    for value in module.env_mut().values_mut() {
+        if value.contains("{{") {
+            *value = substitute(value, &variables);
+        }
+    }
+
+    let mut create_options = serde_json::to_value(module.config().create_options())?;
+    substitute_value(&mut create_options, &variables);
+    *module.config_mut().create_options_mut() = serde_json::from_value(create_options)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::substitute;
+
+    fn variables() -> BTreeMap<String, String> {
+        [
+            ("device_id", "device1"),
+            ("hub_name", "hub.azure-devices.net"),
+            ("site_tag", "plant-7"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn substitutes() {
+        let variables = variables();
+
+        assert_eq!(
+            "device1@hub.azure-devices.net",
+            substitute("{{device_id}}@{{ hub_name }}", &variables)
+        );
+        assert_eq!("plant-7/", substitute("{{site_tag}}/", &variables));
+        assert_eq!("no variables", substitute("no variables", &variables));
+    }
+
+    #[test]
+    fn unknown_left_alone() {
+        let variables = variables();
+
+        assert_eq!(
+            "{{ .Name }} on device1",
+            substitute("{{ .Name }} on {{device_id}}", &variables)
+        );
+        assert_eq!("{{device_id", substitute("{{device_id", &variables));
+        assert_eq!("device1 }}", substitute("{{device_id}} }}", &variables));
+    }
+
+    #[test]
+    fn apply() {
+        let create_options = serde_json::from_value(serde_json::json!({
+            "Hostname": "{{device_id}}-{{module_id}}",
+            "Labels": { "{{site_tag}}": "{{site_tag}}" },
+            "Cmd": ["--hub", "{{hub_name}}"],
+        }))
+        .unwrap();
+        let config = edgelet_settings::DockerConfig::new(
+            "image:1".to_string(),
+            create_options,
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+
+        let env = [("SITE".to_string(), "{{site_tag}}".to_string())]
+            .into_iter()
+            .collect();
+        let mut module = edgelet_settings::ModuleSpec::new(
+            "sensor".to_string(),
+            "docker".to_string(),
+            config,
+            env,
+            edgelet_settings::module::ImagePullPolicy::default(),
+        )
+        .unwrap();
+
+        super::apply(&mut module, &variables()).unwrap();
+
+        assert_eq!("plant-7", module.env()["SITE"]);
+
+        let create_options = serde_json::to_value(module.config().create_options()).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "Hostname": "device1-sensor",
+                "Labels": { "{{site_tag}}": "plant-7" },
+                "Cmd": ["--hub", "hub.azure-devices.net"],
+            }),
+            create_options
+        );
+    }
+}
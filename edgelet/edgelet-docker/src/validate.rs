@@ -0,0 +1,256 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Checks of module create options against version 1.34 of the Docker Engine API, which
+//! the client speaks, so that mistakes are reported with the field at fault instead of as
+//! the engine's generic "400 Bad Request".
+
+use docker::models::{ContainerCreateBody, HostConfig};
+
+/// Docker rejects memory limits below 6 MB.
+const MIN_MEMORY: i64 = 6 * 1024 * 1024;
+
+const BIND_OPTIONS: &[&str] = &[
+    "ro",
+    "rw",
+    "z",
+    "Z",
+    "nocopy",
+    "consistent",
+    "cached",
+    "delegated",
+    "shared",
+    "rshared",
+    "slave",
+    "rslave",
+    "private",
+    "rprivate",
+];
+
+const MOUNT_TYPES: &[&str] = &["bind", "volume", "tmpfs", "npipe"];
+
+/// Problems with create options, each naming the field at fault.
+pub(crate) fn create_options(create_options: &ContainerCreateBody) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    for (i, env) in create_options.env().unwrap_or_default().iter().enumerate() {
+        if env.is_empty() || env.starts_with('=') {
+            errors.push(format!("Env[{i}] {env:?} has no variable name"));
+        }
+    }
+
+    if let Some(volumes) = create_options.volumes() {
+        for path in volumes.keys() {
+            if !path.starts_with('/') {
+                errors.push(format!("Volumes[{path:?}] is not absolute"));
+            }
+        }
+    }
+
+    if let Some(host_config) = create_options.host_config() {
+        self::host_config(host_config, &mut errors);
+    }
+
+    errors
+}
+
+fn host_config(host_config: &HostConfig, errors: &mut Vec<String>) {
+    for (i, bind) in host_config.binds().unwrap_or_default().iter().enumerate() {
+        if let Err(err) = self::bind(bind) {
+            errors.push(format!("HostConfig.Binds[{i}] {err}"));
+        }
+    }
+
+    for (i, mount) in host_config.mounts().unwrap_or_default().iter().enumerate() {
+        let mount_type = mount._type().unwrap_or("volume");
+        if !MOUNT_TYPES.contains(&mount_type) {
+            errors.push(format!(
+                "HostConfig.Mounts[{i}].Type {mount_type:?} is not one of {MOUNT_TYPES:?}"
+            ));
+        }
+
+        match mount.target() {
+            Some(target) if target.starts_with('/') => (),
+            Some(_) => errors.push(format!("HostConfig.Mounts[{i}].Target is not absolute")),
+            None => errors.push(format!("HostConfig.Mounts[{i}].Target is missing")),
+        }
+
+        if mount_type == "bind" && !mount.source().map_or(false, |s| s.starts_with('/')) {
+            errors.push(format!(
+                "HostConfig.Mounts[{i}].Source of a bind mount is not absolute"
+            ));
+        }
+    }
+
+    let memory = host_config.memory().filter(|memory| *memory > 0);
+    if let Some(memory) = memory {
+        if memory < MIN_MEMORY {
+            errors.push(format!(
+                "HostConfig.Memory of {memory} bytes is below the minimum of 6 MB"
+            ));
+        }
+    }
+
+    if let (Some(memory), Some(reservation)) = (memory, host_config.memory_reservation()) {
+        if reservation > memory {
+            errors.push(format!(
+                "HostConfig.MemoryReservation of {reservation} bytes is above HostConfig.Memory of {memory} bytes"
+            ));
+        }
+    }
+
+    let nano_cpus = host_config.nano_cp_us().filter(|nano_cpus| *nano_cpus > 0);
+    let cpu_quota = host_config.cpu_quota().filter(|quota| *quota > 0);
+    let cpu_period = host_config.cpu_period().filter(|period| *period > 0);
+
+    if nano_cpus.is_some() && (cpu_quota.is_some() || cpu_period.is_some()) {
+        errors.push(
+            "HostConfig.NanoCPUs can't be set with HostConfig.CpuQuota or HostConfig.CpuPeriod"
+                .to_string(),
+        );
+    }
+
+    if let Some(period) = cpu_period {
+        if !(1000..=1_000_000).contains(&period) {
+            errors.push(format!(
+                "HostConfig.CpuPeriod of {period} is not between 1000 and 1000000 microseconds"
+            ));
+        }
+    }
+
+    if let Some(quota) = cpu_quota {
+        if quota < 1000 {
+            errors.push(format!(
+                "HostConfig.CpuQuota of {quota} is below the minimum of 1000 microseconds"
+            ));
+        }
+    }
+
+    for (i, dns) in host_config.dns().unwrap_or_default().iter().enumerate() {
+        if dns.parse::<std::net::IpAddr>().is_err() {
+            errors.push(format!("HostConfig.Dns[{i}] {dns:?} is not an IP address"));
+        }
+    }
+
+    for (i, host) in host_config
+        .extra_hosts()
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+    {
+        match host.split_once(':') {
+            Some((name, address))
+                if !name.is_empty()
+                    && (address == "host-gateway"
+                        || address.parse::<std::net::IpAddr>().is_ok()) => {}
+            _ => errors.push(format!(
+                "HostConfig.ExtraHosts[{i}] {host:?} is not of the form \"hostname:IP\""
+            )),
+        }
+    }
+}
+
+/// Binds are "source:destination[:options]", where the source is an absolute path or the
+/// name of a volume.
+fn bind(bind: &str) -> Result<(), String> {
+    let mut parts = bind.split(':');
+
+    let (source, destination) = match (parts.next(), parts.next()) {
+        (Some(source), Some(destination)) if !source.is_empty() => (source, destination),
+        _ => {
+            return Err(format!(
+                "{bind:?} is not of the form \"source:destination\""
+            ))
+        }
+    };
+
+    if !source.starts_with('/') && !is_volume_name(source) {
+        return Err(format!(
+            "source {source:?} is neither an absolute path nor a volume name"
+        ));
+    }
+
+    if !destination.starts_with('/') {
+        return Err(format!("destination {destination:?} is not absolute"));
+    }
+
+    if let Some(options) = parts.next() {
+        for option in options.split(',') {
+            if !BIND_OPTIONS.contains(&option) {
+                return Err(format!("option {option:?} is not one of {BIND_OPTIONS:?}"));
+            }
+        }
+    }
+
+    if parts.next().is_some() {
+        return Err(format!(
+            "{bind:?} is not of the form \"source:destination[:options]\""
+        ));
+    }
+
+    Ok(())
+}
+
+fn is_volume_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars.next().map_or(false, |c| c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use docker::models::{ContainerCreateBody, HostConfig};
+
+    #[test]
+    fn binds() {
+        assert!(super::bind("/data:/data").is_ok());
+        assert!(super::bind("edgehub-data:/data:ro,Z").is_ok());
+
+        assert_eq!(
+            Err("destination \"data\" is not absolute".to_string()),
+            super::bind("/data:data")
+        );
+        assert!(super::bind("/data").is_err());
+        assert!(super::bind("../data:/data").is_err());
+        assert!(super::bind("/data:/data:readonly").is_err());
+        assert!(super::bind("/data:/data:ro:rw").is_err());
+    }
+
+    #[test]
+    fn create_options() {
+        let create_options: ContainerCreateBody = serde_json::from_str(
+            r#"{
+                "Env": ["A=1", "=2"],
+                "HostConfig": {
+                    "Binds": ["/data:/data", "/logs:logs"],
+                    "Memory": 1024,
+                    "NanoCPUs": 500000000,
+                    "CpuQuota": 50000,
+                    "Dns": ["8.8.8.8", "dns.contoso.com"],
+                    "ExtraHosts": ["parent:10.0.0.1", "parent"]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                "Env[1] \"=2\" has no variable name",
+                "HostConfig.Binds[1] destination \"logs\" is not absolute",
+                "HostConfig.Memory of 1024 bytes is below the minimum of 6 MB",
+                "HostConfig.NanoCPUs can't be set with HostConfig.CpuQuota or HostConfig.CpuPeriod",
+                "HostConfig.Dns[1] \"dns.contoso.com\" is not an IP address",
+                "HostConfig.ExtraHosts[1] \"parent\" is not of the form \"hostname:IP\"",
+            ],
+            super::create_options(&create_options)
+        );
+
+        let create_options = ContainerCreateBody::new().with_host_config(
+            HostConfig::new()
+                .with_binds(vec!["/data:/data:ro".to_string()])
+                .with_memory(64 * 1024 * 1024)
+                .with_memory_reservation(32 * 1024 * 1024),
+        );
+        assert!(super::create_options(&create_options).is_empty());
+    }
+}
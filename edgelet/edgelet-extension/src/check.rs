@@ -0,0 +1,213 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Checks that extensions contribute to `iotedge check`.
+//!
+//! `iotedge check` runs every executable in [`EXTENSIONS_DIR`] as
+//!
+//! ```sh
+//! <extension> check -o=json-stream [--dont-run "<check id> <check id> ..."]
+//! ```
+//!
+//! and shows the results with its own. An extension handles this by passing its checks
+//! and arguments to [`run_checks`]. Extensions that haven't exited after two minutes are
+//! killed, and reported as a failed check.
+
+/// Directory of the extension executables that `iotedge check` runs.
+pub const EXTENSIONS_DIR: &str = "/usr/libexec/aziot/edge-extensions";
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CheckResult {
+    Ok,
+
+    Warning(String),
+
+    /// The check couldn't run, such as because a service it needs isn't running.
+    Skipped,
+
+    Failed(String),
+
+    /// The check failed in a way that makes any further checks meaningless.
+    Fatal(String),
+}
+
+#[async_trait::async_trait]
+pub trait Check: Send + Sync {
+    /// Unique ID of the check, used with `iotedge check --dont-run`. Prefix it with the
+    /// extension's name so that it doesn't collide with other checks.
+    fn id(&self) -> &'static str;
+
+    /// Description shown next to the check's result.
+    fn description(&self) -> &'static str;
+
+    async fn execute(&self) -> CheckResult;
+}
+
+/// Arguments that `iotedge check` passes after `check`.
+#[derive(Debug, Default)]
+pub struct CheckArgs {
+    pub dont_run: std::collections::BTreeSet<String>,
+}
+
+impl CheckArgs {
+    /// Parse the arguments after `check`. Arguments this version doesn't know are ignored
+    /// so that newer versions of `iotedge` can pass more.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut result = CheckArgs::default();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--dont-run" {
+                if let Some(ids) = args.next() {
+                    result
+                        .dont_run
+                        .extend(ids.split_whitespace().map(ToString::to_string));
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Run `checks` and write their results under a section named `section`.
+///
+/// Checks after a fatal result aren't run.
+pub async fn run_checks<W>(
+    section: &str,
+    checks: &[Box<dyn Check>],
+    args: &CheckArgs,
+    mut out: W,
+) -> std::io::Result<()>
+where
+    W: std::io::Write,
+{
+    use aziotctl_common::{
+        CheckOutputSerializable, CheckOutputSerializableStreaming, CheckResultSerializable,
+        CheckerMetaSerializable,
+    };
+
+    fn write<W>(out: &mut W, output: &CheckOutputSerializableStreaming) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        serde_json::to_writer(&mut *out, output)?;
+        writeln!(out)?;
+        out.flush()
+    }
+
+    write(
+        &mut out,
+        &CheckOutputSerializableStreaming::Section {
+            name: section.to_string(),
+        },
+    )?;
+
+    for check in checks {
+        let (result, fatal) = if args.dont_run.contains(check.id()) {
+            (CheckResultSerializable::Ignored, false)
+        } else {
+            match check.execute().await {
+                CheckResult::Ok => (CheckResultSerializable::Ok, false),
+                CheckResult::Warning(details) => (
+                    CheckResultSerializable::Warning {
+                        details: vec![details],
+                    },
+                    false,
+                ),
+                CheckResult::Skipped => (CheckResultSerializable::Skipped, false),
+                CheckResult::Failed(details) => (
+                    CheckResultSerializable::Error {
+                        details: vec![details],
+                    },
+                    false,
+                ),
+                CheckResult::Fatal(details) => (
+                    CheckResultSerializable::Fatal {
+                        details: vec![details],
+                    },
+                    true,
+                ),
+            }
+        };
+
+        write(
+            &mut out,
+            &CheckOutputSerializableStreaming::Check {
+                meta: CheckerMetaSerializable {
+                    id: check.id().to_string(),
+                    description: check.description().to_string(),
+                },
+                output: CheckOutputSerializable {
+                    result,
+                    additional_info: serde_json::Value::Null,
+                },
+            },
+        )?;
+
+        if fatal {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_checks, Check, CheckArgs, CheckResult};
+
+    struct TestCheck(&'static str, fn() -> CheckResult);
+
+    #[async_trait::async_trait]
+    impl Check for TestCheck {
+        fn id(&self) -> &'static str {
+            self.0
+        }
+
+        fn description(&self) -> &'static str {
+            "test check"
+        }
+
+        async fn execute(&self) -> CheckResult {
+            (self.1)()
+        }
+    }
+
+    #[tokio::test]
+    async fn run() {
+        let checks: Vec<Box<dyn Check>> = vec![
+            Box::new(TestCheck("ext-ok", || CheckResult::Ok)),
+            Box::new(TestCheck("ext-ignored", || CheckResult::Ok)),
+            Box::new(TestCheck("ext-fatal", || {
+                CheckResult::Fatal("broken".to_string())
+            })),
+            Box::new(TestCheck("ext-after-fatal", || CheckResult::Ok)),
+        ];
+
+        let args = CheckArgs::parse(
+            ["--unknown", "--dont-run", "ext-ignored other"]
+                .iter()
+                .map(ToString::to_string),
+        );
+
+        let mut out = Vec::new();
+        run_checks("Test extension", &checks, &args, &mut out)
+            .await
+            .unwrap();
+
+        let out: Vec<aziotctl_common::CheckOutputSerializableStreaming> =
+            serde_json::Deserializer::from_slice(&out)
+                .into_iter()
+                .collect::<Result<_, _>>()
+                .unwrap();
+
+        // The section and the checks up to the fatal one.
+        assert_eq!(4, out.len());
+        assert!(matches!(
+            &out[2],
+            aziotctl_common::CheckOutputSerializableStreaming::Check { output, .. }
+                if matches!(output.result, aziotctl_common::CheckResultSerializable::Ignored)
+        ));
+    }
+}
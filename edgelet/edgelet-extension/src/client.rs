@@ -0,0 +1,109 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::UrlExt;
+
+use crate::error::{ApiError, Error};
+use crate::event::{Event, EventHandler};
+
+/// Version of the management API that the client calls.
+pub const API_VERSION: &str = "2022-08-03";
+
+/// A page of the change feed.
+#[derive(Debug, serde::Deserialize)]
+#[non_exhaustive]
+pub struct Events {
+    #[serde(rename = "changes")]
+    pub events: Vec<Event>,
+
+    /// Token to pass to the next call of [`Client::events`].
+    pub next: u64,
+}
+
+/// A client of the management API, on the socket given by `connect.management_uri` in
+/// the daemon's configuration.
+#[derive(Clone)]
+pub struct Client {
+    connector: http_common::Connector,
+    host: String,
+}
+
+impl Client {
+    pub fn new(management_uri: &url::Url) -> Result<Self, Error> {
+        let connector = http_common::Connector::new(management_uri)
+            .map_err(|err| Error::InvalidUrl(err.to_string()))?;
+
+        let base_path = management_uri
+            .to_base_path()
+            .map_err(|err| Error::InvalidUrl(err.to_string()))?;
+        let base_path = base_path
+            .to_str()
+            .ok_or_else(|| Error::InvalidUrl(management_uri.to_string()))?;
+        let host = hex::encode(base_path.as_bytes());
+
+        Ok(Client { connector, host })
+    }
+
+    /// Events after `from`, oldest first. Without `from`, all events the daemon kept
+    /// are returned.
+    ///
+    /// Fails with HTTP 410 if `from` is older than the oldest event kept.
+    pub async fn events(&self, from: Option<u64>) -> Result<Events, Error> {
+        let mut uri = format!("unix://{}:0/changes?api-version={}", self.host, API_VERSION);
+        if let Some(from) = from {
+            uri.push_str(&format!("&from={from}"));
+        }
+
+        let request = hyper::Request::get(uri)
+            .body(hyper::Body::empty())
+            .map_err(|err| Error::Request(err.into()))?;
+
+        let client = self.connector.clone().into_client();
+        let response = client
+            .request(request)
+            .await
+            .map_err(|err| Error::Request(err.into()))?;
+
+        let (parts, body) = response.into_parts();
+        let body = hyper::body::to_bytes(body)
+            .await
+            .map_err(|err| Error::Request(err.into()))?;
+
+        if !parts.status.is_success() {
+            return Err(ApiError::new(parts.status, &body).into());
+        }
+
+        serde_json::from_slice(&body).map_err(|err| Error::Request(err.into()))
+    }
+
+    /// Pass events after `from` to `handler` as they happen, checking for new events
+    /// every `interval`. This runs until the returned future is dropped.
+    ///
+    /// Errors, such as while the daemon restarts, are logged and retried.
+    pub async fn watch<H>(&self, mut from: Option<u64>, handler: &H, interval: std::time::Duration)
+    where
+        H: EventHandler,
+    {
+        loop {
+            match self.events(from).await {
+                Ok(events) => {
+                    for event in &events.events {
+                        handler.handle(event).await;
+                    }
+
+                    from = Some(events.next);
+                }
+                Err(Error::Api(err)) if err.code == hyper::StatusCode::GONE => {
+                    log::warn!("Missed events from the change feed: {}", err.message);
+
+                    handler.resync().await;
+                    from = None;
+
+                    continue;
+                }
+                Err(err) => log::warn!("Failed to get events from the change feed: {}", err),
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
@@ -0,0 +1,44 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("invalid management API URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("could not call the management API")]
+    Request(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error(transparent)]
+    Api(#[from] ApiError),
+
+    #[error("invalid metric name: {0}")]
+    InvalidMetricName(String),
+
+    #[error("metric {0} is already registered")]
+    DuplicateMetric(String),
+}
+
+/// An error response from the management API.
+#[derive(Debug, thiserror::Error)]
+#[error("HTTP {code}: {message}")]
+pub struct ApiError {
+    pub code: hyper::StatusCode,
+    pub message: String,
+}
+
+impl ApiError {
+    pub(crate) fn new(code: hyper::StatusCode, body: &[u8]) -> Self {
+        #[derive(serde::Deserialize)]
+        struct ErrorBody {
+            message: String,
+        }
+
+        let message = match serde_json::from_slice::<ErrorBody>(body) {
+            Ok(body) => body.message,
+            Err(_) => String::from_utf8_lossy(body).into_owned(),
+        };
+
+        ApiError { code, message }
+    }
+}
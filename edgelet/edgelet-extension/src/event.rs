@@ -0,0 +1,100 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum EventKind {
+    Module,
+    FeatureFlag,
+    Alert,
+
+    /// A kind of event added in a newer daemon.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum EventAction {
+    Created,
+    Removed,
+    StatusChanged,
+    ConfigChanged,
+
+    /// An action added in a newer daemon.
+    #[serde(other)]
+    Unknown,
+}
+
+/// A change on the device, as reported by `GET /changes` on the management API.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct Event {
+    /// Position of this event in the feed. Passing it to [`crate::Client::events`]
+    /// resumes the feed after this event.
+    pub token: u64,
+
+    pub time: chrono::DateTime<chrono::Utc>,
+    pub kind: EventKind,
+
+    /// The name of the module, feature flag or alert.
+    pub name: String,
+
+    #[serde(rename = "event")]
+    pub action: EventAction,
+
+    /// A module's new status.
+    #[serde(default)]
+    pub status: Option<String>,
+
+    /// The new configuration: a module's settings, or a feature flag's value.
+    #[serde(default)]
+    pub config: Option<serde_json::Value>,
+}
+
+#[async_trait::async_trait]
+pub trait EventHandler: Send + Sync {
+    async fn handle(&self, event: &Event);
+
+    /// Called when events were missed because the extension fell too far behind the
+    /// feed, such as while it wasn't running. The extension should reread the state it
+    /// mirrors. Events are passed to `handle` again from the oldest one the daemon kept.
+    async fn resync(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, EventAction, EventKind};
+
+    #[test]
+    fn parse() {
+        let event: Event = serde_json::from_value(serde_json::json!({
+            "token": 7,
+            "time": "2023-06-01T00:00:00Z",
+            "kind": "module",
+            "name": "edgeHub",
+            "event": "statusChanged",
+            "status": "running",
+        }))
+        .unwrap();
+        assert_eq!(7, event.token);
+        assert_eq!(EventKind::Module, event.kind);
+        assert_eq!(EventAction::StatusChanged, event.action);
+        assert_eq!(Some("running"), event.status.as_deref());
+
+        // Values added by newer daemons don't fail the whole feed.
+        let event: Event = serde_json::from_value(serde_json::json!({
+            "token": 8,
+            "time": "2023-06-01T00:00:00Z",
+            "kind": "volume",
+            "name": "data",
+            "event": "resized",
+            "size": 10,
+        }))
+        .unwrap();
+        assert_eq!(EventKind::Unknown, event.kind);
+        assert_eq!(EventAction::Unknown, event.action);
+    }
+}
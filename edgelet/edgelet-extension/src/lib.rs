@@ -0,0 +1,36 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Building blocks for host-side extensions of aziot-edged, such as agents that react to
+//! module changes or contribute checks to `iotedge check`.
+//!
+//! Extensions should depend on this crate rather than on the daemon's internal crates,
+//! whose APIs change without notice. This crate follows semver: types here only change
+//! incompatibly in a new major version. Enums that mirror daemon data are
+//! `#[non_exhaustive]` and parse values added by newer daemons as `Unknown`.
+//!
+//! - [`Client`] follows the daemon's change feed and passes module events to an
+//!   [`EventHandler`].
+//! - [`check::run_checks`] writes the results of [`Check`]s in the format that
+//!   `iotedge check` reads from the executables in [`check::EXTENSIONS_DIR`].
+//! - [`metrics::Registry`] collects counters and gauges and renders them in the
+//!   Prometheus text format used by the Edge Agent and Edge Hub metrics endpoints.
+
+#![deny(rust_2018_idioms, warnings)]
+#![deny(clippy::all, clippy::pedantic)]
+#![allow(
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::module_name_repetitions,
+    clippy::must_use_candidate
+)]
+
+pub mod check;
+mod client;
+mod error;
+mod event;
+pub mod metrics;
+
+pub use check::{Check, CheckResult};
+pub use client::{Client, Events, API_VERSION};
+pub use error::{ApiError, Error};
+pub use event::{Event, EventAction, EventHandler, EventKind};
@@ -0,0 +1,149 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Metrics of an extension, rendered in the Prometheus text format so that they can be
+//! collected the same way as the metrics of Edge Agent and Edge Hub.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::error::Error;
+
+#[derive(Clone, Debug, Default)]
+pub struct Counter(std::sync::Arc<AtomicU64>);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    pub fn inc_by(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Gauge(std::sync::Arc<AtomicU64>);
+
+impl Gauge {
+    pub fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Clone)]
+enum Value {
+    Counter(Counter),
+    Gauge(Gauge),
+}
+
+struct Metric {
+    help: String,
+    value: Value,
+}
+
+#[derive(Clone, Default)]
+pub struct Registry {
+    metrics: std::sync::Arc<std::sync::Mutex<std::collections::BTreeMap<String, Metric>>>,
+}
+
+impl Registry {
+    pub fn counter(&self, name: &str, help: &str) -> Result<Counter, Error> {
+        let counter = Counter::default();
+        self.register(name, help, Value::Counter(counter.clone()))?;
+
+        Ok(counter)
+    }
+
+    pub fn gauge(&self, name: &str, help: &str) -> Result<Gauge, Error> {
+        let gauge = Gauge::default();
+        self.register(name, help, Value::Gauge(gauge.clone()))?;
+
+        Ok(gauge)
+    }
+
+    /// The current value of all metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let metrics = self.metrics.lock().expect("metrics lock poisoned");
+
+        let mut result = String::new();
+        for (name, metric) in metrics.iter() {
+            let help = metric.help.replace('\\', "\\\\").replace('\n', "\\n");
+            result.push_str(&format!("# HELP {name} {help}\n"));
+
+            match &metric.value {
+                Value::Counter(counter) => {
+                    result.push_str(&format!("# TYPE {name} counter\n"));
+                    result.push_str(&format!("{name} {}\n", counter.get()));
+                }
+                Value::Gauge(gauge) => {
+                    result.push_str(&format!("# TYPE {name} gauge\n"));
+                    result.push_str(&format!("{name} {}\n", gauge.get()));
+                }
+            }
+        }
+
+        result
+    }
+
+    fn register(&self, name: &str, help: &str, value: Value) -> Result<(), Error> {
+        let is_valid_name = name.chars().enumerate().all(|(i, c)| {
+            c.is_ascii_alphabetic() || matches!(c, '_' | ':') || (i > 0 && c.is_ascii_digit())
+        });
+        if name.is_empty() || !is_valid_name {
+            return Err(Error::InvalidMetricName(name.to_string()));
+        }
+
+        let mut metrics = self.metrics.lock().expect("metrics lock poisoned");
+        if metrics.contains_key(name) {
+            return Err(Error::DuplicateMetric(name.to_string()));
+        }
+
+        metrics.insert(
+            name.to_string(),
+            Metric {
+                help: help.to_string(),
+                value,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Registry;
+
+    #[test]
+    fn render() {
+        let registry = Registry::default();
+
+        let counter = registry
+            .counter("ext_events_total", "Events handled")
+            .unwrap();
+        let gauge = registry.gauge("ext_queue_length", "Queued work").unwrap();
+        counter.inc_by(3);
+        gauge.set(1.5);
+
+        assert_eq!(
+            "# HELP ext_events_total Events handled\n\
+             # TYPE ext_events_total counter\n\
+             ext_events_total 3\n\
+             # HELP ext_queue_length Queued work\n\
+             # TYPE ext_queue_length gauge\n\
+             ext_queue_length 1.5\n",
+            registry.render()
+        );
+
+        assert!(registry.counter("ext_events_total", "").is_err());
+        assert!(registry.counter("1ext", "").is_err());
+        assert!(registry.counter("ext-events", "").is_err());
+    }
+}
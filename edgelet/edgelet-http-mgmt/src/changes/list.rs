@@ -0,0 +1,137 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    changes: edgelet_http::ChangeFeed,
+    from: Option<String>,
+    _runtime: std::marker::PhantomData<M>,
+}
+
+const PATH: &str = "/changes";
+
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct ChangesResponse {
+    pub changes: Vec<edgelet_http::Change>,
+
+    /// Token to pass as `from` on the next request.
+    pub next: u64,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        let from = edgelet_http::find_query("from", query);
+
+        Some(Route {
+            changes: service.changes.clone(),
+            from,
+            _runtime: std::marker::PhantomData,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        let from = match &self.from {
+            Some(from) => Some(
+                std::str::FromStr::from_str(from)
+                    .map_err(|_| edgelet_http::error::bad_request("invalid parameter: from"))?,
+            ),
+            None => None,
+        };
+
+        // Read the latest token first, so that a change recorded in between is returned
+        // again on the next request rather than skipped.
+        let latest = self.changes.latest();
+
+        let changes = self
+            .changes
+            .since(from)
+            .map_err(|err| http_common::server::Error {
+                status_code: http::StatusCode::GONE,
+                message: err.to_string().into(),
+            })?;
+
+        let next = changes.last().map_or(latest, |change| change.token);
+
+        let res = ChangesResponse { changes, next };
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &res,
+        ))
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(super::PATH);
+        assert!(route.from.is_none());
+
+        let route = test_route_ok!(super::PATH, ("from", "3"));
+        assert_eq!(Some("3".to_string()), route.from);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn get_changes() {
+        let route = test_route_ok!(super::PATH);
+
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: super::ChangesResponse = serde_json::from_slice(&body).unwrap();
+        assert!(body.changes.is_empty());
+        assert_eq!(0, body.next);
+    }
+
+    #[tokio::test]
+    async fn invalid_token() {
+        let route = test_route_ok!(super::PATH, ("from", "abc"));
+        let response = route.get().await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        // Not issued by this feed.
+        let route = test_route_ok!(super::PATH, ("from", "5"));
+        let response = route.get().await.unwrap_err();
+        assert_eq!(hyper::StatusCode::GONE, response.status_code);
+    }
+}
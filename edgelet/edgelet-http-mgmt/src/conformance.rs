@@ -0,0 +1,79 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Checks the management API against `api/managementVersion_2022_08_03.yaml` with the
+//! cases of edgelet-conformance, so that routes and the definition can't drift apart.
+
+/// Operations whose handlers call parts of the test runtime that aren't implemented, or
+/// send on the reprovision channel.
+const SKIP: &[&str] = &[
+    "ListModules",
+    "CreateModule",
+    "GetModule",
+    "UpdateModule",
+    "DeleteModule",
+    "PrepareUpdateModule",
+    "StartModule",
+    "StopModule",
+    "RestartModule",
+    "ValidateModule",
+    "GetSystemResources",
+    "GetSupportBundle",
+    "ReprovisionDevice",
+];
+
+#[tokio::test]
+async fn management_api() {
+    let spec = include_str!("../../api/managementVersion_2022_08_03.yaml");
+    let spec = edgelet_conformance::Spec::parse(spec).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let uri = url::Url::parse(&format!(
+        "unix://{}",
+        dir.path().join("mgmt.sock").display()
+    ))
+    .unwrap();
+
+    let mut incoming = http_common::Connector::new(&uri)
+        .unwrap()
+        .incoming(0o600, 10, None)
+        .await
+        .unwrap();
+
+    let service = crate::Service::new(edgelet_test_utils::runtime::Runtime::default());
+    let service = edgelet_http::CompressionService::new(edgelet_http::ApiVersionService::new(
+        edgelet_http::ETagService::new(service),
+    ));
+
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(async move { incoming.serve(service, shutdown_rx).await });
+
+    let client = edgelet_conformance::Client::new(&uri).unwrap();
+    let mut failures = Vec::new();
+
+    for case in edgelet_conformance::generate(&spec) {
+        let operation = &spec.operations[case.operation];
+        if SKIP.contains(&operation.id.as_str()) {
+            continue;
+        }
+
+        let result = match client
+            .send(case.method.clone(), &case.uri, case.body.clone())
+            .await
+        {
+            Ok((status, body)) => edgelet_conformance::check(&spec, &case, status, &body),
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
+            failures.push(format!(
+                "{} {} ({} {}): {}",
+                operation.id, case.name, case.method, case.uri, err
+            ));
+        }
+    }
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap().unwrap();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
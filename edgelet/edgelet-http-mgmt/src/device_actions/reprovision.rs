@@ -4,7 +4,7 @@ pub(crate) struct Route<M>
 where
     M: edgelet_core::ModuleRuntime + Send + Sync,
 {
-    reprovision: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
+    reprovision: edgelet_core::WatchdogSender,
     pid: libc::pid_t,
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
 }
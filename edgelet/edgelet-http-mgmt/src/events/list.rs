@@ -0,0 +1,146 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    audit_log: edgelet_http::AuditLog,
+    since: Option<String>,
+    event_type: Option<String>,
+    _runtime: std::marker::PhantomData<M>,
+}
+
+const PATH: &str = "/events";
+
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct EventsResponse {
+    pub events: Vec<edgelet_http::AuditEvent>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        let since = edgelet_http::find_query("since", query);
+        let event_type = edgelet_http::find_query("type", query);
+
+        Some(Route {
+            audit_log: service.audit_log.clone(),
+            since,
+            event_type,
+            _runtime: std::marker::PhantomData,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        let since = match &self.since {
+            Some(since) => Some(
+                chrono::DateTime::parse_from_rfc3339(since)
+                    .map_err(|_| edgelet_http::error::bad_request("invalid parameter: since"))?
+                    .with_timezone(&chrono::Utc),
+            ),
+            None => None,
+        };
+
+        let event_type = match &self.event_type {
+            Some(event_type) => Some(
+                std::str::FromStr::from_str(event_type)
+                    .map_err(|_| edgelet_http::error::bad_request("invalid parameter: type"))?,
+            ),
+            None => None,
+        };
+
+        let res = EventsResponse {
+            events: self.audit_log.query(since, event_type),
+        };
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &res,
+        ))
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(super::PATH);
+        assert!(route.since.is_none());
+        assert!(route.event_type.is_none());
+
+        let route = test_route_ok!(
+            super::PATH,
+            ("since", "2023-01-01T00:00:00Z"),
+            ("type", "moduleCreated")
+        );
+        assert_eq!(Some("2023-01-01T00:00:00Z".to_string()), route.since);
+        assert_eq!(Some("moduleCreated".to_string()), route.event_type);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn get_events() {
+        let route = test_route_ok!(super::PATH, ("type", "moduleCreated"));
+        route.audit_log.record(
+            edgelet_http::AuditEvent::new(edgelet_http::AuditEventType::ModuleCreated)
+                .with_module("sensor"),
+        );
+        route.audit_log.record(edgelet_http::AuditEvent::new(
+            edgelet_http::AuditEventType::Reprovision,
+        ));
+
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: super::EventsResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(1, body.events.len());
+        assert_eq!(Some("sensor".to_string()), body.events[0].module);
+    }
+
+    #[tokio::test]
+    async fn invalid_query() {
+        let route = test_route_ok!(super::PATH, ("since", "yesterday"));
+        let response = route.get().await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        let route = test_route_ok!(super::PATH, ("type", "moduleExploded"));
+        let response = route.get().await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+    }
+}
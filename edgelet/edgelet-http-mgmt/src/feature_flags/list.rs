@@ -0,0 +1,79 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    feature_flags: edgelet_http::FeatureFlags,
+    _runtime: std::marker::PhantomData<M>,
+}
+
+const PATH: &str = "/featureflags";
+
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct ListFeatureFlagsResponse {
+    pub flags: std::collections::BTreeMap<String, bool>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        Some(Route {
+            feature_flags: service.feature_flags.clone(),
+            _runtime: std::marker::PhantomData,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        let res = ListFeatureFlagsResponse {
+            flags: self.feature_flags.get_all(),
+        };
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &res,
+        ))
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+}
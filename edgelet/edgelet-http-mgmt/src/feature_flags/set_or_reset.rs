@@ -0,0 +1,169 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    feature_flags: edgelet_http::FeatureFlags,
+    changes: edgelet_http::ChangeFeed,
+    name: String,
+    _runtime: std::marker::PhantomData<M>,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) struct FeatureFlag {
+    pub value: bool,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex = regex::Regex::new("^/featureflags/(?P<name>[^/]+)$")
+            .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let name = &captures["name"];
+        let name = percent_encoding::percent_decode_str(name)
+            .decode_utf8()
+            .ok()?;
+
+        // Like the other operator routes, this is restricted only by the permissions of
+        // the management socket, so that `iotedge feature-flags` works from the host.
+        Some(Route {
+            feature_flags: service.feature_flags.clone(),
+            changes: service.changes.clone(),
+            name: name.into_owned(),
+            _runtime: std::marker::PhantomData,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+    async fn delete(self, _body: Option<Self::DeleteBody>) -> http_common::server::RouteResponse {
+        match self.feature_flags.reset(&self.name) {
+            Ok(true) => {
+                log::info!("Feature flag {} reset to its configured value", self.name);
+
+                // The override is removed, so the flag has its configured value again.
+                self.changes.record(edgelet_http::Change::new(
+                    edgelet_http::ChangeKind::FeatureFlag,
+                    &self.name,
+                    edgelet_http::ChangeEvent::Removed,
+                ));
+
+                Ok(http_common::server::response::no_content())
+            }
+            Ok(false) => Err(http_common::server::Error {
+                status_code: http::StatusCode::NOT_FOUND,
+                message: format!("feature flag {} is not overridden", self.name).into(),
+            }),
+            Err(err) => Err(edgelet_http::error::server_error(err)),
+        }
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = FeatureFlag;
+    async fn put(self, body: Self::PutBody) -> http_common::server::RouteResponse {
+        if !edgelet_http::FeatureFlags::is_valid_name(&self.name) {
+            return Err(edgelet_http::error::bad_request(
+                "invalid feature flag name",
+            ));
+        }
+
+        self.feature_flags
+            .set(&self.name, body.value)
+            .map_err(edgelet_http::error::server_error)?;
+
+        log::info!("Feature flag {} set to {}", self.name, body.value);
+
+        self.changes.record(
+            edgelet_http::Change::new(
+                edgelet_http::ChangeKind::FeatureFlag,
+                &self.name,
+                edgelet_http::ChangeEvent::ConfigChanged,
+            )
+            .with_config(body.value.into()),
+        );
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &body,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    const TEST_PATH: &str = "/featureflags/testFlag";
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(TEST_PATH);
+        assert_eq!("testFlag", &route.name);
+
+        // Missing flag name
+        test_route_err!("/featureflags/");
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", TEST_PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}/", TEST_PATH));
+    }
+
+    #[tokio::test]
+    async fn set_and_reset() {
+        let route = test_route_ok!(TEST_PATH);
+        let feature_flags = route.feature_flags.clone();
+        let changes = route.changes.clone();
+
+        let response = route.put(super::FeatureFlag { value: true }).await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+        assert_eq!(Some(&true), feature_flags.get_all().get("testFlag"));
+
+        let route = super::Route::<edgelet_test_utils::runtime::Runtime> {
+            feature_flags: feature_flags.clone(),
+            changes: changes.clone(),
+            name: "testFlag".to_string(),
+            _runtime: std::marker::PhantomData,
+        };
+        let response = route.delete(None).await.unwrap();
+        assert_eq!(hyper::StatusCode::NO_CONTENT, response.status());
+        assert!(feature_flags.get_all().is_empty());
+
+        let changes = changes.since(None).unwrap();
+        assert_eq!(2, changes.len());
+        assert_eq!(Some(serde_json::json!(true)), changes[0].config);
+        assert_eq!(edgelet_http::ChangeEvent::Removed, changes[1].event);
+    }
+
+    #[tokio::test]
+    async fn invalid_name() {
+        let route = test_route_ok!("/featureflags/bad%20name");
+
+        let response = route
+            .put(super::FeatureFlag { value: true })
+            .await
+            .unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+    }
+}
@@ -1,15 +1,26 @@
 // Copyright (c) Microsoft. All rights reserved.
 
+mod changes;
 mod device_actions;
+mod events;
+mod feature_flags;
 mod identity;
 mod module;
+mod secrets;
 mod system_info;
 
+#[cfg(test)]
+mod conformance;
+
 #[cfg(not(test))]
 use aziot_identity_client_async::Client as IdentityClient;
+#[cfg(not(test))]
+use aziot_key_client_async::Client as KeyClient;
 
 #[cfg(test)]
 use test_common::client::IdentityClient;
+#[cfg(test)]
+use test_common::client::KeyClient;
 
 #[derive(Clone)]
 pub struct Service<M>
@@ -17,19 +28,48 @@ where
     M: edgelet_core::ModuleRuntime,
 {
     identity: std::sync::Arc<tokio::sync::Mutex<IdentityClient>>,
+    key: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
-    reprovision: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
+
+    /// Images are pulled without the runtime lock, so that pulls can run concurrently and
+    /// module status can be read while they do.
+    registry: M::ModuleRegistry,
+
+    reprovision: edgelet_core::WatchdogSender,
+    workload_tcp: Option<edgelet_http::WorkloadTcp>,
+    access_log: edgelet_http::AccessLog,
+    rate_limit: edgelet_http::RateLimit,
+    feature_flags: edgelet_http::FeatureFlags,
+    restarts: edgelet_http::RestartHistory,
+    failures: edgelet_http::FailureReport,
+    data_epochs: edgelet_http::DataEpochs,
+    identity_health: edgelet_http::IdentityHealth,
+    changes: edgelet_http::ChangeFeed,
+    alerts: edgelet_http::Alerts,
+    secrets: edgelet_http::Secrets,
+    audit_log: edgelet_http::AuditLog,
+    module_health: edgelet_http::ModuleHealth,
+    persisted_logs: edgelet_http::PersistedLogs,
+    operations: edgelet_http::Operations,
+    diagnostics: edgelet_http::Diagnostics,
+    parent_health: edgelet_http::ParentHealth,
+    log_filter: edgelet_http::LogFilter,
+    state_dump: edgelet_http::StateDump,
 }
 
 impl<M> Service<M>
 where
     M: edgelet_core::ModuleRuntime,
 {
+    /// `runtime` is shared with the daemon's own tasks that change modules, so that they
+    /// don't interleave with changes made through the API.
     #[cfg(not(test))]
-    pub fn new(
+    pub async fn new(
         identity_socket: &url::Url,
-        runtime: M,
-        reprovision: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
+        key_socket: &url::Url,
+        runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+        reprovision: edgelet_core::WatchdogSender,
+        stores: edgelet_http::Stores,
     ) -> Result<Self, http_common::ConnectorError> {
         let connector = http_common::Connector::new(identity_socket)?;
 
@@ -39,62 +79,99 @@ where
             1,
         );
 
-        let identity = std::sync::Arc::new(tokio::sync::Mutex::new(identity));
-        let runtime = std::sync::Arc::new(tokio::sync::Mutex::new(runtime));
+        let key_connector = http_common::Connector::new(key_socket)?;
+        let key = aziot_key_client_async::Client::new(
+            aziot_key_common_http::ApiVersion::V2020_09_01,
+            key_connector,
+            1,
+        );
+
+        let registry = runtime.lock().await.registry().clone();
 
-        Ok(Service {
+        Ok(Service::from_parts(
             identity,
+            key,
             runtime,
+            registry,
             reprovision,
-        })
+            stores,
+        ))
     }
 
     // Test constructor used to create a test Management Service.
     #[cfg(test)]
     pub fn new(runtime: M) -> Self {
-        let identity = IdentityClient::default();
-        let identity = std::sync::Arc::new(tokio::sync::Mutex::new(identity));
-
-        let runtime = std::sync::Arc::new(tokio::sync::Mutex::new(runtime));
-
         // We won't use the reprovision sender, but it must be created to construct the
         // Service struct. Note that we drop the reprovision receiver, which will cause
         // tests to panic if they use the reprovision sender.
-        let (reprovision_tx, _) =
-            tokio::sync::mpsc::unbounded_channel::<edgelet_core::WatchdogAction>();
+        let (reprovision_tx, _) = edgelet_core::watchdog_channel();
 
-        Service {
-            identity,
-            runtime,
-            reprovision: reprovision_tx,
-        }
+        let registry = runtime.registry().clone();
+
+        Service::from_parts(
+            IdentityClient::default(),
+            KeyClient::default(),
+            std::sync::Arc::new(tokio::sync::Mutex::new(runtime)),
+            registry,
+            reprovision_tx,
+            edgelet_http::Stores::default(),
+        )
     }
 
     // Test constructor that returns the reprovision receiver. Only used by the reprovision
     // API tests.
     #[cfg(test)]
-    pub fn new_with_reprovision(
-        runtime: M,
-    ) -> (
-        Self,
-        tokio::sync::mpsc::UnboundedReceiver<edgelet_core::WatchdogAction>,
-    ) {
-        let identity = IdentityClient::default();
-        let identity = std::sync::Arc::new(tokio::sync::Mutex::new(identity));
-
-        let runtime = std::sync::Arc::new(tokio::sync::Mutex::new(runtime));
-
-        let (reprovision_tx, reprovision_rx) =
-            tokio::sync::mpsc::unbounded_channel::<edgelet_core::WatchdogAction>();
-
-        (
-            Service {
-                identity,
-                runtime,
-                reprovision: reprovision_tx,
-            },
-            reprovision_rx,
-        )
+    pub fn new_with_reprovision(runtime: M) -> (Self, edgelet_core::WatchdogReceiver) {
+        let (reprovision_tx, reprovision_rx) = edgelet_core::watchdog_channel();
+
+        let registry = runtime.registry().clone();
+
+        let service = Service::from_parts(
+            IdentityClient::default(),
+            KeyClient::default(),
+            std::sync::Arc::new(tokio::sync::Mutex::new(runtime)),
+            registry,
+            reprovision_tx,
+            edgelet_http::Stores::default(),
+        );
+
+        (service, reprovision_rx)
+    }
+
+    fn from_parts(
+        identity: IdentityClient,
+        key: KeyClient,
+        runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+        registry: M::ModuleRegistry,
+        reprovision: edgelet_core::WatchdogSender,
+        stores: edgelet_http::Stores,
+    ) -> Self {
+        Service {
+            identity: std::sync::Arc::new(tokio::sync::Mutex::new(identity)),
+            key: std::sync::Arc::new(tokio::sync::Mutex::new(key)),
+            runtime,
+            registry,
+            reprovision,
+            workload_tcp: stores.workload_tcp,
+            access_log: stores.access_log,
+            rate_limit: stores.rate_limit,
+            feature_flags: stores.feature_flags,
+            restarts: stores.restarts,
+            failures: stores.failures,
+            data_epochs: stores.data_epochs,
+            identity_health: stores.identity_health,
+            changes: stores.changes,
+            alerts: stores.alerts,
+            secrets: stores.secrets,
+            audit_log: stores.audit_log,
+            module_health: stores.module_health,
+            persisted_logs: stores.persisted_logs,
+            operations: stores.operations,
+            diagnostics: stores.diagnostics,
+            parent_health: stores.parent_health,
+            log_filter: stores.log_filter,
+            state_dump: stores.state_dump,
+        }
     }
 }
 
@@ -112,14 +189,43 @@ http_common::make_service! {
         module::restart_or_start_or_stop::Route<M>,
         module::logs::Route<M>,
         module::prepare_update::Route<M>,
+        module::purge::Route<M>,
+        module::sbom::Route<M>,
+        module::validate::Route<M>,
 
         identity::create_or_list::Route<M>,
         identity::delete_or_update::Route<M>,
 
         system_info::get::Route<M>,
         system_info::resources::Route<M>,
+        system_info::restarts::Route<M>,
+        system_info::identity_health::Route<M>,
+        system_info::log_level::Route<M>,
+        system_info::alerts::Route<M>,
+        system_info::failures::Route<M>,
+        system_info::diagnostics::Route<M>,
+        system_info::disk_space::Route<M>,
+        system_info::dump::Route<M>,
+        system_info::mirrors::Route<M>,
+        system_info::module_health::Route<M>,
+        system_info::parent::Route<M>,
+        system_info::pulls::Route<M>,
+        system_info::operations::Route<M>,
+        system_info::rate_limit::Route<M>,
+        system_info::registry_credentials::Route<M>,
         system_info::support_bundle::Route<M>,
+        system_info::access_log::Route<M>,
 
         device_actions::reprovision::Route<M>,
+
+        changes::list::Route<M>,
+
+        events::list::Route<M>,
+
+        feature_flags::list::Route<M>,
+        feature_flags::set_or_reset::Route<M>,
+
+        secrets::list::Route<M>,
+        secrets::set_or_delete::Route<M>,
     ],
 }
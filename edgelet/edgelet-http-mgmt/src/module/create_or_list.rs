@@ -5,11 +5,132 @@ where
     M: edgelet_core::ModuleRuntime + Send + Sync,
 {
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    registry: M::ModuleRegistry,
+    workload_tcp: Option<edgelet_http::WorkloadTcp>,
+    audit_log: edgelet_http::AuditLog,
     pid: libc::pid_t,
+    caller: Option<edgelet_http::AuditCaller>,
+    name: Option<String>,
+    fields: Option<String>,
+    limit: Option<String>,
+    after: Option<String>,
 }
 
 const PATH: &str = "/modules";
 
+/// Top-level fields of a module's details that can be selected with `fields`.
+const FIELDS: &[&str] = &["id", "name", "type", "config", "status"];
+
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct ListResponse {
+    pub modules: Vec<serde_json::Value>,
+
+    /// Set when there are more modules. Pass it as `after` to get the next page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+/// Which modules to list and which of their fields to include.
+#[derive(Debug, Default)]
+struct Selection {
+    names: Option<std::collections::BTreeSet<String>>,
+    fields: Option<Vec<String>>,
+    limit: Option<usize>,
+    after: Option<String>,
+}
+
+impl Selection {
+    fn parse(
+        name: Option<&str>,
+        fields: Option<&str>,
+        limit: Option<&str>,
+        after: Option<String>,
+    ) -> Result<Self, http_common::server::Error> {
+        let names = name.map(|name| name.split(',').map(ToString::to_string).collect());
+
+        let fields = match fields {
+            Some(fields) => {
+                let fields: Vec<String> = fields.split(',').map(ToString::to_string).collect();
+
+                if fields.iter().any(|field| !FIELDS.contains(&field.as_str())) {
+                    return Err(edgelet_http::error::bad_request(
+                        "invalid parameter: fields",
+                    ));
+                }
+
+                Some(fields)
+            }
+            None => None,
+        };
+
+        let limit = match limit {
+            Some(limit) => match limit.parse::<usize>() {
+                Ok(limit) if limit > 0 => Some(limit),
+                _ => return Err(edgelet_http::error::bad_request("invalid parameter: limit")),
+            },
+            None => None,
+        };
+
+        Ok(Selection {
+            names,
+            fields,
+            limit,
+            after,
+        })
+    }
+
+    /// Select a page of modules. Modules are ordered by name so that pages stay stable
+    /// while modules are added or removed.
+    fn select(
+        &self,
+        mut modules: Vec<edgelet_http::ModuleDetails>,
+    ) -> Result<ListResponse, serde_json::Error> {
+        modules.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut modules: Vec<_> = modules
+            .into_iter()
+            .filter(|module| {
+                self.names
+                    .as_ref()
+                    .map_or(true, |names| names.contains(&module.name))
+            })
+            .filter(|module| {
+                self.after
+                    .as_ref()
+                    .map_or(true, |after| module.name > *after)
+            })
+            .collect();
+
+        let next = match self.limit {
+            Some(limit) if modules.len() > limit => {
+                modules.truncate(limit);
+
+                modules.last().map(|module| module.name.clone())
+            }
+            _ => None,
+        };
+
+        let modules = modules
+            .into_iter()
+            .map(|module| {
+                let mut module = serde_json::to_value(module)?;
+
+                // The name is always included so that modules can be told apart.
+                if let (Some(fields), serde_json::Value::Object(module)) =
+                    (&self.fields, &mut module)
+                {
+                    module.retain(|key, _| key == "name" || fields.contains(key));
+                }
+
+                Ok(module)
+            })
+            .collect::<Result<_, serde_json::Error>>()?;
+
+        Ok(ListResponse { modules, next })
+    }
+}
+
 #[async_trait::async_trait]
 impl<M> http_common::server::Route for Route<M>
 where
@@ -25,7 +146,7 @@ where
     fn from_uri(
         service: &Self::Service,
         path: &str,
-        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
         extensions: &http::Extensions,
     ) -> Option<Self> {
         // A bug in certain versions of the diagnostics image causes it to make requests to "/modules/"
@@ -42,13 +163,28 @@ where
 
         Some(Route {
             runtime: service.runtime.clone(),
+            registry: service.registry.clone(),
+            workload_tcp: service.workload_tcp.clone(),
+            audit_log: service.audit_log.clone(),
             pid,
+            caller: edgelet_http::AuditCaller::from_extensions(extensions),
+            name: edgelet_http::find_query("name", query),
+            fields: edgelet_http::find_query("fields", query),
+            limit: edgelet_http::find_query("limit", query),
+            after: edgelet_http::find_query("after", query),
         })
     }
 
     type DeleteBody = serde::de::IgnoredAny;
 
     async fn get(self) -> http_common::server::RouteResponse {
+        let selection = Selection::parse(
+            self.name.as_deref(),
+            self.fields.as_deref(),
+            self.limit.as_deref(),
+            self.after,
+        )?;
+
         let runtime = self.runtime.lock().await;
 
         let modules = runtime
@@ -56,8 +192,16 @@ where
             .await
             .map_err(|err| edgelet_http::error::runtime_error(&*runtime, &err))?;
 
-        let res: edgelet_http::ListModulesResponse = modules.into();
-        let res = http_common::server::response::json(hyper::StatusCode::OK, &res);
+        let modules: edgelet_http::ListModulesResponse = modules.into();
+        let res = selection
+            .select(modules.modules)
+            .map_err(edgelet_http::error::server_error)?;
+
+        // Pollers send the tag back in If-None-Match to skip unchanged lists.
+        let body = serde_json::to_vec(&res).map_err(edgelet_http::error::server_error)?;
+        let mut res = http_common::server::response::json(hyper::StatusCode::OK, &res);
+        res.headers_mut()
+            .insert(hyper::header::ETAG, edgelet_http::etag(&body));
 
         Ok(res)
     }
@@ -76,9 +220,21 @@ where
         let details =
             edgelet_http::ModuleDetails::from_spec(&body, edgelet_core::ModuleStatus::Stopped);
 
+        let name = body.name().to_string();
+        let module = super::runtime_spec::<M>(self.workload_tcp.as_ref(), body)?;
+        super::pull_image::<M>(&self.registry, &module).await?;
+
         let runtime = self.runtime.lock().await;
+        runtime
+            .create(module)
+            .await
+            .map_err(|err| edgelet_http::error::runtime_error(&*runtime, &err))?;
 
-        super::create_module(&*runtime, body).await?;
+        self.audit_log.record(
+            edgelet_http::AuditEvent::new(edgelet_http::AuditEventType::ModuleCreated)
+                .with_module(name)
+                .with_caller(self.caller.clone()),
+        );
         let res = http_common::server::response::json(hyper::StatusCode::CREATED, &details);
 
         Ok(res)
@@ -91,11 +247,50 @@ where
 mod tests {
     use edgelet_test_utils::{test_route_err, test_route_ok};
 
+    use super::Selection;
+
+    fn modules(names: &[&str]) -> Vec<edgelet_http::ModuleDetails> {
+        names
+            .iter()
+            .map(|name| {
+                serde_json::from_value(serde_json::json!({
+                    "id": format!("{}-id", name),
+                    "name": name,
+                    "type": "docker",
+                    "config": { "settings": { "image": "image:1" } },
+                    "status": { "runtimeStatus": { "status": "running" } }
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn names(res: &super::ListResponse) -> Vec<&str> {
+        res.modules
+            .iter()
+            .map(|module| module["name"].as_str().unwrap())
+            .collect()
+    }
+
     #[test]
     fn parse_uri() {
         // Valid URI
         let route = test_route_ok!(super::PATH);
         assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+        assert!(route.name.is_none());
+        assert!(route.fields.is_none());
+
+        let route = test_route_ok!(
+            super::PATH,
+            ("name", "edgeAgent,edgeHub"),
+            ("fields", "status"),
+            ("limit", "10"),
+            ("after", "edgeAgent")
+        );
+        assert_eq!(Some("edgeAgent,edgeHub".to_string()), route.name);
+        assert_eq!(Some("status".to_string()), route.fields);
+        assert_eq!(Some("10".to_string()), route.limit);
+        assert_eq!(Some("edgeAgent".to_string()), route.after);
 
         // Extra character at beginning of URI
         test_route_err!(&format!("a{}", super::PATH));
@@ -103,4 +298,42 @@ mod tests {
         // Extra character at end of URI
         test_route_err!(&format!("{}a", super::PATH));
     }
+
+    #[test]
+    fn select() {
+        let all = || modules(&["sensor", "edgeHub", "edgeAgent", "camera"]);
+
+        let res = Selection::default().select(all()).unwrap();
+        assert_eq!(
+            vec!["camera", "edgeAgent", "edgeHub", "sensor"],
+            names(&res)
+        );
+        assert!(res.next.is_none());
+        assert_eq!("docker", res.modules[0]["type"]);
+
+        // Pages follow on from the last module of the previous page.
+        let selection = Selection::parse(None, None, Some("3"), None).unwrap();
+        let res = selection.select(all()).unwrap();
+        assert_eq!(vec!["camera", "edgeAgent", "edgeHub"], names(&res));
+        assert_eq!(Some("edgeHub".to_string()), res.next);
+
+        let selection = Selection::parse(None, None, Some("3"), res.next).unwrap();
+        let res = selection.select(all()).unwrap();
+        assert_eq!(vec!["sensor"], names(&res));
+        assert!(res.next.is_none());
+
+        let selection =
+            Selection::parse(Some("sensor,edgeHub,missing"), Some("status"), None, None).unwrap();
+        let res = selection.select(all()).unwrap();
+        assert_eq!(vec!["edgeHub", "sensor"], names(&res));
+        let fields: Vec<_> = res.modules[0].as_object().unwrap().keys().collect();
+        assert_eq!(vec!["name", "status"], fields);
+    }
+
+    #[test]
+    fn invalid_selection() {
+        assert!(Selection::parse(None, Some("status,secrets"), None, None).is_err());
+        assert!(Selection::parse(None, None, Some("0"), None).is_err());
+        assert!(Selection::parse(None, None, Some("ten"), None).is_err());
+    }
 }
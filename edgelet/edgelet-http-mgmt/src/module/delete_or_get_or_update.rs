@@ -5,7 +5,12 @@ where
     M: edgelet_core::ModuleRuntime + Send + Sync,
 {
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    registry: M::ModuleRegistry,
+    workload_tcp: Option<edgelet_http::WorkloadTcp>,
+    audit_log: edgelet_http::AuditLog,
+    secrets: edgelet_http::Secrets,
     pid: libc::pid_t,
+    caller: Option<edgelet_http::AuditCaller>,
     module: String,
     start: Option<String>,
 }
@@ -47,7 +52,12 @@ where
 
         Some(Route {
             runtime: service.runtime.clone(),
+            registry: service.registry.clone(),
+            workload_tcp: service.workload_tcp.clone(),
+            audit_log: service.audit_log.clone(),
+            secrets: service.secrets.clone(),
             pid,
+            caller: edgelet_http::AuditCaller::from_extensions(extensions),
             module: module.to_owned(),
             start,
         })
@@ -60,7 +70,29 @@ where
         let runtime = self.runtime.lock().await;
 
         match runtime.remove(&self.module).await {
-            Ok(_) => Ok(http_common::server::response::no_content()),
+            Ok(_) => {
+                self.audit_log.record(
+                    edgelet_http::AuditEvent::new(edgelet_http::AuditEventType::ModuleRemoved)
+                        .with_module(&self.module)
+                        .with_caller(self.caller.clone()),
+                );
+
+                if let Some(workload_tcp) = &self.workload_tcp {
+                    workload_tcp.revoke(&self.module);
+                }
+
+                // Updates remove and recreate the module through the runtime, so secrets
+                // are only purged when the module itself is removed.
+                if let Err(err) = self.secrets.remove_module(&self.module) {
+                    log::warn!(
+                        "Failed to remove secrets of module {}: {}",
+                        self.module,
+                        err
+                    );
+                }
+
+                Ok(http_common::server::response::no_content())
+            }
             Err(err) => Err(edgelet_http::error::server_error(err.to_string())),
         }
     }
@@ -108,7 +140,9 @@ where
             // Assign the work to restart edgeAgent to a new task and return the successful response.
             // It doesn't matter if restarting edgeAgent fails because the aziot-edged watchdog will
             // retry on failure.
-            tokio::spawn(async move { self.update_module(body, start).await });
+            edgelet_core::tasks::spawn("module_update", async move {
+                self.update_module(body, start).await
+            });
 
             Ok(res)
         } else {
@@ -127,6 +161,13 @@ where
         body: edgelet_http::ModuleSpec,
         start: bool,
     ) -> http_common::server::RouteResponse {
+        // Reject a module that can't be created while the current one is still running.
+        super::validate_module(&*self.runtime.lock().await, &body)?;
+
+        // Pull the new image while the current module keeps running.
+        let module = super::runtime_spec::<M>(self.workload_tcp.as_ref(), body.clone())?;
+        super::pull_image::<M>(&self.registry, &module).await?;
+
         let runtime = self.runtime.lock().await;
 
         // Stop module first so connections are closed gracefully...
@@ -141,11 +182,28 @@ where
             .await
             .map_err(|err| edgelet_http::error::runtime_error(&*runtime, &err))?;
 
-        super::create_module(&*runtime, body.clone()).await?;
+        runtime
+            .create(module)
+            .await
+            .map_err(|err| edgelet_http::error::runtime_error(&*runtime, &err))?;
+
+        self.audit_log.record(
+            edgelet_http::AuditEvent::new(edgelet_http::AuditEventType::ModuleUpdated)
+                .with_module(&self.module)
+                .with_caller(self.caller.clone()),
+        );
 
         let details = if start {
             match runtime.start(&self.module).await {
-                Ok(()) => log::info!("Successfully started module {}", self.module),
+                Ok(()) => {
+                    log::info!("Successfully started module {}", self.module);
+
+                    self.audit_log.record(
+                        edgelet_http::AuditEvent::new(edgelet_http::AuditEventType::ModuleStarted)
+                            .with_module(&self.module)
+                            .with_caller(self.caller.clone()),
+                    );
+                }
                 Err(err) => log::warn!("Failed to start module {}: {}", self.module, err),
             }
 
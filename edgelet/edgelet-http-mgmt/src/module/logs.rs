@@ -5,8 +5,10 @@ where
     M: edgelet_core::ModuleRuntime + Send + Sync,
 {
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    persisted_logs: edgelet_http::PersistedLogs,
     module: String,
 
+    source: Option<String>,
     follow: Option<String>,
     tail: Option<String>,
     since: Option<String>,
@@ -40,6 +42,7 @@ where
             .decode_utf8()
             .ok()?;
 
+        let source = edgelet_http::find_query("source", query);
         let follow = edgelet_http::find_query("follow", query);
         let tail = edgelet_http::find_query("tail", query);
         let since = edgelet_http::find_query("since", query);
@@ -48,8 +51,10 @@ where
 
         Some(Route {
             runtime: service.runtime.clone(),
+            persisted_logs: service.persisted_logs.clone(),
             module: module.into_owned(),
 
+            source,
             follow,
             tail,
             since,
@@ -63,6 +68,10 @@ where
     async fn get(self) -> http_common::server::RouteResponse {
         let log_options = self.log_options()?;
 
+        if self.persisted()? {
+            return self.get_persisted(&log_options).await;
+        }
+
         let logs = {
             let runtime = self.runtime.lock().await;
 
@@ -85,6 +94,64 @@ impl<M> Route<M>
 where
     M: edgelet_core::ModuleRuntime + Send + Sync,
 {
+    /// Whether the logs kept by the daemon were asked for, rather than the container's.
+    fn persisted(&self) -> Result<bool, http_common::server::Error> {
+        match self.source.as_deref() {
+            None | Some("container") => Ok(false),
+            Some("persisted") => Ok(true),
+            Some(_) => Err(edgelet_http::error::bad_request(
+                "invalid parameter: source",
+            )),
+        }
+    }
+
+    async fn get_persisted(
+        &self,
+        log_options: &edgelet_core::LogOptions,
+    ) -> http_common::server::RouteResponse {
+        if !self.persisted_logs.is_enabled() {
+            return Err(edgelet_http::error::bad_request(
+                "log persistence is not enabled",
+            ));
+        }
+
+        if log_options.follow() {
+            return Err(edgelet_http::error::bad_request(
+                "persisted logs can't be followed",
+            ));
+        }
+
+        let time = |secs: i32| {
+            chrono::NaiveDateTime::from_timestamp_opt(secs.into(), 0)
+                .map(|time| chrono::DateTime::<chrono::Utc>::from_utc(time, chrono::Utc))
+        };
+        let since = Some(log_options.since())
+            .filter(|since| *since > 0)
+            .and_then(time);
+        let until = log_options.until().and_then(time);
+        let tail = match log_options.tail() {
+            edgelet_core::LogTail::All => None,
+            edgelet_core::LogTail::Num(tail) => Some(usize::try_from(*tail).unwrap_or(usize::MAX)),
+        };
+        let timestamps = log_options.timestamps();
+
+        // Old files are decompressed to be read, so this isn't done on the runtime's threads.
+        let persisted_logs = self.persisted_logs.clone();
+        let module = self.module.clone();
+        let entries =
+            tokio::task::spawn_blocking(move || persisted_logs.read(&module, since, until, tail))
+                .await
+                .map_err(edgelet_http::error::server_error)?
+                .map_err(edgelet_http::error::server_error)?;
+
+        let res = http_common::server::response::chunked(
+            hyper::StatusCode::OK,
+            hyper::Body::from(encode(&entries, timestamps)),
+            "text/plain",
+        );
+        Ok(res)
+    }
+
     fn log_options(&self) -> Result<edgelet_core::LogOptions, http_common::server::Error> {
         let mut log_options = edgelet_core::LogOptions::new();
 
@@ -127,8 +194,44 @@ where
     }
 }
 
+/// Encode log entries in the multiplexed format of the container engine's logs, so that
+/// clients read persisted logs the same way as a container's.
+fn encode(entries: &[edgelet_http::LogEntry], timestamps: bool) -> Vec<u8> {
+    let mut body = Vec::new();
+
+    for entry in entries {
+        let mut payload = String::new();
+        if timestamps {
+            payload.push_str(
+                &entry
+                    .time
+                    .to_rfc3339_opts(chrono::SecondsFormat::Nanos, true),
+            );
+            payload.push(' ');
+        }
+        payload.push_str(&entry.log);
+
+        let stream = match entry.stream {
+            edgelet_http::LogStream::Stdout => 1,
+            edgelet_http::LogStream::Stderr => 2,
+        };
+
+        body.extend_from_slice(&[stream, 0, 0, 0]);
+        body.extend_from_slice(
+            &u32::try_from(payload.len())
+                .unwrap_or(u32::MAX)
+                .to_be_bytes(),
+        );
+        body.extend_from_slice(payload.as_bytes());
+    }
+
+    body
+}
+
 #[cfg(test)]
 mod tests {
+    use http_common::server::Route;
+
     use edgelet_test_utils::{test_route_err, test_route_ok};
 
     #[test]
@@ -268,4 +371,63 @@ mod tests {
         assert_eq!(Some(10), log_options.until());
         assert!(log_options.timestamps());
     }
+
+    #[tokio::test]
+    async fn get_logs() {
+        let route = test_route_ok!("/modules/testModule/logs", ("tail", "100"));
+
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+        assert_eq!(
+            "text/plain",
+            response.headers()[hyper::header::CONTENT_TYPE]
+        );
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(b"testModule logs (tail=100)".as_ref(), &body[..]);
+    }
+
+    #[tokio::test]
+    async fn get_persisted_logs() {
+        let uri = "/modules/testModule/logs";
+
+        // Persistence isn't enabled in tests.
+        let route = test_route_ok!(uri, ("source", "persisted"));
+        let response = route.get().await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        let route = test_route_ok!(uri, ("source", "invalid"));
+        let response = route.get().await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        let route = test_route_ok!(uri, ("source", "container"));
+        route.get().await.unwrap();
+    }
+
+    #[test]
+    fn encode() {
+        let time = chrono::NaiveDateTime::from_timestamp_opt(0, 0).unwrap();
+        let time = chrono::DateTime::<chrono::Utc>::from_utc(time, chrono::Utc);
+
+        let entries = [
+            edgelet_http::LogEntry {
+                time,
+                stream: edgelet_http::LogStream::Stdout,
+                log: "out\n".to_string(),
+            },
+            edgelet_http::LogEntry {
+                time,
+                stream: edgelet_http::LogStream::Stderr,
+                log: "err\n".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            b"\x01\x00\x00\x00\x00\x00\x00\x04out\n\x02\x00\x00\x00\x00\x00\x00\x04err\n".as_ref(),
+            &super::encode(&entries, false)[..]
+        );
+
+        let body = super::encode(&entries[..1], true);
+        assert_eq!(b"1970-01-01T00:00:00.000000000Z out\n".as_ref(), &body[8..]);
+    }
 }
@@ -6,36 +6,67 @@ pub(super) mod restart_or_start_or_stop;
 
 pub(super) mod logs;
 pub(super) mod prepare_update;
+pub(super) mod purge;
+pub(super) mod sbom;
+pub(super) mod validate;
 
 use edgelet_core::ModuleRegistry;
 
-async fn create_module<M>(
-    runtime: &M,
+/// Convert a module spec for the runtime, giving the module the address of the TCP
+/// workload listener if it is enabled.
+fn runtime_spec<M>(
+    workload_tcp: Option<&edgelet_http::WorkloadTcp>,
     module: edgelet_http::ModuleSpec,
-) -> Result<(), http_common::server::Error>
+) -> Result<
+    edgelet_settings::ModuleSpec<<M as edgelet_core::ModuleRuntime>::Config>,
+    http_common::server::Error,
+>
 where
     M: edgelet_core::ModuleRuntime,
     <M as edgelet_core::ModuleRuntime>::Config: serde::de::DeserializeOwned,
 {
-    let module = module
+    let host_network = module.host_network();
+    let mut module = module
         .to_runtime_spec::<M>()
         .map_err(|err| http_common::server::Error {
             status_code: http::StatusCode::BAD_REQUEST,
             message: err.into(),
         })?;
 
-    pull_image(runtime, &module).await?;
+    if let Some(workload_tcp) = workload_tcp {
+        let name = module.name().to_string();
+        workload_tcp.set_module_env(&name, host_network, module.env_mut());
+    }
 
-    runtime
-        .create(module)
-        .await
-        .map_err(|err| edgelet_http::error::runtime_error(runtime, &err))?;
+    Ok(module)
+}
 
-    Ok(())
+fn validate_module<M>(
+    runtime: &M,
+    module: &edgelet_http::ModuleSpec,
+) -> Result<(), http_common::server::Error>
+where
+    M: edgelet_core::ModuleRuntime,
+    <M as edgelet_core::ModuleRuntime>::Config: serde::de::DeserializeOwned,
+{
+    let module =
+        module
+            .clone()
+            .to_runtime_spec::<M>()
+            .map_err(|err| http_common::server::Error {
+                status_code: http::StatusCode::BAD_REQUEST,
+                message: err.into(),
+            })?;
+
+    runtime
+        .validate(&module)
+        .map_err(|err| edgelet_http::error::runtime_error(runtime, &err))
 }
 
+/// Pull the image of a module. Callers must not hold the runtime lock, so that pulls run
+/// concurrently up to the runtime's limit and module status can be read meanwhile.
 async fn pull_image<M>(
-    runtime: &M,
+    registry: &<M as edgelet_core::ModuleRuntime>::ModuleRegistry,
     module: &edgelet_settings::ModuleSpec<<M as edgelet_core::ModuleRuntime>::Config>,
 ) -> Result<(), http_common::server::Error>
 where
@@ -43,11 +74,13 @@ where
 {
     match module.image_pull_policy() {
         edgelet_settings::module::ImagePullPolicy::OnCreate => {
-            runtime
-                .registry()
-                .pull(module.config())
+            registry
+                .pull_module(module.name(), module.config())
                 .await
-                .map_err(|err| edgelet_http::error::runtime_error(runtime, &err))?;
+                .map_err(|err| http_common::server::Error {
+                    status_code: M::error_code(&err),
+                    message: err.to_string().into(),
+                })?;
 
             log::debug!("Successfully pulled new image for module {}", module.name());
         }
@@ -5,6 +5,7 @@ where
     M: edgelet_core::ModuleRuntime + Send + Sync,
 {
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    registry: M::ModuleRegistry,
     pid: libc::pid_t,
     module: String,
 }
@@ -43,6 +44,7 @@ where
 
         Some(Route {
             runtime: service.runtime.clone(),
+            registry: service.registry.clone(),
             pid,
             module: module.into_owned(),
         })
@@ -67,8 +69,6 @@ where
             ));
         }
 
-        let runtime = self.runtime.lock().await;
-
         let module = body
             .to_runtime_spec::<M>()
             .map_err(|err| http_common::server::Error {
@@ -76,7 +76,7 @@ where
                 message: err.into(),
             })?;
 
-        super::pull_image(&*runtime, &module).await?;
+        super::pull_image::<M>(&self.registry, &module).await?;
 
         Ok(http_common::server::response::no_content())
     }
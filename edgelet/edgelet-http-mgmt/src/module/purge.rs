@@ -0,0 +1,200 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    data_epochs: edgelet_http::DataEpochs,
+    module: String,
+    pid: libc::pid_t,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) struct PurgeRequest {
+    /// Must repeat the module name, so that data isn't purged by a mistyped URI.
+    confirm: String,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) struct PurgeResponse {
+    volumes: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex = regex::Regex::new("^/modules/(?P<module>[^/]+)/purge$")
+            .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module = &captures["module"];
+        let module = percent_encoding::percent_decode_str(module)
+            .decode_utf8()
+            .ok()?;
+
+        let pid = match extensions.get::<Option<libc::pid_t>>().copied().flatten() {
+            Some(pid) => pid,
+            None => return None,
+        };
+
+        Some(Route {
+            runtime: service.runtime.clone(),
+            data_epochs: service.data_epochs.clone(),
+            module: module.into_owned(),
+            pid,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    type PostBody = PurgeRequest;
+    async fn post(self, body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
+        edgelet_http::auth_agent(self.pid, &self.runtime).await?;
+
+        let body = match body {
+            Some(body) => body,
+            None => {
+                return Err(edgelet_http::error::bad_request("missing request body"));
+            }
+        };
+
+        if body.confirm != self.module {
+            return Err(edgelet_http::error::bad_request(
+                "confirmation does not match module name",
+            ));
+        }
+
+        // Edge Agent's own data holds the deployment, so purging it would not leave the
+        // module deployed.
+        if self.module.trim_start_matches('$') == "edgeAgent" {
+            return Err(edgelet_http::error::bad_request(
+                "data of edgeAgent cannot be purged",
+            ));
+        }
+
+        // Advance the epoch first, so that data encrypted through the workload API is
+        // unreadable even if removing the module's volumes fails.
+        self.data_epochs
+            .advance(&self.module)
+            .map_err(edgelet_http::error::server_error)?;
+
+        let volumes = {
+            let runtime = self.runtime.lock().await;
+
+            runtime
+                .purge_data(&self.module)
+                .await
+                .map_err(|err| edgelet_http::error::runtime_error(&*runtime, &err))?
+        };
+
+        log::info!(
+            "Purged data of module {}: {} volume(s) removed",
+            self.module,
+            volumes.len()
+        );
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &PurgeResponse { volumes },
+        ))
+    }
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!("/modules/testModule/purge");
+        assert_eq!("testModule", &route.module);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+
+        // Missing module name
+        test_route_err!("/modules//purge");
+
+        // Extra character at beginning of URI
+        test_route_err!("a/modules/testModule/purge");
+
+        // Extra character at end of URI
+        test_route_err!("/modules/testModule/purgea");
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn post(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            let body = super::PurgeRequest {
+                confirm: "testModule".to_string(),
+            };
+
+            route.post(Some(body)).await
+        }
+
+        edgelet_test_utils::test_auth_agent!("/modules/testModule/purge", post);
+    }
+
+    #[tokio::test]
+    async fn post() {
+        // Missing body
+        let route = test_route_ok!("/modules/testModule/purge");
+        let response = route.post(None).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        // Confirmation does not match
+        let route = test_route_ok!("/modules/testModule/purge");
+        let body = super::PurgeRequest {
+            confirm: "otherModule".to_string(),
+        };
+        let response = route.post(Some(body)).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        // Edge Agent can't be purged
+        let route = test_route_ok!("/modules/edgeAgent/purge");
+        let body = super::PurgeRequest {
+            confirm: "edgeAgent".to_string(),
+        };
+        let response = route.post(Some(body)).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        // Runtime error
+        let route = test_route_ok!("/modules/runtimeError/purge");
+        let body = super::PurgeRequest {
+            confirm: "runtimeError".to_string(),
+        };
+        route.post(Some(body)).await.unwrap_err();
+
+        // Success
+        let route = test_route_ok!("/modules/testModule/purge");
+        let body = super::PurgeRequest {
+            confirm: "testModule".to_string(),
+        };
+        let response = route.post(Some(body)).await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: super::PurgeResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(vec!["testModule-data".to_string()], body.volumes);
+    }
+}
@@ -5,6 +5,8 @@ where
     M: edgelet_core::ModuleRuntime + Send + Sync,
 {
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    audit_log: edgelet_http::AuditLog,
+    caller: Option<edgelet_http::AuditCaller>,
     module: String,
     action: Action,
 }
@@ -46,7 +48,7 @@ where
         service: &Self::Service,
         path: &str,
         _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
-        _extensions: &http::Extensions,
+        extensions: &http::Extensions,
     ) -> Option<Self> {
         let uri_regex = regex::Regex::new("^/modules/(?P<module>[^/]+)/(?P<action>[^/]+)$")
             .expect("hard-coded regex must compile");
@@ -66,6 +68,8 @@ where
 
         Some(Route {
             runtime: service.runtime.clone(),
+            audit_log: service.audit_log.clone(),
+            caller: edgelet_http::AuditCaller::from_extensions(extensions),
             module: module.to_owned(),
             action,
         })
@@ -77,12 +81,27 @@ where
     async fn post(self, _body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
         let runtime = self.runtime.lock().await;
 
-        match self.action {
-            Action::Restart => runtime.restart(&self.module).await,
-            Action::Start => runtime.start(&self.module).await,
-            Action::Stop => runtime.stop(&self.module, None).await,
-        }
-        .map_err(|err| edgelet_http::error::runtime_error(&*runtime, &err))?;
+        let (result, event_type) = match self.action {
+            Action::Restart => (
+                runtime.restart(&self.module).await,
+                edgelet_http::AuditEventType::ModuleRestarted,
+            ),
+            Action::Start => (
+                runtime.start(&self.module).await,
+                edgelet_http::AuditEventType::ModuleStarted,
+            ),
+            Action::Stop => (
+                runtime.stop(&self.module, None).await,
+                edgelet_http::AuditEventType::ModuleStopped,
+            ),
+        };
+        result.map_err(|err| edgelet_http::error::runtime_error(&*runtime, &err))?;
+
+        self.audit_log.record(
+            edgelet_http::AuditEvent::new(event_type)
+                .with_module(&self.module)
+                .with_caller(self.caller.clone()),
+        );
 
         Ok(http_common::server::response::no_content())
     }
@@ -0,0 +1,118 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    module: String,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex = regex::Regex::new("^/modules/(?P<module>[^/]+)/sbom$")
+            .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module = &captures["module"];
+        let module = percent_encoding::percent_decode_str(module)
+            .decode_utf8()
+            .ok()?;
+
+        Some(Route {
+            runtime: service.runtime.clone(),
+            module: module.into_owned(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        // Exporting the image can take minutes, so the lookup runs after the runtime is
+        // unlocked.
+        let lookup = {
+            let runtime = self.runtime.lock().await;
+
+            runtime
+                .module_sbom(&self.module)
+                .await
+                .map_err(|err| edgelet_http::error::runtime_error(&*runtime, &err))?
+        };
+
+        let sbom = lookup.await.map_err(|err| http_common::server::Error {
+            status_code: <M as edgelet_core::ModuleRuntime>::error_code(&err),
+            message: err.to_string().into(),
+        })?;
+
+        match sbom {
+            Some(sbom) => Ok(http_common::server::response::json(
+                hyper::StatusCode::OK,
+                &sbom,
+            )),
+            None => Err(http_common::server::Error {
+                status_code: http::StatusCode::NOT_FOUND,
+                message: format!("image for module {} has no SBOM", self.module).into(),
+            }),
+        }
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!("/modules/testModule/sbom");
+        assert_eq!("testModule", &route.module);
+
+        // Missing module name
+        test_route_err!("/modules//sbom");
+
+        // Extra character at beginning of URI
+        test_route_err!("a/modules/testModule/sbom");
+
+        // Extra character at end of URI
+        test_route_err!("/modules/testModule/sboma");
+    }
+
+    #[tokio::test]
+    async fn get_sbom() {
+        let route = test_route_ok!("/modules/testModule/sbom");
+
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: edgelet_core::ImageSbom = serde_json::from_slice(&body).unwrap();
+        assert_eq!("https://spdx.dev/Document", body.format);
+        assert_eq!(serde_json::json!({ "name": "testModule" }), body.document);
+
+        // Image without an SBOM.
+        let route = test_route_ok!("/modules/noSbom/sbom");
+        let response = route.get().await.unwrap_err();
+        assert_eq!(hyper::StatusCode::NOT_FOUND, response.status_code);
+    }
+}
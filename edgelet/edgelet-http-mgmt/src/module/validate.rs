@@ -0,0 +1,246 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::{Module, ModuleRegistry};
+
+/// Checks a module spec end-to-end as if it were being deployed, without creating it.
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    registry: M::ModuleRegistry,
+    module: String,
+    pid: libc::pid_t,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Validation {
+    valid: bool,
+    errors: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+    <M as edgelet_core::ModuleRuntime>::Config: serde::de::DeserializeOwned + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex = regex::Regex::new("^/modules/(?P<module>[^/]+)/validate$")
+            .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module = &captures["module"];
+        let module = percent_encoding::percent_decode_str(module)
+            .decode_utf8()
+            .ok()?;
+
+        let pid = match extensions.get::<Option<libc::pid_t>>().copied().flatten() {
+            Some(pid) => pid,
+            None => return None,
+        };
+
+        Some(Route {
+            runtime: service.runtime.clone(),
+            registry: service.registry.clone(),
+            module: module.into_owned(),
+            pid,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    type PostBody = edgelet_http::ModuleSpec;
+    async fn post(self, body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
+        // Validation pulls images with the device's registry credentials, so only Edge Agent
+        // may run it.
+        edgelet_http::auth_agent(self.pid, &self.runtime).await?;
+
+        let body = match body {
+            Some(body) => body,
+            None => {
+                return Err(edgelet_http::error::bad_request("missing request body"));
+            }
+        };
+
+        if body.name() != self.module {
+            return Err(edgelet_http::error::bad_request(
+                "module name in spec does not match URI",
+            ));
+        }
+
+        let module = body
+            .to_runtime_spec::<M>()
+            .map_err(|err| http_common::server::Error {
+                status_code: http::StatusCode::BAD_REQUEST,
+                message: err.into(),
+            })?;
+
+        let image = image(module.config());
+
+        // Nothing is created, so a failed check doesn't stop the others from running.
+        let mut errors = Vec::new();
+
+        let existing = {
+            let runtime = self.runtime.lock().await;
+
+            if let Err(err) = runtime.check(&module) {
+                errors.push(format!("{err:#}"));
+            }
+
+            match &image {
+                Some(image) => runtime
+                    .list_images()
+                    .await
+                    .map_or(true, |images| is_present(&images, image)),
+                None => true,
+            }
+        };
+
+        // The image is pulled regardless of the module's pull policy, so that the device's
+        // registry credentials are checked too. Like other pulls, this is done without the
+        // runtime lock.
+        match self.registry.pull(module.config()).await {
+            Ok(()) => {
+                if let (false, Some(image)) = (existing, &image) {
+                    self.remove_image(image).await;
+                }
+            }
+            Err(err) => errors.push(format!("{err:#}")),
+        }
+
+        let res = Validation {
+            valid: errors.is_empty(),
+            errors,
+        };
+
+        let res = http_common::server::response::json(hyper::StatusCode::OK, &res);
+
+        Ok(res)
+    }
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+impl<M> Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    /// Remove an image that was only pulled for validation, unless a module was created
+    /// with it meanwhile.
+    async fn remove_image(&self, image: &str) {
+        let runtime = self.runtime.lock().await;
+
+        let in_use = match runtime.list().await {
+            Ok(modules) => modules
+                .iter()
+                .any(|module| self::image(module.config()).as_deref() == Some(image)),
+            Err(_) => true,
+        };
+
+        if !in_use {
+            if let Err(err) = self.registry.remove(image).await {
+                log::warn!(
+                    "Failed to remove image {} pulled for validation: {}",
+                    image,
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// The image of a module's config, for runtimes whose config has one.
+fn image<C>(config: &C) -> Option<String>
+where
+    C: serde::Serialize,
+{
+    serde_json::to_value(config)
+        .ok()?
+        .get("image")?
+        .as_str()
+        .map(ToOwned::to_owned)
+}
+
+/// Whether `image` is among the device's images. Images without a tag are stored with
+/// the `latest` tag.
+fn is_present(images: &std::collections::HashMap<String, String>, image: &str) -> bool {
+    let name = image.rsplit('/').next().unwrap_or(image);
+    let untagged = !name.contains(':') && !name.contains('@');
+
+    images.contains_key(image) || (untagged && images.contains_key(&format!("{image}:latest")))
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    const TEST_PATH: &str = "/modules/testModule/validate";
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(TEST_PATH);
+        assert_eq!("testModule", &route.module);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", TEST_PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", TEST_PATH));
+    }
+
+    #[test]
+    fn is_present() {
+        let images = [
+            ("mcr.microsoft.com/azureiotedge-agent:1.4", "sha256:1"),
+            ("localhost:5000/sensor:latest", "sha256:2"),
+        ]
+        .iter()
+        .map(|(name, id)| ((*name).to_string(), (*id).to_string()))
+        .collect();
+
+        assert!(super::is_present(
+            &images,
+            "mcr.microsoft.com/azureiotedge-agent:1.4"
+        ));
+        assert!(super::is_present(&images, "localhost:5000/sensor"));
+        assert!(!super::is_present(
+            &images,
+            "mcr.microsoft.com/azureiotedge-agent:1.5"
+        ));
+        assert!(!super::is_present(&images, "localhost:5000/sensor:1.0"));
+    }
+
+    #[test]
+    fn image() {
+        let config = serde_json::json!({ "image": "sensor:1.0", "createOptions": {} });
+        assert_eq!(Some("sensor:1.0".to_string()), super::image(&config));
+        assert_eq!(None, super::image(&serde_json::json!({})));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn post(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            route.post(None).await
+        }
+
+        edgelet_test_utils::test_auth_agent!(TEST_PATH, post);
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    secrets: edgelet_http::Secrets,
+    _runtime: std::marker::PhantomData<M>,
+}
+
+const PATH: &str = "/secrets";
+
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct ListSecretsResponse {
+    /// Secret values are never returned.
+    pub secrets: Vec<edgelet_http::SecretInfo>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        Some(Route {
+            secrets: service.secrets.clone(),
+            _runtime: std::marker::PhantomData,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        let res = ListSecretsResponse {
+            secrets: self.secrets.list(),
+        };
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &res,
+        ))
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}/", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn list() {
+        let route = test_route_ok!(super::PATH);
+        route
+            .secrets
+            .set("testModule", "password", b"iv", b"ciphertext")
+            .unwrap();
+
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains("ciphertext"));
+
+        let body: super::ListSecretsResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(1, body.secrets.len());
+        assert_eq!("testModule", body.secrets[0].module);
+        assert_eq!("password", body.secrets[0].name);
+    }
+}
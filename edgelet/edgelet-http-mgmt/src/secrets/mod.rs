@@ -0,0 +1,4 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(super) mod list;
+pub(super) mod set_or_delete;
@@ -0,0 +1,187 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[cfg(not(test))]
+use aziot_key_client_async::Client as KeyClient;
+
+#[cfg(test)]
+use test_common::client::KeyClient;
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
+    secrets: edgelet_http::Secrets,
+    module: String,
+    name: String,
+    _runtime: std::marker::PhantomData<M>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+pub(crate) struct Secret {
+    pub value: String,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex = regex::Regex::new("^/secrets/(?P<module>[^/]+)/(?P<name>[^/]+)$")
+            .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module = &captures["module"];
+        let module = percent_encoding::percent_decode_str(module)
+            .decode_utf8()
+            .ok()?;
+
+        let name = &captures["name"];
+        let name = percent_encoding::percent_decode_str(name)
+            .decode_utf8()
+            .ok()?;
+
+        // Like the other operator routes, this is restricted only by the permissions of
+        // the management socket, so that `iotedge secrets` works from the host.
+        Some(Route {
+            client: service.key.clone(),
+            secrets: service.secrets.clone(),
+            module: module.into_owned(),
+            name: name.into_owned(),
+            _runtime: std::marker::PhantomData,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+    async fn delete(self, _body: Option<Self::DeleteBody>) -> http_common::server::RouteResponse {
+        match self.secrets.remove(&self.module, &self.name) {
+            Ok(true) => {
+                log::info!("Removed secret {} of module {}", self.name, self.module);
+
+                Ok(http_common::server::response::no_content())
+            }
+            Ok(false) => Err(http_common::server::Error {
+                status_code: http::StatusCode::NOT_FOUND,
+                message: format!("secret {} not found", self.name).into(),
+            }),
+            Err(err) => Err(edgelet_http::error::server_error(err)),
+        }
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = Secret;
+    async fn put(self, body: Self::PutBody) -> http_common::server::RouteResponse {
+        if !edgelet_http::Secrets::is_valid_name(&self.name) {
+            return Err(edgelet_http::error::bad_request("invalid secret name"));
+        }
+
+        let iv = edgelet_http::Secrets::new_iv().map_err(edgelet_http::error::server_error)?;
+        let aad = edgelet_http::Secrets::aad(&self.module, &self.name);
+        let parameters = aziot_key_common::EncryptMechanism::Aead {
+            iv: iv.clone(),
+            aad,
+        };
+
+        let client = self.client.lock().await;
+        let key = client
+            .create_key_if_not_exists(
+                edgelet_http::SECRETS_KEY_ID,
+                aziot_key_common::CreateKeyValue::Generate,
+                &[aziot_key_common::KeyUsage::Encrypt],
+            )
+            .await
+            .map_err(|err| {
+                edgelet_http::error::server_error(format!("unable to load secrets key: {}", err))
+            })?;
+
+        let ciphertext = client
+            .encrypt(&key, parameters, body.value.as_bytes())
+            .await
+            .map_err(edgelet_http::error::server_error)?;
+
+        self.secrets
+            .set(&self.module, &self.name, &iv, &ciphertext)
+            .map_err(edgelet_http::error::server_error)?;
+
+        // Only the name is logged, never the value.
+        log::info!("Set secret {} of module {}", self.name, self.module);
+
+        Ok(http_common::server::response::no_content())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    const TEST_PATH: &str = "/secrets/testModule/password";
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(TEST_PATH);
+        assert_eq!("testModule", &route.module);
+        assert_eq!("password", &route.name);
+
+        // Missing module name
+        test_route_err!("/secrets//password");
+
+        // Missing secret name
+        test_route_err!("/secrets/testModule/");
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", TEST_PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}/", TEST_PATH));
+    }
+
+    #[tokio::test]
+    async fn invalid_name() {
+        let route = test_route_ok!("/secrets/testModule/bad%20name");
+
+        let response = route
+            .put(super::Secret {
+                value: "value".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+    }
+
+    #[tokio::test]
+    async fn delete() {
+        let route = test_route_ok!(TEST_PATH);
+        let secrets = route.secrets.clone();
+
+        let response = route.delete(None).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::NOT_FOUND, response.status_code);
+
+        secrets
+            .set("testModule", "password", b"iv", b"ciphertext")
+            .unwrap();
+
+        let mut route = test_route_ok!(TEST_PATH);
+        route.secrets = secrets.clone();
+
+        let response = route.delete(None).await.unwrap();
+        assert_eq!(hyper::StatusCode::NO_CONTENT, response.status());
+        assert!(secrets.list().is_empty());
+    }
+}
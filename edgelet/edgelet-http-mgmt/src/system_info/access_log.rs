@@ -0,0 +1,125 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    access_log: edgelet_http::AccessLog,
+    pid: libc::pid_t,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+}
+
+const PATH: &str = "/systeminfo/accesslog";
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        let pid = match extensions.get::<Option<libc::pid_t>>().copied().flatten() {
+            Some(pid) => pid,
+            None => return None,
+        };
+
+        Some(Route {
+            access_log: service.access_log.clone(),
+            pid,
+            runtime: service.runtime.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        let settings = self.access_log.settings();
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &settings,
+        ))
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = edgelet_settings::logging::AccessLogSettings;
+    async fn put(self, body: Self::PutBody) -> http_common::server::RouteResponse {
+        edgelet_http::auth_agent(self.pid, &self.runtime).await?;
+
+        log::info!(
+            "Access logging {} with sample rate {}",
+            if body.enabled { "enabled" } else { "disabled" },
+            body.sample_rate
+        );
+
+        self.access_log.set_settings(body.clone());
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &body,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(super::PATH);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn put(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            route.put(Default::default()).await
+        }
+
+        edgelet_test_utils::test_auth_agent!(super::PATH, put);
+    }
+
+    #[tokio::test]
+    async fn update_settings() {
+        let route = test_route_ok!(super::PATH);
+        let access_log = route.access_log.clone();
+        assert!(!access_log.settings().enabled);
+
+        let settings = edgelet_settings::logging::AccessLogSettings {
+            enabled: true,
+            sample_rate: 0.1,
+            ..Default::default()
+        };
+
+        let response = route.put(settings.clone()).await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+        assert_eq!(settings, access_log.settings());
+    }
+}
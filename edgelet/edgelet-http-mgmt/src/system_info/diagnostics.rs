@@ -0,0 +1,319 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[cfg(not(test))]
+use aziot_identity_client_async::Client as IdentityClient;
+
+#[cfg(test)]
+use test_common::client::IdentityClient;
+
+use edgelet_http::{CheckStatus, DiagnosticCheck};
+
+/// Checks that take longer than this fail, so that a hung dependency doesn't hang the
+/// whole report.
+const CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Clock errors above this are reported as warnings, since they break token validation
+/// with IoT Hub.
+const MAX_CLOCK_ERROR_US: i64 = 1_000_000;
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    identity: std::sync::Arc<tokio::sync::Mutex<IdentityClient>>,
+    diagnostics: edgelet_http::Diagnostics,
+}
+
+const PATH: &str = "/systeminfo/diagnostics";
+
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct DiagnosticsResponse {
+    /// The worst status of the checks.
+    pub status: CheckStatus,
+
+    pub checks: Vec<DiagnosticCheck>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        Some(Route {
+            runtime: service.runtime.clone(),
+            identity: service.identity.clone(),
+            diagnostics: service.diagnostics.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        let checks = vec![
+            self.container_engine().await,
+            self.identity_service().await,
+            self.workload_sockets(),
+            self.upstream().await,
+            time_sync(),
+            self.disk_space().await,
+            self.panics(),
+        ];
+
+        let res = DiagnosticsResponse {
+            status: checks
+                .iter()
+                .map(|check| check.status)
+                .max()
+                .unwrap_or(CheckStatus::Pass),
+            checks,
+        };
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &res,
+        ))
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+impl<M> Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    async fn container_engine(&self) -> DiagnosticCheck {
+        const NAME: &str = "containerEngine";
+
+        let runtime = self.runtime.lock().await;
+
+        match tokio::time::timeout(CHECK_TIMEOUT, runtime.system_info()).await {
+            Ok(Ok(info)) => DiagnosticCheck::new(
+                NAME,
+                CheckStatus::Pass,
+                format!(
+                    "Container engine {} is reachable",
+                    info.server_version
+                        .as_deref()
+                        .unwrap_or("(unknown version)")
+                ),
+            ),
+            Ok(Err(err)) => DiagnosticCheck::new(NAME, CheckStatus::Fail, err),
+            Err(_) => DiagnosticCheck::new(NAME, CheckStatus::Fail, "Container engine timed out"),
+        }
+    }
+
+    async fn identity_service(&self) -> DiagnosticCheck {
+        const NAME: &str = "identityService";
+
+        let identity = self.identity.lock().await;
+
+        match tokio::time::timeout(CHECK_TIMEOUT, identity.get_identities()).await {
+            Ok(Ok(_)) => {
+                DiagnosticCheck::new(NAME, CheckStatus::Pass, "Identity Service responded")
+            }
+            Ok(Err(err)) => DiagnosticCheck::new(NAME, CheckStatus::Fail, err),
+            Err(_) => DiagnosticCheck::new(NAME, CheckStatus::Fail, "Identity Service timed out"),
+        }
+    }
+
+    fn workload_sockets(&self) -> DiagnosticCheck {
+        const NAME: &str = "workloadSockets";
+
+        let Some(mnt_dir) = self.diagnostics.mnt_dir() else {
+            return DiagnosticCheck::new(
+                NAME,
+                CheckStatus::Warn,
+                "Workload socket directory is not known yet",
+            );
+        };
+
+        // Sockets are created in the directory, so it must be writable.
+        let probe = mnt_dir.join(".diagnostics");
+        let result = std::fs::write(&probe, b"").and_then(|()| std::fs::remove_file(&probe));
+
+        match result {
+            Ok(()) => DiagnosticCheck::new(
+                NAME,
+                CheckStatus::Pass,
+                format!("{} is writable", mnt_dir.display()),
+            ),
+            Err(err) => DiagnosticCheck::new(
+                NAME,
+                CheckStatus::Fail,
+                format!("{} is not writable: {}", mnt_dir.display(), err),
+            ),
+        }
+    }
+
+    async fn upstream(&self) -> DiagnosticCheck {
+        const NAME: &str = "upstream";
+
+        let Some(upstream) = self.diagnostics.upstream() else {
+            return DiagnosticCheck::new(NAME, CheckStatus::Warn, "Device is not provisioned yet");
+        };
+
+        let lookup = tokio::net::lookup_host((upstream.as_str(), 443));
+
+        match tokio::time::timeout(CHECK_TIMEOUT, lookup).await {
+            Ok(Ok(mut addrs)) if addrs.next().is_some() => {
+                DiagnosticCheck::new(NAME, CheckStatus::Pass, format!("{upstream} resolves"))
+            }
+            Ok(Ok(_)) => DiagnosticCheck::new(
+                NAME,
+                CheckStatus::Fail,
+                format!("{upstream} has no addresses"),
+            ),
+            Ok(Err(err)) => DiagnosticCheck::new(
+                NAME,
+                CheckStatus::Fail,
+                format!("{upstream} does not resolve: {err}"),
+            ),
+            Err(_) => DiagnosticCheck::new(
+                NAME,
+                CheckStatus::Fail,
+                format!("Resolving {upstream} timed out"),
+            ),
+        }
+    }
+
+    async fn disk_space(&self) -> DiagnosticCheck {
+        const NAME: &str = "diskSpace";
+
+        let runtime = self.runtime.lock().await;
+
+        let disks = match runtime.disk_space().await {
+            Ok(disks) => disks,
+            Err(err) => return DiagnosticCheck::new(NAME, CheckStatus::Fail, err),
+        };
+
+        let Some(worst) = disks.iter().max_by_key(|disk| match disk.level {
+            edgelet_core::DiskSpaceLevel::Ok => 0,
+            edgelet_core::DiskSpaceLevel::Warning => 1,
+            edgelet_core::DiskSpaceLevel::Critical => 2,
+        }) else {
+            return DiagnosticCheck::new(NAME, CheckStatus::Warn, "Disk space is not monitored");
+        };
+
+        let status = match worst.level {
+            edgelet_core::DiskSpaceLevel::Ok => CheckStatus::Pass,
+            edgelet_core::DiskSpaceLevel::Warning => CheckStatus::Warn,
+            edgelet_core::DiskSpaceLevel::Critical => CheckStatus::Fail,
+        };
+
+        DiagnosticCheck::new(
+            NAME,
+            status,
+            format!(
+                "{} has {} of {} bytes free",
+                worst.path.display(),
+                worst.available_bytes,
+                worst.total_bytes
+            ),
+        )
+    }
+
+    fn panics(&self) -> DiagnosticCheck {
+        const NAME: &str = "panics";
+
+        match self.diagnostics.panics() {
+            (count, Some(last)) => DiagnosticCheck::new(
+                NAME,
+                CheckStatus::Fail,
+                format!(
+                    "{} panics since the daemon started; the last at {}: {}",
+                    count, last.time, last.message
+                ),
+            ),
+            (_, None) => DiagnosticCheck::new(NAME, CheckStatus::Pass, "No task has panicked"),
+        }
+    }
+}
+
+fn time_sync() -> DiagnosticCheck {
+    const NAME: &str = "timeSync";
+
+    let time = edgelet_core::TimeStatus::current();
+
+    if !time.synchronized {
+        DiagnosticCheck::new(NAME, CheckStatus::Warn, "Host clock is not synchronized")
+    } else if time.estimated_error_us > MAX_CLOCK_ERROR_US {
+        DiagnosticCheck::new(
+            NAME,
+            CheckStatus::Warn,
+            format!(
+                "Host clock may be off by {} ms",
+                time.estimated_error_us / 1000
+            ),
+        )
+    } else {
+        DiagnosticCheck::new(NAME, CheckStatus::Pass, "Host clock is synchronized")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_http::CheckStatus;
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn get_diagnostics() {
+        let route = test_route_ok!(super::PATH);
+        route.diagnostics.set_mnt_dir(std::env::temp_dir());
+        route.diagnostics.panicked("task panicked".to_string());
+
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: super::DiagnosticsResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(7, body.checks.len());
+        assert_eq!(CheckStatus::Fail, body.status);
+
+        let status = |name: &str| {
+            body.checks
+                .iter()
+                .find(|check| check.name == name)
+                .unwrap()
+                .status
+        };
+        assert_eq!(CheckStatus::Pass, status("containerEngine"));
+        assert_eq!(CheckStatus::Pass, status("workloadSockets"));
+        assert_eq!(CheckStatus::Warn, status("upstream"));
+        assert_eq!(CheckStatus::Warn, status("diskSpace"));
+        assert_eq!(CheckStatus::Fail, status("panics"));
+    }
+}
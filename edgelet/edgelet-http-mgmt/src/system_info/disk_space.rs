@@ -0,0 +1,94 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+}
+
+const PATH: &str = "/systeminfo/diskspace";
+
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct DiskSpaceResponse {
+    pub disks: Vec<edgelet_core::DiskSpaceStatus>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        Some(Route {
+            runtime: service.runtime.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        let runtime = self.runtime.lock().await;
+
+        match runtime.disk_space().await {
+            Ok(disks) => Ok(http_common::server::response::json(
+                hyper::StatusCode::OK,
+                &DiskSpaceResponse { disks },
+            )),
+            Err(err) => Err(edgelet_http::error::server_error(err)),
+        }
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn get_disk_space() {
+        let route = test_route_ok!(super::PATH);
+
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: super::DiskSpaceResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(1, body.disks.len());
+        assert_eq!(5, body.disks[0].available_bytes);
+        assert_eq!(edgelet_core::DiskSpaceLevel::Warning, body.disks[0].level);
+    }
+}
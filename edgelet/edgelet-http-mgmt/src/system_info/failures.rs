@@ -0,0 +1,151 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    failures: edgelet_http::FailureReport,
+    pid: libc::pid_t,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+}
+
+const PATH: &str = "/systeminfo/failures";
+
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct FailuresResponse {
+    pub failures: Vec<edgelet_http::FailureSummary>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct AcknowledgeRequest {
+    pub ids: Vec<u64>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        let pid = match extensions.get::<Option<libc::pid_t>>().copied().flatten() {
+            Some(pid) => pid,
+            None => return None,
+        };
+
+        Some(Route {
+            failures: service.failures.clone(),
+            pid,
+            runtime: service.runtime.clone(),
+        })
+    }
+
+    // Edge Agent acknowledges the summaries it has reported upstream by their ids.
+    type DeleteBody = AcknowledgeRequest;
+    async fn delete(self, body: Option<Self::DeleteBody>) -> http_common::server::RouteResponse {
+        edgelet_http::auth_agent(self.pid, &self.runtime).await?;
+
+        let body = match body {
+            Some(body) => body,
+            None => {
+                return Err(edgelet_http::error::bad_request("missing request body"));
+            }
+        };
+
+        self.failures.acknowledge(&body.ids);
+
+        Ok(http_common::server::response::no_content())
+    }
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        edgelet_http::auth_agent(self.pid, &self.runtime).await?;
+
+        let res = FailuresResponse {
+            failures: self.failures.pending(),
+        };
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &res,
+        ))
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(super::PATH);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn get(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            route.get().await
+        }
+
+        async fn delete(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            route
+                .delete(Some(super::AcknowledgeRequest { ids: Vec::new() }))
+                .await
+        }
+
+        edgelet_test_utils::test_auth_agent!(super::PATH, get);
+        edgelet_test_utils::test_auth_agent!(super::PATH, delete);
+    }
+
+    #[tokio::test]
+    async fn get_failures() {
+        let route = test_route_ok!(super::PATH);
+
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: super::FailuresResponse = serde_json::from_slice(&body).unwrap();
+        assert!(body.failures.is_empty());
+    }
+
+    #[tokio::test]
+    async fn acknowledge_requires_ids() {
+        let route = test_route_ok!(super::PATH);
+
+        let response = route.delete(None).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+    }
+}
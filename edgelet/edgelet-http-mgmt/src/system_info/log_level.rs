@@ -0,0 +1,150 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    log_filter: edgelet_http::LogFilter,
+    _runtime: std::marker::PhantomData<M>,
+}
+
+const PATH: &str = "/systeminfo/loglevel";
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) struct LogLevel {
+    /// In the syntax of `AZIOT_LOG`, e.g. `info,edgelet_docker=debug`.
+    pub filter: String,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        // Like the other operator routes, this is restricted only by the permissions of
+        // the management socket, so that `iotedge system log-filter` works from the host.
+        Some(Route {
+            log_filter: service.log_filter.clone(),
+            _runtime: std::marker::PhantomData,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+    async fn delete(self, _body: Option<Self::DeleteBody>) -> http_common::server::RouteResponse {
+        self.log_filter.reset();
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &LogLevel {
+                filter: self.log_filter.get(),
+            },
+        ))
+    }
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &LogLevel {
+                filter: self.log_filter.get(),
+            },
+        ))
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = LogLevel;
+    async fn put(self, body: Self::PutBody) -> http_common::server::RouteResponse {
+        let filter = body.filter.trim();
+
+        self.log_filter
+            .set(filter)
+            .map_err(|err| http_common::server::Error {
+                status_code: http::StatusCode::BAD_REQUEST,
+                message: err.into(),
+            })?;
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &LogLevel {
+                filter: filter.to_string(),
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn set_and_reset() {
+        let route = test_route_ok!(super::PATH);
+        let log_filter = route.log_filter.clone();
+
+        let response = route
+            .put(super::LogLevel {
+                filter: "info,edgelet_docker=debug".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+        assert_eq!("info,edgelet_docker=debug", log_filter.get());
+
+        let route = super::Route::<edgelet_test_utils::runtime::Runtime> {
+            log_filter: log_filter.clone(),
+            _runtime: std::marker::PhantomData,
+        };
+        let response = route.delete(None).await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: super::LogLevel = serde_json::from_slice(&body).unwrap();
+        assert_eq!("info", body.filter);
+        assert_eq!("info", log_filter.get());
+    }
+
+    #[tokio::test]
+    async fn invalid_filter() {
+        let route = test_route_ok!(super::PATH);
+        let log_filter = route.log_filter.clone();
+
+        let response = route
+            .put(super::LogLevel {
+                filter: "edgelet_docker=verbose".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+        assert_eq!("info", log_filter.get());
+    }
+}
@@ -1,5 +1,21 @@
 // Copyright (c) Microsoft. All rights reserved.
 
+pub(super) mod access_log;
+pub(super) mod alerts;
+pub(super) mod diagnostics;
+pub(super) mod disk_space;
+pub(super) mod dump;
+pub(super) mod failures;
 pub(super) mod get;
+pub(super) mod identity_health;
+pub(super) mod log_level;
+pub(super) mod mirrors;
+pub(super) mod module_health;
+pub(super) mod operations;
+pub(super) mod parent;
+pub(super) mod pulls;
+pub(super) mod rate_limit;
+pub(super) mod registry_credentials;
 pub(super) mod resources;
+pub(super) mod restarts;
 pub(super) mod support_bundle;
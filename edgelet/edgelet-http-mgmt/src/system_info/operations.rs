@@ -0,0 +1,125 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    operations: edgelet_http::Operations,
+    identity_health: edgelet_http::IdentityHealth,
+    pid: libc::pid_t,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+}
+
+const PATH: &str = "/systeminfo/operations";
+
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OperationsResponse {
+    #[serde(flatten)]
+    pub operations: edgelet_http::OperationsStatus,
+
+    pub provisioning: edgelet_http::IdentityState,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        let pid = match extensions.get::<Option<libc::pid_t>>().copied().flatten() {
+            Some(pid) => pid,
+            None => return None,
+        };
+
+        Some(Route {
+            operations: service.operations.clone(),
+            identity_health: service.identity_health.clone(),
+            pid,
+            runtime: service.runtime.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        edgelet_http::auth_agent(self.pid, &self.runtime).await?;
+
+        let res = OperationsResponse {
+            operations: self.operations.get(),
+            provisioning: self.identity_health.get().state,
+        };
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &res,
+        ))
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn get(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            route.get().await
+        }
+
+        edgelet_test_utils::test_auth_agent!(super::PATH, get);
+    }
+
+    #[tokio::test]
+    async fn get_operations() {
+        let route = test_route_ok!(super::PATH);
+        route.operations.image_gc(2, None);
+        route.operations.watchdog_restart();
+
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: super::OperationsResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(2, body.operations.images_reclaimed);
+        assert_eq!(1, body.operations.watchdog_restarts);
+        assert_eq!(0, body.operations.unhealthy_restarts);
+        assert_eq!(edgelet_http::IdentityState::Connecting, body.provisioning);
+    }
+}
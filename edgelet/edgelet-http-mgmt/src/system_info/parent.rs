@@ -0,0 +1,99 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    parent_health: edgelet_http::ParentHealth,
+    _runtime: std::marker::PhantomData<M>,
+}
+
+const PATH: &str = "/systeminfo/parent";
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        Some(Route {
+            parent_health: service.parent_health.clone(),
+            _runtime: std::marker::PhantomData,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        match self.parent_health.get() {
+            Some(status) => Ok(http_common::server::response::json(
+                hyper::StatusCode::OK,
+                &status,
+            )),
+            None => Err(http_common::server::Error {
+                status_code: http::StatusCode::NOT_FOUND,
+                message: "device has no monitored parent".into(),
+            }),
+        }
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn get_parent() {
+        // Devices without a parent have no status.
+        let route = test_route_ok!(super::PATH);
+
+        let response = route.get().await.unwrap_err();
+        assert_eq!(hyper::StatusCode::NOT_FOUND, response.status_code);
+
+        let route = test_route_ok!(super::PATH);
+        route.parent_health.set_parent("parent.example");
+        route.parent_health.unreachable(&"connection refused");
+
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: edgelet_http::ParentHealthStatus = serde_json::from_slice(&body).unwrap();
+        assert_eq!("parent.example", body.host);
+        assert_eq!(edgelet_http::ParentState::Unreachable, body.state);
+        assert_eq!(1, body.consecutive_failures);
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    pid: libc::pid_t,
+    registry: Option<String>,
+}
+
+const PATH: &str = "/systeminfo/registrycredentials";
+
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct CredentialsResponse {
+    pub credentials: Vec<edgelet_core::RegistryCredential>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        let pid = match extensions.get::<Option<libc::pid_t>>().copied().flatten() {
+            Some(pid) => pid,
+            None => return None,
+        };
+
+        Some(Route {
+            runtime: service.runtime.clone(),
+            pid,
+            registry: edgelet_http::find_query("registry", query),
+        })
+    }
+
+    // Edge Agent invalidates the cached credentials when a deployment changes them.
+    type DeleteBody = serde::de::IgnoredAny;
+    async fn delete(self, _body: Option<Self::DeleteBody>) -> http_common::server::RouteResponse {
+        edgelet_http::auth_agent(self.pid, &self.runtime).await?;
+
+        let runtime = self.runtime.lock().await;
+
+        runtime
+            .invalidate_registry_credentials(self.registry.as_deref())
+            .await
+            .map_err(edgelet_http::error::server_error)?;
+
+        Ok(http_common::server::response::no_content())
+    }
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        let runtime = self.runtime.lock().await;
+
+        match runtime.registry_credentials().await {
+            Ok(credentials) => Ok(http_common::server::response::json(
+                hyper::StatusCode::OK,
+                &CredentialsResponse { credentials },
+            )),
+            Err(err) => Err(edgelet_http::error::server_error(err)),
+        }
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(super::PATH);
+        assert!(route.registry.is_none());
+
+        // Valid URI with query parameter
+        let route = test_route_ok!(super::PATH, ("registry", "contoso.azurecr.io"));
+        assert_eq!("contoso.azurecr.io", route.registry.unwrap());
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn delete(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            route.delete(None).await
+        }
+
+        edgelet_test_utils::test_auth_agent!(super::PATH, delete);
+    }
+
+    #[tokio::test]
+    async fn get_credentials() {
+        let route = test_route_ok!(super::PATH);
+
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: super::CredentialsResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(1, body.credentials.len());
+        assert_eq!("contoso.azurecr.io", body.credentials[0].registry);
+    }
+
+    #[tokio::test]
+    async fn invalidate() {
+        let route = test_route_ok!(super::PATH, ("registry", "contoso.azurecr.io"));
+
+        let response = route.delete(None).await.unwrap();
+        assert_eq!(hyper::StatusCode::NO_CONTENT, response.status());
+
+        let route = test_route_ok!(super::PATH, ("registry", "runtimeError"));
+        let response = route.delete(None).await.unwrap_err();
+        assert_eq!(
+            hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            response.status_code
+        );
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_settings::TlsPerformanceMode;
+
+/// Crypto instructions of the CPU that OpenSSL can use.
+///
+/// OpenSSL picks these up by itself. They are detected here to choose key types and
+/// cipher order that make the most of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CpuFeatures {
+    pub aes: bool,
+    pub pmull: bool,
+    pub sha2: bool,
+}
+
+impl CpuFeatures {
+    #[cfg(target_arch = "aarch64")]
+    pub fn detect() -> Self {
+        CpuFeatures {
+            aes: std::arch::is_aarch64_feature_detected!("aes"),
+            pmull: std::arch::is_aarch64_feature_detected!("pmull"),
+            sha2: std::arch::is_aarch64_feature_detected!("sha2"),
+        }
+    }
+
+    // ARMv8 crypto extensions available to 32-bit processes are reported in AT_HWCAP2.
+    #[cfg(all(target_arch = "arm", target_os = "linux"))]
+    pub fn detect() -> Self {
+        const HWCAP2_AES: libc::c_ulong = 1 << 0;
+        const HWCAP2_PMULL: libc::c_ulong = 1 << 1;
+        const HWCAP2_SHA2: libc::c_ulong = 1 << 3;
+
+        let hwcap2 = unsafe { libc::getauxval(libc::AT_HWCAP2) };
+
+        CpuFeatures {
+            aes: hwcap2 & HWCAP2_AES != 0,
+            pmull: hwcap2 & HWCAP2_PMULL != 0,
+            sha2: hwcap2 & HWCAP2_SHA2 != 0,
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    pub fn detect() -> Self {
+        CpuFeatures {
+            aes: std::arch::is_x86_feature_detected!("aes"),
+            pmull: std::arch::is_x86_feature_detected!("pclmulqdq"),
+            sha2: std::arch::is_x86_feature_detected!("sha"),
+        }
+    }
+
+    #[cfg(not(any(
+        target_arch = "aarch64",
+        all(target_arch = "arm", target_os = "linux"),
+        target_arch = "x86_64"
+    )))]
+    pub fn detect() -> Self {
+        CpuFeatures::default()
+    }
+
+    /// Whether AES-GCM is faster than ChaCha20-Poly1305 on this CPU.
+    pub fn fast_aes_gcm(self) -> bool {
+        self.aes && self.pmull
+    }
+}
+
+impl std::fmt::Display for CpuFeatures {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "aes={} pmull={} sha2={}",
+            self.aes, self.pmull, self.sha2
+        )
+    }
+}
+
+/// Resolve `auto` to whether performance mode is used on this device.
+pub fn performance_mode(mode: TlsPerformanceMode) -> bool {
+    match mode {
+        TlsPerformanceMode::Off => false,
+        TlsPerformanceMode::Auto => cfg!(any(target_arch = "arm", target_arch = "aarch64")),
+        TlsPerformanceMode::On => true,
+    }
+}
+
+/// Type of the private keys generated for certificates issued through the workload API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum KeyType {
+    Rsa2048,
+
+    // P-256 key generation and signing is orders of magnitude cheaper than RSA-2048,
+    // and OpenSSL has a NEON implementation of it for ARMv8.
+    EcP256,
+}
+
+impl KeyType {
+    pub fn new(mode: TlsPerformanceMode) -> Self {
+        if performance_mode(mode) {
+            KeyType::EcP256
+        } else {
+            KeyType::Rsa2048
+        }
+    }
+
+    pub fn generate(
+        self,
+    ) -> Result<openssl::pkey::PKey<openssl::pkey::Private>, openssl::error::ErrorStack> {
+        match self {
+            KeyType::Rsa2048 => {
+                let rsa = openssl::rsa::Rsa::generate(2048)?;
+
+                openssl::pkey::PKey::from_rsa(rsa)
+            }
+            KeyType::EcP256 => {
+                let group =
+                    openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1)?;
+                let ec = openssl::ec::EcKey::generate(&group)?;
+
+                openssl::pkey::PKey::from_ec_key(ec)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_settings::TlsPerformanceMode;
+
+    use super::KeyType;
+
+    #[test]
+    fn key_type() {
+        assert_eq!(KeyType::Rsa2048, KeyType::new(TlsPerformanceMode::Off));
+        assert_eq!(KeyType::EcP256, KeyType::new(TlsPerformanceMode::On));
+
+        let auto = KeyType::new(TlsPerformanceMode::Auto);
+        if cfg!(any(target_arch = "arm", target_arch = "aarch64")) {
+            assert_eq!(KeyType::EcP256, auto);
+        } else {
+            assert_eq!(KeyType::Rsa2048, auto);
+        }
+    }
+
+    #[test]
+    fn generate() {
+        let key = KeyType::Rsa2048.generate().unwrap();
+        assert_eq!(openssl::pkey::Id::RSA, key.id());
+
+        let key = KeyType::EcP256.generate().unwrap();
+        assert_eq!(openssl::pkey::Id::EC, key.id());
+    }
+}
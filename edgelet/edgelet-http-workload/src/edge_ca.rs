@@ -20,7 +20,7 @@ pub(crate) struct EdgeCaRenewal {
     cert_client: std::sync::Arc<tokio::sync::Mutex<CertClient>>,
     key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
     key_connector: http_common::Connector,
-    renewal_tx: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
+    renewal_tx: edgelet_core::WatchdogSender,
 }
 
 impl EdgeCaRenewal {
@@ -30,7 +30,7 @@ impl EdgeCaRenewal {
         cert_client: std::sync::Arc<tokio::sync::Mutex<CertClient>>,
         key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
         key_connector: http_common::Connector,
-        renewal_tx: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
+        renewal_tx: edgelet_core::WatchdogSender,
     ) -> Self {
         let temp_cert = format!("{}-temp", config.edge_ca_cert);
 
@@ -333,8 +333,7 @@ mod tests {
         // We won't use the renewal sender, but it must be created to construct the
         // EdgeCaRenewal struct. Note that we drop the renewal receiver, which will cause
         // tests to panic if they use the renewal sender.
-        let (renewal_tx, _) =
-            tokio::sync::mpsc::unbounded_channel::<edgelet_core::WatchdogAction>();
+        let (renewal_tx, _) = edgelet_core::watchdog_channel();
 
         EdgeCaRenewal::new(
             rotate_key,
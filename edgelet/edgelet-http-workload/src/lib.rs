@@ -1,7 +1,10 @@
 // Copyright (c) Microsoft. All rights reserved.
 
+pub mod crypto;
 mod edge_ca;
+mod feature_flags;
 mod module;
+mod time;
 mod trust_bundle;
 
 #[cfg(not(test))]
@@ -31,11 +34,15 @@ where
     identity_client: std::sync::Arc<tokio::sync::Mutex<IdentityClient>>,
 
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
-    renewal_tx: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
+    renewal_tx: edgelet_core::WatchdogSender,
     renewal_engine: Option<
         std::sync::Arc<tokio::sync::Mutex<cert_renewal::RenewalEngine<edge_ca::EdgeCaRenewal>>>,
     >,
     config: WorkloadConfig,
+    feature_flags: edgelet_http::FeatureFlags,
+    data_epochs: edgelet_http::DataEpochs,
+    module_certs: edgelet_http::ModuleCerts,
+    secrets: edgelet_http::Secrets,
 }
 
 impl<M> Service<M>
@@ -43,11 +50,16 @@ where
     M: edgelet_core::ModuleRuntime,
 {
     #[cfg(not(test))]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         settings: &impl edgelet_settings::RuntimeSettings,
         runtime: M,
-        renewal_tx: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
+        renewal_tx: edgelet_core::WatchdogSender,
         device_info: &aziot_identity_common::AzureIoTSpec,
+        feature_flags: edgelet_http::FeatureFlags,
+        data_epochs: edgelet_http::DataEpochs,
+        module_certs: edgelet_http::ModuleCerts,
+        secrets: edgelet_http::Secrets,
     ) -> Result<Self, http_common::ConnectorError> {
         let endpoints = settings.endpoints();
 
@@ -78,6 +90,12 @@ where
         let runtime = std::sync::Arc::new(tokio::sync::Mutex::new(runtime));
         let config = WorkloadConfig::new(settings, device_info);
 
+        log::info!(
+            "Using {:?} keys for issued certificates (CPU crypto extensions: {})",
+            config.cert_key_type,
+            crypto::CpuFeatures::detect()
+        );
+
         let renewal_engine = if config.edge_ca_auto_renew.is_some() {
             let engine = cert_renewal::engine::new();
 
@@ -95,6 +113,10 @@ where
             renewal_tx,
             renewal_engine,
             config,
+            feature_flags,
+            data_epochs,
+            module_certs,
+            secrets,
         })
     }
 
@@ -182,6 +204,40 @@ where
         Ok(())
     }
 
+    /// Issue a server certificate from the Edge CA for one of aziot-edged's own TLS
+    /// listeners. Returns the private key and certificate as PEM.
+    pub async fn issue_server_cert(
+        &self,
+        cert_id: &str,
+        common_name: &str,
+        names: &[String],
+    ) -> Result<(String, String), String> {
+        let api = module::cert::CertApi::new(
+            self.key_connector.clone(),
+            self.key_client.clone(),
+            self.cert_client.clone(),
+            &self.config,
+        );
+
+        let subject_alt_names = names
+            .iter()
+            .map(|name| {
+                if name.parse::<std::net::IpAddr>().is_ok() {
+                    module::cert::SubjectAltName::Ip(name.clone())
+                } else {
+                    module::cert::SubjectAltName::Dns(name.clone())
+                }
+            })
+            .collect();
+
+        let extensions = module::cert::server::server_cert_extensions()
+            .map_err(|_| "failed to set server csr extensions".to_string())?;
+
+        api.issue(cert_id, common_name, subject_alt_names, extensions, None)
+            .await
+            .map_err(|err| err.message.into_owned())
+    }
+
     // Test constructor used to create a test Workload Service.
     #[cfg(test)]
     pub fn new(runtime: M) -> Self {
@@ -211,13 +267,14 @@ where
             edge_ca_subject: aziot_certd_config::CertSubject::CommonName(
                 "aziot-edge CA test-device".to_string(),
             ),
+            cert_key_type: crypto::KeyType::Rsa2048,
+            server_certs: edgelet_settings::WorkloadServerCerts::default(),
         };
 
         // We won't use the renewal sender, but it must be created to construct the
         // Service struct. Note that we drop the renewal receiver, which will cause
         // tests to panic if they use the renewal sender.
-        let (renewal_tx, _) =
-            tokio::sync::mpsc::unbounded_channel::<edgelet_core::WatchdogAction>();
+        let (renewal_tx, _) = edgelet_core::watchdog_channel();
 
         Service {
             key_connector,
@@ -228,6 +285,10 @@ where
             renewal_tx,
             renewal_engine: None,
             config,
+            feature_flags: edgelet_http::FeatureFlags::default(),
+            data_epochs: edgelet_http::DataEpochs::default(),
+            module_certs: edgelet_http::ModuleCerts::default(),
+            secrets: edgelet_http::Secrets::default(),
         }
     }
 }
@@ -243,13 +304,20 @@ http_common::make_service! {
         module::list::Route<M>,
 
         module::cert::identity::Route<M>,
+        module::cert::renewal::Route<M>,
         module::cert::server::Route<M>,
 
         module::data::decrypt::Route<M>,
         module::data::encrypt::Route<M>,
         module::data::sign::Route<M>,
 
+        module::secret::Route<M>,
+
         trust_bundle::Route<M>,
+
+        feature_flags::Route<M>,
+
+        time::Route<M>,
     ],
 }
 
@@ -267,6 +335,9 @@ struct WorkloadConfig {
     edge_ca_key: String,
     edge_ca_auto_renew: Option<cert_renewal::AutoRenewConfig>,
     edge_ca_subject: aziot_certd_config::CertSubject,
+
+    cert_key_type: crypto::KeyType,
+    server_certs: edgelet_settings::WorkloadServerCerts,
 }
 
 impl WorkloadConfig {
@@ -299,6 +370,9 @@ impl WorkloadConfig {
             aziot_certd_config::CertSubject::CommonName(format!("aziot-edge CA {}", device_id))
         });
 
+        let cert_key_type = crypto::KeyType::new(settings.tls_performance_mode());
+        let server_certs = settings.workload_server_certs().clone();
+
         WorkloadConfig {
             hub_name: device_info.hub_name.clone(),
             device_id,
@@ -310,6 +384,9 @@ impl WorkloadConfig {
             edge_ca_key,
             edge_ca_auto_renew,
             edge_ca_subject,
+
+            cert_key_type,
+            server_certs,
         }
     }
 }
@@ -344,7 +421,10 @@ mod tests {
                 edge_ca_auto_renew: None,
                 edge_ca_subject: aziot_certd_config::CertSubject::CommonName(
                     "aziot-edge CA test-device".to_string(),
-                )
+                ),
+
+                cert_key_type: super::crypto::KeyType::Rsa2048,
+                server_certs: edgelet_settings::WorkloadServerCerts::default(),
             },
             config
         );
@@ -370,6 +450,11 @@ mod tests {
             )),
             trust_bundle: Some("test-trust-bundle".to_string()),
             manifest_trust_bundle: Some("test-manifest-trust-bundle".to_string()),
+            tls_performance_mode: edgelet_settings::TlsPerformanceMode::On,
+            workload_server_certs: edgelet_settings::WorkloadServerCerts {
+                max_sans: 4,
+                ..Default::default()
+            },
         };
 
         // Check that values from settings are used when provided.
@@ -387,7 +472,13 @@ mod tests {
                 edge_ca_auto_renew: None,
                 edge_ca_subject: aziot_certd_config::CertSubject::CommonName(
                     "aziot-edge CA test-device".to_string(),
-                )
+                ),
+
+                cert_key_type: super::crypto::KeyType::EcP256,
+                server_certs: edgelet_settings::WorkloadServerCerts {
+                    max_sans: 4,
+                    ..Default::default()
+                },
             },
             config
         );
@@ -48,9 +48,15 @@ where
         };
 
         let api = super::CertApi::new(
+            service.key_connector.clone(),
             service.key_client.clone(),
             service.cert_client.clone(),
             &service.config,
+        )
+        .track(
+            &service.module_certs,
+            module_id.trim_start_matches('$'),
+            edgelet_http::CertKind::Identity,
         );
 
         Some(Route {
@@ -77,7 +83,13 @@ where
         })?;
 
         self.api
-            .issue_cert(cert_id, self.module_id, subject_alt_names, csr_extensions)
+            .issue_cert(
+                cert_id,
+                self.module_id,
+                subject_alt_names,
+                csr_extensions,
+                None,
+            )
             .await
     }
 
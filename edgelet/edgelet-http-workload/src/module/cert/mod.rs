@@ -1,6 +1,7 @@
 // Copyright (c) Microsoft. All rights reserved.
 
 pub(crate) mod identity;
+pub(crate) mod renewal;
 pub(crate) mod server;
 
 #[cfg(not(test))]
@@ -31,54 +32,120 @@ pub(crate) struct CertificateResponse {
     expiration: String,
 }
 
+#[derive(Debug)]
 pub(crate) enum SubjectAltName {
     Dns(String),
     Ip(String),
 }
 
-struct CertApi {
+pub(crate) struct CertApi {
+    key_connector: http_common::Connector,
     key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
     cert_client: std::sync::Arc<tokio::sync::Mutex<CertClient>>,
 
     edge_ca_cert: String,
     edge_ca_key: String,
+    key_type: crate::crypto::KeyType,
+
+    /// Where to record issued certificates, so that the module is told to renew them.
+    track: Option<(edgelet_http::ModuleCerts, String, edgelet_http::CertKind)>,
 }
 
 impl CertApi {
     pub fn new(
+        key_connector: http_common::Connector,
         key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
         cert_client: std::sync::Arc<tokio::sync::Mutex<CertClient>>,
         config: &crate::WorkloadConfig,
     ) -> Self {
         CertApi {
+            key_connector,
             key_client,
             cert_client,
             edge_ca_cert: config.edge_ca_cert.clone(),
             edge_ca_key: config.edge_ca_key.clone(),
+            key_type: config.cert_key_type,
+            track: None,
         }
     }
 
+    /// Record certificates issued to `module` in `module_certs`.
+    #[must_use]
+    pub fn track(
+        mut self,
+        module_certs: &edgelet_http::ModuleCerts,
+        module: &str,
+        kind: edgelet_http::CertKind,
+    ) -> Self {
+        self.track = Some((module_certs.clone(), module.to_string(), kind));
+
+        self
+    }
+
+    /// Issue a certificate and respond with it. With `validity`, the certificate expires
+    /// that long from now, or with the Edge CA if that is sooner.
     pub async fn issue_cert(
         self,
         cert_id: String,
         common_name: String,
         subject_alt_names: Vec<SubjectAltName>,
         extensions: openssl::stack::Stack<openssl::x509::X509Extension>,
+        validity: Option<std::time::Duration>,
     ) -> Result<hyper::Response<hyper::Body>, http_common::server::Error> {
-        let keys = new_keys()
+        let (private_key, cert) = self
+            .issue(
+                &cert_id,
+                &common_name,
+                subject_alt_names,
+                extensions,
+                validity,
+            )
+            .await?;
+
+        let expiration = get_expiration(&cert)?;
+
+        if let Some((module_certs, module, kind)) = &self.track {
+            module_certs.record(module, *kind, expiration);
+        }
+
+        let expiration = expiration.to_rfc3339();
+
+        let response = CertificateResponse {
+            private_key: PrivateKey::Key { bytes: private_key },
+            certificate: cert,
+            expiration,
+        };
+        let response = http_common::server::response::json(hyper::StatusCode::CREATED, &response);
+
+        Ok(response)
+    }
+
+    /// Issue a certificate signed by the Edge CA, returning the new private key and the
+    /// certificate as PEM.
+    pub async fn issue(
+        &self,
+        cert_id: &str,
+        common_name: &str,
+        subject_alt_names: Vec<SubjectAltName>,
+        extensions: openssl::stack::Stack<openssl::x509::X509Extension>,
+        validity: Option<std::time::Duration>,
+    ) -> Result<(String, String), http_common::server::Error> {
+        // Key generation is CPU-bound, especially for RSA. Run it outside the async
+        // executor so that simultaneous renewals don't stall other requests.
+        let key_type = self.key_type;
+        let keys = tokio::task::spawn_blocking(move || new_keys(key_type))
+            .await
+            .map_err(|_| edgelet_http::error::server_error("failed to generate csr keys"))?
             .map_err(|_| edgelet_http::error::server_error("failed to generate csr keys"))?;
         let private_key = key_to_pem(&keys.0);
 
         let mut subject = openssl::x509::X509Name::builder()
             .map_err(|_| edgelet_http::error::server_error("failed to generate csr subject"))?;
         subject
-            .append_entry_by_nid(openssl::nid::Nid::COMMONNAME, &common_name)
+            .append_entry_by_nid(openssl::nid::Nid::COMMONNAME, common_name)
             .map_err(|_| edgelet_http::error::server_error("failed to generate csr subject"))?;
         let subject = subject.build();
 
-        let csr = new_csr(&subject, keys, subject_alt_names, extensions)
-            .map_err(|_| edgelet_http::error::server_error("failed to generate csr"))?;
-
         let edge_ca_key_handle = {
             let key_client = self.key_client.lock().await;
 
@@ -88,20 +155,70 @@ impl CertApi {
                 .map_err(|_| edgelet_http::error::server_error("failed to get edge CA key"))?
         };
 
-        let cert = self
-            .create_cert(&cert_id, &csr, &edge_ca_key_handle)
-            .await?;
+        let cert = if let Some(validity) = validity {
+            let cert = TbsCert {
+                subject,
+                public_key: keys.1,
+                subject_alt_names,
+                extensions,
+                validity,
+            };
 
-        let expiration = get_expiration(&cert)?;
+            self.sign_cert(cert_id, cert, &edge_ca_key_handle).await?
+        } else {
+            let csr = new_csr(&subject, keys, subject_alt_names, extensions)
+                .map_err(|_| edgelet_http::error::server_error("failed to generate csr"))?;
 
-        let response = CertificateResponse {
-            private_key: PrivateKey::Key { bytes: private_key },
-            certificate: cert,
-            expiration,
+            self.create_cert(cert_id, &csr, &edge_ca_key_handle).await?
         };
-        let response = http_common::server::response::json(hyper::StatusCode::CREATED, &response);
 
-        Ok(response)
+        Ok((private_key, cert))
+    }
+
+    /// Sign a certificate with the Edge CA key and store it in certd under `cert_id`.
+    ///
+    /// certd gives every certificate it issues the validity of its own config, so
+    /// certificates with a requested validity are signed here instead.
+    async fn sign_cert(
+        &self,
+        cert_id: &str,
+        cert: TbsCert,
+        edge_ca_key_handle: &aziot_key_common::KeyHandle,
+    ) -> Result<String, http_common::server::Error> {
+        let edge_ca_chain = {
+            let cert_client = self.cert_client.lock().await;
+
+            cert_client
+                .get_cert(&self.edge_ca_cert)
+                .await
+                .map_err(|_| edgelet_http::error::server_error("failed to get edge CA cert"))?
+        };
+
+        // The key engine makes blocking calls to keyd.
+        let key_connector = self.key_connector.clone();
+        let edge_ca_key_handle = edge_ca_key_handle.clone();
+        let cert = tokio::task::spawn_blocking(move || {
+            let (edge_ca_key, _) = crate::edge_ca::keys(key_connector, &edge_ca_key_handle)?;
+
+            cert.sign(&edge_ca_chain, &edge_ca_key)
+                .map_err(|_| "failed to sign cert".to_string())
+        })
+        .await
+        .map_err(|_| edgelet_http::error::server_error("failed to sign cert"))?
+        .map_err(edgelet_http::error::server_error)?;
+
+        {
+            let cert_client = self.cert_client.lock().await;
+
+            cert_client
+                .import_cert(cert_id, cert.as_bytes())
+                .await
+                .map_err(|_| {
+                    edgelet_http::error::server_error(format!("failed to create cert {}", cert_id))
+                })?;
+        }
+
+        Ok(cert)
     }
 
     async fn create_cert(
@@ -128,15 +245,99 @@ impl CertApi {
     }
 }
 
-fn new_keys() -> Result<
+/// A certificate to be signed by the Edge CA.
+struct TbsCert {
+    subject: openssl::x509::X509Name,
+    public_key: openssl::pkey::PKey<openssl::pkey::Public>,
+    subject_alt_names: Vec<SubjectAltName>,
+    extensions: openssl::stack::Stack<openssl::x509::X509Extension>,
+    validity: std::time::Duration,
+}
+
+impl TbsCert {
+    /// Sign the certificate, returning it followed by the Edge CA chain as PEM. It expires
+    /// after its validity, or with the Edge CA if that is sooner.
+    fn sign(
+        self,
+        edge_ca_chain: &[u8],
+        edge_ca_key: &openssl::pkey::PKeyRef<openssl::pkey::Private>,
+    ) -> Result<String, openssl::error::ErrorStack> {
+        let edge_ca = openssl::x509::X509::stack_from_pem(edge_ca_chain)?
+            .into_iter()
+            .next()
+            .ok_or_else(openssl::error::ErrorStack::get)?;
+        let issuer: &openssl::x509::X509Ref = &edge_ca;
+
+        let mut builder = openssl::x509::X509::builder()?;
+        builder.set_version(2)?;
+
+        let mut serial = openssl::bn::BigNum::new()?;
+        serial.rand(127, openssl::bn::MsbOption::MAYBE_ZERO, false)?;
+        builder.set_serial_number(&serial.to_asn1_integer()?)?;
+
+        builder.set_subject_name(&self.subject)?;
+        builder.set_issuer_name(issuer.subject_name())?;
+        builder.set_pubkey(&self.public_key)?;
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let not_after = i64::try_from((now + self.validity).as_secs()).unwrap_or(i64::MAX);
+        let not_after = openssl::asn1::Asn1Time::from_unix(not_after)?;
+
+        builder.set_not_before(&openssl::asn1::Asn1Time::days_from_now(0)?)?;
+        if issuer.not_after().compare(&not_after)? == std::cmp::Ordering::Less {
+            builder.set_not_after(issuer.not_after())?;
+        } else {
+            builder.set_not_after(&not_after)?;
+        }
+
+        builder.append_extension(openssl::x509::extension::BasicConstraints::new().build()?)?;
+        for extension in self.extensions {
+            builder.append_extension(extension)?;
+        }
+
+        let subject_key_id = openssl::x509::extension::SubjectKeyIdentifier::new()
+            .build(&builder.x509v3_context(Some(issuer), None))?;
+        builder.append_extension(subject_key_id)?;
+        let authority_key_id = openssl::x509::extension::AuthorityKeyIdentifier::new()
+            .keyid(false)
+            .build(&builder.x509v3_context(Some(issuer), None))?;
+        builder.append_extension(authority_key_id)?;
+
+        if !self.subject_alt_names.is_empty() {
+            let mut names = openssl::x509::extension::SubjectAlternativeName::new();
+
+            for name in &self.subject_alt_names {
+                match name {
+                    SubjectAltName::Dns(name) => names.dns(name),
+                    SubjectAltName::Ip(name) => names.ip(name),
+                };
+            }
+
+            let names = names.build(&builder.x509v3_context(Some(issuer), None))?;
+            builder.append_extension(names)?;
+        }
+
+        builder.sign(edge_ca_key, openssl::hash::MessageDigest::sha256())?;
+
+        let mut cert = builder.build().to_pem()?;
+        cert.extend_from_slice(edge_ca_chain);
+
+        Ok(String::from_utf8_lossy(&cert).into_owned())
+    }
+}
+
+fn new_keys(
+    key_type: crate::crypto::KeyType,
+) -> Result<
     (
         openssl::pkey::PKey<openssl::pkey::Private>,
         openssl::pkey::PKey<openssl::pkey::Public>,
     ),
     openssl::error::ErrorStack,
 > {
-    let rsa = openssl::rsa::Rsa::generate(2048)?;
-    let private_key = openssl::pkey::PKey::from_rsa(rsa)?;
+    let private_key = key_type.generate()?;
 
     let public_key = private_key.public_key_to_pem()?;
     let public_key = openssl::pkey::PKey::public_key_from_pem(&public_key)?;
@@ -185,7 +386,7 @@ pub(crate) fn new_csr(
     Ok(csr)
 }
 
-fn get_expiration(cert: &str) -> Result<String, http_common::server::Error> {
+fn get_expiration(cert: &str) -> Result<chrono::DateTime<chrono::Utc>, http_common::server::Error> {
     let cert = openssl::x509::X509::from_pem(cert.as_bytes())
         .map_err(|_| edgelet_http::error::server_error("failed to parse cert"))?;
 
@@ -198,7 +399,7 @@ fn get_expiration(cert: &str) -> Result<String, http_common::server::Error> {
         .expect("cert not_after should parse");
     let expiration = chrono::DateTime::<chrono::Utc>::from_utc(expiration, chrono::Utc);
 
-    Ok(expiration.to_rfc3339())
+    Ok(expiration)
 }
 
 fn key_to_pem(key: &openssl::pkey::PKey<openssl::pkey::Private>) -> String {
@@ -221,12 +422,19 @@ mod tests {
         let cert_client = super::CertClient::default();
         let cert_client = std::sync::Arc::new(tokio::sync::Mutex::new(cert_client));
 
+        // Tests won't actually connect to keyd, so just put any URL in the key connector.
+        let key_connector = url::Url::parse("unix:///tmp/test.sock").unwrap();
+        let key_connector = http_common::Connector::new(&key_connector).unwrap();
+
         super::CertApi {
+            key_connector,
             key_client,
             cert_client,
 
             edge_ca_cert: "test-device-cert".to_string(),
             edge_ca_key: "test-device-key".to_string(),
+            key_type: crate::crypto::KeyType::Rsa2048,
+            track: None,
         }
     }
 
@@ -249,6 +457,7 @@ mod tests {
                 // This test won't check these fields, so it doesn't matter what's passed here.
                 vec![],
                 extensions,
+                None,
             )
             .await
             .unwrap();
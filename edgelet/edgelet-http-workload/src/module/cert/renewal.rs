@@ -0,0 +1,172 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    module_id: String,
+    pid: libc::pid_t,
+    timeout: Option<String>,
+    module_certs: edgelet_http::ModuleCerts,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+}
+
+/// How long a request waits for certificates to become due if it doesn't specify.
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+const MAX_TIMEOUT_SECS: u64 = 3600;
+
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct RenewalResponse {
+    /// Certificates the module should request again. Empty if none became due before
+    /// the request timed out.
+    certificates: Vec<edgelet_http::ModuleCert>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex = regex::Regex::new(
+            "^/modules/(?P<moduleId>[^/]+)/genid/(?P<genId>[^/]+)/certificate/renewal$",
+        )
+        .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module_id = &captures["moduleId"];
+        let module_id = percent_encoding::percent_decode_str(module_id)
+            .decode_utf8()
+            .ok()?;
+
+        let pid = match extensions.get::<Option<libc::pid_t>>().copied().flatten() {
+            Some(pid) => pid,
+            None => return None,
+        };
+
+        let timeout = edgelet_http::find_query("timeout", query);
+
+        Some(Route {
+            module_id: module_id.into_owned(),
+            pid,
+            timeout,
+            module_certs: service.module_certs.clone(),
+            runtime: service.runtime.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
+
+        let timeout = match &self.timeout {
+            Some(timeout) => timeout
+                .parse::<u64>()
+                .map_err(|_| edgelet_http::error::bad_request("invalid timeout"))?,
+            None => DEFAULT_TIMEOUT_SECS,
+        };
+        let timeout = std::time::Duration::from_secs(std::cmp::min(timeout, MAX_TIMEOUT_SECS));
+
+        // Certificates are recorded without the leading '$' of modules like '$edgeHub'.
+        let module_id = self.module_id.trim_start_matches('$');
+
+        let res = RenewalResponse {
+            certificates: self.module_certs.wait(module_id, timeout).await,
+        };
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &res,
+        ))
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    const TEST_PATH: &str = "/modules/testModule/genid/testGenId/certificate/renewal";
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(TEST_PATH);
+        assert_eq!("testModule", &route.module_id);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+        assert!(route.timeout.is_none());
+
+        let route = test_route_ok!(TEST_PATH, ("timeout", "10"));
+        assert_eq!(Some("10"), route.timeout.as_deref());
+
+        // Missing module ID
+        test_route_err!("/modules//genid/testGenId/certificate/renewal");
+
+        // Missing generation ID
+        test_route_err!("/modules/testModule/genid//certificate/renewal");
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", TEST_PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", TEST_PATH));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn get(
+            mut route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            // Don't wait for certificates that will never become due.
+            route.timeout = Some("0".to_string());
+
+            route.get().await
+        }
+
+        edgelet_test_utils::test_auth_caller!(TEST_PATH, "testModule", get);
+    }
+
+    #[tokio::test]
+    async fn renewal() {
+        let mut route = test_route_ok!(TEST_PATH);
+        route.timeout = Some("0".to_string());
+
+        route.module_certs.record(
+            "testModule",
+            edgelet_http::CertKind::Server,
+            chrono::Utc::now(),
+        );
+        route.module_certs.record(
+            "otherModule",
+            edgelet_http::CertKind::Server,
+            chrono::Utc::now(),
+        );
+
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: super::RenewalResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(1, body.certificates.len());
+        assert_eq!("testModule", body.certificates[0].module);
+    }
+}
@@ -10,6 +10,7 @@ where
     gen_id: String,
     pid: libc::pid_t,
     api: super::CertApi,
+    policy: edgelet_settings::WorkloadServerCerts,
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
 }
 
@@ -17,6 +18,15 @@ where
 pub(crate) struct ServerCertificateRequest {
     #[serde(rename = "commonName")]
     common_name: String,
+
+    /// DNS names and IP addresses to add to the SANs, e.g. the hostnames that local
+    /// clients connect to the module with.
+    #[serde(rename = "subjectAltNames", default)]
+    subject_alt_names: Vec<String>,
+
+    /// Requested validity, bounded by the server certificate policy.
+    #[serde(rename = "validitySeconds", default)]
+    validity_seconds: Option<u64>,
 }
 
 #[async_trait::async_trait]
@@ -58,9 +68,15 @@ where
         };
 
         let api = super::CertApi::new(
+            service.key_connector.clone(),
             service.key_client.clone(),
             service.cert_client.clone(),
             &service.config,
+        )
+        .track(
+            &service.module_certs,
+            module_id.trim_start_matches('$'),
+            edgelet_http::CertKind::Server,
         );
 
         Some(Route {
@@ -68,6 +84,7 @@ where
             gen_id: gen_id.into_owned(),
             pid,
             api,
+            policy: service.config.server_certs.clone(),
             runtime: service.runtime.clone(),
         })
     }
@@ -78,10 +95,11 @@ where
     async fn post(self, body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
         edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
 
-        let common_name = match body {
-            Some(body) => body.common_name,
+        let body = match body {
+            Some(body) => body,
             None => return Err(edgelet_http::error::bad_request("missing request body")),
         };
+        let common_name = body.common_name;
 
         // Remove any leading '$' from modules like '$edgeAgent' and '$edgeHub' for consistency
         // with previous versions.
@@ -98,24 +116,107 @@ where
             super::SubjectAltName::Dns(common_name_san)
         };
 
-        // Server certificates have the module ID and certificate CN as the SANs.
+        // Server certificates have the module ID and certificate CN as the SANs, followed
+        // by any names the module requested.
         let module_id_san = super::SubjectAltName::Dns(module_id.to_string());
 
-        let subject_alt_names = vec![common_name_san, module_id_san];
+        let mut subject_alt_names = vec![common_name_san, module_id_san];
+        subject_alt_names.extend(requested_sans(
+            &self.policy,
+            &[common_name.as_str(), module_id],
+            body.subject_alt_names,
+        )?);
+
+        let validity = body.validity_seconds.map(|validity| {
+            std::cmp::min(
+                std::time::Duration::from_secs(validity),
+                self.policy.max_validity,
+            )
+        });
 
         let csr_extensions = server_cert_extensions().map_err(|_| {
             edgelet_http::error::server_error("failed to set server csr extensions")
         })?;
 
         self.api
-            .issue_cert(cert_id, common_name, subject_alt_names, csr_extensions)
+            .issue_cert(
+                cert_id,
+                common_name,
+                subject_alt_names,
+                csr_extensions,
+                validity,
+            )
             .await
     }
 
     type PutBody = serde::de::IgnoredAny;
 }
 
-fn server_cert_extensions(
+/// Check the SANs a module requested against the server certificate policy. Names that
+/// the certificate has anyway are skipped.
+fn requested_sans(
+    policy: &edgelet_settings::WorkloadServerCerts,
+    existing: &[&str],
+    names: Vec<String>,
+) -> Result<Vec<super::SubjectAltName>, http_common::server::Error> {
+    let bad_request = |message: String| http_common::server::Error {
+        status_code: http::StatusCode::BAD_REQUEST,
+        message: message.into(),
+    };
+
+    if names.len() > policy.max_sans {
+        return Err(bad_request(format!(
+            "at most {} subject alternative names may be requested",
+            policy.max_sans
+        )));
+    }
+
+    let mut sans = Vec::with_capacity(names.len());
+
+    for name in names {
+        if existing
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(&name))
+        {
+            continue;
+        }
+
+        if std::net::IpAddr::from_str(&name).is_ok() {
+            sans.push(super::SubjectAltName::Ip(name));
+        } else if !is_dns_name(&name) {
+            return Err(bad_request(format!(
+                "{name:?} is not a valid DNS name or IP address"
+            )));
+        } else if !policy.allows_dns_name(&name) {
+            return Err(bad_request(format!(
+                "{name} is not allowed by the server certificate policy"
+            )));
+        } else {
+            sans.push(super::SubjectAltName::Dns(name));
+        }
+    }
+
+    Ok(sans)
+}
+
+/// Whether `name` is a hostname, optionally with a wildcard as its first label.
+fn is_dns_name(name: &str) -> bool {
+    let name = name.strip_prefix("*.").unwrap_or(name);
+
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+pub(crate) fn server_cert_extensions(
 ) -> Result<openssl::stack::Stack<openssl::x509::X509Extension>, openssl::error::ErrorStack> {
     let mut csr_extensions = openssl::stack::Stack::new()?;
 
@@ -144,6 +245,8 @@ mod tests {
     ) -> http_common::server::RouteResponse {
         let body = super::ServerCertificateRequest {
             common_name: MODULE_NAME.to_string(),
+            subject_alt_names: vec![],
+            validity_seconds: None,
         };
 
         route.post(Some(body)).await
@@ -203,4 +306,103 @@ mod tests {
             assert_eq!(MODULE_NAME.to_lowercase(), name.to_lowercase());
         }
     }
+
+    #[test]
+    fn requested_sans() {
+        let policy = edgelet_settings::WorkloadServerCerts {
+            max_sans: 3,
+            dns_domains: vec!["plant.local".to_string()],
+            ..Default::default()
+        };
+
+        let sans = super::requested_sans(
+            &policy,
+            &[MODULE_NAME],
+            vec![
+                "hmi.plant.local".to_string(),
+                "10.0.0.5".to_string(),
+                MODULE_NAME.to_string(),
+            ],
+        )
+        .unwrap();
+        assert!(matches!(
+            sans.as_slice(),
+            [
+                super::super::SubjectAltName::Dns(dns),
+                super::super::SubjectAltName::Ip(ip),
+            ] if dns == "hmi.plant.local" && ip == "10.0.0.5"
+        ));
+
+        // Outside the allowed domains.
+        super::requested_sans(&policy, &[], vec!["example.com".to_string()]).unwrap_err();
+        super::requested_sans(&policy, &[], vec!["evilplant.local".to_string()]).unwrap_err();
+
+        // Not a DNS name.
+        super::requested_sans(&policy, &[], vec!["hmi plant.local".to_string()]).unwrap_err();
+
+        // Too many names.
+        super::requested_sans(&policy, &[], vec!["plant.local".to_string(); 4]).unwrap_err();
+
+        // Without allowed domains, only IP addresses may be requested.
+        let policy = edgelet_settings::WorkloadServerCerts::default();
+        super::requested_sans(&policy, &[], vec!["hmi.plant.local".to_string()]).unwrap_err();
+        super::requested_sans(&policy, &[], vec!["10.0.0.5".to_string()]).unwrap();
+    }
+
+    #[tokio::test]
+    async fn validity() {
+        let mut route = edgelet_test_utils::test_route_ok!(TEST_PATH);
+        route.policy.dns_domains = vec!["local".to_string()];
+        {
+            let pid = nix::unistd::getpid().as_raw();
+            let mut runtime = route.runtime.lock().await;
+            runtime.module_auth = std::collections::BTreeMap::new();
+            runtime
+                .module_auth
+                .insert(MODULE_NAME.to_string(), vec![pid]);
+        }
+
+        // Certificates with a requested validity are signed with the Edge CA directly.
+        {
+            let cert_client = route.api.cert_client.lock().await;
+            let edge_ca = cert_client.issuer.to_pem().unwrap();
+            cert_client
+                .import_cert(&route.api.edge_ca_cert, &edge_ca)
+                .await
+                .unwrap();
+        }
+
+        let body = super::ServerCertificateRequest {
+            common_name: MODULE_NAME.to_string(),
+            subject_alt_names: vec!["hmi.local".to_string()],
+            validity_seconds: Some(60 * 60),
+        };
+
+        let response = route.post(Some(body)).await.unwrap();
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let cert_response: CertificateResponse = serde_json::from_slice(&body_bytes).unwrap();
+
+        // The module renews after the validity it requested.
+        let expiration = chrono::DateTime::parse_from_rfc3339(&cert_response.expiration).unwrap();
+        assert!(expiration <= chrono::Utc::now() + chrono::Duration::hours(1));
+
+        let cert = openssl::x509::X509::from_pem(cert_response.certificate.as_bytes()).unwrap();
+
+        // So does the certificate itself.
+        let limit =
+            openssl::asn1::Asn1Time::from_unix(chrono::Utc::now().timestamp() + 60 * 60 + 60)
+                .unwrap();
+        assert_eq!(
+            std::cmp::Ordering::Less,
+            cert.not_after().compare(&limit).unwrap()
+        );
+
+        let sans: Vec<String> = cert
+            .subject_alt_names()
+            .unwrap()
+            .iter()
+            .filter_map(|san| san.dnsname().map(str::to_lowercase))
+            .collect();
+        assert!(sans.contains(&"hmi.local".to_string()));
+    }
 }
@@ -15,6 +15,7 @@ where
     gen_id: String,
     pid: libc::pid_t,
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    data_epochs: edgelet_http::DataEpochs,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -74,6 +75,7 @@ where
             gen_id: gen_id.into_owned(),
             pid,
             runtime: service.runtime.clone(),
+            data_epochs: service.data_epochs.clone(),
         })
     }
 
@@ -93,7 +95,7 @@ where
             None => return Err(edgelet_http::error::bad_request("missing request body")),
         };
 
-        let aad = format!("{}{}", self.module_id, self.gen_id).into_bytes();
+        let aad = self.data_epochs.aad(&self.module_id, &self.gen_id);
         let parameters = aziot_key_common::EncryptMechanism::Aead { iv, aad };
 
         let client = self.client.lock().await;
@@ -4,3 +4,4 @@ pub(super) mod list;
 
 pub(super) mod cert;
 pub(super) mod data;
+pub(super) mod secret;
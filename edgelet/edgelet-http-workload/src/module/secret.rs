@@ -0,0 +1,174 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[cfg(not(test))]
+use aziot_key_client_async::Client as KeyClient;
+
+#[cfg(test)]
+use test_common::client::KeyClient;
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
+    module_id: String,
+    name: String,
+    pid: libc::pid_t,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    secrets: edgelet_http::Secrets,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct SecretResponse {
+    value: String,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2022_08_03)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex = regex::Regex::new("^/modules/(?P<moduleId>[^/]+)/secrets/(?P<name>[^/]+)$")
+            .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module_id = &captures["moduleId"];
+        let module_id = percent_encoding::percent_decode_str(module_id)
+            .decode_utf8()
+            .ok()?;
+
+        let name = &captures["name"];
+        let name = percent_encoding::percent_decode_str(name)
+            .decode_utf8()
+            .ok()?;
+
+        let pid = match extensions.get::<Option<libc::pid_t>>().copied().flatten() {
+            Some(pid) => pid,
+            None => return None,
+        };
+
+        Some(Route {
+            client: service.key_client.clone(),
+            module_id: module_id.into_owned(),
+            name: name.into_owned(),
+            pid,
+            runtime: service.runtime.clone(),
+            secrets: service.secrets.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
+
+        let (iv, ciphertext) = match self.secrets.get(&self.module_id, &self.name) {
+            Some(secret) => secret,
+            None => {
+                return Err(http_common::server::Error {
+                    status_code: http::StatusCode::NOT_FOUND,
+                    message: format!("secret {} not found", self.name).into(),
+                })
+            }
+        };
+
+        let aad = edgelet_http::Secrets::aad(&self.module_id, &self.name);
+        let parameters = aziot_key_common::EncryptMechanism::Aead { iv, aad };
+
+        let client = self.client.lock().await;
+        let key = client
+            .create_key_if_not_exists(
+                edgelet_http::SECRETS_KEY_ID,
+                aziot_key_common::CreateKeyValue::Generate,
+                &[aziot_key_common::KeyUsage::Encrypt],
+            )
+            .await
+            .map_err(|err| {
+                edgelet_http::error::server_error(format!("unable to load secrets key: {}", err))
+            })?;
+
+        let value = client
+            .decrypt(&key, parameters, &ciphertext)
+            .await
+            .map_err(edgelet_http::error::server_error)?;
+        let value = String::from_utf8(value)
+            .map_err(|_| edgelet_http::error::server_error("secret is not valid UTF-8"))?;
+
+        Ok(http_common::server::response::json(
+            hyper::StatusCode::OK,
+            &SecretResponse { value },
+        ))
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    const TEST_PATH: &str = "/modules/testModule/secrets/password";
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(TEST_PATH);
+        assert_eq!("testModule", &route.module_id);
+        assert_eq!("password", &route.name);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+
+        // Missing module ID
+        test_route_err!("/modules//secrets/password");
+
+        // Missing secret name
+        test_route_err!("/modules/testModule/secrets/");
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", TEST_PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}/", TEST_PATH));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn get(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            route.get().await
+        }
+
+        edgelet_test_utils::test_auth_caller!(TEST_PATH, "testModule", get);
+    }
+
+    #[tokio::test]
+    async fn not_found() {
+        let route = test_route_ok!(TEST_PATH);
+
+        // Secrets of other modules aren't visible.
+        route
+            .secrets
+            .set("otherModule", "password", b"iv", b"ciphertext")
+            .unwrap();
+
+        let response = route.get().await.unwrap_err();
+        assert_eq!(hyper::StatusCode::NOT_FOUND, response.status_code);
+    }
+}
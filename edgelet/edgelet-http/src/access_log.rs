@@ -0,0 +1,254 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_settings::logging::{AccessLogLevel, AccessLogSettings};
+
+/// Log target for access log lines, so they can be filtered separately from other logs.
+const ACCESS_LOG_TARGET: &str = "edgelet_http::access";
+
+/// Runtime-adjustable access log configuration shared by all API listeners.
+#[derive(Clone, Default)]
+pub struct AccessLog {
+    settings: std::sync::Arc<std::sync::RwLock<AccessLogSettings>>,
+
+    // Sampling uses a running count of requests rather than random numbers, so that a
+    // rate of 0.1 logs exactly every tenth request. Requests are counted per configured
+    // endpoint prefix, keyed by the prefix or "" for the global rate, so that a busy
+    // endpoint doesn't shift which requests of other endpoints are logged.
+    counters: std::sync::Arc<std::sync::Mutex<std::collections::BTreeMap<String, u64>>>,
+}
+
+impl AccessLog {
+    pub fn new(settings: AccessLogSettings) -> Self {
+        AccessLog {
+            settings: std::sync::Arc::new(std::sync::RwLock::new(settings)),
+            counters: std::sync::Arc::default(),
+        }
+    }
+
+    pub fn settings(&self) -> AccessLogSettings {
+        self.settings
+            .read()
+            .expect("access log settings lock poisoned")
+            .clone()
+    }
+
+    pub fn set_settings(&self, settings: AccessLogSettings) {
+        *self
+            .settings
+            .write()
+            .expect("access log settings lock poisoned") = settings;
+    }
+
+    /// Wrap an API service so that its requests are logged.
+    pub fn wrap<S>(&self, api: &'static str, inner: S) -> AccessLogService<S> {
+        AccessLogService {
+            inner,
+            api,
+            access_log: self.clone(),
+        }
+    }
+
+    fn level(&self, path: &str) -> Option<log::Level> {
+        let (prefix, sample_rate, level) = {
+            let settings = self
+                .settings
+                .read()
+                .expect("access log settings lock poisoned");
+
+            if !settings.enabled {
+                return None;
+            }
+
+            let (sample_rate, level) = settings.for_path(path);
+            let prefix = settings
+                .endpoint_prefix(path)
+                .unwrap_or_default()
+                .to_string();
+
+            (prefix, sample_rate, level)
+        };
+
+        let level = match level {
+            AccessLogLevel::Off => return None,
+            AccessLogLevel::Debug => log::Level::Debug,
+            AccessLogLevel::Info => log::Level::Info,
+        };
+
+        if sample_rate <= 0.0 {
+            return None;
+        }
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let interval = (1.0 / sample_rate).round() as u64;
+        let count = {
+            let mut counters = self
+                .counters
+                .lock()
+                .expect("access log counters lock poisoned");
+            let counter = counters.entry(prefix).or_default();
+            let count = *counter;
+            *counter = counter.wrapping_add(1);
+
+            count
+        };
+
+        if count % interval.max(1) == 0 {
+            Some(level)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AccessLogService<S> {
+    inner: S,
+    api: &'static str,
+    access_log: AccessLog,
+}
+
+impl<S> hyper::service::Service<hyper::Request<hyper::Body>> for AccessLogService<S>
+where
+    S: hyper::service::Service<
+        hyper::Request<hyper::Body>,
+        Response = hyper::Response<hyper::Body>,
+        Error = std::convert::Infallible,
+    >,
+    S::Future: Send + 'static,
+{
+    type Response = hyper::Response<hyper::Body>;
+    type Error = std::convert::Infallible;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: hyper::Request<hyper::Body>) -> Self::Future {
+        let level = self.access_log.level(req.uri().path());
+        let correlation_id = crate::correlation::from_request(&req);
+
+        let request = level.map(|_| {
+            let pid = req
+                .extensions()
+                .get::<Option<libc::pid_t>>()
+                .copied()
+                .flatten();
+
+            (req.method().clone(), req.uri().path().to_string(), pid)
+        });
+
+        let api = self.api;
+        let start = std::time::Instant::now();
+        let response =
+            crate::correlation::sync_scope(correlation_id.clone(), || self.inner.call(req));
+
+        Box::pin(crate::correlation::scope(
+            correlation_id.clone(),
+            async move {
+                let mut response = response.await;
+
+                if let (Some(level), Some((method, path, pid)), Ok(response)) =
+                    (level, request, &response)
+                {
+                    let pid = pid.map_or_else(|| "-".to_string(), |pid| pid.to_string());
+
+                    log::log!(
+                        target: ACCESS_LOG_TARGET,
+                        level,
+                        "api={} method={} path={} status={} latency_ms={} pid={} correlation_id={}",
+                        api,
+                        method,
+                        path,
+                        response.status().as_u16(),
+                        start.elapsed().as_millis(),
+                        pid,
+                        correlation_id
+                    );
+                }
+
+                if let Ok(response) = &mut response {
+                    if let Ok(value) = hyper::header::HeaderValue::from_str(&correlation_id) {
+                        response
+                            .headers_mut()
+                            .insert(crate::CORRELATION_ID_HEADER, value);
+                    }
+                }
+
+                response
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_settings::logging::{AccessLogSettings, EndpointAccessLog};
+
+    use super::AccessLog;
+
+    #[test]
+    fn disabled() {
+        let access_log = AccessLog::default();
+
+        assert_eq!(None, access_log.level("/modules"));
+    }
+
+    #[test]
+    fn sampling() {
+        let access_log = AccessLog::new(AccessLogSettings {
+            enabled: true,
+            sample_rate: 0.25,
+            ..Default::default()
+        });
+
+        let logged = (0..100)
+            .filter(|_| access_log.level("/modules").is_some())
+            .count();
+        assert_eq!(25, logged);
+
+        // Settings can be changed at runtime.
+        access_log.set_settings(AccessLogSettings {
+            enabled: true,
+            sample_rate: 0.0,
+            ..Default::default()
+        });
+        assert_eq!(None, access_log.level("/modules"));
+    }
+
+    #[test]
+    fn sampling_per_endpoint() {
+        let mut settings = AccessLogSettings {
+            enabled: true,
+            sample_rate: 0.5,
+            ..Default::default()
+        };
+        settings.endpoints.insert(
+            "/modules".to_string(),
+            EndpointAccessLog {
+                sample_rate: Some(0.1),
+                level: None,
+            },
+        );
+        let access_log = AccessLog::new(settings);
+
+        // Requests to one endpoint don't change which requests to others are sampled.
+        for _ in 0..100 {
+            assert!(access_log.level("/trust-bundle").is_some());
+            assert!(access_log.level("/modules").is_some());
+            for _ in 0..9 {
+                assert!(access_log.level("/modules").is_none());
+            }
+            assert!(access_log.level("/trust-bundle").is_none());
+        }
+    }
+}
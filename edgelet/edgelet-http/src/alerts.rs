@@ -0,0 +1,133 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertStatus {
+    pub name: String,
+    pub firing: bool,
+
+    /// When the alert last fired or resolved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// What the condition found when the alert last fired.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Whether Edge Agent should include the alert in its reported properties.
+    pub report: bool,
+}
+
+/// The state of the alert rules evaluated on the device.
+#[derive(Clone, Default)]
+pub struct Alerts {
+    alerts: std::sync::Arc<std::sync::Mutex<Vec<AlertStatus>>>,
+}
+
+impl Alerts {
+    /// Alerts for the configured rules, none of which are firing yet.
+    pub fn new(rules: &[edgelet_settings::alerts::Rule]) -> Self {
+        let alerts = rules
+            .iter()
+            .map(|rule| AlertStatus {
+                name: rule.name().to_string(),
+                firing: false,
+                since: None,
+                message: None,
+                report: rule
+                    .actions()
+                    .contains(&edgelet_settings::alerts::Action::TwinReport),
+            })
+            .collect();
+
+        Alerts {
+            alerts: std::sync::Arc::new(std::sync::Mutex::new(alerts)),
+        }
+    }
+
+    pub fn list(&self) -> Vec<AlertStatus> {
+        self.alerts.lock().expect("alerts lock poisoned").clone()
+    }
+
+    /// Returns the alert as it now is, or `None` if it was already firing.
+    pub fn fire(&self, name: &str, message: &str) -> Option<AlertStatus> {
+        self.update(name, |alert| {
+            if alert.firing {
+                return false;
+            }
+
+            alert.firing = true;
+            alert.message = Some(message.to_string());
+
+            true
+        })
+    }
+
+    /// Returns the alert as it now is, or `None` if it wasn't firing.
+    pub fn resolve(&self, name: &str) -> Option<AlertStatus> {
+        self.update(name, |alert| {
+            let firing = alert.firing;
+            alert.firing = false;
+
+            firing
+        })
+    }
+
+    fn update(&self, name: &str, f: impl FnOnce(&mut AlertStatus) -> bool) -> Option<AlertStatus> {
+        let mut alerts = self.alerts.lock().expect("alerts lock poisoned");
+
+        let alert = alerts.iter_mut().find(|alert| alert.name == name)?;
+        if !f(alert) {
+            return None;
+        }
+
+        alert.since = Some(chrono::Utc::now());
+
+        Some(alert.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Alerts;
+
+    #[test]
+    fn fire_and_resolve() {
+        let rules: Vec<edgelet_settings::alerts::Rule> =
+            serde_json::from_value(serde_json::json!([
+                {
+                    "name": "disk",
+                    "condition": { "type": "disk_usage", "path": "/", "above_percent": 90 },
+                    "actions": [{ "type": "twin_report" }],
+                },
+                {
+                    "name": "edgehub-down",
+                    "condition": { "type": "module_down", "module": "edgeHub" },
+                    "actions": [{ "type": "event" }],
+                },
+            ]))
+            .unwrap();
+
+        let alerts = Alerts::new(&rules);
+
+        let list = alerts.list();
+        assert!(list.iter().all(|alert| !alert.firing));
+        assert!(list[0].report);
+        assert!(!list[1].report);
+
+        // Resolving an alert that isn't firing is a no-op.
+        assert!(alerts.resolve("disk").is_none());
+
+        let alert = alerts.fire("disk", "95% used").unwrap();
+        assert!(alert.firing);
+        assert_eq!(Some("95% used".to_string()), alert.message);
+
+        // Actions are taken only once while the alert keeps firing.
+        assert!(alerts.fire("disk", "96% used").is_none());
+
+        let alert = alerts.resolve("disk").unwrap();
+        assert!(!alert.firing);
+
+        assert!(alerts.fire("unknown", "").is_none());
+    }
+}
@@ -0,0 +1,473 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::Write;
+
+/// Size of the audit log file at which the oldest events are dropped.
+const MAX_LOG_SIZE: usize = 4 * 1024 * 1024;
+
+/// Size the audit log file is trimmed to once it reaches `MAX_LOG_SIZE`, so that it isn't
+/// rewritten on every event after that.
+const TRIMMED_LOG_SIZE: usize = MAX_LOG_SIZE * 3 / 4;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditEventType {
+    ModuleCreated,
+    ModuleUpdated,
+    ModuleStarted,
+    ModuleStopped,
+    ModuleRestarted,
+    ModuleRemoved,
+    ImageGarbageCollection,
+    Reprovision,
+    WatchdogRestart,
+
+    /// A request to the management API that changes state.
+    ApiCall,
+}
+
+impl std::str::FromStr for AuditEventType {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.to_string()))
+    }
+}
+
+/// Who caused an event, for events caused by an API call.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditCaller {
+    /// The API the call was made on, e.g. "management".
+    pub api: String,
+
+    /// The listener the call was made on, e.g. the management socket's URI.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listener: Option<String>,
+
+    /// The calling process. This is unknown for callers on TCP listeners.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<libc::pid_t>,
+}
+
+impl AuditCaller {
+    /// The caller of a request on a listener wrapped by [`AuditLog::wrap`].
+    pub fn from_extensions(extensions: &hyper::http::Extensions) -> Option<Self> {
+        extensions.get::<AuditCaller>().cloned()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    pub time: chrono::DateTime<chrono::Utc>,
+
+    #[serde(rename = "type")]
+    pub event_type: AuditEventType,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub module: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caller: Option<AuditCaller>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(event_type: AuditEventType) -> Self {
+        AuditEvent {
+            time: chrono::Utc::now(),
+            event_type,
+            module: None,
+            caller: None,
+            details: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_module(mut self, module: impl Into<String>) -> Self {
+        self.module = Some(module.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_caller(mut self, caller: Option<AuditCaller>) -> Self {
+        self.caller = caller;
+        self
+    }
+
+    #[must_use]
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+}
+
+#[derive(Default)]
+struct Log {
+    /// Events oldest first, each with its size in the file.
+    events: std::collections::VecDeque<(AuditEvent, usize)>,
+    size: usize,
+}
+
+/// A write to the audit log file.
+enum Write {
+    Append(String),
+    Rewrite(String),
+
+    /// Signals once the writes before it are done.
+    #[cfg(test)]
+    Flush(std::sync::mpsc::SyncSender<()>),
+}
+
+/// A trail of daemon events and state-changing API calls for security reviews.
+///
+/// Events are appended to a file in the home directory as JSON lines. The oldest events
+/// are dropped when the file reaches `MAX_LOG_SIZE`. The file is written by a thread of
+/// its own, so that recording an event never waits on the disk.
+#[derive(Clone, Default)]
+pub struct AuditLog {
+    log: std::sync::Arc<std::sync::Mutex<Log>>,
+    writer: Option<std::sync::mpsc::Sender<Write>>,
+}
+
+impl AuditLog {
+    pub fn new(path: std::path::PathBuf) -> std::io::Result<Self> {
+        let mut log = Log::default();
+
+        match std::fs::read_to_string(&path) {
+            Ok(events) => {
+                for line in events.lines() {
+                    // A line may be cut short if the daemon stopped while writing it.
+                    match serde_json::from_str(line) {
+                        Ok(event) => {
+                            log.size += line.len() + 1;
+                            log.events.push_back((event, line.len() + 1));
+                        }
+                        Err(err) => log::warn!("Discarding invalid audit log entry: {}", err),
+                    }
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => (),
+            Err(err) if err.kind() == std::io::ErrorKind::InvalidData => {
+                crate::persist::set_aside(&path, "audit log", &err);
+            }
+            Err(err) => return Err(err),
+        }
+
+        let (writer, writes) = std::sync::mpsc::channel();
+        std::thread::Builder::new()
+            .name("audit-log".to_string())
+            .spawn(move || write_file(&path, &writes))?;
+
+        Ok(AuditLog {
+            log: std::sync::Arc::new(std::sync::Mutex::new(log)),
+            writer: Some(writer),
+        })
+    }
+
+    pub fn record(&self, event: AuditEvent) {
+        let mut line = serde_json::to_string(&event).expect("audit events must serialize");
+        line.push('\n');
+
+        let mut log = self.log.lock().expect("audit log lock poisoned");
+
+        log.size += line.len();
+        log.events.push_back((event, line.len()));
+
+        if log.size > MAX_LOG_SIZE {
+            while log.size > TRIMMED_LOG_SIZE {
+                let (_, size) = log.events.pop_front().expect("log size counts its events");
+                log.size -= size;
+            }
+
+            self.rewrite(&log);
+        } else {
+            self.write(Write::Append(line));
+        }
+    }
+
+    /// Events at or after `since` and of type `event_type`, oldest first. Either filter
+    /// may be omitted.
+    pub fn query(
+        &self,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        event_type: Option<AuditEventType>,
+    ) -> Vec<AuditEvent> {
+        let log = self.log.lock().expect("audit log lock poisoned");
+
+        log.events
+            .iter()
+            .map(|(event, _)| event)
+            .filter(|event| since.map_or(true, |since| event.time >= since))
+            .filter(|event| event_type.map_or(true, |event_type| event.event_type == event_type))
+            .cloned()
+            .collect()
+    }
+
+    /// Wrap the service of an API listener so that its state-changing requests are
+    /// recorded. `listener` identifies the listener in the recorded caller.
+    pub fn wrap<S>(&self, api: &'static str, listener: String, inner: S) -> AuditLogService<S> {
+        AuditLogService {
+            inner,
+            api,
+            listener,
+            audit_log: self.clone(),
+        }
+    }
+
+    /// Wait for the events recorded so far to be written.
+    #[cfg(test)]
+    fn flush(&self) {
+        let (done_tx, done_rx) = std::sync::mpsc::sync_channel(1);
+        self.write(Write::Flush(done_tx));
+        let _ = done_rx.recv();
+    }
+
+    /// Queue a write. This is called with the log locked, so that writes are queued in
+    /// the order of the events.
+    fn write(&self, write: Write) {
+        if let Some(writer) = &self.writer {
+            if writer.send(write).is_err() {
+                log::warn!("Failed to save audit log: writer stopped");
+            }
+        }
+    }
+
+    fn rewrite(&self, log: &Log) {
+        if self.writer.is_some() {
+            let mut events = String::with_capacity(log.size);
+            for (event, _) in &log.events {
+                events
+                    .push_str(&serde_json::to_string(event).expect("audit events must serialize"));
+                events.push('\n');
+            }
+
+            self.write(Write::Rewrite(events));
+        }
+    }
+}
+
+fn write_file(path: &std::path::Path, writes: &std::sync::mpsc::Receiver<Write>) {
+    // The loop ends when every handle to the log has been dropped.
+    while let Ok(write) = writes.recv() {
+        let result = match write {
+            Write::Append(line) => std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(line.as_bytes())),
+            Write::Rewrite(events) => crate::persist::write(path, events.as_bytes()),
+
+            #[cfg(test)]
+            Write::Flush(done) => {
+                let _ = done.send(());
+
+                Ok(())
+            }
+        };
+
+        if let Err(err) = result {
+            log::warn!("Failed to save audit log: {}", err);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuditLogService<S> {
+    inner: S,
+    api: &'static str,
+    listener: String,
+    audit_log: AuditLog,
+}
+
+impl<S> hyper::service::Service<hyper::Request<hyper::Body>> for AuditLogService<S>
+where
+    S: hyper::service::Service<
+        hyper::Request<hyper::Body>,
+        Response = hyper::Response<hyper::Body>,
+        Error = std::convert::Infallible,
+    >,
+    S::Future: Send + 'static,
+{
+    type Response = hyper::Response<hyper::Body>;
+    type Error = std::convert::Infallible;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: hyper::Request<hyper::Body>) -> Self::Future {
+        let caller = AuditCaller {
+            api: self.api.to_string(),
+            listener: Some(self.listener.clone()),
+            pid: req
+                .extensions()
+                .get::<Option<libc::pid_t>>()
+                .copied()
+                .flatten(),
+        };
+
+        // Reads don't change state, so they aren't recorded.
+        let request = if req.method() == hyper::Method::GET || req.method() == hyper::Method::HEAD {
+            None
+        } else {
+            Some((
+                req.method().clone(),
+                req.uri().path().to_string(),
+                caller.clone(),
+            ))
+        };
+
+        // Routes record the caller of the events they cause.
+        req.extensions_mut().insert(caller);
+
+        let audit_log = self.audit_log.clone();
+        let response = self.inner.call(req);
+
+        Box::pin(async move {
+            let response = response.await;
+
+            if let (Some((method, path, caller)), Ok(response)) = (request, &response) {
+                audit_log.record(
+                    AuditEvent::new(AuditEventType::ApiCall)
+                        .with_caller(Some(caller))
+                        .with_details(format!(
+                            "{} {} {}",
+                            method,
+                            path,
+                            response.status().as_u16()
+                        )),
+                );
+            }
+
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuditCaller, AuditEvent, AuditEventType, AuditLog};
+
+    #[test]
+    fn query() {
+        let log = AuditLog::default();
+
+        let mut old = AuditEvent::new(AuditEventType::ModuleCreated).with_module("sensor");
+        old.time -= chrono::Duration::hours(1);
+        log.record(old);
+
+        log.record(AuditEvent::new(AuditEventType::ModuleStarted).with_module("sensor"));
+        log.record(AuditEvent::new(AuditEventType::Reprovision));
+
+        assert_eq!(3, log.query(None, None).len());
+
+        let since = chrono::Utc::now() - chrono::Duration::minutes(1);
+        let events = log.query(Some(since), None);
+        assert_eq!(
+            vec![AuditEventType::ModuleStarted, AuditEventType::Reprovision],
+            events.iter().map(|e| e.event_type).collect::<Vec<_>>()
+        );
+
+        let events = log.query(None, Some(AuditEventType::ModuleCreated));
+        assert_eq!(1, events.len());
+        assert_eq!(Some("sensor".to_string()), events[0].module);
+
+        assert_eq!(
+            AuditEventType::WatchdogRestart,
+            "watchdogRestart".parse().unwrap()
+        );
+        "moduleExploded".parse::<AuditEventType>().unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn service_records_caller() {
+        let log = AuditLog::default();
+
+        let inner = hyper::service::service_fn(|req: hyper::Request<hyper::Body>| async move {
+            let caller = AuditCaller::from_extensions(req.extensions()).unwrap();
+            assert_eq!(Some(1234), caller.pid);
+
+            Ok::<_, std::convert::Infallible>(hyper::Response::new(hyper::Body::empty()))
+        });
+        let mut service = log.wrap("management", "tcp://0.0.0.0:15580".to_string(), inner);
+
+        for method in [hyper::Method::GET, hyper::Method::POST] {
+            let mut req = hyper::Request::builder()
+                .method(method)
+                .uri("/modules/sensor/stop")
+                .body(hyper::Body::empty())
+                .unwrap();
+            req.extensions_mut().insert(Some::<libc::pid_t>(1234));
+
+            hyper::service::Service::call(&mut service, req)
+                .await
+                .unwrap();
+        }
+
+        // Only the POST is recorded.
+        let events = log.query(None, None);
+        assert_eq!(1, events.len());
+        assert_eq!(
+            Some(AuditCaller {
+                api: "management".to_string(),
+                listener: Some("tcp://0.0.0.0:15580".to_string()),
+                pid: Some(1234),
+            }),
+            events[0].caller
+        );
+        assert_eq!(
+            Some("POST /modules/sensor/stop 200"),
+            events[0].details.as_deref()
+        );
+    }
+
+    #[test]
+    fn persisted_and_bounded() {
+        let dir = std::env::temp_dir().join(format!("audit-log-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.log");
+        let _ = std::fs::remove_file(&path);
+
+        let log = AuditLog::new(path.clone()).unwrap();
+        log.record(
+            AuditEvent::new(AuditEventType::ApiCall)
+                .with_caller(Some(AuditCaller {
+                    api: "management".to_string(),
+                    listener: Some("unix:///var/run/iotedge/mgmt.sock".to_string()),
+                    pid: Some(1234),
+                }))
+                .with_details("POST /modules/sensor/stop 204"),
+        );
+        log.flush();
+
+        let events = AuditLog::new(path.clone()).unwrap().query(None, None);
+        assert_eq!(1, events.len());
+        assert_eq!(Some(1234), events[0].caller.as_ref().unwrap().pid);
+
+        let details = "x".repeat(1024);
+        for _ in 0..(super::MAX_LOG_SIZE / 1024) {
+            log.record(AuditEvent::new(AuditEventType::ApiCall).with_details(&details));
+        }
+        log.flush();
+
+        let size = usize::try_from(std::fs::metadata(&path).unwrap().len()).unwrap();
+        assert!(size <= super::MAX_LOG_SIZE);
+
+        // The oldest events were dropped, and the file matches what's kept in memory.
+        let events = AuditLog::new(path.clone()).unwrap().query(None, None);
+        assert_eq!(log.query(None, None), events);
+        assert_eq!(Some(details.as_str()), events[0].details.as_deref());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,410 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Number of changes to keep. Readers that fall further behind than this must resync.
+const MAX_CHANGES: usize = 1000;
+
+/// Module settings that the feed records. Other settings, such as registry credentials
+/// and create options, may hold secrets and are never persisted or served.
+const MODULE_SETTINGS: [&str; 3] = ["image", "imageHash", "digest"];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Module,
+    FeatureFlag,
+    Alert,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeEvent {
+    Created,
+    Removed,
+    StatusChanged,
+    ConfigChanged,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    /// Position of this change in the feed. Passing it back as `from` resumes the feed
+    /// after this change.
+    pub token: u64,
+
+    pub time: chrono::DateTime<chrono::Utc>,
+    pub kind: ChangeKind,
+    pub name: String,
+    pub event: ChangeEvent,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+
+    /// The new configuration: a module's image settings, or a feature flag's overridden
+    /// value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<serde_json::Value>,
+}
+
+impl Change {
+    pub fn new(kind: ChangeKind, name: impl Into<String>, event: ChangeEvent) -> Self {
+        Change {
+            token: 0,
+            time: chrono::Utc::now(),
+            kind,
+            name: name.into(),
+            event,
+            status: None,
+            config: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_status(mut self, status: impl Into<String>) -> Self {
+        self.status = Some(status.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_config(mut self, config: serde_json::Value) -> Self {
+        self.config = Some(config);
+        self
+    }
+}
+
+/// The state of a module as last seen by the feed, used to tell what changed.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+struct ModuleState {
+    status: String,
+    config: serde_json::Value,
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+struct Feed {
+    /// Token of the most recent change. Kept separately from `changes` so that tokens
+    /// keep increasing after old changes are dropped.
+    latest: u64,
+    changes: std::collections::VecDeque<Change>,
+    modules: std::collections::BTreeMap<String, ModuleState>,
+}
+
+#[derive(Debug)]
+pub enum ChangeFeedError {
+    /// The token is older than the oldest change kept, or wasn't issued by this feed.
+    /// The reader must resync from the current state.
+    Expired(u64),
+}
+
+impl std::fmt::Display for ChangeFeedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeFeedError::Expired(token) => write!(
+                f,
+                "token {token} has expired; list modules and resume from the latest token"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ChangeFeedError {}
+
+/// Ordered module and configuration changes, so that a reader can mirror the device's
+/// state by resuming from the last token it saw.
+///
+/// The feed is persisted in the home directory so that tokens remain valid across
+/// daemon restarts.
+#[derive(Clone, Default)]
+pub struct ChangeFeed {
+    feed: std::sync::Arc<std::sync::Mutex<Feed>>,
+    path: Option<std::path::PathBuf>,
+}
+
+impl ChangeFeed {
+    pub fn new(path: std::path::PathBuf) -> std::io::Result<Self> {
+        let mut feed: Feed = crate::persist::read_json(&path, "change feed")?.unwrap_or_default();
+
+        // Feeds saved by earlier versions recorded all of a module's settings.
+        for change in &mut feed.changes {
+            if change.kind == ChangeKind::Module {
+                change.config = change.config.as_ref().map(module_settings);
+            }
+        }
+        for state in feed.modules.values_mut() {
+            state.config = module_settings(&state.config);
+        }
+
+        Ok(ChangeFeed {
+            feed: std::sync::Arc::new(std::sync::Mutex::new(feed)),
+            path: Some(path),
+        })
+    }
+
+    /// Token of the most recent change.
+    pub fn latest(&self) -> u64 {
+        self.feed.lock().expect("change feed lock poisoned").latest
+    }
+
+    /// Changes after `from`, oldest first. Without `from`, all changes kept are returned.
+    pub fn since(&self, from: Option<u64>) -> Result<Vec<Change>, ChangeFeedError> {
+        let feed = self.feed.lock().expect("change feed lock poisoned");
+
+        let Some(from) = from else {
+            return Ok(feed.changes.iter().cloned().collect());
+        };
+
+        let oldest = feed
+            .changes
+            .front()
+            .map_or(feed.latest, |change| change.token - 1);
+        if from < oldest || from > feed.latest {
+            return Err(ChangeFeedError::Expired(from));
+        }
+
+        Ok(feed
+            .changes
+            .iter()
+            .filter(|change| change.token > from)
+            .cloned()
+            .collect())
+    }
+
+    pub fn record(&self, change: Change) {
+        let mut feed = self.feed.lock().expect("change feed lock poisoned");
+
+        push(&mut feed, change);
+        self.persist(&feed);
+    }
+
+    /// Record how the modules differ from when they were last observed.
+    pub fn observe_modules(&self, modules: &[crate::ModuleDetails]) {
+        let mut feed = self.feed.lock().expect("change feed lock poisoned");
+
+        let mut current = std::collections::BTreeMap::new();
+        for module in modules {
+            current.insert(
+                module.name.clone(),
+                ModuleState {
+                    status: module.status.runtime_status.status.clone(),
+                    config: module_settings(&module.config.settings),
+                },
+            );
+        }
+
+        if current == feed.modules {
+            return;
+        }
+
+        let previous = std::mem::take(&mut feed.modules);
+
+        for (name, state) in &current {
+            let change = match previous.get(name) {
+                None => Change::new(ChangeKind::Module, name, ChangeEvent::Created)
+                    .with_status(&state.status)
+                    .with_config(state.config.clone()),
+                Some(last) if last.config != state.config => {
+                    Change::new(ChangeKind::Module, name, ChangeEvent::ConfigChanged)
+                        .with_status(&state.status)
+                        .with_config(state.config.clone())
+                }
+                Some(last) if last.status != state.status => {
+                    Change::new(ChangeKind::Module, name, ChangeEvent::StatusChanged)
+                        .with_status(&state.status)
+                }
+                Some(_) => continue,
+            };
+
+            push(&mut feed, change);
+        }
+
+        for name in previous.keys() {
+            if !current.contains_key(name) {
+                push(
+                    &mut feed,
+                    Change::new(ChangeKind::Module, name, ChangeEvent::Removed),
+                );
+            }
+        }
+
+        feed.modules = current;
+        self.persist(&feed);
+    }
+
+    fn persist(&self, feed: &Feed) {
+        if let Some(path) = &self.path {
+            if let Err(err) = crate::persist::write_json(path, feed) {
+                log::warn!("Failed to save change feed: {}", err);
+            }
+        }
+    }
+}
+
+/// The subset of a module's settings in `MODULE_SETTINGS`.
+fn module_settings(settings: &serde_json::Value) -> serde_json::Value {
+    let mut filtered = serde_json::Map::new();
+
+    if let Some(settings) = settings.as_object() {
+        for key in MODULE_SETTINGS {
+            if let Some(value) = settings.get(key) {
+                filtered.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+
+    serde_json::Value::Object(filtered)
+}
+
+fn push(feed: &mut Feed, mut change: Change) {
+    feed.latest += 1;
+    change.token = feed.latest;
+
+    feed.changes.push_back(change);
+
+    if feed.changes.len() > MAX_CHANGES {
+        feed.changes.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Change, ChangeEvent, ChangeFeed, ChangeKind};
+
+    fn module(name: &str, status: &str, image: &str) -> crate::ModuleDetails {
+        serde_json::from_value(serde_json::json!({
+            "id": "id",
+            "name": name,
+            "type": "docker",
+            "config": { "settings": { "image": image } },
+            "status": { "runtimeStatus": { "status": status } },
+        }))
+        .unwrap()
+    }
+
+    fn events(changes: &[Change]) -> Vec<(u64, &str, ChangeEvent)> {
+        changes
+            .iter()
+            .map(|change| (change.token, change.name.as_str(), change.event))
+            .collect()
+    }
+
+    #[test]
+    fn modules() {
+        let feed = ChangeFeed::default();
+
+        feed.observe_modules(&[module("edgeAgent", "stopped", "agent:1.4")]);
+        feed.observe_modules(&[module("edgeAgent", "stopped", "agent:1.4")]);
+        feed.observe_modules(&[
+            module("edgeAgent", "running", "agent:1.4"),
+            module("edgeHub", "stopped", "hub:1.4"),
+        ]);
+        feed.observe_modules(&[module("edgeAgent", "running", "agent:1.5")]);
+
+        let changes = feed.since(None).unwrap();
+        assert_eq!(
+            vec![
+                (1, "edgeAgent", ChangeEvent::Created),
+                (2, "edgeAgent", ChangeEvent::StatusChanged),
+                (3, "edgeHub", ChangeEvent::Created),
+                (4, "edgeAgent", ChangeEvent::ConfigChanged),
+                (5, "edgeHub", ChangeEvent::Removed),
+            ],
+            events(&changes)
+        );
+        assert_eq!(Some("running".to_string()), changes[1].status);
+        assert_eq!(
+            Some(serde_json::json!({ "image": "agent:1.5" })),
+            changes[3].config
+        );
+
+        assert_eq!(5, feed.latest());
+    }
+
+    #[test]
+    fn module_secrets() {
+        let feed = ChangeFeed::default();
+
+        let agent = |password: &str| {
+            let mut agent = module("edgeAgent", "running", "agent:1.4");
+            agent.config.settings = serde_json::json!({
+                "image": "agent:1.4",
+                "auth": { "username": "user", "password": password },
+                "createOptions": { "Env": ["KEY=secret"] },
+            });
+            agent
+        };
+        feed.observe_modules(&[agent("secret")]);
+
+        // Changes to settings other than the image's aren't recorded.
+        feed.observe_modules(&[agent("rotated")]);
+
+        let changes = feed.since(None).unwrap();
+        assert_eq!(1, changes.len());
+        assert_eq!(
+            Some(serde_json::json!({ "image": "agent:1.4" })),
+            changes[0].config
+        );
+    }
+
+    #[test]
+    fn resume() {
+        let feed = ChangeFeed::default();
+
+        // A reader that starts before any changes can resume from token 0.
+        assert!(feed.since(Some(0)).unwrap().is_empty());
+
+        for name in ["a", "b", "c"] {
+            feed.record(Change::new(
+                ChangeKind::FeatureFlag,
+                name,
+                ChangeEvent::ConfigChanged,
+            ));
+        }
+
+        assert_eq!(
+            vec![
+                (2, "b", ChangeEvent::ConfigChanged),
+                (3, "c", ChangeEvent::ConfigChanged)
+            ],
+            events(&feed.since(Some(1)).unwrap())
+        );
+        assert!(feed.since(Some(3)).unwrap().is_empty());
+
+        // Not issued by this feed.
+        feed.since(Some(4)).unwrap_err();
+    }
+
+    #[test]
+    fn expired() {
+        let feed = ChangeFeed::default();
+
+        for _ in 0..=super::MAX_CHANGES {
+            feed.record(Change::new(
+                ChangeKind::FeatureFlag,
+                "flag",
+                ChangeEvent::ConfigChanged,
+            ));
+        }
+
+        // Change 1 was dropped, so a reader at token 0 would miss it.
+        feed.since(Some(0)).unwrap_err();
+        assert_eq!(super::MAX_CHANGES, feed.since(Some(1)).unwrap().len());
+    }
+
+    #[test]
+    fn persisted() {
+        let dir = std::env::temp_dir().join(format!("change-feed-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("changes.json");
+        let _ = std::fs::remove_file(&path);
+
+        let feed = ChangeFeed::new(path.clone()).unwrap();
+        feed.observe_modules(&[module("edgeAgent", "running", "agent:1.4")]);
+
+        // Tokens and the last seen module state survive a restart.
+        let feed = ChangeFeed::new(path.clone()).unwrap();
+        assert_eq!(1, feed.latest());
+
+        feed.observe_modules(&[module("edgeAgent", "running", "agent:1.4")]);
+        assert_eq!(1, feed.latest());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
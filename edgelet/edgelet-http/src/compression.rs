@@ -0,0 +1,249 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::Write;
+
+/// Responses smaller than this aren't worth the CPU time of compressing them.
+const MIN_COMPRESS_SIZE: u64 = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+
+    fn encode(self, body: &[u8]) -> std::io::Result<Vec<u8>> {
+        let level = flate2::Compression::fast();
+
+        match self {
+            Encoding::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), level);
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+            Encoding::Deflate => {
+                let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), level);
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Choose the encoding of a response from the request's `Accept-Encoding`.
+///
+/// The encoding with the highest quality wins, with gzip preferred on ties. Encodings
+/// with a quality of 0 are refused.
+fn negotiate(accept_encoding: &str) -> Option<Encoding> {
+    let mut best: Option<(Encoding, f32)> = None;
+
+    for item in accept_encoding.split(',') {
+        let mut params = item.split(';');
+
+        let encoding = match params.next().map(str::trim) {
+            Some(coding) if coding.eq_ignore_ascii_case("gzip") => Encoding::Gzip,
+            Some(coding) if coding.eq_ignore_ascii_case("deflate") => Encoding::Deflate,
+            _ => continue,
+        };
+
+        let quality = params
+            .find_map(|param| param.trim().strip_prefix("q="))
+            .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok());
+        let quality = match quality {
+            Some(quality) if quality > 0.0 => quality,
+            _ => continue,
+        };
+
+        let is_better = best.map_or(true, |(_, best_quality)| {
+            quality > best_quality || (encoding == Encoding::Gzip && quality >= best_quality)
+        });
+        if is_better {
+            best = Some((encoding, quality));
+        }
+    }
+
+    best.map(|(encoding, _)| encoding)
+}
+
+/// Whether the response is a complete JSON or text document worth compressing.
+///
+/// Streamed responses such as followed module logs have no known length and are
+/// passed through, since they must not be buffered.
+fn is_compressible(response: &hyper::Response<hyper::Body>) -> bool {
+    use hyper::body::HttpBody;
+
+    if response
+        .headers()
+        .contains_key(hyper::header::CONTENT_ENCODING)
+    {
+        return false;
+    }
+
+    let is_document = response
+        .headers()
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map_or(false, |content_type| {
+            content_type.starts_with("application/json") || content_type.starts_with("text/")
+        });
+
+    let size = response.body().size_hint().exact();
+
+    is_document && size.map_or(false, |size| size >= MIN_COMPRESS_SIZE)
+}
+
+/// Compresses API responses with gzip or deflate when the caller accepts them.
+#[derive(Clone)]
+pub struct CompressionService<S> {
+    inner: S,
+}
+
+impl<S> CompressionService<S> {
+    pub fn new(inner: S) -> Self {
+        CompressionService { inner }
+    }
+}
+
+impl<S> hyper::service::Service<hyper::Request<hyper::Body>> for CompressionService<S>
+where
+    S: hyper::service::Service<
+        hyper::Request<hyper::Body>,
+        Response = hyper::Response<hyper::Body>,
+        Error = std::convert::Infallible,
+    >,
+    S::Future: Send + 'static,
+{
+    type Response = hyper::Response<hyper::Body>;
+    type Error = std::convert::Infallible;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: hyper::Request<hyper::Body>) -> Self::Future {
+        let encoding = req
+            .headers()
+            .get(hyper::header::ACCEPT_ENCODING)
+            .and_then(|accept_encoding| accept_encoding.to_str().ok())
+            .and_then(negotiate);
+
+        let response = self.inner.call(req);
+
+        Box::pin(async move {
+            let response = response.await?;
+
+            let encoding = match encoding {
+                Some(encoding) if is_compressible(&response) => encoding,
+                _ => return Ok(response),
+            };
+
+            let (mut parts, body) = response.into_parts();
+
+            let body = match hyper::body::to_bytes(body).await {
+                Ok(body) => body,
+                Err(err) => {
+                    log::warn!("Failed to read response body for compression: {}", err);
+
+                    let body = serde_json::json!({ "message": "internal server error" });
+
+                    return Ok(hyper::Response::builder()
+                        .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                        .header(hyper::header::CONTENT_TYPE, "application/json")
+                        .body(body.to_string().into())
+                        .expect("cannot fail to build hyper response"));
+                }
+            };
+
+            let body = match encoding.encode(&body) {
+                Ok(encoded) => {
+                    parts.headers.insert(
+                        hyper::header::CONTENT_ENCODING,
+                        hyper::header::HeaderValue::from_static(encoding.as_str()),
+                    );
+                    parts.headers.insert(
+                        hyper::header::CONTENT_LENGTH,
+                        hyper::header::HeaderValue::from(encoded.len()),
+                    );
+
+                    encoded
+                }
+
+                // The response can still be sent as is.
+                Err(err) => {
+                    log::warn!("Failed to compress response: {}", err);
+
+                    body.to_vec()
+                }
+            };
+
+            parts.headers.insert(
+                hyper::header::VARY,
+                hyper::header::HeaderValue::from_static("accept-encoding"),
+            );
+
+            Ok(hyper::Response::from_parts(parts, hyper::Body::from(body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::{negotiate, Encoding};
+
+    #[test]
+    fn negotiation() {
+        assert_eq!(Some(Encoding::Gzip), negotiate("gzip"));
+        assert_eq!(Some(Encoding::Deflate), negotiate("deflate"));
+        assert_eq!(Some(Encoding::Gzip), negotiate("deflate, gzip"));
+        assert_eq!(Some(Encoding::Gzip), negotiate("GZIP, br"));
+        assert_eq!(Some(Encoding::Deflate), negotiate("gzip;q=0.5, deflate"));
+        assert_eq!(
+            Some(Encoding::Deflate),
+            negotiate("gzip;q=0, deflate;q=0.1")
+        );
+
+        assert_eq!(None, negotiate(""));
+        assert_eq!(None, negotiate("identity"));
+        assert_eq!(None, negotiate("br, *"));
+        assert_eq!(None, negotiate("gzip;q=0"));
+        assert_eq!(None, negotiate("gzip;q=invalid"));
+    }
+
+    #[test]
+    fn encode() {
+        let body = b"{\"certificate\":\"-----BEGIN CERTIFICATE-----\"}".repeat(100);
+
+        let encoded = Encoding::Gzip.encode(&body).unwrap();
+        assert!(encoded.len() < body.len());
+
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(encoded.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(body, decoded);
+
+        let encoded = Encoding::Deflate.encode(&body).unwrap();
+        assert!(encoded.len() < body.len());
+
+        let mut decoded = Vec::new();
+        flate2::read::ZlibDecoder::new(encoded.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(body, decoded);
+    }
+}
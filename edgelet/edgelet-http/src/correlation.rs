@@ -0,0 +1,92 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Header that carries the correlation ID of an API request. A caller's ID is kept, so
+/// that its logs can be joined with the daemon's; requests without one are given one.
+/// The ID is returned in the same header of the response.
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+const MAX_LEN: usize = 64;
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+/// The correlation ID of the API request that the current task is handling.
+pub fn correlation_id() -> Option<String> {
+    CORRELATION_ID.try_with(Clone::clone).ok()
+}
+
+/// Run a future as part of the request with the given correlation ID.
+pub(crate) async fn scope<F>(id: String, f: F) -> F::Output
+where
+    F: std::future::Future,
+{
+    CORRELATION_ID.scope(id, f).await
+}
+
+/// Run a function as part of the request with the given correlation ID.
+pub(crate) fn sync_scope<R>(id: String, f: impl FnOnce() -> R) -> R {
+    CORRELATION_ID.sync_scope(id, f)
+}
+
+/// The caller's correlation ID, if it's a reasonable one, or a new one.
+pub(crate) fn from_request(req: &hyper::Request<hyper::Body>) -> String {
+    req.headers()
+        .get(CORRELATION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_LEN
+                && id
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.')
+        })
+        .map_or_else(new_id, ToString::to_string)
+}
+
+fn new_id() -> String {
+    let mut id = [0; 8];
+    openssl::rand::rand_bytes(&mut id).expect("random bytes should be available");
+
+    id.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{correlation_id, from_request, scope, CORRELATION_ID_HEADER};
+
+    fn request(id: Option<&str>) -> hyper::Request<hyper::Body> {
+        let mut request = hyper::Request::get("/modules");
+        if let Some(id) = id {
+            request = request.header(CORRELATION_ID_HEADER, id);
+        }
+
+        request.body(hyper::Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn caller_id() {
+        assert_eq!("3f2c-agent.1", from_request(&request(Some("3f2c-agent.1"))));
+
+        // Unreasonable IDs are replaced, so they can't be used to forge log lines.
+        let forged = from_request(&request(Some("a\" level=\"error")));
+        assert_eq!(16, forged.len());
+
+        let long = "a".repeat(65);
+        assert_ne!(long, from_request(&request(Some(&long))));
+
+        let id = from_request(&request(None));
+        assert_eq!(16, id.len());
+        assert_ne!(id, from_request(&request(None)));
+    }
+
+    #[tokio::test]
+    async fn scoped() {
+        assert_eq!(None, correlation_id());
+
+        let id = scope("abc".to_string(), async { correlation_id() }).await;
+        assert_eq!(Some("abc".to_string()), id);
+
+        assert_eq!(None, correlation_id());
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+type Epochs = std::collections::BTreeMap<String, u64>;
+
+/// Per-module epochs for data encrypted through the workload API.
+///
+/// A module's epoch is part of the additional authenticated data used when it
+/// encrypts, so advancing the epoch makes everything the module encrypted earlier
+/// impossible to decrypt without affecting other modules.
+///
+/// Epochs must never go back, or purged data would be readable again, so they are kept
+/// in two copies, the second with a `.bak` suffix. A copy that is missing or corrupt is
+/// restored from the other, and a copy that can't be read at all fails startup rather
+/// than being ignored.
+#[derive(Clone, Default)]
+pub struct DataEpochs {
+    epochs: std::sync::Arc<std::sync::RwLock<Epochs>>,
+    path: Option<std::path::PathBuf>,
+}
+
+impl DataEpochs {
+    pub fn new(path: std::path::PathBuf) -> std::io::Result<Self> {
+        // A write may have been interrupted between the copies, so the later epoch of each
+        // module wins.
+        let mut epochs = Epochs::new();
+        for copy_path in [path.clone(), backup_path(&path)] {
+            let copy: Epochs =
+                crate::persist::read_json(&copy_path, "data epochs")?.unwrap_or_default();
+
+            for (module, epoch) in copy {
+                let current = epochs.entry(module).or_default();
+                *current = (*current).max(epoch);
+            }
+        }
+
+        if !epochs.is_empty() {
+            persist(&path, &epochs)?;
+        }
+
+        Ok(DataEpochs {
+            epochs: std::sync::Arc::new(std::sync::RwLock::new(epochs)),
+            path: Some(path),
+        })
+    }
+
+    /// Additional authenticated data for a module's workload encryption.
+    pub fn aad(&self, module_id: &str, gen_id: &str) -> Vec<u8> {
+        let epoch = self
+            .epochs
+            .read()
+            .expect("data epochs lock poisoned")
+            .get(module_id.trim_start_matches('$'))
+            .copied()
+            .unwrap_or_default();
+
+        // Data encrypted before the module's first purge used no epoch.
+        if epoch == 0 {
+            format!("{module_id}{gen_id}").into_bytes()
+        } else {
+            format!("{module_id}{gen_id}:{epoch}").into_bytes()
+        }
+    }
+
+    /// Start a new epoch for a module, returning the new epoch.
+    pub fn advance(&self, module_id: &str) -> std::io::Result<u64> {
+        let mut epochs = self.epochs.write().expect("data epochs lock poisoned");
+
+        let epoch = epochs
+            .entry(module_id.trim_start_matches('$').to_string())
+            .or_default();
+        *epoch += 1;
+        let epoch = *epoch;
+
+        if let Some(path) = &self.path {
+            persist(path, &epochs)?;
+        }
+
+        Ok(epoch)
+    }
+}
+
+fn persist(path: &std::path::Path, epochs: &Epochs) -> std::io::Result<()> {
+    crate::persist::write_json(path, epochs)?;
+    crate::persist::write_json(&backup_path(path), epochs)
+}
+
+fn backup_path(path: &std::path::Path) -> std::path::PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DataEpochs;
+
+    #[test]
+    fn advance() {
+        let dir = std::env::temp_dir().join(format!("data-epochs-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data_epochs.json");
+        let _ = std::fs::remove_file(&path);
+
+        let epochs = DataEpochs::new(path.clone()).unwrap();
+        assert_eq!(b"$edgeHub1".to_vec(), epochs.aad("$edgeHub", "1"));
+
+        assert_eq!(1, epochs.advance("edgeHub").unwrap());
+        assert_eq!(b"$edgeHub1:1".to_vec(), epochs.aad("$edgeHub", "1"));
+
+        // Other modules are not affected.
+        assert_eq!(b"testModule1".to_vec(), epochs.aad("testModule", "1"));
+
+        // Epochs are persisted.
+        let epochs = DataEpochs::new(path.clone()).unwrap();
+        assert_eq!(2, epochs.advance("$edgeHub").unwrap());
+        assert_eq!(b"$edgeHub1:2".to_vec(), epochs.aad("$edgeHub", "1"));
+
+        // Losing or corrupting a copy doesn't roll epochs back.
+        std::fs::remove_file(&path).unwrap();
+        let epochs = DataEpochs::new(path.clone()).unwrap();
+        assert_eq!(b"$edgeHub1:2".to_vec(), epochs.aad("$edgeHub", "1"));
+
+        std::fs::write(dir.join("data_epochs.json.bak"), "{\"edgeHub\":").unwrap();
+        let epochs = DataEpochs::new(path.clone()).unwrap();
+        assert_eq!(b"$edgeHub1:2".to_vec(), epochs.aad("$edgeHub", "1"));
+
+        // An interrupted write leaves the later epoch in one of the copies.
+        std::fs::write(&path, "{\"edgeHub\":1}").unwrap();
+        let epochs = DataEpochs::new(path).unwrap();
+        assert_eq!(b"$edgeHub1:2".to_vec(), epochs.aad("$edgeHub", "1"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
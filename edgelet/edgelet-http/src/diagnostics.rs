@@ -0,0 +1,128 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Maximum length of the recorded panic message.
+const MAX_MESSAGE_LEN: usize = 256;
+
+#[derive(
+    Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, serde::Deserialize, serde::Serialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+impl DiagnosticCheck {
+    pub fn new(name: &str, status: CheckStatus, message: impl std::fmt::Display) -> Self {
+        DiagnosticCheck {
+            name: name.to_string(),
+            status,
+            message: message.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PanicRecord {
+    pub time: chrono::DateTime<chrono::Utc>,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct Inner {
+    mnt_dir: Option<std::path::PathBuf>,
+    upstream: Option<String>,
+    panics: u64,
+    last_panic: Option<PanicRecord>,
+}
+
+/// Daemon state that the diagnostics checks need and that only the daemon knows: where
+/// the workload sockets are, which host it connects upstream to, and whether any of its
+/// tasks panicked.
+#[derive(Clone, Default)]
+pub struct Diagnostics {
+    inner: std::sync::Arc<std::sync::Mutex<Inner>>,
+}
+
+impl Diagnostics {
+    pub fn set_mnt_dir(&self, mnt_dir: std::path::PathBuf) {
+        self.lock().mnt_dir = Some(mnt_dir);
+    }
+
+    /// Set the host that modules connect upstream to, once the device is provisioned.
+    pub fn set_upstream(&self, upstream: String) {
+        self.lock().upstream = Some(upstream);
+    }
+
+    pub fn mnt_dir(&self) -> Option<std::path::PathBuf> {
+        self.lock().mnt_dir.clone()
+    }
+
+    pub fn upstream(&self) -> Option<String> {
+        self.lock().upstream.clone()
+    }
+
+    /// Record a panic. Panics in spawned tasks don't stop the daemon, so without this
+    /// they would only show in its logs.
+    pub fn panicked(&self, mut message: String) {
+        if message.len() > MAX_MESSAGE_LEN {
+            let mut end = MAX_MESSAGE_LEN;
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+        }
+
+        // The lock may be poisoned by the panicking thread itself.
+        let mut inner = match self.inner.lock() {
+            Ok(inner) => inner,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        inner.panics += 1;
+        inner.last_panic = Some(PanicRecord {
+            time: chrono::Utc::now(),
+            message,
+        });
+    }
+
+    /// Panics since the daemon started, and the last one.
+    pub fn panics(&self) -> (u64, Option<PanicRecord>) {
+        let inner = self.lock();
+
+        (inner.panics, inner.last_panic.clone())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().expect("diagnostics lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn panicked() {
+        let diagnostics = super::Diagnostics::default();
+        assert_eq!((0, None), diagnostics.panics());
+
+        diagnostics.panicked("first".to_string());
+        diagnostics.panicked("é".repeat(super::MAX_MESSAGE_LEN));
+
+        let (count, last) = diagnostics.panics();
+        assert_eq!(2, count);
+
+        let last = last.unwrap();
+        assert_eq!(super::MAX_MESSAGE_LEN, last.message.len());
+        assert!(last.message.starts_with('é'));
+    }
+}
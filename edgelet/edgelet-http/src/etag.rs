@@ -0,0 +1,126 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Compute the entity tag of a response body.
+///
+/// The tag is weak, since the body may be sent compressed.
+pub fn etag(body: &[u8]) -> hyper::header::HeaderValue {
+    let digest: String = openssl::sha::sha256(body)[..16]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    hyper::header::HeaderValue::from_str(&format!("W/\"{digest}\""))
+        .expect("entity tag is a valid header value")
+}
+
+/// Whether the request's `If-None-Match` matches the response's entity tag. Weak
+/// comparison is used, as required for `If-None-Match`.
+fn matches(if_none_match: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+/// Answers `GET` requests with `304 Not Modified` when the route tagged its response
+/// with an `ETag` that the caller already has.
+///
+/// Routes opt in by setting the `ETag` header, so pollers of large lists don't download
+/// them again while they are unchanged.
+#[derive(Clone)]
+pub struct ETagService<S> {
+    inner: S,
+}
+
+impl<S> ETagService<S> {
+    pub fn new(inner: S) -> Self {
+        ETagService { inner }
+    }
+}
+
+impl<S> hyper::service::Service<hyper::Request<hyper::Body>> for ETagService<S>
+where
+    S: hyper::service::Service<
+        hyper::Request<hyper::Body>,
+        Response = hyper::Response<hyper::Body>,
+        Error = std::convert::Infallible,
+    >,
+    S::Future: Send + 'static,
+{
+    type Response = hyper::Response<hyper::Body>;
+    type Error = std::convert::Infallible;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: hyper::Request<hyper::Body>) -> Self::Future {
+        let if_none_match = if req.method() == hyper::Method::GET {
+            req.headers()
+                .get(hyper::header::IF_NONE_MATCH)
+                .and_then(|if_none_match| if_none_match.to_str().ok())
+                .map(ToString::to_string)
+        } else {
+            None
+        };
+
+        let response = self.inner.call(req);
+
+        Box::pin(async move {
+            let response = response.await?;
+
+            let if_none_match = match if_none_match {
+                Some(if_none_match) if response.status() == hyper::StatusCode::OK => if_none_match,
+                _ => return Ok(response),
+            };
+
+            let etag = match response.headers().get(hyper::header::ETAG) {
+                Some(etag) => etag.clone(),
+                None => return Ok(response),
+            };
+
+            if !etag
+                .to_str()
+                .map_or(false, |etag| matches(&if_none_match, etag))
+            {
+                return Ok(response);
+            }
+
+            Ok(hyper::Response::builder()
+                .status(hyper::StatusCode::NOT_MODIFIED)
+                .header(hyper::header::ETAG, etag)
+                .body(hyper::Body::empty())
+                .expect("cannot fail to build hyper response"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{etag, matches};
+
+    #[test]
+    fn etag_matches() {
+        let tag = etag(b"{\"modules\":[]}");
+        let tag = tag.to_str().unwrap();
+        assert!(tag.starts_with("W/\""));
+        assert_eq!(tag, etag(b"{\"modules\":[]}").to_str().unwrap());
+        assert_ne!(tag, etag(b"{\"modules\":[{}]}").to_str().unwrap());
+
+        assert!(matches(tag, tag));
+        assert!(matches(tag.trim_start_matches("W/"), tag));
+        assert!(matches(&format!("\"other\", {}", tag), tag));
+        assert!(matches("*", tag));
+
+        assert!(!matches("\"other\"", tag));
+        assert!(!matches("", tag));
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_settings::CloudNotify;
+
+/// Error messages are truncated so that summaries stay small enough to report upstream.
+const MAX_ERROR_LEN: usize = 256;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FailureKind {
+    Provisioning,
+    ModuleRuntime,
+    ImageGarbageCollection,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailureSummary {
+    /// Changes whenever the summary does, so that acknowledging a summary that was
+    /// updated after it was read leaves it pending.
+    #[serde(default)]
+    pub id: u64,
+    pub kind: FailureKind,
+    pub count: u64,
+    pub first_time: chrono::DateTime<chrono::Utc>,
+    pub last_time: chrono::DateTime<chrono::Utc>,
+    pub last_error: String,
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+struct State {
+    pending: Vec<FailureSummary>,
+    last_report: Option<chrono::DateTime<chrono::Utc>>,
+
+    #[serde(default)]
+    last_id: u64,
+}
+
+/// Summaries of repeated local failures, handed to Edge Agent at a bounded rate so
+/// that it can report them to the cloud.
+///
+/// Failures are counted per kind between reports rather than reported one by one.
+/// The summaries are persisted, since many of these failures stop the daemon.
+#[derive(Clone, Default)]
+pub struct FailureReport {
+    settings: std::sync::Arc<CloudNotify>,
+    state: std::sync::Arc<std::sync::Mutex<State>>,
+    path: Option<std::path::PathBuf>,
+}
+
+impl FailureReport {
+    pub fn new(settings: CloudNotify, path: std::path::PathBuf) -> Self {
+        let state = crate::persist::read_json(&path, "failure report state")
+            .unwrap_or_else(|err| {
+                log::warn!("Failed to read failure report state: {}", err);
+
+                None
+            })
+            .unwrap_or_default();
+
+        FailureReport {
+            settings: std::sync::Arc::new(settings),
+            state: std::sync::Arc::new(std::sync::Mutex::new(state)),
+            path: Some(path),
+        }
+    }
+
+    pub fn record(&self, kind: FailureKind, error: &impl std::fmt::Display) {
+        self.record_at(kind, &error.to_string(), chrono::Utc::now());
+    }
+
+    /// Failure summaries that are due to be reported. This is empty until the minimum
+    /// interval has passed since the last acknowledged report.
+    pub fn pending(&self) -> Vec<FailureSummary> {
+        self.pending_at(chrono::Utc::now())
+    }
+
+    /// Clear the summaries with the given ids after Edge Agent has reported them.
+    /// Summaries that were recorded or updated since Edge Agent read them stay pending.
+    pub fn acknowledge(&self, ids: &[u64]) {
+        let mut state = self.state.lock().expect("failure report lock poisoned");
+
+        let pending = state.pending.len();
+        state.pending.retain(|summary| !ids.contains(&summary.id));
+
+        if state.pending.len() < pending {
+            state.last_report = Some(chrono::Utc::now());
+            self.persist(&state);
+        }
+    }
+
+    fn record_at(&self, kind: FailureKind, error: &str, now: chrono::DateTime<chrono::Utc>) {
+        if !self.settings.enabled {
+            return;
+        }
+
+        let mut last_error: String = error.chars().take(MAX_ERROR_LEN).collect();
+        if last_error.len() < error.len() {
+            last_error.push_str("...");
+        }
+
+        let mut state = self.state.lock().expect("failure report lock poisoned");
+
+        state.last_id += 1;
+        let id = state.last_id;
+
+        if let Some(summary) = state
+            .pending
+            .iter_mut()
+            .find(|summary| summary.kind == kind)
+        {
+            summary.id = id;
+            summary.count += 1;
+            summary.last_time = now;
+            summary.last_error = last_error;
+        } else {
+            state.pending.push(FailureSummary {
+                id,
+                kind,
+                count: 1,
+                first_time: now,
+                last_time: now,
+                last_error,
+            });
+        }
+
+        self.persist(&state);
+    }
+
+    fn pending_at(&self, now: chrono::DateTime<chrono::Utc>) -> Vec<FailureSummary> {
+        let state = self.state.lock().expect("failure report lock poisoned");
+
+        let min_interval = chrono::Duration::seconds(
+            i64::try_from(self.settings.min_interval_secs).unwrap_or(i64::MAX),
+        );
+
+        match state.last_report {
+            Some(last_report) if now - last_report < min_interval => Vec::new(),
+            _ => state.pending.clone(),
+        }
+    }
+
+    fn persist(&self, state: &State) {
+        if let Some(path) = &self.path {
+            if let Err(err) = crate::persist::write_json(path, state) {
+                log::warn!("Failed to save failure report state: {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_settings::CloudNotify;
+
+    use super::{FailureKind, FailureReport};
+
+    fn failure_report() -> FailureReport {
+        FailureReport {
+            settings: std::sync::Arc::new(CloudNotify {
+                enabled: true,
+                min_interval_secs: 60,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn disabled() {
+        let report = FailureReport::default();
+
+        report.record(FailureKind::Provisioning, &"error");
+        assert!(report.pending().is_empty());
+    }
+
+    #[test]
+    fn summarize() {
+        let report = failure_report();
+        let start = chrono::Utc::now();
+
+        report.record_at(FailureKind::ModuleRuntime, "error 1", start);
+        report.record_at(
+            FailureKind::ModuleRuntime,
+            "error 2",
+            start + chrono::Duration::seconds(1),
+        );
+        report.record_at(FailureKind::Provisioning, "error 3", start);
+
+        let pending = report.pending_at(start);
+        assert_eq!(2, pending.len());
+        assert_eq!(FailureKind::ModuleRuntime, pending[0].kind);
+        assert_eq!(2, pending[0].count);
+        assert_eq!(start, pending[0].first_time);
+        assert_eq!("error 2", pending[0].last_error);
+        assert_eq!(1, pending[1].count);
+
+        let long_error = "x".repeat(super::MAX_ERROR_LEN * 2);
+        report.record_at(FailureKind::ImageGarbageCollection, &long_error, start);
+        assert_eq!(
+            super::MAX_ERROR_LEN + 3,
+            report.pending_at(start)[2].last_error.len()
+        );
+    }
+
+    #[test]
+    fn throttle() {
+        let report = failure_report();
+
+        report.record(FailureKind::ModuleRuntime, &"error");
+        let pending = report.pending();
+        assert_eq!(1, pending.len());
+
+        report.acknowledge(&[pending[0].id]);
+        assert!(report.pending().is_empty());
+
+        // New failures aren't handed out again until the interval has passed.
+        report.record(FailureKind::ModuleRuntime, &"error");
+        let now = chrono::Utc::now();
+        assert!(report.pending_at(now).is_empty());
+        assert_eq!(
+            1,
+            report.pending_at(now + chrono::Duration::seconds(61)).len()
+        );
+    }
+
+    #[test]
+    fn acknowledge_returned_summaries() {
+        let report = failure_report();
+        let start = chrono::Utc::now();
+
+        report.record_at(FailureKind::ModuleRuntime, "error 1", start);
+        report.record_at(FailureKind::Provisioning, "error 2", start);
+        let pending = report.pending_at(start);
+
+        // Failures recorded after the summaries were read.
+        report.record_at(FailureKind::ModuleRuntime, "error 3", start);
+        report.record_at(FailureKind::ImageGarbageCollection, "error 4", start);
+
+        let ids: Vec<_> = pending.iter().map(|summary| summary.id).collect();
+        report.acknowledge(&ids);
+
+        let later = chrono::Utc::now() + chrono::Duration::seconds(61);
+        let pending = report.pending_at(later);
+        assert_eq!(2, pending.len());
+        assert_eq!(FailureKind::ModuleRuntime, pending[0].kind);
+        assert_eq!(2, pending[0].count);
+        assert_eq!(FailureKind::ImageGarbageCollection, pending[1].kind);
+    }
+}
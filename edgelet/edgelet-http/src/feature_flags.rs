@@ -0,0 +1,135 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+type Flags = std::collections::BTreeMap<String, bool>;
+
+/// Device-wide feature flags that modules can read through the workload API.
+///
+/// Flags come from the `[feature_flags]` section of the config. They can be
+/// overridden through the management API; overrides are persisted so that they
+/// survive daemon restarts. Overrides that can't be read are dropped, so that they
+/// never stop the daemon from starting.
+#[derive(Clone, Default)]
+pub struct FeatureFlags {
+    defaults: std::sync::Arc<Flags>,
+    overrides: std::sync::Arc<std::sync::RwLock<Flags>>,
+    path: Option<std::path::PathBuf>,
+}
+
+impl FeatureFlags {
+    pub fn new(defaults: Flags, path: std::path::PathBuf) -> Self {
+        let overrides = crate::persist::read_json(&path, "feature flag overrides")
+            .unwrap_or_else(|err| {
+                log::warn!("Failed to read feature flag overrides: {}", err);
+
+                None
+            })
+            .unwrap_or_default();
+
+        FeatureFlags {
+            defaults: std::sync::Arc::new(defaults),
+            overrides: std::sync::Arc::new(std::sync::RwLock::new(overrides)),
+            path: Some(path),
+        }
+    }
+
+    /// Flag names are restricted so they can be used in URLs and environment variables.
+    pub fn is_valid_name(name: &str) -> bool {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+    }
+
+    /// The current value of every flag, with overrides applied.
+    pub fn get_all(&self) -> Flags {
+        let mut flags = (*self.defaults).clone();
+
+        let overrides = self.overrides.read().expect("feature flags lock poisoned");
+        flags.extend(overrides.iter().map(|(name, value)| (name.clone(), *value)));
+
+        flags
+    }
+
+    pub fn set(&self, name: &str, value: bool) -> std::io::Result<()> {
+        let mut overrides = self.overrides.write().expect("feature flags lock poisoned");
+        overrides.insert(name.to_string(), value);
+
+        self.persist(&overrides)
+    }
+
+    /// Remove an override, returning the flag to its configured value. Returns
+    /// `false` if the flag was not overridden.
+    pub fn reset(&self, name: &str) -> std::io::Result<bool> {
+        let mut overrides = self.overrides.write().expect("feature flags lock poisoned");
+
+        if overrides.remove(name).is_none() {
+            return Ok(false);
+        }
+
+        self.persist(&overrides)?;
+
+        Ok(true)
+    }
+
+    fn persist(&self, overrides: &Flags) -> std::io::Result<()> {
+        if let Some(path) = &self.path {
+            crate::persist::write_json(path, overrides)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FeatureFlags;
+
+    #[test]
+    fn overrides() {
+        let dir = std::env::temp_dir().join(format!("feature-flags-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("feature_flags.json");
+        let _ = std::fs::remove_file(&path);
+
+        let mut defaults = std::collections::BTreeMap::new();
+        defaults.insert("flagA".to_string(), true);
+        defaults.insert("flagB".to_string(), false);
+
+        let flags = FeatureFlags::new(defaults.clone(), path.clone());
+        assert_eq!(defaults, flags.get_all());
+
+        flags.set("flagB", true).unwrap();
+        flags.set("flagC", false).unwrap();
+
+        let mut expected = defaults.clone();
+        expected.insert("flagB".to_string(), true);
+        expected.insert("flagC".to_string(), false);
+        assert_eq!(expected, flags.get_all());
+
+        // Overrides are persisted.
+        let flags = FeatureFlags::new(defaults.clone(), path.clone());
+        assert_eq!(expected, flags.get_all());
+
+        // Resetting an override restores the configured value.
+        assert!(flags.reset("flagB").unwrap());
+        assert!(!flags.reset("flagB").unwrap());
+        expected.insert("flagB".to_string(), false);
+        assert_eq!(expected, flags.get_all());
+
+        // Corrupt overrides don't stop the flags from loading.
+        std::fs::write(&path, "{\"flagB\": tru").unwrap();
+        let flags = FeatureFlags::new(defaults.clone(), path);
+        assert_eq!(defaults, flags.get_all());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn valid_name() {
+        assert!(FeatureFlags::is_valid_name("my-flag_1.0"));
+
+        assert!(!FeatureFlags::is_valid_name(""));
+        assert!(!FeatureFlags::is_valid_name("my flag"));
+        assert!(!FeatureFlags::is_valid_name("my/flag"));
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Maximum length of the recorded error message.
+const MAX_ERROR_LEN: usize = 256;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IdentityState {
+    /// No request has been made to Identity Service yet.
+    Connecting,
+
+    Available,
+
+    /// Identity Service could not be reached, e.g. because it hasn't started yet.
+    Unavailable,
+
+    /// Identity Service was reached, but could not provide the device identity.
+    ProvisioningFailed,
+
+    /// Provisioning failed at startup, so the device runs with the identity from its
+    /// last successful provisioning until provisioning succeeds.
+    Cached,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityHealthStatus {
+    pub state: IdentityState,
+
+    /// When the state last changed.
+    pub since: chrono::DateTime<chrono::Utc>,
+
+    /// Failed requests since the last successful one.
+    pub consecutive_failures: u64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_success: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Whether aziot-edged can reach Identity Service, so that a daemon waiting on it can
+/// be told apart from one that failed to provision.
+#[derive(Clone)]
+pub struct IdentityHealth {
+    status: std::sync::Arc<std::sync::Mutex<IdentityHealthStatus>>,
+}
+
+impl Default for IdentityHealth {
+    fn default() -> Self {
+        IdentityHealth {
+            status: std::sync::Arc::new(std::sync::Mutex::new(IdentityHealthStatus {
+                state: IdentityState::Connecting,
+                since: chrono::Utc::now(),
+                consecutive_failures: 0,
+                last_error: None,
+                last_success: None,
+            })),
+        }
+    }
+}
+
+impl IdentityHealth {
+    pub fn get(&self) -> IdentityHealthStatus {
+        self.status
+            .lock()
+            .expect("identity health lock poisoned")
+            .clone()
+    }
+
+    pub fn available(&self) {
+        let now = chrono::Utc::now();
+        let mut status = self.status.lock().expect("identity health lock poisoned");
+
+        if status.state != IdentityState::Available {
+            log::info!("Identity Service is available");
+
+            status.state = IdentityState::Available;
+            status.since = now;
+        }
+
+        status.consecutive_failures = 0;
+        status.last_success = Some(now);
+    }
+
+    pub fn unavailable(&self, error: &impl std::fmt::Display) {
+        self.failed(IdentityState::Unavailable, &error.to_string());
+    }
+
+    pub fn provisioning_failed(&self, error: &impl std::fmt::Display) {
+        self.failed(IdentityState::ProvisioningFailed, &error.to_string());
+    }
+
+    pub fn cached(&self, error: &impl std::fmt::Display) {
+        self.failed(IdentityState::Cached, &error.to_string());
+    }
+
+    fn failed(&self, state: IdentityState, error: &str) {
+        let mut last_error: String = error.chars().take(MAX_ERROR_LEN).collect();
+        if last_error.len() < error.len() {
+            last_error.push_str("...");
+        }
+
+        let mut status = self.status.lock().expect("identity health lock poisoned");
+
+        if status.state != state {
+            status.state = state;
+            status.since = chrono::Utc::now();
+        }
+
+        status.consecutive_failures += 1;
+        status.last_error = Some(last_error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IdentityHealth, IdentityState};
+
+    #[test]
+    fn transitions() {
+        let health = IdentityHealth::default();
+        assert_eq!(IdentityState::Connecting, health.get().state);
+
+        health.unavailable(&"connection refused");
+        health.unavailable(&"connection refused");
+
+        let status = health.get();
+        assert_eq!(IdentityState::Unavailable, status.state);
+        assert_eq!(2, status.consecutive_failures);
+        assert_eq!(Some("connection refused".to_string()), status.last_error);
+        assert!(status.last_success.is_none());
+
+        // Failures are counted across states, but the state change is recorded.
+        health.provisioning_failed(&"device not found");
+
+        let failed = health.get();
+        assert_eq!(IdentityState::ProvisioningFailed, failed.state);
+        assert_eq!(3, failed.consecutive_failures);
+        assert!(failed.since >= status.since);
+
+        health.available();
+
+        let status = health.get();
+        assert_eq!(IdentityState::Available, status.state);
+        assert_eq!(0, status.consecutive_failures);
+        assert!(status.last_success.is_some());
+
+        // The last error is kept for troubleshooting.
+        assert_eq!(Some("device not found".to_string()), status.last_error);
+    }
+
+    #[test]
+    fn long_error() {
+        let health = IdentityHealth::default();
+
+        health.unavailable(&"x".repeat(super::MAX_ERROR_LEN * 2));
+
+        let last_error = health.get().last_error.unwrap();
+        assert_eq!(super::MAX_ERROR_LEN + 3, last_error.len());
+        assert!(last_error.ends_with("..."));
+    }
+}
@@ -8,12 +8,53 @@
     clippy::must_use_candidate
 )]
 
+mod access_log;
+mod alerts;
+mod audit_log;
 mod auth;
+mod change_feed;
+mod compression;
+mod correlation;
+mod data_epochs;
+mod diagnostics;
 pub mod error;
+mod etag;
+mod failure_report;
+mod feature_flags;
+mod identity_health;
+mod log_filter;
+mod module_certs;
+mod module_health;
 mod modules;
+mod operations;
+mod parent_health;
+pub mod persist;
+mod persisted_logs;
+mod rate_limit;
+mod restarts;
+mod secrets;
+mod state_dump;
+mod stores;
 mod version;
+mod version_negotiation;
+mod workload_tcp;
 
+pub use access_log::{AccessLog, AccessLogService};
+pub use alerts::{AlertStatus, Alerts};
+pub use audit_log::{AuditCaller, AuditEvent, AuditEventType, AuditLog, AuditLogService};
 pub use auth::{auth_agent, auth_caller};
+pub use change_feed::{Change, ChangeEvent, ChangeFeed, ChangeFeedError, ChangeKind};
+pub use compression::CompressionService;
+pub use correlation::{correlation_id, CORRELATION_ID_HEADER};
+pub use data_epochs::DataEpochs;
+pub use diagnostics::{CheckStatus, DiagnosticCheck, Diagnostics, PanicRecord};
+pub use etag::{etag, ETagService};
+pub use failure_report::{FailureKind, FailureReport, FailureSummary};
+pub use feature_flags::FeatureFlags;
+pub use identity_health::{IdentityHealth, IdentityHealthStatus, IdentityState};
+pub use log_filter::LogFilter;
+pub use module_certs::{CertKind, ModuleCert, ModuleCerts};
+pub use module_health::{ModuleHealth, ModuleHealthStatus, ProbeResult, ProbeSource};
 
 // Common types shared between management and workload APIs.
 pub use modules::{ListModulesResponse, ModuleConfig, ModuleDetails, ModuleStatus};
@@ -21,7 +62,28 @@ pub use modules::{ListModulesResponse, ModuleConfig, ModuleDetails, ModuleStatus
 // HTTP bodies that represent module specs.
 pub use modules::ModuleSpec;
 
+pub use operations::{ImageGcRun, Operations, OperationsStatus};
+
+pub use parent_health::{ParentHealth, ParentHealthStatus, ParentState};
+
+pub use persisted_logs::{LogEntry, LogStream, PersistedLogs};
+
+pub use rate_limit::{RateLimit, RateLimitCounters, RateLimitService};
+
+pub use restarts::{Restart, RestartHistory, ShutdownReason};
+
+pub use secrets::{SecretInfo, Secrets, SECRETS_KEY_ID};
+
+pub use state_dump::{StateDump, StateDumpReport};
+
+pub use stores::Stores;
+
 pub use version::ApiVersion;
+pub use version_negotiation::ApiVersionService;
+
+pub use workload_tcp::{
+    is_loopback, TokenAuth, WorkloadTcp, WORKLOAD_TCP_URI_ENV, WORKLOAD_TOKEN_ENV,
+};
 
 /// Search a query string for the provided key.
 pub fn find_query(
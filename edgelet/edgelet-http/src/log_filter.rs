@@ -0,0 +1,198 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Filter used when the daemon starts without one.
+const DEFAULT_FILTER: &str = "info";
+
+/// Filter that `toggle_debug` switches to.
+const DEBUG_FILTER: &str = "debug";
+
+struct Inner {
+    spec: String,
+    initial_spec: String,
+    filter: env_logger::filter::Filter,
+}
+
+/// The daemon's log filter, in the syntax of `AZIOT_LOG`, e.g. `info,edgelet_docker=debug`.
+///
+/// It can be changed while the daemon runs, so that an intermittent issue can be logged
+/// in detail without a restart that would make it go away.
+#[derive(Clone)]
+pub struct LogFilter {
+    inner: std::sync::Arc<std::sync::RwLock<Inner>>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        LogFilter::new(DEFAULT_FILTER).expect("default log filter is valid")
+    }
+}
+
+impl LogFilter {
+    pub fn new(spec: &str) -> Result<Self, String> {
+        let filter = parse(spec)?;
+
+        Ok(LogFilter {
+            inner: std::sync::Arc::new(std::sync::RwLock::new(Inner {
+                spec: spec.to_string(),
+                initial_spec: spec.to_string(),
+                filter,
+            })),
+        })
+    }
+
+    /// The current filter.
+    pub fn get(&self) -> String {
+        self.read().spec.clone()
+    }
+
+    /// The filter the daemon started with.
+    pub fn initial(&self) -> String {
+        self.read().initial_spec.clone()
+    }
+
+    pub fn set(&self, spec: &str) -> Result<(), String> {
+        let filter = parse(spec)?;
+        let max_level = filter.filter();
+
+        {
+            let mut inner = self.inner.write().expect("log filter lock poisoned");
+
+            inner.spec = spec.to_string();
+            inner.filter = filter;
+        }
+
+        log::set_max_level(max_level);
+        log::info!("Log filter set to {}", spec);
+
+        Ok(())
+    }
+
+    /// Restore the filter the daemon started with.
+    pub fn reset(&self) {
+        let initial_spec = self.initial();
+
+        self.set(&initial_spec)
+            .expect("initial log filter was validated");
+    }
+
+    /// Switch between debug logging and the filter the daemon started with. Returns the
+    /// new filter.
+    pub fn toggle_debug(&self) -> String {
+        if self.get() == self.initial() {
+            self.set(DEBUG_FILTER).expect("debug log filter is valid");
+        } else {
+            self.reset();
+        }
+
+        self.get()
+    }
+
+    /// The most verbose level that any target is logged at.
+    pub fn max_level(&self) -> log::LevelFilter {
+        self.read().filter.filter()
+    }
+
+    pub fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        self.read().filter.enabled(metadata)
+    }
+
+    pub fn matches(&self, record: &log::Record<'_>) -> bool {
+        self.read().filter.matches(record)
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Inner> {
+        self.inner.read().expect("log filter lock poisoned")
+    }
+}
+
+/// Parse a filter. env_logger ignores invalid directives, so they are rejected here
+/// rather than silently changing what is logged.
+fn parse(spec: &str) -> Result<env_logger::filter::Filter, String> {
+    let directives = spec.split('/').next().unwrap_or_default();
+
+    if directives.trim().is_empty() {
+        return Err("log filter must not be empty".to_string());
+    }
+
+    for directive in directives.split(',').map(str::trim) {
+        let mut parts = directive.split('=');
+
+        let valid = match (parts.next(), parts.next(), parts.next()) {
+            // A bare level, or a target that is logged at every level.
+            (Some(part), None, None) => !part.is_empty(),
+
+            (Some(target), Some(level), None) => {
+                !target.is_empty() && level.parse::<log::LevelFilter>().is_ok()
+            }
+
+            _ => false,
+        };
+
+        if !valid {
+            return Err(format!("invalid log filter directive \"{directive}\""));
+        }
+    }
+
+    Ok(env_logger::filter::Builder::new().parse(spec).build())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse() {
+        for valid in [
+            "info",
+            "debug",
+            "info,edgelet_docker=debug",
+            "warn, aziot_edged=trace",
+            "edgelet_http_mgmt",
+            "info/module",
+        ] {
+            assert!(super::parse(valid).is_ok(), "{valid}");
+        }
+
+        for invalid in [
+            "",
+            " ",
+            "info,",
+            "edgelet_docker=verbose",
+            "=debug",
+            "a=b=c",
+        ] {
+            assert!(super::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn set_and_reset() {
+        let filter = super::LogFilter::new("info").unwrap();
+        assert_eq!(log::LevelFilter::Info, filter.max_level());
+
+        filter.set("info,edgelet_docker=debug").unwrap();
+        assert_eq!("info,edgelet_docker=debug", filter.get());
+        assert_eq!(log::LevelFilter::Debug, filter.max_level());
+
+        let docker = log::Metadata::builder()
+            .level(log::Level::Debug)
+            .target("edgelet_docker::runtime")
+            .build();
+        let http = log::Metadata::builder()
+            .level(log::Level::Debug)
+            .target("edgelet_http")
+            .build();
+        assert!(filter.enabled(&docker));
+        assert!(!filter.enabled(&http));
+
+        // Invalid filters leave the current one in place.
+        assert!(filter.set("verbose=").is_err());
+        assert_eq!("info,edgelet_docker=debug", filter.get());
+
+        filter.reset();
+        assert_eq!("info", filter.get());
+        assert!(!filter.enabled(&docker));
+
+        assert_eq!("debug", filter.toggle_debug());
+        assert!(filter.enabled(&http));
+        assert_eq!("info", filter.toggle_debug());
+    }
+}
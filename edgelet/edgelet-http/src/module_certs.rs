@@ -0,0 +1,224 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CertKind {
+    Identity,
+    Server,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleCert {
+    pub module: String,
+
+    #[serde(rename = "type")]
+    pub kind: CertKind,
+
+    /// The expiration the module was given when the certificate was issued.
+    pub expiration: chrono::DateTime<chrono::Utc>,
+
+    /// Whether the module was restarted to renew this certificate.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub restarted: bool,
+}
+
+/// Certificates issued to modules through the workload API, so that modules can be
+/// told to renew them before they expire.
+///
+/// The certificates are persisted in the home directory, since modules keep them across
+/// daemon restarts.
+#[derive(Clone, Default)]
+pub struct ModuleCerts {
+    certs: std::sync::Arc<std::sync::Mutex<Vec<ModuleCert>>>,
+    renew_before: std::time::Duration,
+    notify: std::sync::Arc<tokio::sync::Notify>,
+    path: Option<std::path::PathBuf>,
+}
+
+impl ModuleCerts {
+    /// Certificates are due for renewal `renew_before` their expiration.
+    pub fn new(
+        path: std::path::PathBuf,
+        renew_before: std::time::Duration,
+    ) -> std::io::Result<Self> {
+        let certs =
+            crate::persist::read_json(&path, "module certificate list")?.unwrap_or_default();
+
+        chrono::Duration::from_std(renew_before)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+
+        Ok(ModuleCerts {
+            certs: std::sync::Arc::new(std::sync::Mutex::new(certs)),
+            renew_before,
+            notify: std::sync::Arc::default(),
+            path: Some(path),
+        })
+    }
+
+    /// Record a certificate issued to a module, replacing the one it renews.
+    pub fn record(&self, module: &str, kind: CertKind, expiration: chrono::DateTime<chrono::Utc>) {
+        let mut certs = self.certs.lock().expect("module certs lock poisoned");
+
+        certs.retain(|cert| !(cert.module == module && cert.kind == kind));
+        certs.push(ModuleCert {
+            module: module.to_string(),
+            kind,
+            expiration,
+            restarted: false,
+        });
+
+        self.persist(&certs);
+    }
+
+    /// Forget the certificates of a module that was removed.
+    pub fn remove(&self, module: &str) {
+        let mut certs = self.certs.lock().expect("module certs lock poisoned");
+
+        let len = certs.len();
+        certs.retain(|cert| cert.module != module);
+
+        if certs.len() != len {
+            self.persist(&certs);
+        }
+    }
+
+    /// Certificates of all modules that are due for renewal.
+    pub fn due(&self) -> Vec<ModuleCert> {
+        let due = chrono::Utc::now()
+            + chrono::Duration::from_std(self.renew_before).expect("checked in new");
+
+        self.certs
+            .lock()
+            .expect("module certs lock poisoned")
+            .iter()
+            .filter(|cert| cert.expiration <= due)
+            .cloned()
+            .collect()
+    }
+
+    /// Certificates of a module that are due for renewal.
+    pub fn due_for(&self, module: &str) -> Vec<ModuleCert> {
+        let mut due = self.due();
+        due.retain(|cert| cert.module == module);
+
+        due
+    }
+
+    pub fn mark_restarted(&self, module: &str) {
+        let mut certs = self.certs.lock().expect("module certs lock poisoned");
+
+        for cert in certs.iter_mut().filter(|cert| cert.module == module) {
+            cert.restarted = true;
+        }
+
+        self.persist(&certs);
+    }
+
+    /// Wake modules waiting for their certificates to become due.
+    pub fn notify(&self) {
+        self.notify.notify_waiters();
+    }
+
+    /// Wait until a module has certificates due for renewal, or until `timeout` passes.
+    pub async fn wait(&self, module: &str, timeout: std::time::Duration) -> Vec<ModuleCert> {
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        loop {
+            // Created before checking so that a notification in between isn't missed.
+            let notified = self.notify.notified();
+
+            let due = self.due_for(module);
+            if !due.is_empty() {
+                return due;
+            }
+
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return Vec::new();
+            }
+        }
+    }
+
+    fn persist(&self, certs: &[ModuleCert]) {
+        if let Some(path) = &self.path {
+            if let Err(err) = crate::persist::write_json(path, certs) {
+                log::warn!("Failed to save module certificate list: {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CertKind, ModuleCerts};
+
+    #[test]
+    fn due() {
+        let certs = ModuleCerts {
+            renew_before: std::time::Duration::from_secs(24 * 60 * 60),
+            ..Default::default()
+        };
+
+        let now = chrono::Utc::now();
+        certs.record(
+            "edgeHub",
+            CertKind::Server,
+            now + chrono::Duration::hours(12),
+        );
+        certs.record(
+            "edgeHub",
+            CertKind::Identity,
+            now + chrono::Duration::days(30),
+        );
+        certs.record("sensor", CertKind::Server, now + chrono::Duration::days(30));
+
+        let due = certs.due();
+        assert_eq!(1, due.len());
+        assert_eq!("edgeHub", due[0].module);
+        assert_eq!(CertKind::Server, due[0].kind);
+        assert!(certs.due_for("sensor").is_empty());
+
+        // Renewing replaces the certificate.
+        certs.record(
+            "edgeHub",
+            CertKind::Server,
+            now + chrono::Duration::days(30),
+        );
+        assert!(certs.due().is_empty());
+
+        certs.remove("edgeHub");
+        assert_eq!(1, certs.certs.lock().unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn wait() {
+        let certs = ModuleCerts::default();
+
+        // Nothing is due.
+        certs.record(
+            "edgeHub",
+            CertKind::Server,
+            chrono::Utc::now() + chrono::Duration::days(30),
+        );
+        assert!(certs
+            .wait("edgeHub", std::time::Duration::from_millis(10))
+            .await
+            .is_empty());
+
+        let waiter = {
+            let certs = certs.clone();
+
+            tokio::spawn(async move {
+                certs
+                    .wait("edgeHub", std::time::Duration::from_secs(10))
+                    .await
+            })
+        };
+        tokio::task::yield_now().await;
+
+        certs.record("edgeHub", CertKind::Server, chrono::Utc::now());
+        certs.notify();
+
+        assert_eq!(1, waiter.await.unwrap().len());
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProbeSource {
+    /// The container's Docker healthcheck, which includes exec probes.
+    Docker,
+    Http,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeResult {
+    pub time: chrono::DateTime<chrono::Utc>,
+    pub source: ProbeSource,
+    pub healthy: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl ProbeResult {
+    pub fn new(source: ProbeSource, healthy: bool, message: Option<String>) -> Self {
+        ProbeResult {
+            time: chrono::Utc::now(),
+            source,
+            healthy,
+            message,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleHealthStatus {
+    pub name: String,
+
+    /// When the module's probes started failing, if they are failing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unhealthy_since: Option<chrono::DateTime<chrono::Utc>>,
+
+    pub restart_count: u64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_restart: Option<chrono::DateTime<chrono::Utc>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_probe: Option<ProbeResult>,
+}
+
+/// Probe results of running modules and the restarts made because of them, since the
+/// daemon started.
+#[derive(Clone, Default)]
+pub struct ModuleHealth {
+    modules:
+        std::sync::Arc<std::sync::Mutex<std::collections::BTreeMap<String, ModuleHealthStatus>>>,
+}
+
+impl ModuleHealth {
+    /// Record the result of probing a module. Returns whether the module has been
+    /// unhealthy for at least `grace_period` and should be restarted.
+    pub fn record(
+        &self,
+        module: &str,
+        result: ProbeResult,
+        grace_period: std::time::Duration,
+    ) -> bool {
+        let mut modules = self.modules.lock().expect("module health lock poisoned");
+
+        let status = modules
+            .entry(module.to_string())
+            .or_insert_with(|| ModuleHealthStatus {
+                name: module.to_string(),
+                unhealthy_since: None,
+                restart_count: 0,
+                last_restart: None,
+                last_probe: None,
+            });
+
+        let time = result.time;
+
+        let restart = if result.healthy {
+            status.unhealthy_since = None;
+
+            false
+        } else {
+            let since = *status.unhealthy_since.get_or_insert(time);
+
+            chrono::Duration::from_std(grace_period).map_or(false, |grace| time - since >= grace)
+        };
+
+        status.last_probe = Some(result);
+
+        restart
+    }
+
+    /// Record that a module was restarted for being unhealthy. Its grace period starts
+    /// over.
+    pub fn restarted(&self, module: &str) {
+        let mut modules = self.modules.lock().expect("module health lock poisoned");
+
+        if let Some(status) = modules.get_mut(module) {
+            status.unhealthy_since = None;
+            status.restart_count += 1;
+            status.last_restart = Some(chrono::Utc::now());
+        }
+    }
+
+    /// Forget modules other than `modules`, e.g. after they are removed.
+    pub fn retain(&self, modules: &std::collections::BTreeSet<String>) {
+        self.modules
+            .lock()
+            .expect("module health lock poisoned")
+            .retain(|name, _| modules.contains(name));
+    }
+
+    pub fn get(&self) -> Vec<ModuleHealthStatus> {
+        self.modules
+            .lock()
+            .expect("module health lock poisoned")
+            .values()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ModuleHealth, ProbeResult, ProbeSource};
+
+    fn probe(healthy: bool, minutes_ago: i64) -> ProbeResult {
+        let mut result = ProbeResult::new(ProbeSource::Http, healthy, None);
+        result.time -= chrono::Duration::minutes(minutes_ago);
+
+        result
+    }
+
+    #[test]
+    fn grace_period() {
+        let health = ModuleHealth::default();
+        let grace = std::time::Duration::from_secs(3 * 60);
+
+        assert!(!health.record("sensor", probe(false, 5), grace));
+        assert!(!health.record("sensor", probe(false, 3), grace));
+
+        // Healthy again, so the grace period starts over.
+        assert!(!health.record("sensor", probe(true, 2), grace));
+        assert!(!health.record("sensor", probe(false, 1), grace));
+        assert!(health.get()[0].unhealthy_since.is_some());
+
+        assert!(health.record("sensor", probe(false, -2), grace));
+
+        health.restarted("sensor");
+        let status = &health.get()[0];
+        assert_eq!(1, status.restart_count);
+        assert!(status.unhealthy_since.is_none());
+        assert!(!status.last_probe.as_ref().unwrap().healthy);
+
+        health.retain(&std::collections::BTreeSet::new());
+        assert!(health.get().is_empty());
+    }
+}
@@ -50,6 +50,10 @@ pub struct ModuleStatus {
     pub exit_status: Option<ExitStatus>,
 
     pub runtime_status: RuntimeStatus,
+
+    /// Present for modules with a restart policy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_breaker: Option<edgelet_core::CircuitBreaker>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -73,6 +77,15 @@ impl ModuleSpec {
         &self.name
     }
 
+    /// Whether the module's create options put it on the host's network.
+    pub fn host_network(&self) -> bool {
+        self.config
+            .settings
+            .pointer("/createOptions/HostConfig/NetworkMode")
+            .and_then(serde_json::Value::as_str)
+            == Some("host")
+    }
+
     pub fn to_runtime_spec<M>(
         self,
     ) -> Result<edgelet_settings::ModuleSpec<<M as edgelet_core::ModuleRuntime>::Config>, String>
@@ -134,6 +147,7 @@ impl ModuleDetails {
                     status: status.to_string(),
                     description: None,
                 },
+                circuit_breaker: None,
             },
         }
     }
@@ -181,8 +195,9 @@ impl std::convert::From<edgelet_core::ModuleRuntimeState> for ModuleStatus {
             exit_status,
             runtime_status: RuntimeStatus {
                 status: state.status().to_string(),
-                description: None, // The description was only ever `state.status().to_owned()` anyway.
+                description: state.description().map(ToOwned::to_owned),
             },
+            circuit_breaker: state.circuit_breaker().cloned(),
         }
     }
 }
@@ -253,7 +268,8 @@ mod tests {
                 runtime_status: super::RuntimeStatus {
                     status: "running".to_string(),
                     description: None,
-                }
+                },
+                circuit_breaker: None,
             },
             status.into()
         );
@@ -275,10 +291,24 @@ mod tests {
                 runtime_status: super::RuntimeStatus {
                     status: "stopped".to_string(),
                     description: None,
-                }
+                },
+                circuit_breaker: None,
             },
             status.into()
         );
+
+        // Module whose circuit breaker tripped
+        let circuit_breaker = edgelet_core::CircuitBreaker {
+            tripped: true,
+            restarts: 5,
+            next_retry: Some(timestamp),
+        };
+        let status = ModuleRuntimeState::default()
+            .with_status(edgelet_core::ModuleStatus::Stopped)
+            .with_circuit_breaker(Some(circuit_breaker.clone()));
+
+        let status: super::ModuleStatus = status.into();
+        assert_eq!(Some(circuit_breaker), status.circuit_breaker);
     }
 
     // Common data set for tests.
@@ -0,0 +1,149 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Maximum length of the recorded error message.
+const MAX_ERROR_LEN: usize = 256;
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageGcRun {
+    pub time: chrono::DateTime<chrono::Utc>,
+    pub images_removed: u64,
+
+    /// Set if the run failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationsStatus {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_image_gc: Option<ImageGcRun>,
+
+    /// Images removed by garbage collection over all runs.
+    #[serde(default)]
+    pub images_reclaimed: u64,
+
+    /// Times the watchdog started or recreated Edge Agent.
+    #[serde(default)]
+    pub watchdog_restarts: u64,
+
+    /// Times modules were restarted for failing their health probes.
+    #[serde(default)]
+    pub unhealthy_restarts: u64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_reprovision: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Results of the daemon's own operations, for Edge Agent to report in its twin.
+///
+/// Like the restart history, the counts are persisted in the home directory so that
+/// they survive daemon restarts and reprovisioning.
+#[derive(Clone, Default)]
+pub struct Operations {
+    status: std::sync::Arc<std::sync::Mutex<OperationsStatus>>,
+    path: Option<std::path::PathBuf>,
+}
+
+impl Operations {
+    /// Load the operations recorded by previous runs of the daemon.
+    pub fn start(path: std::path::PathBuf) -> std::io::Result<Self> {
+        let status = crate::persist::read_json(&path, "operations report")?.unwrap_or_default();
+
+        Ok(Operations {
+            status: std::sync::Arc::new(std::sync::Mutex::new(status)),
+            path: Some(path),
+        })
+    }
+
+    pub fn get(&self) -> OperationsStatus {
+        self.status
+            .lock()
+            .expect("operations lock poisoned")
+            .clone()
+    }
+
+    /// Record a run of image garbage collection.
+    pub fn image_gc(&self, images_removed: usize, error: Option<String>) {
+        let images_removed = u64::try_from(images_removed).unwrap_or(u64::MAX);
+
+        self.update(|status| {
+            status.images_reclaimed = status.images_reclaimed.saturating_add(images_removed);
+            status.last_image_gc = Some(ImageGcRun {
+                time: chrono::Utc::now(),
+                images_removed,
+                error: error.map(|mut error| {
+                    if error.len() > MAX_ERROR_LEN {
+                        let mut end = MAX_ERROR_LEN;
+                        while !error.is_char_boundary(end) {
+                            end -= 1;
+                        }
+                        error.truncate(end);
+                    }
+
+                    error
+                }),
+            });
+        });
+    }
+
+    pub fn watchdog_restart(&self) {
+        self.update(|status| status.watchdog_restarts += 1);
+    }
+
+    pub fn unhealthy_restart(&self) {
+        self.update(|status| status.unhealthy_restarts += 1);
+    }
+
+    pub fn reprovisioned(&self) {
+        self.update(|status| status.last_reprovision = Some(chrono::Utc::now()));
+    }
+
+    fn update(&self, f: impl FnOnce(&mut OperationsStatus)) {
+        let mut status = self.status.lock().expect("operations lock poisoned");
+        f(&mut status);
+
+        if let Some(path) = &self.path {
+            if let Err(err) = crate::persist::write_json(path, &*status) {
+                log::warn!("Failed to save operations report: {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Operations;
+
+    #[test]
+    fn persisted() {
+        let dir = std::env::temp_dir().join(format!("operations-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("operations.json");
+        let _ = std::fs::remove_file(&path);
+
+        let operations = Operations::start(path.clone()).unwrap();
+        assert_eq!(super::OperationsStatus::default(), operations.get());
+
+        operations.image_gc(3, None);
+        operations.image_gc(1, Some("x".repeat(1000)));
+        operations.watchdog_restart();
+        operations.unhealthy_restart();
+        operations.unhealthy_restart();
+        operations.reprovisioned();
+
+        // The counts survive a restart of the daemon.
+        let status = Operations::start(path).unwrap().get();
+        assert_eq!(4, status.images_reclaimed);
+        assert_eq!(1, status.watchdog_restarts);
+        assert_eq!(2, status.unhealthy_restarts);
+        assert!(status.last_reprovision.is_some());
+
+        let last_image_gc = status.last_image_gc.unwrap();
+        assert_eq!(1, last_image_gc.images_removed);
+        assert_eq!(super::MAX_ERROR_LEN, last_image_gc.error.unwrap().len());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
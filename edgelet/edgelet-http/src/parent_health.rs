@@ -0,0 +1,186 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Maximum length of the recorded error message.
+const MAX_ERROR_LEN: usize = 256;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ParentState {
+    /// The parent hasn't been checked yet.
+    Connecting,
+
+    Reachable,
+
+    /// No TCP connection could be made to the parent.
+    Unreachable,
+
+    /// The parent was reached, but its server certificate isn't trusted by the device's
+    /// trust bundle.
+    Untrusted,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParentHealthStatus {
+    /// The parent's hostname, as resolved from the device's gateway host.
+    pub host: String,
+
+    pub state: ParentState,
+
+    /// When the state last changed.
+    pub since: chrono::DateTime<chrono::Utc>,
+
+    /// Failed checks since the last successful one.
+    pub consecutive_failures: u64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_success: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Whether a nested device can reach its parent, so that work that needs the parent,
+/// like pulling images through its registry proxy, can wait for it.
+///
+/// Devices that connect to IoT Hub directly have no parent, and no status.
+#[derive(Clone, Default)]
+pub struct ParentHealth {
+    status: std::sync::Arc<std::sync::Mutex<Option<ParentHealthStatus>>>,
+}
+
+impl ParentHealth {
+    /// Start tracking the parent, once the device is provisioned.
+    pub fn set_parent(&self, host: &str) {
+        let mut status = self.status.lock().expect("parent health lock poisoned");
+
+        if status.as_ref().map_or(true, |status| status.host != host) {
+            *status = Some(ParentHealthStatus {
+                host: host.to_string(),
+                state: ParentState::Connecting,
+                since: chrono::Utc::now(),
+                consecutive_failures: 0,
+                last_error: None,
+                last_success: None,
+            });
+        }
+    }
+
+    pub fn get(&self) -> Option<ParentHealthStatus> {
+        self.status
+            .lock()
+            .expect("parent health lock poisoned")
+            .clone()
+    }
+
+    /// Whether the device has a parent that failed its last check.
+    pub fn is_down(&self) -> bool {
+        self.get().map_or(false, |status| {
+            matches!(
+                status.state,
+                ParentState::Unreachable | ParentState::Untrusted
+            )
+        })
+    }
+
+    pub fn reachable(&self) {
+        let now = chrono::Utc::now();
+        let mut status = self.status.lock().expect("parent health lock poisoned");
+
+        let Some(status) = status.as_mut() else {
+            return;
+        };
+
+        if status.state != ParentState::Reachable {
+            log::info!("Parent {} is reachable", status.host);
+
+            status.state = ParentState::Reachable;
+            status.since = now;
+        }
+
+        status.consecutive_failures = 0;
+        status.last_success = Some(now);
+    }
+
+    pub fn unreachable(&self, error: &impl std::fmt::Display) {
+        self.failed(ParentState::Unreachable, &error.to_string());
+    }
+
+    pub fn untrusted(&self, error: &impl std::fmt::Display) {
+        self.failed(ParentState::Untrusted, &error.to_string());
+    }
+
+    fn failed(&self, state: ParentState, error: &str) {
+        let mut last_error: String = error.chars().take(MAX_ERROR_LEN).collect();
+        if last_error.len() < error.len() {
+            last_error.push_str("...");
+        }
+
+        let mut status = self.status.lock().expect("parent health lock poisoned");
+
+        let Some(status) = status.as_mut() else {
+            return;
+        };
+
+        if status.state != state {
+            let state_name = match state {
+                ParentState::Untrusted => "not trusted",
+                _ => "unreachable",
+            };
+            log::warn!("Parent {} is {}: {}", status.host, state_name, last_error);
+
+            status.state = state;
+            status.since = chrono::Utc::now();
+        }
+
+        status.consecutive_failures += 1;
+        status.last_error = Some(last_error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParentHealth, ParentState};
+
+    #[test]
+    fn transitions() {
+        // Without a parent, checks aren't recorded.
+        let health = ParentHealth::default();
+        health.unreachable(&"connection refused");
+        assert!(health.get().is_none());
+        assert!(!health.is_down());
+
+        health.set_parent("parent.example");
+        assert_eq!(ParentState::Connecting, health.get().unwrap().state);
+        assert!(!health.is_down());
+
+        health.unreachable(&"connection refused");
+        health.untrusted(&"certificate verify failed");
+
+        let status = health.get().unwrap();
+        assert_eq!(ParentState::Untrusted, status.state);
+        assert_eq!(2, status.consecutive_failures);
+        assert!(health.is_down());
+
+        health.reachable();
+
+        let status = health.get().unwrap();
+        assert_eq!(ParentState::Reachable, status.state);
+        assert_eq!(0, status.consecutive_failures);
+        assert!(status.last_success.is_some());
+        assert!(!health.is_down());
+
+        // The last error is kept for troubleshooting.
+        assert_eq!(
+            Some("certificate verify failed".to_string()),
+            status.last_error
+        );
+
+        // Setting the same parent again keeps its status.
+        health.set_parent("parent.example");
+        assert_eq!(ParentState::Reachable, health.get().unwrap().state);
+
+        health.set_parent("other.example");
+        assert_eq!(ParentState::Connecting, health.get().unwrap().state);
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! State files that the daemon keeps across restarts.
+//!
+//! Files are replaced by writing a temporary file next to them and renaming it over
+//! them, as for the image use data of image garbage collection, so that a crash or a
+//! full disk leaves either the old or the new contents. A file that can't be parsed
+//! anyway, e.g. because it was edited by hand, is moved aside to `<file>.corrupt` and
+//! its state starts over from the default, so that it never stops the daemon from
+//! starting.
+
+use std::io::Write;
+
+/// Read a JSON state file. Returns `None` if it doesn't exist or is corrupt.
+pub fn read_json<T>(path: &std::path::Path, description: &str) -> std::io::Result<Option<T>>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    match serde_json::from_slice(&contents) {
+        Ok(value) => Ok(Some(value)),
+        Err(err) => {
+            set_aside(path, description, &err);
+
+            Ok(None)
+        }
+    }
+}
+
+/// Move a corrupt state file aside, so that it's replaced by the default state but can
+/// still be inspected.
+pub fn set_aside(path: &std::path::Path, description: &str, err: &dyn std::fmt::Display) {
+    let corrupt = with_suffix(path, "corrupt");
+
+    log::warn!(
+        "Discarding invalid {} and moving it to {}: {}",
+        description,
+        corrupt.display(),
+        err
+    );
+
+    if let Err(err) = std::fs::rename(path, &corrupt) {
+        log::warn!("Failed to move invalid {} aside: {}", description, err);
+    }
+}
+
+/// Replace a state file.
+pub fn write(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+    let temp = with_suffix(path, "tmp");
+
+    let result = std::fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&temp, path));
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+
+    result
+}
+
+/// Replace a state file with a value serialized as JSON.
+pub fn write_json<T>(path: &std::path::Path, value: &T) -> std::io::Result<()>
+where
+    T: serde::Serialize + ?Sized,
+{
+    let contents = serde_json::to_vec(value)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+
+    write(path, &contents)
+}
+
+fn with_suffix(path: &std::path::Path, suffix: &str) -> std::path::PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_json, write_json};
+
+    #[test]
+    fn write_and_read() {
+        let dir = std::env::temp_dir().join("edgelet-http-persist-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.json");
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(None, read_json::<Vec<u32>>(&path, "state").unwrap());
+
+        write_json(&path, &[1, 2, 3]).unwrap();
+        assert_eq!(
+            Some(vec![1, 2, 3]),
+            read_json::<Vec<u32>>(&path, "state").unwrap()
+        );
+        assert!(!dir.join("state.json.tmp").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt() {
+        let dir = std::env::temp_dir().join("edgelet-http-persist-corrupt-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.json");
+        std::fs::write(&path, "[1, 2").unwrap();
+
+        // A corrupt file is moved aside, so that it's only reported once.
+        assert_eq!(None, read_json::<Vec<u32>>(&path, "state").unwrap());
+        assert!(!path.exists());
+        assert_eq!(
+            "[1, 2",
+            std::fs::read_to_string(dir.join("state.json.corrupt")).unwrap()
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,403 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::{BufRead, Write};
+
+/// The file that a module's output is appended to until it is rotated.
+const CURRENT_FILE: &str = "current.log";
+
+/// Suffix of rotated files, which are compressed.
+const ROTATED_SUFFIX: &str = ".log.gz";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// One write of a module to its stdout or stderr.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LogEntry {
+    pub time: chrono::DateTime<chrono::Utc>,
+    pub stream: LogStream,
+    pub log: String,
+}
+
+struct CurrentFile {
+    size: u64,
+
+    /// Time of the first entry in the file, which is when it's due to be rotated from.
+    opened: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Time of the last entry in the file, or of the last rotated file if it's empty.
+    last: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+struct Inner {
+    dir: std::path::PathBuf,
+    settings: edgelet_settings::LogPersistence,
+    files: std::sync::Mutex<std::collections::HashMap<String, CurrentFile>>,
+}
+
+/// Module output kept by the daemon under `homedir/logs/<module>/`.
+///
+/// Output is appended to `current.log` as JSON lines. The file is rotated when it reaches
+/// the configured size or age, and rotated files are compressed and deleted when there
+/// are too many of them or they are too old. The default value persists nothing.
+#[derive(Clone, Default)]
+pub struct PersistedLogs {
+    inner: Option<std::sync::Arc<Inner>>,
+}
+
+impl PersistedLogs {
+    pub fn new(dir: std::path::PathBuf, settings: &edgelet_settings::LogPersistence) -> Self {
+        PersistedLogs {
+            inner: Some(std::sync::Arc::new(Inner {
+                dir,
+                settings: settings.clone(),
+                files: std::sync::Mutex::default(),
+            })),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Whether a module's output is kept.
+    pub fn persists(&self, module: &str) -> bool {
+        self.inner.as_ref().map_or(false, |inner| {
+            let modules = inner.settings.modules();
+            (modules.is_empty() || modules.iter().any(|m| m == module))
+                && module_dir(&inner.dir, module).is_some()
+        })
+    }
+
+    /// Time of the last entry kept for a module, so that following its output can resume
+    /// from there.
+    pub fn last_time(
+        &self,
+        module: &str,
+    ) -> std::io::Result<Option<chrono::DateTime<chrono::Utc>>> {
+        let inner = match &self.inner {
+            Some(inner) => inner,
+            None => return Ok(None),
+        };
+
+        let mut files = inner.files.lock().expect("persisted logs lock poisoned");
+        let file = inner.current_file(&mut files, module)?;
+
+        Ok(file.last)
+    }
+
+    pub fn append(&self, module: &str, entries: &[LogEntry]) -> std::io::Result<()> {
+        let inner = match &self.inner {
+            Some(inner) => inner,
+            None => return Ok(()),
+        };
+
+        let dir = module_dir(&inner.dir, module).ok_or_else(|| invalid_module(module))?;
+        std::fs::create_dir_all(&dir)?;
+
+        let mut files = inner.files.lock().expect("persisted logs lock poisoned");
+        let file = inner.current_file(&mut files, module)?;
+
+        let rotate_interval = chrono::Duration::from_std(inner.settings.rotate_interval())
+            .unwrap_or_else(|_| chrono::Duration::max_value());
+
+        let mut writer = std::io::BufWriter::new(
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(dir.join(CURRENT_FILE))?,
+        );
+
+        for entry in entries {
+            let due = file.size >= inner.settings.max_file_size()
+                || file
+                    .opened
+                    .map_or(false, |opened| entry.time - opened >= rotate_interval);
+
+            if due && file.size > 0 {
+                writer.flush()?;
+                inner.rotate(&dir, file)?;
+
+                writer = std::io::BufWriter::new(
+                    std::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(dir.join(CURRENT_FILE))?,
+                );
+            }
+
+            let mut line = serde_json::to_vec(entry).expect("log entries must serialize");
+            line.push(b'\n');
+            writer.write_all(&line)?;
+
+            file.size += line.len() as u64;
+            file.opened.get_or_insert(entry.time);
+            file.last = Some(entry.time);
+        }
+
+        writer.flush()
+    }
+
+    /// Entries kept for a module, oldest first. Only entries at or after `since` and
+    /// before `until` are returned, and only the last `tail` of those if given.
+    pub fn read(
+        &self,
+        module: &str,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        tail: Option<usize>,
+    ) -> std::io::Result<Vec<LogEntry>> {
+        let inner = match &self.inner {
+            Some(inner) => inner,
+            None => return Ok(Vec::new()),
+        };
+
+        let dir = module_dir(&inner.dir, module).ok_or_else(|| invalid_module(module))?;
+
+        let mut paths = rotated_files(&dir)?;
+        paths.push(dir.join(CURRENT_FILE));
+
+        let mut entries = std::collections::VecDeque::new();
+
+        for path in paths {
+            let file = match std::fs::File::open(&path) {
+                Ok(file) => file,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+
+            let reader: Box<dyn BufRead> = if path.ends_with(CURRENT_FILE) {
+                Box::new(std::io::BufReader::new(file))
+            } else {
+                Box::new(std::io::BufReader::new(flate2::read::GzDecoder::new(file)))
+            };
+
+            for line in reader.lines() {
+                // A line may be cut short if the daemon stopped while writing it.
+                let entry: LogEntry = match serde_json::from_str(&line?) {
+                    Ok(entry) => entry,
+                    Err(_) => continue,
+                };
+
+                if since.map_or(false, |since| entry.time < since)
+                    || until.map_or(false, |until| entry.time >= until)
+                {
+                    continue;
+                }
+
+                entries.push_back(entry);
+
+                if tail.map_or(false, |tail| entries.len() > tail) {
+                    entries.pop_front();
+                }
+            }
+        }
+
+        Ok(entries.into())
+    }
+}
+
+impl Inner {
+    fn current_file<'a>(
+        &self,
+        files: &'a mut std::collections::HashMap<String, CurrentFile>,
+        module: &str,
+    ) -> std::io::Result<&'a mut CurrentFile> {
+        match files.entry(module.to_string()) {
+            std::collections::hash_map::Entry::Occupied(file) => Ok(file.into_mut()),
+            std::collections::hash_map::Entry::Vacant(vacant) => {
+                let dir = module_dir(&self.dir, module).ok_or_else(|| invalid_module(module))?;
+                let path = dir.join(CURRENT_FILE);
+
+                let mut file = CurrentFile {
+                    size: 0,
+                    opened: None,
+                    last: None,
+                };
+
+                match std::fs::File::open(&path) {
+                    Ok(current) => {
+                        file.size = current.metadata()?.len();
+
+                        for line in std::io::BufReader::new(current).lines() {
+                            if let Ok(entry) = serde_json::from_str::<LogEntry>(&line?) {
+                                file.opened.get_or_insert(entry.time);
+                                file.last = Some(entry.time);
+                            }
+                        }
+                    }
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => (),
+                    Err(err) => return Err(err),
+                }
+
+                Ok(vacant.insert(file))
+            }
+        }
+    }
+
+    /// Compress the current file into a rotated file, and delete the rotated files that
+    /// are no longer kept.
+    fn rotate(&self, dir: &std::path::Path, file: &mut CurrentFile) -> std::io::Result<()> {
+        let opened = file.opened.unwrap_or_else(chrono::Utc::now);
+        let rotated = dir.join(format!(
+            "{}{}",
+            opened.format("%Y%m%dT%H%M%S%.6fZ"),
+            ROTATED_SUFFIX
+        ));
+
+        let current = dir.join(CURRENT_FILE);
+
+        let mut encoder = flate2::write::GzEncoder::new(
+            std::fs::File::create(&rotated)?,
+            flate2::Compression::default(),
+        );
+        std::io::copy(&mut std::fs::File::open(&current)?, &mut encoder)?;
+        encoder.finish()?.sync_all()?;
+
+        std::fs::remove_file(&current)?;
+
+        file.size = 0;
+        file.opened = None;
+
+        self.prune(dir)
+    }
+
+    fn prune(&self, dir: &std::path::Path) -> std::io::Result<()> {
+        let mut rotated = rotated_files(dir)?;
+
+        let excess = rotated.len().saturating_sub(self.settings.max_files());
+        let mut expired: Vec<_> = rotated.drain(..excess).collect();
+
+        let now = std::time::SystemTime::now();
+        for path in rotated {
+            let modified = std::fs::metadata(&path)?.modified()?;
+
+            if now
+                .duration_since(modified)
+                .map_or(false, |age| age > self.settings.max_age())
+            {
+                expired.push(path);
+            }
+        }
+
+        for path in expired {
+            if let Err(err) = std::fs::remove_file(&path) {
+                log::warn!("Failed to delete old log file {}: {}", path.display(), err);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Rotated files of a module, oldest first.
+fn rotated_files(dir: &std::path::Path) -> std::io::Result<Vec<std::path::PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+
+        if path
+            .file_name()
+            .and_then(std::ffi::OsStr::to_str)
+            .map_or(false, |name| name.ends_with(ROTATED_SUFFIX))
+        {
+            paths.push(path);
+        }
+    }
+
+    // Names start with the time of their first entry, so they sort by age.
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// The directory of a module's files. Names that would escape the logs directory have none.
+fn module_dir(dir: &std::path::Path, module: &str) -> Option<std::path::PathBuf> {
+    if module.is_empty() || module.starts_with('.') || module.contains(['/', '\\']) {
+        None
+    } else {
+        Some(dir.join(module))
+    }
+}
+
+fn invalid_module(module: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("invalid module name: {module:?}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogEntry, LogStream, PersistedLogs};
+
+    fn entry(time: chrono::DateTime<chrono::Utc>, log: &str) -> LogEntry {
+        LogEntry {
+            time,
+            stream: LogStream::Stdout,
+            log: log.to_string(),
+        }
+    }
+
+    #[test]
+    fn rotate_and_read() {
+        let dir = std::env::temp_dir().join(format!("persisted-logs-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let settings: edgelet_settings::LogPersistence =
+            serde_json::from_value(serde_json::json!({ "rotate_interval": "1h", "max_files": 2 }))
+                .unwrap();
+        let logs = PersistedLogs::new(dir.clone(), &settings);
+
+        let t0 = chrono::Utc::now() - chrono::Duration::hours(10);
+        let at = |hours| t0 + chrono::Duration::hours(hours);
+
+        assert!(logs.persists("sensor"));
+        assert!(!logs.persists(".."));
+        assert!(logs
+            .append("../sensor", &[entry(at(0), "escaped")])
+            .is_err());
+        assert_eq!(None, logs.last_time("sensor").unwrap());
+
+        // Each entry is an hour apart, so each one rotates the file before it.
+        for hour in 0..5 {
+            logs.append("sensor", &[entry(at(hour), &hour.to_string())])
+                .unwrap();
+        }
+
+        // Only the newest two rotated files are kept, along with the current file.
+        let entries = logs.read("sensor", None, None, None).unwrap();
+        assert_eq!(
+            ["2", "3", "4"],
+            entries.iter().map(|e| e.log.as_str()).collect::<Vec<_>>()[..]
+        );
+
+        let entries = logs.read("sensor", Some(at(3)), None, None).unwrap();
+        assert_eq!(2, entries.len());
+
+        let entries = logs.read("sensor", None, Some(at(4)), Some(1)).unwrap();
+        assert_eq!(vec![entry(at(3), "3")], entries);
+
+        // The state of the current file is recovered after a restart.
+        let logs = PersistedLogs::new(dir.clone(), &settings);
+        assert_eq!(Some(at(4)), logs.last_time("sensor").unwrap());
+
+        // Nothing is kept when persistence isn't configured.
+        let logs = PersistedLogs::default();
+        assert!(!logs.persists("sensor"));
+        logs.append("sensor", &[entry(at(5), "5")]).unwrap();
+        assert!(logs.read("sensor", None, None, None).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_settings::WorkloadRateLimit;
+
+/// Number of requests from a module that were allowed and rejected by the rate limit.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RateLimitCounters {
+    pub allowed: u64,
+    pub limited: u64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: std::time::Instant,
+    counters: RateLimitCounters,
+}
+
+/// Per-module token bucket rate limits for the workload API.
+#[derive(Clone, Default)]
+pub struct RateLimit {
+    settings: std::sync::Arc<WorkloadRateLimit>,
+    buckets: std::sync::Arc<std::sync::Mutex<std::collections::BTreeMap<String, Bucket>>>,
+}
+
+impl RateLimit {
+    pub fn new(settings: WorkloadRateLimit) -> Self {
+        RateLimit {
+            settings: std::sync::Arc::new(settings),
+            buckets: std::sync::Arc::default(),
+        }
+    }
+
+    /// Wrap a module's workload API service so that its requests are rate limited.
+    pub fn wrap<S>(&self, module_id: &str, inner: S) -> RateLimitService<S> {
+        RateLimitService {
+            inner,
+            module_id: module_id.to_string(),
+            rate_limit: self.clone(),
+        }
+    }
+
+    /// Request counters for each module that has called the workload API.
+    pub fn counters(&self) -> std::collections::BTreeMap<String, RateLimitCounters> {
+        let buckets = self.buckets.lock().expect("rate limit lock poisoned");
+
+        buckets
+            .iter()
+            .map(|(module_id, bucket)| (module_id.clone(), bucket.counters))
+            .collect()
+    }
+
+    /// Take a token for a request from a module. If none are available, returns how
+    /// long the module should wait before retrying.
+    fn acquire(&self, module_id: &str, now: std::time::Instant) -> Result<(), std::time::Duration> {
+        if !self.settings.is_enabled() {
+            return Ok(());
+        }
+
+        let rate = self.settings.requests_per_second;
+        let burst = f64::from(self.settings.burst.max(1));
+
+        let mut buckets = self.buckets.lock().expect("rate limit lock poisoned");
+        let bucket = buckets
+            .entry(module_id.to_string())
+            .or_insert_with(|| Bucket {
+                tokens: burst,
+                updated: now,
+                counters: RateLimitCounters::default(),
+            });
+
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            bucket.counters.allowed += 1;
+
+            Ok(())
+        } else {
+            bucket.counters.limited += 1;
+
+            // A tiny rate can make the wait too long to represent.
+            Err(
+                std::time::Duration::try_from_secs_f64((1.0 - bucket.tokens) / rate)
+                    .unwrap_or(std::time::Duration::MAX),
+            )
+        }
+    }
+
+    /// Take a token for a request from a module, or build the response that rejects it.
+    pub(crate) fn check(
+        &self,
+        module_id: &str,
+        req: &hyper::Request<hyper::Body>,
+    ) -> Option<hyper::Response<hyper::Body>> {
+        let retry_after = self.acquire(module_id, std::time::Instant::now()).err()?;
+
+        log::debug!(
+            "Rate limited {} {} from module {}",
+            req.method(),
+            req.uri().path(),
+            module_id
+        );
+
+        Some(too_many_requests(retry_after))
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    module_id: String,
+    rate_limit: RateLimit,
+}
+
+impl<S> hyper::service::Service<hyper::Request<hyper::Body>> for RateLimitService<S>
+where
+    S: hyper::service::Service<
+        hyper::Request<hyper::Body>,
+        Response = hyper::Response<hyper::Body>,
+        Error = std::convert::Infallible,
+    >,
+    S::Future: Send + 'static,
+{
+    type Response = hyper::Response<hyper::Body>;
+    type Error = std::convert::Infallible;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: hyper::Request<hyper::Body>) -> Self::Future {
+        if let Some(response) = self.rate_limit.check(&self.module_id, &req) {
+            return Box::pin(async move { Ok(response) });
+        }
+
+        Box::pin(self.inner.call(req))
+    }
+}
+
+fn too_many_requests(retry_after: std::time::Duration) -> hyper::Response<hyper::Body> {
+    // Retry-After is in whole seconds, so round up to avoid an immediate retry that
+    // would also be rejected.
+    let mut retry_after_secs = retry_after.as_secs();
+    if retry_after.subsec_nanos() > 0 || retry_after_secs == 0 {
+        retry_after_secs = retry_after_secs.saturating_add(1);
+    }
+
+    let body = serde_json::json!({ "message": "too many requests" }).to_string();
+
+    hyper::Response::builder()
+        .status(hyper::StatusCode::TOO_MANY_REQUESTS)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .header(hyper::header::RETRY_AFTER, retry_after_secs.to_string())
+        .body(body.into())
+        .expect("cannot fail to build hyper response")
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_settings::WorkloadRateLimit;
+
+    use super::{RateLimit, RateLimitCounters};
+
+    #[test]
+    fn disabled() {
+        let rate_limit = RateLimit::default();
+        let now = std::time::Instant::now();
+
+        for _ in 0..100 {
+            rate_limit.acquire("testModule", now).unwrap();
+        }
+
+        assert!(rate_limit.counters().is_empty());
+    }
+
+    #[test]
+    fn token_bucket() {
+        let rate_limit = RateLimit::new(WorkloadRateLimit {
+            requests_per_second: 2.0,
+            burst: 5,
+        });
+        let now = std::time::Instant::now();
+
+        // The full burst is allowed at once.
+        for _ in 0..5 {
+            rate_limit.acquire("testModule", now).unwrap();
+        }
+
+        let retry_after = rate_limit.acquire("testModule", now).unwrap_err();
+        assert_eq!(std::time::Duration::from_millis(500), retry_after);
+
+        // Other modules have their own bucket.
+        rate_limit.acquire("otherModule", now).unwrap();
+
+        // Tokens are refilled at the sustained rate.
+        let later = now + std::time::Duration::from_secs(1);
+        rate_limit.acquire("testModule", later).unwrap();
+        rate_limit.acquire("testModule", later).unwrap();
+        rate_limit.acquire("testModule", later).unwrap_err();
+
+        let counters = rate_limit.counters();
+        assert_eq!(
+            RateLimitCounters {
+                allowed: 7,
+                limited: 2
+            },
+            counters["testModule"]
+        );
+        assert_eq!(
+            RateLimitCounters {
+                allowed: 1,
+                limited: 0
+            },
+            counters["otherModule"]
+        );
+    }
+
+    #[test]
+    fn tiny_rate() {
+        let rate_limit = RateLimit::new(WorkloadRateLimit {
+            requests_per_second: f64::MIN_POSITIVE,
+            burst: 1,
+        });
+        let now = std::time::Instant::now();
+
+        rate_limit.acquire("testModule", now).unwrap();
+
+        let retry_after = rate_limit.acquire("testModule", now).unwrap_err();
+        assert_eq!(std::time::Duration::MAX, retry_after);
+
+        let response = super::too_many_requests(retry_after);
+        assert_eq!(
+            u64::MAX.to_string(),
+            response.headers()[hyper::header::RETRY_AFTER]
+        );
+    }
+
+    #[test]
+    fn retry_after() {
+        let response = super::too_many_requests(std::time::Duration::from_millis(200));
+
+        assert_eq!(hyper::StatusCode::TOO_MANY_REQUESTS, response.status());
+        assert_eq!("1", response.headers()[hyper::header::RETRY_AFTER]);
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Number of daemon restarts to keep in the history.
+const MAX_RESTARTS: usize = 20;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShutdownReason {
+    /// The daemon is still running.
+    Running,
+
+    /// The daemon stopped without recording a reason, e.g. it was killed or the device
+    /// lost power.
+    Unknown,
+
+    Signal,
+    Reprovision,
+    Error,
+    Panic,
+}
+
+impl From<&edgelet_core::WatchdogAction> for ShutdownReason {
+    fn from(action: &edgelet_core::WatchdogAction) -> Self {
+        match action {
+            edgelet_core::WatchdogAction::Signal => ShutdownReason::Signal,
+            edgelet_core::WatchdogAction::Reprovision => ShutdownReason::Reprovision,
+
+            // Edge CA renewal doesn't stop the daemon, so it isn't expected here.
+            edgelet_core::WatchdogAction::EdgeCaRenewal => ShutdownReason::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Restart {
+    pub start_time: chrono::DateTime<chrono::Utc>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_time: Option<chrono::DateTime<chrono::Utc>>,
+
+    pub reason: ShutdownReason,
+}
+
+/// The most recent daemon starts and why each of them stopped.
+///
+/// The history is persisted in the home directory rather than the cache directory,
+/// since reprovisioning clears the cache.
+#[derive(Clone, Default)]
+pub struct RestartHistory {
+    restarts: std::sync::Arc<std::sync::Mutex<Vec<Restart>>>,
+    path: Option<std::path::PathBuf>,
+}
+
+impl RestartHistory {
+    /// Load the restart history and record that the daemon has started.
+    pub fn start(path: std::path::PathBuf) -> std::io::Result<Self> {
+        let mut restarts: Vec<Restart> =
+            crate::persist::read_json(&path, "restart history")?.unwrap_or_default();
+
+        // A previous run that is still marked as running didn't shut down cleanly.
+        for restart in &mut restarts {
+            if restart.reason == ShutdownReason::Running {
+                restart.reason = ShutdownReason::Unknown;
+            }
+        }
+
+        restarts.push(Restart {
+            start_time: chrono::Utc::now(),
+            stop_time: None,
+            reason: ShutdownReason::Running,
+        });
+
+        if restarts.len() > MAX_RESTARTS {
+            restarts.drain(..restarts.len() - MAX_RESTARTS);
+        }
+
+        let history = RestartHistory {
+            restarts: std::sync::Arc::new(std::sync::Mutex::new(restarts)),
+            path: Some(path),
+        };
+
+        history.persist(
+            &history
+                .restarts
+                .lock()
+                .expect("restart history lock poisoned"),
+        )?;
+
+        Ok(history)
+    }
+
+    /// Restarts in the history, most recent first.
+    pub fn get(&self) -> Vec<Restart> {
+        let restarts = self.restarts.lock().expect("restart history lock poisoned");
+
+        restarts.iter().rev().cloned().collect()
+    }
+
+    /// Record why the current run of the daemon is stopping.
+    pub fn stop(&self, reason: ShutdownReason) {
+        let mut restarts = self.restarts.lock().expect("restart history lock poisoned");
+
+        if let Some(current) = restarts.last_mut() {
+            current.stop_time = Some(chrono::Utc::now());
+            current.reason = reason;
+        }
+
+        if let Err(err) = self.persist(&restarts) {
+            log::warn!("Failed to save restart history: {}", err);
+        }
+    }
+
+    fn persist(&self, restarts: &[Restart]) -> std::io::Result<()> {
+        if let Some(path) = &self.path {
+            crate::persist::write_json(path, restarts)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RestartHistory, ShutdownReason};
+
+    #[test]
+    fn history() {
+        let dir = std::env::temp_dir().join(format!("restarts-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("restarts.json");
+        let _ = std::fs::remove_file(&path);
+
+        let history = RestartHistory::start(path.clone()).unwrap();
+        let restarts = history.get();
+        assert_eq!(1, restarts.len());
+        assert_eq!(ShutdownReason::Running, restarts[0].reason);
+        assert!(restarts[0].stop_time.is_none());
+
+        history.stop(ShutdownReason::Signal);
+
+        // A run that didn't record a reason is reported as unknown.
+        RestartHistory::start(path.clone()).unwrap();
+
+        let history = RestartHistory::start(path).unwrap();
+        let reasons: Vec<_> = history.get().iter().map(|restart| restart.reason).collect();
+        assert_eq!(
+            vec![
+                ShutdownReason::Running,
+                ShutdownReason::Unknown,
+                ShutdownReason::Signal
+            ],
+            reasons
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn max_restarts() {
+        let dir = std::env::temp_dir().join(format!("restarts-max-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("restarts.json");
+        let _ = std::fs::remove_file(&path);
+
+        for _ in 0..super::MAX_RESTARTS + 5 {
+            RestartHistory::start(path.clone()).unwrap();
+        }
+
+        let history = RestartHistory::start(path).unwrap();
+        assert_eq!(super::MAX_RESTARTS, history.get().len());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
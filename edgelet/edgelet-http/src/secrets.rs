@@ -0,0 +1,227 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// ID of the Keys Service key that secrets are encrypted with.
+pub const SECRETS_KEY_ID: &str = "iotedge_secrets";
+
+type Store = std::collections::BTreeMap<String, std::collections::BTreeMap<String, StoredSecret>>;
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+struct StoredSecret {
+    /// Base64-encoded.
+    iv: String,
+
+    /// Base64-encoded.
+    ciphertext: String,
+
+    updated: chrono::DateTime<chrono::Utc>,
+}
+
+/// A secret in the store, without its value.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SecretInfo {
+    pub module: String,
+    pub name: String,
+    pub updated: chrono::DateTime<chrono::Utc>,
+}
+
+/// Named secrets that operators provide to modules through the workload API, so that
+/// they don't have to be put in module environment variables.
+///
+/// Secrets are stored encrypted with a Keys Service key, with the module and secret
+/// name as additional authenticated data so that a secret can't be moved to another
+/// module.
+#[derive(Clone, Default)]
+pub struct Secrets {
+    secrets: std::sync::Arc<std::sync::Mutex<Store>>,
+    path: Option<std::path::PathBuf>,
+}
+
+impl Secrets {
+    pub fn new(path: std::path::PathBuf) -> std::io::Result<Self> {
+        let secrets = crate::persist::read_json(&path, "secret store")?.unwrap_or_default();
+
+        Ok(Secrets {
+            secrets: std::sync::Arc::new(std::sync::Mutex::new(secrets)),
+            path: Some(path),
+        })
+    }
+
+    /// Secret names are used in URIs and logs, so they are limited to letters, digits,
+    /// '.', '-' and '_'.
+    pub fn is_valid_name(name: &str) -> bool {
+        !name.is_empty()
+            && name.len() <= 128
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+    }
+
+    /// Additional authenticated data for a module's secret.
+    pub fn aad(module: &str, name: &str) -> Vec<u8> {
+        format!("secret:{}/{}", module.trim_start_matches('$'), name).into_bytes()
+    }
+
+    pub fn new_iv() -> Result<Vec<u8>, openssl::error::ErrorStack> {
+        let mut iv = vec![0; 16];
+        openssl::rand::rand_bytes(&mut iv)?;
+
+        Ok(iv)
+    }
+
+    pub fn set(
+        &self,
+        module: &str,
+        name: &str,
+        iv: &[u8],
+        ciphertext: &[u8],
+    ) -> std::io::Result<()> {
+        let mut secrets = self.secrets.lock().expect("secrets lock poisoned");
+
+        secrets
+            .entry(module.trim_start_matches('$').to_string())
+            .or_default()
+            .insert(
+                name.to_string(),
+                StoredSecret {
+                    iv: openssl::base64::encode_block(iv),
+                    ciphertext: openssl::base64::encode_block(ciphertext),
+                    updated: chrono::Utc::now(),
+                },
+            );
+
+        self.persist(&secrets)
+    }
+
+    /// The IV and ciphertext of a module's secret.
+    pub fn get(&self, module: &str, name: &str) -> Option<(Vec<u8>, Vec<u8>)> {
+        let secrets = self.secrets.lock().expect("secrets lock poisoned");
+        let secret = secrets.get(module.trim_start_matches('$'))?.get(name)?;
+
+        let iv = openssl::base64::decode_block(&secret.iv).ok()?;
+        let ciphertext = openssl::base64::decode_block(&secret.ciphertext).ok()?;
+
+        Some((iv, ciphertext))
+    }
+
+    /// Remove a secret. Returns whether it existed.
+    pub fn remove(&self, module: &str, name: &str) -> std::io::Result<bool> {
+        let mut secrets = self.secrets.lock().expect("secrets lock poisoned");
+        let module = module.trim_start_matches('$');
+
+        let removed = match secrets.get_mut(module) {
+            Some(names) => names.remove(name).is_some(),
+            None => false,
+        };
+
+        if removed {
+            if secrets[module].is_empty() {
+                secrets.remove(module);
+            }
+
+            self.persist(&secrets)?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Remove all of a module's secrets, when the module is removed.
+    pub fn remove_module(&self, module: &str) -> std::io::Result<()> {
+        let mut secrets = self.secrets.lock().expect("secrets lock poisoned");
+
+        if secrets.remove(module.trim_start_matches('$')).is_some() {
+            self.persist(&secrets)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<SecretInfo> {
+        self.secrets
+            .lock()
+            .expect("secrets lock poisoned")
+            .iter()
+            .flat_map(|(module, names)| {
+                names.iter().map(move |(name, secret)| SecretInfo {
+                    module: module.clone(),
+                    name: name.clone(),
+                    updated: secret.updated,
+                })
+            })
+            .collect()
+    }
+
+    fn persist(&self, secrets: &Store) -> std::io::Result<()> {
+        if let Some(path) = &self.path {
+            crate::persist::write_json(path, secrets)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Secrets;
+
+    #[test]
+    fn names() {
+        assert!(Secrets::is_valid_name("db-password_2.txt"));
+        assert!(!Secrets::is_valid_name(""));
+        assert!(!Secrets::is_valid_name("../password"));
+        assert!(!Secrets::is_valid_name(&"a".repeat(129)));
+    }
+
+    #[test]
+    fn store() {
+        let dir = std::env::temp_dir().join(format!("secrets-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("secrets.json");
+        let _ = std::fs::remove_file(&path);
+
+        let secrets = Secrets::new(path.clone()).unwrap();
+        secrets
+            .set("$edgeHub", "password", b"iv", b"ciphertext")
+            .unwrap();
+
+        // Secrets are persisted, and '$' is ignored as elsewhere.
+        let secrets = Secrets::new(path.clone()).unwrap();
+        assert_eq!(
+            Some((b"iv".to_vec(), b"ciphertext".to_vec())),
+            secrets.get("edgeHub", "password")
+        );
+        assert_eq!(None, secrets.get("otherModule", "password"));
+
+        let list = secrets.list();
+        assert_eq!(1, list.len());
+        assert_eq!("edgeHub", list[0].module);
+        assert_eq!("password", list[0].name);
+
+        assert!(secrets.remove("edgeHub", "password").unwrap());
+        assert!(!secrets.remove("edgeHub", "password").unwrap());
+        assert!(secrets.list().is_empty());
+
+        // Removing a module removes all of its secrets.
+        secrets.set("edgeHub", "a", b"iv", b"ciphertext").unwrap();
+        secrets.set("edgeHub", "b", b"iv", b"ciphertext").unwrap();
+        secrets.set("other", "a", b"iv", b"ciphertext").unwrap();
+        secrets.remove_module("$edgeHub").unwrap();
+        let secrets = Secrets::new(path.clone()).unwrap();
+        assert_eq!(None, secrets.get("edgeHub", "a"));
+        assert!(secrets.get("other", "a").is_some());
+        secrets.remove_module("other").unwrap();
+
+        // A corrupt store is set aside rather than failing to load.
+        std::fs::write(&path, "{\"edgeHub\":").unwrap();
+        let secrets = Secrets::new(path.clone()).unwrap();
+        assert!(secrets.list().is_empty());
+        assert!(dir.join("secrets.json.corrupt").exists());
+
+        // Secrets are bound to their module and name.
+        assert_ne!(
+            Secrets::aad("edgeHub", "password"),
+            Secrets::aad("otherModule", "password")
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
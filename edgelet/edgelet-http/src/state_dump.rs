@@ -0,0 +1,74 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+type Section = Box<dyn Fn() -> serde_json::Value + Send + Sync>;
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateDumpReport {
+    pub time: chrono::DateTime<chrono::Utc>,
+
+    /// The state of each part of the daemon, by name.
+    pub sections: std::collections::BTreeMap<String, serde_json::Value>,
+}
+
+/// A snapshot of the daemon's internal state, for when it appears hung while its
+/// process is alive.
+///
+/// The parts of the daemon that have state to report add a section for it. Sections
+/// must not wait on locks that the daemon holds while it works, since the dump is most
+/// needed when that work is stuck.
+#[derive(Clone, Default)]
+pub struct StateDump {
+    sections: std::sync::Arc<std::sync::Mutex<std::collections::BTreeMap<String, Section>>>,
+}
+
+impl StateDump {
+    pub fn add_section(
+        &self,
+        name: &str,
+        section: impl Fn() -> serde_json::Value + Send + Sync + 'static,
+    ) {
+        self.sections
+            .lock()
+            .expect("state dump lock poisoned")
+            .insert(name.to_string(), Box::new(section));
+    }
+
+    pub fn collect(&self) -> StateDumpReport {
+        let sections = self.sections.lock().expect("state dump lock poisoned");
+
+        StateDumpReport {
+            time: chrono::Utc::now(),
+            sections: sections
+                .iter()
+                .map(|(name, section)| (name.clone(), section()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn collect() {
+        let dump = super::StateDump::default();
+        assert!(dump.collect().sections.is_empty());
+
+        let count = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(1));
+        let section_count = count.clone();
+        dump.add_section("tasks", move || {
+            serde_json::json!({
+                "running": section_count.load(std::sync::atomic::Ordering::Relaxed),
+            })
+        });
+
+        // Sections are collected when the dump is, not when they are added.
+        count.store(3, std::sync::atomic::Ordering::Relaxed);
+
+        let report = dump.collect();
+        assert_eq!(
+            Some(&serde_json::json!({ "running": 3 })),
+            report.sections.get("tasks")
+        );
+    }
+}
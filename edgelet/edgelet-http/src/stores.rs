@@ -0,0 +1,27 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Handles to the state that the daemon shares between the workload and management
+/// APIs and its background tasks. Clones refer to the same state.
+#[derive(Clone, Default)]
+pub struct Stores {
+    pub workload_tcp: Option<crate::WorkloadTcp>,
+    pub access_log: crate::AccessLog,
+    pub rate_limit: crate::RateLimit,
+    pub feature_flags: crate::FeatureFlags,
+    pub restarts: crate::RestartHistory,
+    pub failures: crate::FailureReport,
+    pub data_epochs: crate::DataEpochs,
+    pub identity_health: crate::IdentityHealth,
+    pub changes: crate::ChangeFeed,
+    pub alerts: crate::Alerts,
+    pub secrets: crate::Secrets,
+    pub module_certs: crate::ModuleCerts,
+    pub audit_log: crate::AuditLog,
+    pub module_health: crate::ModuleHealth,
+    pub persisted_logs: crate::PersistedLogs,
+    pub operations: crate::Operations,
+    pub diagnostics: crate::Diagnostics,
+    pub parent_health: crate::ParentHealth,
+    pub log_filter: crate::LogFilter,
+    pub state_dump: crate::StateDump,
+}
@@ -1,45 +1,61 @@
 // Copyright (c) Microsoft. All rights reserved.
 
-#[allow(clippy::module_name_repetitions)]
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
-pub enum ApiVersion {
-    V2018_06_28,
-    V2019_01_30,
-    V2019_10_22,
-    V2019_11_05,
-    V2020_07_07,
-    V2021_12_07,
-    V2022_08_03,
+/// Define `ApiVersion` with its variants in order, oldest first, so that `ALL`, parsing
+/// and formatting all come from the one list.
+macro_rules! api_versions {
+    ($($variant:ident => $name:literal,)+) => {
+        #[allow(clippy::module_name_repetitions)]
+        #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+        pub enum ApiVersion {
+            $($variant,)+
+        }
+
+        impl ApiVersion {
+            /// Every version the daemon serves, oldest first.
+            pub const ALL: &'static [ApiVersion] = &[$(ApiVersion::$variant,)+];
+        }
+
+        impl std::fmt::Display for ApiVersion {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(match self {
+                    $(ApiVersion::$variant => $name,)+
+                })
+            }
+        }
+
+        impl std::str::FromStr for ApiVersion {
+            type Err = ();
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($name => Ok(ApiVersion::$variant),)+
+                    _ => Err(()),
+                }
+            }
+        }
+    };
 }
 
-impl std::fmt::Display for ApiVersion {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(match self {
-            ApiVersion::V2018_06_28 => "2018-06-28",
-            ApiVersion::V2019_01_30 => "2019-01-30",
-            ApiVersion::V2019_10_22 => "2019-10-22",
-            ApiVersion::V2019_11_05 => "2019-11-05",
-            ApiVersion::V2020_07_07 => "2020-07-07",
-            ApiVersion::V2021_12_07 => "2021-12-07",
-            ApiVersion::V2022_08_03 => "2022-08-03",
-        })
-    }
+api_versions! {
+    V2018_06_28 => "2018-06-28",
+    V2019_01_30 => "2019-01-30",
+    V2019_10_22 => "2019-10-22",
+    V2019_11_05 => "2019-11-05",
+    V2020_07_07 => "2020-07-07",
+    V2021_12_07 => "2021-12-07",
+    V2022_08_03 => "2022-08-03",
 }
 
-impl std::str::FromStr for ApiVersion {
-    type Err = ();
+impl ApiVersion {
+    /// Versions before this one still work, but callers are told to move off them.
+    const DEPRECATED_BEFORE: ApiVersion = ApiVersion::V2019_01_30;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "2018-06-28" => Ok(ApiVersion::V2018_06_28),
-            "2019-01-30" => Ok(ApiVersion::V2019_01_30),
-            "2019-10-22" => Ok(ApiVersion::V2019_10_22),
-            "2019-11-05" => Ok(ApiVersion::V2019_11_05),
-            "2020-07-07" => Ok(ApiVersion::V2020_07_07),
-            "2021-12-07" => Ok(ApiVersion::V2021_12_07),
-            "2022-08-03" => Ok(ApiVersion::V2022_08_03),
-            _ => Err(()),
-        }
+    pub fn latest() -> Self {
+        *Self::ALL.last().expect("there is at least one API version")
+    }
+
+    pub fn is_deprecated(self) -> bool {
+        self < Self::DEPRECATED_BEFORE
     }
 }
 
@@ -82,4 +98,20 @@ mod tests {
 
         assert!(ApiVersion::from_str("1900-01-01").is_err());
     }
+
+    #[test]
+    fn all_api_versions() {
+        // Every version round-trips, and the list is sorted.
+        for version in ApiVersion::ALL {
+            assert_eq!(
+                *version,
+                ApiVersion::from_str(&version.to_string()).unwrap()
+            );
+        }
+        assert!(ApiVersion::ALL.windows(2).all(|pair| pair[0] < pair[1]));
+
+        assert_eq!(ApiVersion::V2022_08_03, ApiVersion::latest());
+        assert!(ApiVersion::V2018_06_28.is_deprecated());
+        assert!(!ApiVersion::latest().is_deprecated());
+    }
 }
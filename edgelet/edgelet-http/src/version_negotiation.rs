@@ -0,0 +1,158 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use crate::ApiVersion;
+
+/// Lists the versions the daemon serves on every response.
+const SUPPORTED_VERSIONS: &str = "api-supported-versions";
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UnsupportedVersion {
+    message: String,
+    supported_versions: Vec<String>,
+}
+
+fn supported_versions() -> hyper::header::HeaderValue {
+    let versions: Vec<String> = ApiVersion::ALL.iter().map(ToString::to_string).collect();
+
+    hyper::header::HeaderValue::from_str(&versions.join(", "))
+        .expect("API versions are valid header values")
+}
+
+/// Check the request's `api-version`, and build the error response if it isn't one
+/// the daemon serves.
+fn negotiate(query: Option<&str>) -> Result<ApiVersion, hyper::Response<hyper::Body>> {
+    let query: Vec<_> = url::form_urlencoded::parse(query.unwrap_or_default().as_bytes()).collect();
+
+    let message = match crate::find_query("api-version", &query) {
+        Some(version) => match std::str::FromStr::from_str(&version) {
+            Ok(version) => return Ok(version),
+            Err(()) => format!("api-version {version} is not supported"),
+        },
+        None => "api-version is required".to_string(),
+    };
+
+    let body = UnsupportedVersion {
+        message,
+        supported_versions: ApiVersion::ALL.iter().map(ToString::to_string).collect(),
+    };
+    let body = serde_json::to_string(&body).expect("cannot fail to serialize error body");
+
+    Err(hyper::Response::builder()
+        .status(hyper::StatusCode::BAD_REQUEST)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .header(SUPPORTED_VERSIONS, supported_versions())
+        .body(body.into())
+        .expect("cannot fail to build hyper response"))
+}
+
+/// Negotiates the API version of requests before they are routed.
+///
+/// Requests with a missing or unknown `api-version` are refused with the list of
+/// supported versions, so that clients which drifted from the daemon can tell why.
+/// Responses to deprecated versions carry `Deprecation` and `Warning` headers.
+///
+/// Routes declare the versions they serve, and the router only considers routes whose
+/// versions include the request's. A new version of an endpoint is added as a separate
+/// route for the same path, with the old route's versions capped below it.
+#[derive(Clone)]
+pub struct ApiVersionService<S> {
+    inner: S,
+}
+
+impl<S> ApiVersionService<S> {
+    pub fn new(inner: S) -> Self {
+        ApiVersionService { inner }
+    }
+}
+
+impl<S> hyper::service::Service<hyper::Request<hyper::Body>> for ApiVersionService<S>
+where
+    S: hyper::service::Service<
+        hyper::Request<hyper::Body>,
+        Response = hyper::Response<hyper::Body>,
+        Error = std::convert::Infallible,
+    >,
+    S::Future: Send + 'static,
+{
+    type Response = hyper::Response<hyper::Body>;
+    type Error = std::convert::Infallible;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: hyper::Request<hyper::Body>) -> Self::Future {
+        let version = match negotiate(req.uri().query()) {
+            Ok(version) => version,
+            Err(response) => return Box::pin(async move { Ok(response) }),
+        };
+
+        let response = self.inner.call(req);
+
+        Box::pin(async move {
+            let mut response = response.await?;
+
+            let headers = response.headers_mut();
+            headers.insert(SUPPORTED_VERSIONS, supported_versions());
+
+            if version.is_deprecated() {
+                let warning = format!(
+                    "299 - \"api-version {} is deprecated, use {}\"",
+                    version,
+                    ApiVersion::latest()
+                );
+
+                headers.insert(
+                    "deprecation",
+                    hyper::header::HeaderValue::from_static("true"),
+                );
+                headers.insert(
+                    hyper::header::WARNING,
+                    hyper::header::HeaderValue::from_str(&warning)
+                        .expect("warning is a valid header value"),
+                );
+            }
+
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ApiVersion;
+
+    use super::negotiate;
+
+    #[tokio::test]
+    async fn negotiation() {
+        assert_eq!(
+            ApiVersion::V2022_08_03,
+            negotiate(Some("api-version=2022-08-03")).unwrap()
+        );
+        assert_eq!(
+            ApiVersion::V2018_06_28,
+            negotiate(Some("follow=true&api-version=2018-06-28")).unwrap()
+        );
+
+        for query in [None, Some("follow=true"), Some("api-version=2099-01-01")] {
+            let response = negotiate(query).unwrap_err();
+            assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status());
+            assert_eq!(
+                "2018-06-28, 2019-01-30, 2019-10-22, 2019-11-05, 2020-07-07, 2021-12-07, 2022-08-03",
+                response.headers()[super::SUPPORTED_VERSIONS]
+            );
+
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(7, body["supportedVersions"].as_array().unwrap().len());
+        }
+    }
+}
@@ -0,0 +1,583 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::Write;
+
+use edgelet_settings::RuntimeSettings;
+
+/// Environment variable containing the URI of the TCP workload listener.
+pub const WORKLOAD_TCP_URI_ENV: &str = "IOTEDGE_WORKLOADTCPURI";
+
+/// Environment variable containing the module's bearer token for the TCP workload listener.
+pub const WORKLOAD_TOKEN_ENV: &str = "IOTEDGE_WORKLOADTOKEN";
+
+const TOKEN_KEY_FILE: &str = "workload_token.key";
+const TOKEN_KEY_LEN: usize = 32;
+const TOKEN_NONCES_FILE: &str = "workload_tokens.json";
+const TOKEN_NONCE_LEN: usize = 16;
+
+/// Bearer tokens for modules that call the workload API over TCP.
+///
+/// Modules on the Unix socket listeners are identified by their PID. That isn't
+/// available over TCP, so each module is instead given a token of the form
+/// `<module_id>:<nonce>:<hmac>`, where the HMAC is computed over the module ID and
+/// nonce with a key that never leaves the device.
+///
+/// A module's nonce is replaced whenever a token is minted for it, i.e. whenever the
+/// module is created or updated, and removed with the module. Only the token of the
+/// module's current container is then accepted.
+#[derive(Clone)]
+pub struct WorkloadTcp {
+    key: std::sync::Arc<[u8]>,
+    listen_uri: url::Url,
+    connect_uri: Option<url::Url>,
+    nonces: Nonces,
+}
+
+/// The current token nonce of each module, saved in the state directory so that tokens
+/// injected into existing containers remain valid across daemon restarts.
+#[derive(Clone)]
+struct Nonces {
+    path: std::path::PathBuf,
+    nonces: std::sync::Arc<std::sync::Mutex<std::collections::BTreeMap<String, String>>>,
+}
+
+impl WorkloadTcp {
+    /// Returns `None` if the TCP workload listener is not enabled in settings.
+    ///
+    /// The token key is persisted in the home directory so that tokens injected into
+    /// existing containers remain valid across daemon restarts.
+    pub fn new(settings: &impl RuntimeSettings) -> std::io::Result<Option<Self>> {
+        Self::with_key(settings, load_or_create_key)
+    }
+
+    /// Like `new`, but only reads the token key that `new` created, so that it can't
+    /// replace the key that existing tokens were minted with.
+    pub fn load(settings: &impl RuntimeSettings) -> std::io::Result<Option<Self>> {
+        Self::with_key(settings, read_key)
+    }
+
+    fn with_key(
+        settings: &impl RuntimeSettings,
+        key: impl FnOnce(&std::path::Path) -> std::io::Result<Vec<u8>>,
+    ) -> std::io::Result<Option<Self>> {
+        let listen_uri = match settings.listen().workload_tcp_uri() {
+            Some(uri) => uri.clone(),
+            None => return Ok(None),
+        };
+
+        // Callers on TCP are only authenticated by their tokens, so the listener must
+        // not be reachable from off the device.
+        if !is_loopback(&listen_uri) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("workload TCP listener {listen_uri} is not a loopback address"),
+            ));
+        }
+
+        let connect_uri = settings.connect().workload_tcp_uri().cloned();
+
+        let state_dir = settings.storage().state_dir(settings.homedir());
+        let key = key(&state_dir.join(TOKEN_KEY_FILE))?;
+        let nonces = Nonces::load(state_dir.join(TOKEN_NONCES_FILE))?;
+
+        Ok(Some(WorkloadTcp {
+            key: key.into(),
+            listen_uri,
+            connect_uri,
+            nonces,
+        }))
+    }
+
+    pub fn listen_uri(&self) -> &url::Url {
+        &self.listen_uri
+    }
+
+    /// The URI that a module reaches the listener at. The loopback listen URI is only
+    /// reachable from the host's network namespace, so modules on other networks can
+    /// only reach the listener at a configured connect URI.
+    pub fn connect_uri(&self, host_network: bool) -> Option<&url::Url> {
+        self.connect_uri
+            .as_ref()
+            .or_else(|| host_network.then_some(&self.listen_uri))
+    }
+
+    /// Generate a new bearer token for a module. The module's previous token is no
+    /// longer valid.
+    pub fn mint(&self, module_id: &str) -> String {
+        let module_id = module_id.trim_start_matches('$');
+
+        let mut nonce = [0; TOKEN_NONCE_LEN];
+        openssl::rand::rand_bytes(&mut nonce).expect("random bytes should be available");
+        let nonce = hex(&nonce);
+
+        self.nonces.set(module_id, Some(&nonce));
+
+        self.token(module_id, &nonce)
+    }
+
+    /// Invalidate a removed module's token.
+    pub fn revoke(&self, module_id: &str) {
+        self.nonces.set(module_id.trim_start_matches('$'), None);
+    }
+
+    /// Validate a bearer token, returning the module ID it was minted for.
+    pub fn validate(&self, token: &str) -> Option<String> {
+        let mut parts = token.rsplitn(3, ':');
+        let (_, nonce, module_id) = (parts.next()?, parts.next()?, parts.next()?);
+
+        let expected = self.token(module_id, nonce);
+
+        if expected.len() == token.len()
+            && openssl::memcmp::eq(expected.as_bytes(), token.as_bytes())
+            && self.nonces.is_current(module_id, nonce)
+        {
+            Some(module_id.to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Add the TCP workload URI and the module's token to a module's environment, if the
+    /// module can reach the listener.
+    pub fn set_module_env(
+        &self,
+        module_id: &str,
+        host_network: bool,
+        env: &mut std::collections::BTreeMap<String, String>,
+    ) {
+        if let Some(connect_uri) = self.connect_uri(host_network) {
+            env.insert(WORKLOAD_TCP_URI_ENV.to_string(), connect_uri.to_string());
+            env.insert(WORKLOAD_TOKEN_ENV.to_string(), self.mint(module_id));
+        }
+    }
+
+    fn token(&self, module_id: &str, nonce: &str) -> String {
+        let mac = hex(&self.mac(&format!("{module_id}:{nonce}")));
+
+        format!("{module_id}:{nonce}:{mac}")
+    }
+
+    fn mac(&self, data: &str) -> Vec<u8> {
+        let key = openssl::pkey::PKey::hmac(&self.key).expect("HMAC key should be valid");
+        let mut signer = openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), &key)
+            .expect("HMAC signer should be valid");
+
+        signer
+            .update(data.as_bytes())
+            .expect("HMAC update should not fail");
+
+        signer.sign_to_vec().expect("HMAC sign should not fail")
+    }
+}
+
+impl Nonces {
+    fn load(path: std::path::PathBuf) -> std::io::Result<Self> {
+        let nonces = crate::persist::read_json(&path, "workload token nonces")?.unwrap_or_default();
+
+        Ok(Nonces {
+            path,
+            nonces: std::sync::Arc::new(std::sync::Mutex::new(nonces)),
+        })
+    }
+
+    /// The saved nonces are read again first, so that nonces that another instance
+    /// minted aren't overwritten.
+    fn set(&self, module_id: &str, nonce: Option<&str>) {
+        let mut nonces = self.lock();
+        self.reload(&mut nonces);
+
+        let changed = if let Some(nonce) = nonce {
+            nonces.insert(module_id.to_string(), nonce.to_string());
+
+            true
+        } else {
+            nonces.remove(module_id).is_some()
+        };
+
+        if changed {
+            if let Err(err) = crate::persist::write_json(&self.path, &*nonces) {
+                log::warn!("Failed to save workload token nonces: {}", err);
+            }
+        }
+    }
+
+    /// Tokens may be minted by another instance, e.g. the watchdog's for Edge Agent, so
+    /// the saved nonces are read again before a token is rejected.
+    fn is_current(&self, module_id: &str, nonce: &str) -> bool {
+        let mut nonces = self.lock();
+
+        if nonces.get(module_id).map(String::as_str) == Some(nonce) {
+            return true;
+        }
+
+        self.reload(&mut nonces);
+
+        nonces.get(module_id).map(String::as_str) == Some(nonce)
+    }
+
+    fn reload(&self, nonces: &mut std::collections::BTreeMap<String, String>) {
+        match crate::persist::read_json(&self.path, "workload token nonces") {
+            Ok(Some(saved)) => *nonces = saved,
+            Ok(None) => {}
+            Err(err) => log::warn!("Failed to read workload token nonces: {}", err),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, std::collections::BTreeMap<String, String>> {
+        self.nonces
+            .lock()
+            .expect("workload token nonces lock poisoned")
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Whether a URI's host is a loopback address.
+pub fn is_loopback(uri: &url::Url) -> bool {
+    match uri.host() {
+        Some(url::Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+        Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    }
+}
+
+fn read_key(path: &std::path::Path) -> std::io::Result<Vec<u8>> {
+    let key = std::fs::read(path)?;
+
+    if key.len() == TOKEN_KEY_LEN {
+        Ok(key)
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("workload token key {} is invalid", path.display()),
+        ))
+    }
+}
+
+fn load_or_create_key(path: &std::path::Path) -> std::io::Result<Vec<u8>> {
+    match std::fs::read(path) {
+        Ok(key) if key.len() == TOKEN_KEY_LEN => return Ok(key),
+        Ok(_) => log::warn!(
+            "Workload token key {} is invalid and will be regenerated",
+            path.display()
+        ),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+
+    let mut key = vec![0; TOKEN_KEY_LEN];
+    openssl::rand::rand_bytes(&mut key)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+
+    let mut file = {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?
+    };
+    file.write_all(&key)?;
+
+    Ok(key)
+}
+
+/// Wraps the workload API service for the TCP listener.
+///
+/// Requests must carry a valid bearer token. They are rate limited by the token's
+/// module, as on the module's own socket. The workload routes authorize callers by
+/// PID, so authenticated requests are attributed to one of the token owner's processes
+/// before they reach the inner service.
+#[derive(Clone)]
+pub struct TokenAuth<S, M> {
+    inner: S,
+    workload_tcp: WorkloadTcp,
+    rate_limit: crate::RateLimit,
+    runtime: M,
+}
+
+impl<S, M> TokenAuth<S, M> {
+    pub fn new(
+        inner: S,
+        workload_tcp: WorkloadTcp,
+        rate_limit: crate::RateLimit,
+        runtime: M,
+    ) -> Self {
+        TokenAuth {
+            inner,
+            workload_tcp,
+            rate_limit,
+            runtime,
+        }
+    }
+}
+
+impl<S, M> hyper::service::Service<hyper::Request<hyper::Body>> for TokenAuth<S, M>
+where
+    S: hyper::service::Service<
+            hyper::Request<hyper::Body>,
+            Response = hyper::Response<hyper::Body>,
+            Error = std::convert::Infallible,
+        > + Clone
+        + Send
+        + 'static,
+    S::Future: Send,
+    M: edgelet_core::ModuleRuntime + Clone + Send + Sync + 'static,
+{
+    type Response = hyper::Response<hyper::Body>;
+    type Error = std::convert::Infallible;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: hyper::Request<hyper::Body>) -> Self::Future {
+        // Keep the service that was polled ready for this request.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let workload_tcp = self.workload_tcp.clone();
+        let rate_limit = self.rate_limit.clone();
+        let runtime = self.runtime.clone();
+
+        Box::pin(async move {
+            let module_id = req
+                .headers()
+                .get(hyper::header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .and_then(|token| workload_tcp.validate(token));
+
+            let module_id = if let Some(module_id) = module_id {
+                module_id
+            } else {
+                log::warn!(
+                    "Rejected workload request {} {} on TCP listener: missing or invalid token",
+                    req.method(),
+                    req.uri().path()
+                );
+
+                return Ok(error_response(
+                    hyper::StatusCode::UNAUTHORIZED,
+                    "missing or invalid workload token",
+                ));
+            };
+
+            if let Some(response) = rate_limit.check(&module_id, &req) {
+                log::warn!(
+                    "Rejected workload request {} {} from {} on TCP listener: rate limited",
+                    req.method(),
+                    req.uri().path(),
+                    module_id
+                );
+
+                return Ok(response);
+            }
+
+            let pid = match runtime.module_top(&module_id).await {
+                Ok(pids) => pids.first().copied(),
+                Err(err) => {
+                    log::info!("Auth for {} failed: {}", module_id, err);
+
+                    None
+                }
+            };
+
+            if let Some(pid) = pid {
+                req.extensions_mut().insert(Some(pid));
+
+                inner.call(req).await
+            } else {
+                log::warn!(
+                    "Rejected workload request {} {} from {} on TCP listener: module isn't running",
+                    req.method(),
+                    req.uri().path(),
+                    module_id
+                );
+
+                Ok(error_response(hyper::StatusCode::FORBIDDEN, "forbidden"))
+            }
+        })
+    }
+}
+
+fn error_response(status: hyper::StatusCode, message: &str) -> hyper::Response<hyper::Body> {
+    let body = serde_json::json!({ "message": message }).to_string();
+
+    hyper::Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(body.into())
+        .expect("cannot fail to build hyper response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WorkloadTcp;
+
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("workload-tcp-{}-test-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn test_workload_tcp(dir: &std::path::Path) -> WorkloadTcp {
+        WorkloadTcp {
+            key: vec![7; super::TOKEN_KEY_LEN].into(),
+            listen_uri: url::Url::parse("http://127.0.0.1:15581").unwrap(),
+            connect_uri: None,
+            nonces: super::Nonces::load(dir.join(super::TOKEN_NONCES_FILE)).unwrap(),
+        }
+    }
+
+    #[test]
+    fn mint_and_validate() {
+        let dir = test_dir("mint-and-validate");
+        let workload_tcp = test_workload_tcp(&dir);
+
+        let token = workload_tcp.mint("$edgeAgent");
+        assert!(token.starts_with("edgeAgent:"));
+        assert_eq!(Some("edgeAgent".to_string()), workload_tcp.validate(&token));
+
+        let token = workload_tcp.mint("testModule");
+        assert_eq!(
+            Some("testModule".to_string()),
+            workload_tcp.validate(&token)
+        );
+
+        // Another instance, as after a restart, accepts the saved tokens.
+        let restarted = test_workload_tcp(&dir);
+        assert_eq!(Some("testModule".to_string()), restarted.validate(&token));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rotate_and_revoke() {
+        let dir = test_dir("rotate-and-revoke");
+        let workload_tcp = test_workload_tcp(&dir);
+
+        // Recreating a module invalidates the token of its previous container.
+        let old = workload_tcp.mint("testModule");
+        let new = workload_tcp.mint("testModule");
+        assert_eq!(None, workload_tcp.validate(&old));
+        assert_eq!(Some("testModule".to_string()), workload_tcp.validate(&new));
+
+        // A token minted by another instance is accepted, and doesn't replace the
+        // tokens of other modules.
+        let other = test_workload_tcp(&dir);
+        let agent = other.mint("$edgeAgent");
+        assert_eq!(Some("edgeAgent".to_string()), workload_tcp.validate(&agent));
+        assert_eq!(Some("testModule".to_string()), other.validate(&new));
+
+        workload_tcp.revoke("testModule");
+        assert_eq!(None, workload_tcp.validate(&new));
+        assert_eq!(None, other.validate(&new));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reject_forged_token() {
+        let dir = test_dir("reject-forged-token");
+        let workload_tcp = test_workload_tcp(&dir);
+
+        // Token minted for a different module.
+        let token = workload_tcp.mint("testModule");
+        workload_tcp.mint("edgeAgent");
+        let (_, nonce_and_mac) = token.split_once(':').unwrap();
+        let forged = format!("edgeAgent:{nonce_and_mac}");
+        assert_eq!(None, workload_tcp.validate(&forged));
+
+        // Token minted with a different key.
+        let other = WorkloadTcp {
+            key: vec![8; super::TOKEN_KEY_LEN].into(),
+            ..workload_tcp.clone()
+        };
+        assert_eq!(None, workload_tcp.validate(&other.mint("testModule")));
+
+        // Malformed tokens.
+        assert_eq!(None, workload_tcp.validate("testModule"));
+        assert_eq!(None, workload_tcp.validate(""));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn module_env() {
+        let dir = test_dir("module-env");
+        let workload_tcp = test_workload_tcp(&dir);
+        let mut env = std::collections::BTreeMap::new();
+
+        workload_tcp.set_module_env("testModule", true, &mut env);
+
+        assert_eq!(
+            "http://127.0.0.1:15581/",
+            env[super::WORKLOAD_TCP_URI_ENV].as_str()
+        );
+        assert_eq!(
+            Some("testModule".to_string()),
+            workload_tcp.validate(&env[super::WORKLOAD_TOKEN_ENV])
+        );
+
+        // Modules on bridge networks can't reach the loopback listener.
+        let mut env = std::collections::BTreeMap::new();
+        workload_tcp.set_module_env("testModule", false, &mut env);
+        assert!(env.is_empty());
+
+        let workload_tcp = WorkloadTcp {
+            connect_uri: Some(url::Url::parse("http://172.17.0.1:15581").unwrap()),
+            ..workload_tcp
+        };
+        workload_tcp.set_module_env("testModule", false, &mut env);
+        assert_eq!(
+            "http://172.17.0.1:15581/",
+            env[super::WORKLOAD_TCP_URI_ENV].as_str()
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn loopback() {
+        let is_loopback = |uri: &str| super::is_loopback(&url::Url::parse(uri).unwrap());
+
+        assert!(is_loopback("http://127.0.0.1:15580"));
+        assert!(is_loopback("http://[::1]:15580"));
+        assert!(is_loopback("http://localhost:15580"));
+        assert!(!is_loopback("http://0.0.0.0:15580"));
+        assert!(!is_loopback("http://192.168.0.1:15580"));
+        assert!(!is_loopback("http://example.com:15580"));
+    }
+
+    #[test]
+    fn load_key() {
+        let dir = std::env::temp_dir().join(format!("workload-tcp-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(super::TOKEN_KEY_FILE);
+
+        // Only creating the key may generate it.
+        super::read_key(&path).unwrap_err();
+        let key = super::load_or_create_key(&path).unwrap();
+        assert_eq!(key, super::read_key(&path).unwrap());
+
+        std::fs::write(&path, b"short").unwrap();
+        super::read_key(&path).unwrap_err();
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -25,6 +25,8 @@ pub async fn image_garbage_collect(
     settings: ImagePruneSettings,
     runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
     image_use_data: ImagePruneData,
+    audit_log: edgelet_http::AuditLog,
+    operations: edgelet_http::Operations,
 ) -> Result<(), ImageCleanupError> {
     log::info!("Starting image garbage collection task...");
 
@@ -34,8 +36,26 @@ pub async fn image_garbage_collect(
 
     let cleanup_time_in_mins = &mut settings.cleanup_time();
 
-    let diff_in_secs: u64 = get_sleep_time_mins(*cleanup_time_in_mins) * 60;
-    tokio::time::sleep(Duration::from_secs(diff_in_secs)).await;
+    if settings.is_frequent() {
+        log::warn!(
+            "Image garbage collection runs every {} minute(s); cleanup_time is ignored",
+            settings.cleanup_recurrence().as_secs() / 60
+        );
+    }
+
+    // A run missed while the device was off is made up right away. Runs more frequent
+    // than daily aren't aligned to the cleanup time; they start one recurrence after the
+    // daemon does.
+    if settings.run_on_startup_if_missed()
+        && missed_run(&image_use_data, settings.cleanup_recurrence())
+    {
+        log::info!("Last scheduled image garbage collection was missed; running now");
+    } else if settings.is_frequent() {
+        tokio::time::sleep(settings.cleanup_recurrence()).await;
+    } else {
+        let diff_in_secs: u64 = get_sleep_time_mins(*cleanup_time_in_mins) * 60;
+        tokio::time::sleep(Duration::from_secs(diff_in_secs)).await;
+    }
 
     let mut bootstrap_image_id_option = None;
     let mut is_bootstrap_image_deleted: bool = false;
@@ -59,20 +79,43 @@ pub async fn image_garbage_collect(
         if bootstrap_image_id_option.is_some()
             || (bootstrap_image_id_option.is_none() && is_bootstrap_image_deleted)
         {
-            remove_unused_images(
+            let removed = match remove_unused_images(
                 runtime,
                 image_use_data.clone(),
                 bootstrap_image_id_option.clone(),
             )
-            .await?;
+            .await
+            {
+                Ok(removed) => removed,
+                Err(err) => {
+                    operations.image_gc(0, Some(err.to_string()));
+
+                    return Err(err);
+                }
+            };
+
+            operations.image_gc(removed, None);
+
+            if let Err(err) = image_use_data.record_run() {
+                log::warn!("Could not record image garbage collection run: {}", err);
+            }
+
+            audit_log.record(
+                edgelet_http::AuditEvent::new(edgelet_http::AuditEventType::ImageGarbageCollection)
+                    .with_details(format!("removed {removed} image(s)")),
+            );
         }
 
         // sleep till it's time to wake up based on recurrence (and on current time post-last-execution to avoid time drift)
         let recurrence = settings.cleanup_recurrence();
-        let delay = recurrence
-            - Duration::from_secs(
-                (TOTAL_MINS_IN_DAY - get_sleep_time_mins(*cleanup_time_in_mins)) * 60,
-            );
+        let delay = if settings.is_frequent() {
+            recurrence
+        } else {
+            recurrence
+                - Duration::from_secs(
+                    (TOTAL_MINS_IN_DAY - get_sleep_time_mins(*cleanup_time_in_mins)) * 60,
+                )
+        };
         tokio::time::sleep(delay).await;
     }
 }
@@ -81,7 +124,7 @@ async fn remove_unused_images(
     runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
     image_use_data: ImagePruneData,
     bootstrap_image_id_option: Option<String>,
-) -> Result<(), ImageCleanupError> {
+) -> Result<usize, ImageCleanupError> {
     log::info!("Image Garbage Collection starting scheduled run");
 
     let bootstrap_img_id = match bootstrap_image_id_option.clone() {
@@ -113,13 +156,16 @@ async fn remove_unused_images(
         .map_err(ImageCleanupError::PruneImages)?;
 
     // delete images
+    let mut removed = 0;
     for key in image_map.keys() {
         if let Err(e) = ModuleRegistry::remove(runtime, key).await {
             log::error!("Could not delete image {} : {}", key, e);
+        } else {
+            removed += 1;
         }
     }
 
-    Ok(())
+    Ok(removed)
 }
 
 /* ================================================ HELPER METHODS ================================================ */
@@ -164,6 +210,29 @@ async fn get_bootstrap_image_id(
     ))
 }
 
+/// Whether more than a recurrence has passed since garbage collection last completed.
+/// Devices that have never run it count as having missed a run, since they may have
+/// accumulated images before upgrading.
+fn missed_run(image_use_data: &ImagePruneData, recurrence: Duration) -> bool {
+    let last_run = match image_use_data.last_run() {
+        Ok(last_run) => last_run,
+        Err(err) => {
+            log::warn!("Could not read last image garbage collection run: {}", err);
+            return false;
+        }
+    };
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+
+    is_missed(last_run, now, recurrence)
+}
+
+fn is_missed(last_run: Option<Duration>, now: Duration, recurrence: Duration) -> bool {
+    last_run.map_or(true, |last_run| now.saturating_sub(last_run) > recurrence)
+}
+
 fn get_sleep_time_mins(cleanup_mins: u64) -> u64 {
     let current_hour = chrono::Local::now().hour();
     let current_minute = chrono::Local::now().minute();
@@ -178,7 +247,9 @@ fn get_sleep_time_mins(cleanup_mins: u64) -> u64 {
 
 #[cfg(test)]
 mod tests {
-    use super::get_sleep_time_mins;
+    use std::time::Duration;
+
+    use super::{get_sleep_time_mins, is_missed};
     use chrono::Timelike;
 
     const TOTAL_MINS_IN_DAY: u64 = 1440;
@@ -201,4 +272,17 @@ mod tests {
 
         assert!(answer == result);
     }
+
+    #[test]
+    fn test_is_missed() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let now = Duration::from_secs(1_700_000_000);
+
+        assert!(is_missed(None, now, day));
+        assert!(!is_missed(Some(now - day / 2), now, day));
+        assert!(is_missed(Some(now - day * 2), now, day));
+
+        // A clock that went backwards isn't a missed run.
+        assert!(!is_missed(Some(now + day), now, day));
+    }
 }
@@ -0,0 +1,122 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// An alert evaluated on the device, so that conditions are noticed without a round
+/// trip to the cloud.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Rule {
+    pub name: String,
+
+    pub condition: Condition,
+
+    /// How long the condition must hold before the alert fires.
+    #[serde(
+        rename = "for",
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "std::time::Duration::is_zero"
+    )]
+    pub for_duration: std::time::Duration,
+
+    /// Taken when the alert fires and again when it resolves.
+    pub actions: Vec<Action>,
+}
+
+impl Rule {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn condition(&self) -> &Condition {
+        &self.condition
+    }
+
+    pub fn for_duration(&self) -> std::time::Duration {
+        self.for_duration
+    }
+
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /// The module isn't running, or doesn't exist.
+    ModuleDown { module: String },
+
+    /// The filesystem containing `path` is fuller than `above_percent`.
+    DiskUsage {
+        path: std::path::PathBuf,
+        above_percent: u8,
+    },
+
+    /// A certificate in Identity Service's cert store expires within `within`. The
+    /// Edge CA certificate if `cert` isn't given.
+    CertExpiry {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cert: Option<String>,
+
+        #[serde(with = "humantime_serde")]
+        within: std::time::Duration,
+    },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    /// Record the alert in the management API's change feed.
+    Event,
+
+    /// Include the alert in the alerts Edge Agent reports in its reported properties.
+    TwinReport,
+
+    /// POST the alert as JSON to an HTTP endpoint on the device or local network, such
+    /// as an HMI.
+    Webhook { url: url::Url },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Condition, Rule};
+
+    #[test]
+    fn deserialize() {
+        let rules: Vec<Rule> = serde_json::from_value(serde_json::json!([
+            {
+                "name": "edgehub-down",
+                "condition": { "type": "module_down", "module": "edgeHub" },
+                "for": "5m",
+                "actions": [
+                    { "type": "event" },
+                    { "type": "webhook", "url": "http://127.0.0.1:8080/alerts" },
+                ],
+            },
+            {
+                "name": "ca-expiring",
+                "condition": { "type": "cert_expiry", "within": "7d" },
+                "actions": [{ "type": "twin_report" }],
+            },
+        ]))
+        .unwrap();
+
+        assert_eq!(std::time::Duration::from_secs(300), rules[0].for_duration());
+        assert_eq!(
+            &Condition::ModuleDown {
+                module: "edgeHub".to_string()
+            },
+            rules[0].condition()
+        );
+        assert_eq!(Action::Event, rules[0].actions()[0]);
+
+        // Fires as soon as the condition holds, for the Edge CA certificate.
+        assert!(rules[1].for_duration().is_zero());
+        assert_eq!(
+            &Condition::CertExpiry {
+                cert: None,
+                within: std::time::Duration::from_secs(7 * 24 * 60 * 60)
+            },
+            rules[1].condition()
+        );
+    }
+}
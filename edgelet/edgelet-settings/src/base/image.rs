@@ -6,26 +6,47 @@ use serde::{Deserialize, Serialize};
 /// This struct is a wrapper for options that allow a user to override the defaults of
 /// the image gabage collection job and customize their settings.
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(try_from = "UncheckedImagePruneSettings")]
 pub struct ImagePruneSettings {
-    #[serde(
-        default = "default_cleanup_recurrence",
-        deserialize_with = "validate_recurrence",
-        serialize_with = "humantime_serde::serialize"
-    )]
+    #[serde(serialize_with = "humantime_serde::serialize")]
     /// how frequently images should be garbage collected
     cleanup_recurrence: Duration,
+    #[serde(serialize_with = "humantime_serde::serialize")]
+    /// minimum (unused) image "age" to be eligible for garbage collection
+    image_age_cleanup_threshold: Duration,
+    /// time in "HH::MM" format when cleanup job runs
+    #[serde(serialize_with = "hhmm_as_minutes::serialize")]
+    cleanup_time: u64,
+    // is image garbage collection enabled
+    enabled: bool,
+    /// allow a cleanup_recurrence of less than a day, for devices that churn images
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    allow_frequent_cleanup: bool,
+    /// run at startup if more than cleanup_recurrence has passed since the last run,
+    /// for devices that are off at cleanup_time
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    run_on_startup_if_missed: bool,
+}
+
+/// Image garbage collection settings as written, before the recurrence is checked
+/// against `allow_frequent_cleanup`.
+#[derive(Deserialize)]
+struct UncheckedImagePruneSettings {
+    #[serde(default = "default_cleanup_recurrence", with = "humantime_serde")]
+    cleanup_recurrence: Duration,
     #[serde(
         default = "default_image_age_cleanup_threshold",
         with = "humantime_serde"
     )]
-    /// minimum (unused) image "age" to be eligible for garbage collection
     image_age_cleanup_threshold: Duration,
-    /// time in "HH::MM" format when cleanup job runs
     #[serde(default, with = "hhmm_as_minutes")]
     cleanup_time: u64,
-    // is image garbage collection enabled
     #[serde(default = "default_enabled")]
     enabled: bool,
+    #[serde(default)]
+    allow_frequent_cleanup: bool,
+    #[serde(default)]
+    run_on_startup_if_missed: bool,
 }
 
 impl ImagePruneSettings {
@@ -40,6 +61,8 @@ impl ImagePruneSettings {
             image_age_cleanup_threshold,
             cleanup_time,
             enabled,
+            allow_frequent_cleanup: false,
+            run_on_startup_if_missed: false,
         }
     }
 
@@ -59,14 +82,29 @@ impl ImagePruneSettings {
         self.enabled
     }
 
+    /// Whether garbage collection runs more than once a day. Frequent runs are scheduled
+    /// by `cleanup_recurrence` alone, so `cleanup_time` doesn't apply to them.
+    pub fn is_frequent(&self) -> bool {
+        self.cleanup_recurrence < ONE_DAY
+    }
+
+    pub fn run_on_startup_if_missed(&self) -> bool {
+        self.run_on_startup_if_missed
+    }
+
     pub fn is_default(value: &Self) -> bool {
         value == &Self::default()
     }
 }
 
-// 1 day
+const ONE_DAY: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// Shortest recurrence allowed with `allow_frequent_cleanup`. Each run lists every image
+/// and container, so shorter periods would keep the container engine busy.
+const MIN_FREQUENT_CLEANUP_RECURRENCE: Duration = Duration::from_secs(10 * 60);
+
 fn default_cleanup_recurrence() -> Duration {
-    Duration::from_secs(60 * 60 * 24)
+    ONE_DAY
 }
 
 // 7 days
@@ -78,21 +116,39 @@ fn default_enabled() -> bool {
     true
 }
 
-fn validate_recurrence<'de, D>(de: D) -> Result<Duration, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    const MIN_CLEANUP_RECURRENCE: u128 = 60 * 60 * 24 * 1_000_000_000; // 1 day in nanoseconds
-    let recurrence: Duration = humantime_serde::deserialize(de)?;
-
-    if (recurrence.as_nanos() % MIN_CLEANUP_RECURRENCE) != 0 {
-        return Err(<D::Error as serde::de::Error>::invalid_value(
-            serde::de::Unexpected::Other(&format!("{recurrence:?}")),
-            &"duration that is a multiple of days",
-        ));
-    }
+impl TryFrom<UncheckedImagePruneSettings> for ImagePruneSettings {
+    type Error = String;
+
+    fn try_from(settings: UncheckedImagePruneSettings) -> Result<Self, Self::Error> {
+        let recurrence = settings.cleanup_recurrence;
+
+        if recurrence < ONE_DAY {
+            if !settings.allow_frequent_cleanup {
+                return Err(format!(
+                    "invalid cleanup_recurrence {recurrence:?}: must be at least 1 day unless allow_frequent_cleanup is set"
+                ));
+            }
+
+            if recurrence < MIN_FREQUENT_CLEANUP_RECURRENCE {
+                return Err(format!(
+                    "invalid cleanup_recurrence {recurrence:?}: must be at least {MIN_FREQUENT_CLEANUP_RECURRENCE:?}"
+                ));
+            }
+        } else if recurrence.as_nanos() % ONE_DAY.as_nanos() != 0 {
+            return Err(format!(
+                "invalid cleanup_recurrence {recurrence:?}: must be a multiple of days"
+            ));
+        }
 
-    Ok(recurrence)
+        Ok(ImagePruneSettings {
+            cleanup_recurrence: recurrence,
+            image_age_cleanup_threshold: settings.image_age_cleanup_threshold,
+            cleanup_time: settings.cleanup_time,
+            enabled: settings.enabled,
+            allow_frequent_cleanup: settings.allow_frequent_cleanup,
+            run_on_startup_if_missed: settings.run_on_startup_if_missed,
+        })
+    }
 }
 
 mod hhmm_as_minutes {
@@ -135,6 +191,59 @@ impl Default for ImagePruneSettings {
             image_age_cleanup_threshold: default_image_age_cleanup_threshold(),
             cleanup_time: 0,
             enabled: default_enabled(),
+            allow_frequent_cleanup: false,
+            run_on_startup_if_missed: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::ImagePruneSettings;
+
+    #[test]
+    fn cleanup_recurrence() {
+        let settings: ImagePruneSettings =
+            serde_json::from_value(serde_json::json!({ "cleanup_recurrence": "2d" })).unwrap();
+        assert_eq!(
+            Duration::from_secs(2 * 24 * 60 * 60),
+            settings.cleanup_recurrence()
+        );
+        assert!(!settings.is_frequent());
+
+        // Recurrences over a day must be whole days.
+        serde_json::from_value::<ImagePruneSettings>(
+            serde_json::json!({ "cleanup_recurrence": "36h" }),
+        )
+        .unwrap_err();
+
+        // Recurrences under a day must be opted into.
+        serde_json::from_value::<ImagePruneSettings>(
+            serde_json::json!({ "cleanup_recurrence": "1h" }),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn frequent_cleanup() {
+        let settings: ImagePruneSettings = serde_json::from_value(serde_json::json!({
+            "cleanup_recurrence": "1h",
+            "allow_frequent_cleanup": true,
+        }))
+        .unwrap();
+        assert_eq!(Duration::from_secs(60 * 60), settings.cleanup_recurrence());
+        assert!(settings.is_frequent());
+
+        // The opt-in is kept when the settings are written back.
+        let value = serde_json::to_value(&settings).unwrap();
+        assert_eq!(serde_json::json!(true), value["allow_frequent_cleanup"]);
+
+        serde_json::from_value::<ImagePruneSettings>(serde_json::json!({
+            "cleanup_recurrence": "5m",
+            "allow_frequent_cleanup": true,
+        }))
+        .unwrap_err();
+    }
+}
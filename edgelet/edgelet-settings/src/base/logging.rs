@@ -0,0 +1,156 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Plain-text lines, as emitted by the `logger` crate.
+    #[default]
+    Text,
+
+    /// One JSON object per line.
+    Json,
+}
+
+impl LogFormat {
+    pub fn is_default(&self) -> bool {
+        self == &LogFormat::default()
+    }
+}
+
+/// Access logging for the management and workload APIs.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct AccessLogSettings {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Fraction of requests that are logged, between 0 and 1.
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f64,
+
+    #[serde(default)]
+    pub level: AccessLogLevel,
+
+    /// Overrides for requests whose path starts with the given prefix. The longest
+    /// matching prefix is used.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub endpoints: std::collections::BTreeMap<String, EndpointAccessLog>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct EndpointAccessLog {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<f64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<AccessLogLevel>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogLevel {
+    Off,
+    Debug,
+    #[default]
+    Info,
+}
+
+fn default_sample_rate() -> f64 {
+    1.0
+}
+
+impl Default for AccessLogSettings {
+    fn default() -> Self {
+        AccessLogSettings {
+            enabled: false,
+            sample_rate: default_sample_rate(),
+            level: AccessLogLevel::default(),
+            endpoints: std::collections::BTreeMap::new(),
+        }
+    }
+}
+
+impl AccessLogSettings {
+    pub fn is_default(&self) -> bool {
+        self == &AccessLogSettings::default()
+    }
+
+    /// Returns the longest configured endpoint prefix that matches a request path.
+    pub fn endpoint_prefix(&self, path: &str) -> Option<&str> {
+        self.endpoints
+            .keys()
+            .filter(|prefix| path.starts_with(prefix.as_str()))
+            .max_by_key(|prefix| prefix.len())
+            .map(String::as_str)
+    }
+
+    /// Returns the sample rate and level that apply to a request path.
+    pub fn for_path(&self, path: &str) -> (f64, AccessLogLevel) {
+        let endpoint = self
+            .endpoint_prefix(path)
+            .and_then(|prefix| self.endpoints.get(prefix));
+
+        let sample_rate = endpoint
+            .and_then(|endpoint| endpoint.sample_rate)
+            .unwrap_or(self.sample_rate)
+            .clamp(0.0, 1.0);
+        let level = endpoint
+            .and_then(|endpoint| endpoint.level)
+            .unwrap_or(self.level);
+
+        (sample_rate, level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccessLogLevel, AccessLogSettings, EndpointAccessLog};
+
+    #[test]
+    fn access_log_for_path() {
+        let mut settings = AccessLogSettings {
+            sample_rate: 0.5,
+            ..Default::default()
+        };
+        settings.endpoints.insert(
+            "/modules".to_string(),
+            EndpointAccessLog {
+                sample_rate: Some(1.0),
+                level: None,
+            },
+        );
+        settings.endpoints.insert(
+            "/modules/edgeHub".to_string(),
+            EndpointAccessLog {
+                sample_rate: None,
+                level: Some(AccessLogLevel::Off),
+            },
+        );
+
+        assert_eq!(
+            (0.5, AccessLogLevel::Info),
+            settings.for_path("/trust-bundle")
+        );
+        assert_eq!((1.0, AccessLogLevel::Info), settings.for_path("/modules"));
+        assert_eq!(
+            (0.5, AccessLogLevel::Off),
+            settings.for_path("/modules/edgeHub/genid/1/sign")
+        );
+
+        assert_eq!(
+            Some("/modules"),
+            settings.endpoint_prefix("/modules/edgeAgent")
+        );
+        assert_eq!(
+            Some("/modules/edgeHub"),
+            settings.endpoint_prefix("/modules/edgeHub/genid/1/sign")
+        );
+        assert_eq!(None, settings.endpoint_prefix("/identities"));
+
+        // Out of range sample rates are clamped.
+        settings.sample_rate = 2.0;
+        assert_eq!(
+            (1.0, AccessLogLevel::Info),
+            settings.for_path("/identities")
+        );
+    }
+}
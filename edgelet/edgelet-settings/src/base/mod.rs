@@ -1,7 +1,9 @@
 // Copyright (c) Microsoft. All rights reserved.
 
+pub mod alerts;
 pub mod aziot;
 pub mod image;
+pub mod logging;
 pub mod module;
 pub mod uri;
 pub mod watchdog;
@@ -30,6 +32,13 @@ pub trait RuntimeSettings {
     fn agent(&self) -> &module::Settings<Self::ModuleConfig>;
     fn agent_mut(&mut self) -> &mut module::Settings<Self::ModuleConfig>;
 
+    /// Images to try, in order, if Edge Agent's image can't be pulled.
+    fn agent_fallback_images(&self) -> &[String];
+
+    /// Whether Edge Agent may be started from a cached image when none of its images
+    /// can be pulled.
+    fn agent_cached_image_fallback(&self) -> bool;
+
     fn connect(&self) -> &uri::Connect;
     fn listen(&self) -> &uri::Listen;
 
@@ -40,6 +49,52 @@ pub trait RuntimeSettings {
     fn additional_info(&self) -> &std::collections::BTreeMap<String, String>;
 
     fn image_garbage_collection(&self) -> &image::ImagePruneSettings;
+
+    fn log_format(&self) -> logging::LogFormat;
+
+    fn api_access_log(&self) -> &logging::AccessLogSettings;
+
+    fn feature_flags(&self) -> &std::collections::BTreeMap<String, bool>;
+
+    fn warm_standby_modules(&self) -> &[String];
+
+    fn inject_host_time(&self) -> bool;
+
+    fn degraded_mode(&self) -> &DegradedMode<Self::ModuleConfig>;
+
+    fn site_overlay(&self) -> Option<&SiteOverlaySource>;
+
+    fn log_sink(&self) -> Option<&LogSink>;
+
+    fn log_persistence(&self) -> Option<&LogPersistence>;
+
+    fn proxy(&self) -> Option<&Proxy>;
+
+    fn device_mapping(&self) -> &DeviceMapping;
+
+    fn storage(&self) -> &Storage;
+
+    fn template_variables(&self) -> &std::collections::BTreeMap<String, String>;
+
+    fn offline_start(&self) -> bool;
+
+    fn warm_restart(&self) -> bool;
+
+    fn reprovision_check_interval(&self) -> Option<std::time::Duration>;
+
+    fn alerts(&self) -> &[alerts::Rule];
+
+    fn workload_rate_limit(&self) -> &WorkloadRateLimit;
+
+    fn workload_server_certs(&self) -> &WorkloadServerCerts;
+
+    fn module_cert_renewal(&self) -> &ModuleCertRenewal;
+
+    fn workload_sockets(&self) -> &std::collections::BTreeMap<String, WorkloadSocket>;
+
+    fn cloud_notify(&self) -> &CloudNotify;
+
+    fn tls_performance_mode(&self) -> TlsPerformanceMode;
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
@@ -87,6 +142,499 @@ impl IotedgeMaxRequests {
     }
 }
 
+/// Token bucket rate limit applied to each module's workload socket.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct WorkloadRateLimit {
+    /// Sustained requests per second allowed for each module. 0 disables rate limiting.
+    #[serde(default)]
+    pub requests_per_second: f64,
+
+    /// Number of requests a module can make in a burst above the sustained rate.
+    #[serde(default = "default_rate_limit_burst")]
+    pub burst: u32,
+}
+
+fn default_rate_limit_burst() -> u32 {
+    10
+}
+
+impl Default for WorkloadRateLimit {
+    fn default() -> WorkloadRateLimit {
+        WorkloadRateLimit {
+            requests_per_second: 0.0,
+            burst: default_rate_limit_burst(),
+        }
+    }
+}
+
+impl WorkloadRateLimit {
+    pub fn is_default(&self) -> bool {
+        self == &WorkloadRateLimit::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.requests_per_second > 0.0
+    }
+}
+
+/// Limits on the server certificates that modules request through the workload API.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct WorkloadServerCerts {
+    /// Number of subject alternative names a module may request in addition to its
+    /// common name and module ID.
+    #[serde(default = "default_server_cert_max_sans")]
+    pub max_sans: usize,
+
+    /// DNS names requested as SANs must be one of these domains or a subdomain of one.
+    /// No names may be requested if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dns_domains: Vec<String>,
+
+    /// Longest validity a module may request.
+    #[serde(default = "default_server_cert_max_validity", with = "humantime_serde")]
+    pub max_validity: std::time::Duration,
+}
+
+fn default_server_cert_max_sans() -> usize {
+    16
+}
+
+// 30 days
+fn default_server_cert_max_validity() -> std::time::Duration {
+    std::time::Duration::from_secs(30 * 24 * 60 * 60)
+}
+
+impl Default for WorkloadServerCerts {
+    fn default() -> WorkloadServerCerts {
+        WorkloadServerCerts {
+            max_sans: default_server_cert_max_sans(),
+            dns_domains: Vec::new(),
+            max_validity: default_server_cert_max_validity(),
+        }
+    }
+}
+
+impl WorkloadServerCerts {
+    pub fn is_default(&self) -> bool {
+        self == &WorkloadServerCerts::default()
+    }
+
+    /// Whether a module may request `name` as a DNS SAN.
+    pub fn allows_dns_name(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+
+        self.dns_domains.iter().any(|domain| {
+            let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+
+            !domain.is_empty() && (name == domain || name.ends_with(&format!(".{domain}")))
+        })
+    }
+}
+
+/// When modules are told to renew the certificates they were issued through the workload API.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ModuleCertRenewal {
+    /// Certificates are due for renewal this long before they expire.
+    #[serde(default = "default_module_cert_renew_before", with = "humantime_serde")]
+    pub renew_before: std::time::Duration,
+
+    /// Restart modules whose certificates are due, for modules that don't watch for renewal.
+    #[serde(default)]
+    pub restart_modules: bool,
+}
+
+// 1 day
+fn default_module_cert_renew_before() -> std::time::Duration {
+    std::time::Duration::from_secs(24 * 60 * 60)
+}
+
+impl Default for ModuleCertRenewal {
+    fn default() -> ModuleCertRenewal {
+        ModuleCertRenewal {
+            renew_before: default_module_cert_renew_before(),
+            restart_modules: false,
+        }
+    }
+}
+
+impl ModuleCertRenewal {
+    pub fn is_default(&self) -> bool {
+        self == &ModuleCertRenewal::default()
+    }
+}
+
+/// Ownership and permissions of a module's workload socket, so that modules that run as
+/// a non-root user can connect to it.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct WorkloadSocket {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gid: Option<u32>,
+
+    /// Permission bits of the socket. Defaults to 0o666.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+}
+
+impl WorkloadSocket {
+    /// The settings for a module's socket. Modules without their own settings use those
+    /// of "*", if any.
+    pub fn for_module<'a>(
+        sockets: &'a std::collections::BTreeMap<String, WorkloadSocket>,
+        module_id: &str,
+    ) -> Option<&'a WorkloadSocket> {
+        sockets.get(module_id).or_else(|| sockets.get("*"))
+    }
+}
+
+/// Policy for summarizing repeated local failures so that Edge Agent can report them
+/// to the cloud.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CloudNotify {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Minimum time between failure summaries handed to Edge Agent.
+    #[serde(default = "default_cloud_notify_interval")]
+    pub min_interval_secs: u64,
+}
+
+fn default_cloud_notify_interval() -> u64 {
+    3600
+}
+
+impl Default for CloudNotify {
+    fn default() -> CloudNotify {
+        CloudNotify {
+            enabled: false,
+            min_interval_secs: default_cloud_notify_interval(),
+        }
+    }
+}
+
+impl CloudNotify {
+    pub fn is_default(&self) -> bool {
+        self == &CloudNotify::default()
+    }
+}
+
+/// Modules that are started when the device can't be provisioned, or when Edge Agent
+/// stays unhealthy, so that there is something to troubleshoot the device with locally.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct DegradedMode<ModuleConfig> {
+    /// How long provisioning may fail, or Edge Agent may be unhealthy, before the
+    /// modules are started.
+    #[serde(default = "default_degraded_mode_after", with = "humantime_serde")]
+    pub after: std::time::Duration,
+
+    /// Names must not be used by modules in deployments, since the modules are
+    /// removed once the device is provisioned.
+    #[serde(default = "Vec::new")]
+    pub modules: Vec<module::Settings<ModuleConfig>>,
+}
+
+// 2 minutes
+fn default_degraded_mode_after() -> std::time::Duration {
+    std::time::Duration::from_secs(120)
+}
+
+impl<ModuleConfig> Default for DegradedMode<ModuleConfig> {
+    fn default() -> Self {
+        DegradedMode {
+            after: default_degraded_mode_after(),
+            modules: Vec::new(),
+        }
+    }
+}
+
+impl<ModuleConfig> DegradedMode<ModuleConfig> {
+    pub fn after(&self) -> std::time::Duration {
+        self.after
+    }
+
+    pub fn modules(&self) -> &[module::Settings<ModuleConfig>] {
+        &self.modules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+}
+
+/// Where a child device in a nested topology fetches its site overlay from.
+///
+/// The overlay is fetched from the parent over HTTPS, with the parent's certificate
+/// verified against the trust bundle.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SiteOverlaySource {
+    /// Path of the overlay on the parent, such as "/iotedge/site-overlay.json".
+    #[serde(default = "default_site_overlay_path")]
+    pub path: String,
+
+    /// HTTPS port of the parent.
+    #[serde(default = "default_site_overlay_port")]
+    pub port: u16,
+}
+
+impl SiteOverlaySource {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+fn default_site_overlay_path() -> String {
+    "/iotedge/site-overlay.json".to_string()
+}
+
+fn default_site_overlay_port() -> u16 {
+    443
+}
+
+/// Local socket that the output of modules is copied to as it is written.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LogSink {
+    /// "unix:///path/to/socket" for a Unix datagram socket, or "udp://host:port".
+    pub url: url::Url,
+
+    /// Modules whose output is copied. All modules if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<String>,
+}
+
+impl LogSink {
+    pub fn url(&self) -> &url::Url {
+        &self.url
+    }
+
+    pub fn modules(&self) -> &[String] {
+        &self.modules
+    }
+}
+
+/// Module output that the daemon keeps in rotated files in its logs directory, so that it
+/// outlives the module's containers.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LogPersistence {
+    /// Modules whose output is kept. All modules if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<String>,
+
+    /// Size at which a module's current log file is rotated.
+    #[serde(default = "default_log_max_file_size_mb")]
+    pub max_file_size_mb: u64,
+
+    /// Age at which a module's current log file is rotated, however small it is.
+    #[serde(default = "default_log_rotate_interval", with = "humantime_serde")]
+    pub rotate_interval: std::time::Duration,
+
+    /// Rotated files kept per module. Older files are deleted.
+    #[serde(default = "default_log_max_files")]
+    pub max_files: usize,
+
+    /// Age after which rotated files are deleted, however few there are.
+    #[serde(default = "default_log_max_age", with = "humantime_serde")]
+    pub max_age: std::time::Duration,
+}
+
+impl LogPersistence {
+    pub fn modules(&self) -> &[String] {
+        &self.modules
+    }
+
+    pub fn max_file_size(&self) -> u64 {
+        self.max_file_size_mb.saturating_mul(1024 * 1024)
+    }
+
+    pub fn rotate_interval(&self) -> std::time::Duration {
+        self.rotate_interval
+    }
+
+    pub fn max_files(&self) -> usize {
+        self.max_files
+    }
+
+    pub fn max_age(&self) -> std::time::Duration {
+        self.max_age
+    }
+}
+
+fn default_log_max_file_size_mb() -> u64 {
+    10
+}
+
+fn default_log_rotate_interval() -> std::time::Duration {
+    std::time::Duration::from_secs(24 * 60 * 60)
+}
+
+fn default_log_max_files() -> usize {
+    5
+}
+
+fn default_log_max_age() -> std::time::Duration {
+    std::time::Duration::from_secs(7 * 24 * 60 * 60)
+}
+
+/// Where the daemon keeps its working directories. Each is under the home directory
+/// unless configured, so that e.g. the sockets can be put on tmpfs and the cache on
+/// persistent storage.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Storage {
+    /// Device identity cached for offline starts. Cleared on reprovisioning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<std::path::PathBuf>,
+
+    /// Module workload sockets, which are recreated at every start.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnt_dir: Option<std::path::PathBuf>,
+
+    /// Image use data of image garbage collection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gc_dir: Option<std::path::PathBuf>,
+
+    /// State files of the daemon, such as the change feed, module secrets and the audit
+    /// log.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_dir: Option<std::path::PathBuf>,
+
+    /// Module output kept by log persistence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logs_dir: Option<std::path::PathBuf>,
+}
+
+impl Storage {
+    pub fn is_default(&self) -> bool {
+        self == &Storage::default()
+    }
+
+    pub fn cache_dir(&self, homedir: &std::path::Path) -> std::path::PathBuf {
+        self.cache_dir
+            .clone()
+            .unwrap_or_else(|| homedir.join("cache"))
+    }
+
+    pub fn mnt_dir(&self, homedir: &std::path::Path) -> std::path::PathBuf {
+        self.mnt_dir.clone().unwrap_or_else(|| homedir.join("mnt"))
+    }
+
+    pub fn gc_dir(&self, homedir: &std::path::Path) -> std::path::PathBuf {
+        self.gc_dir.clone().unwrap_or_else(|| homedir.join("gc"))
+    }
+
+    pub fn state_dir(&self, homedir: &std::path::Path) -> std::path::PathBuf {
+        self.state_dir
+            .clone()
+            .unwrap_or_else(|| homedir.join("state"))
+    }
+
+    pub fn logs_dir(&self, homedir: &std::path::Path) -> std::path::PathBuf {
+        self.logs_dir
+            .clone()
+            .unwrap_or_else(|| homedir.join("logs"))
+    }
+}
+
+/// Proxy that aziot-edged, the services it depends on and Edge Agent connect through.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Proxy {
+    pub uri: url::Url,
+
+    /// Hosts, domains and addresses that are connected to directly.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+}
+
+impl Proxy {
+    pub fn uri(&self) -> &url::Url {
+        &self.uri
+    }
+
+    pub fn no_proxy(&self) -> &[String] {
+        &self.no_proxy
+    }
+
+    /// The environment variables that clients read the proxy from.
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            ("https_proxy", self.uri.to_string()),
+            ("http_proxy", self.uri.to_string()),
+        ];
+
+        if !self.no_proxy.is_empty() {
+            env.push(("no_proxy", self.no_proxy.join(",")));
+        }
+
+        env
+    }
+}
+
+/// Host devices that modules may map into their containers.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct DeviceMapping {
+    /// Give modules labeled as GPU workloads the NVIDIA container runtime, which maps the
+    /// GPUs into their containers.
+    #[serde(default)]
+    pub nvidia_runtime: bool,
+
+    /// Paths of the devices that modules may map, such as "/dev/gpiomem". A path ending
+    /// with '*' allows every device it prefixes. Any device may be mapped if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed: Option<Vec<String>>,
+}
+
+impl DeviceMapping {
+    /// Label of the modules that are given the NVIDIA container runtime, with the value "true".
+    pub const GPU_LABEL: &'static str = "net.azure-devices.edge.gpu";
+
+    pub fn is_default(&self) -> bool {
+        self == &DeviceMapping::default()
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        self.allowed.is_some()
+    }
+
+    /// Whether modules may map the device at `path`.
+    pub fn allows(&self, path: &str) -> bool {
+        self.allowed.as_ref().map_or(true, |allowed| {
+            allowed
+                .iter()
+                .any(|allowed| match allowed.strip_suffix('*') {
+                    Some(prefix) => path.starts_with(prefix),
+                    None => path == allowed,
+                })
+        })
+    }
+}
+
+/// Trades compatibility for CPU time in the TLS work aziot-edged does, such as issuing
+/// module certificates.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TlsPerformanceMode {
+    /// RSA-2048 keys for issued certificates and the default cipher order.
+    #[default]
+    Off,
+
+    /// Performance mode on ARM devices, off elsewhere.
+    Auto,
+
+    /// ECDSA P-256 keys for issued certificates, and ChaCha20-Poly1305 preferred over
+    /// AES-GCM on CPUs without AES instructions.
+    On,
+}
+
+impl TlsPerformanceMode {
+    pub fn is_default(&self) -> bool {
+        self == &TlsPerformanceMode::default()
+    }
+}
+
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct Settings<ModuleConfig> {
     pub hostname: String,
@@ -110,7 +658,8 @@ pub struct Settings<ModuleConfig> {
     #[serde(default, skip_serializing_if = "EdgeCa::is_default")]
     pub edge_ca: EdgeCa,
 
-    pub agent: module::Settings<ModuleConfig>,
+    #[serde(bound(deserialize = "ModuleConfig: serde::de::DeserializeOwned"))]
+    pub agent: module::AgentSettings<ModuleConfig>,
     pub connect: uri::Connect,
     pub listen: uri::Listen,
 
@@ -130,6 +679,96 @@ pub struct Settings<ModuleConfig> {
 
     #[serde(default, skip_serializing_if = "image::ImagePruneSettings::is_default")]
     pub image_garbage_collection: image::ImagePruneSettings,
+
+    #[serde(default, skip_serializing_if = "logging::LogFormat::is_default")]
+    pub log_format: logging::LogFormat,
+
+    #[serde(
+        default,
+        skip_serializing_if = "logging::AccessLogSettings::is_default"
+    )]
+    pub api_access_log: logging::AccessLogSettings,
+
+    /// Device-wide feature flags exposed to modules through the workload API.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub feature_flags: std::collections::BTreeMap<String, bool>,
+
+    /// Modules that are kept with a created-but-stopped standby container, which is
+    /// started in place of the module if it fails.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warm_standby_modules: Vec<String>,
+
+    /// Give modules the host's timezone and clock synchronization state.
+    #[serde(default)]
+    pub inject_host_time: bool,
+
+    #[serde(default, skip_serializing_if = "DegradedMode::is_empty")]
+    pub degraded_mode: DegradedMode<ModuleConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_overlay: Option<SiteOverlaySource>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_sink: Option<LogSink>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_persistence: Option<LogPersistence>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<Proxy>,
+
+    #[serde(default, skip_serializing_if = "DeviceMapping::is_default")]
+    pub device_mapping: DeviceMapping,
+
+    #[serde(default, skip_serializing_if = "Storage::is_default")]
+    pub storage: Storage,
+
+    /// Variables substituted into module env and createOptions, in addition to the
+    /// device's identity.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub template_variables: std::collections::BTreeMap<String, String>,
+
+    /// Start modules with the device identity from the last successful provisioning if
+    /// the device can't be provisioned at startup, e.g. because it has no connectivity.
+    #[serde(default)]
+    pub offline_start: bool,
+
+    /// Leave modules running when systemd restarts the daemon, and keep their workload
+    /// sockets open in systemd so that the next run of the daemon serves them without
+    /// a gap.
+    #[serde(default)]
+    pub warm_restart: bool,
+
+    /// How often to ask Identity Service to reprovision, so that a changed DPS
+    /// assignment is picked up without restarting the daemon.
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reprovision_check_interval: Option<std::time::Duration>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<alerts::Rule>,
+
+    #[serde(default, skip_serializing_if = "WorkloadRateLimit::is_default")]
+    pub workload_rate_limit: WorkloadRateLimit,
+
+    #[serde(default, skip_serializing_if = "WorkloadServerCerts::is_default")]
+    pub workload_server_certs: WorkloadServerCerts,
+
+    #[serde(default, skip_serializing_if = "ModuleCertRenewal::is_default")]
+    pub module_cert_renewal: ModuleCertRenewal,
+
+    /// Workload socket settings by module name.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub workload_sockets: std::collections::BTreeMap<String, WorkloadSocket>,
+
+    #[serde(default, skip_serializing_if = "CloudNotify::is_default")]
+    pub cloud_notify: CloudNotify,
+
+    #[serde(default, skip_serializing_if = "TlsPerformanceMode::is_default")]
+    pub tls_performance_mode: TlsPerformanceMode,
 }
 
 pub(crate) fn default_allow_elevated_docker_permissions() -> bool {
@@ -184,11 +823,19 @@ impl<T: Clone> RuntimeSettings for Settings<T> {
     }
 
     fn agent(&self) -> &module::Settings<Self::ModuleConfig> {
-        &self.agent
+        self.agent.spec()
     }
 
     fn agent_mut(&mut self) -> &mut module::Settings<Self::ModuleConfig> {
-        &mut self.agent
+        self.agent.spec_mut()
+    }
+
+    fn agent_fallback_images(&self) -> &[String] {
+        self.agent.fallback_images()
+    }
+
+    fn agent_cached_image_fallback(&self) -> bool {
+        self.agent.cached_image_fallback()
     }
 
     fn connect(&self) -> &uri::Connect {
@@ -214,4 +861,96 @@ impl<T: Clone> RuntimeSettings for Settings<T> {
     fn image_garbage_collection(&self) -> &image::ImagePruneSettings {
         &self.image_garbage_collection
     }
+
+    fn log_format(&self) -> logging::LogFormat {
+        self.log_format
+    }
+
+    fn api_access_log(&self) -> &logging::AccessLogSettings {
+        &self.api_access_log
+    }
+
+    fn feature_flags(&self) -> &std::collections::BTreeMap<String, bool> {
+        &self.feature_flags
+    }
+
+    fn warm_standby_modules(&self) -> &[String] {
+        &self.warm_standby_modules
+    }
+
+    fn inject_host_time(&self) -> bool {
+        self.inject_host_time
+    }
+
+    fn degraded_mode(&self) -> &DegradedMode<Self::ModuleConfig> {
+        &self.degraded_mode
+    }
+
+    fn site_overlay(&self) -> Option<&SiteOverlaySource> {
+        self.site_overlay.as_ref()
+    }
+
+    fn log_sink(&self) -> Option<&LogSink> {
+        self.log_sink.as_ref()
+    }
+
+    fn log_persistence(&self) -> Option<&LogPersistence> {
+        self.log_persistence.as_ref()
+    }
+
+    fn proxy(&self) -> Option<&Proxy> {
+        self.proxy.as_ref()
+    }
+
+    fn device_mapping(&self) -> &DeviceMapping {
+        &self.device_mapping
+    }
+
+    fn storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    fn template_variables(&self) -> &std::collections::BTreeMap<String, String> {
+        &self.template_variables
+    }
+
+    fn offline_start(&self) -> bool {
+        self.offline_start
+    }
+
+    fn warm_restart(&self) -> bool {
+        self.warm_restart
+    }
+
+    fn reprovision_check_interval(&self) -> Option<std::time::Duration> {
+        self.reprovision_check_interval
+    }
+
+    fn alerts(&self) -> &[alerts::Rule] {
+        &self.alerts
+    }
+
+    fn workload_rate_limit(&self) -> &WorkloadRateLimit {
+        &self.workload_rate_limit
+    }
+
+    fn workload_server_certs(&self) -> &WorkloadServerCerts {
+        &self.workload_server_certs
+    }
+
+    fn module_cert_renewal(&self) -> &ModuleCertRenewal {
+        &self.module_cert_renewal
+    }
+
+    fn workload_sockets(&self) -> &std::collections::BTreeMap<String, WorkloadSocket> {
+        &self.workload_sockets
+    }
+
+    fn cloud_notify(&self) -> &CloudNotify {
+        &self.cloud_notify
+    }
+
+    fn tls_performance_mode(&self) -> TlsPerformanceMode {
+        self.tls_performance_mode
+    }
 }
@@ -118,6 +118,137 @@ impl<ModuleConfig> Settings<ModuleConfig> {
     }
 }
 
+/// Settings of Edge Agent. Unlike other modules, the `image` of its config may be a list
+/// of images in priority order. aziot-edged tries them in turn when it bootstraps Edge
+/// Agent, so that it can still be started when the first image's registry is unreachable.
+///
+/// `cachedImageFallback` controls whether a previously pulled image is used when none of
+/// the images can be pulled.
+#[derive(Clone, Debug)]
+pub struct AgentSettings<ModuleConfig> {
+    spec: Settings<ModuleConfig>,
+    fallback_images: Vec<String>,
+    cached_image_fallback: bool,
+}
+
+impl<ModuleConfig> AgentSettings<ModuleConfig> {
+    /// Edge Agent's module spec, with the first image of the list.
+    pub fn spec(&self) -> &Settings<ModuleConfig> {
+        &self.spec
+    }
+
+    pub fn spec_mut(&mut self) -> &mut Settings<ModuleConfig> {
+        &mut self.spec
+    }
+
+    /// Images to try, in order, if the spec's image can't be pulled.
+    pub fn fallback_images(&self) -> &[String] {
+        &self.fallback_images
+    }
+
+    pub fn fallback_images_mut(&mut self) -> &mut [String] {
+        &mut self.fallback_images
+    }
+
+    /// Whether Edge Agent may be started from a cached image of the same repository when
+    /// none of its images can be pulled.
+    pub fn cached_image_fallback(&self) -> bool {
+        self.cached_image_fallback
+    }
+}
+
+impl<ModuleConfig> From<Settings<ModuleConfig>> for AgentSettings<ModuleConfig> {
+    fn from(spec: Settings<ModuleConfig>) -> Self {
+        AgentSettings {
+            spec,
+            fallback_images: Vec::new(),
+            cached_image_fallback: true,
+        }
+    }
+}
+
+impl<'de, ModuleConfig> serde::Deserialize<'de> for AgentSettings<ModuleConfig>
+where
+    ModuleConfig: serde::de::DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        // The module config is generic, so the image list is taken out of it before the
+        // rest is deserialized as a module spec.
+        let mut value: serde_json::Value = serde::Deserialize::deserialize(deserializer)?;
+
+        let mut fallback_images = Vec::new();
+        if let Some(image) = value.pointer_mut("/config/image") {
+            if image.is_array() {
+                let images: Vec<String> =
+                    serde_json::from_value(image.take()).map_err(D::Error::custom)?;
+
+                if images.iter().any(|image| image.trim().is_empty()) {
+                    return Err(D::Error::custom("image cannot be empty"));
+                }
+
+                let mut images = images.into_iter();
+                *image = images
+                    .next()
+                    .ok_or_else(|| D::Error::custom("image list cannot be empty"))?
+                    .into();
+                fallback_images = images.collect();
+            }
+        }
+
+        let cached_image_fallback = match value
+            .as_object_mut()
+            .and_then(|value| value.remove("cachedImageFallback"))
+        {
+            Some(enabled) => serde_json::from_value(enabled).map_err(D::Error::custom)?,
+            None => true,
+        };
+
+        let spec = serde_json::from_value(value).map_err(D::Error::custom)?;
+
+        Ok(AgentSettings {
+            spec,
+            fallback_images,
+            cached_image_fallback,
+        })
+    }
+}
+
+impl<ModuleConfig> serde::Serialize for AgentSettings<ModuleConfig>
+where
+    ModuleConfig: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if self.fallback_images.is_empty() && self.cached_image_fallback {
+            return serde::Serialize::serialize(&self.spec, serializer);
+        }
+
+        let mut value = serde_json::to_value(&self.spec).map_err(serde::ser::Error::custom)?;
+        if !self.fallback_images.is_empty() {
+            if let Some(image) = value.pointer_mut("/config/image") {
+                let images = std::iter::once(image.take())
+                    .chain(self.fallback_images.iter().cloned().map(Into::into))
+                    .collect();
+                *image = serde_json::Value::Array(images);
+            }
+        }
+        if !self.cached_image_fallback {
+            if let Some(value) = value.as_object_mut() {
+                value.insert("cachedImageFallback".to_string(), false.into());
+            }
+        }
+
+        serde::Serialize::serialize(&value, serializer)
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ImagePullPolicy {
@@ -138,3 +269,75 @@ impl std::str::FromStr for ImagePullPolicy {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::AgentSettings;
+
+    #[test]
+    fn agent_image_list() {
+        let input_json = json!({
+            "name": "edgeAgent",
+            "type": "docker",
+            "config": {
+                "image": ["primary.azurecr.io/agent:1.5", "mcr.microsoft.com/agent:1.5"]
+            }
+        });
+
+        let agent: AgentSettings<serde_json::Value> =
+            serde_json::from_value(input_json.clone()).unwrap();
+        assert_eq!(
+            json!("primary.azurecr.io/agent:1.5"),
+            agent.spec().config()["image"]
+        );
+        assert_eq!(
+            &["mcr.microsoft.com/agent:1.5".to_string()],
+            agent.fallback_images()
+        );
+
+        // The list is preserved when the settings are serialized.
+        let actual_json = serde_json::to_value(&agent).unwrap();
+        assert_eq!(
+            input_json["config"]["image"],
+            actual_json["config"]["image"]
+        );
+
+        // A single image is still a string.
+        let agent: AgentSettings<serde_json::Value> = serde_json::from_value(json!({
+            "name": "edgeAgent",
+            "type": "docker",
+            "config": { "image": "ubuntu" }
+        }))
+        .unwrap();
+        assert!(agent.fallback_images().is_empty());
+        assert!(agent.cached_image_fallback());
+        let actual_json = serde_json::to_value(&agent).unwrap();
+        assert_eq!(json!("ubuntu"), actual_json["config"]["image"]);
+        assert!(actual_json.get("cachedImageFallback").is_none());
+
+        // Starting from a cached image can be turned off.
+        let agent: AgentSettings<serde_json::Value> = serde_json::from_value(json!({
+            "name": "edgeAgent",
+            "type": "docker",
+            "cachedImageFallback": false,
+            "config": { "image": "ubuntu" }
+        }))
+        .unwrap();
+        assert!(!agent.cached_image_fallback());
+        let actual_json = serde_json::to_value(&agent).unwrap();
+        assert_eq!(json!(false), actual_json["cachedImageFallback"]);
+        assert_eq!(json!("ubuntu"), actual_json["config"]["image"]);
+
+        // Empty lists and empty images are rejected.
+        for image in [json!([]), json!(["ubuntu", " "])] {
+            serde_json::from_value::<AgentSettings<serde_json::Value>>(json!({
+                "name": "edgeAgent",
+                "type": "docker",
+                "config": { "image": image }
+            }))
+            .unwrap_err();
+        }
+    }
+}
@@ -4,6 +4,11 @@
 pub struct Connect {
     pub workload_uri: url::Url,
     pub management_uri: url::Url,
+
+    /// URI that modules use to reach the TCP workload listener, if it is enabled.
+    /// Without it, only modules on the host network are given the listen URI.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workload_tcp_uri: Option<url::Url>,
 }
 
 impl Connect {
@@ -14,6 +19,10 @@ impl Connect {
     pub fn management_uri(&self) -> &url::Url {
         &self.management_uri
     }
+
+    pub fn workload_tcp_uri(&self) -> Option<&url::Url> {
+        self.workload_tcp_uri.as_ref()
+    }
 }
 
 impl Default for Connect {
@@ -28,6 +37,7 @@ impl Default for Connect {
             management_uri: management_uri
                 .parse()
                 .expect("failed to parse management uri"),
+            workload_tcp_uri: None,
         }
     }
 }
@@ -36,6 +46,29 @@ impl Default for Connect {
 pub struct Listen {
     pub workload_uri: url::Url,
     pub management_uri: url::Url,
+
+    /// Optional localhost TCP listener for the workload API. Requests on this
+    /// listener are authenticated with per-module bearer tokens instead of the
+    /// caller's PID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workload_tcp_uri: Option<url::Url>,
+
+    /// Optional mutual TLS listener for the management API.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub management_tcp: Option<ManagementTcp>,
+}
+
+/// TCP listener for the management API, for callers that can't reach the Unix socket.
+///
+/// The listener's server certificate is issued by the Edge CA. Clients must present a
+/// certificate whose SHA-256 thumbprint is in the allowed list.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ManagementTcp {
+    pub address: std::net::SocketAddr,
+
+    /// Hex-encoded SHA-256 thumbprints. Colons and case are ignored.
+    #[serde(default)]
+    pub allowed_client_thumbprints: Vec<String>,
 }
 
 impl Listen {
@@ -43,12 +76,12 @@ impl Listen {
         &self.workload_uri
     }
 
-    pub fn workload_mnt_uri(home_dir: &str) -> String {
-        "unix://".to_string() + home_dir + "/mnt"
+    pub fn workload_mnt_uri(mnt_dir: &str) -> String {
+        "unix://".to_string() + mnt_dir
     }
 
-    pub fn workload_uri(home_dir: &str, module_id: &str) -> Result<url::Url, url::ParseError> {
-        url::Url::parse(&("unix://".to_string() + home_dir + "/mnt/" + module_id + ".sock"))
+    pub fn workload_uri(mnt_dir: &str, module_id: &str) -> Result<url::Url, url::ParseError> {
+        url::Url::parse(&("unix://".to_string() + mnt_dir + "/" + module_id + ".sock"))
     }
 
     pub fn get_workload_systemd_socket_name() -> String {
@@ -62,6 +95,14 @@ impl Listen {
     pub fn management_uri(&self) -> &url::Url {
         &self.management_uri
     }
+
+    pub fn workload_tcp_uri(&self) -> Option<&url::Url> {
+        self.workload_tcp_uri.as_ref()
+    }
+
+    pub fn management_tcp(&self) -> Option<&ManagementTcp> {
+        self.management_tcp.as_ref()
+    }
 }
 
 impl Default for Listen {
@@ -76,6 +117,8 @@ impl Default for Listen {
             management_uri: management_uri
                 .parse()
                 .expect("failed to parse management uri"),
+            workload_tcp_uri: None,
+            management_tcp: None,
         }
     }
 }
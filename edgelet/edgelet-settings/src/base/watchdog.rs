@@ -2,16 +2,359 @@
 
 use std::convert::TryInto;
 
-#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct Settings {
     #[serde(default)]
     pub max_retries: MaxRetries,
+
+    /// The watchdog checks Edge Agent every minute. After consecutive errors, the wait
+    /// doubles up to this. The default of one minute keeps the wait constant.
+    #[serde(default = "default_max_backoff", with = "humantime_serde")]
+    pub max_backoff: std::time::Duration,
+
+    #[serde(default)]
+    pub module_health: ModuleHealth,
+
+    /// Restart policies by module name. Modules without one are restarted whenever asked.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub restart_policies: std::collections::BTreeMap<String, RestartPolicy>,
+
+    #[serde(default)]
+    pub disk_space: DiskSpace,
+
+    #[serde(default)]
+    pub parent: ParentMonitor,
 }
 
 impl Settings {
     pub fn max_retries(&self) -> MaxRetries {
         self.max_retries
     }
+
+    pub fn max_backoff(&self) -> std::time::Duration {
+        self.max_backoff
+    }
+
+    pub fn module_health(&self) -> &ModuleHealth {
+        &self.module_health
+    }
+
+    pub fn restart_policies(&self) -> &std::collections::BTreeMap<String, RestartPolicy> {
+        &self.restart_policies
+    }
+
+    pub fn disk_space(&self) -> &DiskSpace {
+        &self.disk_space
+    }
+
+    pub fn parent(&self) -> &ParentMonitor {
+        &self.parent
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            max_retries: MaxRetries::default(),
+            max_backoff: default_max_backoff(),
+            module_health: ModuleHealth::default(),
+            restart_policies: std::collections::BTreeMap::new(),
+            disk_space: DiskSpace::default(),
+            parent: ParentMonitor::default(),
+        }
+    }
+}
+
+fn default_max_backoff() -> std::time::Duration {
+    std::time::Duration::from_secs(60)
+}
+
+/// Restarts of running modules that stay unhealthy, judged by their Docker healthcheck or
+/// by a probe configured here.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ModuleHealth {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// How long a module must stay unhealthy before it's restarted.
+    #[serde(default = "default_grace_period", with = "humantime_serde")]
+    pub grace_period: std::time::Duration,
+
+    /// Probes by module name, for modules whose images don't have a healthcheck.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub probes: std::collections::BTreeMap<String, Probe>,
+}
+
+impl ModuleHealth {
+    /// The command of a module's exec probe, if it has one.
+    pub fn exec_probe(&self, module: &str) -> Option<&[String]> {
+        match self.probes.get(module) {
+            Some(Probe::Exec { command }) => Some(command),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ModuleHealth {
+    fn default() -> Self {
+        ModuleHealth {
+            enabled: false,
+            grace_period: default_grace_period(),
+            probes: std::collections::BTreeMap::new(),
+        }
+    }
+}
+
+fn default_grace_period() -> std::time::Duration {
+    std::time::Duration::from_secs(3 * 60)
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Probe {
+    /// The module is healthy if a GET of `url` from the host succeeds within `timeout`.
+    /// A `url` whose host is `localhost`, a loopback address or the module's name is
+    /// sent to the module's address on its network.
+    Http {
+        url: url::Url,
+
+        #[serde(default = "default_probe_timeout", with = "humantime_serde")]
+        timeout: std::time::Duration,
+    },
+
+    /// The module is healthy if `command` exits with status 0 in its container. The
+    /// command is set as the container's Docker healthcheck when the module is created,
+    /// unless its create options already have one.
+    Exec { command: Vec<String> },
+}
+
+fn default_probe_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(5)
+}
+
+/// Limits on how often a module may be started or restarted, so that a module in a crash
+/// loop doesn't run forever.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RestartPolicy {
+    /// Starts allowed within `window` before the circuit breaker trips.
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+
+    #[serde(default = "default_restart_window", with = "humantime_serde")]
+    pub window: std::time::Duration,
+
+    /// The wait required after the second start within `window`. It doubles with each
+    /// further start, up to `max_backoff`.
+    #[serde(default = "default_initial_backoff", with = "humantime_serde")]
+    pub initial_backoff: std::time::Duration,
+
+    #[serde(default = "default_restart_max_backoff", with = "humantime_serde")]
+    pub max_backoff: std::time::Duration,
+
+    /// What happens when the circuit breaker trips.
+    #[serde(default)]
+    pub give_up: GiveUpAction,
+
+    /// How long a tripped circuit breaker stays open with `give_up = "stop"`.
+    #[serde(default = "default_cooldown", with = "humantime_serde")]
+    pub cooldown: std::time::Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            max_restarts: default_max_restarts(),
+            window: default_restart_window(),
+            initial_backoff: default_initial_backoff(),
+            max_backoff: default_restart_max_backoff(),
+            give_up: GiveUpAction::default(),
+            cooldown: default_cooldown(),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// The wait required before a start, given the number of starts already within
+    /// `window`.
+    pub fn backoff(&self, starts: u32) -> std::time::Duration {
+        match starts {
+            0 | 1 => std::time::Duration::ZERO,
+            starts => self
+                .initial_backoff
+                .checked_mul(2_u32.saturating_pow(starts - 2))
+                .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff)),
+        }
+    }
+}
+
+fn default_max_restarts() -> u32 {
+    5
+}
+
+fn default_restart_window() -> std::time::Duration {
+    std::time::Duration::from_secs(5 * 60)
+}
+
+fn default_initial_backoff() -> std::time::Duration {
+    std::time::Duration::from_secs(10)
+}
+
+fn default_restart_max_backoff() -> std::time::Duration {
+    std::time::Duration::from_secs(5 * 60)
+}
+
+fn default_cooldown() -> std::time::Duration {
+    std::time::Duration::from_secs(30 * 60)
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GiveUpAction {
+    /// Stop the module, and allow it to start again once the cooldown has passed.
+    #[default]
+    Stop,
+
+    /// Stop the module, and keep it stopped until it is deployed again.
+    Disable,
+}
+
+/// Monitoring of the free space on the file systems of the home directory, the storage
+/// directories and the container engine's data root.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "UncheckedDiskSpace")]
+pub struct DiskSpace {
+    pub enabled: bool,
+
+    #[serde(with = "humantime_serde")]
+    pub check_interval: std::time::Duration,
+
+    /// Below this much free space, a warning is logged.
+    pub warning_free_percent: u8,
+
+    /// Below this much free space, modules aren't created and images aren't pulled.
+    pub critical_free_percent: u8,
+}
+
+/// Disk space settings as written, before the thresholds are checked against each
+/// other.
+#[derive(serde::Deserialize)]
+struct UncheckedDiskSpace {
+    #[serde(default = "default_disk_space_enabled")]
+    enabled: bool,
+
+    #[serde(
+        default = "default_disk_space_check_interval",
+        with = "humantime_serde"
+    )]
+    check_interval: std::time::Duration,
+
+    #[serde(default = "default_warning_free_percent")]
+    warning_free_percent: u8,
+
+    #[serde(default = "default_critical_free_percent")]
+    critical_free_percent: u8,
+}
+
+impl TryFrom<UncheckedDiskSpace> for DiskSpace {
+    type Error = String;
+
+    fn try_from(settings: UncheckedDiskSpace) -> Result<Self, Self::Error> {
+        let UncheckedDiskSpace {
+            enabled,
+            check_interval,
+            warning_free_percent,
+            critical_free_percent,
+        } = settings;
+
+        // Space would be critically low before a warning was ever logged.
+        if warning_free_percent <= critical_free_percent {
+            return Err(format!(
+                "invalid disk_space settings: warning_free_percent ({warning_free_percent}) must be greater than critical_free_percent ({critical_free_percent})"
+            ));
+        }
+
+        if warning_free_percent > 100 {
+            return Err(format!(
+                "invalid disk_space settings: warning_free_percent ({warning_free_percent}) must be at most 100"
+            ));
+        }
+
+        Ok(DiskSpace {
+            enabled,
+            check_interval,
+            warning_free_percent,
+            critical_free_percent,
+        })
+    }
+}
+
+impl Default for DiskSpace {
+    fn default() -> Self {
+        DiskSpace {
+            enabled: default_disk_space_enabled(),
+            check_interval: default_disk_space_check_interval(),
+            warning_free_percent: default_warning_free_percent(),
+            critical_free_percent: default_critical_free_percent(),
+        }
+    }
+}
+
+fn default_disk_space_enabled() -> bool {
+    true
+}
+
+fn default_disk_space_check_interval() -> std::time::Duration {
+    std::time::Duration::from_secs(60)
+}
+
+fn default_warning_free_percent() -> u8 {
+    10
+}
+
+fn default_critical_free_percent() -> u8 {
+    3
+}
+
+/// Monitoring of the connection to the parent of a nested device.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ParentMonitor {
+    #[serde(default = "default_parent_enabled")]
+    pub enabled: bool,
+
+    #[serde(default = "default_parent_check_interval", with = "humantime_serde")]
+    pub check_interval: std::time::Duration,
+
+    /// Parents that don't complete a TLS handshake within this are unreachable.
+    #[serde(default = "default_parent_timeout", with = "humantime_serde")]
+    pub timeout: std::time::Duration,
+
+    /// Whether the watchdog waits to start or recreate Edge Agent while the parent is
+    /// down, rather than failing to pull its image through the parent.
+    #[serde(default)]
+    pub defer_agent_restarts: bool,
+}
+
+impl Default for ParentMonitor {
+    fn default() -> Self {
+        ParentMonitor {
+            enabled: default_parent_enabled(),
+            check_interval: default_parent_check_interval(),
+            timeout: default_parent_timeout(),
+            defer_agent_restarts: false,
+        }
+    }
+}
+
+fn default_parent_enabled() -> bool {
+    true
+}
+
+fn default_parent_check_interval() -> std::time::Duration {
+    std::time::Duration::from_secs(30)
+}
+
+fn default_parent_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(10)
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -125,6 +468,83 @@ impl serde::Serialize for MaxRetries {
 
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn module_health() {
+        let settings: super::Settings = serde_json::from_value(serde_json::json!({
+            "module_health": {
+                "enabled": true,
+                "probes": {
+                    "sensor": { "type": "http", "url": "http://127.0.0.1:8080/healthz" },
+                    "camera": { "type": "exec", "command": ["/bin/check", "--quick"] },
+                },
+            },
+        }))
+        .unwrap();
+
+        let module_health = settings.module_health();
+        assert!(module_health.enabled);
+        assert_eq!(
+            std::time::Duration::from_secs(180),
+            module_health.grace_period
+        );
+        assert_eq!(
+            Some(&super::Probe::Http {
+                url: "http://127.0.0.1:8080/healthz".parse().unwrap(),
+                timeout: std::time::Duration::from_secs(5),
+            }),
+            module_health.probes.get("sensor")
+        );
+        assert_eq!(
+            Some(&["/bin/check".to_string(), "--quick".to_string()][..]),
+            module_health.exec_probe("camera")
+        );
+        assert_eq!(None, module_health.exec_probe("sensor"));
+    }
+
+    #[test]
+    fn restart_policy() {
+        let settings: super::Settings = serde_json::from_value(serde_json::json!({
+            "restart_policies": {
+                "sensor": { "max_restarts": 3, "initial_backoff": "10s", "max_backoff": "30s" },
+                "camera": { "give_up": "disable" },
+            },
+        }))
+        .unwrap();
+
+        let sensor = &settings.restart_policies()["sensor"];
+        assert_eq!(3, sensor.max_restarts);
+        assert_eq!(super::GiveUpAction::Stop, sensor.give_up);
+        assert_eq!(
+            [0, 0, 10, 20, 30, 30],
+            [0, 1, 2, 3, 4, 40].map(|starts| sensor.backoff(starts).as_secs())
+        );
+
+        let camera = &settings.restart_policies()["camera"];
+        assert_eq!(super::GiveUpAction::Disable, camera.give_up);
+        assert_eq!(5, camera.max_restarts);
+    }
+
+    #[test]
+    fn disk_space_thresholds() {
+        let settings: super::Settings = serde_json::from_value(serde_json::json!({
+            "disk_space": { "warning_free_percent": 20, "critical_free_percent": 5 },
+        }))
+        .unwrap();
+        assert_eq!(20, settings.disk_space().warning_free_percent);
+        assert_eq!(5, settings.disk_space().critical_free_percent);
+
+        // Space must be low before it's critically low.
+        for (warning, critical) in [(5, 5), (5, 20), (101, 20)] {
+            serde_json::from_value::<super::Settings>(serde_json::json!({
+                "disk_space": {
+                    "warning_free_percent": warning,
+                    "critical_free_percent": critical,
+                },
+            }))
+            .unwrap_err();
+        }
+    }
+
     #[test]
     fn max_retries_cmp() {
         let max_retries = super::MaxRetries::Infinite;
@@ -107,23 +107,30 @@ impl DockerConfig {
     }
 
     pub fn parent_hostname_resolve(&mut self, parent_hostname: &str) {
-        if let Some(rest) = self.image.strip_prefix(UPSTREAM_PARENT_KEYWORD) {
-            self.image = format!("{parent_hostname}{rest}");
-        }
-
-        let Some(auth) = &self.auth else { return };
+        upstream_resolve(&mut self.image, parent_hostname);
 
-        if let Some(serveraddress) = auth.serveraddress() {
-            if let Some(rest) = serveraddress.strip_prefix(UPSTREAM_PARENT_KEYWORD) {
-                let url = rest.to_string();
-                if let Some(auth) = &mut self.auth {
-                    auth.set_serveraddress(format!("{parent_hostname}{url}"));
-                }
+        if let Some(auth) = &mut self.auth {
+            if let Some(serveraddress) = auth.serveraddress() {
+                let mut serveraddress = serveraddress.to_string();
+                upstream_resolve(&mut serveraddress, parent_hostname);
+                auth.set_serveraddress(serveraddress);
             }
         }
     }
 }
 
+/// Replace the `$upstream` keyword at the start of an image name or registry address with
+/// the parent's hostname.
+pub(crate) fn upstream_resolve(value: &mut String, parent_hostname: &str) {
+    if let Some(rest) = value.strip_prefix(UPSTREAM_PARENT_KEYWORD) {
+        // An IPv6 parent address needs brackets in image names, as in URLs.
+        *value = match parent_hostname.parse::<std::net::Ipv6Addr>() {
+            Ok(address) => format!("[{address}]{rest}"),
+            Err(_) => format!("{parent_hostname}{rest}"),
+        };
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use docker::models::{AuthConfig, ContainerCreateBody, HostConfig, HostConfigPortBindings};
@@ -258,7 +265,7 @@ mod tests {
             "image": "ubuntu"
         });
         let config = serde_json::from_str::<DockerConfig>(&input_json.to_string()).unwrap();
-        assert_eq!(config.image, "ubuntu");
+        assert_eq!(config.image(), "ubuntu");
     }
 
     #[test]
@@ -288,7 +295,7 @@ mod tests {
         });
 
         let config = serde_json::from_str::<DockerConfig>(&input_json.to_string()).unwrap();
-        assert_eq!(config.image, "ubuntu");
+        assert_eq!(config.image(), "ubuntu");
         assert_eq!(&config.create_options.labels().unwrap()["k1"], "v1");
         assert_eq!(&config.create_options.labels().unwrap()["k2"], "v2");
 
@@ -333,7 +340,7 @@ mod tests {
         });
 
         let config: DockerConfig = serde_json::from_str(&input_json.to_string()).unwrap();
-        assert_eq!(config.image, "ubuntu");
+        assert_eq!(config.image(), "ubuntu");
         assert_eq!(&config.create_options.labels().unwrap()["k1"], "v1");
         assert_eq!(&config.create_options.labels().unwrap()["k2"], "v2");
 
@@ -348,4 +355,33 @@ mod tests {
             "27017"
         );
     }
+
+    #[test]
+    fn parent_hostname_resolve() {
+        let mut config = DockerConfig::new(
+            "$upstream:443/azureiotedge-agent:1.5".to_string(),
+            ContainerCreateBody::new(),
+            None,
+            Some(AuthConfig::new().with_serveraddress("$upstream:443".to_string())),
+            true,
+        )
+        .unwrap();
+        config.parent_hostname_resolve("parent.local");
+        assert_eq!("parent.local:443/azureiotedge-agent:1.5", config.image());
+        assert_eq!(
+            Some("parent.local:443"),
+            config.auth().unwrap().serveraddress()
+        );
+
+        let mut config = DockerConfig::new(
+            "$upstream:443/azureiotedge-agent:1.5".to_string(),
+            ContainerCreateBody::new(),
+            None,
+            None,
+            true,
+        )
+        .unwrap();
+        config.parent_hostname_resolve("fd00::1");
+        assert_eq!("[fd00::1]:443/azureiotedge-agent:1.5", config.image());
+    }
 }
@@ -35,9 +35,9 @@ fn agent_vol_mount(
     let workload_connect_uri = settings.connect().workload_uri();
 
     let workload_listen_uri = {
-        let mut path = settings.homedir().canonicalize()?;
+        let homedir = settings.homedir().canonicalize()?;
+        let mut path = settings.storage().mnt_dir(&homedir);
 
-        path.push("mnt");
         path.push(format!("{}.sock", settings.agent().name()));
 
         let path = path.to_str().ok_or("invalid workload socket path")?;
@@ -82,6 +82,16 @@ fn agent_env(settings: &mut crate::docker::Settings) {
         .agent_mut()
         .env_mut()
         .insert("NetworkId".to_string(), network_id);
+
+    // Edge Agent reads the proxy from its environment. Proxy variables set explicitly in
+    // its env take precedence over the proxy settings.
+    if let Some(proxy) = settings.proxy().cloned() {
+        let env = settings.agent_mut().env_mut();
+
+        for (name, value) in proxy.env() {
+            env.entry(name.to_string()).or_insert(value);
+        }
+    }
 }
 
 fn agent_networking(settings: &mut crate::docker::Settings) {
@@ -4,6 +4,7 @@ mod init;
 
 pub mod config;
 pub mod network;
+pub mod overlay;
 pub mod runtime;
 
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
@@ -12,6 +13,9 @@ pub struct Settings {
     pub base: crate::base::Settings<config::DockerConfig>,
 
     pub moby_runtime: runtime::MobyRuntime,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_policy: Option<network::NetworkPolicy>,
 }
 
 pub const CONFIG_FILE_DEFAULT: &str = "/etc/aziot/edged/config.toml";
@@ -42,12 +46,20 @@ impl Settings {
         &self.moby_runtime
     }
 
+    pub fn network_policy(&self) -> Option<&network::NetworkPolicy> {
+        self.network_policy.as_ref()
+    }
+
     #[must_use]
     pub fn agent_upstream_resolve(mut self, parent_hostname: &str) -> Self {
         crate::RuntimeSettings::agent_mut(&mut self)
             .config_mut()
             .parent_hostname_resolve(parent_hostname);
 
+        for image in self.base.agent.fallback_images_mut() {
+            config::upstream_resolve(image, parent_hostname);
+        }
+
         self
     }
 }
@@ -107,6 +119,14 @@ impl crate::RuntimeSettings for Settings {
         self.base.agent_mut()
     }
 
+    fn agent_fallback_images(&self) -> &[String] {
+        self.base.agent_fallback_images()
+    }
+
+    fn agent_cached_image_fallback(&self) -> bool {
+        self.base.agent_cached_image_fallback()
+    }
+
     fn connect(&self) -> &crate::uri::Connect {
         self.base.connect()
     }
@@ -130,6 +150,98 @@ impl crate::RuntimeSettings for Settings {
     fn image_garbage_collection(&self) -> &crate::base::image::ImagePruneSettings {
         self.base.image_garbage_collection()
     }
+
+    fn log_format(&self) -> crate::logging::LogFormat {
+        self.base.log_format()
+    }
+
+    fn api_access_log(&self) -> &crate::logging::AccessLogSettings {
+        self.base.api_access_log()
+    }
+
+    fn feature_flags(&self) -> &std::collections::BTreeMap<String, bool> {
+        self.base.feature_flags()
+    }
+
+    fn warm_standby_modules(&self) -> &[String] {
+        self.base.warm_standby_modules()
+    }
+
+    fn inject_host_time(&self) -> bool {
+        self.base.inject_host_time()
+    }
+
+    fn degraded_mode(&self) -> &crate::DegradedMode<Self::ModuleConfig> {
+        self.base.degraded_mode()
+    }
+
+    fn site_overlay(&self) -> Option<&crate::SiteOverlaySource> {
+        self.base.site_overlay()
+    }
+
+    fn log_sink(&self) -> Option<&crate::LogSink> {
+        self.base.log_sink()
+    }
+
+    fn log_persistence(&self) -> Option<&crate::LogPersistence> {
+        self.base.log_persistence()
+    }
+
+    fn proxy(&self) -> Option<&crate::Proxy> {
+        self.base.proxy()
+    }
+
+    fn device_mapping(&self) -> &crate::DeviceMapping {
+        self.base.device_mapping()
+    }
+
+    fn storage(&self) -> &crate::Storage {
+        self.base.storage()
+    }
+
+    fn template_variables(&self) -> &std::collections::BTreeMap<String, String> {
+        self.base.template_variables()
+    }
+
+    fn offline_start(&self) -> bool {
+        self.base.offline_start()
+    }
+
+    fn warm_restart(&self) -> bool {
+        self.base.warm_restart()
+    }
+
+    fn reprovision_check_interval(&self) -> Option<std::time::Duration> {
+        self.base.reprovision_check_interval()
+    }
+
+    fn alerts(&self) -> &[crate::alerts::Rule] {
+        self.base.alerts()
+    }
+
+    fn workload_rate_limit(&self) -> &crate::WorkloadRateLimit {
+        self.base.workload_rate_limit()
+    }
+
+    fn workload_server_certs(&self) -> &crate::WorkloadServerCerts {
+        self.base.workload_server_certs()
+    }
+
+    fn module_cert_renewal(&self) -> &crate::ModuleCertRenewal {
+        self.base.module_cert_renewal()
+    }
+
+    fn workload_sockets(&self) -> &std::collections::BTreeMap<String, crate::WorkloadSocket> {
+        self.base.workload_sockets()
+    }
+
+    fn cloud_notify(&self) -> &crate::CloudNotify {
+        self.base.cloud_notify()
+    }
+
+    fn tls_performance_mode(&self) -> crate::TlsPerformanceMode {
+        self.base.tls_performance_mode()
+    }
 }
 
 #[cfg(test)]
@@ -138,6 +250,7 @@ mod tests {
 
     use super::Settings;
     use crate::docker::network;
+    use crate::logging::LogFormat;
     use crate::RuntimeSettings;
     use crate::DEFAULT_NETWORKID;
 
@@ -157,6 +270,9 @@ mod tests {
     static GOOD_SETTINGS_CONTENT_TRUST: &str = "test-files/sample_settings_content_trust.toml";
     static GOOD_SETTINGS_NETWORK: &str = "test-files/sample_settings.network.toml";
     static GOOD_SETTINGS_IMAGE_GC: &str = "test-files/sample_settings_image_gc.toml";
+    static GOOD_SETTINGS_LOGGING: &str = "test-files/sample_settings_logging.toml";
+    static GOOD_SETTINGS_DEGRADED_MODE: &str = "test-files/sample_settings_degraded_mode.toml";
+    static GOOD_SETTINGS_AGENT_IMAGES: &str = "test-files/sample_settings_agent_images.toml";
 
     #[test]
     fn err_no_file() {
@@ -323,6 +439,165 @@ mod tests {
         assert_eq!(image_gc_settings.cleanup_time(), 0);
     }
 
+    #[test]
+    fn image_pull_defaults() {
+        let _env_lock = ENV_LOCK.lock().expect("env lock poisoned");
+
+        std::env::set_var("AZIOT_EDGED_CONFIG", GOOD_SETTINGS);
+        std::env::set_var("AZIOT_EDGED_CONFIG_DIR", CONFIG_DIR);
+
+        let settings = Settings::new().unwrap();
+        let image_pull = settings.moby_runtime().image_pull();
+        assert_eq!(image_pull.max_attempts(), 3);
+        assert!(image_pull.fallback_registries().is_empty());
+        assert!(image_pull.mirrors().is_empty());
+        assert_eq!(image_pull.mirror_cooldown(), Duration::from_secs(300));
+        assert_eq!(image_pull.mirror_check_interval(), Duration::from_secs(60));
+
+        // Backoff doubles up to the maximum.
+        assert_eq!(image_pull.backoff(1), Duration::from_secs(2));
+        assert_eq!(image_pull.backoff(2), Duration::from_secs(4));
+        assert_eq!(image_pull.backoff(6), Duration::from_secs(60));
+        assert_eq!(image_pull.backoff(100), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn log_format() {
+        let _env_lock = ENV_LOCK.lock().expect("env lock poisoned");
+
+        std::env::set_var("AZIOT_EDGED_CONFIG_DIR", CONFIG_DIR);
+
+        std::env::set_var("AZIOT_EDGED_CONFIG", GOOD_SETTINGS);
+        let settings = Settings::new().unwrap();
+        assert_eq!(settings.log_format(), LogFormat::Text);
+
+        std::env::set_var("AZIOT_EDGED_CONFIG", GOOD_SETTINGS_LOGGING);
+        let settings = Settings::new().unwrap();
+        assert_eq!(settings.log_format(), LogFormat::Json);
+    }
+
+    #[test]
+    fn degraded_mode() {
+        let _env_lock = ENV_LOCK.lock().expect("env lock poisoned");
+
+        std::env::set_var("AZIOT_EDGED_CONFIG_DIR", CONFIG_DIR);
+
+        std::env::set_var("AZIOT_EDGED_CONFIG", GOOD_SETTINGS);
+        let settings = Settings::new().unwrap();
+        assert!(settings.degraded_mode().is_empty());
+        assert_eq!(settings.degraded_mode().after(), Duration::from_secs(120));
+
+        std::env::set_var("AZIOT_EDGED_CONFIG", GOOD_SETTINGS_DEGRADED_MODE);
+        let settings = Settings::new().unwrap();
+        let degraded_mode = settings.degraded_mode();
+        assert_eq!(degraded_mode.after(), Duration::from_secs(30));
+        assert_eq!(degraded_mode.modules().len(), 1);
+        assert_eq!(degraded_mode.modules()[0].name(), "localui");
+        assert_eq!(degraded_mode.modules()[0].config().image(), "localui:1.0");
+    }
+
+    #[test]
+    fn agent_images() {
+        let _env_lock = ENV_LOCK.lock().expect("env lock poisoned");
+
+        std::env::set_var("AZIOT_EDGED_CONFIG_DIR", CONFIG_DIR);
+
+        std::env::set_var("AZIOT_EDGED_CONFIG", GOOD_SETTINGS);
+        let settings = Settings::new().unwrap();
+        assert!(settings.agent_fallback_images().is_empty());
+        assert!(settings.agent_cached_image_fallback());
+
+        std::env::set_var("AZIOT_EDGED_CONFIG", GOOD_SETTINGS_AGENT_IMAGES);
+        let settings = Settings::new()
+            .unwrap()
+            .agent_upstream_resolve("parent.local");
+        assert_eq!(
+            "contoso.azurecr.io/azureiotedge-agent:1.5",
+            settings.agent().config().image()
+        );
+        assert_eq!(
+            ["parent.local:443/azureiotedge-agent:1.5".to_string()],
+            settings.agent_fallback_images()
+        );
+        assert!(!settings.agent_cached_image_fallback());
+    }
+
+    #[test]
+    fn site_overlay() {
+        let _env_lock = ENV_LOCK.lock().expect("env lock poisoned");
+
+        std::env::set_var("AZIOT_EDGED_CONFIG", GOOD_SETTINGS);
+        std::env::set_var("AZIOT_EDGED_CONFIG_DIR", CONFIG_DIR);
+        let settings = Settings::new().unwrap();
+
+        let overlay: super::overlay::SiteOverlay = serde_json::from_str(
+            r#"{
+                "https_proxy": "http://proxy.contoso.com:3128",
+                "fallback_registries": ["parent:443"],
+                "mirrors": {
+                    "docker.io": ["parent:443"],
+                    "mcr.microsoft.com": ["parent:443"]
+                },
+                "image_garbage_collection": { "enabled": false }
+            }"#,
+        )
+        .unwrap();
+
+        let merged = settings.clone().with_site_overlay(&overlay);
+        assert_eq!(
+            Some("http://proxy.contoso.com:3128"),
+            merged
+                .base
+                .agent
+                .spec()
+                .env()
+                .get("https_proxy")
+                .map(String::as_str)
+        );
+        assert_eq!(
+            ["parent:443".to_string()],
+            merged.moby_runtime.image_pull.fallback_registries()
+        );
+        assert!(!merged.base.image_garbage_collection.is_enabled());
+
+        let mut settings = settings;
+        settings
+            .base
+            .agent
+            .spec_mut()
+            .env_mut()
+            .insert("https_proxy".to_string(), "http://local:3128".to_string());
+        settings.moby_runtime.image_pull.fallback_registries = vec!["local:5000".to_string()];
+        settings.moby_runtime.image_pull.mirrors.insert(
+            "mcr.microsoft.com".to_string(),
+            vec!["local:5000".to_string()],
+        );
+
+        let merged = settings.with_site_overlay(&overlay);
+        assert_eq!(
+            Some("http://local:3128"),
+            merged
+                .base
+                .agent
+                .spec()
+                .env()
+                .get("https_proxy")
+                .map(String::as_str)
+        );
+        assert_eq!(
+            ["local:5000".to_string()],
+            merged.moby_runtime.image_pull.fallback_registries()
+        );
+        assert_eq!(
+            ["local:5000".to_string()],
+            merged.moby_runtime.image_pull.mirrors()["mcr.microsoft.com"].as_slice()
+        );
+        assert_eq!(
+            ["parent:443".to_string()],
+            merged.moby_runtime.image_pull.mirrors()["docker.io"].as_slice()
+        );
+    }
+
     #[test]
     fn content_trust_env() {
         let _env_lock = ENV_LOCK.lock().expect("env lock poisoned");
@@ -20,8 +20,19 @@ impl MobyNetwork {
             MobyNetwork::Network(network) => &network.name,
         }
     }
+
+    /// DNS servers given to modules, in place of the engine's defaults.
+    pub fn dns(&self) -> &[String] {
+        match self {
+            MobyNetwork::Name(_) => &[],
+            MobyNetwork::Network(network) => &network.dns,
+        }
+    }
 }
 
+/// IPv6 subnet of the module network if IPv6 is enabled without an IPv6 IPAM subnet.
+pub const DEFAULT_IPV6_ULA_PREFIX: &str = "fd0e:d9e:a2e::/64";
+
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct Network {
     pub name: String,
@@ -31,6 +42,16 @@ pub struct Network {
 
     #[serde(rename = "ipam", skip_serializing_if = "Option::is_none")]
     pub ipam: Option<Ipam>,
+
+    /// Unique local IPv6 subnet for the network, used if IPv6 is enabled and `ipam`
+    /// has no IPv6 subnet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ula_prefix: Option<String>,
+
+    /// DNS servers for modules, e.g. IPv6 servers that can resolve the parent
+    /// hostname on IPv6-only networks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dns: Vec<String>,
 }
 
 impl Network {
@@ -39,6 +60,8 @@ impl Network {
             name,
             ipv6: None,
             ipam: None,
+            ula_prefix: None,
+            dns: Vec::new(),
         }
     }
 
@@ -71,6 +94,27 @@ impl Network {
         self.ipam = Some(ipam);
         self
     }
+
+    /// The IPv6 subnet of the network, if IPv6 is enabled.
+    pub fn ipv6_subnet(&self) -> Option<&str> {
+        if !self.ipv6.unwrap_or_default() {
+            return None;
+        }
+
+        let ipam_subnet = self
+            .ipam()
+            .and_then(Ipam::config)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(IpamConfig::subnet)
+            .find(|subnet| subnet.contains(':'));
+
+        Some(
+            ipam_subnet
+                .or(self.ula_prefix.as_deref())
+                .unwrap_or(DEFAULT_IPV6_ULA_PREFIX),
+        )
+    }
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
@@ -135,9 +179,62 @@ impl IpamConfig {
     }
 }
 
+/// Which modules may reach the internet. The others are attached only to an internal
+/// network, on which the container engine drops traffic to and from outside the device.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct NetworkPolicy {
+    /// Egress of modules that aren't listed in `modules`.
+    #[serde(default)]
+    pub default_egress: Egress,
+
+    /// Egress by module name.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub modules: std::collections::BTreeMap<String, Egress>,
+
+    /// Name of the internal network.
+    #[serde(default = "default_local_network")]
+    pub local_network: String,
+}
+
+/// Where a module's traffic may go.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Egress {
+    /// The module network, and through it the internet.
+    Internet,
+
+    /// Only the internal network, on which the module reaches Edge Hub and the other
+    /// modules attached to it.
+    #[default]
+    Local,
+}
+
+fn default_local_network() -> String {
+    crate::DEFAULT_LOCAL_NETWORKID.to_string()
+}
+
+impl NetworkPolicy {
+    pub fn local_network(&self) -> &str {
+        &self.local_network
+    }
+
+    /// Egress of a module. Edge Agent and Edge Hub always reach the internet, since they
+    /// connect to IoT Hub.
+    pub fn egress(&self, module: &str) -> Egress {
+        if module == "edgeAgent" || module == "edgeHub" {
+            return Egress::Internet;
+        }
+
+        self.modules
+            .get(module)
+            .copied()
+            .unwrap_or(self.default_egress)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Ipam, IpamConfig, MobyNetwork, Network};
+    use super::{Egress, Ipam, IpamConfig, MobyNetwork, Network, NetworkPolicy};
 
     #[test]
     fn ipam_config_with_values() {
@@ -188,4 +285,48 @@ mod tests {
         assert_eq!(moby_1, moby_network_with_name.name());
         assert_eq!(moby_2, moby_network_config.name());
     }
+
+    #[test]
+    fn ipv6_subnet() {
+        let network = Network::new("my-network".to_string());
+        assert_eq!(None, network.ipv6_subnet());
+
+        let network = network.with_ipv6(Some(true));
+        assert_eq!(Some(super::DEFAULT_IPV6_ULA_PREFIX), network.ipv6_subnet());
+
+        let network = Network {
+            ula_prefix: Some("fd12:3456:789a:1::/64".to_string()),
+            ..network
+        };
+        assert_eq!(Some("fd12:3456:789a:1::/64"), network.ipv6_subnet());
+
+        // An IPv6 subnet in IPAM takes precedence. IPv4 subnets are ignored.
+        let network = network.with_ipam(Ipam::default().with_config(vec![
+            IpamConfig::default().with_subnet("172.18.0.0/16".to_string()),
+            IpamConfig::default().with_subnet("fd00:1::/64".to_string()),
+        ]));
+        assert_eq!(Some("fd00:1::/64"), network.ipv6_subnet());
+    }
+
+    #[test]
+    fn network_policy_egress() {
+        let policy: NetworkPolicy = serde_json::from_value(serde_json::json!({
+            "modules": { "uploader": "internet", "edgeHub": "local" },
+        }))
+        .unwrap();
+
+        assert_eq!(crate::DEFAULT_LOCAL_NETWORKID, policy.local_network());
+        assert_eq!(Egress::Internet, policy.egress("uploader"));
+        assert_eq!(Egress::Local, policy.egress("sensor"));
+
+        // Edge Agent and Edge Hub can't be cut off from IoT Hub.
+        assert_eq!(Egress::Internet, policy.egress("edgeAgent"));
+        assert_eq!(Egress::Internet, policy.egress("edgeHub"));
+
+        let policy = NetworkPolicy {
+            default_egress: Egress::Internet,
+            ..policy
+        };
+        assert_eq!(Egress::Internet, policy.egress("sensor"));
+    }
 }
@@ -0,0 +1,63 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use crate::base::image::ImagePruneSettings;
+
+/// Site-wide settings that a parent gateway hands to its child devices, so that they
+/// are managed once at the gateway.
+///
+/// Settings in a device's own configuration take precedence over the overlay.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SiteOverlay {
+    /// Proxy for Edge Agent, set as its `https_proxy` environment variable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub https_proxy: Option<String>,
+
+    /// Registry mirrors that images are pulled from if pulls from their own registry fail.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_registries: Vec<String>,
+
+    /// Registry mirrors, by the registry they mirror.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub mirrors: std::collections::BTreeMap<String, Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_garbage_collection: Option<ImagePruneSettings>,
+}
+
+impl super::Settings {
+    /// Fill in the settings that aren't set in the device's configuration from a site
+    /// overlay.
+    #[must_use]
+    pub fn with_site_overlay(mut self, overlay: &SiteOverlay) -> Self {
+        if let Some(https_proxy) = &overlay.https_proxy {
+            self.base
+                .agent
+                .spec_mut()
+                .env_mut()
+                .entry("https_proxy".to_string())
+                .or_insert_with(|| https_proxy.clone());
+        }
+
+        let image_pull = &mut self.moby_runtime.image_pull;
+        if image_pull.fallback_registries.is_empty() {
+            image_pull.fallback_registries = overlay.fallback_registries.clone();
+        }
+
+        // Mirrors are merged by registry, so that a device can override the mirrors of
+        // one registry and keep the site's mirrors for others.
+        for (registry, mirrors) in &overlay.mirrors {
+            image_pull
+                .mirrors
+                .entry(registry.clone())
+                .or_insert_with(|| mirrors.clone());
+        }
+
+        if let Some(image_garbage_collection) = &overlay.image_garbage_collection {
+            if ImagePruneSettings::is_default(&self.base.image_garbage_collection) {
+                self.base.image_garbage_collection = image_garbage_collection.clone();
+            }
+        }
+
+        self
+    }
+}
@@ -5,8 +5,34 @@ pub struct MobyRuntime {
     pub uri: url::Url,
     pub network: crate::docker::network::MobyNetwork,
 
+    #[serde(default, skip_serializing_if = "ContainerEngine::is_default")]
+    pub engine: ContainerEngine,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content_trust: Option<ContentTrust>,
+
+    #[serde(default, skip_serializing_if = "ImagePullSettings::is_default")]
+    pub image_pull: ImagePullSettings,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_import_dir: Option<std::path::PathBuf>,
+
+    #[serde(default, skip_serializing_if = "ImageDigestSettings::is_default")]
+    pub image_digests: ImageDigestSettings,
+
+    #[serde(default, skip_serializing_if = "ImageSignatureSettings::is_default")]
+    pub image_signatures: ImageSignatureSettings,
+
+    #[serde(default, skip_serializing_if = "ModuleDefaults::is_default")]
+    pub module_defaults: ModuleDefaults,
+
+    #[serde(default, skip_serializing_if = "ModuleLimits::is_default")]
+    pub module_limits: ModuleLimits,
+
+    /// Hooks that modules are notified through before they're stopped, restarted or
+    /// removed, by module name.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub pre_stop: std::collections::BTreeMap<String, PreStopHook>,
 }
 
 impl MobyRuntime {
@@ -18,9 +44,65 @@ impl MobyRuntime {
         &self.network
     }
 
+    pub fn engine(&self) -> ContainerEngine {
+        self.engine
+    }
+
     pub fn content_trust(&self) -> Option<&ContentTrust> {
         self.content_trust.as_ref()
     }
+
+    pub fn image_pull(&self) -> &ImagePullSettings {
+        &self.image_pull
+    }
+
+    /// Directory of `docker save` tarballs to load images from when they can't be pulled.
+    pub fn image_import_dir(&self) -> Option<&std::path::Path> {
+        self.image_import_dir.as_deref()
+    }
+
+    pub fn image_digests(&self) -> &ImageDigestSettings {
+        &self.image_digests
+    }
+
+    pub fn image_signatures(&self) -> &ImageSignatureSettings {
+        &self.image_signatures
+    }
+
+    pub fn module_defaults(&self) -> &ModuleDefaults {
+        &self.module_defaults
+    }
+
+    pub fn module_limits(&self) -> &ModuleLimits {
+        &self.module_limits
+    }
+
+    pub fn pre_stop(&self) -> &std::collections::BTreeMap<String, PreStopHook> {
+        &self.pre_stop
+    }
+}
+
+/// The container engine that modules run on. Modules are managed through the Docker
+/// Engine API, or through an out-of-process runtime shim.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerEngine {
+    /// An engine that serves the Docker Engine API at `uri`.
+    #[default]
+    Docker,
+
+    /// Podman's Docker-compatible API, usually a rootless user socket.
+    Podman,
+
+    /// A runtime shim that serves the gRPC protocol of `edgelet-shim` at `uri`, and runs
+    /// modules however it likes.
+    Shim,
+}
+
+impl ContainerEngine {
+    pub fn is_default(&self) -> bool {
+        self == &ContainerEngine::default()
+    }
 }
 
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
@@ -34,3 +116,370 @@ impl ContentTrust {
         self.ca_certs.as_ref()
     }
 }
+
+/// Container options given to modules whose create options don't set them.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ModuleDefaults {
+    /// Memory soft limit of each module, in MB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_reservation_mb: Option<u64>,
+
+    /// Size at which a module's log file is rotated, in MB. Modules are given the
+    /// json-file log driver.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_max_size_mb: Option<u64>,
+
+    /// Number of rotated log files to keep. Only used with `log_max_size_mb`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_max_files: Option<u32>,
+}
+
+impl ModuleDefaults {
+    pub fn is_default(&self) -> bool {
+        self == &ModuleDefaults::default()
+    }
+}
+
+/// Resource limits that modules are created with.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ModuleLimits {
+    /// Reject modules whose create options set limits above the configured ones, instead
+    /// of only filling in the limits that create options don't set.
+    #[serde(default)]
+    pub enforce: bool,
+
+    /// Limits of every module.
+    #[serde(default, skip_serializing_if = "ResourceLimits::is_default")]
+    pub defaults: ResourceLimits,
+
+    /// Limits by module name, overriding the defaults.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub modules: std::collections::BTreeMap<String, ResourceLimits>,
+}
+
+impl ModuleLimits {
+    pub fn is_default(&self) -> bool {
+        self == &ModuleLimits::default()
+    }
+
+    pub fn enforce(&self) -> bool {
+        self.enforce
+    }
+
+    /// The limits of a module: its own where set, and the defaults otherwise.
+    pub fn for_module(&self, module: &str) -> ResourceLimits {
+        match self.modules.get(module) {
+            Some(limits) => ResourceLimits {
+                memory_mb: limits.memory_mb.or(self.defaults.memory_mb),
+                cpus: limits.cpus.or(self.defaults.cpus),
+                pids: limits.pids.or(self.defaults.pids),
+            },
+            None => self.defaults.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ResourceLimits {
+    /// Memory limit, in MB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_mb: Option<u64>,
+
+    /// Number of CPUs, such as 0.5.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<f64>,
+
+    /// Maximum number of processes and threads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn is_default(&self) -> bool {
+        self == &ResourceLimits::default()
+    }
+}
+
+/// Notification of a module that it's about to be stopped, restarted or removed, so that
+/// it can flush what it buffered. The module is stopped once it acknowledges, or once the grace
+/// period has passed.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PreStopHook {
+    /// Endpoint that is sent a POST. The module acknowledges with a success status. A host
+    /// that is the module's name is resolved to the module's address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<url::Url>,
+
+    /// Signal, such as "SIGUSR1", that is sent to the module. The module acknowledges by
+    /// exiting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<String>,
+
+    #[serde(default = "default_grace_period", with = "humantime_serde")]
+    pub grace_period: std::time::Duration,
+}
+
+impl PreStopHook {
+    pub fn http(&self) -> Option<&url::Url> {
+        self.http.as_ref()
+    }
+
+    pub fn signal(&self) -> Option<&str> {
+        self.signal.as_deref()
+    }
+
+    pub fn grace_period(&self) -> std::time::Duration {
+        self.grace_period
+    }
+}
+
+fn default_grace_period() -> std::time::Duration {
+    std::time::Duration::from_secs(30)
+}
+
+/// Retry policy of module image pulls.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ImagePullSettings {
+    /// Attempts per registry, including the first one.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+
+    /// Wait before the first retry. It doubles with every retry up to `max_backoff`.
+    #[serde(default = "default_initial_backoff", with = "humantime_serde")]
+    pub initial_backoff: std::time::Duration,
+
+    #[serde(default = "default_max_backoff", with = "humantime_serde")]
+    pub max_backoff: std::time::Duration,
+
+    /// Pulls of large images over slow links can take a while, so this should be generous.
+    #[serde(default = "default_attempt_timeout", with = "humantime_serde")]
+    pub attempt_timeout: std::time::Duration,
+
+    /// Registries that images are pulled from, in order, if pulls from the image's own
+    /// registry fail. The image's repository and tag are kept.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_registries: Vec<String>,
+
+    /// Mirrors of registries, by registry host such as "mcr.microsoft.com" or
+    /// "docker.io". Images are pulled from a registry's mirrors, healthy ones first,
+    /// before the registry itself.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub mirrors: std::collections::BTreeMap<String, Vec<String>>,
+
+    /// How long a mirror that failed a pull is tried after the other mirrors.
+    #[serde(default = "default_mirror_cooldown", with = "humantime_serde")]
+    pub mirror_cooldown: std::time::Duration,
+
+    /// How often mirrors are checked, so that a mirror that is down is found before a
+    /// pull is tried from it, and one that recovered is used again before its cooldown
+    /// passes. Mirrors are only checked by pulls if zero.
+    #[serde(default = "default_mirror_check_interval", with = "humantime_serde")]
+    pub mirror_check_interval: std::time::Duration,
+
+    /// Credentials of registries, by registry host, that are tried when a pull with the
+    /// credentials of the module's deployment is denied. A registry can have several,
+    /// such as the old and new credential while one is rotated.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub credentials: std::collections::BTreeMap<String, Vec<RegistryCredential>>,
+
+    /// Pulls that run at once. Further pulls wait for one of them to finish. Any number of
+    /// pulls run at once if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_pulls: Option<usize>,
+}
+
+/// A registry credential whose password is kept in a file. The file is read at every
+/// pull, so the password can be rotated without restarting the daemon.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RegistryCredential {
+    pub username: String,
+    pub password_file: std::path::PathBuf,
+}
+
+impl ImagePullSettings {
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts.max(1)
+    }
+
+    /// Wait before the given retry, starting at 1.
+    pub fn backoff(&self, retry: u32) -> std::time::Duration {
+        let factor = 2_u32.saturating_pow(retry.saturating_sub(1));
+
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+
+    pub fn attempt_timeout(&self) -> std::time::Duration {
+        self.attempt_timeout
+    }
+
+    pub fn fallback_registries(&self) -> &[String] {
+        &self.fallback_registries
+    }
+
+    pub fn mirrors(&self) -> &std::collections::BTreeMap<String, Vec<String>> {
+        &self.mirrors
+    }
+
+    pub fn mirror_cooldown(&self) -> std::time::Duration {
+        self.mirror_cooldown
+    }
+
+    pub fn mirror_check_interval(&self) -> std::time::Duration {
+        self.mirror_check_interval
+    }
+
+    pub fn credentials(&self) -> &std::collections::BTreeMap<String, Vec<RegistryCredential>> {
+        &self.credentials
+    }
+
+    pub fn max_concurrent_pulls(&self) -> Option<usize> {
+        self.max_concurrent_pulls.map(|max| max.max(1))
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &ImagePullSettings::default()
+    }
+}
+
+impl Default for ImagePullSettings {
+    fn default() -> Self {
+        ImagePullSettings {
+            max_attempts: default_max_attempts(),
+            initial_backoff: default_initial_backoff(),
+            max_backoff: default_max_backoff(),
+            attempt_timeout: default_attempt_timeout(),
+            fallback_registries: Vec::new(),
+            mirrors: std::collections::BTreeMap::new(),
+            mirror_cooldown: default_mirror_cooldown(),
+            mirror_check_interval: default_mirror_check_interval(),
+            credentials: std::collections::BTreeMap::new(),
+            max_concurrent_pulls: None,
+        }
+    }
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_initial_backoff() -> std::time::Duration {
+    std::time::Duration::from_secs(2)
+}
+
+fn default_max_backoff() -> std::time::Duration {
+    std::time::Duration::from_secs(60)
+}
+
+// 10 minutes
+fn default_attempt_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(60 * 10)
+}
+
+// 5 minutes
+fn default_mirror_cooldown() -> std::time::Duration {
+    std::time::Duration::from_secs(60 * 5)
+}
+
+// 1 minute
+fn default_mirror_check_interval() -> std::time::Duration {
+    std::time::Duration::from_secs(60)
+}
+
+/// Pinning of module images to digests of their content.
+///
+/// Images are pinned by referencing them by digest, with the
+/// `net.azure-devices.edge.image-digest` label in their create options, or through
+/// `allowlist`. Pinned images are verified before modules are created from them.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ImageDigestSettings {
+    /// Refuse to create modules with images that aren't pinned.
+    #[serde(default)]
+    pub require_pinned: bool,
+
+    /// Digests that images are pinned to, such as "sha256:...", by image name.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub allowlist: std::collections::BTreeMap<String, String>,
+}
+
+impl ImageDigestSettings {
+    pub fn require_pinned(&self) -> bool {
+        self.require_pinned
+    }
+
+    pub fn allowlist(&self) -> &std::collections::BTreeMap<String, String> {
+        &self.allowlist
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &ImageDigestSettings::default()
+    }
+}
+
+/// Verification of cosign and Notation signatures of module images, configured per registry.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ImageSignatureSettings {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policies: Vec<SignaturePolicy>,
+}
+
+impl ImageSignatureSettings {
+    /// Policy for images from a registry. The policy for "*" applies to registries
+    /// without a policy of their own.
+    pub fn policy(&self, registry: &str) -> Option<&SignaturePolicy> {
+        self.policies
+            .iter()
+            .find(|policy| policy.registry.eq_ignore_ascii_case(registry))
+            .or_else(|| self.policies.iter().find(|policy| policy.registry == "*"))
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &ImageSignatureSettings::default()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SignaturePolicy {
+    /// Registry host, such as "example.azurecr.io", or "*".
+    pub registry: String,
+
+    #[serde(default)]
+    pub mode: SignatureMode,
+
+    /// PEM files of the public keys that cosign signatures are trusted from.
+    #[serde(default)]
+    pub public_keys: Vec<std::path::PathBuf>,
+
+    /// PEM files of the CA certificates that the signing certificates of Notation
+    /// signatures must chain to.
+    #[serde(default)]
+    pub trust_roots: Vec<std::path::PathBuf>,
+}
+
+impl SignaturePolicy {
+    pub fn mode(&self) -> SignatureMode {
+        self.mode
+    }
+
+    pub fn public_keys(&self) -> &[std::path::PathBuf] {
+        &self.public_keys
+    }
+
+    pub fn trust_roots(&self) -> &[std::path::PathBuf] {
+        &self.trust_roots
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureMode {
+    /// Modules with unsigned images or invalid signatures are refused.
+    #[default]
+    Enforce,
+
+    /// Failed verifications are only logged.
+    Warn,
+
+    Off,
+}
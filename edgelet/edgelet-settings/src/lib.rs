@@ -12,16 +12,25 @@
 pub mod base;
 
 pub use base::module::Settings as ModuleSpec;
-pub use base::{aziot, module, uri, watchdog};
-pub use base::{IotedgeMaxRequests, RuntimeSettings};
+pub use base::{alerts, aziot, logging, module, uri, watchdog};
+pub use base::{
+    CloudNotify, DegradedMode, DeviceMapping, IotedgeMaxRequests, LogPersistence, LogSink,
+    ModuleCertRenewal, Proxy, RuntimeSettings, SiteOverlaySource, Storage, TlsPerformanceMode,
+    WorkloadRateLimit, WorkloadServerCerts, WorkloadSocket,
+};
 
 #[cfg(feature = "settings-docker")]
 pub mod docker;
 #[cfg(feature = "settings-docker")]
 pub use crate::docker::{
     config::{DockerConfig, UPSTREAM_PARENT_KEYWORD},
-    network::{Ipam, MobyNetwork},
-    runtime::{ContentTrust, MobyRuntime},
+    network::{Egress, Ipam, MobyNetwork, NetworkPolicy},
+    overlay::SiteOverlay,
+    runtime::{
+        ContainerEngine, ContentTrust, ImageDigestSettings, ImagePullSettings,
+        ImageSignatureSettings, MobyRuntime, ModuleDefaults, ModuleLimits, PreStopHook,
+        ResourceLimits, SignatureMode, SignaturePolicy,
+    },
     Settings, CONFIG_FILE_DEFAULT,
 };
 
@@ -36,3 +45,6 @@ pub const MANIFEST_TRUST_BUNDLE_ALIAS: &str = "aziot-edged-manifest-trust-bundle
 
 /// This is the name of the network created by the aziot-edged
 pub const DEFAULT_NETWORKID: &str = "azure-iot-edge";
+
+/// Name of the internal network of modules that may not reach the internet.
+pub const DEFAULT_LOCAL_NETWORKID: &str = "azure-iot-edge-local";
@@ -0,0 +1,45 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::{RegistryOperation, RuntimeOperation};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("runtime shim error")]
+    Shim,
+
+    #[error("invalid runtime shim URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("registry operation error: {0}")]
+    RegistryOperation(RegistryOperation),
+
+    #[error("runtime operation error: {0}")]
+    RuntimeOperation(RuntimeOperation),
+}
+
+/// An error status returned by the shim, with its gRPC code mapped to the HTTP status
+/// that management API callers see.
+#[derive(Debug, thiserror::Error)]
+#[error("HTTP {code}: {message}")]
+pub struct ApiError {
+    pub code: hyper::StatusCode,
+    pub message: String,
+}
+
+impl From<tonic::Status> for ApiError {
+    fn from(status: tonic::Status) -> Self {
+        let code = match status.code() {
+            tonic::Code::NotFound => hyper::StatusCode::NOT_FOUND,
+            tonic::Code::AlreadyExists => hyper::StatusCode::CONFLICT,
+            tonic::Code::FailedPrecondition => hyper::StatusCode::NOT_MODIFIED,
+            tonic::Code::InvalidArgument => hyper::StatusCode::BAD_REQUEST,
+            tonic::Code::Unavailable => hyper::StatusCode::SERVICE_UNAVAILABLE,
+            _ => hyper::StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        ApiError {
+            code,
+            message: status.message().to_owned(),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! A `ModuleRuntime` that delegates to an out-of-process runtime shim.
+//!
+//! The shim serves the gRPC service in `proto/runtime_shim.proto` on a Unix socket.
+//! This lets integrators run modules as something other than containers, such as
+//! systemd services or WASM workloads, without changing edgelet.
+
+#![deny(rust_2018_idioms, warnings)]
+#![deny(clippy::all, clippy::pedantic)]
+#![allow(
+    clippy::missing_errors_doc,
+    clippy::module_name_repetitions,
+    clippy::must_use_candidate
+)]
+
+mod error;
+mod module;
+mod proto;
+mod runtime;
+
+pub use error::{ApiError, Error};
+pub use module::ShimModule;
+pub use runtime::ShimModuleRuntime;
@@ -0,0 +1,132 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::{Module, ModuleRuntimeState, ModuleStatus};
+
+use crate::proto;
+
+/// A module as reported by the shim, with the runtime state it had when it was listed.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ShimModule {
+    name: String,
+
+    r#type: String,
+
+    /// The module's `settings` from the deployment, which only the shim interprets.
+    config: serde_json::Value,
+
+    state: ModuleRuntimeState,
+}
+
+impl ShimModule {
+    pub(crate) fn state(&self) -> &ModuleRuntimeState {
+        &self.state
+    }
+}
+
+impl TryFrom<proto::Module> for ShimModule {
+    type Error = serde_json::Error;
+
+    fn try_from(module: proto::Module) -> Result<Self, Self::Error> {
+        let config = if module.config.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::from_str(&module.config)?
+        };
+
+        Ok(ShimModule {
+            name: module.name,
+            r#type: module.r#type,
+            config,
+            state: module.state.map(runtime_state).unwrap_or_default(),
+        })
+    }
+}
+
+fn runtime_state(state: proto::ModuleState) -> ModuleRuntimeState {
+    let status = match proto::ModuleStatus::try_from(state.status) {
+        Ok(proto::ModuleStatus::Running) => ModuleStatus::Running,
+        Ok(proto::ModuleStatus::Stopped) => ModuleStatus::Stopped,
+        Ok(proto::ModuleStatus::Failed) => ModuleStatus::Failed,
+        Ok(proto::ModuleStatus::Dead) => ModuleStatus::Dead,
+        Ok(proto::ModuleStatus::Unknown) | Err(_) => ModuleStatus::Unknown,
+    };
+
+    ModuleRuntimeState::default()
+        .with_status(status)
+        .with_exit_code(state.exit_code)
+        .with_started_at(state.started_at.and_then(timestamp))
+        .with_finished_at(state.finished_at.and_then(timestamp))
+        .with_image_id(state.image_id)
+        .with_pid(state.pid)
+        .with_description(state.description)
+}
+
+fn timestamp(timestamp: prost_types::Timestamp) -> Option<chrono::DateTime<chrono::Utc>> {
+    let nanos = u32::try_from(timestamp.nanos).ok()?;
+
+    chrono::NaiveDateTime::from_timestamp_opt(timestamp.seconds, nanos)
+        .map(|time| chrono::DateTime::<chrono::Utc>::from_utc(time, chrono::Utc))
+}
+
+#[async_trait::async_trait]
+impl Module for ShimModule {
+    type Config = serde_json::Value;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn type_(&self) -> &str {
+        &self.r#type
+    }
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    async fn runtime_state(&self) -> anyhow::Result<ModuleRuntimeState> {
+        Ok(self.state.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_core::{Module, ModuleStatus};
+
+    use super::ShimModule;
+    use crate::proto;
+
+    #[test]
+    fn from_proto() {
+        let module = proto::Module {
+            name: "sensor".to_owned(),
+            r#type: "wasm".to_owned(),
+            config: r#"{"image":"sensor.wasm"}"#.to_owned(),
+            state: Some(proto::ModuleState {
+                status: proto::ModuleStatus::Running as i32,
+                started_at: Some(prost_types::Timestamp {
+                    seconds: 1_700_000_000,
+                    nanos: 0,
+                }),
+                pid: Some(42),
+                ..Default::default()
+            }),
+        };
+
+        let module = ShimModule::try_from(module).unwrap();
+        assert_eq!("sensor", module.name());
+        assert_eq!("sensor.wasm", module.config()["image"]);
+        assert_eq!(&ModuleStatus::Running, module.state().status());
+        assert_eq!(Some(42), module.state().pid());
+        assert_eq!(
+            1_700_000_000,
+            module.state().started_at().unwrap().timestamp()
+        );
+
+        let module = proto::Module {
+            config: "not json".to_owned(),
+            ..Default::default()
+        };
+        ShimModule::try_from(module).unwrap_err();
+    }
+}
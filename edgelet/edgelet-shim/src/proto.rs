@@ -0,0 +1,206 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Messages and client of the `iotedge.runtimeshim.v1.RuntimeShim` service in
+//! `proto/runtime_shim.proto`.
+//!
+//! These are written out rather than generated by a build script so that building
+//! edgelet doesn't need `protoc`. Keep them in sync with the proto file.
+
+use std::collections::HashMap;
+
+use tonic::codegen::http::uri::PathAndQuery;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Empty {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct JsonDocument {
+    #[prost(string, tag = "1")]
+    pub json: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ImageRequest {
+    #[prost(string, tag = "1")]
+    pub image: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PullImageRequest {
+    #[prost(string, tag = "1")]
+    pub config: String,
+    #[prost(string, tag = "2")]
+    pub module: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListImagesResponse {
+    #[prost(map = "string, string", tag = "1")]
+    pub images: HashMap<String, String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CreateModuleRequest {
+    #[prost(string, tag = "1")]
+    pub module: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ModuleRequest {
+    #[prost(string, tag = "1")]
+    pub name: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StopModuleRequest {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(uint64, optional, tag = "2")]
+    pub timeout: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ModuleLogsRequest {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(bool, tag = "2")]
+    pub follow: bool,
+    #[prost(uint64, optional, tag = "3")]
+    pub tail: Option<u64>,
+    #[prost(bool, tag = "4")]
+    pub timestamps: bool,
+    #[prost(int32, tag = "5")]
+    pub since: i32,
+    #[prost(int32, optional, tag = "6")]
+    pub until: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct LogChunk {
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TopModuleResponse {
+    #[prost(int32, repeated, tag = "1")]
+    pub pids: Vec<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PurgeModuleDataResponse {
+    #[prost(string, repeated, tag = "1")]
+    pub removed: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListModulesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub modules: Vec<Module>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Module {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub r#type: String,
+    #[prost(string, tag = "3")]
+    pub config: String,
+    #[prost(message, optional, tag = "4")]
+    pub state: Option<ModuleState>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum ModuleStatus {
+    Unknown = 0,
+    Running = 1,
+    Stopped = 2,
+    Failed = 3,
+    Dead = 4,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ModuleState {
+    #[prost(enumeration = "ModuleStatus", tag = "1")]
+    pub status: i32,
+    #[prost(int64, optional, tag = "2")]
+    pub exit_code: Option<i64>,
+    #[prost(message, optional, tag = "3")]
+    pub started_at: Option<prost_types::Timestamp>,
+    #[prost(message, optional, tag = "4")]
+    pub finished_at: Option<prost_types::Timestamp>,
+    #[prost(string, optional, tag = "5")]
+    pub image_id: Option<String>,
+    #[prost(int32, optional, tag = "6")]
+    pub pid: Option<i32>,
+    #[prost(string, optional, tag = "7")]
+    pub description: Option<String>,
+}
+
+/// Client of the `RuntimeShim` service.
+#[derive(Clone)]
+pub struct RuntimeShimClient {
+    channel: tonic::transport::Channel,
+}
+
+impl RuntimeShimClient {
+    pub fn new(channel: tonic::transport::Channel) -> Self {
+        RuntimeShimClient { channel }
+    }
+
+    async fn ready(&self) -> Result<tonic::client::Grpc<tonic::transport::Channel>, tonic::Status> {
+        let mut client = tonic::client::Grpc::new(self.channel.clone());
+        client
+            .ready()
+            .await
+            .map_err(|err| tonic::Status::unavailable(format!("runtime shim not ready: {err}")))?;
+
+        Ok(client)
+    }
+
+    /// Calls the unary method at `path`, e.g. `/iotedge.runtimeshim.v1.RuntimeShim/GetModule`.
+    pub async fn unary<Req, Resp>(
+        &self,
+        path: &'static str,
+        request: Req,
+    ) -> Result<Resp, tonic::Status>
+    where
+        Req: prost::Message + 'static,
+        Resp: prost::Message + Default + 'static,
+    {
+        let mut client = self.ready().await?;
+        let response = client
+            .unary(
+                tonic::Request::new(request),
+                PathAndQuery::from_static(path),
+                tonic::codec::ProstCodec::default(),
+            )
+            .await?;
+
+        Ok(response.into_inner())
+    }
+
+    /// Calls the server-streaming method at `path`.
+    pub async fn server_streaming<Req, Resp>(
+        &self,
+        path: &'static str,
+        request: Req,
+    ) -> Result<tonic::Streaming<Resp>, tonic::Status>
+    where
+        Req: prost::Message + 'static,
+        Resp: prost::Message + Default + 'static,
+    {
+        let mut client = self.ready().await?;
+        let response = client
+            .server_streaming(
+                tonic::Request::new(request),
+                PathAndQuery::from_static(path),
+                tonic::codec::ProstCodec::default(),
+            )
+            .await?;
+
+        Ok(response.into_inner())
+    }
+}
@@ -0,0 +1,429 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Context;
+use futures_util::TryStreamExt;
+
+use edgelet_core::{
+    DiskSpaceStatus, LogOptions, LogTail, Module, ModuleRegistry, ModuleRuntime,
+    ModuleRuntimeState, RegistryCredential, RegistryMirror, RegistryOperation, RuntimeOperation,
+    SbomLookup, SystemInfo, SystemResources, UrlExt,
+};
+use edgelet_settings::module::Settings as ModuleSpec;
+
+use crate::error::{ApiError, Error};
+use crate::module::ShimModule;
+use crate::proto;
+
+/// Path of a method of the `RuntimeShim` service.
+macro_rules! method {
+    ($name:literal) => {
+        concat!("/iotedge.runtimeshim.v1.RuntimeShim/", $name)
+    };
+}
+
+#[derive(Clone)]
+pub struct ShimModuleRuntime {
+    client: proto::RuntimeShimClient,
+}
+
+impl ShimModuleRuntime {
+    /// Creates a runtime that talks to the shim listening on the Unix socket at `url`.
+    ///
+    /// The shim is connected to on first use and reconnected to if it restarts, so it
+    /// doesn't need to be running yet.
+    pub fn new(url: &url::Url) -> anyhow::Result<Self> {
+        if url.scheme() != edgelet_core::UNIX_SCHEME {
+            return Err(Error::InvalidUrl(url.to_string()).into());
+        }
+
+        let path = url
+            .to_base_path()
+            .map_err(|err| Error::InvalidUrl(err.to_string()))?;
+
+        // The URI is only used for the HTTP/2 authority; the connector ignores it.
+        let channel = tonic::transport::Endpoint::from_static("http://runtime-shim")
+            .connect_with_connector_lazy(tower::service_fn(move |_: tonic::transport::Uri| {
+                tokio::net::UnixStream::connect(path.clone())
+            }));
+
+        Ok(ShimModuleRuntime {
+            client: proto::RuntimeShimClient::new(channel),
+        })
+    }
+
+    async fn call<Req, Resp>(&self, method: &'static str, request: Req) -> anyhow::Result<Resp>
+    where
+        Req: prost::Message + 'static,
+        Resp: prost::Message + Default + 'static,
+    {
+        self.client
+            .unary(method, request)
+            .await
+            .map_err(|status| ApiError::from(status).into())
+    }
+
+    async fn call_json<Req, T>(&self, method: &'static str, request: Req) -> anyhow::Result<T>
+    where
+        Req: prost::Message + 'static,
+        T: serde::de::DeserializeOwned,
+    {
+        let document: proto::JsonDocument = self.call(method, request).await?;
+
+        serde_json::from_str(&document.json).context(Error::Shim)
+    }
+}
+
+fn module_request(id: &str) -> proto::ModuleRequest {
+    proto::ModuleRequest {
+        name: id.to_owned(),
+    }
+}
+
+#[async_trait::async_trait]
+impl ModuleRegistry for ShimModuleRuntime {
+    type Config = serde_json::Value;
+
+    async fn pull(&self, config: &Self::Config) -> anyhow::Result<()> {
+        self.pull_module("", config).await
+    }
+
+    async fn pull_module(&self, module: &str, config: &Self::Config) -> anyhow::Result<()> {
+        let image = config
+            .get("image")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default()
+            .to_owned();
+
+        let request = proto::PullImageRequest {
+            config: serde_json::to_string(config).context(Error::Shim)?,
+            module: module.to_owned(),
+        };
+
+        self.call::<_, proto::Empty>(method!("PullImage"), request)
+            .await
+            .with_context(|| Error::RegistryOperation(RegistryOperation::PullImage(image)))?;
+
+        Ok(())
+    }
+
+    async fn remove(&self, name: &str) -> anyhow::Result<()> {
+        let request = proto::ImageRequest {
+            image: name.to_owned(),
+        };
+
+        self.call::<_, proto::Empty>(method!("RemoveImage"), request)
+            .await
+            .with_context(|| {
+                Error::RegistryOperation(RegistryOperation::RemoveImage(name.to_owned()))
+            })?;
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl ModuleRuntime for ShimModuleRuntime {
+    type Config = serde_json::Value;
+    type Module = ShimModule;
+    type ModuleRegistry = Self;
+
+    async fn create(&self, module: ModuleSpec<Self::Config>) -> anyhow::Result<()> {
+        let request = proto::CreateModuleRequest {
+            module: serde_json::to_string(&module).context(Error::Shim)?,
+        };
+
+        self.call::<_, proto::Empty>(method!("CreateModule"), request)
+            .await
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::CreateModule(module.name().to_owned()))
+            })?;
+
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<(Self::Module, ModuleRuntimeState)> {
+        let module: proto::Module = self
+            .call(method!("GetModule"), module_request(id))
+            .await
+            .with_context(|| Error::RuntimeOperation(RuntimeOperation::GetModule(id.to_owned())))?;
+        let module = ShimModule::try_from(module)
+            .context(Error::Shim)
+            .with_context(|| Error::RuntimeOperation(RuntimeOperation::GetModule(id.to_owned())))?;
+        let state = module.state().clone();
+
+        Ok((module, state))
+    }
+
+    async fn start(&self, id: &str) -> anyhow::Result<()> {
+        self.call::<_, proto::Empty>(method!("StartModule"), module_request(id))
+            .await
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::StartModule(id.to_owned()))
+            })?;
+
+        Ok(())
+    }
+
+    async fn stop(&self, id: &str, wait_before_kill: Option<Duration>) -> anyhow::Result<()> {
+        let request = proto::StopModuleRequest {
+            name: id.to_owned(),
+            timeout: wait_before_kill.map(|timeout| timeout.as_secs()),
+        };
+
+        self.call::<_, proto::Empty>(method!("StopModule"), request)
+            .await
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::StopModule(id.to_owned()))
+            })?;
+
+        Ok(())
+    }
+
+    async fn restart(&self, id: &str) -> anyhow::Result<()> {
+        self.call::<_, proto::Empty>(method!("RestartModule"), module_request(id))
+            .await
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::RestartModule(id.to_owned()))
+            })?;
+
+        Ok(())
+    }
+
+    async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        self.call::<_, proto::Empty>(method!("RemoveModule"), module_request(id))
+            .await
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::RemoveModule(id.to_owned()))
+            })?;
+
+        Ok(())
+    }
+
+    async fn system_info(&self) -> anyhow::Result<SystemInfo> {
+        self.call_json(method!("GetSystemInfo"), proto::Empty {})
+            .await
+            .context(Error::RuntimeOperation(RuntimeOperation::SystemInfo))
+    }
+
+    async fn system_resources(&self) -> anyhow::Result<SystemResources> {
+        self.call_json(method!("GetSystemResources"), proto::Empty {})
+            .await
+            .context(Error::RuntimeOperation(RuntimeOperation::SystemResources))
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<Self::Module>> {
+        let response: proto::ListModulesResponse = self
+            .call(method!("ListModules"), proto::Empty {})
+            .await
+            .context(Error::RuntimeOperation(RuntimeOperation::ListModules))?;
+
+        response
+            .modules
+            .into_iter()
+            .map(ShimModule::try_from)
+            .collect::<Result<_, _>>()
+            .context(Error::Shim)
+            .context(Error::RuntimeOperation(RuntimeOperation::ListModules))
+    }
+
+    async fn list_with_details(&self) -> anyhow::Result<Vec<(Self::Module, ModuleRuntimeState)>> {
+        let modules = self
+            .list()
+            .await?
+            .into_iter()
+            .map(|module| {
+                let state = module.state().clone();
+
+                (module, state)
+            })
+            .collect();
+
+        Ok(modules)
+    }
+
+    async fn list_images(&self) -> anyhow::Result<HashMap<String, String>> {
+        let response: proto::ListImagesResponse = self
+            .call(method!("ListImages"), proto::Empty {})
+            .await
+            .context(Error::RuntimeOperation(RuntimeOperation::ListImages))?;
+
+        Ok(response.images)
+    }
+
+    async fn logs(&self, id: &str, options: &LogOptions) -> anyhow::Result<hyper::Body> {
+        let request = proto::ModuleLogsRequest {
+            name: id.to_owned(),
+            follow: options.follow(),
+            tail: match options.tail() {
+                LogTail::All => None,
+                LogTail::Num(tail) => Some(*tail),
+            },
+            timestamps: options.timestamps(),
+            since: options.since(),
+            until: options.until(),
+        };
+
+        let chunks = self
+            .client
+            .server_streaming::<_, proto::LogChunk>(method!("ModuleLogs"), request)
+            .await
+            .map_err(ApiError::from)
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::GetModuleLogs(id.to_owned()))
+            })?;
+
+        Ok(hyper::Body::wrap_stream(chunks.map_ok(|chunk| chunk.data)))
+    }
+
+    async fn module_sbom(&self, id: &str) -> anyhow::Result<SbomLookup> {
+        // The shim exports and scans the image itself, so the call is made by the caller.
+        let runtime = self.clone();
+        let id = id.to_owned();
+
+        Ok(Box::pin(async move {
+            match runtime
+                .call_json(method!("GetModuleSbom"), module_request(&id))
+                .await
+            {
+                Ok(sbom) => Ok(Some(sbom)),
+                Err(err) if Self::error_code(&err) == hyper::StatusCode::NOT_FOUND => Ok(None),
+                Err(err) => {
+                    Err(err.context(Error::RuntimeOperation(RuntimeOperation::GetModuleSbom(id))))
+                }
+            }
+        }))
+    }
+
+    async fn registry_mirrors(&self) -> anyhow::Result<Vec<RegistryMirror>> {
+        // The shim pulls its own images, so there are no mirrors to report.
+        Ok(Vec::new())
+    }
+
+    async fn registry_credentials(&self) -> anyhow::Result<Vec<RegistryCredential>> {
+        // The shim pulls its own images, so it caches no credentials.
+        Ok(Vec::new())
+    }
+
+    async fn invalidate_registry_credentials(&self, _registry: Option<&str>) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn disk_space(&self) -> anyhow::Result<Vec<DiskSpaceStatus>> {
+        // The shim's backend manages its own storage.
+        Ok(Vec::new())
+    }
+
+    async fn purge_data(&self, id: &str) -> anyhow::Result<Vec<String>> {
+        let response: proto::PurgeModuleDataResponse = self
+            .call(method!("PurgeModuleData"), module_request(id))
+            .await
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::PurgeModuleData(id.to_owned()))
+            })?;
+
+        Ok(response.removed)
+    }
+
+    async fn remove_all(&self) -> anyhow::Result<()> {
+        for module in self.list().await? {
+            ModuleRuntime::remove(self, module.name()).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn stop_all(&self, wait_before_kill: Option<Duration>) -> anyhow::Result<()> {
+        for module in self.list().await? {
+            let name = module.name();
+
+            // A module that is already stopped can't be stopped again, but that
+            // shouldn't prevent the others from being stopped.
+            if let Err(err) = self.stop(name, wait_before_kill).await {
+                log::warn!("Failed to stop module {}: {:?}", name, err);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn module_top(&self, id: &str) -> anyhow::Result<Vec<i32>> {
+        let response: proto::TopModuleResponse = self
+            .call(method!("TopModule"), module_request(id))
+            .await
+            .with_context(|| Error::RuntimeOperation(RuntimeOperation::TopModule(id.to_owned())))?;
+
+        Ok(response.pids)
+    }
+
+    fn registry(&self) -> &Self::ModuleRegistry {
+        self
+    }
+
+    fn error_code(error: &anyhow::Error) -> hyper::StatusCode {
+        if let Some(error) = error.root_cause().downcast_ref::<ApiError>() {
+            error.code
+        } else {
+            hyper::StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_core::ModuleRuntime;
+
+    use super::ShimModuleRuntime;
+    use crate::ApiError;
+
+    #[tokio::test]
+    async fn new() {
+        let url = url::Url::parse("unix:///run/shim.sock").unwrap();
+        ShimModuleRuntime::new(&url).unwrap();
+
+        let url = url::Url::parse("http://localhost:8080").unwrap();
+        assert_eq!(
+            "invalid runtime shim URL: http://localhost:8080/",
+            ShimModuleRuntime::new(&url).err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn error_code() {
+        for (status, code) in [
+            (tonic::Code::NotFound, hyper::StatusCode::NOT_FOUND),
+            (tonic::Code::AlreadyExists, hyper::StatusCode::CONFLICT),
+            (
+                tonic::Code::FailedPrecondition,
+                hyper::StatusCode::NOT_MODIFIED,
+            ),
+            (tonic::Code::InvalidArgument, hyper::StatusCode::BAD_REQUEST),
+            (
+                tonic::Code::Unavailable,
+                hyper::StatusCode::SERVICE_UNAVAILABLE,
+            ),
+            (
+                tonic::Code::Internal,
+                hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+        ] {
+            let error: anyhow::Error =
+                ApiError::from(tonic::Status::new(status, "module not found")).into();
+            let error = error.context(crate::Error::Shim);
+
+            assert_eq!(code, ShimModuleRuntime::error_code(&error));
+        }
+
+        let error: anyhow::Error =
+            ApiError::from(tonic::Status::not_found("module not found")).into();
+        assert_eq!("HTTP 404 Not Found: module not found", error.to_string());
+
+        let error = anyhow::anyhow!("connection refused");
+        assert_eq!(
+            hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            ShimModuleRuntime::error_code(&error)
+        );
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+type ResponseFuture = Pin<Box<dyn Future<Output = hyper::Response<hyper::Body>> + Send>>;
+type Handler = dyn Fn(hyper::Request<hyper::Body>) -> ResponseFuture + Send + Sync;
+
+/// Connector that serves every connection with a request handler in the test process, for
+/// tests that need a server to respond differently to each request or to hold responses.
+#[derive(Clone)]
+pub struct HandlerConnector {
+    handler: Arc<Handler>,
+}
+
+impl HandlerConnector {
+    pub fn new<F, R>(handler: F) -> Self
+    where
+        F: Fn(hyper::Request<hyper::Body>) -> R + Send + Sync + 'static,
+        R: Future<Output = hyper::Response<hyper::Body>> + Send + 'static,
+    {
+        HandlerConnector {
+            handler: Arc::new(move |req| -> ResponseFuture { Box::pin(handler(req)) }),
+        }
+    }
+}
+
+impl hyper::service::Service<hyper::Uri> for HandlerConnector {
+    type Response = HandlerStream;
+    type Error = std::convert::Infallible;
+    type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _req: hyper::Uri) -> Self::Future {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+
+        let handler = self.handler.clone();
+        let service = hyper::service::service_fn(move |req| {
+            let response = handler(req);
+
+            async move { Ok::<_, std::convert::Infallible>(response.await) }
+        });
+
+        tokio::spawn(async move {
+            // The client closing the connection ends it.
+            let _ = hyper::server::conn::Http::new()
+                .serve_connection(server, service)
+                .await;
+        });
+
+        std::future::ready(Ok(HandlerStream(client)))
+    }
+}
+
+pub struct HandlerStream(tokio::io::DuplexStream);
+
+impl tokio::io::AsyncRead for HandlerStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_read(cx, buf)
+    }
+}
+
+impl tokio::io::AsyncWrite for HandlerStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.get_mut().0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_shutdown(cx)
+    }
+}
+
+impl hyper::client::connect::Connection for HandlerStream {
+    fn connected(&self) -> hyper::client::connect::Connected {
+        hyper::client::connect::Connected::new()
+    }
+}
@@ -3,6 +3,9 @@
 pub mod route;
 pub mod runtime;
 
+mod handler_connector;
+pub use handler_connector::HandlerConnector;
+
 mod json_connector;
 pub use json_connector::JsonConnector;
 
@@ -43,18 +43,31 @@ impl edgelet_core::Module for Module {
     }
 }
 
+#[derive(Clone, Default)]
 pub struct ModuleRegistry {}
 
 #[async_trait::async_trait]
 impl edgelet_core::ModuleRegistry for ModuleRegistry {
     type Config = Config;
 
+    fn pulls(&self) -> Vec<edgelet_core::ImagePull> {
+        vec![edgelet_core::ImagePull {
+            image: "image:1".to_string(),
+            modules: vec!["testModule".to_string()],
+            status: "pulling image, 50% (0/1 images pulled)".to_string(),
+        }]
+    }
+
     // The fuctions below aren't used in tests.
 
     async fn pull(&self, _config: &Self::Config) -> anyhow::Result<()> {
         unimplemented!()
     }
 
+    async fn pull_module(&self, _module: &str, _config: &Self::Config) -> anyhow::Result<()> {
+        unimplemented!()
+    }
+
     async fn remove(&self, _name: &str) -> anyhow::Result<()> {
         unimplemented!()
     }
@@ -62,6 +75,7 @@ impl edgelet_core::ModuleRegistry for ModuleRegistry {
 
 pub struct Runtime {
     pub module_auth: std::collections::BTreeMap<String, Vec<i32>>,
+    registry: ModuleRegistry,
 }
 
 impl Default for Runtime {
@@ -75,6 +89,7 @@ impl Default for Runtime {
 
         Runtime {
             module_auth: modules,
+            registry: ModuleRegistry::default(),
         }
     }
 }
@@ -101,6 +116,84 @@ impl edgelet_core::ModuleRuntime for Runtime {
         }
     }
 
+    async fn logs(
+        &self,
+        id: &str,
+        options: &edgelet_core::LogOptions,
+    ) -> anyhow::Result<hyper::Body> {
+        if id == "runtimeError" {
+            Err(crate::test_error())
+        } else {
+            Ok(format!("{} logs (tail={})", id, options.tail()).into())
+        }
+    }
+
+    async fn purge_data(&self, id: &str) -> anyhow::Result<Vec<String>> {
+        if id == "runtimeError" {
+            Err(crate::test_error())
+        } else {
+            Ok(vec![format!("{id}-data")])
+        }
+    }
+
+    async fn module_sbom(&self, id: &str) -> anyhow::Result<edgelet_core::SbomLookup> {
+        let sbom = match id {
+            "runtimeError" => return Err(crate::test_error()),
+            "noSbom" => None,
+            _ => Some(edgelet_core::ImageSbom {
+                format: "https://spdx.dev/Document".to_string(),
+                subject: None,
+                document: serde_json::json!({ "name": id }),
+            }),
+        };
+
+        Ok(Box::pin(async move { Ok(sbom) }))
+    }
+
+    async fn registry_mirrors(&self) -> anyhow::Result<Vec<edgelet_core::RegistryMirror>> {
+        Ok(vec![edgelet_core::RegistryMirror {
+            registry: "mcr.microsoft.com".to_string(),
+            mirror: "mirror.contoso.com".to_string(),
+            healthy: true,
+            pulls: 1,
+            failures: 0,
+            last_success: None,
+            last_failure: None,
+            last_error: None,
+        }])
+    }
+
+    async fn registry_credentials(&self) -> anyhow::Result<Vec<edgelet_core::RegistryCredential>> {
+        Ok(vec![edgelet_core::RegistryCredential {
+            registry: "contoso.azurecr.io".to_string(),
+            module: Some("edgeAgent".to_string()),
+            username: "contoso".to_string(),
+            source: edgelet_core::RegistryCredentialSource::Deployment,
+            accepted: Default::default(),
+        }])
+    }
+
+    async fn invalidate_registry_credentials(&self, registry: Option<&str>) -> anyhow::Result<()> {
+        match registry {
+            Some("runtimeError") => Err(crate::test_error()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn disk_space(&self) -> anyhow::Result<Vec<edgelet_core::DiskSpaceStatus>> {
+        Ok(vec![edgelet_core::DiskSpaceStatus {
+            path: "/var/lib/aziot/edged".into(),
+            total_bytes: 100,
+            available_bytes: 5,
+            level: edgelet_core::DiskSpaceLevel::Warning,
+            checked: Default::default(),
+        }])
+    }
+
+    fn registry(&self) -> &Self::ModuleRegistry {
+        &self.registry
+    }
+
     // The functions below aren't used in tests.
 
     async fn create(
@@ -138,7 +231,10 @@ impl edgelet_core::ModuleRuntime for Runtime {
     }
 
     async fn system_info(&self) -> anyhow::Result<edgelet_core::SystemInfo> {
-        unimplemented!()
+        Ok(edgelet_core::SystemInfo {
+            server_version: Some("20.10.0".to_string()),
+            ..Default::default()
+        })
     }
 
     async fn system_resources(&self) -> anyhow::Result<edgelet_core::SystemResources> {
@@ -159,14 +255,6 @@ impl edgelet_core::ModuleRuntime for Runtime {
         unimplemented!()
     }
 
-    async fn logs(
-        &self,
-        _id: &str,
-        _options: &edgelet_core::LogOptions,
-    ) -> anyhow::Result<hyper::Body> {
-        unimplemented!()
-    }
-
     async fn remove_all(&self) -> anyhow::Result<()> {
         unimplemented!()
     }
@@ -175,10 +263,6 @@ impl edgelet_core::ModuleRuntime for Runtime {
         unimplemented!()
     }
 
-    fn registry(&self) -> &Self::ModuleRegistry {
-        unimplemented!()
-    }
-
     fn error_code(_error: &anyhow::Error) -> hyper::StatusCode {
         unimplemented!()
     }
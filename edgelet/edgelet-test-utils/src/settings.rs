@@ -9,6 +9,10 @@ pub struct Settings {
 
     pub trust_bundle: Option<String>,
     pub manifest_trust_bundle: Option<String>,
+
+    pub tls_performance_mode: edgelet_settings::TlsPerformanceMode,
+
+    pub workload_server_certs: edgelet_settings::WorkloadServerCerts,
 }
 
 impl edgelet_settings::RuntimeSettings for Settings {
@@ -38,6 +42,14 @@ impl edgelet_settings::RuntimeSettings for Settings {
         self.manifest_trust_bundle.as_deref()
     }
 
+    fn tls_performance_mode(&self) -> edgelet_settings::TlsPerformanceMode {
+        self.tls_performance_mode
+    }
+
+    fn workload_server_certs(&self) -> &edgelet_settings::WorkloadServerCerts {
+        &self.workload_server_certs
+    }
+
     // The functions below aren't used in tests.
 
     fn hostname(&self) -> &str {
@@ -68,6 +80,14 @@ impl edgelet_settings::RuntimeSettings for Settings {
         unimplemented!()
     }
 
+    fn agent_fallback_images(&self) -> &[String] {
+        unimplemented!()
+    }
+
+    fn agent_cached_image_fallback(&self) -> bool {
+        unimplemented!()
+    }
+
     fn connect(&self) -> &edgelet_settings::uri::Connect {
         unimplemented!()
     }
@@ -91,4 +111,90 @@ impl edgelet_settings::RuntimeSettings for Settings {
     fn image_garbage_collection(&self) -> &edgelet_settings::base::image::ImagePruneSettings {
         unimplemented!()
     }
+
+    fn log_format(&self) -> edgelet_settings::logging::LogFormat {
+        unimplemented!()
+    }
+
+    fn api_access_log(&self) -> &edgelet_settings::logging::AccessLogSettings {
+        unimplemented!()
+    }
+
+    fn feature_flags(&self) -> &std::collections::BTreeMap<String, bool> {
+        unimplemented!()
+    }
+
+    fn warm_standby_modules(&self) -> &[String] {
+        unimplemented!()
+    }
+
+    fn inject_host_time(&self) -> bool {
+        unimplemented!()
+    }
+
+    fn degraded_mode(&self) -> &edgelet_settings::DegradedMode<Self::ModuleConfig> {
+        unimplemented!()
+    }
+
+    fn site_overlay(&self) -> Option<&edgelet_settings::SiteOverlaySource> {
+        unimplemented!()
+    }
+
+    fn log_sink(&self) -> Option<&edgelet_settings::LogSink> {
+        unimplemented!()
+    }
+
+    fn log_persistence(&self) -> Option<&edgelet_settings::LogPersistence> {
+        unimplemented!()
+    }
+
+    fn proxy(&self) -> Option<&edgelet_settings::Proxy> {
+        unimplemented!()
+    }
+
+    fn device_mapping(&self) -> &edgelet_settings::DeviceMapping {
+        unimplemented!()
+    }
+
+    fn storage(&self) -> &edgelet_settings::Storage {
+        unimplemented!()
+    }
+
+    fn template_variables(&self) -> &std::collections::BTreeMap<String, String> {
+        unimplemented!()
+    }
+
+    fn offline_start(&self) -> bool {
+        unimplemented!()
+    }
+
+    fn warm_restart(&self) -> bool {
+        unimplemented!()
+    }
+
+    fn reprovision_check_interval(&self) -> Option<std::time::Duration> {
+        unimplemented!()
+    }
+
+    fn alerts(&self) -> &[edgelet_settings::alerts::Rule] {
+        unimplemented!()
+    }
+
+    fn workload_rate_limit(&self) -> &edgelet_settings::WorkloadRateLimit {
+        unimplemented!()
+    }
+
+    fn module_cert_renewal(&self) -> &edgelet_settings::ModuleCertRenewal {
+        unimplemented!()
+    }
+
+    fn workload_sockets(
+        &self,
+    ) -> &std::collections::BTreeMap<String, edgelet_settings::WorkloadSocket> {
+        unimplemented!()
+    }
+
+    fn cloud_notify(&self) -> &edgelet_settings::CloudNotify {
+        unimplemented!()
+    }
 }
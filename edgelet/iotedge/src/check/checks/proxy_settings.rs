@@ -29,6 +29,7 @@ impl ProxySettings {
         let edge_agent_proxy_uri = settings
             .base
             .agent
+            .spec()
             .env()
             .get("https_proxy")
             .cloned()
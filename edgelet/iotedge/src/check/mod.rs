@@ -29,6 +29,9 @@ use shared::{CheckResult, Checker, CheckerMeta};
 
 mod checks;
 
+/// How long an extension's checks may run before the extension is killed.
+const EXTENSION_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+
 pub struct Check {
     container_engine_config_path: PathBuf,
     diagnostics_image_name: String,
@@ -425,32 +428,6 @@ impl Check {
         // run the aziot checks first, as certain bits of `additional_info` from
         // aziot are required to run iotedge checks. e.g: the "iothub_hostname".
         {
-            fn to_check_result(res: CheckResultSerializable) -> CheckResult {
-                fn vec_to_err(mut v: Vec<String>) -> anyhow::Error {
-                    let mut err =
-                        anyhow::anyhow!(v.pop().expect("errors always have at least one source"),);
-                    while let Some(s) = v.pop() {
-                        err = err.context(s);
-                    }
-                    err
-                }
-
-                match res {
-                    CheckResultSerializable::Ok => CheckResult::Ok,
-                    CheckResultSerializable::Warning { details } => {
-                        CheckResult::Warning(vec_to_err(details))
-                    }
-                    CheckResultSerializable::Ignored => CheckResult::Ignored,
-                    CheckResultSerializable::Skipped => CheckResult::Skipped,
-                    CheckResultSerializable::Fatal { details } => {
-                        CheckResult::Fatal(vec_to_err(details))
-                    }
-                    CheckResultSerializable::Error { details } => {
-                        CheckResult::Failed(vec_to_err(details))
-                    }
-                }
-            }
-
             let mut aziot_check = std::process::Command::new(&self.aziot_bin);
             aziot_check
                 .arg("check")
@@ -568,6 +545,70 @@ impl Check {
             }
         }
 
+        // run the checks contributed by host extensions
+        'extensions: for extension in extension_checks() {
+            let name = extension
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let output = match run_extension_checks(&extension, &self.dont_run).await {
+                Ok(output) => output,
+                Err(err) => {
+                    self.output_section(&format!("({name})"));
+                    if output_check(
+                        CheckOutput {
+                            id: format!("({name}-error)"),
+                            description: format!(
+                                "{name} checks unavailable - could not run '{}'.",
+                                extension.display()
+                            ),
+                            result: CheckResult::Failed(err),
+                            additional_info: serde_json::Value::Null,
+                        },
+                        self.verbose,
+                        self.warnings_as_errors,
+                    )? {
+                        break 'extensions;
+                    }
+
+                    continue;
+                }
+            };
+
+            for val in serde_json::Deserializer::from_slice(&output).into_iter() {
+                let val = match val {
+                    Ok(val) => val,
+                    Err(err) => {
+                        log::warn!("Invalid check output from {}: {}", name, err);
+
+                        break;
+                    }
+                };
+
+                match val {
+                    CheckOutputSerializableStreaming::Section { name: section } => {
+                        self.output_section(&format!("{section} ({name})"));
+                    }
+                    CheckOutputSerializableStreaming::Check { meta, output } => {
+                        if output_check(
+                            CheckOutput {
+                                id: meta.id,
+                                description: meta.description,
+                                result: to_check_result(output.result),
+                                additional_info: output.additional_info,
+                            },
+                            self.verbose,
+                            self.warnings_as_errors,
+                        )? {
+                            break 'extensions;
+                        }
+                    }
+                    CheckOutputSerializableStreaming::AdditionalInfo(_) => {}
+                }
+            }
+        }
+
         stdout.write_success(|stdout| {
             writeln!(stdout, "{num_successful} check(s) succeeded.")?;
             Ok(())
@@ -636,6 +677,80 @@ impl Check {
     }
 }
 
+fn to_check_result(res: CheckResultSerializable) -> CheckResult {
+    fn vec_to_err(mut v: Vec<String>) -> anyhow::Error {
+        let mut err = anyhow::anyhow!(v.pop().expect("errors always have at least one source"),);
+        while let Some(s) = v.pop() {
+            err = err.context(s);
+        }
+        err
+    }
+
+    match res {
+        CheckResultSerializable::Ok => CheckResult::Ok,
+        CheckResultSerializable::Warning { details } => CheckResult::Warning(vec_to_err(details)),
+        CheckResultSerializable::Ignored => CheckResult::Ignored,
+        CheckResultSerializable::Skipped => CheckResult::Skipped,
+        CheckResultSerializable::Fatal { details } => CheckResult::Fatal(vec_to_err(details)),
+        CheckResultSerializable::Error { details } => CheckResult::Failed(vec_to_err(details)),
+    }
+}
+
+/// Run an extension's checks and return their JSON stream. An extension that hangs is
+/// killed after [`EXTENSION_CHECK_TIMEOUT`], so that it can't hold up `iotedge check`.
+async fn run_extension_checks(
+    extension: &std::path::Path,
+    dont_run: &BTreeSet<String>,
+) -> anyhow::Result<Vec<u8>> {
+    let mut extension_check = tokio::process::Command::new(extension);
+    extension_check
+        .arg("check")
+        .arg("-o=json-stream")
+        .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true);
+
+    if !dont_run.is_empty() {
+        extension_check
+            .arg("--dont-run")
+            .arg(dont_run.iter().cloned().collect::<Vec<_>>().join(" "));
+    }
+
+    let child = extension_check.spawn()?;
+
+    // The child is killed and reaped when the timed out wait is dropped.
+    let output = tokio::time::timeout(EXTENSION_CHECK_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow::anyhow!("did not finish within {:?}", EXTENSION_CHECK_TIMEOUT))??;
+
+    if !output.status.success() {
+        log::warn!("{} exited with {}", extension.display(), output.status);
+    }
+
+    Ok(output.stdout)
+}
+
+/// Executables in the extensions directory, in name order so that output is stable.
+fn extension_checks() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(edgelet_extension::check::EXTENSIONS_DIR) else {
+        return Vec::new();
+    };
+
+    let mut extensions: Vec<_> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            use std::os::unix::fs::PermissionsExt;
+
+            entry.metadata().map_or(false, |metadata| {
+                metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+            })
+        })
+        .map(|entry| entry.path())
+        .collect();
+    extensions.sort();
+
+    extensions
+}
+
 fn get_proxy_uri(arg: Option<String>) -> Option<String> {
     // If proxy address was passed in as command line argument, we are good
     if arg.is_some() {
@@ -5,8 +5,8 @@ use hyper::Uri;
 use url::Url;
 
 use edgelet_core::{
-    LogOptions, Module, ModuleRegistry, ModuleRuntime, ModuleRuntimeState, SystemInfo,
-    SystemResources, UrlExt,
+    DiskSpaceStatus, LogOptions, Module, ModuleRegistry, ModuleRuntime, ModuleRuntimeState,
+    RegistryCredential, RegistryMirror, SbomLookup, SystemInfo, SystemResources, UrlExt,
 };
 use edgelet_http::{ListModulesResponse, ModuleDetails};
 use edgelet_settings::module::Settings as ModuleSpec;
@@ -16,6 +16,9 @@ use crate::error::Error;
 
 const API_VERSION: &str = "2020-07-07";
 
+/// Version of the routes that manage the daemon itself rather than modules.
+const SYSTEM_API_VERSION: &str = "2022-08-03";
+
 #[derive(serde::Serialize, Clone)]
 pub struct MgmtConfig {}
 
@@ -24,6 +27,7 @@ pub struct MgmtModule {
     pub image: String,
 }
 
+#[derive(Clone)]
 pub struct MgmtClient {
     connector: Connector,
     host: String,
@@ -52,6 +56,150 @@ impl MgmtClient {
 
         Ok(uri)
     }
+
+    /// Send a request to `path` and return the response body, or the error message of an
+    /// unsuccessful response.
+    async fn send(
+        &self,
+        method: hyper::Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> anyhow::Result<hyper::body::Bytes> {
+        let uri = self.get_uri(&format!("{path}?api-version={SYSTEM_API_VERSION}"))?;
+
+        let req = hyper::Request::builder().method(method).uri(uri);
+        let req = match body {
+            Some(body) => req
+                .header(hyper::header::CONTENT_TYPE, "application/json")
+                .body(hyper::Body::from(body.to_string())),
+            None => req.body(hyper::Body::empty()),
+        }
+        .expect("could not build hyper::Request");
+
+        let client = self.connector.clone().into_client();
+        let resp = client.request(req).await.context(Error::ModuleRuntime)?;
+
+        let (hyper::http::response::Parts { status, .. }, body) = resp.into_parts();
+        let body = hyper::body::to_bytes(body)
+            .await
+            .context(Error::ModuleRuntime)?;
+
+        if status.is_success() {
+            Ok(body)
+        } else {
+            let message = serde_json::from_slice::<ErrorBody<'_>>(&body)
+                .map_or_else(|_| status.to_string(), |err| err.message.into_owned());
+
+            Err(Error::Misc(message).into())
+        }
+    }
+
+    async fn send_log_filter(
+        &self,
+        method: hyper::Method,
+        body: Option<serde_json::Value>,
+    ) -> anyhow::Result<String> {
+        #[derive(serde::Deserialize)]
+        struct LogLevel {
+            filter: String,
+        }
+
+        let body = self.send(method, "/systeminfo/loglevel", body).await?;
+        let body: LogLevel = serde_json::from_slice(&body).context(Error::ModuleRuntime)?;
+
+        Ok(body.filter)
+    }
+
+    /// The daemon's current log filter.
+    pub async fn log_filter(&self) -> anyhow::Result<String> {
+        self.send_log_filter(hyper::Method::GET, None).await
+    }
+
+    /// Change the daemon's log filter until it restarts.
+    pub async fn set_log_filter(&self, filter: &str) -> anyhow::Result<String> {
+        self.send_log_filter(
+            hyper::Method::PUT,
+            Some(serde_json::json!({ "filter": filter })),
+        )
+        .await
+    }
+
+    /// Restore the log filter the daemon started with.
+    pub async fn reset_log_filter(&self) -> anyhow::Result<String> {
+        self.send_log_filter(hyper::Method::DELETE, None).await
+    }
+
+    /// Feature flags and their values, including overrides.
+    pub async fn feature_flags(&self) -> anyhow::Result<std::collections::BTreeMap<String, bool>> {
+        #[derive(serde::Deserialize)]
+        struct ListFeatureFlagsResponse {
+            flags: std::collections::BTreeMap<String, bool>,
+        }
+
+        let body = self.send(hyper::Method::GET, "/featureflags", None).await?;
+        let body: ListFeatureFlagsResponse =
+            serde_json::from_slice(&body).context(Error::ModuleRuntime)?;
+
+        Ok(body.flags)
+    }
+
+    /// Override a feature flag's configured value.
+    pub async fn set_feature_flag(&self, name: &str, value: bool) -> anyhow::Result<()> {
+        let path = format!("/featureflags/{}", encode_path(name));
+        self.send(
+            hyper::Method::PUT,
+            &path,
+            Some(serde_json::json!({ "value": value })),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove a feature flag's override, so that it has its configured value again.
+    pub async fn reset_feature_flag(&self, name: &str) -> anyhow::Result<()> {
+        let path = format!("/featureflags/{}", encode_path(name));
+        self.send(hyper::Method::DELETE, &path, None).await?;
+
+        Ok(())
+    }
+
+    /// Modules' secrets, without their values.
+    pub async fn secrets(&self) -> anyhow::Result<Vec<edgelet_http::SecretInfo>> {
+        #[derive(serde::Deserialize)]
+        struct ListSecretsResponse {
+            secrets: Vec<edgelet_http::SecretInfo>,
+        }
+
+        let body = self.send(hyper::Method::GET, "/secrets", None).await?;
+        let body: ListSecretsResponse =
+            serde_json::from_slice(&body).context(Error::ModuleRuntime)?;
+
+        Ok(body.secrets)
+    }
+
+    pub async fn set_secret(&self, module: &str, name: &str, value: &str) -> anyhow::Result<()> {
+        let path = format!("/secrets/{}/{}", encode_path(module), encode_path(name));
+        self.send(
+            hyper::Method::PUT,
+            &path,
+            Some(serde_json::json!({ "value": value })),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_secret(&self, module: &str, name: &str) -> anyhow::Result<()> {
+        let path = format!("/secrets/{}/{}", encode_path(module), encode_path(name));
+        self.send(hyper::Method::DELETE, &path, None).await?;
+
+        Ok(())
+    }
+}
+
+fn encode_path(segment: &str) -> String {
+    percent_encoding::utf8_percent_encode(segment, percent_encoding::NON_ALPHANUMERIC).to_string()
 }
 
 #[async_trait::async_trait]
@@ -182,6 +330,30 @@ impl ModuleRuntime for MgmtClient {
         unimplemented!()
     }
 
+    async fn module_sbom(&self, _id: &str) -> anyhow::Result<SbomLookup> {
+        unimplemented!()
+    }
+
+    async fn registry_mirrors(&self) -> anyhow::Result<Vec<RegistryMirror>> {
+        unimplemented!()
+    }
+
+    async fn registry_credentials(&self) -> anyhow::Result<Vec<RegistryCredential>> {
+        unimplemented!()
+    }
+
+    async fn invalidate_registry_credentials(&self, _registry: Option<&str>) -> anyhow::Result<()> {
+        unimplemented!()
+    }
+
+    async fn disk_space(&self) -> anyhow::Result<Vec<DiskSpaceStatus>> {
+        unimplemented!()
+    }
+
+    async fn purge_data(&self, _id: &str) -> anyhow::Result<Vec<String>> {
+        unimplemented!()
+    }
+
     fn registry(&self) -> &Self::ModuleRegistry {
         unimplemented!()
     }
@@ -218,6 +390,10 @@ impl ModuleRegistry for MgmtClient {
         Ok(())
     }
 
+    async fn pull_module(&self, _module: &str, _config: &Self::Config) -> anyhow::Result<()> {
+        Ok(())
+    }
+
     async fn remove(&self, _name: &str) -> anyhow::Result<()> {
         Ok(())
     }
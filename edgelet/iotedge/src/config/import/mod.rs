@@ -447,6 +447,7 @@ fn execute_inner(
                     }
                 },
             )?
+            .into()
         },
 
         connect: {
@@ -457,6 +458,7 @@ fn execute_inner(
             edgelet_settings::uri::Connect {
                 workload_uri,
                 management_uri,
+                workload_tcp_uri: None,
             }
         },
         listen: {
@@ -495,6 +497,8 @@ fn execute_inner(
             edgelet_settings::uri::Listen {
                 workload_uri,
                 management_uri,
+                workload_tcp_uri: None,
+                management_tcp: None,
             }
         },
 
@@ -509,6 +513,7 @@ fn execute_inner(
                         edgelet_settings::watchdog::MaxRetries::Num(num)
                     }
                 },
+                ..Default::default()
             }
         },
 
@@ -521,7 +526,7 @@ fn execute_inner(
                 content_trust,
             } = moby_runtime;
             super_config::MobyRuntime {
-                uri,
+                uri: Some(uri),
 
                 network: match network {
                     old_config::MobyNetwork::Network(network) => {
@@ -552,6 +557,8 @@ fn execute_inner(
                                         }),
                                     }
                                 }),
+                                ula_prefix: None,
+                                dns: Vec::new(),
                             }
                         })
                     }
@@ -561,6 +568,8 @@ fn execute_inner(
                     }
                 },
 
+                engine: Default::default(),
+
                 content_trust: content_trust
                     .map(
                         |content_trust| -> Result<_, std::borrow::Cow<'static, str>> {
@@ -590,9 +599,41 @@ fn execute_inner(
                         },
                     )
                     .transpose()?,
+                image_pull: Default::default(),
+                image_import_dir: None,
+                image_digests: Default::default(),
+                image_signatures: Default::default(),
+                module_defaults: Default::default(),
+                module_limits: Default::default(),
+                pre_stop: Default::default(),
             }
         },
         image_garbage_collection: ImagePruneSettings::default(),
+        log_format: Default::default(),
+        api_access_log: Default::default(),
+        feature_flags: Default::default(),
+        warm_standby_modules: Default::default(),
+        inject_host_time: Default::default(),
+        degraded_mode: Default::default(),
+        site_overlay: Default::default(),
+        log_sink: Default::default(),
+        log_persistence: Default::default(),
+        proxy: None,
+        device_mapping: Default::default(),
+        storage: Default::default(),
+        template_variables: Default::default(),
+        offline_start: Default::default(),
+        warm_restart: Default::default(),
+        reprovision_check_interval: Default::default(),
+        alerts: Default::default(),
+        workload_rate_limit: Default::default(),
+        workload_server_certs: Default::default(),
+        module_cert_renewal: Default::default(),
+        workload_sockets: Default::default(),
+        cloud_notify: Default::default(),
+        tls_performance_mode: Default::default(),
+        network_policy: None,
+        profile: None,
     };
 
     let config =
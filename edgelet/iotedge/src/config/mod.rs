@@ -3,4 +3,5 @@
 pub mod apply;
 pub mod import;
 pub mod mp;
+pub mod profile;
 pub mod super_config;
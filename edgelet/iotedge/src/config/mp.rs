@@ -99,6 +99,42 @@ To reconfigure IoT Edge, run:
         moby_runtime: Default::default(),
 
         image_garbage_collection: Default::default(),
+
+        log_format: Default::default(),
+
+        api_access_log: Default::default(),
+
+        feature_flags: Default::default(),
+
+        warm_standby_modules: Default::default(),
+
+        inject_host_time: Default::default(),
+
+        degraded_mode: Default::default(),
+
+        site_overlay: Default::default(),
+
+        log_sink: Default::default(),
+        log_persistence: Default::default(),
+        proxy: None,
+        device_mapping: Default::default(),
+        storage: Default::default(),
+        template_variables: Default::default(),
+        offline_start: Default::default(),
+        warm_restart: Default::default(),
+        reprovision_check_interval: Default::default(),
+        alerts: Default::default(),
+
+        workload_rate_limit: Default::default(),
+        workload_server_certs: Default::default(),
+        module_cert_renewal: Default::default(),
+        workload_sockets: Default::default(),
+
+        cloud_notify: Default::default(),
+
+        tls_performance_mode: Default::default(),
+        network_policy: None,
+        profile: None,
     };
     let config = toml::to_string(&config)
         .map_err(|err| format!("could not serialize system config: {err}"))?;
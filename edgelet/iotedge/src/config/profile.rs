@@ -0,0 +1,153 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Presets of settings for common classes of devices, selected with `profile` in the super-config.
+///
+/// A preset only provides defaults. Any setting in the super-config overrides the preset's.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Profile {
+    #[serde(rename = "constrained-512mb")]
+    Constrained512Mb,
+
+    #[serde(rename = "gateway-nested")]
+    GatewayNested,
+
+    #[serde(rename = "gpu-vision")]
+    GpuVision,
+}
+
+impl Profile {
+    fn preset(self) -> &'static str {
+        match self {
+            Profile::Constrained512Mb => include_str!("profiles/constrained-512mb.toml"),
+            Profile::GatewayNested => include_str!("profiles/gateway-nested.toml"),
+            Profile::GpuVision => include_str!("profiles/gpu-vision.toml"),
+        }
+    }
+}
+
+/// Parse a super-config, filling in the settings of its profile, if any, that it doesn't set itself.
+pub fn apply(config: &str) -> Result<toml::Value, String> {
+    let mut config: toml::Value =
+        toml::from_str(config).map_err(|err| format!("could not parse config file: {err}"))?;
+
+    let profile = match config.get("profile") {
+        Some(profile) => Some(
+            profile
+                .clone()
+                .try_into::<Profile>()
+                .map_err(|err| format!("invalid profile: {err}"))?,
+        ),
+        None => None,
+    };
+
+    if let (Some(profile), toml::Value::Table(config)) = (profile, &mut config) {
+        let preset = toml::from_str(profile.preset()).expect("profile presets must parse");
+
+        merge(config, preset);
+    }
+
+    Ok(config)
+}
+
+/// Merge `preset` into `config`. Values in `config` win, and tables in both are merged.
+fn merge(config: &mut toml::value::Table, preset: toml::value::Table) {
+    for (key, value) in preset {
+        match value {
+            toml::Value::Table(preset) => match config.get_mut(&key) {
+                Some(toml::Value::Table(table)) => merge(table, preset),
+                Some(_) => (),
+                None => {
+                    config.insert(key, toml::Value::Table(preset));
+                }
+            },
+            value => {
+                config.entry(key).or_insert(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Profile;
+
+    #[test]
+    fn presets_parse() {
+        for profile in [
+            Profile::Constrained512Mb,
+            Profile::GatewayNested,
+            Profile::GpuVision,
+        ] {
+            let preset: toml::Value = toml::from_str(profile.preset()).unwrap();
+
+            let moby_runtime: crate::config::super_config::MobyRuntime =
+                preset["moby_runtime"].clone().try_into().unwrap();
+            assert!(!moby_runtime.module_defaults.is_default());
+
+            let _: edgelet_settings::watchdog::Settings =
+                preset["watchdog"].clone().try_into().unwrap();
+            let _: edgelet_settings::base::image::ImagePruneSettings = preset
+                ["image_garbage_collection"]
+                .clone()
+                .try_into()
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn explicit_settings_override_profile() {
+        let config = super::apply(
+            r#"
+profile = "constrained-512mb"
+hostname = "my-device"
+
+[watchdog]
+max_retries = 3
+
+[moby_runtime]
+network = "my-network"
+
+[moby_runtime.module_defaults]
+log_max_files = 10
+"#,
+        )
+        .unwrap();
+
+        assert_eq!("my-device", config["hostname"].as_str().unwrap());
+
+        // Tables in both are merged.
+        assert_eq!(3, config["watchdog"]["max_retries"].as_integer().unwrap());
+        assert_eq!("10m", config["watchdog"]["max_backoff"].as_str().unwrap());
+
+        let moby_runtime = &config["moby_runtime"];
+        assert_eq!("my-network", moby_runtime["network"].as_str().unwrap());
+        assert_eq!(
+            10,
+            moby_runtime["module_defaults"]["log_max_files"]
+                .as_integer()
+                .unwrap()
+        );
+        assert_eq!(
+            5,
+            moby_runtime["module_defaults"]["log_max_size_mb"]
+                .as_integer()
+                .unwrap()
+        );
+
+        // Tables only in the preset are added.
+        assert_eq!(
+            "1d",
+            config["image_garbage_collection"]["image_age_cleanup_threshold"]
+                .as_str()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn no_profile() {
+        let config = super::apply("hostname = \"my-device\"").unwrap();
+        assert!(config.get("watchdog").is_none());
+
+        super::apply("profile = \"huge\"").unwrap_err();
+    }
+}
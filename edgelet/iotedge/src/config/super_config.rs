@@ -47,7 +47,7 @@ pub struct Config {
     pub aziot: aziotctl_common::config::super_config::Config,
 
     #[serde(default = "default_agent")]
-    pub agent: edgelet_settings::ModuleSpec<edgelet_settings::DockerConfig>,
+    pub agent: edgelet_settings::module::AgentSettings<edgelet_settings::DockerConfig>,
 
     #[serde(default)]
     pub connect: edgelet_settings::uri::Connect,
@@ -65,9 +65,116 @@ pub struct Config {
 
     #[serde(default, skip_serializing_if = "image::ImagePruneSettings::is_default")]
     pub image_garbage_collection: image::ImagePruneSettings,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::logging::LogFormat::is_default"
+    )]
+    pub log_format: edgelet_settings::logging::LogFormat,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::logging::AccessLogSettings::is_default"
+    )]
+    pub api_access_log: edgelet_settings::logging::AccessLogSettings,
+
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub feature_flags: std::collections::BTreeMap<String, bool>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warm_standby_modules: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inject_host_time: bool,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::DegradedMode::is_empty"
+    )]
+    pub degraded_mode: edgelet_settings::DegradedMode<edgelet_settings::DockerConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_overlay: Option<edgelet_settings::SiteOverlaySource>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_sink: Option<edgelet_settings::LogSink>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_persistence: Option<edgelet_settings::LogPersistence>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<edgelet_settings::Proxy>,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::DeviceMapping::is_default"
+    )]
+    pub device_mapping: edgelet_settings::DeviceMapping,
+
+    #[serde(default, skip_serializing_if = "edgelet_settings::Storage::is_default")]
+    pub storage: edgelet_settings::Storage,
+
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub template_variables: std::collections::BTreeMap<String, String>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub offline_start: bool,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub warm_restart: bool,
+
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reprovision_check_interval: Option<std::time::Duration>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<edgelet_settings::alerts::Rule>,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::WorkloadRateLimit::is_default"
+    )]
+    pub workload_rate_limit: edgelet_settings::WorkloadRateLimit,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::WorkloadServerCerts::is_default"
+    )]
+    pub workload_server_certs: edgelet_settings::WorkloadServerCerts,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::ModuleCertRenewal::is_default"
+    )]
+    pub module_cert_renewal: edgelet_settings::ModuleCertRenewal,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub workload_sockets: BTreeMap<String, edgelet_settings::WorkloadSocket>,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::CloudNotify::is_default"
+    )]
+    pub cloud_notify: edgelet_settings::CloudNotify,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::TlsPerformanceMode::is_default"
+    )]
+    pub tls_performance_mode: edgelet_settings::TlsPerformanceMode,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_policy: Option<edgelet_settings::NetworkPolicy>,
+
+    /// Preset of settings that this config is based on. Settings in this config override it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<super::profile::Profile>,
 }
 
-pub fn default_agent() -> edgelet_settings::ModuleSpec<edgelet_settings::DockerConfig> {
+pub fn default_agent() -> edgelet_settings::module::AgentSettings<edgelet_settings::DockerConfig> {
     edgelet_settings::ModuleSpec::new(
         /* image */ "edgeAgent".to_owned(),
         /* type */ "docker".to_owned(),
@@ -84,6 +191,7 @@ pub fn default_agent() -> edgelet_settings::ModuleSpec<edgelet_settings::DockerC
         /* image pull policy */ Default::default(),
     )
     .expect("name and type are never empty")
+    .into()
 }
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -113,24 +221,94 @@ pub enum EdgeCa {
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct MobyRuntime {
-    pub uri: Url,
+    /// Defaults to the engine's usual socket if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<Url>,
     pub network: edgelet_settings::MobyNetwork,
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::ContainerEngine::is_default"
+    )]
+    pub engine: edgelet_settings::ContainerEngine,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content_trust: Option<ContentTrust>,
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::ImagePullSettings::is_default"
+    )]
+    pub image_pull: edgelet_settings::ImagePullSettings,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_import_dir: Option<std::path::PathBuf>,
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::ImageDigestSettings::is_default"
+    )]
+    pub image_digests: edgelet_settings::ImageDigestSettings,
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::ImageSignatureSettings::is_default"
+    )]
+    pub image_signatures: edgelet_settings::ImageSignatureSettings,
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::ModuleDefaults::is_default"
+    )]
+    pub module_defaults: edgelet_settings::ModuleDefaults,
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::ModuleLimits::is_default"
+    )]
+    pub module_limits: edgelet_settings::ModuleLimits,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pre_stop: BTreeMap<String, edgelet_settings::PreStopHook>,
+}
+
+impl MobyRuntime {
+    pub const DEFAULT_DOCKER_URI: &'static str = "unix:///var/run/docker.sock";
+    pub const DEFAULT_SHIM_URI: &'static str = "unix:///run/iotedge/runtime-shim.sock";
+
+    /// The socket of the engine, with the engine's usual socket as the default.
+    ///
+    /// Podman is expected to run rootless as the user that aziot-edged runs as, so its
+    /// default is that user's socket rather than the system-wide `/run/podman/podman.sock`.
+    pub fn uri_or_default(&self, iotedge_uid: nix::unistd::Uid) -> Url {
+        if let Some(uri) = &self.uri {
+            return uri.clone();
+        }
+
+        let uri = match self.engine {
+            edgelet_settings::ContainerEngine::Podman => {
+                format!("unix:///run/user/{iotedge_uid}/podman/podman.sock")
+            }
+            edgelet_settings::ContainerEngine::Docker => Self::DEFAULT_DOCKER_URI.to_string(),
+            edgelet_settings::ContainerEngine::Shim => Self::DEFAULT_SHIM_URI.to_string(),
+        };
+
+        uri.parse()
+            .expect("hard-coded url::Url must parse successfully")
+    }
 }
 
 impl Default for MobyRuntime {
     fn default() -> Self {
-        const DEFAULT_URI: &str = "unix:///var/run/docker.sock";
-
         MobyRuntime {
-            uri: DEFAULT_URI
-                .parse()
-                .expect("hard-coded url::Url must parse successfully"),
+            uri: Some(
+                Self::DEFAULT_DOCKER_URI
+                    .parse()
+                    .expect("hard-coded url::Url must parse successfully"),
+            ),
             network: edgelet_settings::MobyNetwork::Name(
                 edgelet_settings::DEFAULT_NETWORKID.to_owned(),
             ),
+            engine: Default::default(),
             content_trust: None,
+            image_pull: Default::default(),
+            image_import_dir: None,
+            image_digests: Default::default(),
+            image_signatures: Default::default(),
+            module_defaults: Default::default(),
+            module_limits: Default::default(),
+            pre_stop: Default::default(),
         }
     }
 }
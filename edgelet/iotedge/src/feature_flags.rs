@@ -0,0 +1,57 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::Write;
+
+use anyhow::Context;
+use tabwriter::TabWriter;
+
+use crate::error::Error;
+use crate::MgmtClient;
+
+pub struct FeatureFlags;
+
+impl FeatureFlags {
+    /// Print each feature flag and its value, including overrides.
+    pub async fn list<W>(client: &MgmtClient, output: W) -> anyhow::Result<()>
+    where
+        W: Write,
+    {
+        let flags = client
+            .feature_flags()
+            .await
+            .context("Failed to list feature flags")?;
+
+        let mut w = TabWriter::new(output).minwidth(15);
+        writeln!(w, "NAME\tVALUE").context(Error::WriteToStdout)?;
+        for (name, value) in flags {
+            writeln!(w, "{name}\t{value}").context(Error::WriteToStdout)?;
+        }
+        w.flush().context(Error::WriteToStdout)?;
+
+        Ok(())
+    }
+
+    /// Override a flag's configured value until it is reset.
+    pub async fn set(client: &MgmtClient, name: &str, value: bool) -> anyhow::Result<()> {
+        client
+            .set_feature_flag(name, value)
+            .await
+            .with_context(|| format!("Failed to set feature flag {name}"))?;
+
+        println!("Feature flag {name} set to {value}.");
+
+        Ok(())
+    }
+
+    /// Remove a flag's override, so that it has its configured value again.
+    pub async fn reset(client: &MgmtClient, name: &str) -> anyhow::Result<()> {
+        client
+            .reset_feature_flag(name)
+            .await
+            .with_context(|| format!("Failed to reset feature flag {name}"))?;
+
+        println!("Feature flag {name} reset to its configured value.");
+
+        Ok(())
+    }
+}
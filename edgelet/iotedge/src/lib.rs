@@ -23,9 +23,11 @@ mod check;
 mod client;
 pub mod config;
 mod error;
+mod feature_flags;
 mod list;
 mod logs;
 mod restart;
+mod secrets;
 mod support_bundle;
 mod system;
 mod version;
@@ -33,9 +35,11 @@ mod version;
 pub use crate::check::{Check, OutputFormat};
 pub use crate::client::{MgmtClient, MgmtModule};
 pub use crate::error::{Error, FetchLatestVersionsReason};
+pub use crate::feature_flags::FeatureFlags;
 pub use crate::list::List;
 pub use crate::logs::Logs;
 pub use crate::restart::Restart;
+pub use crate::secrets::Secrets;
 pub use crate::support_bundle::SupportBundleCommand;
 pub use crate::system::System;
 pub use crate::version::Version;
@@ -18,8 +18,8 @@ use edgelet_core::{parse_since, LogOptions, LogTail};
 use support_bundle::OutputLocation;
 
 use iotedge::{
-    Check, Error, List, Logs, MgmtClient, OutputFormat, Restart, SupportBundleCommand, System,
-    Version,
+    Check, Error, FeatureFlags, List, Logs, MgmtClient, OutputFormat, Restart, Secrets,
+    SupportBundleCommand, System, Version,
 };
 
 #[tokio::main]
@@ -254,6 +254,26 @@ async fn run() -> anyhow::Result<()> {
                     )
                 )
         )
+        .subcommand(
+            Command::new("feature-flags")
+                .about("List feature flags, or override their configured values")
+                .subcommand(Command::new("list").about("List feature flags and their values"))
+                .subcommand(
+                    Command::new("set")
+                        .about("Override the configured value of a feature flag")
+                        .arg(Arg::new("name").required(true))
+                        .arg(
+                            Arg::new("value")
+                                .required(true)
+                                .value_parser(clap::value_parser!(bool)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("reset")
+                        .about("Remove the override of a feature flag")
+                        .arg(Arg::new("name").required(true)),
+                ),
+        )
         .subcommand(Command::new("list").about("List modules"))
         .subcommand(
             Command::new("restart")
@@ -349,11 +369,43 @@ async fn run() -> anyhow::Result<()> {
                         .required(true),
                     )
                 )
+                .subcommand(
+                    Command::new("log-filter")
+                    .about("Show or change the log filter of the running aziot-edged without restarting it. Changes last until it restarts.")
+                    .arg(
+                        Arg::new("filter")
+                        .help("The new filter, in the syntax of AZIOT_LOG, e.g. info,edgelet_docker=debug")
+                        .conflicts_with("reset"),
+                    )
+                    .arg(
+                        Arg::new("reset")
+                        .long("reset")
+                        .num_args(0)
+                        .help("Restore the filter aziot-edged started with"),
+                    )
+                )
                 .subcommand(
                     Command::new("reprovision")
                     .about("Reprovision device with IoT Hub.")
                 )
         )
+        .subcommand(
+            Command::new("secrets")
+                .about("Manage the secrets that modules read through the workload API")
+                .subcommand(Command::new("list").about("List secrets, without their values"))
+                .subcommand(
+                    Command::new("set")
+                        .about("Set a secret of a module to the value read from standard input")
+                        .arg(Arg::new("module").required(true))
+                        .arg(Arg::new("name").required(true)),
+                )
+                .subcommand(
+                    Command::new("delete")
+                        .about("Delete a secret of a module")
+                        .arg(Arg::new("module").required(true))
+                        .arg(Arg::new("name").required(true)),
+                ),
+        )
         .subcommand(
             Command::new("support-bundle")
                 .about("Bundles troubleshooting information")
@@ -506,6 +558,32 @@ async fn run() -> anyhow::Result<()> {
                 }
             }
         }
+        ("feature-flags", args) => {
+            let client = runtime()?;
+
+            match args.subcommand() {
+                None | Some(("list", _)) => FeatureFlags::list(&client, io::stdout()).await,
+                Some(("set", args)) => {
+                    FeatureFlags::set(
+                        &client,
+                        args.get_one::<String>("name").expect("arg is required"),
+                        *args.get_one::<bool>("value").expect("arg is required"),
+                    )
+                    .await
+                }
+                Some(("reset", args)) => {
+                    FeatureFlags::reset(
+                        &client,
+                        args.get_one::<String>("name").expect("arg is required"),
+                    )
+                    .await
+                }
+                Some((command, _)) => {
+                    eprintln!("Unknown feature-flags subcommand: {command}");
+                    std::process::exit(1);
+                }
+            }
+        }
         ("list", _) => List::new(runtime()?, io::stdout()).execute().await,
         ("restart", args) => {
             Restart::new(
@@ -565,6 +643,14 @@ async fn run() -> anyhow::Result<()> {
                     .copied()
                     .expect("Value is required"),
             ),
+            ("log-filter", args) => {
+                System::log_filter(
+                    &runtime()?,
+                    args.get_one::<String>("filter").map(String::as_str),
+                    args.get_flag("reset"),
+                )
+                .await
+            }
             ("reprovision", _) => System::reprovision().await,
             (command, _) => {
                 eprintln!("Unknown system subcommand: {command}");
@@ -572,6 +658,34 @@ async fn run() -> anyhow::Result<()> {
             }
         })
         .map_err(anyhow::Error::from),
+        ("secrets", args) => {
+            let client = runtime()?;
+
+            match args.subcommand() {
+                None | Some(("list", _)) => Secrets::list(&client, io::stdout()).await,
+                Some(("set", args)) => {
+                    Secrets::set(
+                        &client,
+                        args.get_one::<String>("module").expect("arg is required"),
+                        args.get_one::<String>("name").expect("arg is required"),
+                        io::stdin(),
+                    )
+                    .await
+                }
+                Some(("delete", args)) => {
+                    Secrets::delete(
+                        &client,
+                        args.get_one::<String>("module").expect("arg is required"),
+                        args.get_one::<String>("name").expect("arg is required"),
+                    )
+                    .await
+                }
+                Some((command, _)) => {
+                    eprintln!("Unknown secrets subcommand: {command}");
+                    std::process::exit(1);
+                }
+            }
+        }
         ("support-bundle", args) => {
             let location = args
                 .get_one::<PathBuf>("output")
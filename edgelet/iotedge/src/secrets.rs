@@ -0,0 +1,79 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::{Read, Write};
+
+use anyhow::Context;
+use tabwriter::TabWriter;
+
+use crate::error::Error;
+use crate::MgmtClient;
+
+pub struct Secrets;
+
+impl Secrets {
+    /// Print the module and name of each secret. Values are never returned by the daemon.
+    pub async fn list<W>(client: &MgmtClient, output: W) -> anyhow::Result<()>
+    where
+        W: Write,
+    {
+        let secrets = client.secrets().await.context("Failed to list secrets")?;
+
+        let mut w = TabWriter::new(output).minwidth(15);
+        writeln!(w, "MODULE\tNAME\tUPDATED").context(Error::WriteToStdout)?;
+        for secret in secrets {
+            writeln!(
+                w,
+                "{}\t{}\t{}",
+                secret.module,
+                secret.name,
+                secret
+                    .updated
+                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            )
+            .context(Error::WriteToStdout)?;
+        }
+        w.flush().context(Error::WriteToStdout)?;
+
+        Ok(())
+    }
+
+    /// Set a secret to the value read from `input`, so that the value doesn't appear in
+    /// the command line or shell history. A trailing newline is not part of the value.
+    pub async fn set<R>(
+        client: &MgmtClient,
+        module: &str,
+        name: &str,
+        mut input: R,
+    ) -> anyhow::Result<()>
+    where
+        R: Read,
+    {
+        let mut value = String::new();
+        input
+            .read_to_string(&mut value)
+            .context("Failed to read the secret's value")?;
+        let value = value
+            .strip_suffix('\n')
+            .map_or(value.as_str(), |value| value.trim_end_matches('\r'));
+
+        client
+            .set_secret(module, name, value)
+            .await
+            .with_context(|| format!("Failed to set secret {name} of module {module}"))?;
+
+        println!("Secret {name} of module {module} set.");
+
+        Ok(())
+    }
+
+    pub async fn delete(client: &MgmtClient, module: &str, name: &str) -> anyhow::Result<()> {
+        client
+            .delete_secret(module, name)
+            .await
+            .with_context(|| format!("Failed to delete secret {name} of module {module}"))?;
+
+        println!("Secret {name} of module {module} deleted.");
+
+        Ok(())
+    }
+}
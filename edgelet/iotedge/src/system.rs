@@ -16,6 +16,7 @@ use aziot_identity_client_async::Client as IdentityClient;
 use aziot_identity_common_http::ApiVersion;
 
 use crate::error::Error;
+use crate::MgmtClient;
 
 #[cfg(feature = "snapctl")]
 lazy_static! {
@@ -97,6 +98,31 @@ impl System {
         })
     }
 
+    /// Show or change the log filter of the running daemon. Unlike `set_log_level`, the
+    /// daemon isn't restarted, and the change lasts until it is.
+    pub async fn log_filter(
+        client: &MgmtClient,
+        filter: Option<&str>,
+        reset: bool,
+    ) -> Result<(), Error> {
+        let filter = if reset {
+            client.reset_log_filter().await
+        } else if let Some(filter) = filter {
+            client.set_log_filter(filter).await
+        } else {
+            client.log_filter().await
+        };
+
+        let filter = filter.map_err(|err| {
+            eprintln!("Failed to manage the log filter of aziot-edged: {err:#}");
+            Error::System
+        })?;
+
+        println!("{filter}");
+
+        Ok(())
+    }
+
     pub fn get_system_status() -> Result<(), Error> {
         get_status(&SERVICE_DEFINITIONS).map_err(|err| {
             eprintln!("{err:#?}");
@@ -6,6 +6,7 @@
 
 mod error;
 mod runtime_util;
+mod settings_util;
 mod shell_util;
 mod support_bundle;
 
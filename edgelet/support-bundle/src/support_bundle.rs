@@ -11,6 +11,7 @@ use edgelet_core::{LogOptions, ModuleRuntime};
 
 use crate::error::Error;
 use crate::runtime_util::{get_modules, write_logs};
+use crate::settings_util::write_daemon_state;
 use crate::shell_util::{
     get_docker_networks, write_check, write_inspect, write_network_inspect, write_system_log,
 };
@@ -126,6 +127,9 @@ where
         write_system_log(name, unit, &log_options, zip_writer, &file_options, verbose).await?;
     }
 
+    // Get aziot-edged config and internal state
+    write_daemon_state(zip_writer, &file_options, verbose)?;
+
     // Finilize buffer and set cursur to 0 for reading.
     let mut buffer = zip_writer.finish().context(Error::SupportBundle)?;
     let len = buffer.stream_position().context(Error::SupportBundle)?;